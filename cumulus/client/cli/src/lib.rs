@@ -504,6 +504,14 @@ impl sc_cli::CliConfiguration for NormalizedRunCmd {
 		self.base.runtime_cache_size()
 	}
 
+	fn offchain_execution_budget(&self) -> sc_cli::Result<Option<u64>> {
+		self.base.offchain_execution_budget()
+	}
+
+	fn runtime_artifact_cache(&self) -> sc_cli::Result<bool> {
+		self.base.runtime_artifact_cache()
+	}
+
 	fn base_path(&self) -> sc_cli::Result<Option<BasePath>> {
 		self.base.base_path()
 	}
@@ -38,6 +38,10 @@ use std::sync::Arc;
 
 use crate::service::CollatorService;
 
+pub use polkadot_node_primitives::pov_dump::{
+	PovDumpConfig, DEFAULT_MAX_SIZE_MIB as DEFAULT_POV_DUMP_MAX_SIZE_MIB,
+};
+
 pub mod service;
 
 /// The logging target.
@@ -215,6 +219,7 @@ pub mod relay_chain_driven {
 					this_rx.await.ok().flatten()
 				})
 			})),
+			pov_dump: None,
 		};
 
 		overseer_handle
@@ -233,13 +238,16 @@ pub mod relay_chain_driven {
 ///
 /// This must be done prior to collation, and does not set up any callback for collation.
 /// For callback-driven collators, use the [`relay_chain_driven`] module.
+///
+/// If `pov_dump` is set, the collation-generation subsystem dumps a sample of the collations.
 pub async fn initialize_collator_subsystems(
 	overseer_handle: &mut OverseerHandle,
 	key: CollatorPair,
 	para_id: ParaId,
 	reinitialize: bool,
+	pov_dump: Option<PovDumpConfig>,
 ) {
-	let config = CollationGenerationConfig { key, para_id, collator: None };
+	let config = CollationGenerationConfig { key, para_id, collator: None, pov_dump };
 
 	if reinitialize {
 		overseer_handle
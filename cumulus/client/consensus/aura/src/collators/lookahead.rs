@@ -48,6 +48,7 @@ use polkadot_primitives::{
 };
 
 use crate::{collator as collator_util, export_pov_to_path};
+use cumulus_client_collator::PovDumpConfig;
 use futures::prelude::*;
 use sc_client_api::{backend::AuxStore, BlockBackend, BlockOf};
 use sc_consensus::BlockImport;
@@ -128,7 +129,11 @@ where
 	P::Public: AppPublic + Member + Codec,
 	P::Signature: TryFrom<Vec<u8>> + Member + Codec,
 {
-	run_with_export::<_, P, _, _, _, _, _, _, _, _>(ParamsWithExport { params, export_pov: None })
+	run_with_export::<_, P, _, _, _, _, _, _, _, _>(ParamsWithExport {
+		params,
+		export_pov: None,
+		pov_dump: None,
+	})
 }
 
 /// Parameters for [`run_with_export`].
@@ -138,6 +143,10 @@ pub struct ParamsWithExport<BI, CIDP, Client, Backend, RClient, CHP, Proposer, C
 
 	/// When set, the collator will export every produced `POV` to this folder.
 	pub export_pov: Option<PathBuf>,
+
+	/// When set, the collation-generation subsystem dumps a sample of the collations, along with
+	/// everything needed to validate them again.
+	pub pov_dump: Option<PovDumpConfig>,
 }
 
 /// Run async-backing-friendly Aura.
@@ -145,7 +154,7 @@ pub struct ParamsWithExport<BI, CIDP, Client, Backend, RClient, CHP, Proposer, C
 /// This is exactly the same as [`run`], but it supports the optional export of each produced `POV`
 /// to the file system.
 pub fn run_with_export<Block, P, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS>(
-	ParamsWithExport { mut params, export_pov }: ParamsWithExport<
+	ParamsWithExport { mut params, export_pov, pov_dump }: ParamsWithExport<
 		BI,
 		CIDP,
 		Client,
@@ -186,6 +195,7 @@ where
 			params.collator_key,
 			params.para_id,
 			params.reinitialize,
+			pov_dump,
 		)
 		.await;
 
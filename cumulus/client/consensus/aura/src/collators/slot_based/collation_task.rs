@@ -18,7 +18,9 @@
 use codec::Encode;
 use std::path::PathBuf;
 
-use cumulus_client_collator::service::ServiceInterface as CollatorServiceInterface;
+use cumulus_client_collator::{
+	service::ServiceInterface as CollatorServiceInterface, PovDumpConfig,
+};
 use cumulus_relay_chain_interface::RelayChainInterface;
 
 use polkadot_node_primitives::{MaybeCompressedPoV, SubmitCollationParams};
@@ -55,6 +57,8 @@ pub struct Params<Block: BlockT, RClient, CS> {
 	pub block_import_handle: super::SlotBasedBlockImportHandle<Block>,
 	/// When set, the collator will export every produced `POV` to this folder.
 	pub export_pov: Option<PathBuf>,
+	/// When set, the collation-generation subsystem dumps a sample of the collations.
+	pub pov_dump: Option<PovDumpConfig>,
 }
 
 /// Asynchronously executes the collation task for a parachain.
@@ -73,6 +77,7 @@ pub async fn run_collation_task<Block, RClient, CS>(
 		mut collator_receiver,
 		mut block_import_handle,
 		export_pov,
+		pov_dump,
 	}: Params<Block, RClient, CS>,
 ) where
 	Block: BlockT,
@@ -89,6 +94,7 @@ pub async fn run_collation_task<Block, RClient, CS>(
 		collator_key,
 		para_id,
 		reinitialize,
+		pov_dump,
 	)
 	.await;
 
@@ -70,7 +70,9 @@ use self::{block_builder_task::run_block_builder, collation_task::run_collation_
 pub use block_import::{SlotBasedBlockImport, SlotBasedBlockImportHandle};
 use codec::Codec;
 use consensus_common::ParachainCandidate;
-use cumulus_client_collator::service::ServiceInterface as CollatorServiceInterface;
+use cumulus_client_collator::{
+	service::ServiceInterface as CollatorServiceInterface, PovDumpConfig,
+};
 use cumulus_client_consensus_common::{self as consensus_common, ParachainBlockImportMarker};
 use cumulus_client_consensus_proposer::ProposerInterface;
 use cumulus_primitives_aura::AuraUnincludedSegmentApi;
@@ -144,6 +146,9 @@ pub struct Params<Block, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS,
 	pub relay_chain_slot_duration: Duration,
 	/// When set, the collator will export every produced `POV` to this folder.
 	pub export_pov: Option<PathBuf>,
+	/// When set, the collation-generation subsystem dumps a sample of the collations, along with
+	/// everything needed to validate them again.
+	pub pov_dump: Option<PovDumpConfig>,
 	/// The maximum percentage of the maximum PoV size that the collator can use.
 	/// It will be removed once <https://github.com/paritytech/polkadot-sdk/issues/6020> is fixed.
 	pub max_pov_percentage: Option<u32>,
@@ -198,6 +203,7 @@ pub fn run<Block, P, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS, Spaw
 		block_import_handle,
 		spawner,
 		export_pov,
+		pov_dump,
 		relay_chain_slot_duration,
 		max_pov_percentage,
 	} = params;
@@ -212,6 +218,7 @@ pub fn run<Block, P, BI, CIDP, Client, Backend, RClient, CHP, Proposer, CS, Spaw
 		collator_receiver: rx,
 		block_import_handle,
 		export_pov,
+		pov_dump,
 	};
 
 	let collation_task_fut = run_collation_task::<Block, _, _>(collator_task_params);
@@ -517,6 +517,7 @@ where
 				body: Some(body),
 				import_existing: false,
 				allow_missing_state: false,
+				allow_missing_parent: false,
 				justifications: None,
 				origin: None,
 				skip_execution: false,
@@ -418,6 +418,7 @@ fn build_polkadot_full_node(
 		prepare_workers_soft_max_num: None,
 		enable_approval_voting_parallel: false,
 		keep_finalized_for: None,
+		pov_dump: None,
 	};
 
 	let (relay_chain_full_node, paranode_req_receiver) = match config.network.network_backend {
@@ -25,6 +25,7 @@ use crate::{
 };
 use chain_spec_builder::ChainSpecBuilder;
 use clap::{Command, CommandFactory, FromArgMatches, ValueEnum};
+use cumulus_client_collator::{PovDumpConfig, DEFAULT_POV_DUMP_MAX_SIZE_MIB};
 use sc_chain_spec::ChainSpec;
 use sc_cli::{
 	CliConfiguration, DefaultConfigurationValues, ImportParams, KeystoreParams, NetworkParams,
@@ -34,6 +35,7 @@ use sc_service::{config::PrometheusConfig, BasePath};
 use std::{
 	fmt::{Debug, Display, Formatter},
 	marker::PhantomData,
+	num::NonZeroU32,
 	path::PathBuf,
 };
 /// Trait that can be used to customize some of the customer-facing info related to the node binary
@@ -194,6 +196,24 @@ pub struct Cli<Config: CliConfig> {
 	#[arg(long)]
 	pub export_pov_to_path: Option<PathBuf>,
 
+	/// Dump the collations sampled by `--pov-dump-sample-rate` to the given folder.
+	///
+	/// Unlike `--export-pov-to-path`, the dumps hold everything needed to validate the collations
+	/// again with `polkadot-pvf-validator replay`: the relay parent, the executor parameters and
+	/// the validation code.
+	#[arg(long, requires = "pov_dump_sample_rate")]
+	pub pov_dump_path: Option<PathBuf>,
+
+	/// Dump one in N of the collations to the folder given by `--pov-dump-path`.
+	#[arg(long, requires = "pov_dump_path")]
+	pub pov_dump_sample_rate: Option<NonZeroU32>,
+
+	/// Maximum size of the `--pov-dump-path` folder, in MiB.
+	///
+	/// The oldest dumps are removed to make room for the new ones.
+	#[arg(long, value_name = "MiB", default_value_t = DEFAULT_POV_DUMP_MAX_SIZE_MIB)]
+	pub pov_dump_max_size: u64,
+
 	/// Relay chain arguments
 	#[arg(raw = true)]
 	pub relay_chain_args: Vec<String>,
@@ -231,6 +251,11 @@ impl<Config: CliConfig> Cli<Config> {
 				.then(|| AuthoringPolicy::SlotBased)
 				.unwrap_or(self.authoring),
 			export_pov: self.export_pov_to_path.clone(),
+			pov_dump: self.pov_dump_path.clone().map(|path| PovDumpConfig {
+				path,
+				sample_rate: self.pov_dump_sample_rate,
+				max_size: self.pov_dump_max_size.saturating_mul(1024 * 1024),
+			}),
 			max_pov_percentage: self.run.experimental_max_pov_percentage,
 		}
 	}
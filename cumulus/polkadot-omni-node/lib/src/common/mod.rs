@@ -27,6 +27,7 @@ pub mod spec;
 pub mod types;
 
 use crate::cli::AuthoringPolicy;
+use cumulus_client_collator::PovDumpConfig;
 use cumulus_primitives_core::{CollectCollationInfo, GetCoreSelectorApi, RelayParentOffsetApi};
 use sc_client_db::DbHash;
 use sc_offchain::OffchainWorkerApi;
@@ -115,6 +116,10 @@ pub struct NodeExtraArgs {
 	/// If set, each `PoV` build by the node will be exported to this folder.
 	pub export_pov: Option<PathBuf>,
 
+	/// If set, a sample of the collations is dumped along with everything needed to validate
+	/// them again.
+	pub pov_dump: Option<PovDumpConfig>,
+
 	/// The maximum percentage of the maximum PoV size that the collator can use.
 	/// It will be removed once <https://github.com/paritytech/polkadot-sdk/issues/6020> is fixed.
 	pub max_pov_percentage: Option<u32>,
@@ -173,13 +173,16 @@ pub(crate) trait BaseNodeSpec {
 				HeapAllocStrategy::Static { extra_pages: h as _ }
 			});
 
-		let executor = sc_executor::WasmExecutor::<ParachainHostFunctions>::builder()
+		let mut executor = sc_executor::WasmExecutor::<ParachainHostFunctions>::builder()
 			.with_execution_method(config.executor.wasm_method)
 			.with_max_runtime_instances(config.executor.max_runtime_instances)
 			.with_runtime_cache_size(config.executor.runtime_cache_size)
 			.with_onchain_heap_alloc_strategy(heap_pages)
-			.with_offchain_heap_alloc_strategy(heap_pages)
-			.build();
+			.with_offchain_heap_alloc_strategy(heap_pages);
+		if let Some(path) = &config.executor.artifact_cache_path {
+			executor = executor.with_artifact_cache_path(path);
+		}
+		let executor = executor.build();
 
 		let (client, backend, keystore_container, task_manager) =
 			sc_service::new_full_parts_record_import::<Self::Block, Self::RuntimeApi, _>(
@@ -363,6 +363,7 @@ where
 			block_import_handle,
 			spawner: task_manager.spawn_handle(),
 			export_pov: node_extra_args.export_pov,
+			pov_dump: node_extra_args.pov_dump,
 			max_pov_percentage: node_extra_args.max_pov_percentage,
 		};
 
@@ -467,6 +468,7 @@ where
 
 		let params = aura::ParamsWithExport {
 			export_pov: node_extra_args.export_pov,
+			pov_dump: node_extra_args.pov_dump,
 			params: AuraParams {
 				create_inherent_data_providers: move |_, ()| async move { Ok(()) },
 				block_import,
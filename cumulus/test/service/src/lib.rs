@@ -509,6 +509,7 @@ where
 					block_import_handle: slot_based_handle,
 					spawner: task_manager.spawn_handle(),
 					export_pov: None,
+					pov_dump: None,
 					max_pov_percentage: None,
 				};
 
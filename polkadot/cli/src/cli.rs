@@ -19,7 +19,8 @@
 pub use polkadot_node_primitives::NODE_VERSION;
 
 use clap::{ArgAction, Parser};
-use std::path::PathBuf;
+use polkadot_node_primitives::pov_dump::DEFAULT_MAX_SIZE_MIB as DEFAULT_POV_DUMP_MAX_SIZE_MIB;
+use std::{num::NonZeroU32, path::PathBuf};
 
 #[allow(missing_docs)]
 #[derive(Debug, Parser)]
@@ -64,6 +65,94 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Export the record of the votes signed by the node.
+	ExportSlashingProtection(sc_cli::ExportSlashingProtectionCmd),
+
+	/// Import a record of signed votes exported from another node.
+	ImportSlashingProtection(sc_cli::ImportSlashingProtectionCmd),
+
+	/// Report the disputes stored in the database of the node, as JSON.
+	DisputeReport(DisputeReportCmd),
+
+	/// Report the content of the availability store of the node, as JSON.
+	InspectAvailabilityStore(InspectAvailabilityStoreCmd),
+}
+
+/// The `dispute-report` command, reporting every dispute of the dispute coordinator database
+/// along with the votes cast in it and whether our node participated.
+///
+/// The node must not be running while the command is executed.
+#[derive(Debug, Parser)]
+pub struct DisputeReportCmd {
+	/// Output file name or stdout if unspecified.
+	#[arg()]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub keystore_params: sc_cli::KeystoreParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: sc_cli::DatabaseParams,
+}
+
+impl sc_cli::CliConfiguration for DisputeReportCmd {
+	fn shared_params(&self) -> &sc_cli::SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&sc_cli::KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+
+	fn database_params(&self) -> Option<&sc_cli::DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `inspect-availability-store` command, listing the candidates of the availability store
+/// along with the inconsistencies found in it, or exporting the available data of a candidate.
+///
+/// The node must not be running while the command is executed.
+#[derive(Debug, Parser)]
+pub struct InspectAvailabilityStoreCmd {
+	/// Output file name or stdout if unspecified.
+	#[arg()]
+	pub output: Option<PathBuf>,
+
+	/// Verify the stored chunks against the erasure root of their candidate, and look for the
+	/// chunks missing from the meta information. This is slow.
+	#[arg(long, conflicts_with = "export")]
+	pub verify: bool,
+
+	/// Export the SCALE-encoded available data of the given candidate, instead of reporting the
+	/// content of the store.
+	#[arg(long, value_name = "CANDIDATE_HASH")]
+	pub export: Option<sp_core::H256>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: sc_cli::DatabaseParams,
+}
+
+impl sc_cli::CliConfiguration for InspectAvailabilityStoreCmd {
+	fn shared_params(&self) -> &sc_cli::SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&sc_cli::DatabaseParams> {
+		Some(&self.database_params)
+	}
 }
 
 #[allow(missing_docs)]
@@ -166,6 +255,21 @@ pub struct RunCmd {
 	/// networks.
 	#[arg(long)]
 	pub keep_finalized_for: Option<u32>,
+
+	/// Dump the candidates failing validation to the given directory, along with everything
+	/// needed to validate them again with `polkadot-pvf-validator replay`.
+	#[arg(long)]
+	pub pov_dump_path: Option<PathBuf>,
+
+	/// Also dump one in N of the validated candidates, whatever the outcome of their validation.
+	#[arg(long, requires = "pov_dump_path")]
+	pub pov_dump_sample_rate: Option<NonZeroU32>,
+
+	/// Maximum size of the `--pov-dump-path` directory, in MiB.
+	///
+	/// The oldest dumps are removed to make room for the new ones.
+	#[arg(long, value_name = "MiB", default_value_t = DEFAULT_POV_DUMP_MAX_SIZE_MIB)]
+	pub pov_dump_max_size: u64,
 }
 
 #[allow(missing_docs)]
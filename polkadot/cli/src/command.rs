@@ -15,11 +15,14 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::cli::{Cli, Subcommand, NODE_VERSION};
+use codec::Encode;
 use frame_benchmarking_cli::{
 	BenchmarkCmd, ExtrinsicFactory, SubstrateRemarkBuilder, SUBSTRATE_REFERENCE_HARDWARE,
 };
 use futures::future::TryFutureExt;
 use log::info;
+use polkadot_node_primitives::pov_dump::PovDumpConfig;
+use polkadot_primitives::CandidateHash;
 use polkadot_service::{
 	self,
 	benchmarking::{benchmark_inherent_data, TransferKeepAliveBuilder},
@@ -34,9 +37,32 @@ use sp_keyring::Sr25519Keyring;
 pub use crate::error::Error;
 #[cfg(feature = "pyroscope")]
 use std::net::ToSocketAddrs;
+use std::{
+	fs,
+	io::{self, Write},
+	path::Path,
+};
 
 type Result<T> = std::result::Result<T, Error>;
 
+// Write to the file at `path`, or to stdout if there is none.
+fn write_output(
+	path: Option<&Path>,
+	write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<()> {
+	let file: Box<dyn Write> = match path {
+		Some(path) => Box::new(
+			fs::File::create(path)
+				.map_err(|e| Error::Other(format!("Cannot create {}: {e}", path.display())))?,
+		),
+		None => Box::new(io::stdout()),
+	};
+	let mut file = io::BufWriter::new(file);
+	write(&mut file)
+		.and_then(|()| file.flush())
+		.map_err(|e| Error::Other(format!("Cannot write the output: {e}")))
+}
+
 fn get_exec_name() -> Option<String> {
 	std::env::current_exe()
 		.ok()
@@ -236,6 +262,11 @@ where
 				prepare_workers_soft_max_num: cli.run.prepare_workers_soft_max_num,
 				enable_approval_voting_parallel: cli.run.enable_approval_voting_parallel,
 				keep_finalized_for: cli.run.keep_finalized_for,
+				pov_dump: cli.run.pov_dump_path.map(|path| PovDumpConfig {
+					path,
+					sample_rate: cli.run.pov_dump_sample_rate,
+					max_size: cli.run.pov_dump_max_size.saturating_mul(1024 * 1024),
+				}),
 			},
 		)
 		.map(|full| full.task_manager)?;
@@ -469,6 +500,47 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			Ok(runner.sync_run(|config| cmd.run::<polkadot_service::Block>(&config))?)
 		},
+		Some(Subcommand::ExportSlashingProtection(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			Ok(runner.sync_run(|config| cmd.run(&config))?)
+		},
+		Some(Subcommand::ImportSlashingProtection(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			Ok(runner.sync_run(|config| cmd.run(&config))?)
+		},
+		Some(Subcommand::DisputeReport(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			Ok(runner.sync_run(|config| {
+				let report = polkadot_service::dispute_report(&config)?;
+
+				write_output(cmd.output.as_deref(), |file| {
+					Ok(serde_json::to_writer_pretty(file, &report)?)
+				})
+			})?)
+		},
+		Some(Subcommand::InspectAvailabilityStore(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			Ok(runner.sync_run(|config| {
+				if let Some(candidate_hash) = cmd.export {
+					let available_data = polkadot_service::export_available_data(
+						&config,
+						&CandidateHash(candidate_hash),
+					)?
+					.ok_or_else(|| {
+						Error::Other(format!("No available data for candidate {candidate_hash:?}"))
+					})?;
+
+					return write_output(cmd.output.as_deref(), |file| {
+						file.write_all(&available_data.encode())
+					})
+				}
+
+				let report = polkadot_service::inspect_availability_store(&config, cmd.verify)?;
+				write_output(cmd.output.as_deref(), |file| {
+					Ok(serde_json::to_writer_pretty(file, &report)?)
+				})
+			})?)
+		},
 	}?;
 
 	#[cfg(feature = "pyroscope")]
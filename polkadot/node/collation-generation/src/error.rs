@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
-use polkadot_primitives::vstaging::CommittedCandidateReceiptError;
+use polkadot_primitives::{vstaging::CommittedCandidateReceiptError, ValidationCodeHash};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -37,6 +37,10 @@ pub enum Error {
 	CandidateReceiptCheck(CommittedCandidateReceiptError),
 	#[error("PoV size {0} exceeded maximum size of {1}")]
 	POVSizeExceeded(usize, usize),
+	#[error("Validation code {0:?} not found")]
+	ValidationCodeNotFound(ValidationCodeHash),
+	#[error(transparent)]
+	Io(#[from] std::io::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -28,6 +28,8 @@
 //!   * Invoke the `collator`, and use its outputs to produce a [`CandidateReceipt`], signed with
 //!     the configuration's `key`.
 //!   * Dispatch a [`CollatorProtocolMessage::DistributeCollation`]`(receipt, pov)`.
+//!   * If the configuration asks for it, dump a sample of the collations along with everything
+//!     needed to validate them again, see [`polkadot_node_primitives::pov_dump`].
 
 #![deny(missing_docs)]
 
@@ -35,6 +37,7 @@ use codec::Encode;
 use error::{Error, Result};
 use futures::{channel::oneshot, future::FutureExt, select};
 use polkadot_node_primitives::{
+	pov_dump::{self, PovDump, PovDumpConfig},
 	AvailableData, Collation, CollationGenerationConfig, CollationSecondedSignal, PoV,
 	SubmitCollationParams,
 };
@@ -44,9 +47,10 @@ use polkadot_node_subsystem::{
 	SubsystemContext, SubsystemError, SubsystemResult, SubsystemSender,
 };
 use polkadot_node_subsystem_util::{
-	request_claim_queue, request_node_features, request_persisted_validation_data,
-	request_session_index_for_child, request_validation_code_hash, request_validators,
-	runtime::ClaimQueueSnapshot,
+	executor_params_at_relay_parent, request_claim_queue, request_node_features,
+	request_persisted_validation_data, request_session_index_for_child,
+	request_validation_code_by_hash, request_validation_code_hash, request_validators,
+	runtime::{fetch_validation_code_bomb_limit, ClaimQueueSnapshot},
 };
 use polkadot_primitives::{
 	collator_signature_payload,
@@ -55,8 +59,9 @@ use polkadot_primitives::{
 		transpose_claim_queue, CandidateDescriptorV2, CandidateReceiptV2 as CandidateReceipt,
 		CommittedCandidateReceiptV2, TransposedClaimQueue,
 	},
-	CandidateCommitments, CandidateDescriptor, CollatorPair, CoreIndex, Hash, Id as ParaId,
-	OccupiedCoreAssumption, PersistedValidationData, SessionIndex, ValidationCodeHash,
+	CandidateCommitments, CandidateDescriptor, CandidateHash, CollatorPair, CoreIndex, Hash,
+	Id as ParaId, OccupiedCoreAssumption, PersistedValidationData, PvfExecKind, SessionIndex,
+	ValidationCodeHash,
 };
 use schnellru::{ByLength, LruMap};
 use sp_core::crypto::Pair;
@@ -233,6 +238,7 @@ impl CollationGenerationSubsystem {
 		construct_and_distribute_receipt(
 			collation,
 			config.key.clone(),
+			config.pov_dump.as_ref(),
 			ctx.sender(),
 			result_sender,
 			&mut self.metrics,
@@ -445,6 +451,7 @@ impl CollationGenerationSubsystem {
 							session_index,
 						},
 						task_config.key.clone(),
+						task_config.pov_dump.as_ref(),
 						&mut task_sender,
 						result_sender,
 						&metrics,
@@ -542,6 +549,7 @@ struct PreparedCollation {
 async fn construct_and_distribute_receipt(
 	collation: PreparedCollation,
 	key: CollatorPair,
+	pov_dump: Option<&PovDumpConfig>,
 	sender: &mut impl overseer::CollationGenerationSenderTrait,
 	result_sender: Option<oneshot::Sender<CollationSecondedSignal>>,
 	metrics: &Metrics,
@@ -590,6 +598,8 @@ async fn construct_and_distribute_receipt(
 		&validation_code_hash,
 	);
 
+	// Only cloned when dumping, as the validation data is consumed by the erasure coding.
+	let dumped_validation_data = pov_dump.map(|_| validation_data.clone());
 	let erasure_root = erasure_root(n_validators, validation_data, pov.clone())?;
 
 	let commitments = CandidateCommitments {
@@ -666,6 +676,26 @@ async fn construct_and_distribute_receipt(
 
 	metrics.on_collation_generated();
 
+	if let (Some(config), Some(persisted_validation_data)) = (pov_dump, dumped_validation_data) {
+		let candidate_hash = receipt.hash();
+		// Collators don't learn whether validators reject their collations, only samples can be
+		// dumped.
+		if config.should_dump(&candidate_hash, false) {
+			dump_collation(
+				config,
+				sender,
+				candidate_hash,
+				para_id,
+				relay_parent,
+				session_index,
+				validation_code_hash,
+				persisted_validation_data,
+				pov.clone(),
+			)
+			.await;
+		}
+	}
+
 	sender
 		.send_message(CollatorProtocolMessage::DistributeCollation {
 			candidate_receipt: receipt,
@@ -680,6 +710,75 @@ async fn construct_and_distribute_receipt(
 	Ok(())
 }
 
+/// Dump a collation along with the executor parameters of its session and its validation code.
+///
+/// Failures are only logged, the collation is distributed anyway.
+async fn dump_collation(
+	config: &PovDumpConfig,
+	sender: &mut impl overseer::CollationGenerationSenderTrait,
+	candidate_hash: CandidateHash,
+	para_id: ParaId,
+	relay_parent: Hash,
+	session_index: SessionIndex,
+	validation_code_hash: ValidationCodeHash,
+	persisted_validation_data: PersistedValidationData,
+	pov: PoV,
+) {
+	let result = async {
+		let executor_params = executor_params_at_relay_parent(relay_parent, sender).await?;
+		let validation_code_bomb_limit =
+			fetch_validation_code_bomb_limit(relay_parent, session_index, sender).await?;
+
+		let validation_code =
+			if pov_dump::validation_code_path(&config.path, &validation_code_hash).exists() {
+				None
+			} else {
+				let validation_code =
+					request_validation_code_by_hash(relay_parent, validation_code_hash, sender)
+						.await
+						.await??
+						.ok_or(Error::ValidationCodeNotFound(validation_code_hash))?;
+				Some(validation_code)
+			};
+
+		let dump = PovDump {
+			candidate_hash,
+			para_id,
+			relay_parent,
+			validation_code_hash,
+			validation_code_bomb_limit,
+			executor_params,
+			exec_kind: PvfExecKind::Backing,
+			persisted_validation_data,
+			pov: Arc::new(pov),
+			error: None,
+		};
+		Ok::<_, Error>(dump.write_capped(config, validation_code.as_ref())?)
+	}
+	.await;
+
+	match result {
+		Ok(None) => gum::warn!(
+			target: LOG_TARGET,
+			?candidate_hash,
+			max_size = config.max_size,
+			"Collation dump directory is full",
+		),
+		Ok(Some(path)) => gum::debug!(
+			target: LOG_TARGET,
+			?candidate_hash,
+			path = %path.display(),
+			"Dumped collation",
+		),
+		Err(err) => gum::warn!(
+			target: LOG_TARGET,
+			?candidate_hash,
+			?err,
+			"Failed to dump collation",
+		),
+	}
+}
+
 fn erasure_root(
 	n_validators: usize,
 	persisted_validation: PersistedValidationData,
@@ -160,6 +160,7 @@ fn test_config<Id: Into<ParaId>>(
 		key: CollatorPair::generate().0,
 		collator: Some(test_collator.create_collation_function()),
 		para_id: para_id.into(),
+		pov_dump: None,
 	}
 }
 
@@ -168,6 +169,7 @@ fn test_config_no_collator<Id: Into<ParaId>>(para_id: Id) -> CollationGeneration
 		key: CollatorPair::generate().0,
 		collator: None,
 		para_id: para_id.into(),
+		pov_dump: None,
 	}
 }
 
@@ -0,0 +1,313 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offline inspection of the availability store, for diagnosing availability-recovery failures.
+//!
+//! Only the meta column is ordered, so the stored candidates are found from their meta
+//! information and the entries of the data column are looked up from there. The store doesn't
+//! keep the receipts of the candidates either: the erasure roots the chunks are verified against
+//! are provided by the caller, and are otherwise computed again from the available data, which is
+//! only possible when it is stored.
+
+use serde::Serialize;
+
+use polkadot_primitives::{BlakeTwo256, HashT};
+
+use super::*;
+
+/// The content of the availability store.
+#[derive(Debug, Serialize)]
+pub struct StoreReport {
+	/// The stored candidates, sorted by the number of the first relay block they are known to be
+	/// in, or else of their relay parent.
+	pub candidates: Vec<StoredCandidate>,
+	/// The entries which are missing or can't be reached from the meta information.
+	pub orphans: Vec<Orphan>,
+}
+
+/// A candidate of the availability store.
+#[derive(Debug, Serialize)]
+pub struct StoredCandidate {
+	/// The hash of the candidate.
+	pub candidate_hash: Hash,
+	/// Where the candidate is in its lifecycle.
+	pub state: CandidateState,
+	/// The number of the relay parent of the candidate, if its available data is stored.
+	pub relay_parent_number: Option<BlockNumber>,
+	/// Whether the available data of the candidate is stored.
+	pub data_available: bool,
+	/// The number of validators of the session of the candidate.
+	pub n_validators: usize,
+	/// The validators whose chunk is stored.
+	pub chunks_stored: Vec<u32>,
+	/// The verification of the chunks, `None` if not requested or if neither the erasure root nor
+	/// the available data of the candidate is known.
+	pub verification: Option<Verification>,
+}
+
+/// Where a candidate is in its lifecycle. Times are in seconds since the UNIX epoch.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateState {
+	/// The candidate isn't included in any block.
+	Unavailable {
+		/// When the candidate was first observed.
+		since: u64,
+	},
+	/// The candidate is included in unfinalized blocks.
+	Unfinalized {
+		/// When the candidate was first observed.
+		since: u64,
+		/// The blocks including the candidate.
+		blocks: Vec<(BlockNumber, Hash)>,
+	},
+	/// The candidate is included in a finalized block.
+	Finalized {
+		/// When the block was finalized.
+		at: u64,
+	},
+}
+
+/// The verification of the stored chunks of a candidate.
+#[derive(Debug, Serialize)]
+pub struct Verification {
+	/// The erasure root the chunks are verified against.
+	pub erasure_root: Hash,
+	/// Whether the erasure root is the one of the candidate receipt, rather than computed from the
+	/// available data.
+	pub from_receipt: bool,
+	/// Whether the available data matches the erasure root of the candidate receipt, `None` if
+	/// either isn't known.
+	pub available_data_valid: Option<bool>,
+	/// The validators whose chunk doesn't match the erasure root.
+	pub invalid_chunks: Vec<u32>,
+}
+
+/// An inconsistency of the availability store.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Orphan {
+	/// The available data of a candidate is missing, although its meta says it's stored.
+	MissingAvailableData {
+		/// The hash of the candidate.
+		candidate_hash: Hash,
+	},
+	/// The chunk of a validator is missing, although the meta of its candidate says it's stored.
+	MissingChunk {
+		/// The hash of the candidate.
+		candidate_hash: Hash,
+		/// The validator the chunk is stored for.
+		validator_index: u32,
+	},
+	/// The chunk of a validator is stored, but not recorded in the meta of its candidate.
+	UnrecordedChunk {
+		/// The hash of the candidate.
+		candidate_hash: Hash,
+		/// The validator the chunk is stored for.
+		validator_index: u32,
+	},
+	/// A candidate is recorded as included in an unfinalized block, but has no meta.
+	UnfinalizedEntry {
+		/// The number of the block.
+		block_number: BlockNumber,
+		/// The hash of the block.
+		block_hash: Hash,
+		/// The hash of the candidate.
+		candidate_hash: Hash,
+	},
+	/// A candidate is scheduled for pruning, but has no meta.
+	PruningEntry {
+		/// When the candidate is pruned, in seconds since the UNIX epoch.
+		at: u64,
+		/// The hash of the candidate.
+		candidate_hash: Hash,
+	},
+	/// A candidate which isn't in an unfinalized block is never pruned.
+	Unpruned {
+		/// The hash of the candidate.
+		candidate_hash: Hash,
+	},
+}
+
+/// Report the content of the availability store.
+///
+/// If `verify` is set, the chunks are verified against the erasure root of their candidate, taken
+/// from `erasure_roots` or else computed from the stored available data, and the chunks of every
+/// validator are looked up to find the unrecorded ones, which is slow.
+pub fn inspect(
+	db: &Arc<dyn Database>,
+	config: &Config,
+	verify: bool,
+	erasure_roots: &HashMap<CandidateHash, Hash>,
+) -> Result<StoreReport, Error> {
+	let mut candidates = Vec::new();
+	let mut orphans = Vec::new();
+
+	let mut metas = Vec::new();
+	for entry in db.iter_with_prefix(config.col_meta, META_PREFIX) {
+		let (key, value) = entry?;
+		let candidate_hash = CandidateHash::decode(&mut &key[META_PREFIX.len()..])?;
+		metas.push((candidate_hash, CandidateMeta::decode(&mut &value[..])?));
+	}
+	let known: HashSet<CandidateHash> = metas.iter().map(|(hash, _)| *hash).collect();
+
+	let mut pruned = HashSet::new();
+	for entry in db.iter_with_prefix(config.col_meta, PRUNE_BY_TIME_PREFIX) {
+		let (at, candidate_hash) = decode_pruning_key(&entry?.0)?;
+		if !known.contains(&candidate_hash) {
+			orphans
+				.push(Orphan::PruningEntry { at: at.as_secs(), candidate_hash: candidate_hash.0 });
+		}
+		pruned.insert(candidate_hash);
+	}
+
+	for entry in db.iter_with_prefix(config.col_meta, UNFINALIZED_PREFIX) {
+		let (block_number, block_hash, candidate_hash) = decode_unfinalized_key(&entry?.0)?;
+		if !known.contains(&candidate_hash) {
+			orphans.push(Orphan::UnfinalizedEntry {
+				block_number,
+				block_hash,
+				candidate_hash: candidate_hash.0,
+			});
+		}
+	}
+
+	for (candidate_hash, meta) in metas {
+		let state = match meta.state {
+			State::Unavailable(since) => CandidateState::Unavailable { since: since.0 },
+			State::Unfinalized(since, blocks) => CandidateState::Unfinalized {
+				since: since.0,
+				blocks: blocks.into_iter().map(|(number, hash)| (number.0, hash)).collect(),
+			},
+			State::Finalized(at) => CandidateState::Finalized { at: at.0 },
+		};
+		if !matches!(state, CandidateState::Unfinalized { .. }) && !pruned.contains(&candidate_hash)
+		{
+			orphans.push(Orphan::Unpruned { candidate_hash: candidate_hash.0 });
+		}
+
+		let available_data = if meta.data_available {
+			let available_data = load_available_data(db, config, &candidate_hash)?;
+			if available_data.is_none() {
+				orphans.push(Orphan::MissingAvailableData { candidate_hash: candidate_hash.0 });
+			}
+			available_data
+		} else {
+			None
+		};
+
+		let n_validators = meta.chunks_stored.len();
+		let verification = if verify && n_validators > 0 {
+			let receipt_root = erasure_roots.get(&candidate_hash).copied();
+			let data_root = match &available_data {
+				Some(available_data) => {
+					let chunks =
+						polkadot_erasure_coding::obtain_chunks_v1(n_validators, available_data)?;
+					Some(polkadot_erasure_coding::branches(chunks.as_ref()).root())
+				},
+				None => None,
+			};
+			match (receipt_root, data_root) {
+				(Some(erasure_root), data_root) => Some(Verification {
+					erasure_root,
+					from_receipt: true,
+					available_data_valid: data_root.map(|root| root == erasure_root),
+					invalid_chunks: Vec::new(),
+				}),
+				(None, Some(erasure_root)) => Some(Verification {
+					erasure_root,
+					from_receipt: false,
+					available_data_valid: None,
+					invalid_chunks: Vec::new(),
+				}),
+				(None, None) => None,
+			}
+		} else {
+			None
+		};
+
+		let mut candidate = StoredCandidate {
+			candidate_hash: candidate_hash.0,
+			state,
+			relay_parent_number: available_data
+				.map(|data| data.validation_data.relay_parent_number),
+			data_available: meta.data_available,
+			n_validators,
+			chunks_stored: meta.chunks_stored.iter_ones().map(|index| index as u32).collect(),
+			verification,
+		};
+
+		for (index, stored) in meta.chunks_stored.iter().by_vals().enumerate() {
+			// Without verification, only the chunks recorded in the meta are looked up.
+			if !stored && !verify {
+				continue
+			}
+
+			let validator_index = ValidatorIndex(index as u32);
+			match load_chunk(db, config, &candidate_hash, validator_index)? {
+				Some(chunk) => {
+					if !stored {
+						orphans.push(Orphan::UnrecordedChunk {
+							candidate_hash: candidate_hash.0,
+							validator_index: validator_index.0,
+						});
+					}
+					if let Some(verification) = &mut candidate.verification {
+						if !is_chunk_valid(&verification.erasure_root, &chunk) {
+							verification.invalid_chunks.push(validator_index.0);
+						}
+					}
+				},
+				None if stored => orphans.push(Orphan::MissingChunk {
+					candidate_hash: candidate_hash.0,
+					validator_index: validator_index.0,
+				}),
+				None => {},
+			}
+		}
+
+		candidates.push(candidate);
+	}
+
+	candidates.sort_by_key(|candidate| {
+		let block_number = block_number(candidate);
+		(block_number.is_none(), block_number, candidate.candidate_hash)
+	});
+
+	Ok(StoreReport { candidates, orphans })
+}
+
+/// Load the available data of a candidate, if it is stored.
+pub fn export_available_data(
+	db: &Arc<dyn Database>,
+	config: &Config,
+	candidate_hash: &CandidateHash,
+) -> Result<Option<AvailableData>, Error> {
+	load_available_data(db, config, candidate_hash)
+}
+
+fn is_chunk_valid(erasure_root: &Hash, chunk: &ErasureChunk) -> bool {
+	polkadot_erasure_coding::branch_hash(erasure_root, chunk.proof(), chunk.index.0 as usize)
+		.is_ok_and(|hash| hash == BlakeTwo256::hash(&chunk.chunk))
+}
+
+// The number of the first block the candidate is known to be in, or else of its relay parent.
+fn block_number(candidate: &StoredCandidate) -> Option<BlockNumber> {
+	match &candidate.state {
+		CandidateState::Unfinalized { blocks, .. } if !blocks.is_empty() => Some(blocks[0].0),
+		_ => candidate.relay_parent_number,
+	}
+}
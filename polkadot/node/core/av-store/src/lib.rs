@@ -55,6 +55,9 @@ use util::availability_chunks::availability_chunk_indices;
 mod metrics;
 pub use self::metrics::*;
 
+/// Offline inspection of the store.
+pub mod inspect;
+
 #[cfg(test)]
 mod tests;
 
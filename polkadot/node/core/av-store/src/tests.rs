@@ -1384,3 +1384,151 @@ fn query_chunk_size_works() {
 		virtual_overseer
 	});
 }
+
+#[test]
+fn inspect_verifies_chunks_and_finds_orphans() {
+	let store = test_store();
+	let test_state = TestState::default();
+	let candidate_hash = CandidateHash(Hash::repeat_byte(1));
+	let n_validators = 4;
+
+	let available_data = AvailableData {
+		pov: Arc::new(PoV { block_data: BlockData(vec![4, 5, 6]) }),
+		validation_data: test_state.persisted_validation_data.clone(),
+	};
+	let chunks = polkadot_erasure_coding::obtain_chunks_v1(n_validators, &available_data).unwrap();
+	let branches = polkadot_erasure_coding::branches(chunks.as_ref());
+	let erasure_root = branches.root();
+	let mut erasure_chunks: Vec<_> = chunks
+		.iter()
+		.zip(branches.map(|(proof, _)| proof))
+		.enumerate()
+		.map(|(index, (chunk, proof))| ErasureChunk {
+			chunk: chunk.clone(),
+			proof,
+			index: ChunkIndex(index as u32),
+		})
+		.collect();
+	erasure_chunks[1].chunk[0] ^= 1;
+
+	// The chunk of validator 2 is stored but not recorded, the one of validator 3 is recorded but
+	// missing.
+	with_tx(&store, |tx| {
+		super::write_meta(
+			tx,
+			&TEST_CONFIG,
+			&candidate_hash,
+			&CandidateMeta {
+				data_available: true,
+				chunks_stored: bitvec::bitvec![u8, BitOrderLsb0; 1, 1, 0, 1],
+				state: State::Unavailable(BETimestamp(0)),
+			},
+		);
+		super::write_available_data(tx, &TEST_CONFIG, &candidate_hash, &available_data);
+		for (index, chunk) in erasure_chunks.iter().enumerate().take(3) {
+			super::write_chunk(
+				tx,
+				&TEST_CONFIG,
+				&candidate_hash,
+				ValidatorIndex(index as u32),
+				chunk,
+			);
+		}
+		super::write_pruning_key(
+			tx,
+			&TEST_CONFIG,
+			Duration::from_secs(10),
+			&CandidateHash(Hash::repeat_byte(2)),
+		);
+	});
+
+	let report = inspect::inspect(&store, &TEST_CONFIG, true, &HashMap::new()).unwrap();
+
+	assert_eq!(report.candidates.len(), 1);
+	let candidate = &report.candidates[0];
+	assert_eq!(candidate.relay_parent_number, Some(5));
+	assert_eq!(candidate.chunks_stored, vec![0, 1, 3]);
+	let verification = candidate.verification.as_ref().unwrap();
+	assert_eq!(verification.erasure_root, erasure_root);
+	assert!(!verification.from_receipt);
+	assert_eq!(verification.invalid_chunks, vec![1]);
+
+	assert_matches!(
+		&report.orphans[..],
+		[
+			inspect::Orphan::PruningEntry { at: 10, candidate_hash: pruned },
+			inspect::Orphan::Unpruned { candidate_hash: unpruned },
+			inspect::Orphan::UnrecordedChunk { validator_index: 2, .. },
+			inspect::Orphan::MissingChunk { validator_index: 3, .. },
+		] => {
+			assert_eq!(*pruned, Hash::repeat_byte(2));
+			assert_eq!(*unpruned, candidate_hash.0);
+		}
+	);
+
+	assert_eq!(
+		inspect::export_available_data(&store, &TEST_CONFIG, &candidate_hash).unwrap(),
+		Some(available_data),
+	);
+}
+
+#[test]
+fn inspect_verifies_lone_chunks_against_receipt() {
+	let store = test_store();
+	let test_state = TestState::default();
+	let candidate_hash = CandidateHash(Hash::repeat_byte(1));
+	let n_validators = 4;
+
+	let available_data = AvailableData {
+		pov: Arc::new(PoV { block_data: BlockData(vec![4, 5, 6]) }),
+		validation_data: test_state.persisted_validation_data.clone(),
+	};
+	let chunks = polkadot_erasure_coding::obtain_chunks_v1(n_validators, &available_data).unwrap();
+	let branches = polkadot_erasure_coding::branches(chunks.as_ref());
+	let erasure_root = branches.root();
+	let mut erasure_chunks: Vec<_> = chunks
+		.iter()
+		.zip(branches.map(|(proof, _)| proof))
+		.enumerate()
+		.map(|(index, (chunk, proof))| ErasureChunk {
+			chunk: chunk.clone(),
+			proof,
+			index: ChunkIndex(index as u32),
+		})
+		.collect();
+	erasure_chunks[2].chunk[0] ^= 1;
+
+	// Only the chunks are stored, as on validators which didn't back the candidate.
+	with_tx(&store, |tx| {
+		super::write_meta(
+			tx,
+			&TEST_CONFIG,
+			&candidate_hash,
+			&CandidateMeta {
+				data_available: false,
+				chunks_stored: bitvec::bitvec![u8, BitOrderLsb0; 0, 1, 1, 0],
+				state: State::Unavailable(BETimestamp(0)),
+			},
+		);
+		for index in [1, 2] {
+			super::write_chunk(
+				tx,
+				&TEST_CONFIG,
+				&candidate_hash,
+				ValidatorIndex(index),
+				&erasure_chunks[index as usize],
+			);
+		}
+	});
+
+	let report = inspect::inspect(&store, &TEST_CONFIG, true, &HashMap::new()).unwrap();
+	assert!(report.candidates[0].verification.is_none());
+
+	let erasure_roots = [(candidate_hash, erasure_root)].into_iter().collect();
+	let report = inspect::inspect(&store, &TEST_CONFIG, true, &erasure_roots).unwrap();
+	let verification = report.candidates[0].verification.as_ref().unwrap();
+	assert_eq!(verification.erasure_root, erasure_root);
+	assert!(verification.from_receipt);
+	assert_eq!(verification.available_data_valid, None);
+	assert_eq!(verification.invalid_chunks, vec![2]);
+}
@@ -27,7 +27,10 @@ use polkadot_node_core_pvf::{
 	InternalValidationError, InvalidCandidate as WasmInvalidCandidate, PossiblyInvalidError,
 	PrepareError, PrepareJobKind, PvfPrepData, ValidationError, ValidationHost,
 };
-use polkadot_node_primitives::{InvalidCandidate, PoV, ValidationResult};
+use polkadot_node_primitives::{
+	pov_dump::{self, PovDump, PovDumpConfig},
+	InvalidCandidate, PoV, ValidationResult,
+};
 use polkadot_node_subsystem::{
 	errors::RuntimeApiError,
 	messages::{
@@ -62,7 +65,11 @@ use sp_keystore::KeystorePtr;
 
 use codec::Encode;
 
-use futures::{channel::oneshot, prelude::*, stream::FuturesUnordered};
+use futures::{
+	channel::{mpsc, oneshot},
+	prelude::*,
+	stream::FuturesUnordered,
+};
 
 use std::{
 	collections::HashSet,
@@ -94,6 +101,9 @@ const PVF_APPROVAL_EXECUTION_RETRY_DELAY: Duration = Duration::from_millis(200);
 // to allow exhaustive validation messages to fall through in case the tasks are clogged
 const TASK_LIMIT: usize = 30;
 
+// Number of candidate dumps waiting to be written, above which new dumps are dropped.
+const POV_DUMP_QUEUE_SIZE: usize = 16;
+
 /// Configuration for the candidate validation subsystem
 #[derive(Clone, Default)]
 pub struct Config {
@@ -114,6 +124,8 @@ pub struct Config {
 	pub pvf_prepare_workers_soft_max_num: usize,
 	/// The absolute number of pvf workers that can be spawned in the pvf prepare pool.
 	pub pvf_prepare_workers_hard_max_num: usize,
+	/// If set, the validated candidates are dumped to be validated again with the PVF host.
+	pub pov_dump: Option<PovDumpConfig>,
 }
 
 /// The candidate validation subsystem.
@@ -175,6 +187,7 @@ fn handle_validation_message<S>(
 	mut sender: S,
 	validation_host: ValidationHost,
 	metrics: Metrics,
+	pov_dump: Option<PovDumper>,
 	msg: CandidateValidationMessage,
 ) -> Pin<Box<dyn Future<Output = ()> + Send>>
 where
@@ -230,6 +243,19 @@ where
 				return
 			};
 
+			let candidate_hash = candidate_receipt.hash();
+			let para_id = candidate_receipt.descriptor.para_id();
+			// Whether the candidate is dumped is only known once it is validated. The validation
+			// code isn't kept, it is fetched again if needed.
+			let dump_inputs = pov_dump.as_ref().map(|_| {
+				(
+					validation_code.hash(),
+					validation_data.clone(),
+					pov.clone(),
+					executor_params.clone(),
+				)
+			});
+
 			let res = validate_candidate_exhaustive(
 				session_index,
 				validation_host,
@@ -246,7 +272,65 @@ where
 			.await;
 
 			metrics.on_validation_event(&res);
+			let error = match &res {
+				Ok(ValidationResult::Valid(..)) => None,
+				Ok(ValidationResult::Invalid(err)) => Some(format!("{err:?}")),
+				Err(err) => Some(format!("{err:?}")),
+			};
 			let _ = response_sender.send(res);
+
+			let Some((
+				mut pov_dump,
+				(validation_code_hash, persisted_validation_data, pov, executor_params),
+			)) = pov_dump.zip(dump_inputs)
+			else {
+				return
+			};
+			if !pov_dump.config.should_dump(&candidate_hash, error.is_some()) {
+				return
+			}
+
+			let validation_code =
+				if pov_dump::validation_code_path(&pov_dump.config.path, &validation_code_hash)
+					.exists()
+				{
+					None
+				} else {
+					match request_validation_code_by_hash(
+						&mut sender,
+						relay_parent,
+						validation_code_hash,
+					)
+					.await
+					{
+						Ok(Some(validation_code)) => Some(validation_code),
+						Ok(None) | Err(_) => {
+							gum::warn!(
+								target: LOG_TARGET,
+								?candidate_hash,
+								?validation_code_hash,
+								"Validation code of the dumped candidate not found",
+							);
+							return
+						},
+					}
+				};
+
+			pov_dump.send(
+				PovDump {
+					candidate_hash,
+					para_id,
+					relay_parent,
+					validation_code_hash,
+					validation_code_bomb_limit,
+					executor_params,
+					exec_kind: exec_kind.into(),
+					persisted_validation_data,
+					pov,
+					error,
+				},
+				validation_code,
+			);
 		}
 		.boxed(),
 		CandidateValidationMessage::PreCheck {
@@ -302,6 +386,65 @@ where
 	}
 }
 
+/// Queue of the candidates to dump, written by [`write_pov_dumps`].
+#[derive(Clone)]
+struct PovDumper {
+	config: Arc<PovDumpConfig>,
+	sender: mpsc::Sender<(PovDump, Option<ValidationCode>)>,
+}
+
+impl PovDumper {
+	/// Create the queue, along with the task writing the dumps.
+	fn new(config: PovDumpConfig) -> (Self, impl Future<Output = ()>) {
+		let config = Arc::new(config);
+		let (sender, receiver) = mpsc::channel(POV_DUMP_QUEUE_SIZE);
+		(Self { config: config.clone(), sender }, write_pov_dumps(config, receiver))
+	}
+
+	/// Queue the dump of a candidate, along with its validation code unless it is already dumped.
+	///
+	/// The dump is dropped if the queue is full.
+	fn send(&mut self, dump: PovDump, validation_code: Option<ValidationCode>) {
+		let candidate_hash = dump.candidate_hash;
+		if self.sender.try_send((dump, validation_code)).is_err() {
+			gum::warn!(target: LOG_TARGET, ?candidate_hash, "Candidate dump queue is full");
+		}
+	}
+}
+
+/// Write the queued candidate dumps.
+///
+/// The files are written synchronously, this must be spawned as a blocking task. Failures are only
+/// logged.
+async fn write_pov_dumps(
+	config: Arc<PovDumpConfig>,
+	mut dumps: mpsc::Receiver<(PovDump, Option<ValidationCode>)>,
+) {
+	while let Some((dump, validation_code)) = dumps.next().await {
+		let candidate_hash = dump.candidate_hash;
+		match dump.write_capped(&config, validation_code.as_ref()) {
+			Ok(Some(path)) => gum::debug!(
+				target: LOG_TARGET,
+				?candidate_hash,
+				path = %path.display(),
+				"Dumped candidate",
+			),
+			Ok(None) => gum::warn!(
+				target: LOG_TARGET,
+				?candidate_hash,
+				max_size = config.max_size,
+				"Candidate dump directory is full",
+			),
+			Err(err) => gum::warn!(
+				target: LOG_TARGET,
+				?candidate_hash,
+				?err,
+				"Failed to dump candidate",
+			),
+		}
+	}
+}
+
 #[overseer::contextbounds(CandidateValidation, prefix = self::overseer)]
 async fn run<Context>(
 	mut ctx: Context,
@@ -317,8 +460,18 @@ async fn run<Context>(
 		pvf_execute_workers_max_num,
 		pvf_prepare_workers_soft_max_num,
 		pvf_prepare_workers_hard_max_num,
+		pov_dump,
 	}: Config,
 ) -> SubsystemResult<()> {
+	let pov_dump = match pov_dump {
+		Some(config) => {
+			let (pov_dump, task) = PovDumper::new(config);
+			ctx.spawn_blocking("pov-dump-writer", task.boxed())?;
+			Some(pov_dump)
+		},
+		None => None,
+	};
+
 	let (mut validation_host, task) = polkadot_node_core_pvf::start(
 		polkadot_node_core_pvf::Config::new(
 			artifacts_cache_path,
@@ -355,7 +508,7 @@ async fn run<Context>(
 						Ok(FromOrchestra::Signal(OverseerSignal::BlockFinalized(..))) => {},
 						Ok(FromOrchestra::Signal(OverseerSignal::Conclude)) => return Ok(()),
 						Ok(FromOrchestra::Communication { msg }) => {
-							let task = handle_validation_message(ctx.sender().clone(), validation_host.clone(), metrics.clone(), msg);
+							let task = handle_validation_message(ctx.sender().clone(), validation_host.clone(), metrics.clone(), pov_dump.clone(), msg);
 							tasks.push(task);
 							if tasks.len() >= TASK_LIMIT {
 								break
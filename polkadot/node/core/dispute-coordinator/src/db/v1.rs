@@ -296,6 +296,19 @@ pub(crate) fn load_candidate_votes(
 		.map_err(|e| FatalError::DbReadFailed(e))
 }
 
+/// Load the votes of all the candidates.
+pub(crate) fn load_all_candidate_votes(
+	db: &dyn Database,
+	config: &ColumnConfiguration,
+) -> FatalResult<Vec<CandidateVotes>> {
+	db.iter_with_prefix(config.col_dispute_data, CANDIDATE_VOTES_SUBKEY)
+		.map(|entry| {
+			let (_, value) = entry.map_err(|e| FatalError::DbReadFailed(e.into()))?;
+			CandidateVotes::decode(&mut &value[..]).map_err(|e| FatalError::DbReadFailed(e.into()))
+		})
+		.collect()
+}
+
 /// Load the earliest session, if any.
 pub(crate) fn load_earliest_session(
 	db: &dyn Database,
@@ -109,6 +109,9 @@ mod metrics;
 /// Status tracking of disputes (`DisputeStatus`).
 mod status;
 
+/// Offline report of the disputes stored in the database.
+pub mod report;
+
 use crate::status::Clock;
 
 #[cfg(test)]
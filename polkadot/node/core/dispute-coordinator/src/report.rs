@@ -0,0 +1,349 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offline report of the disputes stored in the database, for investigating disputes after the
+//! fact.
+//!
+//! The database doesn't record which validator indices are ours, and the session info needed to
+//! find them out may not be available anymore. Our votes are instead identified by checking their
+//! signatures against the validator keys of our keystore.
+
+use std::{collections::HashMap, sync::Arc};
+
+use serde::Serialize;
+
+use polkadot_node_primitives::{disputes::Timestamp, CandidateVotes, DisputeStatus};
+use polkadot_node_subsystem_util::database::Database;
+use polkadot_primitives::{
+	CandidateHash, DisputeStatement, Hash, InvalidDisputeStatementKind, SessionIndex,
+	ValidDisputeStatementKind, ValidatorId, ValidatorSignature,
+};
+
+use crate::{
+	backend::Backend,
+	db::v1::{self, DbBackend},
+	error::{FatalError, FatalResult},
+	metrics::Metrics,
+	Config,
+};
+
+/// Error of [`dispute_report`].
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct ReportError(#[from] FatalError);
+
+/// All the disputes of the database.
+#[derive(Debug, Serialize)]
+pub struct DisputeReport {
+	/// The earliest session votes are kept for.
+	pub earliest_session: Option<SessionIndex>,
+	/// The disputes, by session and candidate hash.
+	pub disputes: Vec<DisputeEntry>,
+}
+
+/// A dispute, with the votes cast in it.
+#[derive(Debug, Serialize)]
+pub struct DisputeEntry {
+	/// The session the candidate was included in.
+	pub session: SessionIndex,
+	/// The hash of the disputed candidate.
+	pub candidate_hash: Hash,
+	/// The parachain of the candidate, `None` if its votes were pruned.
+	pub para_id: Option<u32>,
+	/// The relay parent of the candidate, `None` if its votes were pruned.
+	pub relay_parent: Option<Hash>,
+	/// The status of the dispute.
+	pub status: Status,
+	/// When the dispute concluded, in seconds since the UNIX epoch.
+	pub concluded_at: Option<Timestamp>,
+	/// The validators which voted for the candidate.
+	pub valid_votes: Vec<u32>,
+	/// The validators which voted against the candidate.
+	pub invalid_votes: Vec<u32>,
+	/// The votes cast by our node.
+	pub our_votes: Vec<OurVote>,
+	/// Whether our node participated, i.e. validated the candidate because of the dispute.
+	pub participated: bool,
+	/// The outcome of our participation.
+	pub participation_outcome: Option<Outcome>,
+}
+
+/// The status of a dispute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+	/// The dispute is ongoing.
+	Active,
+	/// The dispute is ongoing and confirmed, i.e. not spam.
+	Confirmed,
+	/// The dispute concluded in favor of the candidate.
+	ConcludedFor,
+	/// The dispute concluded against the candidate.
+	ConcludedAgainst,
+}
+
+/// The side of a vote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+	/// The candidate is valid.
+	Valid,
+	/// The candidate is invalid.
+	Invalid,
+}
+
+/// A vote cast by our node.
+#[derive(Debug, Serialize)]
+pub struct OurVote {
+	/// The index of our validator in the session.
+	pub validator_index: u32,
+	/// The side of the vote.
+	pub outcome: Outcome,
+	/// The statement the vote was cast with, e.g. `backing_valid` or `explicit`.
+	pub kind: &'static str,
+}
+
+/// Build the report of the disputes stored in `db`.
+///
+/// Votes signed with one of `our_keys` are reported as ours.
+pub fn dispute_report(
+	db: Arc<dyn Database>,
+	config: Config,
+	our_keys: &[ValidatorId],
+) -> Result<DisputeReport, ReportError> {
+	let backend = DbBackend::new(db, config.column_config(), Metrics::default());
+	build_report(&backend, our_keys).map_err(ReportError)
+}
+
+/// The erasure roots of the candidates with votes in `db`, e.g. to verify their chunks.
+///
+/// Backing votes are imported for every included candidate, so this covers the candidates of the
+/// sessions votes are kept for.
+pub fn erasure_roots(
+	db: &Arc<dyn Database>,
+	config: Config,
+) -> Result<HashMap<CandidateHash, Hash>, ReportError> {
+	let votes = v1::load_all_candidate_votes(&**db, &config.column_config())?;
+	Ok(votes
+		.into_iter()
+		.map(|votes| {
+			let receipt = votes.candidate_receipt;
+			(receipt.hash(), receipt.descriptor.erasure_root())
+		})
+		.collect())
+}
+
+fn build_report(backend: &impl Backend, our_keys: &[ValidatorId]) -> FatalResult<DisputeReport> {
+	let earliest_session = backend.load_earliest_session()?;
+	let recent_disputes = backend.load_recent_disputes()?.unwrap_or_default();
+
+	let mut disputes = Vec::with_capacity(recent_disputes.len());
+	for ((session, candidate_hash), status) in recent_disputes {
+		let votes = backend.load_candidate_votes(session, &candidate_hash)?.map(Into::into);
+		disputes.push(dispute_entry(session, candidate_hash, status, votes, our_keys));
+	}
+
+	Ok(DisputeReport { earliest_session, disputes })
+}
+
+fn dispute_entry(
+	session: SessionIndex,
+	candidate_hash: CandidateHash,
+	status: DisputeStatus,
+	votes: Option<CandidateVotes>,
+	our_keys: &[ValidatorId],
+) -> DisputeEntry {
+	let (status, concluded_at) = match status {
+		DisputeStatus::Active => (Status::Active, None),
+		DisputeStatus::Confirmed => (Status::Confirmed, None),
+		DisputeStatus::ConcludedFor(at) => (Status::ConcludedFor, Some(at)),
+		DisputeStatus::ConcludedAgainst(at) => (Status::ConcludedAgainst, Some(at)),
+	};
+
+	let mut entry = DisputeEntry {
+		session,
+		candidate_hash: candidate_hash.0,
+		para_id: None,
+		relay_parent: None,
+		status,
+		concluded_at,
+		valid_votes: Vec::new(),
+		invalid_votes: Vec::new(),
+		our_votes: Vec::new(),
+		participated: false,
+		participation_outcome: None,
+	};
+	let Some(votes) = votes else { return entry };
+
+	entry.para_id = Some(votes.candidate_receipt.descriptor.para_id().into());
+	entry.relay_parent = Some(votes.candidate_receipt.descriptor.relay_parent());
+
+	let valid = votes.valid.raw().iter().map(|(index, (kind, signature))| {
+		(*index, DisputeStatement::Valid(kind.clone()), signature)
+	});
+	let invalid = votes.invalid.iter().map(|(index, (kind, signature))| {
+		(*index, DisputeStatement::Invalid(kind.clone()), signature)
+	});
+	for (index, statement, signature) in valid.chain(invalid) {
+		let outcome = if statement.indicates_validity() {
+			entry.valid_votes.push(index.0);
+			Outcome::Valid
+		} else {
+			entry.invalid_votes.push(index.0);
+			Outcome::Invalid
+		};
+
+		if !is_ours(&statement, candidate_hash, session, signature, our_keys) {
+			continue
+		}
+		// Explicit statements are only issued when participating in a dispute.
+		let participated = matches!(
+			statement,
+			DisputeStatement::Valid(ValidDisputeStatementKind::Explicit) |
+				DisputeStatement::Invalid(InvalidDisputeStatementKind::Explicit)
+		);
+		if participated {
+			entry.participated = true;
+			entry.participation_outcome = Some(outcome);
+		}
+		entry.our_votes.push(OurVote {
+			validator_index: index.0,
+			outcome,
+			kind: statement_kind(&statement),
+		});
+	}
+
+	entry
+}
+
+fn is_ours(
+	statement: &DisputeStatement,
+	candidate_hash: CandidateHash,
+	session: SessionIndex,
+	signature: &ValidatorSignature,
+	our_keys: &[ValidatorId],
+) -> bool {
+	our_keys
+		.iter()
+		.any(|key| statement.check_signature(key, candidate_hash, session, signature).is_ok())
+}
+
+fn statement_kind(statement: &DisputeStatement) -> &'static str {
+	match statement {
+		DisputeStatement::Valid(ValidDisputeStatementKind::Explicit) |
+		DisputeStatement::Invalid(InvalidDisputeStatementKind::Explicit) => "explicit",
+		DisputeStatement::Valid(ValidDisputeStatementKind::BackingSeconded(_)) =>
+			"backing_seconded",
+		DisputeStatement::Valid(ValidDisputeStatementKind::BackingValid(_)) => "backing_valid",
+		DisputeStatement::Valid(ValidDisputeStatementKind::ApprovalChecking) => "approval_checking",
+		DisputeStatement::Valid(ValidDisputeStatementKind::ApprovalCheckingMultipleCandidates(
+			_,
+		)) => "approval_checking_multiple_candidates",
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::backend::OverlayedBackend;
+	use polkadot_node_primitives::SignedDisputeStatement;
+	use polkadot_primitives::{vstaging::CandidateReceiptV2 as CandidateReceipt, ValidatorIndex};
+	use polkadot_primitives_test_helpers::{dummy_candidate_receipt_v2, dummy_hash};
+	use sc_keystore::LocalKeystore;
+	use sp_application_crypto::AppCrypto;
+	use sp_keyring::Sr25519Keyring;
+	use sp_keystore::{Keystore, KeystorePtr};
+
+	fn make_db() -> DbBackend {
+		let db = kvdb_memorydb::create(1);
+		let db = polkadot_node_subsystem_util::database::kvdb_impl::DbAdapter::new(db, &[0]);
+		let config = Config { col_dispute_data: 0 };
+		DbBackend::new(Arc::new(db), config.column_config(), Metrics::default())
+	}
+
+	fn sign(
+		keyring: Sr25519Keyring,
+		valid: bool,
+		candidate_hash: CandidateHash,
+		session: SessionIndex,
+	) -> ValidatorSignature {
+		let keystore: KeystorePtr = Arc::new(LocalKeystore::in_memory());
+		keystore
+			.sr25519_generate_new(ValidatorId::ID, Some(&keyring.to_seed()))
+			.unwrap();
+		SignedDisputeStatement::sign_explicit(
+			&keystore,
+			valid,
+			candidate_hash,
+			session,
+			keyring.public().into(),
+		)
+		.unwrap()
+		.unwrap()
+		.validator_signature()
+		.clone()
+	}
+
+	#[test]
+	fn reports_our_participation() {
+		let mut backend = make_db();
+		let session = 1;
+		let candidate_receipt: CandidateReceipt = dummy_candidate_receipt_v2(dummy_hash());
+		let candidate_hash = candidate_receipt.hash();
+
+		let alice_signature = sign(Sr25519Keyring::Alice, false, candidate_hash, session);
+		let bob_signature = sign(Sr25519Keyring::Bob, true, candidate_hash, session);
+		let votes = CandidateVotes {
+			candidate_receipt,
+			valid: [(ValidatorIndex(1), (ValidDisputeStatementKind::Explicit, bob_signature))]
+				.into_iter()
+				.collect(),
+			invalid: [(
+				ValidatorIndex(0),
+				(InvalidDisputeStatementKind::Explicit, alice_signature),
+			)]
+			.into_iter()
+			.collect(),
+		};
+
+		let mut overlay_db = OverlayedBackend::new(&backend);
+		overlay_db.write_earliest_session(0);
+		overlay_db.write_recent_disputes(
+			[((session, candidate_hash), DisputeStatus::ConcludedAgainst(42))]
+				.into_iter()
+				.collect(),
+		);
+		overlay_db.write_candidate_votes(session, candidate_hash, votes.into());
+		let write_ops = overlay_db.into_write_ops();
+		backend.write(write_ops).unwrap();
+
+		let report = build_report(&backend, &[Sr25519Keyring::Bob.public().into()]).unwrap();
+		assert_eq!(report.earliest_session, Some(0));
+		assert_eq!(report.disputes.len(), 1);
+
+		let dispute = &report.disputes[0];
+		assert_eq!(dispute.candidate_hash, candidate_hash.0);
+		assert_eq!(dispute.status, Status::ConcludedAgainst);
+		assert_eq!(dispute.concluded_at, Some(42));
+		assert_eq!(dispute.valid_votes, vec![1]);
+		assert_eq!(dispute.invalid_votes, vec![0]);
+		assert_eq!(dispute.our_votes.len(), 1);
+		assert_eq!(dispute.our_votes[0].validator_index, 1);
+		assert_eq!(dispute.our_votes[0].kind, "explicit");
+		assert!(dispute.participated);
+		assert_eq!(dispute.participation_outcome, Some(Outcome::Valid));
+	}
+}
@@ -37,6 +37,8 @@ pub struct WorkerResponse {
 	pub duration: Duration,
 	/// The uncompressed PoV size.
 	pub pov_size: u32,
+	/// The `ru_maxrss` (maximum resident set size) of the job, if it ran.
+	pub max_rss: Option<i64>,
 }
 
 /// An error occurred in the worker process.
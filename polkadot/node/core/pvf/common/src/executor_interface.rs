@@ -93,6 +93,9 @@ pub const DEFAULT_CONFIG: Config = Config {
 		wasm_simd: false,
 		wasm_bulk_memory: false,
 		wasm_multi_value: false,
+
+		// The PVF execution is bounded by a timeout instead.
+		fuel_metering: false,
 	},
 };
 
@@ -56,6 +56,7 @@ use polkadot_parachain_primitives::primitives::ValidationResult;
 use polkadot_primitives::{ExecutorParams, PersistedValidationData};
 use std::{
 	io::{self, Read},
+	mem::MaybeUninit,
 	os::{
 		fd::{AsRawFd, FromRawFd},
 		unix::net::UnixStream,
@@ -225,6 +226,7 @@ pub fn worker_entrypoint(
 									job_response: JobResponse::PoVDecompressionFailure,
 									duration: Duration::ZERO,
 									pov_size: 0,
+									max_rss: None,
 								}),
 								worker_info,
 							)?;
@@ -583,7 +585,7 @@ fn handle_parent_process(
 		// Should retry at any rate.
 		.map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
 
-	let status = nix::sys::wait::waitpid(job_pid, None);
+	let (status, max_rss) = wait_for_job(job_pid);
 	gum::trace!(
 		target: LOG_TARGET,
 		?worker_info,
@@ -629,7 +631,7 @@ fn handle_parent_process(
 						))));
 					}
 
-					Ok(Ok(WorkerResponse { job_response, pov_size, duration: cpu_tv }))
+					Ok(Ok(WorkerResponse { job_response, pov_size, duration: cpu_tv, max_rss }))
 				},
 				Err(job_error) => {
 					gum::warn!(
@@ -666,6 +668,26 @@ fn handle_parent_process(
 	}
 }
 
+/// Waits for the job process to terminate, and returns its wait status along with its
+/// `ru_maxrss`.
+///
+/// `getrusage(RUSAGE_CHILDREN)` reports the largest `ru_maxrss` of all the terminated children of
+/// the worker, so `wait4` is used to get the usage of this job alone.
+fn wait_for_job(job_pid: Pid) -> (nix::Result<WaitStatus>, Option<i64>) {
+	let mut status = 0;
+	let mut usage: MaybeUninit<libc::rusage> = MaybeUninit::zeroed();
+
+	// SAFETY: `status` and `usage` are valid pointers, so calling this is safe.
+	if unsafe { libc::wait4(job_pid.as_raw(), &mut status, 0, usage.as_mut_ptr()) } == -1 {
+		return (Err(Errno::last()), None)
+	}
+
+	// SAFETY: `usage` was successfully initialized by `wait4`.
+	// `c_long` is either `i32` or `i64` depending on architecture. `i64::from` always works.
+	let max_rss = i64::from(unsafe { usage.assume_init() }.ru_maxrss);
+	(WaitStatus::from_raw(job_pid, status), Some(max_rss))
+}
+
 /// Write a job response to the pipe and exit process after.
 ///
 /// # Arguments
@@ -443,6 +443,14 @@ async fn handle_job_finish(
 	artifact_id: ArtifactId,
 	result_tx: ResultSender,
 ) {
+	if let Ok(WorkerInterfaceResponse {
+		worker_response: WorkerResponse { max_rss: Some(max_rss), .. },
+		..
+	}) = &worker_result
+	{
+		queue.metrics.observe_execution_max_rss(*max_rss);
+	}
+
 	let (idle_worker, result, duration, sync_channel, pov_size) = match worker_result {
 		Ok(WorkerInterfaceResponse {
 			worker_response:
@@ -450,6 +458,7 @@ async fn handle_job_finish(
 					job_response: JobResponse::Ok { result_descriptor },
 					duration,
 					pov_size,
+					..
 				},
 			idle_worker,
 		}) => {
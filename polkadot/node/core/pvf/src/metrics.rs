@@ -81,6 +81,13 @@ impl Metrics {
 		});
 	}
 
+	/// Observe the `ru_maxrss` of an execution job.
+	pub(crate) fn observe_execution_max_rss(&self, max_rss: i64) {
+		if let Some(metrics) = &self.0 {
+			metrics.execution_max_rss.observe(max_rss as f64);
+		}
+	}
+
 	/// Observe memory stats for preparation.
 	#[allow(unused_variables)]
 	pub(crate) fn observe_preparation_memory_metrics(&self, memory_stats: MemoryStats) {
@@ -142,6 +149,7 @@ struct MetricsInner {
 	preparation_time: prometheus::Histogram,
 	execution_time: prometheus::Histogram,
 	execution_queued_time: prometheus::Histogram,
+	execution_max_rss: prometheus::Histogram,
 	#[cfg(target_os = "linux")]
 	preparation_max_rss: prometheus::Histogram,
 	// Max. allocated memory, tracked by Jemallocator, polling-based
@@ -298,6 +306,18 @@ impl metrics::Metrics for Metrics {
 				)?,
 				registry,
 			)?,
+			execution_max_rss: prometheus::register(
+				prometheus::Histogram::with_opts(
+					prometheus::HistogramOpts::new(
+						"polkadot_pvf_execution_max_rss",
+						"ru_maxrss (maximum resident set size) observed for execution (in kilobytes)",
+					).buckets(
+						prometheus::exponential_buckets(8192.0, 2.0, 10)
+							.expect("arguments are always valid; qed"),
+					),
+				)?,
+				registry,
+			)?,
 			#[cfg(target_os = "linux")]
 			preparation_max_rss: prometheus::register(
 				prometheus::Histogram::with_opts(
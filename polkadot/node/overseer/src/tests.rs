@@ -831,6 +831,7 @@ fn test_collator_generation_msg() -> CollationGenerationMessage {
 		key: CollatorPair::generate().0,
 		collator: Some(Box::new(|_, _| TestCollator.boxed())),
 		para_id: Default::default(),
+		pov_dump: None,
 	})
 }
 struct TestCollator;
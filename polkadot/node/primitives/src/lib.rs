@@ -57,6 +57,9 @@ pub use disputes::{
 	ValidDisputeVote, ACTIVE_DURATION_SECS,
 };
 
+#[cfg(not(target_os = "unknown"))]
+pub mod pov_dump;
+
 /// The current node version, which takes the basic SemVer form `<major>.<minor>.<patch>`.
 /// In general, minor should be bumped on every release while major or patch releases are
 /// relatively rare.
@@ -510,6 +513,8 @@ pub struct CollationGenerationConfig {
 	pub collator: Option<CollatorFn>,
 	/// The parachain that this collator collates for
 	pub para_id: ParaId,
+	/// If set, the collations are dumped to be validated again with the PVF host.
+	pub pov_dump: Option<pov_dump::PovDumpConfig>,
 }
 
 #[cfg(not(target_os = "unknown"))]
@@ -0,0 +1,307 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dumps of candidates, to investigate the candidates rejected by validators.
+//!
+//! A dump holds everything needed to run a candidate through the PVF host again: its [`PoV`],
+//! its [`PersistedValidationData`] and the executor parameters of its session. Each dump is
+//! written to a `<candidate hash>.pov` file, and the validation code it refers to is written once
+//! to a `<validation code hash>.code` file of the same directory. The oldest dumps are removed to
+//! keep the size of the directory under [`PovDumpConfig::max_size`].
+
+use crate::PoV;
+use codec::{Decode, Encode};
+use polkadot_primitives::{
+	CandidateHash, ExecutorParams, Hash, Id as ParaId, PersistedValidationData, PvfExecKind,
+	ValidationCode, ValidationCodeHash,
+};
+use std::{
+	fs,
+	io::{self, Write},
+	num::NonZeroU32,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+/// Extension of the candidate dumps.
+pub const POV_DUMP_EXTENSION: &str = "pov";
+
+/// Extension of the validation code files.
+pub const VALIDATION_CODE_EXTENSION: &str = "code";
+
+/// Default [`PovDumpConfig::max_size`], in MiB.
+pub const DEFAULT_MAX_SIZE_MIB: u64 = 1024;
+
+/// Which candidates are dumped, and where.
+#[derive(Clone, Debug)]
+pub struct PovDumpConfig {
+	/// The directory the dumps are written to.
+	pub path: PathBuf,
+	/// Dump one in `sample_rate` candidates, whatever the outcome of their validation.
+	///
+	/// If `None`, only the candidates which fail validation are dumped.
+	pub sample_rate: Option<NonZeroU32>,
+	/// Maximum size of the files in `path`, in bytes.
+	///
+	/// The oldest dumps are removed to make room for the new ones, which aren't written if that
+	/// isn't enough.
+	pub max_size: u64,
+}
+
+impl PovDumpConfig {
+	/// Whether the candidate should be dumped, given whether it failed validation.
+	///
+	/// The sampling only depends on the candidate hash, so that all the nodes sampling at the same
+	/// rate dump the same candidates.
+	pub fn should_dump(&self, candidate_hash: &CandidateHash, failed: bool) -> bool {
+		failed ||
+			self.sample_rate.is_some_and(|sample_rate| {
+				let bytes = candidate_hash.0.as_bytes();
+				let sample = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+				sample % sample_rate.get() == 0
+			})
+	}
+}
+
+/// A candidate, along with everything needed to validate it again.
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct PovDump {
+	/// The hash of the candidate.
+	pub candidate_hash: CandidateHash,
+	/// The parachain of the candidate.
+	pub para_id: ParaId,
+	/// The relay parent of the candidate.
+	pub relay_parent: Hash,
+	/// The hash of the validation code, stored next to the dump.
+	pub validation_code_hash: ValidationCodeHash,
+	/// The maximum size of the decompressed validation code.
+	pub validation_code_bomb_limit: u32,
+	/// The executor parameters of the session of the candidate.
+	pub executor_params: ExecutorParams,
+	/// The kind of execution the candidate went through, which determines its timeout.
+	pub exec_kind: PvfExecKind,
+	/// The persisted validation data of the candidate.
+	pub persisted_validation_data: PersistedValidationData,
+	/// The proof of validity of the candidate.
+	pub pov: Arc<PoV>,
+	/// Why the validation failed, `None` if it didn't fail or the candidate wasn't validated.
+	pub error: Option<String>,
+}
+
+impl PovDump {
+	/// Read the dump at `path`.
+	pub fn read(path: &Path) -> io::Result<Self> {
+		let file = fs::read(path)?;
+		Self::decode(&mut &file[..]).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+
+	/// Write the dump to `dir`, returning the path of the written file.
+	pub fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+		let path = self.path(dir);
+		write_atomically(&path, &self.encode())?;
+		Ok(path)
+	}
+
+	/// Write the dump to the directory of `config`, along with its `validation_code` unless it is
+	/// already there.
+	///
+	/// The oldest dumps are removed to keep the directory under [`PovDumpConfig::max_size`].
+	/// Returns the path of the written file, `None` if there is no room for the dump.
+	pub fn write_capped(
+		&self,
+		config: &PovDumpConfig,
+		validation_code: Option<&ValidationCode>,
+	) -> io::Result<Option<PathBuf>> {
+		let dump = self.encode();
+		let code_path = validation_code_path(&config.path, &self.validation_code_hash);
+		let validation_code = validation_code.filter(|_| !code_path.exists());
+		let size = dump.len() + validation_code.map_or(0, |code| code.0.len());
+		if !make_room(&config.path, config.max_size, size as u64)? {
+			return Ok(None)
+		}
+
+		if let Some(validation_code) = validation_code {
+			write_atomically(&code_path, &validation_code.0)?;
+		}
+		let path = self.path(&config.path);
+		write_atomically(&path, &dump)?;
+		Ok(Some(path))
+	}
+
+	fn path(&self, dir: &Path) -> PathBuf {
+		dir.join(format!("{:?}.{POV_DUMP_EXTENSION}", self.candidate_hash.0))
+	}
+}
+
+/// The path of the validation code with the given hash in `dir`.
+pub fn validation_code_path(dir: &Path, validation_code_hash: &ValidationCodeHash) -> PathBuf {
+	dir.join(format!("{validation_code_hash:?}.{VALIDATION_CODE_EXTENSION}"))
+}
+
+/// Write the validation code to `dir`, unless it is already there.
+pub fn write_validation_code(dir: &Path, validation_code: &ValidationCode) -> io::Result<()> {
+	let path = validation_code_path(dir, &validation_code.hash());
+	if path.exists() {
+		return Ok(())
+	}
+	write_atomically(&path, &validation_code.0)
+}
+
+/// Read the validation code with the given hash from `dir`.
+pub fn read_validation_code(
+	dir: &Path,
+	validation_code_hash: &ValidationCodeHash,
+) -> io::Result<ValidationCode> {
+	fs::read(validation_code_path(dir, validation_code_hash)).map(ValidationCode)
+}
+
+// Remove the oldest dumps of `dir` until `size` more bytes fit in `max_size`. Returns `false` if
+// they don't fit once all the dumps are removed.
+fn make_room(dir: &Path, max_size: u64, size: u64) -> io::Result<bool> {
+	let entries = match fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(size <= max_size),
+		Err(e) => return Err(e),
+	};
+
+	let mut total = 0u64;
+	let mut dumps = Vec::new();
+	for entry in entries {
+		let entry = entry?;
+		let metadata = entry.metadata()?;
+		if !metadata.is_file() {
+			continue
+		}
+		total += metadata.len();
+		let path = entry.path();
+		if path.extension().is_some_and(|extension| extension == POV_DUMP_EXTENSION) {
+			dumps.push((metadata.modified()?, metadata.len(), path));
+		}
+	}
+
+	// Oldest first.
+	dumps.sort_unstable_by_key(|(modified, ..)| *modified);
+	let mut dumps = dumps.into_iter();
+	while total.saturating_add(size) > max_size {
+		let Some((_, len, path)) = dumps.next() else { return Ok(false) };
+		fs::remove_file(path)?;
+		total -= len;
+	}
+	Ok(true)
+}
+
+// Write to a temporary file first, so that readers never see a partial file.
+fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	let tmp_path = path.with_extension("tmp");
+	let mut file = fs::File::create(&tmp_path)?;
+	file.write_all(data)?;
+	file.sync_all()?;
+	fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::BlockData;
+
+	#[test]
+	fn dumps_roundtrip() {
+		let dir = tempfile::tempdir().unwrap();
+		let validation_code = ValidationCode(vec![1, 2, 3]);
+		let dump = PovDump {
+			candidate_hash: CandidateHash(Hash::repeat_byte(1)),
+			para_id: 100.into(),
+			relay_parent: Hash::repeat_byte(2),
+			validation_code_hash: validation_code.hash(),
+			validation_code_bomb_limit: 1024,
+			executor_params: ExecutorParams::default(),
+			exec_kind: PvfExecKind::Backing,
+			persisted_validation_data: PersistedValidationData::default(),
+			pov: Arc::new(PoV { block_data: BlockData(vec![4, 5, 6]) }),
+			error: Some("invalid".into()),
+		};
+
+		let path = dump.write(dir.path()).unwrap();
+		write_validation_code(dir.path(), &validation_code).unwrap();
+
+		assert_eq!(PovDump::read(&path).unwrap(), dump);
+		assert_eq!(
+			read_validation_code(dir.path(), &dump.validation_code_hash).unwrap(),
+			validation_code,
+		);
+	}
+
+	#[test]
+	fn sampling_depends_on_candidate_hash() {
+		let config = PovDumpConfig {
+			path: PathBuf::new(),
+			sample_rate: NonZeroU32::new(2),
+			max_size: DEFAULT_MAX_SIZE_MIB * 1024 * 1024,
+		};
+		let even = CandidateHash(Hash::repeat_byte(2));
+		let odd = CandidateHash(Hash::repeat_byte(1));
+
+		assert!(config.should_dump(&even, false));
+		assert!(!config.should_dump(&odd, false));
+		assert!(config.should_dump(&odd, true));
+
+		let config = PovDumpConfig { sample_rate: None, ..config };
+		assert!(!config.should_dump(&even, false));
+		assert!(config.should_dump(&even, true));
+	}
+
+	#[test]
+	fn oldest_dumps_are_removed_to_stay_under_max_size() {
+		let dir = tempfile::tempdir().unwrap();
+		let validation_code = ValidationCode(vec![1; 100]);
+		let dump = |byte| PovDump {
+			candidate_hash: CandidateHash(Hash::repeat_byte(byte)),
+			para_id: 100.into(),
+			relay_parent: Hash::repeat_byte(2),
+			validation_code_hash: validation_code.hash(),
+			validation_code_bomb_limit: 1024,
+			executor_params: ExecutorParams::default(),
+			exec_kind: PvfExecKind::Backing,
+			persisted_validation_data: PersistedValidationData::default(),
+			pov: Arc::new(PoV { block_data: BlockData(vec![byte; 1000]) }),
+			error: None,
+		};
+		let (first, second) = (dump(1), dump(2));
+		// Room for the validation code and a single dump.
+		let config = PovDumpConfig {
+			path: dir.path().to_path_buf(),
+			sample_rate: None,
+			max_size: 100 + first.encode().len() as u64 + 10,
+		};
+
+		let first_path = first.write_capped(&config, Some(&validation_code)).unwrap().unwrap();
+		let second_path = second.write_capped(&config, Some(&validation_code)).unwrap().unwrap();
+
+		assert!(!first_path.exists());
+		assert_eq!(PovDump::read(&second_path).unwrap(), second);
+		assert_eq!(
+			read_validation_code(dir.path(), &second.validation_code_hash).unwrap(),
+			validation_code,
+		);
+
+		// A dump that doesn't fit once the others are removed isn't written.
+		let config = PovDumpConfig { max_size: 500, ..config };
+		assert!(dump(3).write_capped(&config, None).unwrap().is_none());
+	}
+}
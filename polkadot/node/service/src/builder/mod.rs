@@ -45,10 +45,12 @@ use polkadot_node_network_protocol::{
 	peer_set::{PeerSet, PeerSetProtocolNames},
 	request_response::{IncomingRequest, ReqProtocolNames},
 };
+use polkadot_node_primitives::pov_dump::PovDumpConfig;
 use polkadot_node_subsystem_types::DefaultSubsystemClient;
 use polkadot_overseer::{Handle, OverseerConnector};
 use polkadot_primitives::Block;
 use sc_client_api::Backend;
+use sc_consensus_slashing_protection::SlashingProtection;
 use sc_network::config::FullNetworkConfiguration;
 use sc_network_sync::WarpSyncConfig;
 use sc_service::{Configuration, RpcHandlers, TaskManager};
@@ -92,6 +94,8 @@ pub struct NewFullParams<OverseerGenerator: OverseerGen> {
 	pub hwbench: Option<sc_sysinfo::HwBench>,
 	/// Enable approval voting processing in parallel.
 	pub enable_approval_voting_parallel: bool,
+	/// If set, the validated candidates are dumped to be validated again with the PVF host.
+	pub pov_dump: Option<PovDumpConfig>,
 }
 
 /// Completely built polkadot node service.
@@ -203,6 +207,7 @@ where
 					prepare_workers_hard_max_num,
 					keep_finalized_for,
 					enable_approval_voting_parallel,
+					pov_dump,
 				},
 			overseer_connector,
 			partial_components:
@@ -221,6 +226,7 @@ where
 		} = self;
 
 		let role = config.role;
+		let data_path = config.data_path.clone();
 		let auth_or_collator = config.role.is_authority() || is_parachain_node.is_collator();
 		let is_offchain_indexing_enabled = config.offchain_worker.indexing_enabled;
 		let force_authoring = config.force_authoring;
@@ -393,6 +399,7 @@ where
 					pvf_execute_workers_max_num: execute_workers_max_num.unwrap_or(4),
 					pvf_prepare_workers_soft_max_num: prepare_workers_soft_max_num.unwrap_or(1),
 					pvf_prepare_workers_hard_max_num: prepare_workers_hard_max_num.unwrap_or(2),
+					pov_dump,
 				})
 			} else {
 				None
@@ -431,7 +438,7 @@ where
 			};
 
 			Some(ExtendedOverseerGenArgs {
-				keystore: keystore_container.local_keystore(),
+				keystore: keystore_container.local_keystore()?,
 				parachains_db,
 				candidate_validation_config,
 				availability_config,
@@ -719,6 +726,12 @@ where
 		// need a keystore, regardless of which protocol we use below.
 		let keystore_opt =
 			if role.is_authority() { Some(keystore_container.keystore()) } else { None };
+		let slashing_protection = if role.is_authority() {
+			let path = data_path.join(sc_consensus_slashing_protection::DEFAULT_FILE_NAME);
+			Some(Arc::new(SlashingProtection::open(path)?))
+		} else {
+			None
+		};
 
 		// beefy is enabled if its notification service exists
 		if let Some(notification_service) = beefy_notification_service {
@@ -739,6 +752,7 @@ where
 				payload_provider,
 				runtime: client.clone(),
 				key_store: keystore_opt.clone(),
+				slashing_protection: slashing_protection.clone(),
 				network_params,
 				min_block_delta: 8,
 				prometheus_registry: prometheus_registry.clone(),
@@ -786,6 +800,7 @@ where
 			name: Some(name),
 			observer_enabled: false,
 			keystore: keystore_opt,
+			slashing_protection,
 			local_role: role,
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
 			protocol_name: grandpa_protocol_name,
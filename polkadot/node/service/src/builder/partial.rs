@@ -109,13 +109,16 @@ pub(crate) fn new_partial_basics(
 		.default_heap_pages
 		.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |h| HeapAllocStrategy::Static { extra_pages: h as _ });
 
-	let executor = WasmExecutor::builder()
+	let mut executor = WasmExecutor::builder()
 		.with_execution_method(config.executor.wasm_method)
 		.with_onchain_heap_alloc_strategy(heap_pages)
 		.with_offchain_heap_alloc_strategy(heap_pages)
 		.with_max_runtime_instances(config.executor.max_runtime_instances)
-		.with_runtime_cache_size(config.executor.runtime_cache_size)
-		.build();
+		.with_runtime_cache_size(config.executor.runtime_cache_size);
+	if let Some(path) = &config.executor.artifact_cache_path {
+		executor = executor.with_artifact_cache_path(path);
+	}
+	let executor = executor.build();
 
 	let (client, backend, keystore_container, task_manager) =
 		sc_service::new_full_parts::<Block, RuntimeApi, _>(
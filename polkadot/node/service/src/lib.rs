@@ -205,6 +205,10 @@ pub enum Error {
 	#[error(transparent)]
 	Telemetry(#[from] sc_telemetry::Error),
 
+	#[cfg(feature = "full-node")]
+	#[error(transparent)]
+	SlashingProtection(#[from] sc_consensus_slashing_protection::Error),
+
 	#[cfg(feature = "full-node")]
 	#[error(transparent)]
 	Availability(#[from] AvailabilityError),
@@ -216,6 +220,10 @@ pub enum Error {
 	#[error("Creating a custom database is required for validators")]
 	DatabasePathRequired,
 
+	#[cfg(feature = "full-node")]
+	#[error(transparent)]
+	DisputeReport(#[from] polkadot_node_core_dispute_coordinator::report::ReportError),
+
 	#[cfg(feature = "full-node")]
 	#[error("Expected at least one of polkadot, kusama, westend or rococo runtime feature")]
 	NoRuntime,
@@ -345,6 +353,110 @@ pub fn open_database(db_source: &DatabaseSource) -> Result<Arc<dyn Database>, Er
 	Ok(parachains_db)
 }
 
+/// Open the existing parachains database of a node which isn't running, without creating or
+/// upgrading it.
+#[cfg(feature = "full-node")]
+fn open_existing_database(db_source: &DatabaseSource) -> Result<Arc<dyn Database>, Error> {
+	let parachains_db = match db_source {
+		DatabaseSource::RocksDb { path, .. } => parachains_db::open_existing_rocksdb(path.clone())?,
+		DatabaseSource::ParityDb { path, .. } => parachains_db::open_existing_paritydb(
+			path.parent().ok_or(Error::DatabasePathRequired)?.into(),
+		)?,
+		DatabaseSource::Auto { paritydb_path, rocksdb_path, .. } => {
+			if paritydb_path.is_dir() && paritydb_path.exists() {
+				parachains_db::open_existing_paritydb(
+					paritydb_path.parent().ok_or(Error::DatabasePathRequired)?.into(),
+				)?
+			} else {
+				parachains_db::open_existing_rocksdb(rocksdb_path.clone())?
+			}
+		},
+		DatabaseSource::Custom { .. } => {
+			unimplemented!("No polkadot subsystem db for custom source.");
+		},
+	};
+	Ok(parachains_db)
+}
+
+/// Report the disputes stored in the parachains database of the node, which must not be running.
+///
+/// The votes signed with the validator keys of the keystore are reported as ours.
+#[cfg(feature = "full-node")]
+pub fn dispute_report(
+	config: &Configuration,
+) -> Result<polkadot_node_core_dispute_coordinator::report::DisputeReport, Error> {
+	let parachains_db = open_existing_database(&config.database)?;
+	let keystore = sc_service::KeystoreContainer::new(&config.keystore)?.keystore();
+	let our_keys: Vec<polkadot_primitives::ValidatorId> = keystore
+		.sr25519_public_keys(polkadot_primitives::PARACHAIN_KEY_TYPE_ID)
+		.into_iter()
+		.map(Into::into)
+		.collect();
+
+	let config = polkadot_node_core_dispute_coordinator::Config {
+		col_dispute_data: parachains_db::REAL_COLUMNS.col_dispute_coordinator_data,
+	};
+	Ok(polkadot_node_core_dispute_coordinator::report::dispute_report(
+		parachains_db,
+		config,
+		&our_keys,
+	)?)
+}
+
+/// Report the content of the availability store of the node, which must not be running.
+///
+/// If `verify` is set, the stored chunks are verified against the erasure root of their candidate,
+/// as recorded by the dispute coordinator or else computed from the stored available data.
+#[cfg(feature = "full-node")]
+pub fn inspect_availability_store(
+	config: &Configuration,
+	verify: bool,
+) -> Result<polkadot_node_core_av_store::inspect::StoreReport, Error> {
+	let parachains_db = open_existing_database(&config.database)?;
+	// The receipts of the candidates are kept with their votes by the dispute coordinator.
+	let erasure_roots = if verify {
+		polkadot_node_core_dispute_coordinator::report::erasure_roots(
+			&parachains_db,
+			polkadot_node_core_dispute_coordinator::Config {
+				col_dispute_data: parachains_db::REAL_COLUMNS.col_dispute_coordinator_data,
+			},
+		)?
+	} else {
+		Default::default()
+	};
+	Ok(polkadot_node_core_av_store::inspect::inspect(
+		&parachains_db,
+		&availability_config(),
+		verify,
+		&erasure_roots,
+	)?)
+}
+
+/// Load the available data of a candidate from the availability store of the node, which must
+/// not be running.
+#[cfg(feature = "full-node")]
+pub fn export_available_data(
+	config: &Configuration,
+	candidate_hash: &polkadot_primitives::CandidateHash,
+) -> Result<Option<polkadot_node_primitives::AvailableData>, Error> {
+	let parachains_db = open_existing_database(&config.database)?;
+	Ok(polkadot_node_core_av_store::inspect::export_available_data(
+		&parachains_db,
+		&availability_config(),
+		candidate_hash,
+	)?)
+}
+
+#[cfg(feature = "full-node")]
+fn availability_config() -> polkadot_node_core_av_store::Config {
+	polkadot_node_core_av_store::Config {
+		col_data: parachains_db::REAL_COLUMNS.col_availability_data,
+		col_meta: parachains_db::REAL_COLUMNS.col_availability_meta,
+		// Only used for pruning.
+		keep_finalized_for: KEEP_FINALIZED_FOR_LIVE_NETWORKS,
+	}
+}
+
 /// Is this node running as in-process node for a parachain node?
 #[cfg(feature = "full-node")]
 #[derive(Clone)]
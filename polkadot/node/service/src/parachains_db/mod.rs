@@ -150,6 +150,56 @@ pub fn open_creating_rocksdb(
 	Ok(Arc::new(db))
 }
 
+/// Open the existing database on disk read-only, without creating or upgrading it.
+///
+/// The database is opened as a RocksDB secondary instance, so it can be read while a node is
+/// running on it. Fails if the database doesn't exist or doesn't have the current version.
+#[cfg(feature = "full-node")]
+pub fn open_existing_rocksdb(root: PathBuf) -> io::Result<Arc<dyn Database>> {
+	use kvdb_rocksdb::{Database, DatabaseConfig};
+
+	let path = root.join("parachains").join("db");
+
+	let mut db_config = DatabaseConfig::with_columns(columns::v4::NUM_COLUMNS);
+	db_config.create_if_missing = false;
+	// The secondary instance only writes its info logs there, the database is left untouched.
+	db_config.secondary = Some(
+		std::env::temp_dir()
+			.join(format!("polkadot-parachains-db-secondary-{}", std::process::id())),
+	);
+
+	let path_str = path
+		.to_str()
+		.ok_or_else(|| other_io_error(format!("Bad database path: {:?}", path)))?;
+
+	upgrade::check_db_version(&path, DatabaseKind::RocksDB)?;
+	let db = Database::open(&db_config, &path_str)?;
+	let db = polkadot_node_subsystem_util::database::kvdb_impl::DbAdapter::new(
+		db,
+		columns::v4::ORDERED_COL,
+	);
+
+	Ok(Arc::new(db))
+}
+
+/// Open the existing parity db database read-only, without creating or upgrading it.
+///
+/// Fails if the database doesn't exist or doesn't have the current version.
+#[cfg(feature = "full-node")]
+pub fn open_existing_paritydb(root: PathBuf) -> io::Result<Arc<dyn Database>> {
+	let path = root.join("parachains");
+
+	upgrade::check_db_version(&path, DatabaseKind::ParityDB)?;
+	let db = parity_db::Db::open_read_only(&upgrade::paritydb_version_3_config(&path))
+		.map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
+
+	let db = polkadot_node_subsystem_util::database::paritydb_impl::DbAdapter::new(
+		db,
+		columns::v4::ORDERED_COL,
+	);
+	Ok(Arc::new(db))
+}
+
 /// Open a parity db database.
 #[cfg(feature = "full-node")]
 pub fn open_creating_paritydb(
@@ -54,6 +54,12 @@ pub enum Error {
 	MigrationFailed,
 	#[error("Parachain DB migration would take forever")]
 	MigrationLoop,
+	#[error("Parachains DB not found")]
+	NotFound,
+	#[error(
+		"Parachains DB has version {got:?} instead of {current:?}, the node must upgrade it first"
+	)]
+	VersionMismatch { current: Version, got: Option<Version> },
 }
 
 impl From<Error> for io::Error {
@@ -131,6 +137,21 @@ pub(crate) fn try_upgrade_db_to_next_version(
 	Ok(new_version)
 }
 
+/// Check that the parachain's database exists and has the current version, without upgrading it.
+pub(crate) fn check_db_version(db_path: &Path, db_kind: DatabaseKind) -> Result<(), Error> {
+	let is_empty = db_path.read_dir().map_or(true, |mut d| d.next().is_none());
+	if is_empty {
+		return Err(Error::NotFound)
+	}
+
+	match get_db_version(db_path)? {
+		Some(CURRENT_VERSION) => Ok(()),
+		// No version file. For `RocksDB` this is handled as the current version by the upgrade.
+		None if db_kind == DatabaseKind::RocksDB => Ok(()),
+		got => Err(Error::VersionMismatch { current: CURRENT_VERSION, got }),
+	}
+}
+
 /// Reads current database version from the file at given path.
 /// If the file does not exist returns `None`, otherwise the version stored in the file.
 fn get_db_version(path: &Path) -> Result<Option<Version>, Error> {
@@ -754,4 +775,22 @@ mod tests {
 
 		assert_eq!(db.num_columns(), super::columns::v3::NUM_COLUMNS);
 	}
+
+	#[test]
+	fn check_db_version_does_not_upgrade() {
+		let db_dir = tempfile::tempdir().unwrap();
+		let db_path = db_dir.path().join("parachains");
+
+		assert!(matches!(check_db_version(&db_path, DatabaseKind::ParityDB), Err(Error::NotFound)));
+
+		update_version(&db_path, 2).unwrap();
+		assert!(matches!(
+			check_db_version(&db_path, DatabaseKind::ParityDB),
+			Err(Error::VersionMismatch { current: CURRENT_VERSION, got: Some(2) })
+		));
+		assert_eq!(get_db_version(&db_path).unwrap(), Some(2));
+
+		update_version(&db_path, CURRENT_VERSION).unwrap();
+		check_db_version(&db_path, DatabaseKind::ParityDB).unwrap();
+	}
 }
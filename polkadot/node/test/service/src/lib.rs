@@ -104,6 +104,7 @@ pub fn new_full<OverseerGenerator: OverseerGen>(
 					prepare_workers_soft_max_num: None,
 					enable_approval_voting_parallel: false,
 					keep_finalized_for: None,
+					pov_dump: None,
 				},
 			),
 		sc_network::config::NetworkBackendType::Litep2p =>
@@ -127,6 +128,7 @@ pub fn new_full<OverseerGenerator: OverseerGen>(
 					prepare_workers_soft_max_num: None,
 					enable_approval_voting_parallel: false,
 					keep_finalized_for: None,
+					pov_dump: None,
 				},
 			),
 	}
@@ -424,8 +426,12 @@ impl PolkadotTestNode {
 		para_id: ParaId,
 		collator: CollatorFn,
 	) {
-		let config =
-			CollationGenerationConfig { key: collator_key, collator: Some(collator), para_id };
+		let config = CollationGenerationConfig {
+			key: collator_key,
+			collator: Some(collator),
+			para_id,
+			pov_dump: None,
+		};
 
 		self.overseer_handle
 			.send_msg(CollationGenerationMessage::Initialize(config), "Collator")
@@ -99,6 +99,7 @@ fn main() -> Result<()> {
 						prepare_workers_soft_max_num: None,
 						enable_approval_voting_parallel: false,
 						keep_finalized_for: None,
+						pov_dump: None,
 					},
 				)
 				.map_err(|e| e.to_string())?;
@@ -123,6 +124,7 @@ fn main() -> Result<()> {
 						collator.create_collation_function(full_node.task_manager.spawn_handle()),
 					),
 					para_id,
+					pov_dump: None,
 				};
 				overseer_handle
 					.send_msg(CollationGenerationMessage::Initialize(config), "Collator")
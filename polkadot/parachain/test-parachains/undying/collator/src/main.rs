@@ -111,6 +111,7 @@ fn main() -> Result<()> {
 						prepare_workers_soft_max_num: None,
 						enable_approval_voting_parallel: false,
 						keep_finalized_for: None,
+						pov_dump: None,
 					},
 				)
 				.map_err(|e| e.to_string())?;
@@ -143,6 +144,7 @@ fn main() -> Result<()> {
 						None
 					},
 					para_id,
+					pov_dump: None,
 				};
 				overseer_handle
 					.send_msg(CollationGenerationMessage::Initialize(config), "Collator")
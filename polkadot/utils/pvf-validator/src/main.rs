@@ -0,0 +1,455 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prepare and execute PVFs locally, to check whether candidates would be valid and how long
+//! validators take to validate them.
+//!
+//! The candidates go through the PVF validation host, with the same prepare and execute workers,
+//! timeouts and security sandboxing as on validators. The workers must be of the same version as
+//! this tool.
+//!
+//! `validate` runs a single candidate, while `replay` runs the candidates dumped by validators and
+//! collators started with `--pov-dump-path`.
+//!
+//! The preparation and the execution are timed separately, on the wall clock, so the times include
+//! the queueing and the spawning of the workers. Their memory usage is the one reported by the
+//! workers to the metrics of the host.
+
+use anyhow::{anyhow, Context};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use codec::Decode;
+use polkadot_node_core_pvf::{
+	get_worker_version, start, Config, Metrics, PrepareError, PrepareJobKind, Priority,
+	PvfPrepData, ValidationError, ValidationHost, EXECUTE_BINARY_NAME, PREPARE_BINARY_NAME,
+};
+use polkadot_node_metrics::metrics::Metrics as _;
+use polkadot_node_primitives::{
+	pov_dump::{self, PovDump, POV_DUMP_EXTENSION},
+	PoV, NODE_VERSION,
+};
+use polkadot_node_subsystem::messages::PvfExecKind;
+use polkadot_parachain_primitives::primitives::ValidationResult;
+use polkadot_primitives::{
+	executor_params::{
+		DEFAULT_APPROVAL_EXECUTION_TIMEOUT, DEFAULT_BACKING_EXECUTION_TIMEOUT,
+		DEFAULT_LENIENT_PREPARATION_TIMEOUT,
+	},
+	ExecutorParams, Hash, PersistedValidationData, PvfExecKind as RuntimePvfExecKind, PvfPrepKind,
+};
+use prometheus_endpoint::Registry;
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use tempfile::TempDir;
+
+// This is determined by the chain, see the `validation_code_bomb_limit` runtime API.
+const DEFAULT_VALIDATION_CODE_BOMB_LIMIT: u32 = 30 * 1024 * 1024;
+
+/// The kind of execution, which determines its timeout.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ExecKind {
+	/// Execution for backing, with the shortest timeout.
+	Backing,
+	/// Execution for approval and disputes.
+	Approval,
+}
+
+impl From<ExecKind> for RuntimePvfExecKind {
+	fn from(exec_kind: ExecKind) -> Self {
+		match exec_kind {
+			ExecKind::Backing => RuntimePvfExecKind::Backing,
+			ExecKind::Approval => RuntimePvfExecKind::Approval,
+		}
+	}
+}
+
+/// Prepare and execute PVFs locally, through the same pipeline as the validators.
+#[derive(Parser)]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+
+	/// The directory of the `polkadot-prepare-worker` and `polkadot-execute-worker` binaries.
+	///
+	/// Defaults to the directory of this binary.
+	#[arg(long, global = true)]
+	workers_path: Option<PathBuf>,
+
+	/// Run the workers even if some of the security features of Secure Validator Mode are
+	/// missing. They are still enabled on a best-effort basis.
+	#[arg(long = "insecure-validator-i-know-what-i-do", global = true)]
+	insecure_validator: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Prepare and execute a candidate, and report the time and the memory they take.
+	Validate(ValidateCmd),
+	/// Prepare and execute again the candidates dumped with `--pov-dump-path`.
+	///
+	/// Only the PVF is executed: the candidates rejected by validators for other reasons, like a
+	/// mismatch of their commitments, are valid when replayed.
+	Replay(ReplayCmd),
+}
+
+#[derive(Args)]
+struct ValidateCmd {
+	/// The path to the validation code, compressed or not.
+	#[arg(long)]
+	validation_code: PathBuf,
+
+	/// The path to the SCALE encoded `PoV`.
+	///
+	/// The `PoV`s exported by `polkadot-parachain --export-pov-to-path` are followed by their
+	/// `PersistedValidationData`, which is used if `--pvd` isn't given.
+	#[arg(long)]
+	pov: PathBuf,
+
+	/// The path to the SCALE encoded `PersistedValidationData`.
+	#[arg(long)]
+	pvd: Option<PathBuf>,
+
+	/// The path to the SCALE encoded `ExecutorParams` of the session, as returned by the
+	/// `ParachainHost_session_executor_params` runtime API.
+	///
+	/// The default parameters are used if unspecified.
+	#[arg(long)]
+	executor_params: Option<PathBuf>,
+
+	/// The kind of execution whose timeout is applied.
+	#[arg(long, value_enum, default_value_t = ExecKind::Backing)]
+	exec_kind: ExecKind,
+
+	/// The maximum size of the decompressed validation code.
+	#[arg(long, default_value_t = DEFAULT_VALIDATION_CODE_BOMB_LIMIT)]
+	validation_code_bomb_limit: u32,
+}
+
+#[derive(Args)]
+struct ReplayCmd {
+	/// The dumps to replay, or the directories they were dumped to.
+	///
+	/// The validation code of a dump is read from the directory of the dump.
+	#[arg(required = true)]
+	paths: Vec<PathBuf>,
+}
+
+/// The validation host, along with the registry of its metrics.
+struct Host {
+	host: ValidationHost,
+	registry: Registry,
+	// The artifacts are prepared again on each run, the cache only lives as long as the tool.
+	_cache_dir: TempDir,
+}
+
+impl Host {
+	async fn start(
+		workers_path: Option<PathBuf>,
+		insecure_validator: bool,
+	) -> anyhow::Result<Self> {
+		let (prepare_worker_path, execute_worker_path) = workers_paths(workers_path)?;
+
+		let cache_dir = tempfile::tempdir().context("Failed to create the artifacts cache")?;
+		let registry = Registry::new();
+		let metrics = Metrics::register(Some(&registry))?;
+		let config = Config::new(
+			cache_dir.path().to_owned(),
+			Some(NODE_VERSION.to_owned()),
+			!insecure_validator,
+			prepare_worker_path,
+			execute_worker_path,
+			1,
+			1,
+			1,
+		);
+		let (host, task) = start(config, metrics)
+			.await
+			.map_err(|e| anyhow!("Failed to start the validation host: {e}"))?;
+		tokio::spawn(task);
+
+		Ok(Self { host, registry, _cache_dir: cache_dir })
+	}
+
+	/// Prepare `pvf`, unless its artifact is already prepared.
+	async fn prepare(&mut self, pvf: PvfPrepData) -> anyhow::Result<Result<(), PrepareError>> {
+		let (result_tx, result_rx) = futures::channel::oneshot::channel();
+		self.host
+			.precheck_pvf(pvf, result_tx)
+			.await
+			.map_err(|e| anyhow!("Failed to send the PVF to the validation host: {e}"))?;
+		result_rx.await.context("The validation host shut down")
+	}
+
+	/// Execute a candidate of `pvf`, which is prepared first if needed.
+	async fn execute(
+		&mut self,
+		pvf: PvfPrepData,
+		exec_timeout: Duration,
+		pvd: PersistedValidationData,
+		pov: Arc<PoV>,
+		exec_kind: PvfExecKind,
+	) -> anyhow::Result<Result<ValidationResult, ValidationError>> {
+		let (result_tx, result_rx) = futures::channel::oneshot::channel();
+		self.host
+			.execute_pvf(
+				pvf,
+				exec_timeout,
+				Arc::new(pvd),
+				pov,
+				Priority::Normal,
+				exec_kind,
+				result_tx,
+			)
+			.await
+			.map_err(|e| anyhow!("Failed to send the candidate to the validation host: {e}"))?;
+		result_rx.await.context("The validation host shut down")
+	}
+
+	/// The value observed by the validation host histogram `name`, if it observed a single one.
+	///
+	/// Only used for the memory reported by the workers, which isn't returned along with the
+	/// results.
+	fn observed(&self, name: &str) -> Option<f64> {
+		let family = self.registry.gather().into_iter().find(|family| family.get_name() == name)?;
+		let histogram = family.get_metric().first()?.get_histogram();
+		(histogram.get_sample_count() == 1).then(|| histogram.get_sample_sum())
+	}
+}
+
+/// Read and decode the file at `path`.
+fn decode_file<T: Decode>(path: &PathBuf, what: &str) -> anyhow::Result<T> {
+	let file = fs::read(path).with_context(|| format!("Failed to read {what}"))?;
+	T::decode(&mut &file[..]).with_context(|| format!("Failed to decode {what}"))
+}
+
+/// Find the workers in `workers_path`, or next to the current executable, and check that their
+/// version is the one of this tool.
+fn workers_paths(workers_path: Option<PathBuf>) -> anyhow::Result<(PathBuf, PathBuf)> {
+	let workers_path = match workers_path {
+		Some(workers_path) => workers_path,
+		None => {
+			let mut exe_path = std::env::current_exe().context("Failed to get the executable")?;
+			exe_path.pop();
+			exe_path
+		},
+	};
+
+	let prepare_worker_path = workers_path.join(PREPARE_BINARY_NAME);
+	let execute_worker_path = workers_path.join(EXECUTE_BINARY_NAME);
+	for worker_path in [&prepare_worker_path, &execute_worker_path] {
+		let version = get_worker_version(worker_path)
+			.with_context(|| format!("Failed to run the worker {}", worker_path.display()))?;
+		if version != NODE_VERSION {
+			return Err(anyhow!(
+				"Version {version} of the worker {} doesn't match the version {NODE_VERSION} \
+					of this tool",
+				worker_path.display(),
+			))
+		}
+	}
+
+	Ok((prepare_worker_path, execute_worker_path))
+}
+
+/// The preparation and execution timeouts, as applied by validators.
+fn timeouts(
+	executor_params: &ExecutorParams,
+	exec_kind: RuntimePvfExecKind,
+) -> (Duration, Duration) {
+	let prep_timeout = executor_params
+		.pvf_prep_timeout(PvfPrepKind::Prepare)
+		.unwrap_or(DEFAULT_LENIENT_PREPARATION_TIMEOUT);
+	let exec_timeout = executor_params.pvf_exec_timeout(exec_kind).unwrap_or(match exec_kind {
+		RuntimePvfExecKind::Backing => DEFAULT_BACKING_EXECUTION_TIMEOUT,
+		RuntimePvfExecKind::Approval => DEFAULT_APPROVAL_EXECUTION_TIMEOUT,
+	});
+	(prep_timeout, exec_timeout)
+}
+
+/// The kind of execution job of a candidate of `relay_parent`.
+fn exec_job_kind(exec_kind: RuntimePvfExecKind, relay_parent: Hash) -> PvfExecKind {
+	match exec_kind {
+		RuntimePvfExecKind::Backing => PvfExecKind::Backing(relay_parent),
+		RuntimePvfExecKind::Approval => PvfExecKind::Approval,
+	}
+}
+
+async fn validate(cmd: ValidateCmd, host: &mut Host) -> anyhow::Result<()> {
+	let validation_code =
+		fs::read(&cmd.validation_code).context("Failed to read the validation code")?;
+
+	let pov_file = fs::read(&cmd.pov).context("Failed to read the PoV")?;
+	let mut pov_input = &pov_file[..];
+	let pov = PoV::decode(&mut pov_input).context("Failed to decode the PoV")?;
+	let pvd = match &cmd.pvd {
+		Some(path) => decode_file(path, "the persisted validation data")?,
+		None => PersistedValidationData::decode(&mut pov_input)
+			.context("Failed to decode the persisted validation data following the PoV")?,
+	};
+
+	let executor_params: ExecutorParams = match &cmd.executor_params {
+		Some(path) => decode_file(path, "the executor params")?,
+		None => ExecutorParams::default(),
+	};
+
+	let (prep_timeout, exec_timeout) = timeouts(&executor_params, cmd.exec_kind.into());
+	let pvf = PvfPrepData::from_code(
+		validation_code,
+		executor_params,
+		prep_timeout,
+		PrepareJobKind::Compilation,
+		cmd.validation_code_bomb_limit,
+	);
+
+	let started = Instant::now();
+	let prepared = host.prepare(pvf.clone()).await?;
+	let prep_time = started.elapsed();
+
+	println!("Preparation timeout:   {} ms", prep_timeout.as_millis());
+	println!("Preparation time:      {} ms", prep_time.as_millis());
+	if let Some(max_rss) = host.observed("polkadot_pvf_preparation_max_rss") {
+		println!("Preparation max RSS:   {max_rss} KiB");
+	}
+	if let Some(peak) = host.observed("polkadot_pvf_preparation_peak_tracked_allocation") {
+		println!("Preparation peak heap: {peak} KiB");
+	}
+	if let Err(error) = prepared {
+		return Err(anyhow!("Preparation failed: {error:?}"))
+	}
+
+	let started = Instant::now();
+	let exec_kind = exec_job_kind(cmd.exec_kind.into(), Hash::zero());
+	let result = host.execute(pvf, exec_timeout, pvd, Arc::new(pov), exec_kind).await?;
+	let exec_time = started.elapsed();
+
+	println!("Execution timeout:     {} ms", exec_timeout.as_millis());
+	println!("Execution time:        {} ms", exec_time.as_millis());
+	if let Some(max_rss) = host.observed("polkadot_pvf_execution_max_rss") {
+		println!("Execution max RSS:     {max_rss} KiB");
+	}
+
+	match result {
+		Ok(validation_result) => {
+			println!("Candidate is valid: {validation_result:?}");
+			Ok(())
+		},
+		Err(error) => Err(anyhow!("Candidate is invalid: {error:?}")),
+	}
+}
+
+/// The dumps at `paths`, with the dumps of the directories sorted by name.
+fn dump_paths(paths: Vec<PathBuf>) -> anyhow::Result<Vec<PathBuf>> {
+	let mut dumps = Vec::new();
+	for path in paths {
+		if !path.is_dir() {
+			dumps.push(path);
+			continue
+		}
+
+		let mut dir_dumps = Vec::new();
+		for entry in fs::read_dir(&path)
+			.with_context(|| format!("Failed to read the directory {}", path.display()))?
+		{
+			let entry_path = entry?.path();
+			if entry_path.extension().is_some_and(|extension| extension == POV_DUMP_EXTENSION) {
+				dir_dumps.push(entry_path);
+			}
+		}
+		dir_dumps.sort();
+		dumps.extend(dir_dumps);
+	}
+	Ok(dumps)
+}
+
+async fn replay(cmd: ReplayCmd, host: &mut Host) -> anyhow::Result<()> {
+	let dumps = dump_paths(cmd.paths)?;
+	let mut differing = 0;
+
+	for path in &dumps {
+		let dump = PovDump::read(path)
+			.with_context(|| format!("Failed to read the dump {}", path.display()))?;
+		let dir = path.parent().unwrap_or(Path::new("."));
+		let validation_code = pov_dump::read_validation_code(dir, &dump.validation_code_hash)
+			.with_context(|| {
+				format!("Failed to read the validation code {:?}", dump.validation_code_hash)
+			})?;
+
+		let (prep_timeout, exec_timeout) = timeouts(&dump.executor_params, dump.exec_kind);
+		let pvf = PvfPrepData::from_code(
+			validation_code.0,
+			dump.executor_params,
+			prep_timeout,
+			PrepareJobKind::Compilation,
+			dump.validation_code_bomb_limit,
+		);
+
+		// The preparation errors are reported by the execution, as they are on validators.
+		let started = Instant::now();
+		let _ = host.prepare(pvf.clone()).await?;
+		let prep_time = started.elapsed();
+
+		let started = Instant::now();
+		let exec_kind = exec_job_kind(dump.exec_kind, dump.relay_parent);
+		let result = host
+			.execute(pvf, exec_timeout, dump.persisted_validation_data, dump.pov, exec_kind)
+			.await?;
+		let exec_time = started.elapsed();
+
+		println!(
+			"{}: candidate {:?} of para {} at relay parent {:?}",
+			path.display(),
+			dump.candidate_hash,
+			dump.para_id,
+			dump.relay_parent,
+		);
+		println!("  Dumped outcome: {}", dump.error.as_deref().unwrap_or("valid"));
+		match &result {
+			Ok(_) => println!("  Replay outcome: valid"),
+			Err(error) => println!("  Replay outcome: {error:?}"),
+		}
+		println!("  Preparation:    {} ms", prep_time.as_millis());
+		println!("  Execution:      {} ms", exec_time.as_millis());
+
+		if dump.error.is_some() != result.is_err() {
+			differing += 1;
+		}
+	}
+
+	println!("Replayed {} candidates, {differing} with a different outcome", dumps.len());
+	Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+	let _ = tracing_subscriber::fmt()
+		.with_env_filter(
+			tracing_subscriber::EnvFilter::from_default_env()
+				.add_directive(tracing_subscriber::filter::LevelFilter::INFO.into()),
+		)
+		.with_writer(std::io::stderr)
+		.try_init();
+
+	let cli = Cli::parse();
+	let mut host = Host::start(cli.workers_path, cli.insecure_validator).await?;
+
+	match cli.command {
+		Command::Validate(cmd) => validate(cmd, &mut host).await,
+		Command::Replay(cmd) => replay(cmd, &mut host).await,
+	}
+}
@@ -100,6 +100,12 @@ pub enum Subcommand {
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Export a snapshot of the state of a finalized block.
+	ExportSnapshot(sc_cli::ExportSnapshotCmd),
+
+	/// Seed an empty database with a snapshot.
+	ImportSnapshot(sc_cli::ImportSnapshotCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
@@ -108,4 +114,13 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Convert an archive database to a pruned one.
+	PruneState(sc_cli::PruneStateCmd),
+
+	/// Export the record of the votes signed by the node.
+	ExportSlashingProtection(sc_cli::ExportSlashingProtectionCmd),
+
+	/// Import a record of signed votes exported from another node.
+	ImportSlashingProtection(sc_cli::ImportSlashingProtectionCmd),
 }
@@ -296,7 +296,7 @@ pub fn new_partial(
 		&config.data_path,
 		Default::default(),
 		client.clone(),
-		keystore_container.local_keystore(),
+		keystore_container.local_keystore()?,
 		config.prometheus_registry(),
 		&task_manager.spawn_handle(),
 	)
@@ -414,6 +414,7 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 ) -> Result<NewFullBase, ServiceError> {
 	let is_offchain_indexing_enabled = config.offchain_worker.indexing_enabled;
 	let role = config.role;
+	let data_path = config.data_path.clone();
 	let force_authoring = config.force_authoring;
 	let backoff_authoring_blocks =
 		Some(sc_consensus_slots::BackoffAuthoringOnFinalizedHeadLagging::default());
@@ -678,6 +679,14 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 	// if the node isn't actively participating in consensus then it doesn't
 	// need a keystore, regardless of which protocol we use below.
 	let keystore = if role.is_authority() { Some(keystore_container.keystore()) } else { None };
+	let slashing_protection = if role.is_authority() {
+		let path = data_path.join(sc_consensus_slashing_protection::DEFAULT_FILE_NAME);
+		let slashing_protection = sc_consensus_slashing_protection::SlashingProtection::open(path)
+			.map_err(|e| ServiceError::Other(format!("Failed to open slashing protection: {e}")))?;
+		Some(Arc::new(slashing_protection))
+	} else {
+		None
+	};
 
 	// beefy is enabled if its notification service exists
 	let network_params = beefy::BeefyNetworkParams {
@@ -694,6 +703,7 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 		payload_provider: sp_consensus_beefy::mmr::MmrRootProvider::new(client.clone()),
 		runtime: client.clone(),
 		key_store: keystore.clone(),
+		slashing_protection: slashing_protection.clone(),
 		network_params,
 		min_block_delta: 8,
 		prometheus_registry: prometheus_registry.clone(),
@@ -728,6 +738,7 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 		name: Some(name),
 		observer_enabled: false,
 		keystore,
+		slashing_protection,
 		local_role: role,
 		telemetry: telemetry.as_ref().map(|x| x.handle()),
 		protocol_name: grandpa_protocol_name,
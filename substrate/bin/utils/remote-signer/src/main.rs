@@ -0,0 +1,142 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Reference remote signer.
+//!
+//! Serves the keys of a local keystore to the nodes started with `--remote-signer`, using the
+//! protocol described in [`sc_keystore::remote::protocol`]. The signed GRANDPA votes and BABE
+//! slot claims are recorded to a protection file, and conflicting votes and claims are refused.
+
+use clap::Parser;
+use sc_keystore::{remote::Signer, LocalKeystore};
+use sp_core::crypto::{ExposeSecret, SecretString};
+use std::{
+	fs,
+	net::TcpListener,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+/// Default name of the protection file, in the keystore directory.
+const DEFAULT_PROTECTION_FILE: &str = "signed-votes.json";
+
+#[derive(Debug, Parser)]
+#[command(
+	name = "remote-signer",
+	about = "Serve the keys of a keystore to nodes started with `--remote-signer`."
+)]
+struct Cli {
+	/// Path of the keystore holding the keys.
+	#[arg(long, value_name = "PATH")]
+	keystore_path: PathBuf,
+
+	/// File that contains the password used by the keystore.
+	#[arg(long, value_name = "PATH")]
+	password_filename: Option<PathBuf>,
+
+	/// File that contains the password the keystore is encrypted with.
+	#[arg(long, value_name = "PATH")]
+	keystore_encryption_password_filename: Option<PathBuf>,
+
+	/// Serve the requests on the Unix socket at this path.
+	#[arg(long, value_name = "PATH", required_unless_present = "http", conflicts_with = "http")]
+	unix: Option<PathBuf>,
+
+	/// Serve the requests over HTTP on this address, e.g. `127.0.0.1:9955`.
+	///
+	/// Addresses other than loopback ones require `--http-token-filename`. The requests are not
+	/// encrypted, so a signer reachable from other hosts must be put behind a TLS terminating
+	/// proxy.
+	#[arg(long, value_name = "ADDRESS")]
+	http: Option<String>,
+
+	/// File that contains the token the HTTP requests must carry.
+	#[arg(long, value_name = "PATH", requires = "http")]
+	http_token_filename: Option<PathBuf>,
+
+	/// File the signed GRANDPA votes are recorded to.
+	///
+	/// Defaults to `signed-votes.json` in the keystore directory.
+	#[arg(long, value_name = "PATH")]
+	protection_file: Option<PathBuf>,
+}
+
+fn read_secret(path: &Path) -> Result<SecretString, String> {
+	fs::read_to_string(path)
+		.map(SecretString::new)
+		.map_err(|e| format!("Failed to read {}: {e}", path.display()))
+}
+
+fn main() -> Result<(), String> {
+	let cli = Cli::parse();
+
+	let password = cli.password_filename.as_deref().map(read_secret).transpose()?;
+	let keystore = match cli.keystore_encryption_password_filename.as_deref() {
+		Some(path) =>
+			LocalKeystore::open_encrypted(cli.keystore_path.clone(), password, &read_secret(path)?),
+		None => LocalKeystore::open(cli.keystore_path.clone(), password),
+	}
+	.map_err(|e| format!("Failed to open the keystore: {e}"))?;
+
+	let protection_file = cli
+		.protection_file
+		.unwrap_or_else(|| cli.keystore_path.join(DEFAULT_PROTECTION_FILE));
+	let signer = Signer::new(Arc::new(keystore), Some(protection_file))
+		.map_err(|e| format!("Failed to load the protection file: {e}"))?;
+	let signer = Arc::new(signer);
+
+	match (cli.unix, cli.http) {
+		(Some(path), _) => serve_unix(signer, &path),
+		(None, Some(address)) => {
+			let auth_token = cli
+				.http_token_filename
+				.as_deref()
+				.map(|path| {
+					read_secret(path).map(|t| SecretString::new(t.expose_secret().trim().into()))
+				})
+				.transpose()?;
+			let listener = TcpListener::bind(&address).map_err(|e| e.to_string())?;
+			eprintln!("Serving the keystore at http://{address}");
+			signer.serve_http(listener, auth_token).map_err(|e| e.to_string())
+		},
+		(None, None) => unreachable!("clap requires one of `--unix` and `--http`; qed"),
+	}
+}
+
+#[cfg(unix)]
+fn serve_unix(signer: Arc<Signer>, path: &Path) -> Result<(), String> {
+	use std::os::unix::{
+		fs::{FileTypeExt, PermissionsExt},
+		net::UnixListener,
+	};
+
+	// remove the socket left by a previous run
+	if fs::metadata(path).map(|m| m.file_type().is_socket()).unwrap_or(false) {
+		fs::remove_file(path).map_err(|e| e.to_string())?;
+	}
+
+	let listener = UnixListener::bind(path).map_err(|e| e.to_string())?;
+	fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+	eprintln!("Serving the keystore at unix://{}", path.display());
+	signer.serve_unix(listener).map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_: Arc<Signer>, _: &Path) -> Result<(), String> {
+	Err("Unix sockets are not supported on this platform".into())
+}
@@ -18,7 +18,7 @@
 
 //! A method call executor interface.
 
-use sc_executor::{RuntimeVersion, RuntimeVersionOf};
+use sc_executor::{error::Error as ExecutorError, RuntimeVersion, RuntimeVersionOf};
 use sp_core::traits::CallContext;
 use sp_externalities::Extensions;
 use sp_runtime::traits::{Block as BlockT, HashingFor};
@@ -28,6 +28,20 @@ use std::cell::RefCell;
 use crate::execution_extensions::ExecutionExtensions;
 use sp_api::ProofRecorder;
 
+/// Returns `true` if `error` is a runtime call aborted for exceeding its execution budget.
+///
+/// Only offchain calls are given a budget, when the [`WasmExecutor`](sc_executor::WasmExecutor) is
+/// built with `with_offchain_execution_budget`.
+pub fn is_execution_budget_exceeded(error: &sp_blockchain::Error) -> bool {
+	match error {
+		sp_blockchain::Error::Execution(error) => matches!(
+			error.as_any().downcast_ref::<ExecutorError>(),
+			Some(ExecutorError::ExecutionBudgetExceeded)
+		),
+		_ => false,
+	}
+}
+
 /// Executor Provider
 pub trait ExecutorProvider<Block: BlockT> {
 	/// executor instance
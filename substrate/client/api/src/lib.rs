@@ -25,12 +25,14 @@ pub mod client;
 pub mod execution_extensions;
 pub mod in_mem;
 pub mod leaves;
+pub mod light;
 pub mod notifications;
 pub mod proof_provider;
 
 pub use backend::*;
 pub use call_executor::*;
 pub use client::*;
+pub use light::*;
 pub use notifications::*;
 pub use proof_provider::*;
 pub use sp_blockchain as blockchain;
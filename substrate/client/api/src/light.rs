@@ -0,0 +1,60 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Light client interfaces.
+//!
+//! A light client only keeps the headers of the chain. The storage and the runtime are accessed
+//! by fetching proofs from full nodes, which are checked against the state root of the
+//! requested block.
+
+use futures::future::BoxFuture;
+use sp_runtime::traits::Block as BlockT;
+use sp_storage::PrefixedStorageKey;
+use std::collections::HashMap;
+
+/// Values read from remote storage, keyed by storage key.
+pub type RemoteReadResult = sp_blockchain::Result<HashMap<Vec<u8>, Option<Vec<u8>>>>;
+
+/// Fetches proven data from remote full nodes.
+///
+/// All the results are checked against the header of the requested block, which must be known
+/// locally.
+pub trait RemoteFetcher<Block: BlockT>: Send + Sync {
+	/// Read the values of `keys` in the storage at `block`.
+	fn remote_read(
+		&self,
+		block: Block::Hash,
+		keys: Vec<Vec<u8>>,
+	) -> BoxFuture<'static, RemoteReadResult>;
+
+	/// Read the values of `keys` in the child storage `storage_key` at `block`.
+	fn remote_read_child(
+		&self,
+		block: Block::Hash,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<Vec<u8>>,
+	) -> BoxFuture<'static, RemoteReadResult>;
+
+	/// Call `method` of the runtime at `block` and return the encoded result.
+	fn remote_call(
+		&self,
+		block: Block::Hash,
+		method: String,
+		call_data: Vec<u8>,
+	) -> BoxFuture<'static, sp_blockchain::Result<Vec<u8>>>;
+}
@@ -0,0 +1,72 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `encrypt-keystore` subcommand
+
+use crate::{Error, KeystoreParams, SharedParams, SubstrateCli};
+use clap::Parser;
+use sc_keystore::LocalKeystore;
+use sc_service::config::{BasePath, KeystoreConfig};
+
+/// The `encrypt-keystore` command
+#[derive(Debug, Clone, Parser)]
+#[command(
+	name = "encrypt-keystore",
+	about = "Encrypt the key files of the keystore of a node in place."
+)]
+pub struct EncryptKeystoreCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl EncryptKeystoreCmd {
+	/// Run the command
+	pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+		let base_path = self
+			.shared_params
+			.base_path()?
+			.unwrap_or_else(|| BasePath::from_project("", "", &C::executable_name()));
+		let chain_id = self.shared_params.chain_id(self.shared_params.is_dev());
+		let chain_spec = cli.load_spec(&chain_id)?;
+		let config_dir = base_path.config_dir(chain_spec.id());
+
+		let (path, encryption_password) = match self.keystore_params.keystore_config(&config_dir)? {
+			KeystoreConfig::Path { path, encryption_password, .. } => (path, encryption_password),
+			KeystoreConfig::Remote { .. } =>
+				return Err(Error::Input("A remote keystore can not be encrypted".into())),
+			KeystoreConfig::InMemory =>
+				unreachable!("keystore_config never returns an in-memory keystore; qed"),
+		};
+		let encryption_password = encryption_password.ok_or_else(|| {
+			Error::Input(
+				"An encryption password is required, see `--keystore-encryption-password-filename`"
+					.into(),
+			)
+		})?;
+
+		let encrypted = LocalKeystore::encrypt(&path, &encryption_password)?;
+		println!("Encrypted {} key files of the keystore at {}", encrypted, path.display());
+
+		Ok(())
+	}
+}
@@ -22,7 +22,7 @@ use crate::{
 	utils, with_crypto_scheme, CryptoScheme, Error, KeystoreParams, SharedParams, SubstrateCli,
 };
 use clap::Parser;
-use sc_keystore::LocalKeystore;
+use sc_keystore::{LocalKeystore, RemoteKeystore};
 use sc_service::config::{BasePath, KeystoreConfig};
 use sp_core::crypto::{KeyTypeId, SecretString};
 use sp_keystore::KeystorePtr;
@@ -67,12 +67,22 @@ impl InsertKeyCmd {
 		let config_dir = base_path.config_dir(chain_spec.id());
 
 		let (keystore, public) = match self.keystore_params.keystore_config(&config_dir)? {
-			KeystoreConfig::Path { path, password } => {
+			KeystoreConfig::Path { path, password, encryption_password } => {
 				let public = with_crypto_scheme!(self.scheme, to_vec(&suri, password.clone()))?;
-				let keystore: KeystorePtr = LocalKeystore::open(path, password)?.into();
+				let keystore: KeystorePtr = match encryption_password {
+					Some(encryption_password) =>
+						LocalKeystore::open_encrypted(path, password, &encryption_password)?.into(),
+					None => LocalKeystore::open(path, password)?.into(),
+				};
 				(keystore, public)
 			},
-			_ => unreachable!("keystore_config always returns path and password; qed"),
+			KeystoreConfig::Remote { endpoint, auth_token } => {
+				let public = with_crypto_scheme!(self.scheme, to_vec(&suri, None))?;
+				let keystore: KeystorePtr = RemoteKeystore::new(endpoint, auth_token).into();
+				(keystore, public)
+			},
+			KeystoreConfig::InMemory =>
+				unreachable!("keystore_config never returns an in-memory keystore; qed"),
 		};
 
 		let key_type =
@@ -19,8 +19,9 @@
 //! Key related CLI utilities
 
 use super::{
-	generate::GenerateCmd, generate_node_key::GenerateNodeKeyCmd, insert_key::InsertKeyCmd,
-	inspect_key::InspectKeyCmd, inspect_node_key::InspectNodeKeyCmd,
+	encrypt_keystore_cmd::EncryptKeystoreCmd, generate::GenerateCmd,
+	generate_node_key::GenerateNodeKeyCmd, insert_key::InsertKeyCmd, inspect_key::InspectKeyCmd,
+	inspect_node_key::InspectNodeKeyCmd,
 };
 use crate::{Error, SubstrateCli};
 
@@ -42,6 +43,9 @@ pub enum KeySubcommand {
 
 	/// Insert a key to the keystore of a node.
 	Insert(InsertKeyCmd),
+
+	/// Encrypt the key files of the keystore of a node in place.
+	EncryptKeystore(EncryptKeystoreCmd),
 }
 
 impl KeySubcommand {
@@ -56,6 +60,7 @@ impl KeySubcommand {
 			KeySubcommand::Inspect(cmd) => cmd.run(),
 			KeySubcommand::Insert(cmd) => cmd.run(cli),
 			KeySubcommand::InspectNodeKey(cmd) => cmd.run(),
+			KeySubcommand::EncryptKeystore(cmd) => cmd.run(cli),
 		}
 	}
 }
@@ -21,6 +21,7 @@
 mod build_spec_cmd;
 mod chain_info_cmd;
 mod check_block_cmd;
+mod encrypt_keystore_cmd;
 mod export_blocks_cmd;
 mod export_chain_spec_cmd;
 mod export_state_cmd;
@@ -31,21 +32,40 @@ mod insert_key;
 mod inspect_key;
 mod inspect_node_key;
 mod key;
+mod prune_state_cmd;
 mod purge_chain_cmd;
 mod revert_cmd;
 mod run_cmd;
 mod sign;
+mod slashing_protection_cmd;
+mod snapshot_cmd;
 mod test;
 pub mod utils;
 mod vanity;
 mod verify;
 
 pub use self::{
-	build_spec_cmd::BuildSpecCmd, chain_info_cmd::ChainInfoCmd, check_block_cmd::CheckBlockCmd,
-	export_blocks_cmd::ExportBlocksCmd, export_chain_spec_cmd::ExportChainSpecCmd,
-	export_state_cmd::ExportStateCmd, generate::GenerateCmd,
-	generate_node_key::GenerateKeyCmdCommon, import_blocks_cmd::ImportBlocksCmd,
-	insert_key::InsertKeyCmd, inspect_key::InspectKeyCmd, inspect_node_key::InspectNodeKeyCmd,
-	key::KeySubcommand, purge_chain_cmd::PurgeChainCmd, revert_cmd::RevertCmd, run_cmd::RunCmd,
-	sign::SignCmd, vanity::VanityCmd, verify::VerifyCmd,
+	build_spec_cmd::BuildSpecCmd,
+	chain_info_cmd::ChainInfoCmd,
+	check_block_cmd::CheckBlockCmd,
+	encrypt_keystore_cmd::EncryptKeystoreCmd,
+	export_blocks_cmd::ExportBlocksCmd,
+	export_chain_spec_cmd::ExportChainSpecCmd,
+	export_state_cmd::ExportStateCmd,
+	generate::GenerateCmd,
+	generate_node_key::GenerateKeyCmdCommon,
+	import_blocks_cmd::ImportBlocksCmd,
+	insert_key::InsertKeyCmd,
+	inspect_key::InspectKeyCmd,
+	inspect_node_key::InspectNodeKeyCmd,
+	key::KeySubcommand,
+	prune_state_cmd::PruneStateCmd,
+	purge_chain_cmd::PurgeChainCmd,
+	revert_cmd::RevertCmd,
+	run_cmd::RunCmd,
+	sign::SignCmd,
+	slashing_protection_cmd::{ExportSlashingProtectionCmd, ImportSlashingProtectionCmd},
+	snapshot_cmd::{ExportSnapshotCmd, ImportSnapshotCmd},
+	vanity::VanityCmd,
+	verify::VerifyCmd,
 };
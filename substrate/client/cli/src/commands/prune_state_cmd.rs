@@ -0,0 +1,77 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	CliConfiguration, DatabaseParams, Error, PruningParams, Result as CliResult, SharedParams,
+};
+use sp_runtime::traits::Block as BlockT;
+use std::fmt::Debug;
+
+/// The `prune-state` subcommand used to convert an archive database into a pruned one.
+///
+/// All state that is not reachable from the states retained by the target `--state-pruning`
+/// mode is removed from the database. The node must not be running while the command is
+/// executed.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct PruneStateCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl PruneStateCmd {
+	/// Run the `prune-state` subcommand
+	pub fn run<B>(&self, config: &sc_service::Configuration) -> CliResult<()>
+	where
+		B: BlockT,
+	{
+		let mode = match &config.state_pruning {
+			Some(mode) if !mode.is_archive() => mode.clone(),
+			_ =>
+				return Err(Error::Input(
+					"A non-archive `--state-pruning` mode to convert the database to is required"
+						.into(),
+				)),
+		};
+
+		sc_client_db::prune_archive_state::<B>(&config.database, mode)?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for PruneStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
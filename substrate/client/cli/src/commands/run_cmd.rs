@@ -342,6 +342,14 @@ impl CliConfiguration for RunCmd {
 		Ok(self.runtime_params.runtime_cache_size)
 	}
 
+	fn offchain_execution_budget(&self) -> Result<Option<u64>> {
+		Ok(self.runtime_params.offchain_execution_budget)
+	}
+
+	fn runtime_artifact_cache(&self) -> Result<bool> {
+		Ok(self.runtime_params.runtime_artifact_cache)
+	}
+
 	fn base_path(&self) -> Result<Option<BasePath>> {
 		Ok(if self.tmp {
 			Some(BasePath::new_temp_dir()?)
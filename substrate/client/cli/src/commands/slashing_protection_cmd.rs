@@ -0,0 +1,94 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `export-slashing-protection` and `import-slashing-protection`
+//! subcommands
+
+use crate::{error, CliConfiguration, SharedParams};
+use sc_consensus_slashing_protection::{Interchange, SlashingProtection, DEFAULT_FILE_NAME};
+use sc_service::config::Configuration;
+use std::{fs, io, path::PathBuf};
+
+fn open(config: &Configuration) -> error::Result<SlashingProtection> {
+	Ok(SlashingProtection::open(config.data_path.join(DEFAULT_FILE_NAME))?)
+}
+
+/// The `export-slashing-protection` command used to export the record of the GRANDPA and BEEFY
+/// votes signed by the node, to move a validator to another machine.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportSlashingProtectionCmd {
+	/// Output file name or stdout if unspecified.
+	#[arg()]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ExportSlashingProtectionCmd {
+	/// Run the `export-slashing-protection` command
+	pub fn run(&self, config: &Configuration) -> error::Result<()> {
+		let interchange = open(config)?.export(config.chain_spec.id());
+
+		let file: Box<dyn io::Write> = match &self.output {
+			Some(path) => Box::new(fs::File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+		serde_json::to_writer_pretty(file, &interchange).map_err(|e| error::Error::Io(e.into()))
+	}
+}
+
+impl CliConfiguration for ExportSlashingProtectionCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// The `import-slashing-protection` command used to merge a record exported with
+/// `export-slashing-protection` into the record of the node.
+///
+/// The node must not be running while the command is executed.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ImportSlashingProtectionCmd {
+	/// Input file.
+	#[arg()]
+	pub input: PathBuf,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ImportSlashingProtectionCmd {
+	/// Run the `import-slashing-protection` command
+	pub fn run(&self, config: &Configuration) -> error::Result<()> {
+		let file = fs::File::open(&self.input)?;
+		let interchange: Interchange = serde_json::from_reader(io::BufReader::new(file))
+			.map_err(|e| error::Error::Input(format!("Invalid slashing protection file: {e}")))?;
+
+		open(config)?.import(interchange, config.chain_spec.id())?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ImportSlashingProtectionCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
@@ -0,0 +1,170 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `export-snapshot` and `import-snapshot` subcommands
+
+use crate::{
+	error,
+	params::{BlockNumberOrHash, DatabaseParams, ImportParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use log::info;
+use sc_client_api::{BlockBackend, HeaderBackend, StorageProvider};
+use sc_service::chain_ops::{export_snapshot, import_snapshot};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use std::{
+	fmt::Debug,
+	fs,
+	io::{self, BufReader, BufWriter, Read, Write},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
+
+/// The `export-snapshot` command used to export the state of a finalized block, with its header
+/// and justifications, to bootstrap other nodes.
+#[derive(Debug, Clone, Parser)]
+pub struct ExportSnapshotCmd {
+	/// Output file name or stdout if unspecified.
+	#[arg()]
+	pub output: Option<PathBuf>,
+
+	/// Hash or number of the block to export. Defaults to the last finalized block.
+	#[arg(long, value_name = "HASH or NUMBER")]
+	pub block: Option<BlockNumberOrHash>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ExportSnapshotCmd {
+	/// Run the `export-snapshot` command
+	pub async fn run<B, BA, C>(&self, client: Arc<C>) -> error::Result<()>
+	where
+		B: BlockT,
+		BA: sc_client_api::backend::Backend<B>,
+		C: HeaderBackend<B> + BlockBackend<B> + StorageProvider<B, BA>,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		let block_id = self.block.as_ref().map(|b| b.parse()).transpose()?;
+		let hash = match block_id {
+			Some(id) => client.expect_block_hash_from_id(&id)?,
+			None => client.info().finalized_hash,
+		};
+
+		let file: Box<dyn Write> = match &self.output {
+			Some(filename) => Box::new(fs::File::create(filename)?),
+			None => Box::new(io::stdout()),
+		};
+
+		export_snapshot(client, hash, BufWriter::new(file)).map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for ExportSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `import-snapshot` command used to seed an empty database with a snapshot written by
+/// `export-snapshot`.
+///
+/// The node must not be running while the command is executed.
+#[derive(Debug, Clone, Parser)]
+pub struct ImportSnapshotCmd {
+	/// Input file or stdin if unspecified.
+	#[arg()]
+	pub input: Option<PathBuf>,
+
+	/// Hash of the finalized block the snapshot must be of, obtained from a trusted source.
+	///
+	/// The snapshot isn't verified if unspecified.
+	#[arg(long, value_name = "HASH")]
+	pub trusted_hash: Option<String>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ImportSnapshotCmd {
+	/// Run the `import-snapshot` command
+	///
+	/// The block is imported through `block_import`, which lets the consensus engines initialize
+	/// their state, such as the authority set, from the state of the block.
+	pub async fn run<B, C, BI>(&self, client: Arc<C>, block_import: BI) -> error::Result<()>
+	where
+		B: BlockT,
+		C: HeaderBackend<B>,
+		BI: sc_service::BlockImport<B>,
+		<B::Hash as FromStr>::Err: Debug,
+	{
+		let trusted_hash = self
+			.trusted_hash
+			.as_ref()
+			.map(|hash| {
+				B::Hash::from_str(hash.strip_prefix("0x").unwrap_or(hash))
+					.map_err(|e| format!("Failed to parse trusted hash: {:?}", e))
+			})
+			.transpose()?;
+
+		let file: Box<dyn Read> = match &self.input {
+			Some(filename) => Box::new(fs::File::open(filename)?),
+			None => Box::new(io::stdin()),
+		};
+
+		let hash =
+			import_snapshot(client, block_import, BufReader::new(file), trusted_hash).await?;
+		info!("Database seeded with the snapshot of block {hash:?}");
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ImportSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
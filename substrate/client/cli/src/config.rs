@@ -476,6 +476,20 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(2)
 	}
 
+	/// Get the maximum amount of fuel an offchain runtime call can consume.
+	///
+	/// By default this is `None`, offchain calls aren't metered.
+	fn offchain_execution_budget(&self) -> Result<Option<u64>> {
+		Ok(None)
+	}
+
+	/// Whether the compiled runtimes are stored on disk, to be reused after a restart.
+	///
+	/// By default this is `false`.
+	fn runtime_artifact_cache(&self) -> Result<bool> {
+		Ok(false)
+	}
+
 	/// Activate or not the automatic announcing of blocks after import
 	///
 	/// By default this is `false`.
@@ -514,6 +528,8 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		let keystore = self.keystore_config(&config_dir)?;
 		let telemetry_endpoints = self.telemetry_endpoints(&chain_spec)?;
 		let runtime_cache_size = self.runtime_cache_size()?;
+		let artifact_cache_path =
+			self.runtime_artifact_cache()?.then(|| config_dir.join("runtime-artifacts"));
 
 		let rpc_addrs: Option<Vec<sc_service::config::RpcEndpoint>> = self
 			.rpc_addr(DCV::rpc_listen_port())?
@@ -546,6 +562,8 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 				default_heap_pages: self.default_heap_pages()?,
 				max_runtime_instances,
 				runtime_cache_size,
+				offchain_execution_budget: self.offchain_execution_budget()?,
+				artifact_cache_path,
 			},
 			wasm_runtime_overrides: self.wasm_runtime_overrides(),
 			rpc: RpcConfiguration {
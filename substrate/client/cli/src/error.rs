@@ -71,6 +71,9 @@ pub enum Error {
 	#[error("Key storage issue encountered")]
 	KeyStorage(#[from] sc_keystore::Error),
 
+	#[error(transparent)]
+	SlashingProtection(#[from] sc_consensus_slashing_protection::Error),
+
 	#[error("Invalid hexadecimal string data, {0:?}")]
 	HexDataConversion(array_bytes::Error),
 
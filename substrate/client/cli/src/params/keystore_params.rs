@@ -18,6 +18,7 @@
 
 use crate::{error, error::Result};
 use clap::Args;
+use sc_keystore::remote::Endpoint;
 use sc_service::config::KeystoreConfig;
 use sp_core::crypto::SecretString;
 use std::{
@@ -56,6 +57,37 @@ pub struct KeystoreParams {
 		conflicts_with_all = &["password_interactive", "password"]
 	)]
 	pub password_filename: Option<PathBuf>,
+
+	/// Use interactive shell for entering the password the keystore is encrypted with.
+	#[arg(long, conflicts_with = "keystore_encryption_password_filename")]
+	pub keystore_encryption_password_interactive: bool,
+
+	/// File that contains the password the keystore is encrypted with.
+	///
+	/// When given, the key files are encrypted at rest using a key derived from this password.
+	/// An existing unencrypted keystore has to be encrypted first using the
+	/// `key encrypt-keystore` command.
+	#[arg(long, value_name = "PATH", conflicts_with = "keystore_encryption_password_interactive")]
+	pub keystore_encryption_password_filename: Option<PathBuf>,
+
+	/// Forward the key operations to a remote signer instead of using a local keystore.
+	///
+	/// The signer is given as `unix:///path/to/socket` or `http://host:port`. Only sr25519,
+	/// ed25519 and ecdsa signatures are supported, and VRF signatures only for BABE slot claims.
+	#[arg(
+		long,
+		value_name = "URL",
+		conflicts_with_all = &[
+			"keystore_path",
+			"keystore_encryption_password_interactive",
+			"keystore_encryption_password_filename",
+		]
+	)]
+	pub remote_signer: Option<Endpoint>,
+
+	/// File that contains the token authenticating the requests to the HTTP remote signer.
+	#[arg(long, value_name = "PATH", requires = "remote_signer")]
+	pub remote_signer_token_filename: Option<PathBuf>,
 }
 
 /// Parse a secret string, returning a displayable error.
@@ -66,6 +98,17 @@ pub fn secret_string_from_str(s: &str) -> std::result::Result<SecretString, Stri
 impl KeystoreParams {
 	/// Get the keystore configuration for the parameters
 	pub fn keystore_config(&self, config_dir: &Path) -> Result<KeystoreConfig> {
+		if let Some(endpoint) = &self.remote_signer {
+			let auth_token = match &self.remote_signer_token_filename {
+				Some(file) => {
+					let token = fs::read_to_string(file).map_err(|e| format!("{}", e))?;
+					Some(SecretString::new(token.trim().into()))
+				},
+				None => None,
+			};
+			return Ok(KeystoreConfig::Remote { endpoint: endpoint.clone(), auth_token })
+		}
+
 		let password = if self.password_interactive {
 			Some(SecretString::new(input_keystore_password()?))
 		} else if let Some(ref file) = self.password_filename {
@@ -80,7 +123,27 @@ impl KeystoreParams {
 			.clone()
 			.unwrap_or_else(|| config_dir.join(DEFAULT_KEYSTORE_CONFIG_PATH));
 
-		Ok(KeystoreConfig::Path { path, password })
+		Ok(KeystoreConfig::Path {
+			path,
+			password,
+			encryption_password: self.encryption_password()?,
+		})
+	}
+
+	/// Get the password the keystore is encrypted with, if any.
+	pub fn encryption_password(&self) -> Result<Option<SecretString>> {
+		let password = if self.keystore_encryption_password_interactive {
+			let password = rpassword::prompt_password("Keystore encryption password: ")
+				.map_err(|e| format!("{:?}", e))?;
+			Some(SecretString::new(password))
+		} else if let Some(ref file) = self.keystore_encryption_password_filename {
+			let password = fs::read_to_string(file).map_err(|e| format!("{}", e))?;
+			Some(SecretString::new(password))
+		} else {
+			None
+		};
+
+		Ok(password)
 	}
 
 	/// helper method to fetch password from `KeyParams` or read from stdin
@@ -170,6 +170,18 @@ pub struct NetworkParams {
 	#[arg(long, value_name = "COUNT", default_value_t = 64)]
 	pub max_blocks_per_request: u32,
 
+	/// Maximum number of bytes of block data served to a single peer per second.
+	///
+	/// Requests above the limit are delayed until they fit. Unlimited by default.
+	#[arg(long, value_name = "BYTES")]
+	pub block_server_peer_rate: Option<u64>,
+
+	/// Maximum number of bytes of block data served to all the peers per second.
+	///
+	/// Requests above the limit are delayed until they fit. Unlimited by default.
+	#[arg(long, value_name = "BYTES")]
+	pub block_server_budget: Option<u64>,
+
 	/// Network backend used for P2P networking.
 	///
 	/// Litep2p is a lightweight alternative to libp2p, that is designed to be more
@@ -275,6 +287,8 @@ impl NetworkParams {
 			},
 			max_parallel_downloads: self.max_parallel_downloads,
 			max_blocks_per_request: self.max_blocks_per_request,
+			block_server_peer_rate: self.block_server_peer_rate,
+			block_server_budget: self.block_server_budget,
 			min_peers_to_start_warp_sync: None,
 			enable_dht_random_walk: !self.reserved_only,
 			allow_non_globals_in_dht,
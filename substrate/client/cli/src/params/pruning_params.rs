@@ -41,6 +41,10 @@ pub struct PruningParams {
 	///
 	/// - NUMBER: Keep the data of the last NUMBER of finalized blocks.
 	///
+	/// - sparse-archive:PERIOD[:NUMBER]: Keep the data of the last NUMBER of finalized blocks (256
+	///   by default), and the data of every finalized block whose number is a multiple of PERIOD.
+	///   Requires a database with reference counting support (ParityDb).
+	///
 	/// [default: 256]
 	#[arg(alias = "pruning", long, value_name = "PRUNING_MODE")]
 	pub state_pruning: Option<DatabasePruningMode>,
@@ -74,10 +78,18 @@ impl PruningParams {
 
 	/// Get the block pruning value from the parameters
 	pub fn blocks_pruning(&self) -> error::Result<BlocksPruning> {
+		if let DatabasePruningMode::SparseArchive { .. } = self.blocks_pruning {
+			return Err(error::Error::Input(
+				"Sparse archive is only supported for `--state-pruning`".into(),
+			))
+		}
 		Ok(self.blocks_pruning.into())
 	}
 }
 
+/// Number of last finalized blocks kept by `sparse-archive` when not specified.
+const DEFAULT_SPARSE_ARCHIVE_WINDOW: u32 = 256;
+
 /// Specifies the pruning mode of the database.
 ///
 /// This specifies when the block's data (either state via `--state-pruning`
@@ -91,6 +103,13 @@ pub enum DatabasePruningMode {
 	ArchiveCanonical,
 	/// Keep the data of the last number of finalized blocks.
 	Custom(u32),
+	/// Keep the data of the last `window` finalized blocks and of every `period`-th block.
+	SparseArchive {
+		/// Distance between two blocks whose data is kept.
+		period: u32,
+		/// Number of last finalized blocks whose data is kept.
+		window: u32,
+	},
 }
 
 impl std::str::FromStr for DatabasePruningMode {
@@ -100,6 +119,23 @@ impl std::str::FromStr for DatabasePruningMode {
 		match input {
 			"archive" => Ok(Self::Archive),
 			"archive-canonical" => Ok(Self::ArchiveCanonical),
+			sparse if sparse.starts_with("sparse-archive:") => {
+				let invalid = || "Invalid sparse archive pruning mode specified".to_string();
+				let mut params = sparse["sparse-archive:".len()..].split(':');
+				let period = params
+					.next()
+					.and_then(|period| period.parse().ok())
+					.filter(|period| *period > 0)
+					.ok_or_else(invalid)?;
+				let window = match params.next() {
+					Some(window) => window.parse().map_err(|_| invalid())?,
+					None => DEFAULT_SPARSE_ARCHIVE_WINDOW,
+				};
+				if params.next().is_some() {
+					return Err(invalid())
+				}
+				Ok(Self::SparseArchive { period, window })
+			},
 			bc => bc
 				.parse()
 				.map_err(|_| "Invalid pruning mode specified".to_string())
@@ -114,6 +150,8 @@ impl Into<PruningMode> for DatabasePruningMode {
 			DatabasePruningMode::Archive => PruningMode::ArchiveAll,
 			DatabasePruningMode::ArchiveCanonical => PruningMode::ArchiveCanonical,
 			DatabasePruningMode::Custom(n) => PruningMode::blocks_pruning(n),
+			DatabasePruningMode::SparseArchive { period, window } =>
+				PruningMode::sparse_archive(window, period),
 		}
 	}
 }
@@ -124,6 +162,8 @@ impl Into<BlocksPruning> for DatabasePruningMode {
 			DatabasePruningMode::Archive => BlocksPruning::KeepAll,
 			DatabasePruningMode::ArchiveCanonical => BlocksPruning::KeepFinalized,
 			DatabasePruningMode::Custom(n) => BlocksPruning::Some(n),
+			// Rejected by `PruningParams::blocks_pruning`.
+			DatabasePruningMode::SparseArchive { window, .. } => BlocksPruning::Some(window),
 		}
 	}
 }
@@ -161,5 +201,24 @@ mod tests {
 
 		assert!(matches!(dbg!(pruning.state_pruning), Some(DatabasePruningMode::ArchiveCanonical)));
 		assert!(matches!(pruning.blocks_pruning, DatabasePruningMode::ArchiveCanonical));
+
+		let Cli { pruning } = Cli::parse_from(["", "--state-pruning=sparse-archive:1000"]);
+
+		assert_eq!(
+			pruning.state_pruning,
+			Some(DatabasePruningMode::SparseArchive { period: 1000, window: 256 })
+		);
+
+		let Cli { pruning } = Cli::parse_from(["", "--state-pruning=sparse-archive:1000:64"]);
+
+		assert_eq!(pruning.state_pruning().unwrap(), Some(PruningMode::sparse_archive(64, 1000)));
+
+		assert!(Cli::try_parse_from(["", "--state-pruning=sparse-archive:0"]).is_err());
+		assert!(Cli::try_parse_from(["", "--state-pruning=sparse-archive:1000:64:1"]).is_err());
+		assert!(Cli::try_parse_from(["", "--blocks-pruning=sparse-archive:1000"])
+			.unwrap()
+			.pruning
+			.blocks_pruning()
+			.is_err());
 	}
 }
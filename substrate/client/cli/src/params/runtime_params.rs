@@ -31,6 +31,24 @@ pub struct RuntimeParams {
 	/// Maximum number of different runtimes that can be cached.
 	#[arg(long, default_value_t = 2)]
 	pub runtime_cache_size: u8,
+
+	/// Maximum amount of fuel an offchain runtime call can consume.
+	///
+	/// Offchain calls, such as the ones made by RPC methods, are metered and aborted once they
+	/// exceed the budget. Metering slows down their execution. Onchain calls, which import and
+	/// author blocks, are never metered.
+	///
+	/// Offchain calls aren't metered by default.
+	#[arg(long, value_name = "FUEL")]
+	pub offchain_execution_budget: Option<u64>,
+
+	/// Store the compiled runtimes in the `runtime-artifacts` directory of the base path, and load
+	/// them from there after a restart instead of compiling them again.
+	///
+	/// The artifacts are native code loaded without being validated, their hash only protects
+	/// them against corruption. The directory must be writable only by trusted users.
+	#[arg(long)]
+	pub runtime_artifact_cache: bool,
 }
 
 fn parse_max_runtime_instances(s: &str) -> Result<usize, String> {
@@ -17,7 +17,8 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use clap::{Args, ValueEnum};
-use sc_transaction_pool::TransactionPoolOptions;
+use sc_transaction_pool::{JournalOptions, SenderLimits, TransactionPoolOptions};
+use std::time::Duration;
 
 /// Type of transaction pool to be used
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -59,6 +60,36 @@ pub struct TransactionPoolParams {
 	/// The type of transaction pool to be instantiated.
 	#[arg(long, value_enum, default_value_t = TransactionPoolType::SingleState)]
 	pub pool_type: TransactionPoolType,
+
+	/// Persist the ready and future transactions of the pool across node restarts.
+	///
+	/// The transactions are periodically written to a journal in the chain's data directory.
+	/// On startup they are resubmitted to the pool, which revalidates them against the best
+	/// block.
+	#[arg(long)]
+	pub pool_journal: bool,
+
+	/// Maximum age of the journaled transactions that are resubmitted on startup.
+	#[arg(long, value_name = "SECONDS", default_value_t = 3 * 60 * 60, requires = "pool_journal")]
+	pub pool_journal_max_age: u64,
+
+	/// How often the transaction pool journal is rewritten.
+	#[arg(long, value_name = "SECONDS", default_value_t = 60, requires = "pool_journal")]
+	pub pool_journal_interval: u64,
+
+	/// Maximum number of ready transactions of a single sender kept in the pool.
+	///
+	/// When exceeded, the lowest priority ready transactions of the sender are dropped. Only
+	/// enforced by the fork-aware transaction pool.
+	#[arg(long, value_name = "COUNT")]
+	pub pool_sender_ready_limit: Option<usize>,
+
+	/// Maximum number of future transactions of a single sender kept in the pool.
+	///
+	/// When exceeded, the lowest priority future transactions of the sender are dropped. Only
+	/// enforced by the fork-aware transaction pool.
+	#[arg(long, value_name = "COUNT")]
+	pub pool_sender_future_limit: Option<usize>,
 }
 
 impl TransactionPoolParams {
@@ -71,5 +102,13 @@ impl TransactionPoolParams {
 			self.pool_type.into(),
 			is_dev,
 		)
+		.with_journal(self.pool_journal.then(|| JournalOptions {
+			max_age: Duration::from_secs(self.pool_journal_max_age),
+			interval: Duration::from_secs(self.pool_journal_interval),
+		}))
+		.with_sender_limits(SenderLimits {
+			ready: self.pool_sender_ready_limit,
+			future: self.pool_sender_future_limit,
+		})
 	}
 }
@@ -24,7 +24,8 @@ use sc_consensus_epochs::Epoch as EpochT;
 use sp_application_crypto::AppCrypto;
 use sp_consensus_babe::{
 	digests::{PreDigest, PrimaryPreDigest, SecondaryPlainPreDigest, SecondaryVRFPreDigest},
-	make_vrf_sign_data, AuthorityId, BabeAuthorityWeight, Randomness, Slot,
+	make_vrf_sign_data, with_vrf_transcript_data, AuthorityId, BabeAuthorityWeight, Randomness,
+	Slot, VrfSignature,
 };
 use sp_core::{
 	crypto::{ByteArray, Wraps},
@@ -32,6 +33,22 @@ use sp_core::{
 };
 use sp_keystore::KeystorePtr;
 
+/// VRF signs the BABE input of `slot` with the key of `authority_id`.
+///
+/// The keystore is handed the inputs of the transcript rather than the sign data, which a
+/// remote keystore can't forward.
+fn vrf_sign(
+	keystore: &KeystorePtr,
+	authority_id: &AuthorityId,
+	randomness: &Randomness,
+	slot: Slot,
+	epoch_index: u64,
+) -> Result<Option<VrfSignature>, sp_keystore::Error> {
+	with_vrf_transcript_data(randomness, slot, epoch_index, |label, items| {
+		keystore.sr25519_vrf_sign_transcript(AuthorityId::ID, authority_id.as_ref(), label, items)
+	})
+}
+
 /// Calculates the primary selection threshold for a given authority, taking
 /// into account `c` (`1 - c` represents the probability of a slot being empty).
 pub(super) fn calculate_primary_threshold(
@@ -147,9 +164,7 @@ fn claim_secondary_slot(
 	for (authority_id, authority_index) in keys {
 		if authority_id == expected_author {
 			let pre_digest = if author_secondary_vrf {
-				let data = make_vrf_sign_data(&epoch.randomness, slot, epoch_index);
-				let result =
-					keystore.sr25519_vrf_sign(AuthorityId::ID, authority_id.as_ref(), &data);
+				let result = vrf_sign(keystore, authority_id, &epoch.randomness, slot, epoch_index);
 				if let Ok(Some(vrf_signature)) = result {
 					Some(PreDigest::SecondaryVRF(SecondaryVRFPreDigest {
 						slot,
@@ -240,7 +255,7 @@ fn claim_primary_slot(
 	let data = make_vrf_sign_data(&epoch.randomness, slot, epoch_index);
 
 	for (authority_id, authority_index) in keys {
-		let result = keystore.sr25519_vrf_sign(AuthorityId::ID, authority_id.as_ref(), &data);
+		let result = vrf_sign(keystore, authority_id, &epoch.randomness, slot, epoch_index);
 		if let Ok(Some(vrf_signature)) = result {
 			let threshold = calculate_primary_threshold(c, &epoch.authorities, *authority_index);
 
@@ -37,6 +37,7 @@ use parking_lot::Mutex;
 use prometheus_endpoint::Registry;
 use sc_client_api::{Backend, BlockBackend, BlockchainEvents, FinalityNotification, Finalizer};
 use sc_consensus::BlockImport;
+use sc_consensus_slashing_protection::SlashingProtection;
 use sc_network::{NetworkRequest, NotificationService, ProtocolName};
 use sc_network_gossip::{GossipEngine, Network as GossipNetwork, Syncing as GossipSyncing};
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver};
@@ -221,6 +222,8 @@ pub struct BeefyParams<B: Block, BE, C, N, P, R, S, AuthorityId: AuthorityIdBoun
 	pub runtime: Arc<R>,
 	/// Local key store
 	pub key_store: Option<KeystorePtr>,
+	/// Record of the signed votes, consulted before each vote is signed
+	pub slashing_protection: Option<Arc<SlashingProtection>>,
 	/// BEEFY voter network params
 	pub network_params: BeefyNetworkParams<B, N, S>,
 	/// Minimal delta between blocks, BEEFY should vote for
@@ -315,12 +318,14 @@ where
 		links: BeefyVoterLinks<B, AuthorityId>,
 		pending_justifications: BTreeMap<NumberFor<B>, BeefyVersionedFinalityProof<B, AuthorityId>>,
 		is_authority: bool,
+		slashing_protection: Option<Arc<SlashingProtection>>,
 	) -> BeefyWorker<B, BE, P, R, S, N, AuthorityId> {
 		let key_store = Arc::new(self.key_store);
 		BeefyWorker {
 			backend: self.backend.clone(),
 			runtime: self.runtime.clone(),
 			key_store: key_store.clone(),
+			slashing_protection,
 			payload_provider,
 			sync,
 			fisherman: Arc::new(Fisherman::new(self.backend, self.runtime, key_store)),
@@ -536,6 +541,7 @@ pub async fn start_beefy_gadget<B, BE, C, N, P, R, S, AuthorityId>(
 		payload_provider,
 		runtime,
 		key_store,
+		slashing_protection,
 		network_params,
 		min_block_delta,
 		prometheus_registry,
@@ -629,6 +635,7 @@ pub async fn start_beefy_gadget<B, BE, C, N, P, R, S, AuthorityId>(
 			links.clone(),
 			BTreeMap::new(),
 			is_authority,
+			slashing_protection.clone(),
 		);
 
 		futures::select! {
@@ -454,6 +454,7 @@ where
 			payload_provider,
 			runtime: api.clone(),
 			key_store: Some(keystore),
+			slashing_protection: None,
 			network_params,
 			links: beefy_voter_links.unwrap(),
 			min_block_delta,
@@ -37,6 +37,7 @@ use codec::{Codec, Decode, DecodeAll, Encode};
 use futures::{stream::Fuse, FutureExt, StreamExt};
 use log::{debug, error, info, trace, warn};
 use sc_client_api::{Backend, HeaderBackend};
+use sc_consensus_slashing_protection::{BeefySlot, SlashingProtection};
 use sc_utils::notification::NotificationReceiver;
 use sp_api::ProvideRuntimeApi;
 use sp_arithmetic::traits::{AtLeast32Bit, Saturating};
@@ -385,6 +386,7 @@ pub(crate) struct BeefyWorker<B: Block, BE, P, RuntimeApi, S, N, AuthorityId: Au
 	pub backend: Arc<BE>,
 	pub runtime: Arc<RuntimeApi>,
 	pub key_store: Arc<BeefyKeystore<AuthorityId>>,
+	pub slashing_protection: Option<Arc<SlashingProtection>>,
 	pub payload_provider: P,
 	pub sync: Arc<S>,
 	pub fisherman: Arc<Fisherman<B, BE, RuntimeApi, AuthorityId>>,
@@ -781,6 +783,21 @@ where
 		let commitment = Commitment { payload, block_number: target_number, validator_set_id };
 		let encoded_commitment = commitment.encode();
 
+		if let Some(slashing_protection) = &self.slashing_protection {
+			let slot = BeefySlot { validator_set_id, block_number: target_number.saturated_into() };
+			if let Err(err) = slashing_protection.record_beefy_vote(
+				&authority_id.to_raw_vec(),
+				slot,
+				&encoded_commitment,
+			) {
+				warn!(
+					target: LOG_TARGET,
+					"🥩 Refusing to sign commitment for #{:?}: {}", target_number, err
+				);
+				return Ok(());
+			}
+		}
+
 		let signature = match self.key_store.sign(&authority_id, &encoded_commitment) {
 			Ok(sig) => sig,
 			Err(err) => {
@@ -1152,6 +1169,7 @@ pub(crate) mod tests {
 			backend: backend.clone(),
 			runtime: api.clone(),
 			key_store: key_store.clone(),
+			slashing_protection: None,
 			metrics,
 			payload_provider,
 			sync: Arc::new(sync),
@@ -1676,6 +1676,7 @@ mod tests {
 			gossip_duration: Duration::from_millis(10),
 			justification_generation_period: 256,
 			keystore: None,
+			slashing_protection: None,
 			name: None,
 			local_role: Role::Authority,
 			observer_enabled: true,
@@ -30,7 +30,7 @@
 //! under certain conditions that are used to un-stick the protocol.
 
 use futures::{channel::mpsc, prelude::*};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use parking_lot::Mutex;
 use prometheus_endpoint::Registry;
 use std::{
@@ -59,6 +59,7 @@ use crate::{
 use gossip::{
 	FullCatchUpMessage, FullCommitMessage, GossipMessage, GossipValidator, PeerReport, VoteMessage,
 };
+use sc_consensus_slashing_protection::{GrandpaSlot, GrandpaStage, SlashingProtection};
 use sc_network_sync::SyncEventStream;
 use sc_utils::mpsc::TracingUnboundedReceiver;
 use sp_consensus_grandpa::{AuthorityId, AuthoritySignature, RoundNumber, SetId as SetIdNumber};
@@ -334,6 +335,7 @@ impl<B: BlockT, N: Network<B>, S: Syncing<B>> NetworkBridge<B, N, S> {
 	pub(crate) fn round_communication(
 		&self,
 		keystore: Option<LocalIdKeystore>,
+		slashing_protection: Option<Arc<SlashingProtection>>,
 		round: Round,
 		set_id: SetId,
 		voters: Arc<VoterSet<AuthorityId>>,
@@ -421,6 +423,7 @@ impl<B: BlockT, N: Network<B>, S: Syncing<B>> NetworkBridge<B, N, S> {
 		let (tx, out_rx) = mpsc::channel(0);
 		let outgoing = OutgoingMessages::<B> {
 			keystore,
+			slashing_protection,
 			round: round.0,
 			set_id: set_id.0,
 			network: self.gossip_engine.clone(),
@@ -712,6 +715,7 @@ pub(crate) struct OutgoingMessages<Block: BlockT> {
 	round: RoundNumber,
 	set_id: SetIdNumber,
 	keystore: Option<LocalIdKeystore>,
+	slashing_protection: Option<Arc<SlashingProtection>>,
 	sender: mpsc::Sender<SignedMessage<Block::Header>>,
 	network: Arc<Mutex<GossipEngine<Block>>>,
 	has_voted: HasVoted<Block::Header>,
@@ -757,6 +761,32 @@ impl<Block: BlockT> Sink<Message<Block::Header>> for OutgoingMessages<Block> {
 		// when locals exist, sign messages on import
 		if let Some(ref keystore) = self.keystore {
 			let target_hash = *(msg.target().0);
+
+			if let Some(ref slashing_protection) = self.slashing_protection {
+				let stage = match msg {
+					PrimaryPropose(_) => GrandpaStage::PrimaryPropose,
+					Prevote(_) => GrandpaStage::Prevote,
+					Precommit(_) => GrandpaStage::Precommit,
+				};
+				let slot = GrandpaSlot { set_id: self.set_id, round: self.round, stage };
+				let payload =
+					sp_consensus_grandpa::localized_payload(self.round, self.set_id, &msg);
+				if let Err(e) = slashing_protection.record_grandpa_vote(
+					keystore.local_id().as_ref(),
+					slot,
+					&payload,
+				) {
+					warn!(
+						target: LOG_TARGET,
+						"Refusing to sign GRANDPA vote for round {} targeting {:?}: {}",
+						self.round,
+						target_hash,
+						e,
+					);
+					return Ok(())
+				}
+			}
+
 			let signed = sp_consensus_grandpa::sign_message(
 				keystore.keystore(),
 				msg,
@@ -299,6 +299,7 @@ fn config() -> crate::Config {
 		gossip_duration: std::time::Duration::from_millis(10),
 		justification_generation_period: 256,
 		keystore: None,
+		slashing_protection: None,
 		name: None,
 		local_role: Role::Authority,
 		observer_enabled: true,
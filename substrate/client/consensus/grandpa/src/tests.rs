@@ -326,6 +326,7 @@ fn initialize_grandpa(
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_generation_period: 32,
 				keystore: Some(keystore),
+				slashing_protection: None,
 				name: Some(format!("peer#{}", peer_id)),
 				local_role: Role::Authority,
 				observer_enabled: true,
@@ -485,6 +486,7 @@ async fn finalize_3_voters_1_full_observer() {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_generation_period: 32,
 				keystore: None,
+				slashing_protection: None,
 				name: Some(format!("peer#{}", peer_id)),
 				local_role: Role::Authority,
 				observer_enabled: true,
@@ -584,6 +586,7 @@ async fn transition_3_voters_twice_1_full_observer() {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_generation_period: 32,
 				keystore: Some(keystore),
+				slashing_protection: None,
 				name: Some(format!("peer#{}", peer_id)),
 				local_role: Role::Authority,
 				observer_enabled: true,
@@ -1019,6 +1022,7 @@ async fn voter_persists_its_votes() {
 			gossip_duration: TEST_GOSSIP_DURATION,
 			justification_generation_period: 32,
 			keystore: Some(bob_keystore.clone()),
+			slashing_protection: None,
 			name: Some(format!("peer#{}", 1)),
 			local_role: Role::Authority,
 			observer_enabled: true,
@@ -1067,6 +1071,7 @@ async fn voter_persists_its_votes() {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_generation_period: 32,
 				keystore: Some(keystore),
+				slashing_protection: None,
 				name: Some(format!("peer#{}", 0)),
 				local_role: Role::Authority,
 				observer_enabled: true,
@@ -1117,6 +1122,7 @@ async fn voter_persists_its_votes() {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_generation_period: 32,
 				keystore: Some(keystore),
+				slashing_protection: None,
 				name: Some(format!("peer#{}", 0)),
 				local_role: Role::Authority,
 				observer_enabled: true,
@@ -1161,6 +1167,7 @@ async fn voter_persists_its_votes() {
 	{
 		let (round_rx, round_tx) = bob_network.round_communication(
 			Some((peers[1].public().into(), bob_keystore).into()),
+			None,
 			communication::Round(1),
 			communication::SetId(0),
 			Arc::new(VoterSet::new(voters).unwrap()),
@@ -1286,6 +1293,7 @@ async fn finalize_3_voters_1_light_observer() {
 			gossip_duration: TEST_GOSSIP_DURATION,
 			justification_generation_period: 32,
 			keystore: None,
+			slashing_protection: None,
 			name: Some("observer".to_string()),
 			local_role: Role::Full,
 			observer_enabled: true,
@@ -1335,6 +1343,7 @@ async fn voter_catches_up_to_latest_round_when_behind() {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_generation_period: 32,
 				keystore,
+				slashing_protection: None,
 				name: Some(format!("peer#{}", peer_id)),
 				local_role: Role::Authority,
 				observer_enabled: true,
@@ -1455,6 +1464,7 @@ where
 		gossip_duration: TEST_GOSSIP_DURATION,
 		justification_generation_period: 32,
 		keystore,
+		slashing_protection: None,
 		name: None,
 		local_role: Role::Authority,
 		observer_enabled: true,
@@ -0,0 +1,128 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Interchange format of the slashing protection record.
+//!
+//! The record is exchanged as a JSON document:
+//!
+//! ```json
+//! {
+//!   "metadata": { "interchange_format_version": 1, "chain": "polkadot" },
+//!   "grandpa": [{
+//!     "public_key": "0x88dc...",
+//!     "low_watermark": { "set_id": 7, "round": 120, "stage": "precommit" },
+//!     "signed_votes": [
+//!       { "set_id": 7, "round": 121, "stage": "prevote", "signing_root": "0x5f3a..." }
+//!     ]
+//!   }],
+//!   "beefy": [{
+//!     "public_key": "0x0246...",
+//!     "signed_votes": [
+//!       { "validator_set_id": 7, "block_number": 4096, "signing_root": "0x91c2..." }
+//!     ]
+//!   }]
+//! }
+//! ```
+//!
+//! The signing root is the Blake2-256 hash of the signed payload. It may be omitted when it is
+//! not known, in which case no vote at all can be signed for the slot. No vote can be signed for
+//! a slot at or below the low watermark either, as the votes signed for these slots have been
+//! pruned from the record.
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the interchange format written by this crate.
+pub const INTERCHANGE_FORMAT_VERSION: u32 = 1;
+
+/// Votes signed by a set of authority keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interchange {
+	/// Information about the record.
+	pub metadata: Metadata,
+	/// Votes signed by the GRANDPA keys.
+	#[serde(default)]
+	pub grandpa: Vec<KeyHistory<GrandpaSlot>>,
+	/// Votes signed by the BEEFY keys.
+	#[serde(default)]
+	pub beefy: Vec<KeyHistory<BeefySlot>>,
+}
+
+/// Information about an [`Interchange`] record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+	/// Version of the format, [`INTERCHANGE_FORMAT_VERSION`].
+	pub interchange_format_version: u32,
+	/// Identifier of the chain the votes were signed for.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub chain: Option<String>,
+}
+
+/// Votes signed by a single key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyHistory<S> {
+	/// `0x`-prefixed public key.
+	pub public_key: String,
+	/// Highest slot pruned from the record.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub low_watermark: Option<S>,
+	/// The signed votes.
+	pub signed_votes: Vec<SignedVote<S>>,
+}
+
+/// A signed vote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedVote<S> {
+	/// Slot the vote was signed for.
+	#[serde(flatten)]
+	pub slot: S,
+	/// `0x`-prefixed Blake2-256 hash of the signed payload, if known.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub signing_root: Option<String>,
+}
+
+/// Stage of a GRANDPA round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrandpaStage {
+	/// Primary proposal.
+	PrimaryPropose,
+	/// Prevote.
+	Prevote,
+	/// Precommit.
+	Precommit,
+}
+
+/// Slot a GRANDPA key can sign at most one vote for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GrandpaSlot {
+	/// Authority set id.
+	pub set_id: u64,
+	/// Round number.
+	pub round: u64,
+	/// Stage of the round.
+	pub stage: GrandpaStage,
+}
+
+/// Slot a BEEFY key can sign at most one vote for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BeefySlot {
+	/// Validator set id.
+	pub validator_set_id: u64,
+	/// Number of the block voted on.
+	pub block_number: u64,
+}
@@ -0,0 +1,466 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Slashing protection for the GRANDPA and BEEFY voters.
+//!
+//! [`SlashingProtection`] records the votes signed by the local authority keys and must be
+//! consulted before each vote is signed. It refuses to sign a vote for a slot (a GRANDPA authority
+//! set id, round and stage, or a BEEFY validator set id and block number) that a different vote
+//! was already signed for, which would be an equivocation.
+//!
+//! The record is kept in a file of its own, outside of the node database, so that it survives
+//! database restores and resyncs. It can be exported to and imported from an [`Interchange`]
+//! document to move a validator between machines. The record doesn't protect against the same
+//! keys being used concurrently by another node.
+
+mod interchange;
+
+pub use interchange::{
+	BeefySlot, GrandpaSlot, GrandpaStage, Interchange, KeyHistory, Metadata, SignedVote,
+	INTERCHANGE_FORMAT_VERSION,
+};
+
+use parking_lot::Mutex;
+use std::{
+	collections::BTreeMap,
+	fmt, fs,
+	io::{self, Write},
+	path::{Path, PathBuf},
+};
+
+const LOG_TARGET: &str = "slashing-protection";
+
+/// Name of the record file in the data directory of the chain.
+pub const DEFAULT_FILE_NAME: &str = "slashing_protection.json";
+
+/// Maximum number of votes recorded per key, older votes are pruned.
+const MAX_VOTES_PER_KEY: usize = 512;
+
+/// Slashing protection error.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// A different vote was already signed for the slot.
+	#[error("A different vote was already signed for {0}")]
+	Conflict(String),
+	/// The votes signed for the slot were pruned from the record.
+	#[error("Vote for {0} is at or below the low watermark of the record")]
+	Stale(String),
+	/// The interchange is for another chain.
+	#[error("Interchange is for chain {found}, expected {expected}")]
+	ChainMismatch {
+		/// The chain of the node.
+		expected: String,
+		/// The chain of the interchange.
+		found: String,
+	},
+	/// The interchange format version is not supported.
+	#[error("Unsupported interchange format version {0}")]
+	UnsupportedVersion(u32),
+	/// Invalid hex value in the interchange.
+	#[error("Invalid hex value {0}")]
+	InvalidHex(String),
+	/// I/O error.
+	#[error(transparent)]
+	Io(#[from] io::Error),
+	/// JSON error.
+	#[error(transparent)]
+	Json(#[from] serde_json::Error),
+}
+
+/// Slashing protection result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Blake2-256 hash of a signed payload.
+type SigningRoot = [u8; 32];
+
+/// Votes signed by a key.
+#[derive(Clone)]
+struct History<S> {
+	low_watermark: Option<S>,
+	/// `None` when the vote signed for the slot is unknown.
+	votes: BTreeMap<S, Option<SigningRoot>>,
+}
+
+impl<S> Default for History<S> {
+	fn default() -> Self {
+		History { low_watermark: None, votes: BTreeMap::new() }
+	}
+}
+
+impl<S: Ord + Copy + fmt::Debug> History<S> {
+	/// Checks that the vote with `root` can be signed for `slot`, returns whether it already was.
+	fn check(&self, slot: S, root: &SigningRoot) -> Result<bool> {
+		if self.low_watermark.map_or(false, |low| slot <= low) {
+			return Err(Error::Stale(format!("{slot:?}")))
+		}
+
+		match self.votes.get(&slot) {
+			None => Ok(false),
+			Some(Some(recorded)) if recorded == root => Ok(true),
+			Some(_) => Err(Error::Conflict(format!("{slot:?}"))),
+		}
+	}
+
+	/// Merges a vote from another record, conflicting votes forbid any vote for the slot.
+	fn merge(&mut self, slot: S, root: Option<SigningRoot>) {
+		if self.low_watermark.map_or(false, |low| slot <= low) {
+			return
+		}
+
+		let recorded = self.votes.entry(slot).or_insert(root);
+		if *recorded != root {
+			*recorded = None;
+		}
+	}
+
+	fn raise_low_watermark(&mut self, low: S) {
+		if self.low_watermark.map_or(true, |current| current < low) {
+			self.low_watermark = Some(low);
+			self.votes.retain(|slot, _| *slot > low);
+		}
+	}
+
+	fn prune(&mut self) {
+		while self.votes.len() > MAX_VOTES_PER_KEY {
+			let (slot, _) = self.votes.pop_first().expect("more than zero votes recorded; qed");
+			self.low_watermark = Some(slot);
+		}
+	}
+}
+
+type Histories<S> = BTreeMap<Vec<u8>, History<S>>;
+
+#[derive(Clone, Default)]
+struct Records {
+	grandpa: Histories<GrandpaSlot>,
+	beefy: Histories<BeefySlot>,
+}
+
+impl Records {
+	fn export(&self, chain: Option<String>) -> Interchange {
+		Interchange {
+			metadata: Metadata { interchange_format_version: INTERCHANGE_FORMAT_VERSION, chain },
+			grandpa: export_histories(&self.grandpa),
+			beefy: export_histories(&self.beefy),
+		}
+	}
+
+	fn import(&mut self, interchange: Interchange) -> Result<()> {
+		let version = interchange.metadata.interchange_format_version;
+		if version != INTERCHANGE_FORMAT_VERSION {
+			return Err(Error::UnsupportedVersion(version))
+		}
+
+		import_histories(&mut self.grandpa, interchange.grandpa)?;
+		import_histories(&mut self.beefy, interchange.beefy)
+	}
+}
+
+/// Slot of a vote.
+trait Slot: Ord + Copy + fmt::Debug {
+	fn histories(records: &mut Records) -> &mut Histories<Self>;
+}
+
+impl Slot for GrandpaSlot {
+	fn histories(records: &mut Records) -> &mut Histories<Self> {
+		&mut records.grandpa
+	}
+}
+
+impl Slot for BeefySlot {
+	fn histories(records: &mut Records) -> &mut Histories<Self> {
+		&mut records.beefy
+	}
+}
+
+fn export_histories<S: Copy>(histories: &Histories<S>) -> Vec<KeyHistory<S>> {
+	histories
+		.iter()
+		.map(|(public, history)| KeyHistory {
+			public_key: array_bytes::bytes2hex("0x", public),
+			low_watermark: history.low_watermark,
+			signed_votes: history
+				.votes
+				.iter()
+				.map(|(slot, root)| SignedVote {
+					slot: *slot,
+					signing_root: root.map(|root| array_bytes::bytes2hex("0x", root)),
+				})
+				.collect(),
+		})
+		.collect()
+}
+
+fn import_histories<S: Ord + Copy + fmt::Debug>(
+	histories: &mut Histories<S>,
+	keys: Vec<KeyHistory<S>>,
+) -> Result<()> {
+	for key in keys {
+		let history = histories.entry(parse_hex(&key.public_key)?).or_default();
+		if let Some(low) = key.low_watermark {
+			history.raise_low_watermark(low);
+		}
+		for vote in key.signed_votes {
+			let root = vote
+				.signing_root
+				.map(|root| {
+					parse_hex(&root)?.try_into().map_err(|_| Error::InvalidHex(root.clone()))
+				})
+				.transpose()?;
+			history.merge(vote.slot, root);
+		}
+		history.prune();
+	}
+
+	Ok(())
+}
+
+fn parse_hex(value: &str) -> Result<Vec<u8>> {
+	array_bytes::hex2bytes(value).map_err(|_| Error::InvalidHex(value.into()))
+}
+
+/// Record of the votes signed by the local authority keys.
+pub struct SlashingProtection {
+	path: Option<PathBuf>,
+	records: Mutex<Records>,
+}
+
+impl SlashingProtection {
+	/// Opens the record stored at `path`.
+	///
+	/// The file is created when the first vote is recorded.
+	pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+		let path = path.into();
+		let mut records = Records::default();
+		match fs::read(&path) {
+			Ok(data) => records.import(serde_json::from_slice(&data)?)?,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+			Err(e) => return Err(e.into()),
+		}
+
+		Ok(SlashingProtection { path: Some(path), records: Mutex::new(records) })
+	}
+
+	/// Creates a record that is only kept in memory.
+	pub fn in_memory() -> Self {
+		SlashingProtection { path: None, records: Mutex::new(Records::default()) }
+	}
+
+	/// Records that `public` is about to sign the GRANDPA vote `payload` for `slot`.
+	///
+	/// Returns an error if the vote must not be signed.
+	pub fn record_grandpa_vote(
+		&self,
+		public: &[u8],
+		slot: GrandpaSlot,
+		payload: &[u8],
+	) -> Result<()> {
+		self.record(public, slot, payload)
+	}
+
+	/// Records that `public` is about to sign the BEEFY vote `payload` for `slot`.
+	///
+	/// Returns an error if the vote must not be signed.
+	pub fn record_beefy_vote(&self, public: &[u8], slot: BeefySlot, payload: &[u8]) -> Result<()> {
+		self.record(public, slot, payload)
+	}
+
+	/// Exports the record for the given chain.
+	pub fn export(&self, chain: &str) -> Interchange {
+		self.records.lock().export(Some(chain.into()))
+	}
+
+	/// Merges the `interchange` exported for the given chain into the record.
+	///
+	/// Votes that conflict with the recorded ones forbid signing any vote for their slot.
+	pub fn import(&self, interchange: Interchange, chain: &str) -> Result<()> {
+		match &interchange.metadata.chain {
+			Some(found) if found != chain =>
+				return Err(Error::ChainMismatch { expected: chain.into(), found: found.clone() }),
+			_ => {},
+		}
+
+		let mut records = self.records.lock();
+		let mut imported = records.clone();
+		imported.import(interchange)?;
+		self.persist(&imported)?;
+		*records = imported;
+
+		Ok(())
+	}
+
+	fn record<S: Slot>(&self, public: &[u8], slot: S, payload: &[u8]) -> Result<()> {
+		let root = sp_crypto_hashing::blake2_256(payload);
+		let mut records = self.records.lock();
+
+		{
+			let history = S::histories(&mut records).entry(public.to_vec()).or_default();
+			if history.check(slot, &root)? {
+				return Ok(())
+			}
+			history.votes.insert(slot, Some(root));
+			history.prune();
+		}
+
+		if let Err(e) = self.persist(&records) {
+			log::error!(target: LOG_TARGET, "Failed to record vote for {slot:?}: {e}");
+			if let Some(history) = S::histories(&mut records).get_mut(public) {
+				history.votes.remove(&slot);
+			}
+			return Err(e)
+		}
+
+		Ok(())
+	}
+
+	fn persist(&self, records: &Records) -> Result<()> {
+		let Some(path) = &self.path else { return Ok(()) };
+		write_atomically(path, &serde_json::to_vec(&records.export(None))?)?;
+		Ok(())
+	}
+}
+
+fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+	let tmp_path = path.with_extension("tmp");
+	let mut tmp = fs::File::create(&tmp_path)?;
+	tmp.write_all(data)?;
+	tmp.sync_all()?;
+	fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::TempDir;
+
+	const ALICE: &[u8] = &[1; 32];
+	const BOB: &[u8] = &[2; 32];
+
+	fn grandpa(set_id: u64, round: u64, stage: GrandpaStage) -> GrandpaSlot {
+		GrandpaSlot { set_id, round, stage }
+	}
+
+	fn beefy(validator_set_id: u64, block_number: u64) -> BeefySlot {
+		BeefySlot { validator_set_id, block_number }
+	}
+
+	#[test]
+	fn conflicting_votes_are_refused() {
+		let protection = SlashingProtection::in_memory();
+		let slot = grandpa(1, 10, GrandpaStage::Prevote);
+
+		protection.record_grandpa_vote(ALICE, slot, b"a").unwrap();
+		// signing the same vote again is fine
+		protection.record_grandpa_vote(ALICE, slot, b"a").unwrap();
+		assert!(matches!(
+			protection.record_grandpa_vote(ALICE, slot, b"b"),
+			Err(Error::Conflict(_))
+		));
+
+		// other stages, rounds and keys are independent
+		protection
+			.record_grandpa_vote(ALICE, grandpa(1, 10, GrandpaStage::Precommit), b"b")
+			.unwrap();
+		protection
+			.record_grandpa_vote(ALICE, grandpa(1, 11, GrandpaStage::Prevote), b"b")
+			.unwrap();
+		protection.record_grandpa_vote(BOB, slot, b"b").unwrap();
+
+		protection.record_beefy_vote(ALICE, beefy(1, 100), b"a").unwrap();
+		assert!(matches!(
+			protection.record_beefy_vote(ALICE, beefy(1, 100), b"b"),
+			Err(Error::Conflict(_))
+		));
+	}
+
+	#[test]
+	fn votes_survive_restarts() {
+		let dir = TempDir::new().unwrap();
+		let path = dir.path().join(DEFAULT_FILE_NAME);
+
+		let protection = SlashingProtection::open(&path).unwrap();
+		protection
+			.record_grandpa_vote(ALICE, grandpa(1, 10, GrandpaStage::Prevote), b"a")
+			.unwrap();
+		protection.record_beefy_vote(ALICE, beefy(1, 100), b"a").unwrap();
+		drop(protection);
+
+		let protection = SlashingProtection::open(&path).unwrap();
+		assert!(protection
+			.record_grandpa_vote(ALICE, grandpa(1, 10, GrandpaStage::Prevote), b"b")
+			.is_err());
+		assert!(protection.record_beefy_vote(ALICE, beefy(1, 100), b"b").is_err());
+		protection.record_beefy_vote(ALICE, beefy(1, 100), b"a").unwrap();
+	}
+
+	#[test]
+	fn pruned_votes_are_refused() {
+		let protection = SlashingProtection::in_memory();
+		for block_number in 0..MAX_VOTES_PER_KEY as u64 + 10 {
+			protection.record_beefy_vote(ALICE, beefy(1, block_number), b"a").unwrap();
+		}
+
+		let interchange = protection.export("test");
+		assert_eq!(interchange.beefy[0].signed_votes.len(), MAX_VOTES_PER_KEY);
+		assert_eq!(interchange.beefy[0].low_watermark, Some(beefy(1, 9)));
+
+		assert!(matches!(
+			protection.record_beefy_vote(ALICE, beefy(1, 9), b"a"),
+			Err(Error::Stale(_))
+		));
+		protection.record_beefy_vote(ALICE, beefy(1, 10), b"a").unwrap();
+	}
+
+	#[test]
+	fn interchange_is_merged() {
+		let source = SlashingProtection::in_memory();
+		source
+			.record_grandpa_vote(ALICE, grandpa(1, 10, GrandpaStage::Prevote), b"a")
+			.unwrap();
+		source
+			.record_grandpa_vote(ALICE, grandpa(1, 11, GrandpaStage::Prevote), b"a")
+			.unwrap();
+
+		let target = SlashingProtection::in_memory();
+		target
+			.record_grandpa_vote(ALICE, grandpa(1, 11, GrandpaStage::Prevote), b"b")
+			.unwrap();
+
+		let interchange: Interchange =
+			serde_json::from_str(&serde_json::to_string(&source.export("test")).unwrap()).unwrap();
+		assert!(matches!(
+			target.import(interchange.clone(), "other"),
+			Err(Error::ChainMismatch { .. })
+		));
+		target.import(interchange, "test").unwrap();
+
+		// the imported vote is enforced
+		assert!(target
+			.record_grandpa_vote(ALICE, grandpa(1, 10, GrandpaStage::Prevote), b"b")
+			.is_err());
+		target
+			.record_grandpa_vote(ALICE, grandpa(1, 10, GrandpaStage::Prevote), b"a")
+			.unwrap();
+		// no vote can be signed for conflicting slots
+		for payload in [b"a", b"b"] {
+			assert!(target
+				.record_grandpa_vote(ALICE, grandpa(1, 11, GrandpaStage::Prevote), payload)
+				.is_err());
+		}
+	}
+}
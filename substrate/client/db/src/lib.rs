@@ -36,6 +36,7 @@ mod children;
 mod parity_db;
 mod pinned_blocks_cache;
 mod record_stats_state;
+mod state_pruning;
 mod stats;
 #[cfg(any(feature = "rocksdb", test))]
 mod upgrade;
@@ -100,6 +101,7 @@ pub use sc_state_db::PruningMode;
 pub use sp_database::Database;
 
 pub use bench::BenchmarkingState;
+pub use state_pruning::prune_archive_state;
 
 const CACHE_HEADERS: usize = 8;
 
@@ -2541,6 +2543,14 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 							.build();
 					let state = RefTrackingState::new(db_state, self.storage.clone(), Some(hash));
 					Ok(RecordStatsState::new(state, Some(hash), self.state_usage.clone()))
+				} else if let PruningMode::SparseArchive { period, .. } =
+					self.storage.state_db.pruning_mode()
+				{
+					Err(sp_blockchain::Error::UnknownBlock(format!(
+						"State already discarded for {hash:?} (#{}): only the state of every \
+						{period}th block is kept beyond the pruning window",
+						hdr.number,
+					)))
 				} else {
 					Err(sp_blockchain::Error::UnknownBlock(format!(
 						"State already discarded for {hash:?}",
@@ -2588,7 +2598,9 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	fn requires_full_sync(&self) -> bool {
 		matches!(
 			self.storage.state_db.pruning_mode(),
-			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical
+			PruningMode::ArchiveAll |
+				PruningMode::ArchiveCanonical |
+				PruningMode::SparseArchive { .. }
 		)
 	}
 
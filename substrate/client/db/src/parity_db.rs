@@ -40,6 +40,30 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 	create: bool,
 	upgrade: bool,
 ) -> parity_db::Result<std::sync::Arc<dyn Database<H>>> {
+	let config = options(path, db_type);
+
+	if upgrade {
+		log::info!("Upgrading database metadata.");
+		if let Some(meta) = parity_db::Options::load_metadata(path)? {
+			config.write_metadata_with_version(path, &meta.salt, Some(meta.version))?;
+		}
+	}
+
+	let db = if create {
+		parity_db::Db::open_or_create(&config)?
+	} else {
+		parity_db::Db::open(&config)?
+	};
+
+	Ok(std::sync::Arc::new(DbAdapter(db)))
+}
+
+/// Open an existing parity-db database without wrapping it.
+pub fn open_raw(path: &std::path::Path, db_type: DatabaseType) -> parity_db::Result<parity_db::Db> {
+	parity_db::Db::open(&options(path, db_type))
+}
+
+fn options(path: &std::path::Path, db_type: DatabaseType) -> parity_db::Options {
 	let mut config = parity_db::Options::with_columns(path, NUM_COLUMNS as u8);
 
 	match db_type {
@@ -70,20 +94,7 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 		},
 	}
 
-	if upgrade {
-		log::info!("Upgrading database metadata.");
-		if let Some(meta) = parity_db::Options::load_metadata(path)? {
-			config.write_metadata_with_version(path, &meta.salt, Some(meta.version))?;
-		}
-	}
-
-	let db = if create {
-		parity_db::Db::open_or_create(&config)?
-	} else {
-		parity_db::Db::open(&config)?
-	};
-
-	Ok(std::sync::Arc::new(DbAdapter(db)))
+	config
 }
 
 fn ref_counted_column(col: u32) -> bool {
@@ -0,0 +1,622 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Conversion of an archive database into a pruned one.
+//!
+//! The state pruning mode of a database is chosen when it is created. [`prune_archive_state`]
+//! switches an existing archive database to a non-archive mode in place, without a resync:
+//!
+//! 1. The kept states are walked and every trie node they reference is marked. These are the state
+//!    of the last canonicalized block and, for [`PruningMode::SparseArchive`], the states of the
+//!    checkpoint blocks. The marks are recorded in a temporary database next to the converted one.
+//! 2. The state database metadata is switched to the new mode, with the last canonicalized block as
+//!    the only block of the pruning window. The window fills up again as blocks get finalized.
+//! 3. The state column is swept, removing the nodes that were not marked.
+//!
+//! [`PruningMode::ArchiveAll`] doesn't maintain a non-canonical overlay, so the blocks above the
+//! last canonicalized block are added to it, with the changes of their state relative to the one
+//! of their parent. Like for imported blocks, the nodes they insert are then only kept in the
+//! overlay, and are reclaimed when their fork is discarded.
+//!
+//! The node must not be running during the conversion. If the conversion is interrupted during the
+//! sweep, the database already uses the new mode and only keeps some unreferenced nodes around.
+
+use crate::{
+	apply_state_commit, columns, parity_db,
+	utils::{self, DatabaseType},
+	BlockchainDb, DatabaseSource, DbHash, DbStateBuilder, StateMetaDb, DB_HASH_LEN,
+};
+use hash_db::{Hasher, Prefix, EMPTY_PREFIX};
+use log::{info, warn};
+use parking_lot::Mutex;
+use sc_state_db::{ChangeSet, LastCanonicalized, PruningMode, StateDb, StateDbError};
+use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata, Result as ClientResult};
+use sp_core::{
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, ChildInfo},
+};
+use sp_database::{Database, Transaction};
+use sp_runtime::traits::{Block as BlockT, HashingFor, SaturatedConversion};
+use sp_state_machine::{backend::Backend as _, DBValue, IterArgs};
+use sp_trie::prefixed_key;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+/// Number of changes committed at once while sweeping the state column.
+const SWEEP_BATCH_SIZE: usize = 100_000;
+
+/// Number of marks buffered before they are written to the marks database.
+const MARK_BATCH_SIZE: usize = 100_000;
+
+/// Name of the directory of the marks database, next to the converted database.
+const MARKS_DIRECTORY: &str = "prune-state-marks";
+
+/// The set of the nodes to keep in the state column.
+const KEPT_SET: u32 = 0;
+
+/// The set of the nodes of the state of the last canonicalized block.
+const LAST_CANONICAL_SET: u32 = 1;
+
+/// Sets of marked trie nodes, recorded in a temporary database.
+///
+/// Each mark is keyed by its set, followed by the prefixed key of the node. When the nodes are not
+/// prefixed in the state column, the prefixed key is preceded by the hash of the node, so that the
+/// references to a node can be counted.
+struct Marks {
+	path: PathBuf,
+	db: ::parity_db::Db,
+	by_hash: bool,
+	pending: Mutex<Vec<Vec<u8>>>,
+}
+
+impl Marks {
+	/// Create an empty marks database at `path`, removing any leftover of a previous conversion.
+	fn create(path: PathBuf, by_hash: bool) -> ClientResult<Self> {
+		if path.exists() {
+			std::fs::remove_dir_all(&path).map_err(|e| {
+				ClientError::Backend(format!("Failed to remove {}: {e}", path.display()))
+			})?;
+		}
+		let mut options = ::parity_db::Options::with_columns(&path, 1);
+		options.columns[0].btree_index = true;
+		options.sync_wal = false;
+		options.sync_data = false;
+		let db = ::parity_db::Db::open_or_create(&options).map_err(marks_error)?;
+		Ok(Self { path, db, by_hash, pending: Default::default() })
+	}
+
+	fn key(&self, set: u32, prefixed: &[u8]) -> Vec<u8> {
+		let mut key = set.to_be_bytes().to_vec();
+		if self.by_hash {
+			key.extend_from_slice(&prefixed[prefixed.len() - DB_HASH_LEN..]);
+		}
+		key.extend_from_slice(prefixed);
+		key
+	}
+
+	/// Mark the node with the given prefixed key in `set`.
+	fn insert(&self, set: u32, prefixed: &[u8]) -> ClientResult<()> {
+		let mut pending = self.pending.lock();
+		pending.push(self.key(set, prefixed));
+		if pending.len() >= MARK_BATCH_SIZE {
+			self.write(std::mem::take(&mut *pending))?;
+		}
+		Ok(())
+	}
+
+	/// Write the buffered marks, so that they are visible to the lookups.
+	fn flush(&self) -> ClientResult<()> {
+		let pending = std::mem::take(&mut *self.pending.lock());
+		self.write(pending)
+	}
+
+	fn write(&self, keys: Vec<Vec<u8>>) -> ClientResult<()> {
+		self.db
+			.commit(keys.into_iter().map(|key| (0, key, Some(Vec::new()))))
+			.map_err(marks_error)
+	}
+
+	/// Whether the node with the given prefixed key is marked in `set`.
+	fn contains(&self, set: u32, prefixed: &[u8]) -> ClientResult<bool> {
+		Ok(self.db.get(0, &self.key(set, prefixed)).map_err(marks_error)?.is_some())
+	}
+
+	/// Call `f` with the prefixed key of every node marked in `set`.
+	fn for_each(&self, set: u32, mut f: impl FnMut(&[u8]) -> ClientResult<()>) -> ClientResult<()> {
+		let skip = 4 + if self.by_hash { DB_HASH_LEN } else { 0 };
+		self.for_each_with_prefix(&set.to_be_bytes(), |key| f(&key[skip..]))
+	}
+
+	/// Number of positions the node with the given hash is marked at in `set`.
+	fn references(&self, set: u32, hash: &[u8]) -> ClientResult<u32> {
+		debug_assert!(self.by_hash);
+		let mut prefix = set.to_be_bytes().to_vec();
+		prefix.extend_from_slice(hash);
+		let mut references = 0;
+		self.for_each_with_prefix(&prefix, |_| {
+			references += 1;
+			Ok(())
+		})?;
+		Ok(references)
+	}
+
+	fn for_each_with_prefix(
+		&self,
+		prefix: &[u8],
+		mut f: impl FnMut(&[u8]) -> ClientResult<()>,
+	) -> ClientResult<()> {
+		let mut iter = self.db.iter(0).map_err(marks_error)?;
+		iter.seek(prefix).map_err(marks_error)?;
+		while let Some((key, _)) = iter.next().map_err(marks_error)? {
+			if !key.starts_with(prefix) {
+				break
+			}
+			f(&key)?;
+		}
+		Ok(())
+	}
+
+	/// Close and remove the marks database.
+	fn remove(self) -> ClientResult<()> {
+		drop(self.db);
+		std::fs::remove_dir_all(&self.path).map_err(|e| {
+			ClientError::Backend(format!("Failed to remove {}: {e}", self.path.display()))
+		})
+	}
+}
+
+fn marks_error(e: ::parity_db::Error) -> ClientError {
+	ClientError::Backend(format!("Failed to access the marked trie nodes: {e}"))
+}
+
+/// Trie node storage that marks the prefixed key of every node that is read.
+struct MarkingStorage {
+	db: Arc<dyn Database<DbHash>>,
+	prefix_keys: bool,
+	marks: Arc<Marks>,
+	sets: Vec<u32>,
+}
+
+impl<H: Hasher> sp_state_machine::Storage<H> for MarkingStorage {
+	fn get(&self, key: &H::Out, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		let prefixed = prefixed_key::<H>(key, prefix);
+		let value = if self.prefix_keys {
+			self.db.get(columns::STATE, &prefixed)
+		} else {
+			self.db.get(columns::STATE, key.as_ref())
+		};
+		if value.is_some() {
+			for set in &self.sets {
+				self.marks.insert(*set, &prefixed).map_err(|e| e.to_string())?;
+			}
+		}
+		Ok(value)
+	}
+}
+
+/// Mark all the nodes of the state with the given root in each of `sets`, including its child
+/// tries.
+///
+/// Returns `false` if the state is not in the database.
+fn mark_state<Block: BlockT>(
+	db: &Arc<dyn Database<DbHash>>,
+	marks: &Arc<Marks>,
+	sets: &[u32],
+	root: Block::Hash,
+) -> ClientResult<bool> {
+	let walk_error = |e| ClientError::Backend(format!("Failed to walk state {root:?}: {e}"));
+
+	let storage = Arc::new(MarkingStorage {
+		db: db.clone(),
+		prefix_keys: !marks.by_hash,
+		marks: marks.clone(),
+		sets: sets.to_vec(),
+	});
+	if sp_state_machine::Storage::<HashingFor<Block>>::get(&*storage, &root, EMPTY_PREFIX)
+		.map_err(walk_error)?
+		.is_none()
+	{
+		return Ok(false)
+	}
+
+	let state = DbStateBuilder::<HashingFor<Block>>::new(storage, root).build();
+	let mut child_infos = Vec::new();
+	for pair in state.pairs(Default::default()).map_err(walk_error)? {
+		let (key, _) = pair.map_err(walk_error)?;
+		if let Some(storage_key) =
+			key.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX)
+		{
+			child_infos.push(ChildInfo::new_default(storage_key));
+		}
+	}
+	for child_info in child_infos {
+		let args = IterArgs { child_info: Some(child_info), ..Default::default() };
+		for pair in state.pairs(args).map_err(walk_error)? {
+			pair.map_err(walk_error)?;
+		}
+	}
+	marks.flush()?;
+	Ok(true)
+}
+
+/// The changes of the state marked in `set` relative to the state marked in `parent_set`, keyed
+/// like in the state database.
+fn state_changes(
+	db: &Arc<dyn Database<DbHash>>,
+	marks: &Marks,
+	set: u32,
+	parent_set: u32,
+) -> ClientResult<ChangeSet<Vec<u8>>> {
+	let state_key = |prefixed: &[u8]| {
+		if marks.by_hash {
+			prefixed[prefixed.len() - DB_HASH_LEN..].to_vec()
+		} else {
+			prefixed.to_vec()
+		}
+	};
+
+	let mut changes = ChangeSet::default();
+	marks.for_each(set, |prefixed| {
+		if !marks.contains(parent_set, prefixed)? {
+			let key = state_key(prefixed);
+			let value = db.get(columns::STATE, &key).ok_or_else(|| {
+				ClientError::Backend(format!("Missing trie node {}", HexDisplay::from(&key)))
+			})?;
+			changes.inserted.push((key, value));
+		}
+		Ok(())
+	})?;
+	marks.for_each(parent_set, |prefixed| {
+		if !marks.contains(set, prefixed)? {
+			changes.deleted.push(state_key(prefixed));
+		}
+		Ok(())
+	})?;
+	Ok(changes)
+}
+
+/// Switch the archive database at `source` to the non-archive state pruning `mode`.
+///
+/// See the [module documentation](self) for details. The marked trie nodes are recorded in a
+/// temporary database next to the converted one, which needs enough disk space for the keys of the
+/// kept states.
+pub fn prune_archive_state<Block: BlockT>(
+	source: &DatabaseSource,
+	mode: PruningMode,
+) -> ClientResult<()> {
+	if let DatabaseSource::Custom { .. } = source {
+		return Err(ClientError::Backend("Can't prune the state of a custom database".into()))
+	}
+
+	let db = utils::open_database::<Block>(source, DatabaseType::Full, false)?;
+	let ref_counting = db.supports_ref_counting();
+	// Checked before the lengthy marking, `StateDb` would only refuse to open afterwards.
+	if matches!(mode, PruningMode::SparseArchive { .. }) && !ref_counting {
+		return Err(ClientError::from_state_db(StateDbError::SparseArchiveUnsupported))
+	}
+	let path = if ref_counting {
+		match source {
+			DatabaseSource::ParityDb { path } => path,
+			DatabaseSource::Auto { paritydb_path, .. } => paritydb_path,
+			_ =>
+				return Err(ClientError::Backend(
+					"Only a ParityDb database can support reference counting".into(),
+				)),
+		}
+	} else {
+		match source {
+			DatabaseSource::RocksDb { path, .. } => path,
+			DatabaseSource::Auto { rocksdb_path, .. } => rocksdb_path,
+			_ =>
+				return Err(ClientError::Backend(
+					"Only a RocksDb database can lack reference counting".into(),
+				)),
+		}
+	};
+
+	let (_, state_db) = StateDb::<Block::Hash, Vec<u8>, StateMetaDb>::open(
+		StateMetaDb(db.clone()),
+		None,
+		!ref_counting,
+		false,
+	)
+	.map_err(ClientError::from_state_db)?;
+	let stored = state_db.pruning_mode();
+	if !stored.is_archive() || mode.is_archive() {
+		return Err(ClientError::Backend(format!(
+			"Only an archive database can be pruned [stored: {stored:?}; requested: {mode:?}]"
+		)))
+	}
+
+	let blockchain = BlockchainDb::<Block>::new(db.clone())?;
+	let last_canonical = match state_db.last_canonicalized() {
+		LastCanonicalized::Block(number) => number.saturated_into(),
+		LastCanonicalized::None | LastCanonicalized::NotCanonicalizing =>
+			blockchain.info().finalized_number,
+	};
+	let last_canonical_hash = blockchain.hash(last_canonical)?.ok_or_else(|| {
+		ClientError::UnknownBlock(format!("Missing canonical block #{last_canonical}"))
+	})?;
+
+	let marks = Arc::new(Marks::create(path.with_file_name(MARKS_DIRECTORY), ref_counting)?);
+	let last_canonical_root = blockchain.header_metadata(last_canonical_hash)?.state_root;
+	if !mark_state::<Block>(&db, &marks, &[KEPT_SET, LAST_CANONICAL_SET], last_canonical_root)? {
+		return Err(ClientError::UnknownBlock(format!(
+			"Missing state of the last canonical block #{last_canonical} ({last_canonical_hash:?})"
+		)))
+	}
+
+	// The nodes of the blocks above the last canonical block stay in the state column with
+	// `ArchiveAll`, they are moved to the non-canonical overlay.
+	let mut non_canonical = Vec::new();
+	if stored == PruningMode::ArchiveAll {
+		let mut blocks = HashMap::new();
+		for leaf in blockchain.leaves.read().hashes() {
+			let mut branch = Vec::new();
+			let mut hash = leaf;
+			let on_top_of_last_canonical = loop {
+				if hash == last_canonical_hash || blocks.contains_key(&hash) {
+					break true
+				}
+				let header = blockchain.header_metadata(hash)?;
+				if header.number <= last_canonical {
+					break false
+				}
+				hash = header.parent;
+				branch.push(header);
+			};
+			if on_top_of_last_canonical {
+				blocks.extend(branch.into_iter().map(|header| (header.hash, header)));
+			}
+		}
+
+		let mut blocks = blocks.into_values().collect::<Vec<_>>();
+		blocks.sort_by_key(|header| header.number);
+		let mut sets = HashMap::from([(last_canonical_hash, LAST_CANONICAL_SET)]);
+		for (index, header) in blocks.into_iter().enumerate() {
+			let set = LAST_CANONICAL_SET + 1 + index as u32;
+			let changes = if !mark_state::<Block>(&db, &marks, &[set], header.state_root)? {
+				warn!(target: "db", "Missing state of non-canonical block {:?}", header.hash);
+				ChangeSet::default()
+			} else if let Some(parent_set) = sets.get(&header.parent).copied() {
+				sets.insert(header.hash, set);
+				state_changes(&db, &marks, set, parent_set)?
+			} else {
+				// Without the state of the parent, the nodes of the block stay in the state column.
+				mark_state::<Block>(&db, &marks, &[KEPT_SET], header.state_root)?;
+				sets.insert(header.hash, set);
+				ChangeSet::default()
+			};
+			non_canonical.push((
+				header.hash,
+				header.number.saturated_into::<u64>(),
+				header.parent,
+				changes,
+			));
+		}
+	}
+
+	if let PruningMode::SparseArchive { period, .. } = mode {
+		let last_canonical: u64 = last_canonical.saturated_into();
+		for number in (0..last_canonical).step_by(period.max(1) as usize) {
+			if !mode.is_checkpoint(number) {
+				continue
+			}
+			let Some(hash) = blockchain.hash(number.saturated_into())? else { continue };
+			let root = blockchain.header_metadata(hash)?.state_root;
+			if !mark_state::<Block>(&db, &marks, &[KEPT_SET], root)? {
+				warn!(target: "db", "Missing state of checkpoint block #{number} ({hash:?})");
+			}
+		}
+	}
+	info!(target: "db", "Marked the trie nodes referenced by the kept states");
+
+	let commit = sc_state_db::convert_archive::<_, Vec<u8>, _>(
+		&StateMetaDb(db.clone()),
+		mode,
+		(last_canonical_hash, last_canonical.saturated_into()),
+		non_canonical,
+	)
+	.map_err(ClientError::from_state_db)?;
+	let mut transaction = Transaction::new();
+	apply_state_commit(&mut transaction, commit);
+	db.commit(transaction)?;
+
+	// The sweep needs exclusive access to the database.
+	drop((state_db, blockchain, db));
+	let marks = Arc::into_inner(marks).expect("The marking storages are dropped; qed");
+
+	let removed =
+		if ref_counting { sweep_parity_db(path, &marks)? } else { sweep_rocksdb(path, &marks)? };
+	info!(target: "db", "Removed {removed} unreferenced trie nodes");
+
+	marks.remove()
+}
+
+/// Remove the references to the nodes of the state column that are not marked.
+///
+/// Nodes are not prefixed in ParityDb, so a node that is referenced at several positions of the
+/// kept states keeps one reference per position, just like when the nodes are inserted.
+///
+/// Only the keys and the reference changes are collected while iterating the column, since
+/// committing during the iteration could make it visit the same nodes again. The values of the
+/// nodes which gain references are read back when committing.
+fn sweep_parity_db(path: &Path, marks: &Marks) -> ClientResult<u64> {
+	let db = parity_db::open_raw(path, DatabaseType::Full)
+		.map_err(|e| ClientError::Backend(format!("Failed to open database: {e}")))?;
+	let column = columns::STATE as u8;
+	let mut changes = Vec::new();
+	let mut removed = 0;
+	let mut result = Ok(());
+	db.iter_column_while(column, |item| {
+		let references = match marks.references(KEPT_SET, &item.key) {
+			Ok(references) => references,
+			Err(e) => {
+				result = Err(e);
+				return false
+			},
+		};
+		if references == 0 {
+			removed += 1;
+		}
+		let delta = i64::from(references) - i64::from(item.rc);
+		if delta != 0 {
+			changes.push((item.key, delta));
+		}
+		true
+	})
+	.map_err(|e| ClientError::Backend(format!("Failed to iterate state column: {e}")))?;
+	result?;
+
+	for batch in changes.chunks(SWEEP_BATCH_SIZE) {
+		let mut commit = Vec::with_capacity(batch.len());
+		for (key, delta) in batch {
+			let value = if *delta > 0 {
+				let value = db
+					.get(column, key)
+					.map_err(|e| ClientError::Backend(format!("Failed to read state node: {e}")))?
+					.ok_or_else(|| {
+						ClientError::Backend(format!("Missing trie node {}", HexDisplay::from(key)))
+					})?;
+				Some(value)
+			} else {
+				None
+			};
+			let count = delta.unsigned_abs() as usize;
+			commit.extend(std::iter::repeat((column, key, value)).take(count));
+		}
+		db.commit(commit)
+			.map_err(|e| ClientError::Backend(format!("Failed to remove state nodes: {e}")))?;
+	}
+	Ok(removed)
+}
+
+/// Remove the nodes of the state column that are not marked.
+#[cfg(any(feature = "rocksdb", test))]
+fn sweep_rocksdb(path: &Path, marks: &Marks) -> ClientResult<u64> {
+	use kvdb::KeyValueDB;
+
+	let db_error = |e: std::io::Error| ClientError::Backend(format!("RocksDb error: {e}"));
+	let db = kvdb_rocksdb::Database::open(
+		&kvdb_rocksdb::DatabaseConfig::with_columns(utils::NUM_COLUMNS),
+		path,
+	)
+	.map_err(db_error)?;
+	let mut transaction = db.transaction();
+	let mut removed = 0;
+	for item in db.iter(columns::STATE) {
+		let (key, _) = item.map_err(db_error)?;
+		if !marks.contains(KEPT_SET, &key)? {
+			transaction.delete(columns::STATE, &key);
+			removed += 1;
+			if transaction.ops.len() >= SWEEP_BATCH_SIZE {
+				db.write(std::mem::take(&mut transaction)).map_err(db_error)?;
+			}
+		}
+	}
+	db.write(transaction).map_err(db_error)?;
+	Ok(removed)
+}
+
+#[cfg(not(any(feature = "rocksdb", test)))]
+fn sweep_rocksdb(_path: &Path, _marks: &Marks) -> ClientResult<u64> {
+	Err(ClientError::Backend("`rocksdb` feature not enabled, database can not be swept".into()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		tests::{insert_header, Block},
+		Backend, BlocksPruning, DatabaseSettings,
+	};
+	use sc_client_api::backend::{Backend as _, TrieCacheContext};
+	use sp_core::H256;
+	use sp_state_machine::Backend as _;
+
+	fn open(source: &DatabaseSource, state_pruning: Option<PruningMode>) -> Backend<Block> {
+		Backend::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: None,
+				state_pruning,
+				source: source.clone(),
+				blocks_pruning: BlocksPruning::KeepAll,
+				metrics_registry: None,
+			},
+			0,
+		)
+		.unwrap()
+	}
+
+	/// Prune an archive chain of 10 blocks finalized up to block #6, where the state of each
+	/// block adds the hash of its parent to the state of the parent.
+	fn prune_archive(source: DatabaseSource) {
+		let (hashes, roots) = {
+			let backend = open(&source, Some(PruningMode::ArchiveAll));
+			let mut hashes =
+				vec![insert_header(&backend, 0, Default::default(), None, Default::default())];
+			for number in 1..10 {
+				let parent = hashes[number as usize - 1];
+				hashes.push(insert_header(&backend, number, parent, None, Default::default()));
+			}
+			backend.finalize_block(hashes[6], None).unwrap();
+			let roots = hashes
+				.iter()
+				.map(|hash| backend.blockchain().header(*hash).unwrap().unwrap().state_root)
+				.collect::<Vec<_>>();
+			(hashes, roots)
+		};
+
+		prune_archive_state::<Block>(&source, PruningMode::blocks_pruning(4)).unwrap();
+
+		let backend = open(&source, None);
+		assert_eq!(backend.storage.state_db.pruning_mode(), PruningMode::blocks_pruning(4));
+		// the states of the finalized block and of the blocks above it are still readable
+		for number in 6..10 {
+			let state = backend.state_at(hashes[number], TrieCacheContext::Untrusted).unwrap();
+			for hash in &hashes[..number] {
+				assert_eq!(state.storage(hash.as_ref()).unwrap(), Some(hash.as_ref().to_vec()));
+			}
+		}
+		// only the nodes of the finalized state are kept in the state column, the ones of the
+		// blocks above it are moved to the non-canonical overlay
+		let has_node =
+			|root: &H256| backend.storage.db.get(columns::STATE, root.as_ref()).is_some();
+		assert!(has_node(&roots[6]));
+		for root in roots[..6].iter().chain(&roots[7..]) {
+			assert!(!has_node(root));
+		}
+		assert!(!source.path().unwrap().with_file_name(MARKS_DIRECTORY).exists());
+	}
+
+	#[test]
+	fn prunes_parity_db_archive() {
+		let temp_dir = tempfile::tempdir().unwrap();
+		prune_archive(DatabaseSource::ParityDb { path: temp_dir.path().join("db") });
+	}
+
+	#[cfg(feature = "rocksdb")]
+	#[test]
+	fn prunes_rocksdb_archive() {
+		let temp_dir = tempfile::tempdir().unwrap();
+		prune_archive(DatabaseSource::RocksDb { path: temp_dir.path().join("db"), cache_size: 16 });
+	}
+}
@@ -69,6 +69,12 @@ pub trait WasmInstance: Send {
 	fn call_export(&mut self, method: &str, data: &[u8]) -> Result<Vec<u8>, Error> {
 		self.call(method.into(), data)
 	}
+
+	/// Limit the number of instructions the following calls on this instance can execute.
+	///
+	/// Calls exceeding the `budget` fail with [`Error::ExecutionBudgetExceeded`]. `None` removes
+	/// the limit. This has no effect if the module wasn't created with execution metering.
+	fn set_execution_budget(&mut self, _budget: Option<u64>) {}
 }
 
 /// Defines the heap pages allocation strategy the wasm runtime should use.
@@ -0,0 +1,271 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk cache of the runtimes compiled by wasmtime.
+//!
+//! Compiling a runtime takes seconds, so the compiled artifacts are kept on disk to be loaded
+//! again after a restart. An artifact is stored in a file named after the hash of everything it
+//! depends on: the hash of the code, the version of wasmtime and the semantics the runtime is
+//! compiled with. The file starts with the BLAKE2-256 hash of the artifact, which is checked
+//! before the artifact is loaded.
+//!
+//! The hash only detects corrupted files. Anyone able to write to the directory can make the node
+//! run arbitrary native code, so it must be trusted.
+
+use crate::{error::WasmError, wasm_runtime::WasmExecutionMethod};
+
+use sc_executor_common::{runtime_blob::RuntimeBlob, wasm_runtime::HeapAllocStrategy};
+use sc_executor_wasmtime::{Config, WasmtimeRuntime, WASMTIME_VERSION};
+use sp_core::{hashing::blake2_256, hexdisplay::HexDisplay};
+use sp_wasm_interface::HostFunctions;
+
+use std::{
+	fs,
+	io::{self, Write},
+	path::{Path, PathBuf},
+};
+
+/// Version of the artifact files.
+///
+/// Must be bumped when the layout of the files or the semantics the runtimes are compiled with
+/// change.
+const ARTIFACT_VERSION: u32 = 1;
+
+/// Extension of the artifact files.
+const ARTIFACT_EXTENSION: &str = "artifact";
+
+/// Maximum number of artifacts kept on disk. The oldest ones are removed first.
+const MAX_ARTIFACTS: usize = 8;
+
+/// Parameters a compiled artifact depends on, besides the version of wasmtime.
+pub(crate) struct ArtifactKey<'a> {
+	/// Hash of the runtime code.
+	pub code_hash: &'a [u8],
+	/// Wasm execution method.
+	pub wasm_method: WasmExecutionMethod,
+	/// The heap allocation strategy the runtime is compiled with.
+	pub heap_alloc_strategy: HeapAllocStrategy,
+	/// Whether the execution of the runtime is metered.
+	pub fuel_metering: bool,
+}
+
+impl ArtifactKey<'_> {
+	fn file_name(&self) -> String {
+		let key = format!(
+			"{ARTIFACT_VERSION}:{WASMTIME_VERSION}:{}:{:?}:{:?}:{}",
+			HexDisplay::from(&self.code_hash),
+			self.wasm_method,
+			self.heap_alloc_strategy,
+			self.fuel_metering,
+		);
+		format!("{}.{ARTIFACT_EXTENSION}", HexDisplay::from(&blake2_256(key.as_bytes())))
+	}
+}
+
+/// A directory of compiled artifacts.
+pub(crate) struct ArtifactCache {
+	dir: PathBuf,
+}
+
+impl ArtifactCache {
+	/// Create a cache storing the artifacts in `dir`, which is created when needed.
+	pub fn new(dir: PathBuf) -> Self {
+		Self { dir }
+	}
+
+	/// Create the runtime from the artifact cached for `key`, compiling `blob` and caching the
+	/// artifact if there is none.
+	///
+	/// Artifacts that can't be loaded are compiled again.
+	pub fn load_or_compile<H>(
+		&self,
+		key: &ArtifactKey,
+		blob: RuntimeBlob,
+		config: Config,
+	) -> Result<WasmtimeRuntime, WasmError>
+	where
+		H: HostFunctions,
+	{
+		let path = self.dir.join(key.file_name());
+
+		match self.load::<H>(&path, config.clone()) {
+			Ok(Some(runtime)) => {
+				tracing::debug!(
+					target: "wasm-runtime",
+					path = %path.display(),
+					"Loaded compiled runtime from disk",
+				);
+				return Ok(runtime)
+			},
+			Ok(None) => {},
+			Err(error) => {
+				tracing::warn!(
+					target: "wasm-runtime",
+					path = %path.display(),
+					%error,
+					"Invalid compiled runtime on disk, compiling it again",
+				);
+				let _ = fs::remove_file(&path);
+			},
+		}
+
+		let artifact = sc_executor_wasmtime::prepare_runtime_artifact(blob, &config.semantics)?;
+		if let Err(error) = self.store(&path, &artifact) {
+			tracing::warn!(
+				target: "wasm-runtime",
+				path = %path.display(),
+				%error,
+				"Cannot store the compiled runtime on disk",
+			);
+		}
+
+		// SAFETY: The artifact was just produced by `prepare_runtime_artifact`.
+		unsafe { sc_executor_wasmtime::create_runtime_from_artifact_bytes::<H>(&artifact, config) }
+	}
+
+	/// Load the artifact at `path`, `None` if there is none.
+	fn load<H>(&self, path: &Path, config: Config) -> Result<Option<WasmtimeRuntime>, WasmError>
+	where
+		H: HostFunctions,
+	{
+		let file = match fs::read(path) {
+			Ok(file) => file,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(WasmError::Other(format!("cannot read the artifact: {e}"))),
+		};
+
+		if file.len() < 32 {
+			return Err(WasmError::Other("truncated artifact".into()))
+		}
+		let (hash, artifact) = file.split_at(32);
+		if blake2_256(artifact) != hash {
+			return Err(WasmError::Other("artifact hash mismatch".into()))
+		}
+
+		// SAFETY: The artifact is trusted to have been written by `store` after being produced by
+		//         `prepare_runtime_artifact`: the cache directory must only be writable by
+		//         trusted users. The hash only detects the corruption of the file, e.g. by a
+		//         crash or a disk failure, not its replacement by a malicious artifact.
+		//         Artifacts of another version of wasmtime or configuration are rejected by
+		//         wasmtime.
+		unsafe { sc_executor_wasmtime::create_runtime_from_artifact_bytes::<H>(artifact, config) }
+			.map(Some)
+	}
+
+	/// Write `artifact` to `path`, and remove the oldest artifacts above [`MAX_ARTIFACTS`].
+	fn store(&self, path: &Path, artifact: &[u8]) -> io::Result<()> {
+		fs::create_dir_all(&self.dir)?;
+
+		// Write to a temporary file first, so that a crash never leaves a partial artifact.
+		let tmp_path = path.with_extension("tmp");
+		let mut file = fs::File::create(&tmp_path)?;
+		file.write_all(&blake2_256(artifact))?;
+		file.write_all(artifact)?;
+		file.sync_all()?;
+		fs::rename(&tmp_path, path)?;
+
+		self.prune()
+	}
+
+	fn prune(&self) -> io::Result<()> {
+		let mut artifacts = Vec::new();
+		for entry in fs::read_dir(&self.dir)? {
+			let path = entry?.path();
+			if path.extension().is_some_and(|extension| extension == ARTIFACT_EXTENSION) {
+				artifacts.push((fs::metadata(&path)?.modified()?, path));
+			}
+		}
+
+		if artifacts.len() > MAX_ARTIFACTS {
+			artifacts.sort_unstable();
+			for (_, path) in &artifacts[..artifacts.len() - MAX_ARTIFACTS] {
+				fs::remove_file(path)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm_runtime::wasmtime_config;
+	use sc_executor_common::wasm_runtime::{WasmInstance, WasmModule, DEFAULT_HEAP_ALLOC_STRATEGY};
+	use sc_runtime_test::wasm_binary_unwrap;
+
+	type HostFunctions = sp_io::SubstrateHostFunctions;
+
+	fn key(code_hash: &[u8]) -> ArtifactKey {
+		ArtifactKey {
+			code_hash,
+			wasm_method: WasmExecutionMethod::default(),
+			heap_alloc_strategy: DEFAULT_HEAP_ALLOC_STRATEGY,
+			fuel_metering: false,
+		}
+	}
+
+	fn load_or_compile(cache: &ArtifactCache, code_hash: &[u8]) -> WasmtimeRuntime {
+		let blob = RuntimeBlob::uncompress_if_needed(wasm_binary_unwrap()).unwrap();
+		let config = wasmtime_config(
+			WasmExecutionMethod::default(),
+			DEFAULT_HEAP_ALLOC_STRATEGY,
+			false,
+			true,
+			None,
+		);
+		cache.load_or_compile::<HostFunctions>(&key(code_hash), blob, config).unwrap()
+	}
+
+	#[test]
+	fn artifacts_are_reused_and_corrupted_ones_replaced() {
+		let dir = tempfile::tempdir().unwrap();
+		let cache = ArtifactCache::new(dir.path().join("artifacts"));
+		let path = cache.dir.join(key(b"code").file_name());
+
+		load_or_compile(&cache, b"code");
+		let artifact = fs::read(&path).unwrap();
+
+		let runtime = load_or_compile(&cache, b"code");
+		runtime.new_instance().unwrap().call_export("test_empty_return", &[0]).unwrap();
+		assert_eq!(fs::read(&path).unwrap(), artifact);
+
+		let mut corrupted = artifact;
+		*corrupted.last_mut().unwrap() ^= 1;
+		fs::write(&path, &corrupted).unwrap();
+
+		let runtime = load_or_compile(&cache, b"code");
+		runtime.new_instance().unwrap().call_export("test_empty_return", &[0]).unwrap();
+		let artifact = fs::read(&path).unwrap();
+		assert_ne!(artifact, corrupted);
+		assert_eq!(&artifact[..32], blake2_256(&artifact[32..]));
+	}
+
+	#[test]
+	fn artifacts_are_pruned() {
+		let dir = tempfile::tempdir().unwrap();
+		let cache = ArtifactCache::new(dir.path().to_path_buf());
+
+		for i in 0..MAX_ARTIFACTS as u32 + 2 {
+			let path = cache.dir.join(key(&i.to_le_bytes()).file_name());
+			cache.store(&path, b"artifact").unwrap();
+		}
+
+		assert_eq!(fs::read_dir(dir.path()).unwrap().count(), MAX_ARTIFACTS);
+	}
+}
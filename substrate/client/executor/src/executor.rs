@@ -88,9 +88,11 @@ pub struct WasmExecutorBuilder<H = sp_io::SubstrateHostFunctions> {
 	method: WasmExecutionMethod,
 	onchain_heap_alloc_strategy: Option<HeapAllocStrategy>,
 	offchain_heap_alloc_strategy: Option<HeapAllocStrategy>,
+	offchain_execution_budget: Option<u64>,
 	ignore_onchain_heap_pages: bool,
 	max_runtime_instances: usize,
 	cache_path: Option<PathBuf>,
+	artifact_cache_path: Option<PathBuf>,
 	allow_missing_host_functions: bool,
 	runtime_cache_size: u8,
 }
@@ -105,11 +107,13 @@ impl<H> WasmExecutorBuilder<H> {
 			method: WasmExecutionMethod::default(),
 			onchain_heap_alloc_strategy: None,
 			offchain_heap_alloc_strategy: None,
+			offchain_execution_budget: None,
 			ignore_onchain_heap_pages: false,
 			max_runtime_instances: 2,
 			runtime_cache_size: 4,
 			allow_missing_host_functions: false,
 			cache_path: None,
+			artifact_cache_path: None,
 		}
 	}
 
@@ -139,6 +143,18 @@ impl<H> WasmExecutorBuilder<H> {
 		self
 	}
 
+	/// Create the wasm executor with the given execution `budget` for offchain runtime calls.
+	///
+	/// The execution of offchain calls is metered, and a call that consumes more than `budget`
+	/// units of fuel is aborted with [`Error::ExecutionBudgetExceeded`]. Onchain calls are never
+	/// metered.
+	///
+	/// By default offchain calls aren't metered.
+	pub fn with_offchain_execution_budget(mut self, budget: u64) -> Self {
+		self.offchain_execution_budget = Some(budget);
+		self
+	}
+
 	/// Create the wasm executor and follow/ignore onchain heap pages value.
 	///
 	/// By default this the onchain heap pages value is followed.
@@ -170,6 +186,21 @@ impl<H> WasmExecutorBuilder<H> {
 		self
 	}
 
+	/// Create the wasm executor with the given `artifact_cache_path`.
+	///
+	/// The runtimes compiled by the executor are stored in this directory, keyed by the hash of
+	/// their code, the version of wasmtime and the configuration they are compiled with. They are
+	/// loaded from there instead of being compiled again, e.g. after a restart.
+	///
+	/// The artifacts are native code loaded without validation, their hash only protects them
+	/// against corruption. The directory must only be writable by trusted users.
+	///
+	/// By default there is no `artifact_cache_path` given.
+	pub fn with_artifact_cache_path(mut self, artifact_cache_path: impl Into<PathBuf>) -> Self {
+		self.artifact_cache_path = Some(artifact_cache_path.into());
+		self
+	}
+
 	/// Create the wasm executor and allow/forbid missing host functions.
 	///
 	/// If missing host functions are forbidden, the instantiation of a wasm blob will fail
@@ -203,10 +234,12 @@ impl<H> WasmExecutorBuilder<H> {
 			default_onchain_heap_alloc_strategy: unwrap_heap_pages(
 				self.onchain_heap_alloc_strategy,
 			),
+			offchain_execution_budget: self.offchain_execution_budget,
 			ignore_onchain_heap_pages: self.ignore_onchain_heap_pages,
 			cache: Arc::new(RuntimeCache::new(
 				self.max_runtime_instances,
 				self.cache_path.clone(),
+				self.artifact_cache_path,
 				self.runtime_cache_size,
 			)),
 			cache_path: self.cache_path,
@@ -225,6 +258,8 @@ pub struct WasmExecutor<H = sp_io::SubstrateHostFunctions> {
 	default_onchain_heap_alloc_strategy: HeapAllocStrategy,
 	/// The heap allocation strategy for offchain Wasm calls.
 	default_offchain_heap_alloc_strategy: HeapAllocStrategy,
+	/// The execution budget of offchain Wasm calls, `None` if they aren't metered.
+	offchain_execution_budget: Option<u64>,
 	/// Ignore onchain heap pages value.
 	ignore_onchain_heap_pages: bool,
 	/// WASM runtime cache.
@@ -243,6 +278,7 @@ impl<H> Clone for WasmExecutor<H> {
 			method: self.method,
 			default_onchain_heap_alloc_strategy: self.default_onchain_heap_alloc_strategy,
 			default_offchain_heap_alloc_strategy: self.default_offchain_heap_alloc_strategy,
+			offchain_execution_budget: self.offchain_execution_budget,
 			ignore_onchain_heap_pages: self.ignore_onchain_heap_pages,
 			cache: self.cache.clone(),
 			cache_path: self.cache_path.clone(),
@@ -293,10 +329,12 @@ impl<H> WasmExecutor<H> {
 			default_offchain_heap_alloc_strategy: unwrap_heap_pages(
 				default_heap_pages.map(|h| HeapAllocStrategy::Static { extra_pages: h as _ }),
 			),
+			offchain_execution_budget: None,
 			ignore_onchain_heap_pages: false,
 			cache: Arc::new(RuntimeCache::new(
 				max_runtime_instances,
 				cache_path.clone(),
+				None,
 				runtime_cache_size,
 			)),
 			cache_path,
@@ -341,6 +379,28 @@ where
 		heap_alloc_strategy: HeapAllocStrategy,
 		f: F,
 	) -> Result<R>
+	where
+		F: FnOnce(
+			AssertUnwindSafe<&dyn WasmModule>,
+			AssertUnwindSafe<&mut dyn WasmInstance>,
+			Option<&RuntimeVersion>,
+			AssertUnwindSafe<&mut dyn Externalities>,
+		) -> Result<Result<R>>,
+	{
+		self.with_metered_instance(runtime_code, ext, heap_alloc_strategy, false, f)
+	}
+
+	/// Execute the given closure `f` with the latest runtime, metered if `fuel_metering` is set.
+	///
+	/// Metered and unmetered runtimes are cached separately.
+	fn with_metered_instance<R, F>(
+		&self,
+		runtime_code: &RuntimeCode,
+		ext: &mut dyn Externalities,
+		heap_alloc_strategy: HeapAllocStrategy,
+		fuel_metering: bool,
+		f: F,
+	) -> Result<R>
 	where
 		F: FnOnce(
 			AssertUnwindSafe<&dyn WasmModule>,
@@ -354,6 +414,7 @@ where
 			ext,
 			self.method,
 			heap_alloc_strategy,
+			fuel_metering,
 			self.allow_missing_host_functions,
 			|module, instance, version, ext| {
 				let module = AssertUnwindSafe(module);
@@ -427,6 +488,7 @@ where
 		let module = crate::wasm_runtime::create_wasm_runtime_with_code::<H>(
 			self.method,
 			self.default_onchain_heap_alloc_strategy,
+			false,
 			runtime_blob,
 			allow_missing_host_functions,
 			self.cache_path.as_deref(),
@@ -517,16 +579,19 @@ where
 				.unwrap_or_else(|| self.default_onchain_heap_alloc_strategy)
 		};
 
-		let heap_alloc_strategy = match context {
-			CallContext::Offchain => self.default_offchain_heap_alloc_strategy,
-			CallContext::Onchain => on_chain_heap_alloc_strategy,
+		let (heap_alloc_strategy, execution_budget) = match context {
+			CallContext::Offchain =>
+				(self.default_offchain_heap_alloc_strategy, self.offchain_execution_budget),
+			CallContext::Onchain => (on_chain_heap_alloc_strategy, None),
 		};
 
-		let result = self.with_instance(
+		let result = self.with_metered_instance(
 			runtime_code,
 			ext,
 			heap_alloc_strategy,
+			execution_budget.is_some(),
 			|_, mut instance, _on_chain_version, mut ext| {
+				instance.set_execution_budget(execution_budget);
 				with_externalities_safe(&mut **ext, move || instance.call_export(method, data))
 			},
 		);
@@ -30,6 +30,7 @@
 
 #![warn(missing_docs)]
 
+mod artifact_cache;
 #[macro_use]
 mod executor;
 #[cfg(test)]
@@ -21,7 +21,10 @@
 //! The primary means of accessing the runtimes is through a cache which saves the reusable
 //! components of the runtime that are expensive to initialize.
 
-use crate::error::{Error, WasmError};
+use crate::{
+	artifact_cache::{ArtifactCache, ArtifactKey},
+	error::{Error, WasmError},
+};
 
 use codec::Decode;
 use parking_lot::Mutex;
@@ -66,6 +69,8 @@ struct VersionedRuntimeId {
 	wasm_method: WasmExecutionMethod,
 	/// The heap allocation strategy this runtime was created with.
 	heap_alloc_strategy: HeapAllocStrategy,
+	/// Whether the execution of this runtime is metered.
+	fuel_metering: bool,
 }
 
 /// A Wasm runtime object along with its cached runtime version.
@@ -167,6 +172,8 @@ pub struct RuntimeCache {
 	/// The size of the instances cache for each runtime.
 	max_runtime_instances: usize,
 	cache_path: Option<PathBuf>,
+	/// The on-disk cache of compiled runtimes.
+	artifact_cache: Option<ArtifactCache>,
 }
 
 impl RuntimeCache {
@@ -178,15 +185,24 @@ impl RuntimeCache {
 	/// `cache_path` allows to specify an optional directory where the executor can store files
 	/// for caching.
 	///
+	/// `artifact_cache_path` allows to specify an optional directory where the compiled runtimes
+	/// are stored, to be loaded instead of compiled again after a restart.
+	///
 	/// `runtime_cache_size` specifies the number of different runtimes versions preserved in an
 	/// in-memory cache, must always be at least 1.
 	pub fn new(
 		max_runtime_instances: usize,
 		cache_path: Option<PathBuf>,
+		artifact_cache_path: Option<PathBuf>,
 		runtime_cache_size: u8,
 	) -> RuntimeCache {
 		let cap = ByLength::new(runtime_cache_size.max(1) as u32);
-		RuntimeCache { runtimes: Mutex::new(LruMap::new(cap)), max_runtime_instances, cache_path }
+		RuntimeCache {
+			runtimes: Mutex::new(LruMap::new(cap)),
+			max_runtime_instances,
+			cache_path,
+			artifact_cache: artifact_cache_path.map(ArtifactCache::new),
+		}
 	}
 
 	/// Prepares a WASM module instance and executes given function for it.
@@ -202,6 +218,8 @@ impl RuntimeCache {
 	///
 	/// `heap_alloc_strategy` - The heap allocation strategy to use.
 	///
+	/// `fuel_metering` - Meter the execution, so that it can be given an execution budget.
+	///
 	/// `allow_missing_func_imports` - Ignore missing function imports.
 	///
 	/// `f` - Function to execute.
@@ -221,6 +239,7 @@ impl RuntimeCache {
 		ext: &mut dyn Externalities,
 		wasm_method: WasmExecutionMethod,
 		heap_alloc_strategy: HeapAllocStrategy,
+		fuel_metering: bool,
 		allow_missing_func_imports: bool,
 		f: F,
 	) -> Result<Result<R, Error>, Error>
@@ -235,8 +254,12 @@ impl RuntimeCache {
 	{
 		let code_hash = &runtime_code.hash;
 
-		let versioned_runtime_id =
-			VersionedRuntimeId { code_hash: code_hash.clone(), heap_alloc_strategy, wasm_method };
+		let versioned_runtime_id = VersionedRuntimeId {
+			code_hash: code_hash.clone(),
+			heap_alloc_strategy,
+			wasm_method,
+			fuel_metering,
+		};
 
 		let mut runtimes = self.runtimes.lock(); // this must be released prior to calling f
 		let versioned_runtime = if let Some(versioned_runtime) = runtimes.get(&versioned_runtime_id)
@@ -249,12 +272,15 @@ impl RuntimeCache {
 
 			let result = create_versioned_wasm_runtime::<H>(
 				&code,
+				code_hash,
 				ext,
 				wasm_method,
 				heap_alloc_strategy,
+				fuel_metering,
 				allow_missing_func_imports,
 				self.max_runtime_instances,
 				self.cache_path.as_deref(),
+				self.artifact_cache.as_ref(),
 			);
 
 			match result {
@@ -287,9 +313,13 @@ impl RuntimeCache {
 }
 
 /// Create a wasm runtime with the given `code`.
+///
+/// If `fuel_metering` is set, the execution of the runtime is metered and its instances can be
+/// given an execution budget. It is ignored by PolkaVM runtimes.
 pub fn create_wasm_runtime_with_code<H>(
 	wasm_method: WasmExecutionMethod,
 	heap_alloc_strategy: HeapAllocStrategy,
+	fuel_metering: bool,
 	blob: RuntimeBlob,
 	allow_missing_func_imports: bool,
 	cache_path: Option<&Path>,
@@ -301,27 +331,41 @@ where
 		return sc_executor_polkavm::create_runtime::<H>(blob);
 	}
 
-	match wasm_method {
-		WasmExecutionMethod::Compiled { instantiation_strategy } =>
-			sc_executor_wasmtime::create_runtime::<H>(
-				blob,
-				sc_executor_wasmtime::Config {
-					allow_missing_func_imports,
-					cache_path: cache_path.map(ToOwned::to_owned),
-					semantics: sc_executor_wasmtime::Semantics {
-						heap_alloc_strategy,
-						instantiation_strategy,
-						deterministic_stack_limit: None,
-						canonicalize_nans: false,
-						parallel_compilation: true,
-						wasm_multi_value: false,
-						wasm_bulk_memory: false,
-						wasm_reference_types: false,
-						wasm_simd: false,
-					},
-				},
-			)
-			.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) }),
+	let config = wasmtime_config(
+		wasm_method,
+		heap_alloc_strategy,
+		fuel_metering,
+		allow_missing_func_imports,
+		cache_path,
+	);
+	sc_executor_wasmtime::create_runtime::<H>(blob, config)
+		.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) })
+}
+
+/// The wasmtime configuration the runtimes are compiled with.
+pub(crate) fn wasmtime_config(
+	wasm_method: WasmExecutionMethod,
+	heap_alloc_strategy: HeapAllocStrategy,
+	fuel_metering: bool,
+	allow_missing_func_imports: bool,
+	cache_path: Option<&Path>,
+) -> sc_executor_wasmtime::Config {
+	let WasmExecutionMethod::Compiled { instantiation_strategy } = wasm_method;
+	sc_executor_wasmtime::Config {
+		allow_missing_func_imports,
+		cache_path: cache_path.map(ToOwned::to_owned),
+		semantics: sc_executor_wasmtime::Semantics {
+			heap_alloc_strategy,
+			instantiation_strategy,
+			deterministic_stack_limit: None,
+			canonicalize_nans: false,
+			parallel_compilation: true,
+			wasm_multi_value: false,
+			wasm_bulk_memory: false,
+			wasm_reference_types: false,
+			wasm_simd: false,
+			fuel_metering,
+		},
 	}
 }
 
@@ -383,12 +427,15 @@ pub fn read_embedded_version(blob: &RuntimeBlob) -> Result<Option<RuntimeVersion
 
 fn create_versioned_wasm_runtime<H>(
 	code: &[u8],
+	code_hash: &[u8],
 	ext: &mut dyn Externalities,
 	wasm_method: WasmExecutionMethod,
 	heap_alloc_strategy: HeapAllocStrategy,
+	fuel_metering: bool,
 	allow_missing_func_imports: bool,
 	max_instances: usize,
 	cache_path: Option<&Path>,
+	artifact_cache: Option<&ArtifactCache>,
 ) -> Result<VersionedRuntime, WasmError>
 where
 	H: HostFunctions,
@@ -402,13 +449,27 @@ where
 	// runtime.
 	let mut version = read_embedded_version(&blob)?;
 
-	let runtime = create_wasm_runtime_with_code::<H>(
-		wasm_method,
-		heap_alloc_strategy,
-		blob,
-		allow_missing_func_imports,
-		cache_path,
-	)?;
+	let runtime: Box<dyn WasmModule> = match artifact_cache {
+		Some(artifact_cache) if blob.as_polkavm_blob().is_none() => {
+			let key = ArtifactKey { code_hash, wasm_method, heap_alloc_strategy, fuel_metering };
+			let config = wasmtime_config(
+				wasm_method,
+				heap_alloc_strategy,
+				fuel_metering,
+				allow_missing_func_imports,
+				cache_path,
+			);
+			Box::new(artifact_cache.load_or_compile::<H>(&key, blob, config)?)
+		},
+		_ => create_wasm_runtime_with_code::<H>(
+			wasm_method,
+			heap_alloc_strategy,
+			fuel_metering,
+			blob,
+			allow_missing_func_imports,
+			cache_path,
+		)?,
+	};
 
 	// If the runtime blob doesn't embed the runtime version then use the legacy version query
 	// mechanism: call the runtime.
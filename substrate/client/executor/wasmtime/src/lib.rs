@@ -45,3 +45,8 @@ pub use sc_executor_common::{
 	runtime_blob::RuntimeBlob,
 	wasm_runtime::{HeapAllocStrategy, WasmModule},
 };
+
+/// The version of wasmtime the runtimes are compiled with.
+///
+/// Artifacts produced by [`prepare_runtime_artifact`] can only be loaded by the same version.
+pub const WASMTIME_VERSION: &str = "8.0.1";
@@ -137,6 +137,7 @@ pub struct WasmtimeRuntime {
 	instance_pre: Arc<wasmtime::InstancePre<StoreData>>,
 	instantiation_strategy: InternalInstantiationStrategy,
 	instance_counter: Arc<InstanceCounter>,
+	fuel_metering: bool,
 }
 
 impl WasmModule for WasmtimeRuntime {
@@ -149,7 +150,11 @@ impl WasmModule for WasmtimeRuntime {
 			}),
 		};
 
-		Ok(Box::new(WasmtimeInstance { strategy }))
+		Ok(Box::new(WasmtimeInstance {
+			strategy,
+			fuel_metering: self.fuel_metering,
+			execution_budget: None,
+		}))
 	}
 }
 
@@ -157,6 +162,8 @@ impl WasmModule for WasmtimeRuntime {
 /// to execute the compiled code.
 pub struct WasmtimeInstance {
 	strategy: Strategy,
+	fuel_metering: bool,
+	execution_budget: Option<u64>,
 }
 
 impl WasmtimeInstance {
@@ -169,6 +176,12 @@ impl WasmtimeInstance {
 		match &mut self.strategy {
 			Strategy::RecreateInstance(ref mut instance_creator) => {
 				let mut instance_wrapper = instance_creator.instantiate()?;
+				if self.fuel_metering {
+					let fuel = self.execution_budget.unwrap_or(u64::MAX);
+					instance_wrapper.store_mut().add_fuel(fuel).map_err(|e| {
+						WasmError::Other(format!("cannot set the execution budget: {:#}", e))
+					})?;
+				}
 				let heap_base = instance_wrapper.extract_heap_base()?;
 				let entrypoint = instance_wrapper.resolve_entrypoint(method)?;
 				let allocator = FreeingBumpHeapAllocator::new(heap_base);
@@ -189,6 +202,10 @@ impl WasmInstance for WasmtimeInstance {
 		let result = self.call_impl(method, data, &mut allocation_stats);
 		(result, allocation_stats)
 	}
+
+	fn set_execution_budget(&mut self, budget: Option<u64>) {
+		self.execution_budget = budget;
+	}
 }
 
 /// Prepare a directory structure and a config file to enable wasmtime caching.
@@ -268,6 +285,8 @@ fn common_config(semantics: &Semantics) -> std::result::Result<wasmtime::Config,
 
 	config.parallel_compilation(semantics.parallel_compilation);
 
+	config.consume_fuel(semantics.fuel_metering);
+
 	// Be clear and specific about the extensions we support. If an update brings new features
 	// they should be introduced here as well.
 	config.wasm_reference_types(semantics.wasm_reference_types);
@@ -456,6 +475,12 @@ pub struct Semantics {
 
 	/// Enables WASM Fixed-Width SIMD proposal
 	pub wasm_simd: bool,
+
+	/// Meter the instructions executed by the runtime, so that the calls on its instances can be
+	/// given an execution budget with [`WasmInstance::set_execution_budget`].
+	///
+	/// The metering is deterministic, but slows down the execution.
+	pub fuel_metering: bool,
 }
 
 #[derive(Clone)]
@@ -636,6 +661,7 @@ where
 		instance_pre: Arc::new(instance_pre),
 		instantiation_strategy,
 		instance_counter: Default::default(),
+		fuel_metering: config.semantics.fuel_metering,
 	})
 }
 
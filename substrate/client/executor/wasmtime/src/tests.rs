@@ -70,6 +70,7 @@ struct RuntimeBuilder {
 	deterministic_stack: bool,
 	heap_pages: HeapAllocStrategy,
 	precompile_runtime: bool,
+	fuel_metering: bool,
 	tmpdir: Option<tempfile::TempDir>,
 }
 
@@ -82,6 +83,7 @@ impl RuntimeBuilder {
 			deterministic_stack: false,
 			heap_pages: DEFAULT_HEAP_ALLOC_STRATEGY,
 			precompile_runtime: false,
+			fuel_metering: false,
 			tmpdir: None,
 		}
 	}
@@ -111,6 +113,11 @@ impl RuntimeBuilder {
 		self
 	}
 
+	fn fuel_metering(mut self, fuel_metering: bool) -> Self {
+		self.fuel_metering = fuel_metering;
+		self
+	}
+
 	fn build(&mut self) -> impl WasmModule + '_ {
 		let blob = {
 			let wasm: Vec<u8>;
@@ -146,6 +153,7 @@ impl RuntimeBuilder {
 				wasm_bulk_memory: false,
 				wasm_reference_types: false,
 				wasm_simd: false,
+				fuel_metering: self.fuel_metering,
 			},
 		};
 
@@ -245,6 +253,27 @@ fn test_consume_over_1mb_of_stack_does_trap(instantiation_strategy: Instantiatio
 	}
 }
 
+test_wasm_execution!(test_execution_budget);
+fn test_execution_budget(instantiation_strategy: InstantiationStrategy) {
+	let wat = deep_call_stack_wat(1000);
+	let mut builder = RuntimeBuilder::new(instantiation_strategy).use_wat(wat).fuel_metering(true);
+	let runtime = builder.build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	instance.set_execution_budget(Some(100));
+	match instance.call_export("main", &[]).unwrap_err() {
+		Error::ExecutionBudgetExceeded => {},
+		error => panic!("unexpected error: {:?}", error),
+	}
+
+	// The budget applies to each call, and the instance can still be used afterwards.
+	instance.set_execution_budget(Some(1_000_000));
+	instance.call_export("main", &[]).unwrap();
+
+	instance.set_execution_budget(None);
+	instance.call_export("main", &[]).unwrap();
+}
+
 test_wasm_execution!(test_nan_canonicalization);
 fn test_nan_canonicalization(instantiation_strategy: InstantiationStrategy) {
 	let mut builder = RuntimeBuilder::new(instantiation_strategy).canonicalize_nans(true);
@@ -473,6 +502,7 @@ fn test_instances_without_reuse_are_not_leaked() {
 				wasm_bulk_memory: false,
 				wasm_reference_types: false,
 				wasm_simd: false,
+				fuel_metering: false,
 			},
 		},
 	)
@@ -489,6 +519,21 @@ fn test_instances_without_reuse_are_not_leaked() {
 	}
 }
 
+#[test]
+fn test_wasmtime_version_matches() {
+	let metadata = cargo_metadata::MetadataCommand::new().exec().unwrap();
+
+	let wasmtime = metadata.packages.iter().find(|pkg| pkg.name == "wasmtime").unwrap();
+	if wasmtime.version.to_string() != crate::WASMTIME_VERSION {
+		panic!(
+			"`WASMTIME_VERSION` ({0}) doesn't match the version of wasmtime ({1}); \
+				set it to '{1}' and try again",
+			crate::WASMTIME_VERSION,
+			wasmtime.version,
+		);
+	}
+}
+
 #[test]
 fn test_rustix_version_matches_with_wasmtime() {
 	let metadata = cargo_metadata::MetadataCommand::new().exec().unwrap();
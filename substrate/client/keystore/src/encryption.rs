@@ -0,0 +1,204 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Encryption at rest of the key files.
+//!
+//! The encryption key is derived from the encryption password using PBKDF2-HMAC-SHA256 with a
+//! random salt, which is stored with the KDF parameters in [`ENCRYPTION_FILE`] of the keystore.
+//! Every key file is encrypted using AES-256-GCM with a random nonce. The name of the key file
+//! is used as associated data, so that the content of a key file can not be moved to another
+//! one.
+
+use crate::{Error, Result};
+use aes_gcm::{
+	aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+	Aes256Gcm, Nonce,
+};
+use hmac::Hmac;
+use pbkdf2::pbkdf2;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sp_core::crypto::{ExposeSecret, SecretString};
+use std::{fs, path::Path};
+use zeroize::Zeroizing;
+
+/// Name of the file holding the encryption parameters of the keystore.
+///
+/// It is not a valid hex string, so it is never mistaken for a key file.
+pub(crate) const ENCRYPTION_FILE: &str = "encryption.json";
+
+/// Version of the encryption format.
+const VERSION: u32 = 1;
+
+/// Number of PBKDF2 rounds used for newly encrypted keystores.
+const KDF_ROUNDS: u32 = if cfg!(test) { 1_000 } else { 600_000 };
+
+/// Plaintext encrypted into the encryption parameters to check the password on unlock.
+const CHECK_PLAINTEXT: &[u8] = b"substrate-keystore";
+
+/// Data encrypted with the keystore encryption key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct EncryptedData {
+	nonce: String,
+	ciphertext: String,
+}
+
+/// Content of a key file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum KeyFile {
+	/// The secret uri stored in plaintext.
+	Plain(String),
+	/// The secret uri encrypted with the keystore encryption key.
+	Encrypted(EncryptedData),
+}
+
+/// Encryption parameters of the keystore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptionParams {
+	version: u32,
+	kdf_rounds: u32,
+	salt: String,
+	check: EncryptedData,
+}
+
+/// Cipher encrypting and decrypting the key files of an unlocked keystore.
+pub(crate) struct KeyCipher {
+	cipher: Aes256Gcm,
+}
+
+impl KeyCipher {
+	/// Derives the cipher from the password.
+	fn derive(password: &SecretString, salt: &[u8], rounds: u32) -> Result<Self> {
+		let mut key = Zeroizing::new([0u8; 32]);
+		pbkdf2::<Hmac<Sha256>>(password.expose_secret().as_bytes(), salt, rounds, &mut *key)
+			.map_err(|_| Error::InvalidEncryptionParams)?;
+		let cipher =
+			Aes256Gcm::new_from_slice(&*key).map_err(|_| Error::InvalidEncryptionParams)?;
+		Ok(Self { cipher })
+	}
+
+	/// Returns whether the keystore at `path` is encrypted.
+	pub(crate) fn is_encrypted(path: &Path) -> bool {
+		path.join(ENCRYPTION_FILE).exists()
+	}
+
+	/// Unlocks the encrypted keystore at `path` with the given password.
+	pub(crate) fn unlock(path: &Path, password: &SecretString) -> Result<Self> {
+		let file = fs::File::open(path.join(ENCRYPTION_FILE))?;
+		let params: EncryptionParams = serde_json::from_reader(&file)?;
+		if params.version != VERSION {
+			return Err(Error::InvalidEncryptionParams)
+		}
+
+		let salt =
+			array_bytes::hex2bytes(&params.salt).map_err(|_| Error::InvalidEncryptionParams)?;
+		let cipher = Self::derive(password, &salt, params.kdf_rounds)?;
+		match cipher.decrypt(ENCRYPTION_FILE, &params.check) {
+			Ok(check) if &check[..] == CHECK_PLAINTEXT => Ok(cipher),
+			_ => Err(Error::InvalidEncryptionPassword),
+		}
+	}
+
+	/// Sets up the encryption of the keystore at `path` with the given password.
+	///
+	/// The key files are not touched, they need to be encrypted separately.
+	pub(crate) fn initialize(path: &Path, password: &SecretString) -> Result<Self> {
+		let mut salt = [0u8; 32];
+		OsRng.fill_bytes(&mut salt);
+		let cipher = Self::derive(password, &salt, KDF_ROUNDS)?;
+
+		let params = EncryptionParams {
+			version: VERSION,
+			kdf_rounds: KDF_ROUNDS,
+			salt: array_bytes::bytes2hex("", salt),
+			check: cipher.encrypt(ENCRYPTION_FILE, CHECK_PLAINTEXT)?,
+		};
+		crate::local::write_atomically(&path.join(ENCRYPTION_FILE), |file| {
+			serde_json::to_writer(file, &params).map_err(Into::into)
+		})?;
+
+		Ok(cipher)
+	}
+
+	/// Encrypts `plaintext` stored in the file `name`.
+	pub(crate) fn encrypt(&self, name: &str, plaintext: &[u8]) -> Result<EncryptedData> {
+		let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+		let ciphertext = self
+			.cipher
+			.encrypt(&nonce, Payload { msg: plaintext, aad: name.as_bytes() })
+			.map_err(|_| Error::Encryption)?;
+
+		Ok(EncryptedData {
+			nonce: array_bytes::bytes2hex("", nonce),
+			ciphertext: array_bytes::bytes2hex("", ciphertext),
+		})
+	}
+
+	/// Decrypts `data` stored in the file `name`.
+	pub(crate) fn decrypt(&self, name: &str, data: &EncryptedData) -> Result<Zeroizing<Vec<u8>>> {
+		let nonce = array_bytes::hex2bytes(&data.nonce).map_err(|_| Error::Encryption)?;
+		if nonce.len() != 12 {
+			return Err(Error::Encryption)
+		}
+		let ciphertext = array_bytes::hex2bytes(&data.ciphertext).map_err(|_| Error::Encryption)?;
+
+		self.cipher
+			.decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: name.as_bytes() })
+			.map(Zeroizing::new)
+			.map_err(|_| Error::Encryption)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+	use tempfile::TempDir;
+
+	fn password(password: &str) -> SecretString {
+		FromStr::from_str(password).unwrap()
+	}
+
+	#[test]
+	fn unlock_checks_password() {
+		let temp_dir = TempDir::new().unwrap();
+		assert!(!KeyCipher::is_encrypted(temp_dir.path()));
+
+		let cipher = KeyCipher::initialize(temp_dir.path(), &password("secret")).unwrap();
+		assert!(KeyCipher::is_encrypted(temp_dir.path()));
+		let data = cipher.encrypt("key", b"//Alice").unwrap();
+
+		let cipher = KeyCipher::unlock(temp_dir.path(), &password("secret")).unwrap();
+		assert_eq!(&cipher.decrypt("key", &data).unwrap()[..], b"//Alice");
+
+		assert!(matches!(
+			KeyCipher::unlock(temp_dir.path(), &password("wrong")),
+			Err(Error::InvalidEncryptionPassword)
+		));
+	}
+
+	#[test]
+	fn ciphertext_is_bound_to_file_name() {
+		let temp_dir = TempDir::new().unwrap();
+		let cipher = KeyCipher::initialize(temp_dir.path(), &password("secret")).unwrap();
+
+		let data = cipher.encrypt("key", b"//Alice").unwrap();
+		assert!(matches!(cipher.decrypt("other", &data), Err(Error::Encryption)));
+	}
+}
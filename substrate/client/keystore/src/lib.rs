@@ -23,9 +23,13 @@ use sp_core::crypto::KeyTypeId;
 use sp_keystore::Error as TraitError;
 use std::io;
 
+mod encryption;
 /// Local keystore implementation
 mod local;
+/// Remote keystore implementation
+pub mod remote;
 pub use local::LocalKeystore;
+pub use remote::RemoteKeystore;
 pub use sp_keystore::Keystore;
 
 /// Keystore error.
@@ -55,6 +59,21 @@ pub enum Error {
 	/// Keystore unavailable
 	#[error("Keystore unavailable")]
 	Unavailable,
+	/// The keystore is encrypted, but no encryption password was given.
+	#[error("Keystore is encrypted, an encryption password is required to open it")]
+	Locked,
+	/// The keystore contains unencrypted keys, but an encryption password was given.
+	#[error("Keystore contains unencrypted keys, it needs to be encrypted first")]
+	NotEncrypted,
+	/// Invalid encryption password.
+	#[error("Invalid keystore encryption password")]
+	InvalidEncryptionPassword,
+	/// Invalid encryption parameters.
+	#[error("Invalid keystore encryption parameters")]
+	InvalidEncryptionParams,
+	/// A key file could not be encrypted or decrypted.
+	#[error("Key file could not be encrypted or decrypted, it is likely corrupted")]
+	Encryption,
 }
 
 /// Keystore Result
@@ -66,7 +85,11 @@ impl From<Error> for TraitError {
 			Error::KeyNotSupported(id) => TraitError::KeyNotSupported(id),
 			Error::InvalidSeed | Error::InvalidPhrase | Error::PublicKeyMismatch =>
 				TraitError::ValidationError(error.to_string()),
-			Error::Unavailable => TraitError::Unavailable,
+			Error::Unavailable | Error::Locked => TraitError::Unavailable,
+			Error::NotEncrypted |
+			Error::InvalidEncryptionPassword |
+			Error::InvalidEncryptionParams |
+			Error::Encryption => TraitError::Other(error.to_string()),
 			Error::Io(e) => TraitError::Other(e.to_string()),
 			Error::Json(e) => TraitError::Other(e.to_string()),
 		}
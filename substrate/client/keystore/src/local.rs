@@ -29,7 +29,7 @@ use std::{
 	collections::HashMap,
 	fs::{self, File},
 	io::Write,
-	path::PathBuf,
+	path::{Path, PathBuf},
 	sync::Arc,
 };
 
@@ -41,7 +41,10 @@ sp_keystore::bls_experimental_enabled! {
 use sp_core::{bls381, ecdsa_bls381, KeccakHasher, proof_of_possession::ProofOfPossessionGenerator};
 }
 
-use crate::{Error, Result};
+use crate::{
+	encryption::{KeyCipher, KeyFile},
+	Error, Result,
+};
 
 /// A local based keystore that is either memory-based or filesystem-based.
 pub struct LocalKeystore(RwLock<KeystoreInner>);
@@ -55,11 +58,39 @@ impl LocalKeystore {
 	/// NOTE: Even when passing a `password`, the keys on disk appear to look like normal secret
 	/// uris. However, without having the correct password the secret uri will not generate the
 	/// correct private key. See [`SecretUri`](sp_core::crypto::SecretUri) for more information.
+	///
+	/// Fails with [`Error::Locked`] if the keystore is encrypted, see
+	/// [`LocalKeystore::open_encrypted`].
 	pub fn open<T: Into<PathBuf>>(path: T, password: Option<SecretString>) -> Result<Self> {
 		let inner = KeystoreInner::open(path, password)?;
 		Ok(Self(RwLock::new(inner)))
 	}
 
+	/// Create a local keystore from filesystem, with the key files encrypted at rest.
+	///
+	/// The key files are encrypted using a key derived from `encryption_password`. The `password`
+	/// has the same meaning as in [`LocalKeystore::open`]. The encryption is set up if the
+	/// keystore does not contain any key yet, an existing unencrypted keystore has to be
+	/// migrated first using [`LocalKeystore::encrypt`].
+	pub fn open_encrypted<T: Into<PathBuf>>(
+		path: T,
+		password: Option<SecretString>,
+		encryption_password: &SecretString,
+	) -> Result<Self> {
+		let inner = KeystoreInner::open_encrypted(path, password, encryption_password)?;
+		Ok(Self(RwLock::new(inner)))
+	}
+
+	/// Encrypt the key files of the keystore at `path` in place.
+	///
+	/// Sets up the encryption of the keystore if it is not encrypted yet. Key files which are
+	/// already encrypted are left untouched, so an interrupted migration can be resumed.
+	///
+	/// Returns the number of encrypted key files.
+	pub fn encrypt<T: Into<PathBuf>>(path: T, encryption_password: &SecretString) -> Result<usize> {
+		KeystoreInner::encrypt(path, encryption_password)
+	}
+
 	/// Create a local keystore in memory.
 	pub fn in_memory() -> Self {
 		let inner = KeystoreInner::new_in_memory();
@@ -452,6 +483,8 @@ struct KeystoreInner {
 	/// Map over `(KeyTypeId, Raw public key)` -> `Key phrase/seed`
 	additional: HashMap<(KeyTypeId, Vec<u8>), String>,
 	password: Option<SecretString>,
+	/// The cipher of the key files, if the keystore is encrypted.
+	cipher: Option<KeyCipher>,
 }
 
 impl KeystoreInner {
@@ -461,8 +494,57 @@ impl KeystoreInner {
 	fn open<T: Into<PathBuf>>(path: T, password: Option<SecretString>) -> Result<Self> {
 		let path = path.into();
 		fs::create_dir_all(&path)?;
+		if KeyCipher::is_encrypted(&path) {
+			return Err(Error::Locked)
+		}
+
+		Ok(Self { path: Some(path), additional: HashMap::new(), password, cipher: None })
+	}
+
+	/// Open the encrypted store at the given path.
+	///
+	/// The encryption is set up if the store does not contain any key yet.
+	fn open_encrypted<T: Into<PathBuf>>(
+		path: T,
+		password: Option<SecretString>,
+		encryption_password: &SecretString,
+	) -> Result<Self> {
+		let path = path.into();
+		fs::create_dir_all(&path)?;
+
+		let cipher = if KeyCipher::is_encrypted(&path) {
+			KeyCipher::unlock(&path, encryption_password)?
+		} else if key_files(&path)?.is_empty() {
+			KeyCipher::initialize(&path, encryption_password)?
+		} else {
+			return Err(Error::NotEncrypted)
+		};
+
+		Ok(Self { path: Some(path), additional: HashMap::new(), password, cipher: Some(cipher) })
+	}
+
+	/// Encrypt the key files of the store at the given path in place.
+	fn encrypt<T: Into<PathBuf>>(path: T, encryption_password: &SecretString) -> Result<usize> {
+		let path = path.into();
+		fs::create_dir_all(&path)?;
 
-		Ok(Self { path: Some(path), additional: HashMap::new(), password })
+		let cipher = if KeyCipher::is_encrypted(&path) {
+			KeyCipher::unlock(&path, encryption_password)?
+		} else {
+			KeyCipher::initialize(&path, encryption_password)?
+		};
+		let store =
+			Self { path: None, additional: HashMap::new(), password: None, cipher: Some(cipher) };
+
+		let mut encrypted = 0;
+		for (file, _) in key_files(&path)? {
+			if let KeyFile::Plain(suri) = Self::read_key_file(&file)? {
+				store.write_to_file(file, &suri)?;
+				encrypted += 1;
+			}
+		}
+
+		Ok(encrypted)
 	}
 
 	/// Get the password for this store.
@@ -472,7 +554,7 @@ impl KeystoreInner {
 
 	/// Create a new in-memory store.
 	fn new_in_memory() -> Self {
-		Self { path: None, additional: HashMap::new(), password: None }
+		Self { path: None, additional: HashMap::new(), password: None, cipher: None }
 	}
 
 	/// Get the key phrase for the given public key and key type from the in-memory store.
@@ -499,7 +581,7 @@ impl KeystoreInner {
 	/// Places it into the file system store, if a path is configured.
 	fn insert(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<()> {
 		if let Some(path) = self.key_file_path(public, key_type) {
-			Self::write_to_file(path, suri)?;
+			self.write_to_file(path, suri)?;
 		}
 
 		Ok(())
@@ -512,7 +594,7 @@ impl KeystoreInner {
 	fn generate_by_type<Pair: CorePair>(&mut self, key_type: KeyTypeId) -> Result<Pair> {
 		let (pair, phrase, _) = Pair::generate_with_phrase(self.password());
 		if let Some(path) = self.key_file_path(pair.public().as_slice(), key_type) {
-			Self::write_to_file(path, &phrase)?;
+			self.write_to_file(path, &phrase)?;
 		} else {
 			self.insert_ephemeral_pair(&pair, &phrase, key_type);
 		}
@@ -520,19 +602,21 @@ impl KeystoreInner {
 		Ok(pair)
 	}
 
-	/// Write the given `data` to `file`.
-	fn write_to_file(file: PathBuf, data: &str) -> Result<()> {
-		let mut file = File::create(file)?;
+	/// Write the given `data` to `file`, encrypted if the store is encrypted.
+	fn write_to_file(&self, file: PathBuf, data: &str) -> Result<()> {
+		let content = match &self.cipher {
+			Some(cipher) =>
+				KeyFile::Encrypted(cipher.encrypt(&key_file_name(&file), data.as_bytes())?),
+			None => KeyFile::Plain(data.into()),
+		};
 
-		#[cfg(target_family = "unix")]
-		{
-			use std::os::unix::fs::PermissionsExt;
-			file.set_permissions(fs::Permissions::from_mode(0o600))?;
-		}
+		write_atomically(&file, |file| serde_json::to_writer(file, &content).map_err(Into::into))
+	}
 
-		serde_json::to_writer(&file, data)?;
-		file.flush()?;
-		Ok(())
+	/// Read the content of the given key `file`.
+	fn read_key_file(file: &Path) -> Result<KeyFile> {
+		let file = File::open(file)?;
+		serde_json::from_reader(&file).map_err(Into::into)
 	}
 
 	/// Create a new key from seed.
@@ -560,12 +644,20 @@ impl KeystoreInner {
 			return Ok(None)
 		};
 
-		if path.exists() {
-			let file = File::open(path)?;
+		if !path.exists() {
+			return Ok(None)
+		}
 
-			serde_json::from_reader(&file).map_err(Into::into).map(Some)
-		} else {
-			Ok(None)
+		match Self::read_key_file(&path)? {
+			// A plain key file must not end up in an encrypted store, it needs to be encrypted with
+			// the others.
+			KeyFile::Plain(_) if self.cipher.is_some() => Err(Error::NotEncrypted),
+			KeyFile::Plain(phrase) => Ok(Some(phrase)),
+			KeyFile::Encrypted(data) => {
+				let cipher = self.cipher.as_ref().ok_or(Error::Locked)?;
+				let phrase = cipher.decrypt(&key_file_name(&path), &data)?;
+				String::from_utf8(phrase.to_vec()).map(Some).map_err(|_| Error::Encryption)
+			},
 		}
 	}
 
@@ -611,24 +703,11 @@ impl KeystoreInner {
 			.collect();
 
 		if let Some(path) = &self.path {
-			for entry in fs::read_dir(&path)? {
-				let entry = entry?;
-				let path = entry.path();
-
-				// skip directories and non-unicode file names (hex is unicode)
-				if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-					match array_bytes::hex2bytes(name) {
-						Ok(ref hex) if hex.len() > 4 => {
-							if hex[0..4] != key_type.0 {
-								continue
-							}
-							let public = hex[4..].to_vec();
-							public_keys.push(public);
-						},
-						_ => continue,
-					}
-				}
-			}
+			public_keys.extend(
+				key_files(path)?
+					.into_iter()
+					.filter_map(|(_, hex)| (hex[0..4] == key_type.0).then(|| hex[4..].to_vec())),
+			);
 		}
 
 		Ok(public_keys)
@@ -647,6 +726,52 @@ impl KeystoreInner {
 	}
 }
 
+/// Returns the key files of the store at `path`, along with their decoded names.
+fn key_files(path: &Path) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+	let mut files = Vec::new();
+	for entry in fs::read_dir(path)? {
+		let path = entry?.path();
+
+		// skip directories and non-unicode file names (hex is unicode)
+		if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+			match array_bytes::hex2bytes(name) {
+				Ok(hex) if hex.len() > 4 => files.push((path, hex)),
+				_ => continue,
+			}
+		}
+	}
+
+	Ok(files)
+}
+
+/// Returns the name of the given key file, which is authenticated along with its content.
+fn key_file_name(file: &Path) -> String {
+	file.file_name()
+		.map(|name| name.to_string_lossy().into_owned())
+		.unwrap_or_default()
+}
+
+/// Replace `file` with the content written by `write`.
+///
+/// The content is written to a temporary file first, so that `file` is never left partially
+/// written.
+pub(crate) fn write_atomically(file: &Path, write: impl FnOnce(&File) -> Result<()>) -> Result<()> {
+	let tmp_file = file.with_extension("tmp");
+	let mut tmp = File::create(&tmp_file)?;
+
+	#[cfg(target_family = "unix")]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		tmp.set_permissions(fs::Permissions::from_mode(0o600))?;
+	}
+
+	write(&tmp)?;
+	tmp.flush()?;
+	tmp.sync_all()?;
+	fs::rename(&tmp_file, file)?;
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -842,6 +967,97 @@ mod tests {
 		assert_eq!(store.sr25519_public_keys(TEST_KEY_TYPE).len(), 2);
 	}
 
+	#[test]
+	fn encrypted_store_works() {
+		let temp_dir = TempDir::new().unwrap();
+		let encryption_password = FromStr::from_str("encryption").unwrap();
+		let mut store =
+			KeystoreInner::open_encrypted(temp_dir.path(), None, &encryption_password).unwrap();
+
+		let pair: ed25519::AppPair = store.generate().unwrap();
+		let path = store.key_file_path(pair.public().as_slice(), ed25519::AppPair::ID).unwrap();
+		assert!(matches!(KeystoreInner::read_key_file(&path).unwrap(), KeyFile::Encrypted(_)));
+
+		let store =
+			KeystoreInner::open_encrypted(temp_dir.path(), None, &encryption_password).unwrap();
+		assert_eq!(
+			pair.public(),
+			store.key_pair::<ed25519::AppPair>(&pair.public()).unwrap().unwrap().public(),
+		);
+
+		assert!(matches!(
+			KeystoreInner::open_encrypted(
+				temp_dir.path(),
+				None,
+				&FromStr::from_str("wrong").unwrap()
+			),
+			Err(Error::InvalidEncryptionPassword)
+		));
+		assert!(matches!(KeystoreInner::open(temp_dir.path(), None), Err(Error::Locked)));
+	}
+
+	#[test]
+	fn plain_key_in_encrypted_store_is_rejected() {
+		let temp_dir = TempDir::new().unwrap();
+		let encryption_password = FromStr::from_str("encryption").unwrap();
+
+		let mut store = KeystoreInner::open(temp_dir.path(), None).unwrap();
+		let pair: ed25519::AppPair = store.generate().unwrap();
+
+		// The encryption is set up, but the existing key isn't migrated.
+		KeyCipher::initialize(temp_dir.path(), &encryption_password).unwrap();
+		let store =
+			KeystoreInner::open_encrypted(temp_dir.path(), None, &encryption_password).unwrap();
+		assert!(matches!(
+			store.key_pair::<ed25519::AppPair>(&pair.public()),
+			Err(Error::NotEncrypted)
+		));
+
+		assert_eq!(LocalKeystore::encrypt(temp_dir.path(), &encryption_password).unwrap(), 1);
+		let store =
+			KeystoreInner::open_encrypted(temp_dir.path(), None, &encryption_password).unwrap();
+		assert!(store.key_pair::<ed25519::AppPair>(&pair.public()).unwrap().is_some());
+	}
+
+	#[test]
+	fn encrypt_migrates_existing_keys() {
+		let temp_dir = TempDir::new().unwrap();
+		let encryption_password = FromStr::from_str("encryption").unwrap();
+
+		let mut store = KeystoreInner::open(temp_dir.path(), None).unwrap();
+		let ed25519_pair: ed25519::AppPair = store.generate().unwrap();
+		let sr25519_pair: sr25519::AppPair = store.generate().unwrap();
+
+		assert!(matches!(
+			KeystoreInner::open_encrypted(temp_dir.path(), None, &encryption_password),
+			Err(Error::NotEncrypted)
+		));
+
+		assert_eq!(LocalKeystore::encrypt(temp_dir.path(), &encryption_password).unwrap(), 2);
+		// already encrypted keys are skipped
+		assert_eq!(LocalKeystore::encrypt(temp_dir.path(), &encryption_password).unwrap(), 0);
+
+		let store =
+			KeystoreInner::open_encrypted(temp_dir.path(), None, &encryption_password).unwrap();
+		assert_eq!(
+			ed25519_pair.public(),
+			store
+				.key_pair::<ed25519::AppPair>(&ed25519_pair.public())
+				.unwrap()
+				.unwrap()
+				.public(),
+		);
+		assert_eq!(
+			sr25519_pair.public(),
+			store
+				.key_pair::<sr25519::AppPair>(&sr25519_pair.public())
+				.unwrap()
+				.unwrap()
+				.public(),
+		);
+		assert_eq!(store.public_keys::<sr25519::AppPublic>().unwrap(), vec![sr25519_pair.public()]);
+	}
+
 	#[test]
 	#[cfg(target_family = "unix")]
 	fn uses_correct_file_permissions_on_unix() {
@@ -0,0 +1,654 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Keystore forwarding the key operations to a remote signer.
+//!
+//! The [`RemoteKeystore`] holds no secret. Every key operation is sent to a remote signer
+//! process, for example a [`Signer`] running on a separate host, using the protocol described
+//! in [`protocol`].
+//!
+//! Only the sr25519, ed25519 and ecdsa schemes are supported. The sr25519 VRF signatures are
+//! supported for the BABE slot claims, which are signed with
+//! [`Keystore::sr25519_vrf_sign_transcript`]: the inputs of the transcript are sent to the
+//! [`Signer`], which knows how to rebuild it. Opaque transcripts can't be sent, so
+//! [`Keystore::sr25519_vrf_sign`] and [`Keystore::sr25519_vrf_pre_output`] are not supported.
+//! Neither are bandersnatch and BLS keys.
+
+pub mod protocol;
+pub mod signer;
+mod transport;
+
+pub use signer::Signer;
+pub use transport::Endpoint;
+
+use protocol::{Request, Response, Scheme, WireKeyType, WireTranscript};
+use serde::de::DeserializeOwned;
+use sp_core::{
+	crypto::{ByteArray, ExposeSecret, KeyTypeId, SecretString},
+	ecdsa, ed25519, sr25519, Bytes, Decode,
+};
+use sp_keystore::{Error as TraitError, Keystore, KeystorePtr};
+use std::{
+	io::{self, BufRead, BufReader, Write},
+	net::TcpStream,
+	sync::{mpsc, Arc},
+	thread,
+	time::{Duration, Instant},
+};
+use transport::{read_http_message, write_http_request};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+sp_keystore::bandersnatch_experimental_enabled! {
+use sp_core::bandersnatch;
+}
+
+sp_keystore::bls_experimental_enabled! {
+use sp_core::{bls381, ecdsa_bls381};
+}
+
+/// Time a key operation waits for the answer of the remote signer.
+///
+/// The [`Keystore`] methods are synchronous but called from async tasks, so it is kept short.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A request waiting to be sent to the remote signer.
+struct PendingRequest {
+	body: Vec<u8>,
+	/// Nobody waits for the answer after this instant, so the request is dropped if not sent yet.
+	deadline: Instant,
+	response: mpsc::SyncSender<io::Result<Vec<u8>>>,
+}
+
+/// A keystore forwarding the key operations to a remote signer.
+///
+/// The requests are sent by a dedicated thread, so that a slow or unreachable signer delays a
+/// key operation by [`REQUEST_TIMEOUT`] at most.
+pub struct RemoteKeystore {
+	endpoint: Endpoint,
+	requests: mpsc::Sender<PendingRequest>,
+}
+
+impl RemoteKeystore {
+	/// Create a keystore forwarding the key operations to the signer at `endpoint`.
+	///
+	/// The HTTP requests carry `auth_token` if given. The signer is only connected to on the
+	/// first request.
+	pub fn new(endpoint: Endpoint, auth_token: Option<SecretString>) -> Self {
+		let (requests, receiver) = mpsc::channel();
+		let connection = Connection {
+			endpoint: endpoint.clone(),
+			auth_token,
+			#[cfg(unix)]
+			unix: None,
+		};
+		thread::Builder::new()
+			.name("remote-keystore".into())
+			.spawn(move || connection.run(receiver))
+			.expect("Spawning the remote keystore thread only fails when out of resources; qed");
+
+		Self { endpoint, requests }
+	}
+
+	/// Send `request` to the remote signer and decode its result.
+	fn call<T: DeserializeOwned>(&self, request: Request) -> Result<T, TraitError> {
+		let mut body =
+			serde_json::to_vec(&request).map_err(|e| TraitError::Other(e.to_string()))?;
+		body.push(b'\n');
+
+		let response = self.send(body).map_err(|e| {
+			TraitError::Other(format!("Remote signer at {} is unavailable: {e}", self.endpoint))
+		})?;
+
+		match serde_json::from_slice(&response) {
+			Ok(Response::Result(result)) =>
+				serde_json::from_value(result).map_err(|e| TraitError::Other(e.to_string())),
+			Ok(Response::Error(error)) => Err(TraitError::Other(error)),
+			Err(error) =>
+				Err(TraitError::Other(format!("Invalid remote signer response: {error}"))),
+		}
+	}
+
+	/// Hand `body` over to the connection thread and wait for the response.
+	fn send(&self, body: Vec<u8>) -> io::Result<Vec<u8>> {
+		let (response, receiver) = mpsc::sync_channel(1);
+		let deadline = Instant::now() + REQUEST_TIMEOUT;
+		self.requests
+			.send(PendingRequest { body, deadline, response })
+			.map_err(|_| io::Error::other("The connection thread stopped"))?;
+
+		match receiver.recv_timeout(REQUEST_TIMEOUT) {
+			Ok(response) => response,
+			Err(mpsc::RecvTimeoutError::Timeout) =>
+				Err(io::Error::new(io::ErrorKind::TimedOut, "Request timed out")),
+			Err(mpsc::RecvTimeoutError::Disconnected) =>
+				Err(io::Error::other("The connection thread stopped")),
+		}
+	}
+
+	fn public_keys<T: ByteArray>(&self, scheme: Scheme, key_type: KeyTypeId) -> Vec<T> {
+		self.call::<Vec<Bytes>>(Request::PublicKeys { scheme, key_type: WireKeyType(key_type) })
+			.map(|keys| keys.into_iter().filter_map(|k| T::from_slice(&k).ok()).collect())
+			.unwrap_or_default()
+	}
+
+	fn generate_new<T: ByteArray>(
+		&self,
+		scheme: Scheme,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<T, TraitError> {
+		let public = self.call::<Bytes>(Request::GenerateNew {
+			scheme,
+			key_type: WireKeyType(key_type),
+			seed: seed.map(Into::into),
+		})?;
+		T::from_slice(&public).map_err(|_| TraitError::Other("Invalid public key".into()))
+	}
+
+	fn sign<S: ByteArray>(
+		&self,
+		scheme: Scheme,
+		key_type: KeyTypeId,
+		public: &impl ByteArray,
+		msg: &[u8],
+	) -> Result<Option<S>, TraitError> {
+		let signature = self.call::<Option<Bytes>>(Request::Sign {
+			scheme,
+			key_type: WireKeyType(key_type),
+			public: public.to_raw_vec().into(),
+			message: msg.to_vec().into(),
+		})?;
+		signature
+			.map(|s| S::from_slice(&s).map_err(|_| TraitError::Other("Invalid signature".into())))
+			.transpose()
+	}
+
+	/// Send a VRF `request` and decode its SCALE encoded result.
+	fn call_vrf<T: Decode>(&self, request: Request) -> Result<Option<T>, TraitError> {
+		self.call::<Option<Bytes>>(request)?
+			.map(|result| {
+				T::decode(&mut &result[..])
+					.map_err(|_| TraitError::Other("Invalid VRF output".into()))
+			})
+			.transpose()
+	}
+
+	fn unsupported<T>(&self, operation: &str) -> Result<T, TraitError> {
+		Err(TraitError::Other(format!("{operation} is not supported by the remote keystore")))
+	}
+}
+
+/// Connection to the remote signer, owned by the thread sending the requests.
+struct Connection {
+	endpoint: Endpoint,
+	/// Token authenticating the HTTP requests.
+	auth_token: Option<SecretString>,
+	/// Connection to the Unix socket, kept open between requests.
+	#[cfg(unix)]
+	unix: Option<BufReader<UnixStream>>,
+}
+
+impl Connection {
+	/// Send the requests until the keystore is dropped.
+	fn run(mut self, requests: mpsc::Receiver<PendingRequest>) {
+		for request in requests {
+			if Instant::now() >= request.deadline {
+				continue
+			}
+			let response = match &self.endpoint {
+				Endpoint::Unix(_) => self.call_unix(&request.body),
+				Endpoint::Http { address, path } => self.call_http(address, path, &request.body),
+			};
+			let _ = request.response.send(response);
+		}
+	}
+
+	/// Send `body` over the Unix socket, reconnecting once if the connection was lost.
+	#[cfg(unix)]
+	fn call_unix(&mut self, body: &[u8]) -> io::Result<Vec<u8>> {
+		let Endpoint::Unix(path) = &self.endpoint else {
+			unreachable!("Only called for Unix socket endpoints; qed")
+		};
+
+		let mut retried = false;
+		loop {
+			if self.unix.is_none() {
+				let stream = UnixStream::connect(path)?;
+				stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+				stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+				self.unix = Some(BufReader::new(stream));
+			}
+			let reader = self.unix.as_mut().expect("Connected above; qed");
+
+			let result = reader.get_mut().write_all(body).and_then(|_| {
+				let mut response = Vec::new();
+				match reader.read_until(b'\n', &mut response)? {
+					0 => Err(io::ErrorKind::UnexpectedEof.into()),
+					_ => Ok(response),
+				}
+			});
+
+			match result {
+				Ok(response) => return Ok(response),
+				Err(error) => {
+					self.unix = None;
+					if retried {
+						return Err(error)
+					}
+					retried = true;
+				},
+			}
+		}
+	}
+
+	#[cfg(not(unix))]
+	fn call_unix(&mut self, _: &[u8]) -> io::Result<Vec<u8>> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "Unix sockets are not supported"))
+	}
+
+	/// Post `body` to the HTTP server at `address`.
+	fn call_http(&self, address: &str, path: &str, body: &[u8]) -> io::Result<Vec<u8>> {
+		let stream = TcpStream::connect(address)?;
+		stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+		stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+		let auth_token = self.auth_token.as_ref().map(|token| token.expose_secret().as_str());
+		write_http_request(&mut &stream, address, path, auth_token, body)?;
+		let response = read_http_message(&mut BufReader::new(&stream))?;
+		if response.start_line.split_whitespace().nth(1) != Some("200") {
+			return Err(io::Error::new(io::ErrorKind::Other, response.start_line))
+		}
+
+		Ok(response.body)
+	}
+}
+
+impl Keystore for RemoteKeystore {
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(Scheme::Sr25519, key_type)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, TraitError> {
+		self.generate_new(Scheme::Sr25519, key_type, seed)
+	}
+
+	fn sr25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		msg: &[u8],
+	) -> Result<Option<sr25519::Signature>, TraitError> {
+		self.sign(Scheme::Sr25519, key_type, public, msg)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		_: KeyTypeId,
+		_: &sr25519::Public,
+		_: &sr25519::vrf::VrfSignData,
+	) -> Result<Option<sr25519::vrf::VrfSignature>, TraitError> {
+		self.unsupported("VRF signing of an opaque transcript")
+	}
+
+	fn sr25519_vrf_sign_transcript(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		label: &'static [u8],
+		items: &[(&'static [u8], &[u8])],
+	) -> Result<Option<sr25519::vrf::VrfSignature>, TraitError> {
+		self.call_vrf(Request::Sr25519VrfSign {
+			key_type: WireKeyType(key_type),
+			public: public.to_raw_vec().into(),
+			input: WireTranscript::new(label, items),
+		})
+	}
+
+	fn sr25519_vrf_pre_output(
+		&self,
+		_: KeyTypeId,
+		_: &sr25519::Public,
+		_: &sr25519::vrf::VrfInput,
+	) -> Result<Option<sr25519::vrf::VrfPreOutput>, TraitError> {
+		self.unsupported("VRF pre-output of an opaque transcript")
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(Scheme::Ed25519, key_type)
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, TraitError> {
+		self.generate_new(Scheme::Ed25519, key_type, seed)
+	}
+
+	fn ed25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ed25519::Public,
+		msg: &[u8],
+	) -> Result<Option<ed25519::Signature>, TraitError> {
+		self.sign(Scheme::Ed25519, key_type, public, msg)
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(Scheme::Ecdsa, key_type)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, TraitError> {
+		self.generate_new(Scheme::Ecdsa, key_type, seed)
+	}
+
+	fn ecdsa_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8],
+	) -> Result<Option<ecdsa::Signature>, TraitError> {
+		self.sign(Scheme::Ecdsa, key_type, public, msg)
+	}
+
+	fn ecdsa_sign_prehashed(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, TraitError> {
+		let signature = self.call::<Option<Bytes>>(Request::SignPrehashed {
+			key_type: WireKeyType(key_type),
+			public: public.to_raw_vec().into(),
+			message: msg.to_vec().into(),
+		})?;
+		signature
+			.map(|s| {
+				ecdsa::Signature::from_slice(&s)
+					.map_err(|_| TraitError::Other("Invalid signature".into()))
+			})
+			.transpose()
+	}
+
+	sp_keystore::bandersnatch_experimental_enabled! {
+		fn bandersnatch_public_keys(&self, _: KeyTypeId) -> Vec<bandersnatch::Public> {
+			Vec::new()
+		}
+
+		fn bandersnatch_generate_new(
+			&self,
+			_: KeyTypeId,
+			_: Option<&str>,
+		) -> Result<bandersnatch::Public, TraitError> {
+			self.unsupported("Bandersnatch")
+		}
+
+		fn bandersnatch_sign(
+			&self,
+			_: KeyTypeId,
+			_: &bandersnatch::Public,
+			_: &[u8],
+		) -> Result<Option<bandersnatch::Signature>, TraitError> {
+			self.unsupported("Bandersnatch")
+		}
+
+		fn bandersnatch_vrf_sign(
+			&self,
+			_: KeyTypeId,
+			_: &bandersnatch::Public,
+			_: &bandersnatch::vrf::VrfSignData,
+		) -> Result<Option<bandersnatch::vrf::VrfSignature>, TraitError> {
+			self.unsupported("Bandersnatch")
+		}
+
+		fn bandersnatch_vrf_pre_output(
+			&self,
+			_: KeyTypeId,
+			_: &bandersnatch::Public,
+			_: &bandersnatch::vrf::VrfInput,
+		) -> Result<Option<bandersnatch::vrf::VrfPreOutput>, TraitError> {
+			self.unsupported("Bandersnatch")
+		}
+
+		fn bandersnatch_ring_vrf_sign(
+			&self,
+			_: KeyTypeId,
+			_: &bandersnatch::Public,
+			_: &bandersnatch::vrf::VrfSignData,
+			_: &bandersnatch::ring_vrf::RingProver,
+		) -> Result<Option<bandersnatch::ring_vrf::RingVrfSignature>, TraitError> {
+			self.unsupported("Bandersnatch")
+		}
+	}
+
+	sp_keystore::bls_experimental_enabled! {
+		fn bls381_public_keys(&self, _: KeyTypeId) -> Vec<bls381::Public> {
+			Vec::new()
+		}
+
+		fn bls381_generate_new(
+			&self,
+			_: KeyTypeId,
+			_: Option<&str>,
+		) -> Result<bls381::Public, TraitError> {
+			self.unsupported("BLS")
+		}
+
+		fn bls381_sign(
+			&self,
+			_: KeyTypeId,
+			_: &bls381::Public,
+			_: &[u8],
+		) -> Result<Option<bls381::Signature>, TraitError> {
+			self.unsupported("BLS")
+		}
+
+		fn bls381_generate_proof_of_possession(
+			&self,
+			_: KeyTypeId,
+			_: &bls381::Public,
+		) -> Result<Option<bls381::Signature>, TraitError> {
+			self.unsupported("BLS")
+		}
+
+		fn ecdsa_bls381_public_keys(&self, _: KeyTypeId) -> Vec<ecdsa_bls381::Public> {
+			Vec::new()
+		}
+
+		fn ecdsa_bls381_generate_new(
+			&self,
+			_: KeyTypeId,
+			_: Option<&str>,
+		) -> Result<ecdsa_bls381::Public, TraitError> {
+			self.unsupported("BLS")
+		}
+
+		fn ecdsa_bls381_sign(
+			&self,
+			_: KeyTypeId,
+			_: &ecdsa_bls381::Public,
+			_: &[u8],
+		) -> Result<Option<ecdsa_bls381::Signature>, TraitError> {
+			self.unsupported("BLS")
+		}
+
+		fn ecdsa_bls381_sign_with_keccak256(
+			&self,
+			_: KeyTypeId,
+			_: &ecdsa_bls381::Public,
+			_: &[u8],
+		) -> Result<Option<ecdsa_bls381::Signature>, TraitError> {
+			self.unsupported("BLS")
+		}
+	}
+
+	fn insert(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.call::<()>(Request::Insert {
+			key_type: WireKeyType(key_type),
+			suri: suri.into(),
+			public: public.to_vec().into(),
+		})
+		.map_err(|_| ())
+	}
+
+	fn keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>, TraitError> {
+		self.call::<Vec<Bytes>>(Request::Keys { key_type: WireKeyType(key_type) })
+			.map(|keys| keys.into_iter().map(|k| k.0).collect())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		self.call::<bool>(Request::HasKeys {
+			keys: public_keys
+				.iter()
+				.map(|(public, key_type)| (public.clone().into(), WireKeyType(*key_type)))
+				.collect(),
+		})
+		.unwrap_or(false)
+	}
+}
+
+impl Into<KeystorePtr> for RemoteKeystore {
+	fn into(self) -> KeystorePtr {
+		Arc::new(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::LocalKeystore;
+	use sp_core::{
+		crypto::{key_types::BABE, VrfPublic},
+		sr25519::vrf::VrfTranscript,
+		Pair,
+	};
+	use std::{net::TcpListener, thread};
+
+	const TEST: KeyTypeId = KeyTypeId(*b"test");
+
+	fn check_signing(keystore: RemoteKeystore) {
+		let public = keystore.sr25519_generate_new(TEST, None).unwrap();
+		assert_eq!(keystore.sr25519_public_keys(TEST), vec![public]);
+		assert!(keystore.has_keys(&[(public.to_raw_vec(), TEST)]));
+
+		let signature = keystore.sr25519_sign(TEST, &public, b"message").unwrap().unwrap();
+		assert!(sr25519::Pair::verify(&signature, b"message", &public));
+
+		// unknown keys are reported as such
+		let unknown = sr25519::Pair::from_string("//Unknown", None).unwrap().public();
+		assert_eq!(keystore.sr25519_sign(TEST, &unknown, b"message").unwrap(), None);
+		assert!(keystore.sr25519_generate_new(TEST, Some("invalid seed")).is_err());
+	}
+
+	fn serve_http(auth_token: Option<&str>) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap().to_string();
+		let signer = Arc::new(Signer::new(Arc::new(LocalKeystore::in_memory()), None).unwrap());
+		let auth_token = auth_token.map(|token| SecretString::new(token.into()));
+		thread::spawn(move || signer.serve_http(listener, auth_token));
+		address
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn unix_socket_signing_works() {
+		let temp_dir = tempfile::tempdir().unwrap();
+		let path = temp_dir.path().join("signer.sock");
+		let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+		let signer = Arc::new(Signer::new(Arc::new(LocalKeystore::in_memory()), None).unwrap());
+		thread::spawn(move || signer.serve_unix(listener));
+
+		check_signing(RemoteKeystore::new(Endpoint::Unix(path), None));
+	}
+
+	#[test]
+	fn http_signing_works() {
+		let address = serve_http(None);
+		check_signing(RemoteKeystore::new(Endpoint::Http { address, path: "/".into() }, None));
+	}
+
+	#[test]
+	fn http_requests_are_authenticated() {
+		let address = serve_http(Some("secret"));
+		let endpoint = Endpoint::Http { address, path: "/".into() };
+
+		let keystore = RemoteKeystore::new(endpoint.clone(), None);
+		assert!(keystore.sr25519_generate_new(TEST, None).is_err());
+		let keystore = RemoteKeystore::new(endpoint.clone(), Some(SecretString::new("x".into())));
+		assert!(keystore.sr25519_generate_new(TEST, None).is_err());
+
+		check_signing(RemoteKeystore::new(endpoint, Some(SecretString::new("secret".into()))));
+	}
+
+	#[test]
+	fn http_requires_token_on_public_addresses() {
+		let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+		let signer = Arc::new(Signer::new(Arc::new(LocalKeystore::in_memory()), None).unwrap());
+		let error = signer.serve_http(listener, None).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+	}
+
+	#[test]
+	fn babe_slots_are_claimed() {
+		let address = serve_http(None);
+		let keystore = RemoteKeystore::new(Endpoint::Http { address, path: "/".into() }, None);
+		let public = keystore.sr25519_generate_new(BABE, None).unwrap();
+
+		// same as `sp_consensus_babe::with_vrf_transcript_data`
+		let (label, items): (&[u8], [(&[u8], &[u8]); 3]) = (
+			b"BABE",
+			[
+				(b"slot number", &5u64.to_le_bytes()),
+				(b"current epoch", &1u64.to_le_bytes()),
+				(b"chain randomness", &[7; 32]),
+			],
+		);
+		let signature = keystore
+			.sr25519_vrf_sign_transcript(BABE, &public, label, &items)
+			.unwrap()
+			.unwrap();
+		let data = VrfTranscript::new(label, &items).into_sign_data();
+		assert!(public.vrf_verify(&data, &signature));
+		// opaque transcripts can't be signed
+		assert!(keystore.sr25519_vrf_sign(BABE, &public, &data).is_err());
+
+		// a single block can be sealed in the claimed slot
+		assert!(keystore.sr25519_sign(BABE, &public, &[1; 32]).unwrap().is_some());
+		assert!(keystore.sr25519_sign(BABE, &public, &[2; 32]).is_err());
+	}
+
+	#[test]
+	fn unavailable_signer_is_reported() {
+		let keystore = RemoteKeystore::new(
+			Endpoint::Http { address: "127.0.0.1:1".into(), path: "/".into() },
+			None,
+		);
+		assert!(keystore.sr25519_public_keys(TEST).is_empty());
+		assert!(keystore.sr25519_generate_new(TEST, None).is_err());
+	}
+}
@@ -0,0 +1,173 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Wire protocol spoken between the [`RemoteKeystore`](super::RemoteKeystore) and a remote
+//! signer.
+//!
+//! Every message is a JSON object. Binary values are hex encoded with a `0x` prefix, key types
+//! are their four character ids (e.g. `"gran"`) and signature schemes are one of `"sr25519"`,
+//! `"ed25519"` and `"ecdsa"`.
+//!
+//! A request names the `method` and carries its `params`:
+//!
+//! ```json
+//! {"method":"sign","params":{"scheme":"ed25519","key_type":"gran","public":"0x…","message":"0x…"}}
+//! ```
+//!
+//! The response carries either the `result` of the request or an `error` message:
+//!
+//! ```json
+//! {"result":"0x…"}
+//! {"error":"Refusing to sign a conflicting GRANDPA vote"}
+//! ```
+//!
+//! The methods and their results are:
+//!
+//! | Method                   | Params                                    | Result                  |
+//! |--------------------------|-------------------------------------------|-------------------------|
+//! | `public_keys`            | `scheme`, `key_type`                      | public keys             |
+//! | `generate_new`           | `scheme`, `key_type`, optional `seed`     | new public key          |
+//! | `sign`                   | `scheme`, `key_type`, `public`, `message` | signature or `null`     |
+//! | `sign_prehashed`         | `key_type`, `public`, 32-byte `message`   | signature or `null`     |
+//! | `sr25519_vrf_sign`       | `key_type`, `public`, `input`             | VRF signature or `null` |
+//! | `insert`                 | `key_type`, `suri`, `public`              | `null`                  |
+//! | `keys`                   | `key_type`                                | public keys             |
+//! | `has_keys`               | `keys`, a list of `[public, key_type]`    | boolean                 |
+//!
+//! VRF inputs are given as their `label` and the list of their `[domain, message]` items, and
+//! VRF signatures are SCALE encoded.
+//!
+//! Over a Unix socket, every message is written on a single line terminated by `\n`. A
+//! connection carries any number of requests, which are answered in order. Over HTTP, every
+//! request is the body of a `POST` request, and the response is the body of the `200 OK`
+//! response. If the signer requires authentication, the requests carry its token in an
+//! `Authorization: Bearer <token>` header, and are answered `401 Unauthorized` otherwise.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sp_core::{crypto::KeyTypeId, Bytes};
+
+/// Signature scheme of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+	/// Schnorr over ristretto25519.
+	Sr25519,
+	/// Ed25519.
+	Ed25519,
+	/// ECDSA over secp256k1.
+	Ecdsa,
+}
+
+/// Key type serialized as its four character id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireKeyType(pub KeyTypeId);
+
+impl Serialize for WireKeyType {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let id = std::str::from_utf8(&self.0 .0).map_err(serde::ser::Error::custom)?;
+		serializer.serialize_str(id)
+	}
+}
+
+impl<'de> Deserialize<'de> for WireKeyType {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let id = String::deserialize(deserializer)?;
+		KeyTypeId::try_from(id.as_str())
+			.map(WireKeyType)
+			.map_err(|_| serde::de::Error::custom("key type must be 4 bytes long"))
+	}
+}
+
+/// VRF input transcript, given by the label and the `(domain, message)` items it is built from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WireTranscript {
+	/// Label of the transcript.
+	pub label: Bytes,
+	/// The `(domain, message)` items of the transcript.
+	pub items: Vec<(Bytes, Bytes)>,
+}
+
+impl WireTranscript {
+	/// The transcript built from `label` and `items`, see `sr25519::vrf::VrfTranscript::new`.
+	pub fn new(label: &[u8], items: &[(&[u8], &[u8])]) -> Self {
+		Self {
+			label: label.to_vec().into(),
+			items: items
+				.iter()
+				.map(|(domain, message)| (domain.to_vec().into(), message.to_vec().into()))
+				.collect(),
+		}
+	}
+}
+
+/// Request sent to the remote signer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum Request {
+	/// List the public keys of the given scheme and key type.
+	PublicKeys { scheme: Scheme, key_type: WireKeyType },
+	/// Generate a new key pair, which is ephemeral if `seed` is given.
+	GenerateNew { scheme: Scheme, key_type: WireKeyType, seed: Option<String> },
+	/// Sign `message` with the key pair of `public`.
+	Sign { scheme: Scheme, key_type: WireKeyType, public: Bytes, message: Bytes },
+	/// Sign the prehashed `message` with the ecdsa key pair of `public`.
+	SignPrehashed { key_type: WireKeyType, public: Bytes, message: Bytes },
+	/// Sign the VRF `input` with the sr25519 key pair of `public`.
+	Sr25519VrfSign { key_type: WireKeyType, public: Bytes, input: WireTranscript },
+	/// Insert the key pair of `suri` under `public`.
+	Insert { key_type: WireKeyType, suri: String, public: Bytes },
+	/// List the raw public keys of the given key type.
+	Keys { key_type: WireKeyType },
+	/// Check that all the given keys are present.
+	HasKeys { keys: Vec<(Bytes, WireKeyType)> },
+}
+
+/// Response of the remote signer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+	/// The request succeeded.
+	Result(serde_json::Value),
+	/// The request failed.
+	Error(String),
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::key_types::GRANDPA;
+
+	#[test]
+	fn request_wire_format() {
+		let request = Request::Sign {
+			scheme: Scheme::Ed25519,
+			key_type: WireKeyType(GRANDPA),
+			public: vec![1, 2].into(),
+			message: vec![3].into(),
+		};
+		let encoded = serde_json::to_string(&request).unwrap();
+		assert_eq!(
+			encoded,
+			r#"{"method":"sign","params":{"scheme":"ed25519","key_type":"gran","public":"0x0102","message":"0x03"}}"#
+		);
+		assert_eq!(serde_json::from_str::<Request>(&encoded).unwrap(), request);
+
+		let response = Response::Error("failed".into());
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"error":"failed"}"#);
+	}
+}
@@ -0,0 +1,743 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Server side of the remote signer protocol.
+//!
+//! The [`Signer`] answers the requests of [`RemoteKeystore`](super::RemoteKeystore)s using a
+//! local keystore.
+//!
+//! Before signing a GRANDPA vote, the signer checks that the voter did not sign a different
+//! vote of the same kind in the same round, and records the vote. Votes for a set older than
+//! the latest one signed by the voter are refused too. The record is persisted before the
+//! signature is returned, so the protection survives restarts of the signer.
+//!
+//! BABE slots are claimed by VRF signing a transcript of the slot, which the signer rebuilds
+//! from the labelled messages it is sent. Only BABE slot claim transcripts are signed. An
+//! authority may only claim slots above the latest one it claimed, or claim the latest one
+//! again with the same transcript. The BABE seal only signs the header hash, so the signer binds
+//! it to the latest claimed slot instead: a single header can be sealed per claimed slot. Like
+//! the GRANDPA votes, the claims are persisted before the signature is returned.
+//!
+//! The HTTP server can require the requests to carry a bearer token, and refuses to listen on
+//! a non-loopback address without one. The token is sent in clear, so a signer reachable from
+//! other hosts must be put behind a TLS terminating proxy. Both servers only serve a bounded
+//! number of connections at the same time.
+
+use super::{
+	protocol::{Request, Response, Scheme, WireKeyType, WireTranscript},
+	transport::{read_http_message, write_http_response},
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+	crypto::{
+		key_types::{BABE, GRANDPA},
+		ByteArray, ExposeSecret, KeyTypeId, SecretString,
+	},
+	ecdsa,
+	sr25519::vrf::VrfTranscript,
+	Bytes, Encode,
+};
+use sp_keystore::KeystorePtr;
+use std::{
+	collections::HashMap,
+	fs,
+	io::{self, BufRead, BufReader, Write},
+	net::{TcpListener, TcpStream},
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+/// Number of rounds of a voter for which the signed votes are kept.
+const MAX_RECORDED_ROUNDS: u64 = 1024;
+
+/// Maximum number of connections served at the same time.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Timeout of the reads and writes of an HTTP connection.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Label of the BABE slot claim transcript, see `sp_consensus_babe::make_vrf_transcript`.
+const BABE_TRANSCRIPT_LABEL: &[u8] = b"BABE";
+/// Domains of the messages of the BABE slot claim transcript, in order.
+const BABE_TRANSCRIPT_DOMAINS: [&[u8]; 3] = [b"slot number", b"current epoch", b"chain randomness"];
+
+/// A signed GRANDPA vote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SignedVote {
+	round: u64,
+	kind: u8,
+	message: Bytes,
+}
+
+/// GRANDPA votes signed by a voter in its latest set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct VoterRecord {
+	set_id: u64,
+	/// Votes of rounds below this one are refused, as they are no longer recorded.
+	lowest_round: u64,
+	votes: Vec<SignedVote>,
+}
+
+/// Latest BABE slot claimed by an authority.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SlotClaim {
+	slot: u64,
+	/// The `(current epoch, chain randomness)` messages of the claim transcript.
+	transcript: Bytes,
+	/// The header hash sealed in the slot, if any.
+	seal: Option<Bytes>,
+}
+
+/// Everything signed by the keys of the signer, by hex encoded public key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SignedRecords {
+	/// GRANDPA votes signed by the voters.
+	#[serde(default)]
+	grandpa: HashMap<String, VoterRecord>,
+	/// Latest BABE slots claimed by the authorities.
+	#[serde(default)]
+	babe: HashMap<String, SlotClaim>,
+}
+
+/// Answers remote signer requests using a local keystore.
+pub struct Signer {
+	keystore: KeystorePtr,
+	signed: Mutex<SignedRecords>,
+	protection_file: Option<PathBuf>,
+	/// Number of connections currently served.
+	connections: AtomicUsize,
+}
+
+impl Signer {
+	/// Create a new signer using `keystore`.
+	///
+	/// The signed GRANDPA votes and BABE slot claims are persisted to `protection_file`, if
+	/// given, and are only kept in memory otherwise.
+	pub fn new(keystore: KeystorePtr, protection_file: Option<PathBuf>) -> io::Result<Self> {
+		let signed = match &protection_file {
+			Some(path) if path.exists() => serde_json::from_slice(&fs::read(path)?)?,
+			_ => SignedRecords::default(),
+		};
+
+		Ok(Self {
+			keystore,
+			signed: Mutex::new(signed),
+			protection_file,
+			connections: AtomicUsize::new(0),
+		})
+	}
+
+	/// Answer the given request.
+	pub fn handle(&self, request: Request) -> Response {
+		match self.handle_inner(request) {
+			Ok(result) => Response::Result(result),
+			Err(error) => Response::Error(error),
+		}
+	}
+
+	fn handle_inner(&self, request: Request) -> Result<serde_json::Value, String> {
+		let keystore = &self.keystore;
+		let result = match request {
+			Request::PublicKeys { scheme, key_type: WireKeyType(key_type) } => {
+				let keys: Vec<Bytes> = match scheme {
+					Scheme::Sr25519 => to_bytes(keystore.sr25519_public_keys(key_type)),
+					Scheme::Ed25519 => to_bytes(keystore.ed25519_public_keys(key_type)),
+					Scheme::Ecdsa => to_bytes(keystore.ecdsa_public_keys(key_type)),
+				};
+				serde_json::to_value(keys)
+			},
+			Request::GenerateNew { scheme, key_type: WireKeyType(key_type), seed } => {
+				let seed = seed.as_deref();
+				let public = match scheme {
+					Scheme::Sr25519 =>
+						keystore.sr25519_generate_new(key_type, seed).map(|p| p.to_raw_vec()),
+					Scheme::Ed25519 =>
+						keystore.ed25519_generate_new(key_type, seed).map(|p| p.to_raw_vec()),
+					Scheme::Ecdsa =>
+						keystore.ecdsa_generate_new(key_type, seed).map(|p| p.to_raw_vec()),
+				}
+				.map_err(|e| e.to_string())?;
+				serde_json::to_value(Bytes(public))
+			},
+			Request::Sign { scheme, key_type: WireKeyType(key_type), public, message } => {
+				self.check_vote(scheme, key_type, &public, &message)?;
+				self.check_seal(scheme, key_type, &public, &message)?;
+				let signature = match scheme {
+					Scheme::Sr25519 => keystore
+						.sr25519_sign(key_type, &decode(&public)?, &message)
+						.map(|s| s.map(|s| s.to_raw_vec())),
+					Scheme::Ed25519 => keystore
+						.ed25519_sign(key_type, &decode(&public)?, &message)
+						.map(|s| s.map(|s| s.to_raw_vec())),
+					Scheme::Ecdsa => keystore
+						.ecdsa_sign(key_type, &decode(&public)?, &message)
+						.map(|s| s.map(|s| s.to_raw_vec())),
+				}
+				.map_err(|e| e.to_string())?;
+				serde_json::to_value(signature.map(Bytes))
+			},
+			Request::SignPrehashed { key_type: WireKeyType(key_type), public, message } => {
+				let message: [u8; 32] =
+					message.0.try_into().map_err(|_| "Message must be 32 bytes long")?;
+				let signature = keystore
+					.ecdsa_sign_prehashed(key_type, &decode::<ecdsa::Public>(&public)?, &message)
+					.map_err(|e| e.to_string())?;
+				serde_json::to_value(signature.map(|s| Bytes(s.to_raw_vec())))
+			},
+			Request::Sr25519VrfSign { key_type: WireKeyType(key_type), public, input } => {
+				let (slot, claim, transcript) = babe_transcript(key_type, &input)?;
+				self.check_slot_claim(&public, slot, claim)?;
+				let signature = keystore
+					.sr25519_vrf_sign(key_type, &decode(&public)?, &transcript.into_sign_data())
+					.map_err(|e| e.to_string())?;
+				serde_json::to_value(signature.map(|s| Bytes(s.encode())))
+			},
+			Request::Insert { key_type: WireKeyType(key_type), suri, public } => {
+				keystore
+					.insert(key_type, &suri, &public)
+					.map_err(|_| "Failed to insert the key".to_string())?;
+				Ok(serde_json::Value::Null)
+			},
+			Request::Keys { key_type: WireKeyType(key_type) } => {
+				let keys = keystore.keys(key_type).map_err(|e| e.to_string())?;
+				serde_json::to_value(keys.into_iter().map(Bytes).collect::<Vec<_>>())
+			},
+			Request::HasKeys { keys } => {
+				let keys = keys
+					.into_iter()
+					.map(|(public, WireKeyType(key_type))| (public.0, key_type))
+					.collect::<Vec<_>>();
+				serde_json::to_value(keystore.has_keys(&keys))
+			},
+		};
+
+		result.map_err(|e| e.to_string())
+	}
+
+	/// Checks that signing `message` does not make the voter equivocate, and records it.
+	///
+	/// Only GRANDPA votes are checked. They are encoded as `(message, round, set_id)`, where
+	/// the first byte of `message` is the kind of the vote.
+	fn check_vote(
+		&self,
+		scheme: Scheme,
+		key_type: KeyTypeId,
+		public: &[u8],
+		message: &[u8],
+	) -> Result<(), String> {
+		if key_type != GRANDPA || scheme != Scheme::Ed25519 {
+			return Ok(())
+		}
+
+		let len = message.len();
+		if len < 17 {
+			return Err("Refusing to sign a malformed GRANDPA vote".into())
+		}
+		let kind = message[0];
+		let round = u64::from_le_bytes(message[len - 16..len - 8].try_into().expect("8 bytes"));
+		let set_id = u64::from_le_bytes(message[len - 8..].try_into().expect("8 bytes"));
+
+		let mut signed = self.signed.lock();
+		let key = array_bytes::bytes2hex("0x", public);
+		let mut record = signed.grandpa.get(&key).cloned().unwrap_or_default();
+
+		if set_id < record.set_id {
+			return Err(format!("Refusing to sign a GRANDPA vote of the stale set {set_id}"))
+		}
+		if set_id > record.set_id {
+			record = VoterRecord { set_id, ..Default::default() };
+		}
+		if round < record.lowest_round {
+			return Err(format!("Refusing to sign a GRANDPA vote of the pruned round {round}"))
+		}
+		if let Some(vote) = record.votes.iter().find(|v| v.round == round && v.kind == kind) {
+			return if &vote.message[..] == message {
+				Ok(())
+			} else {
+				Err(format!("Refusing to sign a conflicting GRANDPA vote in round {round}"))
+			}
+		}
+
+		record.votes.push(SignedVote { round, kind, message: message.to_vec().into() });
+		let latest_round = record.votes.iter().map(|v| v.round).max().unwrap_or_default();
+		if latest_round >= MAX_RECORDED_ROUNDS {
+			record.lowest_round = record.lowest_round.max(latest_round - MAX_RECORDED_ROUNDS + 1);
+			let lowest_round = record.lowest_round;
+			record.votes.retain(|v| v.round >= lowest_round);
+		}
+
+		self.update(&mut signed, |signed| &mut signed.grandpa, key, record)
+	}
+
+	/// Checks that claiming `slot` with the given epoch and randomness `transcript` does not
+	/// make the authority equivocate, and records the claim.
+	fn check_slot_claim(&self, public: &[u8], slot: u64, transcript: Bytes) -> Result<(), String> {
+		let mut signed = self.signed.lock();
+		let key = array_bytes::bytes2hex("0x", public);
+		match signed.babe.get(&key) {
+			Some(claim) if slot < claim.slot =>
+				return Err(format!("Refusing to claim the past BABE slot {slot}")),
+			Some(claim) if slot == claim.slot && claim.transcript == transcript => return Ok(()),
+			Some(claim) if slot == claim.slot =>
+				return Err(format!("Refusing to claim the BABE slot {slot} in another epoch")),
+			_ => {},
+		}
+
+		self.update(
+			&mut signed,
+			|signed| &mut signed.babe,
+			key,
+			SlotClaim { slot, transcript, seal: None },
+		)
+	}
+
+	/// Checks that sealing `message` does not make the authority equivocate, and records it.
+	///
+	/// Only BABE seals are checked. A single header hash can be sealed in the latest slot
+	/// claimed by the authority.
+	fn check_seal(
+		&self,
+		scheme: Scheme,
+		key_type: KeyTypeId,
+		public: &[u8],
+		message: &[u8],
+	) -> Result<(), String> {
+		if key_type != BABE || scheme != Scheme::Sr25519 {
+			return Ok(())
+		}
+
+		let mut signed = self.signed.lock();
+		let key = array_bytes::bytes2hex("0x", public);
+		let Some(mut claim) = signed.babe.get(&key).cloned() else {
+			return Err("Refusing to seal a block without a claimed BABE slot".into())
+		};
+		match &claim.seal {
+			Some(seal) if &seal[..] == message => return Ok(()),
+			Some(_) =>
+				return Err(format!("Refusing to seal a second block in BABE slot {}", claim.slot)),
+			None => claim.seal = Some(message.to_vec().into()),
+		}
+
+		self.update(&mut signed, |signed| &mut signed.babe, key, claim)
+	}
+
+	/// Replaces the record of `key` in the `records` of `signed` and persists them.
+	///
+	/// The previous record is restored if persisting fails, so that retrying the same request
+	/// isn't taken for the repetition of a recorded one.
+	fn update<V>(
+		&self,
+		signed: &mut SignedRecords,
+		records: fn(&mut SignedRecords) -> &mut HashMap<String, V>,
+		key: String,
+		record: V,
+	) -> Result<(), String> {
+		let previous = records(signed).insert(key.clone(), record);
+		self.persist(signed).inspect_err(|_| match previous {
+			Some(previous) => {
+				records(signed).insert(key, previous);
+			},
+			None => {
+				records(signed).remove(&key);
+			},
+		})
+	}
+
+	/// Persists the signed records to the protection file, if any.
+	fn persist(&self, signed: &SignedRecords) -> Result<(), String> {
+		match &self.protection_file {
+			Some(path) => persist(path, signed)
+				.map_err(|e| format!("Failed to persist the signed records: {e}")),
+			None => Ok(()),
+		}
+	}
+
+	/// Serve the connections of `incoming` with `serve_connection`.
+	///
+	/// Every connection is served on its own thread, and the connections above
+	/// [`MAX_CONNECTIONS`] are closed right away. Only returns on listener errors.
+	fn serve<S: Send + 'static>(
+		self: Arc<Self>,
+		incoming: impl Iterator<Item = io::Result<S>>,
+		serve_connection: fn(&Self, S) -> io::Result<()>,
+	) -> io::Result<()> {
+		for stream in incoming {
+			let stream = stream?;
+			if self.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+				self.connections.fetch_sub(1, Ordering::SeqCst);
+				continue
+			}
+
+			let signer = self.clone();
+			thread::spawn(move || {
+				let _ = serve_connection(&signer, stream);
+				signer.connections.fetch_sub(1, Ordering::SeqCst);
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Serve the requests received on the given Unix socket listener.
+	///
+	/// Only returns on listener errors.
+	#[cfg(unix)]
+	pub fn serve_unix(self: Arc<Self>, listener: UnixListener) -> io::Result<()> {
+		self.serve(listener.incoming(), Self::serve_unix_connection)
+	}
+
+	#[cfg(unix)]
+	fn serve_unix_connection(&self, stream: UnixStream) -> io::Result<()> {
+		let reader = BufReader::new(stream.try_clone()?);
+		let mut writer = stream;
+
+		for line in reader.lines() {
+			let response = match serde_json::from_str(&line?) {
+				Ok(request) => self.handle(request),
+				Err(error) => Response::Error(format!("Invalid request: {error}")),
+			};
+			serde_json::to_writer(&mut writer, &response)?;
+			writer.write_all(b"\n")?;
+			writer.flush()?;
+		}
+
+		Ok(())
+	}
+
+	/// Serve the HTTP requests received on the given TCP listener.
+	///
+	/// The requests must carry `auth_token`, if given. Without a token, the listener must be
+	/// bound to a loopback address. Only returns on listener errors.
+	pub fn serve_http(
+		self: Arc<Self>,
+		listener: TcpListener,
+		auth_token: Option<SecretString>,
+	) -> io::Result<()> {
+		if auth_token.is_none() && !listener.local_addr()?.ip().is_loopback() {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"An authentication token is required to serve on a non-loopback address",
+			))
+		}
+
+		let expected = auth_token.map(|token| format!("Bearer {}", token.expose_secret()));
+		let incoming = listener.incoming().map(|stream| {
+			let stream = stream?;
+			stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+			stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+			Ok((stream, expected.clone()))
+		});
+		self.serve(incoming, Self::serve_http_connection)
+	}
+
+	fn serve_http_connection(
+		&self,
+		(stream, expected): (TcpStream, Option<String>),
+	) -> io::Result<()> {
+		let mut reader = BufReader::new(stream.try_clone()?);
+		let mut writer = stream;
+
+		let request = read_http_message(&mut reader)?;
+		if let Some(expected) = expected {
+			let authorization = request.header("authorization").unwrap_or_default();
+			if !constant_time_eq(authorization.as_bytes(), expected.as_bytes()) {
+				return write_http_response(&mut writer, "401 Unauthorized", b"")
+			}
+		}
+		if !request.start_line.starts_with("POST ") {
+			return write_http_response(&mut writer, "405 Method Not Allowed", b"")
+		}
+
+		let response = match serde_json::from_slice(&request.body) {
+			Ok(request) => self.handle(request),
+			Err(error) => Response::Error(format!("Invalid request: {error}")),
+		};
+		write_http_response(&mut writer, "200 OK", &serde_json::to_vec(&response)?)
+	}
+}
+
+/// Rebuilds the BABE slot claim transcript from `input`.
+///
+/// Returns the claimed slot, the epoch and randomness messages of the claim, and the
+/// transcript. Only the transcripts of the BABE keys are rebuilt, and they must have the layout
+/// of `sp_consensus_babe::make_vrf_transcript`.
+fn babe_transcript(
+	key_type: KeyTypeId,
+	input: &WireTranscript,
+) -> Result<(u64, Bytes, VrfTranscript), String> {
+	let domains = input.items.iter().map(|(domain, _)| &domain[..]);
+	if key_type != BABE ||
+		&input.label[..] != BABE_TRANSCRIPT_LABEL ||
+		!domains.eq(BABE_TRANSCRIPT_DOMAINS)
+	{
+		return Err("Refusing to sign a VRF input which is not a BABE slot claim".into())
+	}
+
+	let [slot, epoch, randomness] = [0, 1, 2].map(|i| &input.items[i].1[..]);
+	if epoch.len() != 8 || randomness.len() != 32 {
+		return Err("Invalid BABE epoch or randomness".into())
+	}
+	let slot = u64::from_le_bytes(slot.try_into().map_err(|_| "Invalid BABE slot")?);
+	let claim = [epoch, randomness].concat().into();
+	let transcript = VrfTranscript::new(
+		BABE_TRANSCRIPT_LABEL,
+		&[
+			(BABE_TRANSCRIPT_DOMAINS[0], &slot.to_le_bytes()),
+			(BABE_TRANSCRIPT_DOMAINS[1], epoch),
+			(BABE_TRANSCRIPT_DOMAINS[2], randomness),
+		],
+	);
+
+	Ok((slot, claim, transcript))
+}
+
+/// Compares `a` and `b` in a time only depending on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Decodes a public key.
+fn decode<T: ByteArray>(public: &[u8]) -> Result<T, String> {
+	T::from_slice(public).map_err(|_| "Invalid public key".to_string())
+}
+
+/// Encodes public keys.
+fn to_bytes<T: ByteArray>(keys: Vec<T>) -> Vec<Bytes> {
+	keys.into_iter().map(|k| Bytes(k.to_raw_vec())).collect()
+}
+
+/// Replaces the protection file with the given signed records.
+fn persist(path: &Path, signed: &SignedRecords) -> io::Result<()> {
+	let tmp_path = path.with_extension("tmp");
+	let mut file = fs::File::create(&tmp_path)?;
+	serde_json::to_writer(&mut file, signed)?;
+	file.sync_all()?;
+	fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::LocalKeystore;
+	use codec::Encode;
+	use sp_core::{
+		crypto::{Pair as _, VrfPublic},
+		ed25519, sr25519, Decode,
+	};
+	use sp_keystore::Keystore;
+	use tempfile::TempDir;
+
+	fn vote(kind: u8, target: u8, round: u64, set_id: u64) -> Vec<u8> {
+		(kind, [target; 32], 10u32, round, set_id).encode()
+	}
+
+	fn sign(signer: &Signer, public: &ed25519::Public, message: Vec<u8>) -> Response {
+		signer.handle(Request::Sign {
+			scheme: Scheme::Ed25519,
+			key_type: WireKeyType(GRANDPA),
+			public: public.to_raw_vec().into(),
+			message: message.into(),
+		})
+	}
+
+	fn is_signed(response: &Response) -> bool {
+		matches!(response, Response::Result(serde_json::Value::String(_)))
+	}
+
+	/// Same as `sp_consensus_babe::with_vrf_transcript_data`.
+	fn babe_input<R>(
+		slot: u64,
+		epoch: u64,
+		f: impl FnOnce(&'static [u8], &[(&'static [u8], &[u8])]) -> R,
+	) -> R {
+		f(
+			b"BABE",
+			&[
+				(b"slot number", &slot.to_le_bytes()),
+				(b"current epoch", &epoch.to_le_bytes()),
+				(b"chain randomness", &[7; 32]),
+			],
+		)
+	}
+
+	fn claim(signer: &Signer, public: &sr25519::Public, slot: u64, epoch: u64) -> Response {
+		signer.handle(Request::Sr25519VrfSign {
+			key_type: WireKeyType(BABE),
+			public: public.to_raw_vec().into(),
+			input: babe_input(slot, epoch, |label, items| WireTranscript::new(label, items)),
+		})
+	}
+
+	fn seal(signer: &Signer, public: &sr25519::Public, hash: u8) -> Response {
+		signer.handle(Request::Sign {
+			scheme: Scheme::Sr25519,
+			key_type: WireKeyType(BABE),
+			public: public.to_raw_vec().into(),
+			message: vec![hash; 32].into(),
+		})
+	}
+
+	#[test]
+	fn signs_with_keystore_keys() {
+		let keystore = Arc::new(LocalKeystore::in_memory());
+		let signer = Signer::new(keystore, None).unwrap();
+
+		let Response::Result(public) = signer.handle(Request::GenerateNew {
+			scheme: Scheme::Sr25519,
+			key_type: WireKeyType(KeyTypeId(*b"test")),
+			seed: Some("//Alice".into()),
+		}) else {
+			panic!("Key is generated")
+		};
+		let public: Bytes = serde_json::from_value(public).unwrap();
+		assert_eq!(public.0, sr25519::Pair::from_string("//Alice", None).unwrap().public().0);
+
+		let Response::Result(signature) = signer.handle(Request::Sign {
+			scheme: Scheme::Sr25519,
+			key_type: WireKeyType(KeyTypeId(*b"test")),
+			public: public.clone(),
+			message: b"message".to_vec().into(),
+		}) else {
+			panic!("Message is signed")
+		};
+		let signature: Bytes = serde_json::from_value(signature).unwrap();
+		assert!(sr25519::Pair::verify(
+			&sr25519::Signature::from_slice(&signature).unwrap(),
+			b"message",
+			&sr25519::Public::from_slice(&public).unwrap(),
+		));
+	}
+
+	#[test]
+	fn refuses_conflicting_grandpa_votes() {
+		let temp_dir = TempDir::new().unwrap();
+		let protection_file = temp_dir.path().join("votes.json");
+		let keystore = Arc::new(LocalKeystore::in_memory());
+		let public = keystore.ed25519_generate_new(GRANDPA, Some("//Alice")).unwrap();
+		let signer = Signer::new(keystore.clone(), Some(protection_file.clone())).unwrap();
+
+		// prevote and precommit of the same round
+		assert!(is_signed(&sign(&signer, &public, vote(0, 1, 5, 1))));
+		assert!(is_signed(&sign(&signer, &public, vote(1, 1, 5, 1))));
+		// signing the same vote again is fine
+		assert!(is_signed(&sign(&signer, &public, vote(0, 1, 5, 1))));
+		// a different prevote in the same round is an equivocation
+		assert!(!is_signed(&sign(&signer, &public, vote(0, 2, 5, 1))));
+		assert!(is_signed(&sign(&signer, &public, vote(0, 2, 6, 1))));
+
+		// the protection survives restarts
+		let signer = Signer::new(keystore, Some(protection_file)).unwrap();
+		assert!(!is_signed(&sign(&signer, &public, vote(0, 3, 6, 1))));
+
+		// votes of older sets are refused
+		assert!(is_signed(&sign(&signer, &public, vote(0, 3, 1, 2))));
+		assert!(!is_signed(&sign(&signer, &public, vote(0, 3, 7, 1))));
+	}
+
+	#[test]
+	fn refuses_conflicting_babe_claims() {
+		let temp_dir = TempDir::new().unwrap();
+		let protection_file = temp_dir.path().join("votes.json");
+		let keystore = Arc::new(LocalKeystore::in_memory());
+		let public = keystore.sr25519_generate_new(BABE, Some("//Alice")).unwrap();
+		let signer = Signer::new(keystore.clone(), Some(protection_file.clone())).unwrap();
+
+		// blocks can't be sealed before claiming a slot
+		assert!(!is_signed(&seal(&signer, &public, 1)));
+
+		let Response::Result(signature) = claim(&signer, &public, 5, 1) else {
+			panic!("Slot is claimed")
+		};
+		let signature: Bytes = serde_json::from_value(signature).unwrap();
+		let signature = sr25519::vrf::VrfSignature::decode(&mut &signature[..]).unwrap();
+		let data = babe_input(5, 1, VrfTranscript::new).into_sign_data();
+		assert!(public.vrf_verify(&data, &signature));
+
+		// claiming the same slot again is fine, but not with another transcript
+		assert!(is_signed(&claim(&signer, &public, 5, 1)));
+		assert!(!is_signed(&claim(&signer, &public, 5, 2)));
+
+		// a single block can be sealed in the slot
+		assert!(is_signed(&seal(&signer, &public, 1)));
+		assert!(is_signed(&seal(&signer, &public, 1)));
+		assert!(!is_signed(&seal(&signer, &public, 2)));
+
+		// the protection survives restarts
+		let signer = Signer::new(keystore, Some(protection_file)).unwrap();
+		assert!(!is_signed(&seal(&signer, &public, 2)));
+
+		// past slots can't be claimed
+		assert!(is_signed(&claim(&signer, &public, 6, 1)));
+		assert!(is_signed(&seal(&signer, &public, 2)));
+		assert!(!is_signed(&claim(&signer, &public, 5, 1)));
+
+		// only BABE transcripts are signed
+		let other = WireTranscript::new(b"other", &[(b"slot number", &7u64.to_le_bytes())]);
+		assert!(!is_signed(&signer.handle(Request::Sr25519VrfSign {
+			key_type: WireKeyType(BABE),
+			public: public.to_raw_vec().into(),
+			input: other,
+		})));
+	}
+
+	#[test]
+	fn prunes_old_grandpa_rounds() {
+		let keystore = Arc::new(LocalKeystore::in_memory());
+		let public = keystore.ed25519_generate_new(GRANDPA, Some("//Alice")).unwrap();
+		let signer = Signer::new(keystore, None).unwrap();
+
+		assert!(is_signed(&sign(&signer, &public, vote(0, 1, 1, 0))));
+		assert!(is_signed(&sign(&signer, &public, vote(0, 1, MAX_RECORDED_ROUNDS + 1, 0))));
+
+		let signed = signer.signed.lock();
+		let record = signed.grandpa.values().next().unwrap();
+		assert_eq!(record.votes.len(), 1);
+		assert_eq!(record.lowest_round, 2);
+		drop(signed);
+
+		// the pruned round can not be signed anymore
+		assert!(!is_signed(&sign(&signer, &public, vote(0, 2, 1, 0))));
+	}
+
+	#[test]
+	fn records_are_not_kept_when_persisting_fails() {
+		let temp_dir = TempDir::new().unwrap();
+		let protection_file = temp_dir.path().join("votes.json");
+		let keystore = Arc::new(LocalKeystore::in_memory());
+		let public = keystore.ed25519_generate_new(GRANDPA, Some("//Alice")).unwrap();
+		let signer = Signer::new(keystore, Some(protection_file)).unwrap();
+		assert!(is_signed(&sign(&signer, &public, vote(0, 1, 5, 1))));
+
+		// the vote can't be persisted, and retrying it must not be taken for a signed one
+		drop(temp_dir);
+		assert!(!is_signed(&sign(&signer, &public, vote(1, 1, 5, 1))));
+		assert!(!is_signed(&sign(&signer, &public, vote(1, 1, 5, 1))));
+		// the persisted votes are still recorded
+		assert!(!is_signed(&sign(&signer, &public, vote(0, 2, 5, 1))));
+		assert!(signer.signed.lock().grandpa.values().all(|record| record.votes.len() == 1));
+	}
+}
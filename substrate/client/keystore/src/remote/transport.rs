@@ -0,0 +1,223 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transports carrying the messages of the remote signer protocol.
+
+use std::{
+	fmt,
+	io::{self, BufRead, Read, Write},
+	path::PathBuf,
+	str::FromStr,
+};
+
+/// Maximum size of a message body.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Maximum size of the start line and headers of a message.
+const MAX_HEADER_SIZE: u64 = 8 * 1024;
+
+/// Endpoint of a remote signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+	/// Unix socket at the given path, given as `unix:///path/to/socket`.
+	Unix(PathBuf),
+	/// HTTP server, given as `http://host:port/path`.
+	Http {
+		/// The `host:port` the server is listening on.
+		address: String,
+		/// The path the requests are posted to.
+		path: String,
+	},
+}
+
+impl FromStr for Endpoint {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Some(path) = s.strip_prefix("unix://") {
+			return Ok(Endpoint::Unix(path.into()))
+		}
+
+		if let Some(rest) = s.strip_prefix("http://") {
+			let (address, path) = match rest.find('/') {
+				Some(index) => (&rest[..index], &rest[index..]),
+				None => (rest, "/"),
+			};
+			if address.is_empty() {
+				return Err(format!("Missing address in remote signer endpoint: {s}"))
+			}
+			return Ok(Endpoint::Http { address: address.into(), path: path.into() })
+		}
+
+		Err(format!("Remote signer endpoint must start with `unix://` or `http://`: {s}"))
+	}
+}
+
+impl fmt::Display for Endpoint {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Endpoint::Unix(path) => write!(f, "unix://{}", path.display()),
+			Endpoint::Http { address, path } => write!(f, "http://{address}{path}"),
+		}
+	}
+}
+
+/// An HTTP message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HttpMessage {
+	/// The request or status line.
+	pub start_line: String,
+	/// The headers, in the order they were received.
+	pub headers: Vec<(String, String)>,
+	/// The body, of the length given by the `Content-Length` header.
+	pub body: Vec<u8>,
+}
+
+impl HttpMessage {
+	/// Returns the value of the header `name`, if any.
+	pub fn header(&self, name: &str) -> Option<&str> {
+		self.headers
+			.iter()
+			.find(|(header, _)| header.eq_ignore_ascii_case(name))
+			.map(|(_, value)| value.as_str())
+	}
+}
+
+/// Reads an HTTP message.
+pub(crate) fn read_http_message(reader: &mut impl BufRead) -> io::Result<HttpMessage> {
+	let mut head = reader.by_ref().take(MAX_HEADER_SIZE);
+	let mut read_line = |line: &mut String| match head.read_line(line)? {
+		0 if head.limit() == 0 =>
+			Err(io::Error::new(io::ErrorKind::InvalidData, "Message headers are too large")),
+		0 => Err(io::ErrorKind::UnexpectedEof.into()),
+		_ => Ok(()),
+	};
+
+	let mut start_line = String::new();
+	read_line(&mut start_line)?;
+
+	let mut headers = Vec::new();
+	loop {
+		let mut line = String::new();
+		read_line(&mut line)?;
+		let line = line.trim_end();
+		if line.is_empty() {
+			break
+		}
+		if let Some((name, value)) = line.split_once(':') {
+			headers.push((name.trim().to_string(), value.trim().to_string()));
+		}
+	}
+
+	let mut message =
+		HttpMessage { start_line: start_line.trim_end().into(), headers, body: Vec::new() };
+	let length = match message.header("content-length") {
+		Some(length) => length
+			.parse()
+			.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid length"))?,
+		None => 0,
+	};
+	if length > MAX_BODY_SIZE {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Message body is too large"))
+	}
+	message.body = vec![0; length];
+	reader.read_exact(&mut message.body)?;
+
+	Ok(message)
+}
+
+/// Writes an HTTP `POST` request carrying `body`, authenticated with `auth_token` if given.
+pub(crate) fn write_http_request(
+	writer: &mut impl Write,
+	address: &str,
+	path: &str,
+	auth_token: Option<&str>,
+	body: &[u8],
+) -> io::Result<()> {
+	write!(
+		writer,
+		"POST {path} HTTP/1.1\r\nHost: {address}\r\nContent-Type: application/json\r\n\
+		Content-Length: {}\r\nConnection: close\r\n",
+		body.len()
+	)?;
+	if let Some(token) = auth_token {
+		write!(writer, "Authorization: Bearer {token}\r\n")?;
+	}
+	writer.write_all(b"\r\n")?;
+	writer.write_all(body)?;
+	writer.flush()
+}
+
+/// Writes an HTTP response with the given `status` carrying `body`.
+pub(crate) fn write_http_response(
+	writer: &mut impl Write,
+	status: &str,
+	body: &[u8],
+) -> io::Result<()> {
+	write!(
+		writer,
+		"HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+		Connection: close\r\n\r\n",
+		body.len()
+	)?;
+	writer.write_all(body)?;
+	writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn endpoint_parsing() {
+		assert_eq!(
+			"unix:///run/signer.sock".parse::<Endpoint>().unwrap(),
+			Endpoint::Unix("/run/signer.sock".into())
+		);
+		assert_eq!(
+			"http://10.0.0.1:9955".parse::<Endpoint>().unwrap(),
+			Endpoint::Http { address: "10.0.0.1:9955".into(), path: "/".into() }
+		);
+		assert_eq!(
+			"http://signer:9955/v1".parse::<Endpoint>().unwrap(),
+			Endpoint::Http { address: "signer:9955".into(), path: "/v1".into() }
+		);
+		assert!("https://signer:9955".parse::<Endpoint>().is_err());
+		assert!("http:///v1".parse::<Endpoint>().is_err());
+	}
+
+	#[test]
+	fn http_message_roundtrip() {
+		let mut buffer = Vec::new();
+		write_http_request(&mut buffer, "signer:9955", "/v1", Some("secret"), b"{}").unwrap();
+
+		let message = read_http_message(&mut &buffer[..]).unwrap();
+		assert_eq!(message.start_line, "POST /v1 HTTP/1.1");
+		assert_eq!(message.header("authorization"), Some("Bearer secret"));
+		assert_eq!(message.body, b"{}");
+	}
+
+	#[test]
+	fn oversized_headers_are_rejected() {
+		let mut buffer = b"POST / HTTP/1.1\r\nX-Padding: ".to_vec();
+		buffer.resize(MAX_HEADER_SIZE as usize * 2, b'a');
+
+		let error = read_http_message(&mut &buffer[..]).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+	}
+}
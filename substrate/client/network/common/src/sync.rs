@@ -34,6 +34,12 @@ pub enum SyncMode {
 	},
 	/// Warp sync - verify authority set transitions and the latest state.
 	Warp,
+	/// Light client - verify authority set transitions, then download and verify headers and
+	/// justifications only.
+	///
+	/// No state is kept locally, storage and runtime calls are answered with proofs fetched
+	/// from peers on demand.
+	Light,
 }
 
 impl SyncMode {
@@ -46,6 +52,11 @@ impl SyncMode {
 	pub fn light_state(&self) -> bool {
 		matches!(self, Self::LightState { .. })
 	}
+
+	/// Returns `true` if `self` is [`Self::Light`].
+	pub fn is_light(&self) -> bool {
+		matches!(self, Self::Light)
+	}
 }
 
 impl Default for SyncMode {
@@ -0,0 +1,340 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Helper for outgoing light client requests.
+//!
+//! Send light client requests to the full nodes we are connected to and check the returned
+//! proofs against the headers of the local chain with
+//! [`LightClientRequestSender`](sender::LightClientRequestSender).
+
+use crate::schema;
+use codec::{Decode, Encode};
+use futures::{future::BoxFuture, prelude::*};
+use log::debug;
+use parking_lot::Mutex;
+use prost::Message;
+use sc_client_api::{light::RemoteReadResult, HeaderBackend, RemoteFetcher};
+use sc_network::{request_responses::IfDisconnected, NetworkRequest, ProtocolName};
+use sc_network_sync::SyncingService;
+use sc_network_types::PeerId;
+use sp_blockchain::Error as ClientError;
+use sp_core::{
+	storage::{well_known_keys, ChildInfo, ChildType, PrefixedStorageKey},
+	traits::{CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+};
+use sp_runtime::traits::{Block, HashingFor, Header, NumberFor};
+use sp_state_machine::{OverlayedChanges, StorageProof};
+use std::sync::Arc;
+
+const LOG_TARGET: &str = "light-client-request-sender";
+
+/// Number of peers a request is sent to before giving up.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Sends light client requests to remote full nodes and checks the responses.
+pub struct LightClientRequestSender<B: Block, Client, Exec> {
+	inner: Arc<Inner<B, Client, Exec>>,
+}
+
+impl<B: Block, Client, Exec> Clone for LightClientRequestSender<B, Client, Exec> {
+	fn clone(&self) -> Self {
+		Self { inner: self.inner.clone() }
+	}
+}
+
+struct Inner<B: Block, Client, Exec> {
+	client: Arc<Client>,
+	executor: Exec,
+	network: Arc<dyn NetworkRequest + Send + Sync>,
+	sync_service: Arc<SyncingService<B>>,
+	protocol_name: ProtocolName,
+	/// Runtime code of the last block a call was made at, with its heap pages.
+	runtime_code: Mutex<Option<(B::Hash, Arc<Vec<u8>>, Option<u64>)>>,
+}
+
+impl<B, Client, Exec> LightClientRequestSender<B, Client, Exec>
+where
+	B: Block,
+	Client: HeaderBackend<B> + Send + Sync + 'static,
+	Exec: CodeExecutor + Clone + Send + Sync + 'static,
+{
+	/// Create a new [`LightClientRequestSender`].
+	///
+	/// The requests are sent over the protocol registered with
+	/// [`generate_protocol_config`](super::generate_protocol_config).
+	pub fn new(
+		fork_id: Option<&str>,
+		client: Arc<Client>,
+		executor: Exec,
+		network: Arc<dyn NetworkRequest + Send + Sync>,
+		sync_service: Arc<SyncingService<B>>,
+	) -> Self {
+		let protocol_name = super::generate_protocol_name(client.info().genesis_hash, fork_id);
+
+		Self {
+			inner: Arc::new(Inner {
+				client,
+				executor,
+				network,
+				sync_service,
+				protocol_name: protocol_name.into(),
+				runtime_code: Mutex::new(None),
+			}),
+		}
+	}
+}
+
+impl<B, Client, Exec> Inner<B, Client, Exec>
+where
+	B: Block,
+	Client: HeaderBackend<B> + Send + Sync + 'static,
+	Exec: CodeExecutor + Clone + Send + Sync + 'static,
+{
+	/// Returns the header of `block`, which must be known locally.
+	fn header(&self, block: B::Hash) -> Result<B::Header, ClientError> {
+		self.client
+			.header(block)?
+			.ok_or_else(|| ClientError::UnknownBlock(format!("{block:?}")))
+	}
+
+	/// Full nodes that may be able to answer a request about the block `number`, best first.
+	async fn peers(&self, number: NumberFor<B>) -> Result<Vec<PeerId>, ClientError> {
+		let mut peers = self
+			.sync_service
+			.peers_info()
+			.await
+			.map_err(|_| ClientError::RemoteFetchCancelled)?
+			.into_iter()
+			.filter(|(_, info)| info.roles.is_full() && info.best_number >= number)
+			.map(|(peer, info)| (peer, info.best_number))
+			.collect::<Vec<_>>();
+		peers.sort_by(|a, b| b.1.cmp(&a.1));
+
+		Ok(peers.into_iter().map(|(peer, _)| peer).take(MAX_ATTEMPTS).collect())
+	}
+
+	/// Send `request` to the peers until one of them answers with a proof that `check` accepts.
+	async fn request<T>(
+		&self,
+		number: NumberFor<B>,
+		request: schema::v1::light::request::Request,
+		check: impl Fn(StorageProof) -> Result<T, ClientError>,
+	) -> Result<T, ClientError> {
+		let request = schema::v1::light::Request { request: Some(request) }.encode_to_vec();
+
+		for peer in self.peers(number).await? {
+			let response = match self
+				.network
+				.request(
+					peer,
+					self.protocol_name.clone(),
+					request.clone(),
+					None,
+					IfDisconnected::ImmediateError,
+				)
+				.await
+			{
+				Ok((response, _)) => response,
+				Err(e) => {
+					debug!(target: LOG_TARGET, "Light client request to {peer} failed: {e}");
+					continue
+				},
+			};
+
+			let proof = match schema::v1::light::Response::decode(&response[..]) {
+				Ok(schema::v1::light::Response {
+					response:
+						Some(schema::v1::light::response::Response::RemoteCallResponse(
+							schema::v1::light::RemoteCallResponse { proof: Some(proof) },
+						)) |
+						Some(schema::v1::light::response::Response::RemoteReadResponse(
+							schema::v1::light::RemoteReadResponse { proof: Some(proof) },
+						)),
+				}) => proof,
+				Ok(_) => {
+					debug!(target: LOG_TARGET, "Peer {peer} couldn't answer the light client request");
+					continue
+				},
+				Err(e) => {
+					debug!(target: LOG_TARGET, "Invalid light client response from {peer}: {e}");
+					continue
+				},
+			};
+
+			match StorageProof::decode(&mut &proof[..])
+				.map_err(|e| ClientError::Application(Box::new(e)))
+				.and_then(&check)
+			{
+				Ok(result) => return Ok(result),
+				Err(e) => debug!(target: LOG_TARGET, "Invalid proof from {peer}: {e}"),
+			}
+		}
+
+		Err(ClientError::RemoteFetchFailed)
+	}
+
+	async fn read(&self, block: B::Hash, keys: Vec<Vec<u8>>) -> RemoteReadResult {
+		let header = self.header(block)?;
+		let state_root = *header.state_root();
+		let request = schema::v1::light::request::Request::RemoteReadRequest(
+			schema::v1::light::RemoteReadRequest { block: block.encode(), keys: keys.clone() },
+		);
+
+		self.request(*header.number(), request, |proof| {
+			sp_state_machine::read_proof_check::<HashingFor<B>, _>(state_root, proof, &keys)
+				.map_err(ClientError::from_state)
+		})
+		.await
+	}
+
+	async fn read_child(
+		&self,
+		block: B::Hash,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<Vec<u8>>,
+	) -> RemoteReadResult {
+		let child_info = match ChildType::from_prefixed_key(&storage_key) {
+			Some((ChildType::ParentKeyId, storage_key)) => ChildInfo::new_default(storage_key),
+			None => return Err(ClientError::InvalidChildStorageKey),
+		};
+		let header = self.header(block)?;
+		let state_root = *header.state_root();
+		let request = schema::v1::light::request::Request::RemoteReadChildRequest(
+			schema::v1::light::RemoteReadChildRequest {
+				block: block.encode(),
+				storage_key: storage_key.into_inner(),
+				keys: keys.clone(),
+			},
+		);
+
+		self.request(*header.number(), request, |proof| {
+			sp_state_machine::read_child_proof_check::<HashingFor<B>, _>(
+				state_root,
+				proof,
+				&child_info,
+				&keys,
+			)
+			.map_err(ClientError::from_state)
+		})
+		.await
+	}
+
+	/// Runtime code and heap pages at `block`.
+	///
+	/// The code is not part of the execution proofs, so it is read separately.
+	async fn runtime_code(
+		&self,
+		block: B::Hash,
+	) -> Result<(Arc<Vec<u8>>, Option<u64>), ClientError> {
+		if let Some((hash, code, heap_pages)) = &*self.runtime_code.lock() {
+			if *hash == block {
+				return Ok((code.clone(), *heap_pages))
+			}
+		}
+
+		let mut values = self
+			.read(block, vec![well_known_keys::CODE.to_vec(), well_known_keys::HEAP_PAGES.to_vec()])
+			.await?;
+		let code = values
+			.remove(well_known_keys::CODE)
+			.flatten()
+			.map(Arc::new)
+			.ok_or(ClientError::RuntimeCodeMissing)?;
+		let heap_pages = values
+			.remove(well_known_keys::HEAP_PAGES)
+			.flatten()
+			.and_then(|pages| u64::decode(&mut &pages[..]).ok());
+
+		*self.runtime_code.lock() = Some((block, code.clone(), heap_pages));
+		Ok((code, heap_pages))
+	}
+
+	async fn call(
+		&self,
+		block: B::Hash,
+		method: String,
+		call_data: Vec<u8>,
+	) -> Result<Vec<u8>, ClientError> {
+		let header = self.header(block)?;
+		let state_root = *header.state_root();
+		let (code, heap_pages) = self.runtime_code(block).await?;
+		let request = schema::v1::light::request::Request::RemoteCallRequest(
+			schema::v1::light::RemoteCallRequest {
+				block: block.encode(),
+				method: method.clone(),
+				data: call_data.clone(),
+			},
+		);
+
+		self.request(*header.number(), request, |proof| {
+			let code_fetcher = WrappedRuntimeCode(code.as_slice().into());
+			let runtime_code = RuntimeCode {
+				code_fetcher: &code_fetcher,
+				heap_pages,
+				hash: sp_core::blake2_256(&code).to_vec(),
+			};
+			sp_state_machine::execution_proof_check::<HashingFor<B>, _>(
+				state_root,
+				proof,
+				&mut OverlayedChanges::default(),
+				&self.executor,
+				&method,
+				&call_data,
+				&runtime_code,
+			)
+			.map_err(ClientError::from_state)
+		})
+		.await
+	}
+}
+
+impl<B, Client, Exec> RemoteFetcher<B> for LightClientRequestSender<B, Client, Exec>
+where
+	B: Block,
+	Client: HeaderBackend<B> + Send + Sync + 'static,
+	Exec: CodeExecutor + Clone + Send + Sync + 'static,
+{
+	fn remote_read(
+		&self,
+		block: B::Hash,
+		keys: Vec<Vec<u8>>,
+	) -> BoxFuture<'static, RemoteReadResult> {
+		let inner = self.inner.clone();
+		async move { inner.read(block, keys).await }.boxed()
+	}
+
+	fn remote_read_child(
+		&self,
+		block: B::Hash,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<Vec<u8>>,
+	) -> BoxFuture<'static, RemoteReadResult> {
+		let inner = self.inner.clone();
+		async move { inner.read_child(block, storage_key, keys).await }.boxed()
+	}
+
+	fn remote_call(
+		&self,
+		block: B::Hash,
+		method: String,
+		call_data: Vec<u8>,
+	) -> BoxFuture<'static, Result<Vec<u8>, ClientError>> {
+		let inner = self.inner.clone();
+		async move { inner.call(block, method, call_data).await }.boxed()
+	}
+}
@@ -626,6 +626,16 @@ pub struct NetworkConfiguration {
 	/// Maximum number of blocks per request.
 	pub max_blocks_per_request: u32,
 
+	/// Maximum number of bytes of block data served to a single peer per second.
+	///
+	/// `None` means unlimited.
+	pub block_server_peer_rate: Option<u64>,
+
+	/// Maximum number of bytes of block data served to all the peers per second.
+	///
+	/// `None` means unlimited.
+	pub block_server_budget: Option<u64>,
+
 	/// Number of peers that need to be connected before warp sync is started.
 	pub min_peers_to_start_warp_sync: Option<usize>,
 
@@ -679,6 +689,8 @@ impl NetworkConfiguration {
 			transport: TransportConfig::Normal { enable_mdns: false, allow_private_ip: true },
 			max_parallel_downloads: 5,
 			max_blocks_per_request: 64,
+			block_server_peer_rate: None,
+			block_server_budget: None,
 			min_peers_to_start_warp_sync: None,
 			sync_mode: SyncMode::Full,
 			enable_dht_random_walk: true,
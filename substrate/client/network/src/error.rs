@@ -80,6 +80,9 @@ pub enum Error {
 	/// Litep2p error.
 	#[error("Litep2p error: `{0}`")]
 	Litep2p(litep2p::Error),
+	/// The simulated network backend was created without entering a simulation.
+	#[error("No simulation entered, see `Simulation::enter`")]
+	NoSimulation,
 }
 
 // Make `Debug` use the `Display` implementation.
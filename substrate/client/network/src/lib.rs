@@ -260,11 +260,12 @@ pub mod peer_store;
 pub mod protocol_controller;
 pub mod request_responses;
 pub mod service;
+pub mod simulated;
 pub mod transport;
 pub mod types;
 pub mod utils;
 
-pub use crate::litep2p::Litep2pNetworkBackend;
+pub use crate::{litep2p::Litep2pNetworkBackend, simulated::SimulatedNetworkBackend};
 pub use event::{DhtEvent, Event};
 #[doc(inline)]
 pub use request_responses::{Config, IfDisconnected, RequestFailure};
@@ -44,7 +44,9 @@ use std::{collections::HashSet, iter, sync::Arc, task::Poll};
 
 use notifications::{Notifications, NotificationsOut};
 
-pub(crate) use notifications::ProtocolHandle;
+pub(crate) use notifications::{
+	NotificationCommand, NotificationsSinkMessage, ProtocolHandle, ValidationCallResult,
+};
 
 pub use notifications::{notification_service, NotificationsSink, ProtocolHandlePair, Ready};
 
@@ -25,7 +25,10 @@ pub use self::{
 	service::{notification_service, ProtocolHandlePair},
 };
 
-pub(crate) use self::service::ProtocolHandle;
+pub(crate) use self::{
+	handler::NotificationsSinkMessage,
+	service::{NotificationCommand, ProtocolHandle, ValidationCallResult},
+};
 
 mod behaviour;
 mod handler;
@@ -0,0 +1,1138 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Simulated network backend.
+//!
+//! [`SimulatedNetworkBackend`] runs the notification and request-response protocols of a node
+//! over a [`Simulation`] shared by all the nodes of a process, instead of over sockets. The
+//! nodes join the simulation entered with [`Simulation::enter`] when they are created, and can
+//! all reach each other unless the simulation is partitioned: listen addresses, boot nodes and
+//! discovery play no part.
+//!
+//! Messages are delivered according to the [`LinkConfig`] of their link. Link bandwidth delays
+//! messages but doesn't slow down their senders. Only the timers of `tokio` follow the virtual
+//! clock of a paused runtime: timers of other crates, e.g. the reputation decay of the peer
+//! store, keep running in real time. The DHT is not simulated, its queries always fail.
+
+use crate::{
+	bitswap::BitswapRequestHandler,
+	config::{
+		FullNetworkConfiguration, IncomingRequest, NonDefaultSetConfig, NonReservedPeerMode,
+		NotificationHandshake, OutgoingResponse, Params, RequestResponseConfig, SetConfig,
+	},
+	error::Error,
+	event::Event,
+	peer_store::{PeerStore, PeerStoreProvider},
+	protocol::{
+		NotificationCommand, NotificationsSink, NotificationsSinkMessage, ProtocolHandle,
+		ValidationCallResult,
+	},
+	service::{
+		out_events,
+		traits::{
+			Direction, NetworkBackend, NetworkService, NotificationService, ValidationResult,
+		},
+		NotificationMetrics,
+	},
+	IfDisconnected, NetworkStatus, OutboundFailure, ProtocolName, RequestFailure,
+};
+
+use codec::Encode;
+use futures::{
+	channel::oneshot,
+	future::{self, BoxFuture},
+	stream::{self, BoxStream, FuturesUnordered},
+	Future, Stream, StreamExt,
+};
+use libp2p::identity::{ed25519, Keypair};
+use prometheus_endpoint::Registry;
+use sc_client_api::BlockBackend;
+use sc_network_common::{role::Roles, ExHashT};
+use sc_network_types::PeerId;
+use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver};
+use sp_runtime::traits::Block as BlockT;
+use tokio::time::Instant;
+use tokio_stream::StreamMap;
+
+use std::{
+	collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap},
+	iter,
+	pin::Pin,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+pub use service::SimulatedNetworkService;
+pub use simulation::{LinkConfig, Simulation, SimulationGuard};
+
+use service::{BanHandle, NetworkServiceCommand};
+use simulation::{Delivery, Message};
+
+mod service;
+mod simulation;
+
+#[cfg(test)]
+mod tests;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "sub-libp2p::simulation";
+
+/// How often the free slots of the notification protocols are filled.
+const SLOT_ALLOCATION_FREQUENCY: Duration = Duration::from_secs(1);
+
+/// How long to wait before opening a substream again once it's closed or refused.
+const RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// State of a notification substream.
+#[derive(Debug)]
+enum Substream {
+	/// The substream was proposed to the peer, which didn't answer yet.
+	Opening,
+
+	/// The peer proposed the substream, which is being validated by the protocol.
+	Validating {
+		/// Negotiated protocol name.
+		negotiated: ProtocolName,
+
+		/// Handshake of the peer.
+		handshake: Vec<u8>,
+	},
+
+	/// The substream is open.
+	Open {
+		/// Which node opened the substream.
+		direction: Direction,
+
+		/// Negotiated protocol name.
+		negotiated: ProtocolName,
+	},
+}
+
+impl Substream {
+	/// Which node opened, or is opening, the substream.
+	fn direction(&self) -> Direction {
+		match self {
+			Substream::Opening => Direction::Outbound,
+			Substream::Validating { .. } => Direction::Inbound,
+			Substream::Open { direction, .. } => *direction,
+		}
+	}
+}
+
+/// Notification protocol run by the backend.
+struct NotificationProtocol {
+	/// Main protocol name.
+	name: ProtocolName,
+
+	/// Main protocol name followed by the fallback names.
+	names: Vec<ProtocolName>,
+
+	/// Local handshake.
+	handshake: Vec<u8>,
+
+	/// Maximum size of a received notification.
+	max_notification_size: u64,
+
+	/// Maximum number of inbound substreams with non-reserved peers.
+	in_peers: u32,
+
+	/// Maximum number of outbound substreams with non-reserved peers.
+	out_peers: u32,
+
+	/// Reserved peers.
+	reserved: BTreeSet<PeerId>,
+
+	/// Whether substreams with non-reserved peers are refused.
+	reserved_only: bool,
+
+	/// Handle for reporting events to the protocol.
+	handle: ProtocolHandle,
+
+	/// Substreams with the peers.
+	substreams: BTreeMap<PeerId, Substream>,
+
+	/// Peers not to open a substream with until the given instant.
+	backoff: HashMap<PeerId, Instant>,
+}
+
+impl NotificationProtocol {
+	/// Number of substreams in `direction` with non-reserved peers.
+	fn num_slots(&self, direction: Direction) -> u32 {
+		self.substreams
+			.iter()
+			.filter(|(peer, substream)| {
+				!self.reserved.contains(*peer) && substream.direction() == direction
+			})
+			.count() as u32
+	}
+}
+
+/// Request sent to a peer, waiting for its response.
+struct OutboundRequest {
+	/// Peer the request was sent to.
+	peer: PeerId,
+
+	/// `oneshot::Sender` for sending the response.
+	tx: oneshot::Sender<Result<(Vec<u8>, ProtocolName), RequestFailure>>,
+
+	/// Request and protocol to use if the peer doesn't support the protocol.
+	fallback_request: Option<(Vec<u8>, ProtocolName)>,
+}
+
+/// Response of a local protocol to a request of a peer.
+struct InboundResponse {
+	/// Peer the request was received from.
+	peer: PeerId,
+
+	/// Identifier of the request.
+	id: u64,
+
+	/// Protocol name the request was sent with.
+	protocol: ProtocolName,
+
+	/// Maximum size of the response.
+	max_response_size: u64,
+
+	/// Response, `None` if the protocol dropped the request.
+	response: Option<OutgoingResponse>,
+}
+
+/// Network backend running over a [`Simulation`].
+pub struct SimulatedNetworkBackend {
+	/// Simulation the node is part of.
+	simulation: Simulation,
+
+	/// Identifier of the node in the simulation.
+	node_id: u64,
+
+	/// Local peer ID.
+	local_peer_id: PeerId,
+
+	/// `NetworkService` implementation for the simulated network.
+	network_service: Arc<SimulatedNetworkService>,
+
+	/// RX channel for receiving commands from `SimulatedNetworkService`.
+	cmd_rx: TracingUnboundedReceiver<NetworkServiceCommand>,
+
+	/// RX channel for receiving the messages of the peers.
+	inbox: TracingUnboundedReceiver<Delivery>,
+
+	/// Received messages, waiting to be delivered.
+	queue: BinaryHeap<Delivery>,
+
+	/// Peers which can be reached.
+	reachable: BTreeSet<PeerId>,
+
+	/// Notification protocols, the block announce protocol first.
+	notification_protocols: Vec<NotificationProtocol>,
+
+	/// Index of the notification protocol of each protocol name, fallback names included.
+	notification_names: HashMap<ProtocolName, usize>,
+
+	/// Commands of the notification protocols, by protocol index.
+	command_streams: StreamMap<usize, Box<dyn Stream<Item = NotificationCommand> + Send + Unpin>>,
+
+	/// Messages written to the `NotificationsSink`s of the open substreams.
+	sinks: StreamMap<(usize, PeerId), BoxStream<'static, NotificationsSinkMessage>>,
+
+	/// Inbound substreams being validated by their protocol.
+	pending_validations: FuturesUnordered<BoxFuture<'static, (usize, PeerId, bool)>>,
+
+	/// Request-response protocols, by main protocol name.
+	request_protocols: HashMap<ProtocolName, RequestResponseConfig>,
+
+	/// Main name of the request-response protocol of each protocol name, fallback names included.
+	request_names: HashMap<ProtocolName, ProtocolName>,
+
+	/// Identifier of the next outbound request.
+	next_request_id: u64,
+
+	/// Outbound requests waiting for their response.
+	outbound_requests: HashMap<u64, OutboundRequest>,
+
+	/// Timeouts of the outbound requests.
+	request_timeouts: FuturesUnordered<BoxFuture<'static, u64>>,
+
+	/// Inbound requests waiting for the response of their protocol.
+	inbound_requests: FuturesUnordered<BoxFuture<'static, InboundResponse>>,
+
+	/// Handle to `PeerStore`.
+	peer_store_handle: Arc<dyn PeerStoreProvider>,
+
+	/// Event streams.
+	event_streams: out_events::OutChannels,
+
+	/// Number of peers with an open block announce substream.
+	num_connected: Arc<AtomicUsize>,
+
+	/// Bytes received so far.
+	total_bytes_inbound: u64,
+
+	/// Bytes sent so far.
+	total_bytes_outbound: u64,
+}
+
+impl SimulatedNetworkBackend {
+	/// Send `message` to `peer`, losing it according to the link if `lossy`.
+	fn send(&mut self, peer: PeerId, message: Message, lossy: bool) {
+		self.total_bytes_outbound += message.size() as u64;
+		self.simulation.send(self.local_peer_id, peer, message, lossy);
+	}
+
+	/// Whether a notification substream is open with `peer`.
+	fn is_connected(&self, peer: &PeerId) -> bool {
+		self.notification_protocols
+			.iter()
+			.any(|protocol| matches!(protocol.substreams.get(peer), Some(Substream::Open { .. })))
+	}
+
+	/// Update the number of peers with an open block announce substream.
+	fn update_num_connected(&self, index: usize) {
+		if index == 0 {
+			let num_peers = self.notification_protocols[0].handle.num_peers();
+			self.num_connected.store(num_peers, Ordering::Relaxed);
+		}
+	}
+
+	/// Propose a substream of protocol `index` to `peer`.
+	fn open_outbound(&mut self, index: usize, peer: PeerId) {
+		let protocol = &mut self.notification_protocols[index];
+		if !self.reachable.contains(&peer) || protocol.substreams.contains_key(&peer) {
+			return
+		}
+
+		protocol.substreams.insert(peer, Substream::Opening);
+		let message = Message::OpenSubstream {
+			protocols: protocol.names.clone(),
+			handshake: protocol.handshake.clone(),
+		};
+		self.send(peer, message, false);
+	}
+
+	/// Open the substream of protocol `index` with `peer`, reporting it to the protocol.
+	fn open(
+		&mut self,
+		index: usize,
+		peer: PeerId,
+		direction: Direction,
+		negotiated: ProtocolName,
+		handshake: Vec<u8>,
+	) {
+		let protocol = &mut self.notification_protocols[index];
+		let negotiated_fallback = (negotiated != protocol.name).then(|| negotiated.clone());
+		let (sink, async_rx, sync_rx) = NotificationsSink::new(peer.into());
+
+		log::debug!(target: LOG_TARGET, "{}: substream opened with {peer}", protocol.name);
+
+		protocol.substreams.insert(peer, Substream::Open { direction, negotiated });
+		let _ = protocol.handle.report_substream_opened(
+			peer.into(),
+			direction,
+			handshake,
+			negotiated_fallback,
+			sink,
+		);
+		self.sinks.insert((index, peer), Box::pin(stream::select(async_rx, sync_rx)));
+		self.update_num_connected(index);
+	}
+
+	/// Forget the substream of protocol `index` with `peer`, reporting it to the protocol if it
+	/// was open.
+	fn close(&mut self, index: usize, peer: PeerId) -> Option<Substream> {
+		let protocol = &mut self.notification_protocols[index];
+		let substream = protocol.substreams.remove(&peer);
+
+		if let Some(Substream::Open { .. }) = substream {
+			log::debug!(target: LOG_TARGET, "{}: substream closed with {peer}", protocol.name);
+
+			let _ = protocol.handle.report_substream_closed(peer.into());
+			self.sinks.remove(&(index, peer));
+			self.update_num_connected(index);
+		}
+
+		substream
+	}
+
+	/// Close the substream of protocol `index` with `peer` and let `peer` know.
+	fn disconnect(&mut self, index: usize, peer: PeerId) {
+		match self.close(index, peer) {
+			Some(Substream::Open { negotiated, .. }) =>
+				self.send(peer, Message::CloseSubstream { protocol: negotiated }, false),
+			Some(Substream::Validating { negotiated, .. }) =>
+				self.send(peer, Message::SubstreamRefused { protocol: negotiated }, false),
+			// The substream is closed when the peer accepts it.
+			Some(Substream::Opening) | None => {},
+		}
+
+		self.notification_protocols[index]
+			.backoff
+			.insert(peer, Instant::now() + RETRY_BACKOFF);
+	}
+
+	/// Fill the free slots of protocol `index`, reserved peers first.
+	fn allocate_slots(&mut self, index: usize) {
+		let now = Instant::now();
+		let protocol = &mut self.notification_protocols[index];
+		protocol.backoff.retain(|_, until| *until > now);
+
+		let protocol = &self.notification_protocols[index];
+		let can_open = |peer: &&PeerId| {
+			let peer = *peer;
+			self.reachable.contains(peer) &&
+				!protocol.substreams.contains_key(peer) &&
+				!protocol.backoff.contains_key(peer) &&
+				!self.peer_store_handle.is_banned(peer)
+		};
+		let free_slots = if protocol.reserved_only {
+			0
+		} else {
+			protocol.out_peers.saturating_sub(protocol.num_slots(Direction::Outbound))
+		};
+
+		let peers: Vec<PeerId> = protocol
+			.reserved
+			.iter()
+			.filter(can_open)
+			.chain(
+				self.reachable
+					.iter()
+					.filter(|peer| !protocol.reserved.contains(*peer))
+					.filter(can_open)
+					.take(free_slots as usize),
+			)
+			.copied()
+			.collect();
+
+		for peer in peers {
+			self.open_outbound(index, peer);
+		}
+	}
+
+	/// Handle a message delivered by the simulation.
+	fn on_message(&mut self, from: PeerId, message: Message) {
+		self.total_bytes_inbound += message.size() as u64;
+
+		match message {
+			Message::Reachable => {
+				self.reachable.insert(from);
+				for index in 0..self.notification_protocols.len() {
+					self.allocate_slots(index);
+				}
+			},
+			Message::Unreachable => self.on_unreachable(from),
+			Message::OpenSubstream { protocols, handshake } =>
+				self.on_open_substream(from, protocols, handshake),
+			Message::SubstreamOpened { protocol, handshake } => {
+				let Some(&index) = self.notification_names.get(&protocol) else { return };
+
+				match self.notification_protocols[index].substreams.get(&from) {
+					Some(Substream::Opening) =>
+						self.open(index, from, Direction::Outbound, protocol, handshake),
+					// The substream was closed while the peer was accepting it.
+					None => self.send(from, Message::CloseSubstream { protocol }, false),
+					Some(substream) => log::debug!(
+						target: LOG_TARGET,
+						"{protocol}: {from} accepted a substream in state {substream:?}",
+					),
+				}
+			},
+			Message::SubstreamRefused { protocol } => {
+				let Some(&index) = self.notification_names.get(&protocol) else { return };
+				let protocol = &mut self.notification_protocols[index];
+
+				if let Some(Substream::Opening) = protocol.substreams.get(&from) {
+					protocol.substreams.remove(&from);
+					protocol.backoff.insert(from, Instant::now() + RETRY_BACKOFF);
+				}
+			},
+			Message::CloseSubstream { protocol } => {
+				let Some(&index) = self.notification_names.get(&protocol) else { return };
+
+				if let Some(Substream::Open { .. }) =
+					self.notification_protocols[index].substreams.get(&from)
+				{
+					self.close(index, from);
+					self.notification_protocols[index]
+						.backoff
+						.insert(from, Instant::now() + RETRY_BACKOFF);
+				}
+			},
+			Message::Notification { protocol, payload } => {
+				let Some(&index) = self.notification_names.get(&protocol) else { return };
+				let protocol = &mut self.notification_protocols[index];
+
+				if !matches!(protocol.substreams.get(&from), Some(Substream::Open { .. })) {
+					return
+				}
+
+				if payload.len() as u64 > protocol.max_notification_size {
+					log::debug!(
+						target: LOG_TARGET,
+						"{}: notification of {} bytes from {from} is too large",
+						protocol.name,
+						payload.len(),
+					);
+					self.disconnect(index, from);
+				} else {
+					let _ = protocol.handle.report_notification_received(from.into(), payload);
+				}
+			},
+			Message::Request { id, protocol, payload } =>
+				self.on_inbound_request(from, id, protocol, payload),
+			Message::Response { id, protocol, result } =>
+				self.on_response(from, id, protocol, result),
+		}
+	}
+
+	/// `peer` can't be reached anymore: close its substreams and fail its requests.
+	fn on_unreachable(&mut self, peer: PeerId) {
+		self.reachable.remove(&peer);
+		self.queue.retain(|delivery| delivery.from != peer);
+
+		for index in 0..self.notification_protocols.len() {
+			self.close(index, peer);
+		}
+
+		let mut ids: Vec<u64> = self
+			.outbound_requests
+			.iter()
+			.filter_map(|(id, request)| (request.peer == peer).then_some(*id))
+			.collect();
+		ids.sort_unstable();
+
+		for id in ids {
+			if let Some(request) = self.outbound_requests.remove(&id) {
+				let _ = request
+					.tx
+					.send(Err(RequestFailure::Network(OutboundFailure::ConnectionClosed)));
+			}
+		}
+	}
+
+	/// `peer` proposed a substream of one of `protocols`.
+	fn on_open_substream(
+		&mut self,
+		peer: PeerId,
+		protocols: Vec<ProtocolName>,
+		handshake: Vec<u8>,
+	) {
+		let Some((index, negotiated)) = protocols
+			.iter()
+			.find_map(|name| self.notification_names.get(name).map(|index| (*index, name.clone())))
+		else {
+			if let Some(protocol) = protocols.into_iter().next() {
+				self.send(peer, Message::SubstreamRefused { protocol }, false);
+			}
+			return
+		};
+
+		let protocol = &mut self.notification_protocols[index];
+		match protocol.substreams.get(&peer) {
+			None => {},
+			// Both nodes proposed the substream at the same time: the one proposed by the node
+			// with the lowest peer ID is kept.
+			Some(Substream::Opening) if self.local_peer_id < peer => return,
+			Some(Substream::Opening) => {
+				protocol.substreams.remove(&peer);
+			},
+			Some(substream) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"{negotiated}: {peer} proposed a substream in state {substream:?}",
+				);
+				return
+			},
+		}
+
+		let accept = !self.peer_store_handle.is_banned(&peer) &&
+			(protocol.reserved.contains(&peer) ||
+				(!protocol.reserved_only &&
+					protocol.num_slots(Direction::Inbound) < protocol.in_peers));
+		if !accept {
+			self.send(peer, Message::SubstreamRefused { protocol: negotiated }, false);
+			return
+		}
+
+		let validation: BoxFuture<'static, bool> =
+			match protocol.handle.report_incoming_substream(peer.into(), handshake.clone()) {
+				Ok(ValidationCallResult::WaitForValidation(rx)) =>
+					Box::pin(async move { matches!(rx.await, Ok(ValidationResult::Accept)) }),
+				Ok(ValidationCallResult::Delegated) => Box::pin(future::ready(true)),
+				Err(()) => {
+					self.send(peer, Message::SubstreamRefused { protocol: negotiated }, false);
+					return
+				},
+			};
+
+		protocol
+			.substreams
+			.insert(peer, Substream::Validating { negotiated, handshake });
+		self.pending_validations
+			.push(Box::pin(async move { (index, peer, validation.await) }));
+	}
+
+	/// The protocol `index` validated the substream proposed by `peer`.
+	fn on_validation(&mut self, index: usize, peer: PeerId, accepted: bool) {
+		let protocol = &mut self.notification_protocols[index];
+		if !matches!(protocol.substreams.get(&peer), Some(Substream::Validating { .. })) {
+			return
+		}
+		let Some(Substream::Validating { negotiated, handshake }) =
+			protocol.substreams.remove(&peer)
+		else {
+			return
+		};
+
+		if accepted {
+			let message = Message::SubstreamOpened {
+				protocol: negotiated.clone(),
+				handshake: protocol.handshake.clone(),
+			};
+			self.send(peer, message, false);
+			self.open(index, peer, Direction::Inbound, negotiated, handshake);
+		} else {
+			self.send(peer, Message::SubstreamRefused { protocol: negotiated }, false);
+		}
+	}
+
+	/// Handle a command of the notification protocol `index`.
+	fn on_protocol_command(&mut self, index: usize, command: NotificationCommand) {
+		match command {
+			NotificationCommand::SetHandshake(handshake) =>
+				self.notification_protocols[index].handshake = handshake,
+			NotificationCommand::OpenSubstream(peer) => self.open_outbound(index, peer.into()),
+			NotificationCommand::CloseSubstream(peer) => self.disconnect(index, peer.into()),
+		}
+	}
+
+	/// Handle a message written to the sink of the substream of protocol `index` with `peer`.
+	fn on_sink_message(&mut self, index: usize, peer: PeerId, message: NotificationsSinkMessage) {
+		match message {
+			NotificationsSinkMessage::Notification { message } => {
+				if let Some(Substream::Open { negotiated, .. }) =
+					self.notification_protocols[index].substreams.get(&peer)
+				{
+					let message =
+						Message::Notification { protocol: negotiated.clone(), payload: message };
+					self.send(peer, message, true);
+				}
+			},
+			NotificationsSinkMessage::ForceClose => self.disconnect(index, peer),
+		}
+	}
+
+	/// Send a request to `peer`.
+	fn start_request(
+		&mut self,
+		peer: PeerId,
+		protocol: ProtocolName,
+		request: Vec<u8>,
+		fallback_request: Option<(Vec<u8>, ProtocolName)>,
+		tx: oneshot::Sender<Result<(Vec<u8>, ProtocolName), RequestFailure>>,
+		connect: IfDisconnected,
+	) {
+		let Some(config) = self.request_protocols.get(&protocol) else {
+			let _ = tx.send(Err(RequestFailure::UnknownProtocol));
+			return
+		};
+
+		if !self.reachable.contains(&peer) {
+			let _ = tx.send(Err(if connect.should_connect() {
+				RequestFailure::Network(OutboundFailure::DialFailure)
+			} else {
+				RequestFailure::NotConnected
+			}));
+			return
+		}
+		if !connect.should_connect() && !self.is_connected(&peer) {
+			let _ = tx.send(Err(RequestFailure::NotConnected));
+			return
+		}
+
+		let id = self.next_request_id;
+		self.next_request_id += 1;
+
+		let deadline = Instant::now() + config.request_timeout;
+		self.request_timeouts.push(Box::pin(async move {
+			tokio::time::sleep_until(deadline).await;
+			id
+		}));
+		self.outbound_requests
+			.insert(id, OutboundRequest { peer, tx, fallback_request });
+		self.send(peer, Message::Request { id, protocol, payload: request }, true);
+	}
+
+	/// `peer` answered the outbound request `id`.
+	fn on_response(
+		&mut self,
+		peer: PeerId,
+		id: u64,
+		protocol: ProtocolName,
+		result: Result<Vec<u8>, RequestFailure>,
+	) {
+		// The request may have timed out already.
+		if !self.outbound_requests.get(&id).is_some_and(|request| request.peer == peer) {
+			return
+		}
+		let Some(OutboundRequest { tx, fallback_request, .. }) = self.outbound_requests.remove(&id)
+		else {
+			return
+		};
+
+		match (result, fallback_request) {
+			(
+				Err(RequestFailure::Network(OutboundFailure::UnsupportedProtocols)),
+				Some((request, fallback)),
+			) => self.start_request(peer, fallback, request, None, tx, IfDisconnected::TryConnect),
+			(result, _) => {
+				let _ = tx.send(result.map(|response| (response, protocol)));
+			},
+		}
+	}
+
+	/// `peer` sent the request `id`.
+	fn on_inbound_request(
+		&mut self,
+		peer: PeerId,
+		id: u64,
+		protocol: ProtocolName,
+		payload: Vec<u8>,
+	) {
+		let Some((inbound_queue, max_request_size, max_response_size)) = self
+			.request_names
+			.get(&protocol)
+			.and_then(|name| self.request_protocols.get(name))
+			.and_then(|config| {
+				let inbound_queue = config.inbound_queue.clone()?;
+				Some((inbound_queue, config.max_request_size, config.max_response_size))
+			})
+		else {
+			let result = Err(RequestFailure::Network(OutboundFailure::UnsupportedProtocols));
+			self.send(peer, Message::Response { id, protocol, result }, true);
+			return
+		};
+
+		if self.peer_store_handle.is_banned(&peer) || payload.len() as u64 > max_request_size {
+			let result = Err(RequestFailure::Refused);
+			self.send(peer, Message::Response { id, protocol, result }, true);
+			return
+		}
+
+		let (pending_response, rx) = oneshot::channel();
+		if inbound_queue
+			.try_send(IncomingRequest { peer, payload, pending_response })
+			.is_err()
+		{
+			log::debug!(target: LOG_TARGET, "{protocol}: inbound queue full, dropping request");
+
+			let result = Err(RequestFailure::Refused);
+			self.send(peer, Message::Response { id, protocol, result }, true);
+			return
+		}
+
+		self.inbound_requests.push(Box::pin(async move {
+			InboundResponse { peer, id, protocol, max_response_size, response: rx.await.ok() }
+		}));
+	}
+
+	/// A local protocol answered the request of a peer.
+	fn on_inbound_response(&mut self, response: InboundResponse) {
+		let InboundResponse { peer, id, protocol, max_response_size, response } = response;
+		let Some(OutgoingResponse { result, reputation_changes, sent_feedback }) = response else {
+			let result = Err(RequestFailure::Refused);
+			self.send(peer, Message::Response { id, protocol, result }, true);
+			return
+		};
+
+		for change in reputation_changes {
+			self.peer_store_handle.report_peer(peer, change);
+		}
+
+		let result = match result {
+			Ok(response) if response.len() as u64 > max_response_size => {
+				log::debug!(
+					target: LOG_TARGET,
+					"{protocol}: response of {} bytes is too large",
+					response.len(),
+				);
+				Err(RequestFailure::Refused)
+			},
+			Ok(response) => Ok(response),
+			Err(()) => Err(RequestFailure::Refused),
+		};
+		self.send(peer, Message::Response { id, protocol, result }, true);
+
+		if let Some(sent_feedback) = sent_feedback {
+			let _ = sent_feedback.send(());
+		}
+	}
+
+	/// The outbound request `id` timed out.
+	fn on_request_timeout(&mut self, id: u64) {
+		if let Some(request) = self.outbound_requests.remove(&id) {
+			let _ = request.tx.send(Err(RequestFailure::Network(OutboundFailure::Timeout)));
+		}
+	}
+
+	/// Index of the notification protocol called `protocol`.
+	fn protocol_index(&self, protocol: &ProtocolName) -> Option<usize> {
+		let index = self.notification_names.get(protocol).copied();
+		if index.is_none() {
+			log::warn!(target: LOG_TARGET, "unknown notification protocol: {protocol}");
+		}
+		index
+	}
+
+	/// Close the substreams of protocol `index` with non-reserved peers if it's reserved-only.
+	fn disconnect_non_reserved(&mut self, index: usize) {
+		let protocol = &self.notification_protocols[index];
+		if !protocol.reserved_only {
+			return
+		}
+
+		let peers: Vec<PeerId> = protocol
+			.substreams
+			.keys()
+			.filter(|peer| !protocol.reserved.contains(*peer))
+			.copied()
+			.collect();
+		for peer in peers {
+			self.disconnect(index, peer);
+		}
+	}
+
+	/// Handle a command of `SimulatedNetworkService`.
+	fn on_command(&mut self, command: NetworkServiceCommand) {
+		match command {
+			NetworkServiceCommand::DhtQueryFailed { event } =>
+				self.event_streams.send(Event::Dht(event)),
+			NetworkServiceCommand::Status { tx } => {
+				let _ = tx.send(NetworkStatus {
+					num_connected_peers: self.num_connected.load(Ordering::Relaxed),
+					total_bytes_inbound: self.total_bytes_inbound,
+					total_bytes_outbound: self.total_bytes_outbound,
+				});
+			},
+			NetworkServiceCommand::SetReservedPeers { protocol, peers } => {
+				let Some(index) = self.protocol_index(&protocol) else { return };
+
+				self.notification_protocols[index].reserved = peers.into_iter().collect();
+				self.disconnect_non_reserved(index);
+				self.allocate_slots(index);
+			},
+			NetworkServiceCommand::AddReservedPeers { protocol, peers } => {
+				let Some(index) = self.protocol_index(&protocol) else { return };
+
+				self.notification_protocols[index].reserved.extend(peers);
+				self.allocate_slots(index);
+			},
+			NetworkServiceCommand::RemoveReservedPeers { protocol, peers } => {
+				let Some(index) = self.protocol_index(&protocol) else { return };
+
+				self.notification_protocols[index].reserved.retain(|peer| !peers.contains(peer));
+				self.disconnect_non_reserved(index);
+			},
+			NetworkServiceCommand::SetReservedOnly { protocol, reserved_only } => {
+				let Some(index) = self.protocol_index(&protocol) else { return };
+
+				self.notification_protocols[index].reserved_only = reserved_only;
+				self.disconnect_non_reserved(index);
+				self.allocate_slots(index);
+			},
+			NetworkServiceCommand::ReservedPeers { protocol, tx } => {
+				let Some(index) = self.protocol_index(&protocol) else { return };
+
+				let _ =
+					tx.send(self.notification_protocols[index].reserved.iter().copied().collect());
+			},
+			NetworkServiceCommand::DisconnectPeer { protocol, peer } => {
+				let Some(index) = self.protocol_index(&protocol) else { return };
+
+				self.disconnect(index, peer);
+			},
+			NetworkServiceCommand::DisconnectBannedPeer { peer } =>
+				for index in 0..self.notification_protocols.len() {
+					self.disconnect(index, peer);
+				},
+			NetworkServiceCommand::Request {
+				peer,
+				protocol,
+				request,
+				fallback_request,
+				tx,
+				connect,
+			} => self.start_request(peer, protocol, request, fallback_request, tx, connect),
+			NetworkServiceCommand::EventStream { tx } => self.event_streams.push(tx),
+		}
+	}
+
+	/// Deliver the queued messages which reached the node.
+	fn deliver_due(&mut self) {
+		let now = Instant::now();
+
+		while self.queue.peek().is_some_and(|delivery| delivery.at <= now) {
+			let Delivery { from, message, .. } = self.queue.pop().expect("peeked above; qed");
+			self.on_message(from, message);
+		}
+	}
+}
+
+impl Drop for SimulatedNetworkBackend {
+	fn drop(&mut self) {
+		self.simulation.remove_node(&self.local_peer_id, self.node_id);
+	}
+}
+
+/// Sleep until `at`, forever if `None`.
+async fn sleep_until(at: Option<Instant>) {
+	match at {
+		Some(at) => tokio::time::sleep_until(at).await,
+		None => future::pending().await,
+	}
+}
+
+#[async_trait::async_trait]
+impl<B: BlockT + 'static, H: ExHashT> NetworkBackend<B, H> for SimulatedNetworkBackend {
+	type NotificationProtocolConfig = NonDefaultSetConfig;
+	type RequestResponseProtocolConfig = RequestResponseConfig;
+	type NetworkService<Block, Hash> = Arc<SimulatedNetworkService>;
+	type PeerStore = PeerStore;
+	type BitswapConfig = RequestResponseConfig;
+
+	fn new(params: Params<B, H, Self>) -> Result<Self, Error>
+	where
+		Self: Sized,
+	{
+		let simulation = Simulation::current().ok_or(Error::NoSimulation)?;
+		let peer_store_handle = params.network_config.peer_store_handle();
+		let FullNetworkConfiguration {
+			notification_protocols,
+			request_response_protocols,
+			network_config,
+			..
+		} = params.network_config;
+
+		let local_identity: ed25519::Keypair =
+			network_config.node_key.clone().into_keypair()?.into();
+		let keypair = Keypair::from(local_identity);
+		let local_peer_id: PeerId = keypair.public().to_peer_id().into();
+
+		log::info!(target: LOG_TARGET, "🏷  Local node identity is: {local_peer_id}");
+
+		let roles = Roles::from(&params.role).encode();
+		let block_announce_protocol = params.block_announce_config.protocol_name().clone();
+		let mut protocols = Vec::new();
+		let mut notification_names = HashMap::new();
+		let mut command_streams = StreamMap::new();
+
+		for (index, config) in iter::once(params.block_announce_config)
+			.chain(notification_protocols)
+			.enumerate()
+		{
+			let name = config.protocol_name().clone();
+			let names: Vec<ProtocolName> =
+				iter::once(name.clone()).chain(config.fallback_names().cloned()).collect();
+			let handshake = config
+				.handshake()
+				.as_ref()
+				.map_or_else(|| roles.clone(), |handshake| handshake.to_vec());
+			let max_notification_size = config.max_notification_size();
+			let set_config = config.set_config().clone();
+			let (mut handle, command_stream) = config.take_protocol_handle().split();
+
+			handle.set_metrics(params.notification_metrics.clone());
+			for name in &names {
+				notification_names.entry(name.clone()).or_insert(index);
+			}
+			command_streams.insert(index, command_stream);
+
+			protocols.push(NotificationProtocol {
+				name,
+				names,
+				handshake,
+				max_notification_size,
+				in_peers: set_config.in_peers,
+				out_peers: set_config.out_peers,
+				reserved: set_config
+					.reserved_nodes
+					.iter()
+					.map(|node| node.peer_id)
+					.filter(|peer| *peer != local_peer_id)
+					.collect(),
+				reserved_only: set_config.non_reserved_mode == NonReservedPeerMode::Deny,
+				handle,
+				substreams: BTreeMap::new(),
+				backoff: HashMap::new(),
+			});
+		}
+
+		let mut request_protocols = HashMap::new();
+		let mut request_names = HashMap::new();
+
+		for config in request_response_protocols.into_iter().chain(params.bitswap_config) {
+			if request_protocols.contains_key(&config.name) {
+				return Err(Error::DuplicateRequestResponseProtocol { protocol: config.name })
+			}
+
+			for name in iter::once(&config.name).chain(config.fallback_names.iter()) {
+				request_names.entry(name.clone()).or_insert_with(|| config.name.clone());
+			}
+			request_protocols.insert(config.name.clone(), config);
+		}
+
+		let (cmd_tx, cmd_rx) = tracing_unbounded("mpsc_simulated_network_worker", 100_000);
+		let num_connected = Arc::new(AtomicUsize::new(0));
+		let network_service = Arc::new(SimulatedNetworkService::new(
+			local_peer_id,
+			keypair,
+			cmd_tx.clone(),
+			Arc::clone(&peer_store_handle),
+			block_announce_protocol,
+			Arc::clone(&num_connected),
+		));
+
+		peer_store_handle.register_protocol(Arc::new(BanHandle { cmd_tx }));
+
+		let (inbox_tx, inbox) = tracing_unbounded("mpsc_simulated_network_inbox", 100_000);
+		let node_id = simulation.add_node(local_peer_id, inbox_tx);
+
+		Ok(Self {
+			simulation,
+			node_id,
+			local_peer_id,
+			network_service,
+			cmd_rx,
+			inbox,
+			queue: BinaryHeap::new(),
+			reachable: BTreeSet::new(),
+			notification_protocols: protocols,
+			notification_names,
+			command_streams,
+			sinks: StreamMap::new(),
+			pending_validations: FuturesUnordered::new(),
+			request_protocols,
+			request_names,
+			next_request_id: 0,
+			outbound_requests: HashMap::new(),
+			request_timeouts: FuturesUnordered::new(),
+			inbound_requests: FuturesUnordered::new(),
+			peer_store_handle,
+			event_streams: out_events::OutChannels::new(None)?,
+			num_connected,
+			total_bytes_inbound: 0,
+			total_bytes_outbound: 0,
+		})
+	}
+
+	fn network_service(&self) -> Arc<dyn NetworkService> {
+		Arc::clone(&self.network_service) as Arc<dyn NetworkService>
+	}
+
+	fn peer_store(bootnodes: Vec<PeerId>, metrics_registry: Option<Registry>) -> Self::PeerStore {
+		PeerStore::new(bootnodes.into_iter().map(From::from).collect(), metrics_registry)
+	}
+
+	fn register_notification_metrics(registry: Option<&Registry>) -> NotificationMetrics {
+		NotificationMetrics::new(registry)
+	}
+
+	fn bitswap_server(
+		client: Arc<dyn BlockBackend<B> + Send + Sync>,
+	) -> (Pin<Box<dyn Future<Output = ()> + Send>>, Self::BitswapConfig) {
+		let (handler, protocol_config) = BitswapRequestHandler::new(client);
+
+		(Box::pin(async move { handler.run().await }), protocol_config)
+	}
+
+	fn notification_config(
+		protocol_name: ProtocolName,
+		fallback_names: Vec<ProtocolName>,
+		max_notification_size: u64,
+		handshake: Option<NotificationHandshake>,
+		set_config: SetConfig,
+		_metrics: NotificationMetrics,
+		_peerstore_handle: Arc<dyn PeerStoreProvider>,
+	) -> (Self::NotificationProtocolConfig, Box<dyn NotificationService>) {
+		NonDefaultSetConfig::new(
+			protocol_name,
+			fallback_names,
+			max_notification_size,
+			handshake,
+			set_config,
+		)
+	}
+
+	fn request_response_config(
+		protocol_name: ProtocolName,
+		fallback_names: Vec<ProtocolName>,
+		max_request_size: u64,
+		max_response_size: u64,
+		request_timeout: Duration,
+		inbound_queue: Option<async_channel::Sender<IncomingRequest>>,
+	) -> Self::RequestResponseProtocolConfig {
+		Self::RequestResponseProtocolConfig {
+			name: protocol_name,
+			fallback_names,
+			max_request_size,
+			max_response_size,
+			request_timeout,
+			inbound_queue,
+		}
+	}
+
+	async fn run(mut self) {
+		log::debug!(target: LOG_TARGET, "starting simulated network backend");
+
+		let mut slot_allocation = tokio::time::interval(SLOT_ALLOCATION_FREQUENCY);
+
+		loop {
+			let next_delivery = self.queue.peek().map(|delivery| delivery.at);
+
+			// Branches are polled in order so that the node behaves the same from one run to
+			// another.
+			tokio::select! {
+				biased;
+
+				delivery = self.inbox.next() => match delivery {
+					Some(delivery) => self.queue.push(delivery),
+					None => return,
+				},
+				_ = sleep_until(next_delivery) => self.deliver_due(),
+				command = self.cmd_rx.next() => match command {
+					Some(command) => self.on_command(command),
+					None => return,
+				},
+				Some((index, command)) = self.command_streams.next() =>
+					self.on_protocol_command(index, command),
+				Some(((index, peer), message)) = self.sinks.next() =>
+					self.on_sink_message(index, peer, message),
+				Some((index, peer, accepted)) = self.pending_validations.next() =>
+					self.on_validation(index, peer, accepted),
+				Some(response) = self.inbound_requests.next() => self.on_inbound_response(response),
+				Some(id) = self.request_timeouts.next() => self.on_request_timeout(id),
+				_ = slot_allocation.tick() =>
+					for index in 0..self.notification_protocols.len() {
+						self.allocate_slots(index);
+					},
+			}
+		}
+	}
+}
@@ -0,0 +1,517 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! `NetworkService` implementation for the simulated network.
+
+use crate::{
+	config::MultiaddrWithPeerId,
+	event::DhtEvent,
+	network_state::NetworkState,
+	peer_store::{PeerStoreProvider, ProtocolHandle},
+	service::{out_events, signature::PublicKey},
+	Event, IfDisconnected, NetworkDHTProvider, NetworkEventStream, NetworkPeers, NetworkRequest,
+	NetworkSigner, NetworkStateInfo, NetworkStatus, NetworkStatusProvider, OutboundFailure,
+	ProtocolName, RequestFailure, Signature,
+};
+
+use codec::DecodeAll;
+use futures::{channel::oneshot, stream::BoxStream};
+use libp2p::identity::{Keypair, SigningError};
+use sc_network_common::{
+	role::{ObservedRole, Roles},
+	types::ReputationChange,
+};
+use sc_network_types::{
+	kad::{Key as KademliaKey, Record},
+	multiaddr::Multiaddr,
+	PeerId,
+};
+use sc_utils::mpsc::TracingUnboundedSender;
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Instant,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "sub-libp2p::simulation";
+
+/// Commands sent by [`SimulatedNetworkService`] to
+/// [`SimulatedNetworkBackend`](super::SimulatedNetworkBackend).
+#[derive(Debug)]
+pub enum NetworkServiceCommand {
+	/// Report the failure of a DHT query, the DHT not being simulated.
+	DhtQueryFailed {
+		/// Event reporting the failure.
+		event: DhtEvent,
+	},
+
+	/// Query network status.
+	Status {
+		/// `oneshot::Sender` for sending the status.
+		tx: oneshot::Sender<NetworkStatus>,
+	},
+
+	/// Set reserved peers for `protocol`.
+	SetReservedPeers {
+		/// Protocol.
+		protocol: ProtocolName,
+
+		/// Reserved peers.
+		peers: HashSet<PeerId>,
+	},
+
+	/// Add `peers` to `protocol`'s reserved set.
+	AddReservedPeers {
+		/// Protocol.
+		protocol: ProtocolName,
+
+		/// Reserved peers.
+		peers: HashSet<PeerId>,
+	},
+
+	/// Remove reserved peers from protocol.
+	RemoveReservedPeers {
+		/// Protocol.
+		protocol: ProtocolName,
+
+		/// Peers to remove from the reserved set.
+		peers: HashSet<PeerId>,
+	},
+
+	/// Set protocol to reserved only (true/false) mode.
+	SetReservedOnly {
+		/// Protocol.
+		protocol: ProtocolName,
+
+		/// Reserved only?
+		reserved_only: bool,
+	},
+
+	/// Get the reserved peers of `protocol`.
+	ReservedPeers {
+		/// Protocol.
+		protocol: ProtocolName,
+
+		/// `oneshot::Sender` for sending the reserved peers.
+		tx: oneshot::Sender<Vec<PeerId>>,
+	},
+
+	/// Disconnect peer from protocol.
+	DisconnectPeer {
+		/// Protocol.
+		protocol: ProtocolName,
+
+		/// Peer ID.
+		peer: PeerId,
+	},
+
+	/// Disconnect banned peer from all protocols.
+	DisconnectBannedPeer {
+		/// Peer ID.
+		peer: PeerId,
+	},
+
+	/// Send request to peer.
+	Request {
+		/// Peer ID.
+		peer: PeerId,
+
+		/// Protocol.
+		protocol: ProtocolName,
+
+		/// Request.
+		request: Vec<u8>,
+
+		/// Request and protocol to use if `protocol` isn't supported by the peer.
+		fallback_request: Option<(Vec<u8>, ProtocolName)>,
+
+		/// `oneshot::Sender` for sending the response.
+		tx: oneshot::Sender<Result<(Vec<u8>, ProtocolName), RequestFailure>>,
+
+		/// Whether to dial the peer if it's not connected.
+		connect: IfDisconnected,
+	},
+
+	/// Create event stream for DHT events.
+	EventStream {
+		/// Sender for the events.
+		tx: out_events::Sender,
+	},
+}
+
+/// `NetworkService` implementation for the simulated network.
+#[derive(Debug, Clone)]
+pub struct SimulatedNetworkService {
+	/// Local peer ID.
+	local_peer_id: PeerId,
+
+	/// The `KeyPair` that defines the `PeerId` of the local node.
+	keypair: Keypair,
+
+	/// TX channel for sending commands to
+	/// [`SimulatedNetworkBackend`](super::SimulatedNetworkBackend).
+	cmd_tx: TracingUnboundedSender<NetworkServiceCommand>,
+
+	/// Handle to `PeerStore`.
+	peer_store_handle: Arc<dyn PeerStoreProvider>,
+
+	/// Name for the block announce protocol.
+	block_announce_protocol: ProtocolName,
+
+	/// Number of peers with an open block announce substream.
+	num_connected: Arc<AtomicUsize>,
+}
+
+impl SimulatedNetworkService {
+	/// Create new [`SimulatedNetworkService`].
+	pub fn new(
+		local_peer_id: PeerId,
+		keypair: Keypair,
+		cmd_tx: TracingUnboundedSender<NetworkServiceCommand>,
+		peer_store_handle: Arc<dyn PeerStoreProvider>,
+		block_announce_protocol: ProtocolName,
+		num_connected: Arc<AtomicUsize>,
+	) -> Self {
+		Self {
+			local_peer_id,
+			keypair,
+			cmd_tx,
+			peer_store_handle,
+			block_announce_protocol,
+			num_connected,
+		}
+	}
+
+	fn report_failed_query(&self, event: DhtEvent) {
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::DhtQueryFailed { event });
+	}
+}
+
+/// Extract the peer IDs of `addresses`, which can't include the local peer ID.
+fn peer_ids(
+	local_peer_id: &PeerId,
+	addresses: HashSet<Multiaddr>,
+) -> Result<HashSet<PeerId>, String> {
+	addresses
+		.into_iter()
+		.map(|address| match PeerId::try_from_multiaddr(&address) {
+			Some(peer) if peer == *local_peer_id =>
+				Err("Local peer ID cannot be added as a reserved peer.".to_string()),
+			Some(peer) => Ok(peer),
+			None => Err(format!("Address without peer ID: {address}")),
+		})
+		.collect()
+}
+
+impl NetworkSigner for SimulatedNetworkService {
+	fn sign_with_local_identity(&self, msg: Vec<u8>) -> Result<Signature, SigningError> {
+		let public_key = self.keypair.public();
+		let bytes = self.keypair.sign(msg.as_ref())?;
+
+		Ok(Signature { public_key: PublicKey::Libp2p(public_key), bytes })
+	}
+
+	fn verify(
+		&self,
+		peer: PeerId,
+		public_key: &Vec<u8>,
+		signature: &Vec<u8>,
+		message: &Vec<u8>,
+	) -> Result<bool, String> {
+		let public_key = libp2p::identity::PublicKey::try_decode_protobuf(public_key)
+			.map_err(|error| error.to_string())?;
+		let peer: libp2p::PeerId = peer.into();
+
+		Ok(peer == public_key.to_peer_id() && public_key.verify(message, signature))
+	}
+}
+
+impl NetworkDHTProvider for SimulatedNetworkService {
+	fn find_closest_peers(&self, target: PeerId) {
+		self.report_failed_query(DhtEvent::ClosestPeersNotFound(target));
+	}
+
+	fn get_value(&self, key: &KademliaKey) {
+		self.report_failed_query(DhtEvent::ValueNotFound(key.clone()));
+	}
+
+	fn put_value(&self, key: KademliaKey, _value: Vec<u8>) {
+		self.report_failed_query(DhtEvent::ValuePutFailed(key));
+	}
+
+	fn put_record_to(&self, record: Record, _peers: HashSet<PeerId>, _update_local_storage: bool) {
+		self.report_failed_query(DhtEvent::ValuePutFailed(record.key));
+	}
+
+	fn store_record(
+		&self,
+		_key: KademliaKey,
+		_value: Vec<u8>,
+		_publisher: Option<PeerId>,
+		_expires: Option<Instant>,
+	) {
+	}
+
+	fn start_providing(&self, key: KademliaKey) {
+		self.report_failed_query(DhtEvent::StartProvidingFailed(key));
+	}
+
+	fn stop_providing(&self, _key: KademliaKey) {}
+
+	fn get_providers(&self, key: KademliaKey) {
+		self.report_failed_query(DhtEvent::ProvidersNotFound(key));
+	}
+}
+
+#[async_trait::async_trait]
+impl NetworkStatusProvider for SimulatedNetworkService {
+	async fn status(&self) -> Result<NetworkStatus, ()> {
+		let (tx, rx) = oneshot::channel();
+		self.cmd_tx
+			.unbounded_send(NetworkServiceCommand::Status { tx })
+			.map_err(|_| ())?;
+
+		rx.await.map_err(|_| ())
+	}
+
+	async fn network_state(&self) -> Result<NetworkState, ()> {
+		Ok(NetworkState {
+			peer_id: self.local_peer_id.to_base58(),
+			listened_addresses: HashSet::new(),
+			external_addresses: HashSet::new(),
+			connected_peers: HashMap::new(),
+			not_connected_peers: HashMap::new(),
+			peerset: serde_json::json!("Unimplemented for the simulated network."),
+		})
+	}
+}
+
+#[async_trait::async_trait]
+impl NetworkPeers for SimulatedNetworkService {
+	fn set_authorized_peers(&self, peers: HashSet<PeerId>) {
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::SetReservedPeers {
+			protocol: self.block_announce_protocol.clone(),
+			peers,
+		});
+	}
+
+	fn set_authorized_only(&self, reserved_only: bool) {
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::SetReservedOnly {
+			protocol: self.block_announce_protocol.clone(),
+			reserved_only,
+		});
+	}
+
+	/// All the nodes of the simulation are known, only the peer is recorded.
+	fn add_known_address(&self, peer: PeerId, _address: Multiaddr) {
+		self.peer_store_handle.add_known_peer(peer);
+	}
+
+	fn peer_reputation(&self, peer_id: &PeerId) -> i32 {
+		self.peer_store_handle.peer_reputation(peer_id)
+	}
+
+	fn report_peer(&self, peer: PeerId, cost_benefit: ReputationChange) {
+		self.peer_store_handle.report_peer(peer, cost_benefit);
+	}
+
+	fn disconnect_peer(&self, peer: PeerId, protocol: ProtocolName) {
+		let _ = self
+			.cmd_tx
+			.unbounded_send(NetworkServiceCommand::DisconnectPeer { protocol, peer });
+	}
+
+	fn accept_unreserved_peers(&self) {
+		self.set_authorized_only(false);
+	}
+
+	fn deny_unreserved_peers(&self) {
+		self.set_authorized_only(true);
+	}
+
+	fn add_reserved_peer(&self, peer: MultiaddrWithPeerId) -> Result<(), String> {
+		if peer.peer_id == self.local_peer_id {
+			return Err("Local peer ID cannot be added as a reserved peer.".to_string())
+		}
+
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::AddReservedPeers {
+			protocol: self.block_announce_protocol.clone(),
+			peers: HashSet::from_iter([peer.peer_id]),
+		});
+
+		Ok(())
+	}
+
+	fn remove_reserved_peer(&self, peer: PeerId) {
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::RemoveReservedPeers {
+			protocol: self.block_announce_protocol.clone(),
+			peers: HashSet::from_iter([peer]),
+		});
+	}
+
+	fn set_reserved_peers(
+		&self,
+		protocol: ProtocolName,
+		peers: HashSet<Multiaddr>,
+	) -> Result<(), String> {
+		let peers = peer_ids(&self.local_peer_id, peers)?;
+		let _ = self
+			.cmd_tx
+			.unbounded_send(NetworkServiceCommand::SetReservedPeers { protocol, peers });
+
+		Ok(())
+	}
+
+	fn add_peers_to_reserved_set(
+		&self,
+		protocol: ProtocolName,
+		peers: HashSet<Multiaddr>,
+	) -> Result<(), String> {
+		let peers = peer_ids(&self.local_peer_id, peers)?;
+		let _ = self
+			.cmd_tx
+			.unbounded_send(NetworkServiceCommand::AddReservedPeers { protocol, peers });
+
+		Ok(())
+	}
+
+	fn remove_peers_from_reserved_set(
+		&self,
+		protocol: ProtocolName,
+		peers: Vec<PeerId>,
+	) -> Result<(), String> {
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::RemoveReservedPeers {
+			protocol,
+			peers: peers.into_iter().collect(),
+		});
+
+		Ok(())
+	}
+
+	fn sync_num_connected(&self) -> usize {
+		self.num_connected.load(Ordering::Relaxed)
+	}
+
+	fn peer_role(&self, peer: PeerId, handshake: Vec<u8>) -> Option<ObservedRole> {
+		match Roles::decode_all(&mut &handshake[..]) {
+			Ok(role) => Some(role.into()),
+			Err(_) => {
+				log::debug!(target: LOG_TARGET, "handshake doesn't contain peer role: {handshake:?}");
+				self.peer_store_handle.peer_role(&peer)
+			},
+		}
+	}
+
+	/// Get the list of reserved peers.
+	///
+	/// Returns an error if the `SimulatedNetworkBackend` is no longer running.
+	async fn reserved_peers(&self) -> Result<Vec<PeerId>, ()> {
+		let (tx, rx) = oneshot::channel();
+		self.cmd_tx
+			.unbounded_send(NetworkServiceCommand::ReservedPeers {
+				protocol: self.block_announce_protocol.clone(),
+				tx,
+			})
+			.map_err(|_| ())?;
+
+		rx.await.map_err(|_| ())
+	}
+}
+
+impl NetworkEventStream for SimulatedNetworkService {
+	fn event_stream(&self, stream_name: &'static str) -> BoxStream<'static, Event> {
+		let (tx, rx) = out_events::channel(stream_name, 100_000);
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::EventStream { tx });
+		Box::pin(rx)
+	}
+}
+
+impl NetworkStateInfo for SimulatedNetworkService {
+	fn external_addresses(&self) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn listen_addresses(&self) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn local_peer_id(&self) -> PeerId {
+		self.local_peer_id
+	}
+}
+
+#[async_trait::async_trait]
+impl NetworkRequest for SimulatedNetworkService {
+	async fn request(
+		&self,
+		target: PeerId,
+		protocol: ProtocolName,
+		request: Vec<u8>,
+		fallback_request: Option<(Vec<u8>, ProtocolName)>,
+		connect: IfDisconnected,
+	) -> Result<(Vec<u8>, ProtocolName), RequestFailure> {
+		let (tx, rx) = oneshot::channel();
+
+		self.start_request(target, protocol, request, fallback_request, tx, connect);
+
+		match rx.await {
+			Ok(v) => v,
+			// The channel can only be closed if the backend no longer exists, in which case all
+			// its connections are closed.
+			Err(_) => Err(RequestFailure::Network(OutboundFailure::ConnectionClosed)),
+		}
+	}
+
+	fn start_request(
+		&self,
+		peer: PeerId,
+		protocol: ProtocolName,
+		request: Vec<u8>,
+		fallback_request: Option<(Vec<u8>, ProtocolName)>,
+		tx: oneshot::Sender<Result<(Vec<u8>, ProtocolName), RequestFailure>>,
+		connect: IfDisconnected,
+	) {
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::Request {
+			peer,
+			protocol,
+			request,
+			fallback_request,
+			tx,
+			connect,
+		});
+	}
+}
+
+/// Handle disconnecting the peers banned by the `PeerStore`.
+#[derive(Debug)]
+pub(crate) struct BanHandle {
+	/// TX channel for sending commands to
+	/// [`SimulatedNetworkBackend`](super::SimulatedNetworkBackend).
+	pub cmd_tx: TracingUnboundedSender<NetworkServiceCommand>,
+}
+
+impl ProtocolHandle for BanHandle {
+	fn disconnect_peer(&self, peer: PeerId) {
+		let _ = self.cmd_tx.unbounded_send(NetworkServiceCommand::DisconnectBannedPeer { peer });
+	}
+}
@@ -0,0 +1,472 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Links between the nodes of a simulated network.
+
+use crate::{types::ProtocolName, RequestFailure};
+
+use codec::Encode;
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sc_network_types::PeerId;
+use sc_utils::mpsc::TracingUnboundedSender;
+use sp_core::hashing::blake2_64;
+use tokio::time::Instant;
+
+use std::{
+	cell::RefCell,
+	cmp::Ordering,
+	collections::{BTreeMap, HashMap},
+	marker::PhantomData,
+	sync::Arc,
+	time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "sub-libp2p::simulation";
+
+thread_local! {
+	/// Simulation the backends created on this thread join.
+	static CURRENT: RefCell<Option<Simulation>> = const { RefCell::new(None) };
+}
+
+/// Characteristics of the link from a node to another.
+#[derive(Debug, Clone, Default)]
+pub struct LinkConfig {
+	/// Time a message takes to reach the remote node once it is transmitted.
+	pub latency: Duration,
+
+	/// Bandwidth of the link in bytes per second, `None` if unlimited.
+	///
+	/// Messages are transmitted one at a time, in the order they are sent.
+	pub bandwidth: Option<u64>,
+
+	/// Probability, between 0 and 1, of losing a notification, a request or a response.
+	///
+	/// Substreams themselves are never lost, as they are opened and closed reliably.
+	pub loss: f64,
+}
+
+/// Network shared by the simulated backends.
+///
+/// Messages are delayed according to the [`LinkConfig`] of their link, and lost at random. Each
+/// directed link draws its losses from its own generator, seeded from the seed of the simulation
+/// and the identities of its ends: the same seed loses the same messages of a link, whatever
+/// happens on the other links.
+///
+/// Delays are measured with the clock of `tokio`. Within a runtime whose time is paused, e.g.
+/// `#[tokio::test(start_paused = true)]`, the clock is virtual: it advances to the next delivery
+/// as soon as all the tasks are idle, so that simulating minutes takes no time, and the nodes see
+/// their messages at exactly the same virtual times from one run to the other.
+#[derive(Clone)]
+pub struct Simulation {
+	inner: Arc<Mutex<Inner>>,
+}
+
+impl Simulation {
+	/// Create a new simulation whose random losses are drawn from `seed`.
+	pub fn new(seed: u64) -> Self {
+		Self {
+			inner: Arc::new(Mutex::new(Inner {
+				seed,
+				default_link: LinkConfig::default(),
+				links: HashMap::new(),
+				link_states: HashMap::new(),
+				groups: None,
+				nodes: BTreeMap::new(),
+				next_node_id: 0,
+				next_seq: 0,
+			})),
+		}
+	}
+
+	/// Enter the simulation on the current thread.
+	///
+	/// The [`SimulatedNetworkBackend`](super::SimulatedNetworkBackend)s created on the thread
+	/// until the guard is dropped join the simulation.
+	pub fn enter(&self) -> SimulationGuard {
+		let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+		SimulationGuard { previous, _not_send: PhantomData }
+	}
+
+	/// The simulation entered on the current thread, if any.
+	pub(crate) fn current() -> Option<Simulation> {
+		CURRENT.with(|current| current.borrow().clone())
+	}
+
+	/// Set the configuration of the links without a configuration of their own.
+	pub fn set_default_link(&self, config: LinkConfig) {
+		assert_loss(&config);
+		self.inner.lock().default_link = config;
+	}
+
+	/// Set the configuration of the links between `a` and `b`, in both directions.
+	pub fn set_link(&self, a: PeerId, b: PeerId, config: LinkConfig) {
+		assert_loss(&config);
+		let mut inner = self.inner.lock();
+		inner.links.insert((a, b), config.clone());
+		inner.links.insert((b, a), config);
+	}
+
+	/// Set the configuration of the link from `from` to `to`.
+	pub fn set_directed_link(&self, from: PeerId, to: PeerId, config: LinkConfig) {
+		assert_loss(&config);
+		self.inner.lock().links.insert((from, to), config);
+	}
+
+	/// Split the network into groups of nodes which can't reach each other.
+	///
+	/// The nodes missing from `groups` form a group of their own. The substreams between the
+	/// groups are closed, and their pending requests fail.
+	pub fn partition<G>(&self, groups: impl IntoIterator<Item = G>)
+	where
+		G: IntoIterator<Item = PeerId>,
+	{
+		let groups = groups
+			.into_iter()
+			.enumerate()
+			.flat_map(|(index, group)| group.into_iter().map(move |peer| (peer, index)))
+			.collect();
+		self.inner.lock().set_groups(Some(groups));
+	}
+
+	/// Undo [`Simulation::partition`], so that all the nodes can reach each other again.
+	pub fn heal(&self) {
+		self.inner.lock().set_groups(None);
+	}
+
+	/// Whether `a` and `b` are part of the simulation and can reach each other.
+	pub fn is_reachable(&self, a: &PeerId, b: &PeerId) -> bool {
+		let inner = self.inner.lock();
+		inner.nodes.contains_key(a) && inner.nodes.contains_key(b) && inner.is_reachable(a, b)
+	}
+
+	/// The nodes of the simulation.
+	pub fn peers(&self) -> Vec<PeerId> {
+		self.inner.lock().nodes.keys().copied().collect()
+	}
+
+	/// Add a node receiving its messages on `inbox`, replacing any node with the same identity.
+	///
+	/// Returns the identifier of the node, to pass to [`Simulation::remove_node`].
+	pub(crate) fn add_node(&self, peer: PeerId, inbox: TracingUnboundedSender<Delivery>) -> u64 {
+		let mut inner = self.inner.lock();
+		if inner.nodes.contains_key(&peer) {
+			inner.remove_node(&peer);
+		}
+
+		let id = inner.next_node_id;
+		inner.next_node_id += 1;
+		let others: Vec<PeerId> = inner.nodes.keys().copied().collect();
+		inner.nodes.insert(peer, (id, inbox));
+		for other in others {
+			if inner.is_reachable(&peer, &other) {
+				inner.notify(&peer, other, Message::Reachable);
+				inner.notify(&other, peer, Message::Reachable);
+			}
+		}
+		id
+	}
+
+	/// Remove the node `id`, closing its substreams.
+	///
+	/// Nothing happens if the node was already replaced by another with the same identity.
+	pub(crate) fn remove_node(&self, peer: &PeerId, id: u64) {
+		let mut inner = self.inner.lock();
+		if inner.nodes.get(peer).is_some_and(|(node_id, _)| *node_id == id) {
+			inner.remove_node(peer);
+		}
+	}
+
+	/// Send `message` over the link from `from` to `to`.
+	///
+	/// If `lossy`, the message may be lost according to the configuration of the link.
+	pub(crate) fn send(&self, from: PeerId, to: PeerId, message: Message, lossy: bool) {
+		self.inner.lock().send(from, to, message, lossy)
+	}
+}
+
+/// Guard returned by [`Simulation::enter`], leaving the simulation when dropped.
+#[must_use]
+pub struct SimulationGuard {
+	previous: Option<Simulation>,
+	_not_send: PhantomData<*const ()>,
+}
+
+impl Drop for SimulationGuard {
+	fn drop(&mut self) {
+		let previous = self.previous.take();
+		CURRENT.with(|current| *current.borrow_mut() = previous);
+	}
+}
+
+/// Message exchanged by the simulated backends.
+#[derive(Debug)]
+pub(crate) enum Message {
+	/// The sender joined the simulation or can be reached again.
+	Reachable,
+
+	/// The sender left the simulation or can't be reached anymore.
+	Unreachable,
+
+	/// Open a notification substream.
+	OpenSubstream {
+		/// Names the protocol may be negotiated with, in order of preference.
+		protocols: Vec<ProtocolName>,
+
+		/// Handshake of the sender.
+		handshake: Vec<u8>,
+	},
+
+	/// The substream was accepted.
+	SubstreamOpened {
+		/// Negotiated protocol name.
+		protocol: ProtocolName,
+
+		/// Handshake of the sender.
+		handshake: Vec<u8>,
+	},
+
+	/// The substream was refused.
+	SubstreamRefused {
+		/// One of the protocol names the substream was proposed with.
+		protocol: ProtocolName,
+	},
+
+	/// Close an open substream.
+	CloseSubstream {
+		/// Negotiated protocol name.
+		protocol: ProtocolName,
+	},
+
+	/// Notification sent over an open substream.
+	Notification {
+		/// Negotiated protocol name.
+		protocol: ProtocolName,
+
+		/// Notification.
+		payload: Vec<u8>,
+	},
+
+	/// Request.
+	Request {
+		/// Identifier of the request, unique for the sender.
+		id: u64,
+
+		/// Protocol name.
+		protocol: ProtocolName,
+
+		/// Request.
+		payload: Vec<u8>,
+	},
+
+	/// Response to a request.
+	Response {
+		/// Identifier of the request.
+		id: u64,
+
+		/// Protocol name the request was sent with.
+		protocol: ProtocolName,
+
+		/// Response, or why there is none.
+		result: Result<Vec<u8>, RequestFailure>,
+	},
+}
+
+impl Message {
+	/// Number of bytes the message takes on the link.
+	pub(crate) fn size(&self) -> usize {
+		match self {
+			Message::OpenSubstream { handshake, .. } |
+			Message::SubstreamOpened { handshake, .. } => handshake.len(),
+			Message::Notification { payload, .. } | Message::Request { payload, .. } =>
+				payload.len(),
+			Message::Response { result: Ok(payload), .. } => payload.len(),
+			_ => 0,
+		}
+	}
+}
+
+/// Message delivered to a node.
+#[derive(Debug)]
+pub(crate) struct Delivery {
+	/// When the message reaches the node.
+	pub at: Instant,
+
+	/// Sequence number, ordering the messages delivered at the same time.
+	pub seq: u64,
+
+	/// Sender of the message.
+	pub from: PeerId,
+
+	/// Message.
+	pub message: Message,
+}
+
+// Deliveries are ordered so that the earliest is the greatest, for use in a `BinaryHeap`.
+impl Ord for Delivery {
+	fn cmp(&self, other: &Self) -> Ordering {
+		(other.at, other.seq).cmp(&(self.at, self.seq))
+	}
+}
+
+impl PartialOrd for Delivery {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl PartialEq for Delivery {
+	fn eq(&self, other: &Self) -> bool {
+		(self.at, self.seq) == (other.at, other.seq)
+	}
+}
+
+impl Eq for Delivery {}
+
+/// State of a directed link.
+struct LinkState {
+	/// Generator of the losses.
+	rng: StdRng,
+
+	/// When the link is done transmitting the messages sent so far.
+	busy_until: Instant,
+}
+
+struct Inner {
+	/// Seed of the simulation.
+	seed: u64,
+
+	/// Configuration of the links without a configuration of their own.
+	default_link: LinkConfig,
+
+	/// Configuration of the directed links.
+	links: HashMap<(PeerId, PeerId), LinkConfig>,
+
+	/// State of the directed links used so far.
+	link_states: HashMap<(PeerId, PeerId), LinkState>,
+
+	/// Group of each node if the network is partitioned.
+	groups: Option<HashMap<PeerId, usize>>,
+
+	/// Identifier and inbox of each node.
+	nodes: BTreeMap<PeerId, (u64, TracingUnboundedSender<Delivery>)>,
+
+	/// Identifier of the next node added.
+	next_node_id: u64,
+
+	/// Sequence number of the next delivery.
+	next_seq: u64,
+}
+
+impl Inner {
+	fn is_reachable(&self, a: &PeerId, b: &PeerId) -> bool {
+		self.groups.as_ref().map_or(true, |groups| groups.get(a) == groups.get(b))
+	}
+
+	fn set_groups(&mut self, groups: Option<HashMap<PeerId, usize>>) {
+		let peers: Vec<PeerId> = self.nodes.keys().copied().collect();
+		let before: Vec<bool> = pairs(&peers).map(|(a, b)| self.is_reachable(a, b)).collect();
+
+		self.groups = groups;
+		for ((a, b), before) in pairs(&peers).zip(before) {
+			let after = self.is_reachable(a, b);
+			if after != before {
+				let message = || if after { Message::Reachable } else { Message::Unreachable };
+				self.notify(a, *b, message());
+				self.notify(b, *a, message());
+			}
+		}
+	}
+
+	fn remove_node(&mut self, peer: &PeerId) {
+		if self.nodes.remove(peer).is_none() {
+			return
+		}
+		self.link_states.retain(|(from, to), _| from != peer && to != peer);
+
+		let others: Vec<PeerId> = self.nodes.keys().copied().collect();
+		for other in others {
+			if self.is_reachable(peer, &other) {
+				self.notify(peer, other, Message::Unreachable);
+			}
+		}
+	}
+
+	/// Deliver `message` to `to` immediately, regardless of the link.
+	fn notify(&mut self, from: &PeerId, to: PeerId, message: Message) {
+		let seq = self.next_seq();
+		if let Some((_, inbox)) = self.nodes.get(&to) {
+			let _ =
+				inbox.unbounded_send(Delivery { at: Instant::now(), seq, from: *from, message });
+		}
+	}
+
+	fn send(&mut self, from: PeerId, to: PeerId, message: Message, lossy: bool) {
+		if !self.nodes.contains_key(&to) || !self.is_reachable(&from, &to) {
+			log::trace!(target: LOG_TARGET, "{from} can't reach {to}, dropping {message:?}");
+			return
+		}
+
+		let config = self.links.get(&(from, to)).unwrap_or(&self.default_link).clone();
+		let seed = self.seed;
+		let now = Instant::now();
+		let state = self.link_states.entry((from, to)).or_insert_with(|| LinkState {
+			rng: StdRng::seed_from_u64(u64::from_le_bytes(blake2_64(
+				&(seed, from.to_bytes(), to.to_bytes()).encode(),
+			))),
+			busy_until: now,
+		});
+
+		// The message is transmitted once the previous ones are, lost or not.
+		let transmission = config.bandwidth.map_or(Duration::ZERO, |bandwidth| {
+			let nanos = message.size() as u128 * 1_000_000_000 / bandwidth.max(1) as u128;
+			Duration::from_nanos(nanos.try_into().unwrap_or(u64::MAX))
+		});
+		state.busy_until = state.busy_until.max(now) + transmission;
+		let at = state.busy_until + config.latency;
+
+		if lossy && config.loss > 0.0 && state.rng.gen_bool(config.loss) {
+			log::trace!(target: LOG_TARGET, "{from} -> {to}: lost {message:?}");
+			return
+		}
+
+		let seq = self.next_seq();
+		if let Some((_, inbox)) = self.nodes.get(&to) {
+			let _ = inbox.unbounded_send(Delivery { at, seq, from, message });
+		}
+	}
+
+	fn next_seq(&mut self) -> u64 {
+		let seq = self.next_seq;
+		self.next_seq += 1;
+		seq
+	}
+}
+
+/// Each unordered pair of distinct `peers`.
+fn pairs(peers: &[PeerId]) -> impl Iterator<Item = (&PeerId, &PeerId)> {
+	peers
+		.iter()
+		.enumerate()
+		.flat_map(move |(index, a)| peers[index + 1..].iter().map(move |b| (a, b)))
+}
+
+fn assert_loss(config: &LinkConfig) {
+	assert!((0.0..=1.0).contains(&config.loss), "loss must be between 0 and 1: {}", config.loss);
+}
@@ -0,0 +1,314 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	config::{
+		FullNetworkConfiguration, IncomingRequest, NetworkConfiguration, NodeKeyConfig,
+		NonReservedPeerMode, NotificationHandshake, OutgoingResponse, Params, ProtocolId, Role,
+		Secret, SetConfig,
+	},
+	service::traits::{
+		NetworkBackend, NetworkService, NotificationEvent, NotificationService, ValidationResult,
+	},
+	simulated::{LinkConfig, SimulatedNetworkBackend, Simulation},
+	IfDisconnected, NetworkRequest, NetworkStateInfo, NotificationMetrics, OutboundFailure,
+	ProtocolName, RequestFailure,
+};
+
+use sc_network_common::{role::Roles, sync::message::BlockAnnouncesHandshake};
+use sc_network_types::{ed25519, PeerId};
+use sp_runtime::traits::Zero;
+use substrate_test_runtime_client::runtime;
+use tokio::{task::JoinHandle, time::Instant};
+
+use std::{sync::Arc, time::Duration};
+
+type Backend = SimulatedNetworkBackend;
+
+const REQUEST_PROTOCOL: &str = "/simulation/request/1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Node {
+	peer_id: PeerId,
+	network_service: Arc<dyn NetworkService>,
+	notification_service: Box<dyn NotificationService>,
+	requests: async_channel::Receiver<IncomingRequest>,
+	handle: JoinHandle<()>,
+}
+
+impl Drop for Node {
+	fn drop(&mut self) {
+		self.handle.abort();
+	}
+}
+
+// create a node whose identity is derived from `index`, within the entered simulation
+fn make_node(index: u8) -> Node {
+	let role = Role::Full;
+	let mut net_conf = NetworkConfiguration::new_local();
+	net_conf.node_key = NodeKeyConfig::Ed25519(Secret::Input(
+		ed25519::SecretKey::try_from_bytes([index; 32]).unwrap(),
+	));
+
+	let mut network_config =
+		FullNetworkConfiguration::<runtime::Block, runtime::Hash, Backend>::new(&net_conf, None);
+	let genesis_hash = runtime::Hash::zero();
+	let (block_announce_config, notification_service) =
+		<Backend as NetworkBackend<runtime::Block, runtime::Hash>>::notification_config(
+			"/block-announces/1".into(),
+			vec![],
+			1024 * 1024,
+			Some(NotificationHandshake::new(BlockAnnouncesHandshake::<runtime::Block>::build(
+				Roles::from(&role),
+				Zero::zero(),
+				genesis_hash,
+				genesis_hash,
+			))),
+			SetConfig {
+				in_peers: 25,
+				out_peers: 25,
+				reserved_nodes: vec![],
+				non_reserved_mode: NonReservedPeerMode::Accept,
+			},
+			NotificationMetrics::new(None),
+			network_config.peer_store_handle(),
+		);
+
+	let (tx, requests) = async_channel::bounded(16);
+	network_config.add_request_response_protocol(<Backend as NetworkBackend<
+		runtime::Block,
+		runtime::Hash,
+	>>::request_response_config(
+		REQUEST_PROTOCOL.into(),
+		vec![],
+		1024,
+		1024,
+		REQUEST_TIMEOUT,
+		Some(tx),
+	));
+
+	let worker = <Backend as NetworkBackend<runtime::Block, runtime::Hash>>::new(Params {
+		block_announce_config,
+		role,
+		executor: Box::new(|f| {
+			tokio::spawn(f);
+		}),
+		genesis_hash,
+		network_config,
+		protocol_id: ProtocolId::from("simulation"),
+		fork_id: None,
+		metrics_registry: None,
+		bitswap_config: None,
+		notification_metrics: NotificationMetrics::new(None),
+	})
+	.unwrap();
+	let network_service =
+		<Backend as NetworkBackend<runtime::Block, runtime::Hash>>::network_service(&worker);
+
+	Node {
+		peer_id: network_service.local_peer_id(),
+		network_service,
+		notification_service,
+		requests,
+		handle: tokio::spawn(<Backend as NetworkBackend<runtime::Block, runtime::Hash>>::run(
+			worker,
+		)),
+	}
+}
+
+// create two nodes taking part in `simulation`
+fn make_nodes(simulation: &Simulation) -> (Node, Node) {
+	let _guard = simulation.enter();
+
+	(make_node(1), make_node(2))
+}
+
+// accept inbound substreams, returning `true` once the substream is open
+fn accept(event: NotificationEvent) -> bool {
+	match event {
+		NotificationEvent::ValidateInboundSubstream { result_tx, .. } => {
+			let _ = result_tx.send(ValidationResult::Accept);
+			false
+		},
+		NotificationEvent::NotificationStreamOpened { .. } => true,
+		_ => false,
+	}
+}
+
+// wait until both nodes have opened the block announce substream with each other
+async fn wait_open(node1: &mut Node, node2: &mut Node) {
+	let mut node1_open = false;
+	let mut node2_open = false;
+
+	while !(node1_open && node2_open) {
+		tokio::select! {
+			Some(event) = node1.notification_service.next_event() => node1_open |= accept(event),
+			Some(event) = node2.notification_service.next_event() => node2_open |= accept(event),
+		}
+	}
+}
+
+// wait until both nodes have closed the block announce substream with each other
+async fn wait_closed(node1: &mut Node, node2: &mut Node) {
+	let mut node1_closed = false;
+	let mut node2_closed = false;
+
+	while !(node1_closed && node2_closed) {
+		tokio::select! {
+			Some(event) = node1.notification_service.next_event() => node1_closed |=
+				matches!(event, NotificationEvent::NotificationStreamClosed { .. }),
+			Some(event) = node2.notification_service.next_event() => node2_closed |=
+				matches!(event, NotificationEvent::NotificationStreamClosed { .. }),
+		}
+	}
+}
+
+// notifications received by `node` until it stays idle for a second
+async fn received(node: &mut Node) -> Vec<Vec<u8>> {
+	let mut notifications = Vec::new();
+
+	while let Ok(Some(event)) =
+		tokio::time::timeout(Duration::from_secs(1), node.notification_service.next_event()).await
+	{
+		if let NotificationEvent::NotificationReceived { notification, .. } = event {
+			notifications.push(notification);
+		}
+	}
+
+	notifications
+}
+
+#[tokio::test(start_paused = true)]
+async fn notification_delayed_by_latency_and_bandwidth() {
+	let simulation = Simulation::new(0);
+	simulation.set_default_link(LinkConfig {
+		latency: Duration::from_millis(100),
+		bandwidth: Some(1_000),
+		loss: 0.0,
+	});
+	let (mut node1, mut node2) = make_nodes(&simulation);
+	wait_open(&mut node1, &mut node2).await;
+
+	let started = Instant::now();
+	node1.notification_service.send_sync_notification(&node2.peer_id, vec![1; 500]);
+
+	let notification = loop {
+		if let Some(NotificationEvent::NotificationReceived { notification, .. }) =
+			node2.notification_service.next_event().await
+		{
+			break notification
+		}
+	};
+	let elapsed = started.elapsed();
+
+	// 500 bytes at 1000 bytes per second, then 100ms of latency
+	assert_eq!(notification, vec![1; 500]);
+	assert!(elapsed >= Duration::from_millis(600) && elapsed < Duration::from_millis(601));
+}
+
+#[tokio::test(start_paused = true)]
+async fn same_seed_loses_same_notifications() {
+	async fn run(seed: u64) -> Vec<Vec<u8>> {
+		let simulation = Simulation::new(seed);
+		simulation.set_default_link(LinkConfig { loss: 0.5, ..Default::default() });
+		let (mut node1, mut node2) = make_nodes(&simulation);
+		wait_open(&mut node1, &mut node2).await;
+
+		for i in 0..100u8 {
+			node1.notification_service.send_sync_notification(&node2.peer_id, vec![i]);
+		}
+
+		received(&mut node2).await
+	}
+
+	let first = run(42).await;
+	let second = run(42).await;
+
+	assert!(!first.is_empty() && first.len() < 100);
+	assert_eq!(first, second);
+}
+
+#[tokio::test(start_paused = true)]
+async fn partition_closes_substreams_and_heal_reopens_them() {
+	let simulation = Simulation::new(0);
+	let (mut node1, mut node2) = make_nodes(&simulation);
+	wait_open(&mut node1, &mut node2).await;
+
+	simulation.partition([vec![node1.peer_id]]);
+	assert!(!simulation.is_reachable(&node1.peer_id, &node2.peer_id));
+	wait_closed(&mut node1, &mut node2).await;
+
+	// notifications don't cross the partition
+	node1.notification_service.send_sync_notification(&node2.peer_id, vec![1]);
+	assert!(received(&mut node2).await.is_empty());
+
+	simulation.heal();
+	wait_open(&mut node1, &mut node2).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn request_answered_then_timed_out() {
+	let simulation = Simulation::new(0);
+	let (mut node1, mut node2) = make_nodes(&simulation);
+	wait_open(&mut node1, &mut node2).await;
+
+	let requests = node2.requests.clone();
+	tokio::spawn(async move {
+		while let Ok(IncomingRequest { payload, pending_response, .. }) = requests.recv().await {
+			let _ = pending_response.send(OutgoingResponse {
+				result: Ok(payload),
+				reputation_changes: Vec::new(),
+				sent_feedback: None,
+			});
+		}
+	});
+
+	let response = node1
+		.network_service
+		.request(
+			node2.peer_id,
+			REQUEST_PROTOCOL.into(),
+			vec![1, 2, 3],
+			None,
+			IfDisconnected::ImmediateError,
+		)
+		.await;
+	assert_eq!(response.unwrap(), (vec![1, 2, 3], ProtocolName::from(REQUEST_PROTOCOL)));
+
+	simulation.set_link(
+		node1.peer_id,
+		node2.peer_id,
+		LinkConfig { loss: 1.0, ..Default::default() },
+	);
+
+	let started = Instant::now();
+	let response = node1
+		.network_service
+		.request(
+			node2.peer_id,
+			REQUEST_PROTOCOL.into(),
+			vec![1, 2, 3],
+			None,
+			IfDisconnected::ImmediateError,
+		)
+		.await;
+	let elapsed = started.elapsed();
+
+	assert!(matches!(response, Err(RequestFailure::Network(OutboundFailure::Timeout))));
+	assert!(elapsed >= REQUEST_TIMEOUT && elapsed < REQUEST_TIMEOUT + Duration::from_millis(1));
+}
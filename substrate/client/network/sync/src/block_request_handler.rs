@@ -31,6 +31,9 @@ use crate::{
 use codec::{Decode, DecodeAll, Encode};
 use futures::{channel::oneshot, stream::StreamExt};
 use log::debug;
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Gauge, Opts, PrometheusError, Registry, U64,
+};
 use prost::Message;
 use schnellru::{ByLength, LruMap};
 
@@ -52,9 +55,10 @@ use sp_runtime::{
 
 use std::{
 	cmp::min,
+	collections::VecDeque,
 	hash::{Hash, Hasher},
 	sync::Arc,
-	time::Duration,
+	time::{Duration, Instant},
 };
 
 /// Maximum blocks per response.
@@ -63,6 +67,20 @@ pub(crate) const MAX_BLOCKS_IN_RESPONSE: usize = 128;
 const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
 const MAX_NUMBER_OF_SAME_REQUESTS_PER_PEER: usize = 2;
 
+/// Window over which the served bytes are accounted for the rate limits.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Interval at which the requests delayed by the rate limits are retried.
+const RATE_LIMIT_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Maximum size of a block body downloaded across multiple responses.
+///
+/// Well above the maximum block size of the known runtimes.
+const MAX_SPLIT_BODY_BYTES: usize = 4 * MAX_BODY_BYTES;
+
+/// Maximum number of requests sent to complete a block body split across multiple responses.
+const MAX_SPLIT_BODY_REQUESTS: usize = 256;
+
 mod rep {
 	use sc_network::ReputationChange as Rep;
 
@@ -74,6 +92,88 @@ mod rep {
 		Rep::new(-(1 << 10), "same small block request multiple times");
 }
 
+/// Limits on the block data served to other peers.
+#[derive(Debug, Clone, Default)]
+pub struct BlockServerLimits {
+	/// Maximum number of bytes served to a single peer per second. `None` means unlimited.
+	pub peer_bytes_per_sec: Option<u64>,
+	/// Maximum number of bytes served to all the peers per second. `None` means unlimited.
+	pub total_bytes_per_sec: Option<u64>,
+}
+
+/// Bytes served within the current rate limit window.
+struct ServedBytes {
+	window_start: Instant,
+	bytes: u64,
+}
+
+impl ServedBytes {
+	fn new() -> Self {
+		Self { window_start: Instant::now(), bytes: 0 }
+	}
+
+	/// Number of bytes that can still be served within the current window.
+	fn remaining(&mut self, limit: u64) -> u64 {
+		if self.window_start.elapsed() >= RATE_LIMIT_WINDOW {
+			self.window_start = Instant::now();
+			self.bytes = 0;
+		}
+		limit.saturating_sub(self.bytes)
+	}
+
+	fn add(&mut self, bytes: u64) {
+		self.bytes = self.bytes.saturating_add(bytes);
+	}
+}
+
+struct Metrics {
+	served_bytes: Counter<U64>,
+	rate_limited_requests: CounterVec<U64>,
+	remaining_budget: Gauge<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			served_bytes: register(
+				Counter::new(
+					"substrate_sync_block_server_served_bytes",
+					"Number of bytes of block data served to other peers.",
+				)?,
+				registry,
+			)?,
+			rate_limited_requests: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_sync_block_server_rate_limited_requests",
+						"Number of block requests delayed because of the rate limits.",
+					),
+					&["limit"],
+				)?,
+				registry,
+			)?,
+			remaining_budget: register(
+				Gauge::new(
+					"substrate_sync_block_server_remaining_budget_bytes",
+					"Number of bytes that can still be served to all the peers in the current \
+					 second.",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// How much of the block bodies to serve in a response.
+struct BodyLimits {
+	/// Number of extrinsics of the body of the first block already sent to the peer.
+	offset: usize,
+	/// Maximum size of the bodies in the response. At least one block is sent, even if above.
+	max_bytes: usize,
+	/// Whether the peer supports bodies split across multiple responses.
+	split: bool,
+}
+
 /// Generates a `RequestResponseProtocolConfig` for the block request protocol,
 /// refusing incoming requests.
 pub fn generate_protocol_config<
@@ -120,6 +220,7 @@ struct SeenRequestsKey<B: BlockT> {
 	direction: Direction,
 	attributes: BlockAttributes,
 	support_multiple_justifications: bool,
+	body_offset: usize,
 }
 
 #[allow(clippy::derived_hash_with_manual_eq)]
@@ -130,6 +231,7 @@ impl<B: BlockT> Hash for SeenRequestsKey<B> {
 		self.direction.hash(state);
 		self.attributes.hash(state);
 		self.support_multiple_justifications.hash(state);
+		self.body_offset.hash(state);
 		match self.from {
 			BlockId::Hash(h) => h.hash(state),
 			BlockId::Number(n) => n.hash(state),
@@ -154,6 +256,19 @@ pub struct BlockRequestHandler<B: BlockT, Client> {
 	///
 	/// This is used to check if a peer is spamming us with the same request.
 	seen_requests: LruMap<SeenRequestsKey<B>, SeenRequestsValue>,
+	limits: BlockServerLimits,
+	/// Bytes served to each peer, for [`BlockServerLimits::peer_bytes_per_sec`].
+	served_to_peers: LruMap<PeerId, ServedBytes>,
+	/// Bytes served to all the peers, for [`BlockServerLimits::total_bytes_per_sec`].
+	served_total: ServedBytes,
+	/// Requests delayed until the rate limits allow serving them.
+	delayed_requests: VecDeque<IncomingRequest>,
+	/// No request is received while `max_delayed_requests` requests are delayed, leaving the
+	/// rest to the capacity of the request channel.
+	max_delayed_requests: usize,
+	/// When the delayed requests are retried next.
+	next_retry: Instant,
+	metrics: Option<Metrics>,
 }
 
 impl<B, Client> BlockRequestHandler<B, Client>
@@ -168,6 +283,31 @@ where
 		fork_id: Option<&str>,
 		client: Arc<Client>,
 		num_peer_hint: usize,
+	) -> BlockRelayParams<B, N> {
+		Self::new_with_limits(
+			network,
+			protocol_id,
+			fork_id,
+			client,
+			num_peer_hint,
+			BlockServerLimits::default(),
+			None,
+		)
+	}
+
+	/// Create a new [`BlockRequestHandler`] serving at most `limits`.
+	///
+	/// The requests exceeding the limits are delayed until they can be served, rather than
+	/// refused, as peers refusing requests are disconnected. The served bytes and the delayed
+	/// requests are reported to `metrics_registry`.
+	pub fn new_with_limits<N: NetworkBackend<B, <B as BlockT>::Hash>>(
+		network: NetworkServiceHandle,
+		protocol_id: &ProtocolId,
+		fork_id: Option<&str>,
+		client: Arc<Client>,
+		num_peer_hint: usize,
+		limits: BlockServerLimits,
+		metrics_registry: Option<&Registry>,
 	) -> BlockRelayParams<B, N> {
 		// Reserve enough request slots for one request per peer when we are at the maximum
 		// number of peers.
@@ -185,11 +325,31 @@ where
 			tx,
 		);
 
-		let capacity = ByLength::new(num_peer_hint.max(1) as u32 * 2);
-		let seen_requests = LruMap::new(capacity);
+		let lru_capacity = ByLength::new(num_peer_hint.max(1) as u32 * 2);
+		let seen_requests = LruMap::new(lru_capacity);
+		let served_to_peers = LruMap::new(lru_capacity);
+
+		let metrics = metrics_registry.and_then(|registry| match Metrics::register(registry) {
+			Ok(metrics) => Some(metrics),
+			Err(err) => {
+				log::error!(target: LOG_TARGET, "Failed to register block server metrics {err:?}");
+				None
+			},
+		});
 
 		BlockRelayParams {
-			server: Box::new(Self { client, request_receiver, seen_requests }),
+			server: Box::new(Self {
+				client,
+				request_receiver,
+				seen_requests,
+				limits,
+				served_to_peers,
+				served_total: ServedBytes::new(),
+				delayed_requests: VecDeque::new(),
+				max_delayed_requests: capacity,
+				next_retry: Instant::now(),
+				metrics,
+			}),
 			downloader: Arc::new(FullBlockDownloader::new(
 				protocol_config.protocol_name().clone(),
 				network,
@@ -200,24 +360,85 @@ where
 
 	/// Run [`BlockRequestHandler`].
 	async fn process_requests(&mut self) {
-		while let Some(request) = self.request_receiver.next().await {
-			let IncomingRequest { peer, payload, pending_response } = request;
+		loop {
+			let retry = tokio::time::sleep_until(self.next_retry.into());
+			// `None` when the delayed requests should be retried.
+			let request = if self.delayed_requests.is_empty() {
+				Some(self.request_receiver.next().await)
+			} else if self.delayed_requests.len() >= self.max_delayed_requests {
+				retry.await;
+				None
+			} else {
+				tokio::select! {
+					request = self.request_receiver.next() => Some(request),
+					_ = retry => None,
+				}
+			};
 
-			match self.handle_request(payload, pending_response, &peer) {
-				Ok(()) => debug!(target: LOG_TARGET, "Handled block request from {}.", peer),
-				Err(e) => debug!(
+			match request {
+				Some(Some(request)) => self.on_request(request),
+				Some(None) => return,
+				None => self.retry_delayed_requests(),
+			}
+		}
+	}
+
+	/// Serve `request` if the rate limits allow it, and delay it otherwise.
+	fn on_request(&mut self, request: IncomingRequest) {
+		match self.allowance(&request.peer) {
+			Ok(allowance) => self.serve_request(request, allowance),
+			Err(limit) => {
+				debug!(
 					target: LOG_TARGET,
-					"Failed to handle block request from {}: {}", peer, e,
-				),
+					"Delaying block request from {}: {limit} rate limit reached.",
+					request.peer,
+				);
+				if let Some(metrics) = &self.metrics {
+					metrics.rate_limited_requests.with_label_values(&[limit]).inc();
+				}
+				if self.delayed_requests.is_empty() {
+					self.next_retry = Instant::now() + RATE_LIMIT_RETRY_INTERVAL;
+				}
+				self.delayed_requests.push_back(request);
+			},
+		}
+	}
+
+	/// Serve the delayed requests the rate limits now allow, in the order they were received.
+	fn retry_delayed_requests(&mut self) {
+		self.next_retry = Instant::now() + RATE_LIMIT_RETRY_INTERVAL;
+
+		for _ in 0..self.delayed_requests.len() {
+			let Some(request) = self.delayed_requests.pop_front() else { break };
+			if request.pending_response.is_canceled() {
+				continue
+			}
+			match self.allowance(&request.peer) {
+				Ok(allowance) => self.serve_request(request, allowance),
+				Err(_) => self.delayed_requests.push_back(request),
 			}
 		}
 	}
 
+	/// Serve `request`, sending at most `allowance` bytes of block bodies.
+	fn serve_request(&mut self, request: IncomingRequest, allowance: u64) {
+		let IncomingRequest { peer, payload, pending_response } = request;
+
+		match self.handle_request(payload, pending_response, &peer, allowance) {
+			Ok(()) => debug!(target: LOG_TARGET, "Handled block request from {}.", peer),
+			Err(e) => debug!(
+				target: LOG_TARGET,
+				"Failed to handle block request from {}: {}", peer, e,
+			),
+		}
+	}
+
 	fn handle_request(
 		&mut self,
 		payload: Vec<u8>,
 		pending_response: oneshot::Sender<OutgoingResponse>,
 		peer: &PeerId,
+		allowance: u64,
 	) -> Result<(), HandleRequestError> {
 		let request = crate::schema::v1::BlockRequest::decode(&payload[..])?;
 
@@ -245,6 +466,8 @@ where
 
 		let support_multiple_justifications = request.support_multiple_justifications;
 
+		let body_offset = request.body_offset as usize;
+
 		let key = SeenRequestsKey {
 			peer: *peer,
 			max_blocks,
@@ -252,6 +475,7 @@ where
 			from: from_block_id,
 			attributes,
 			support_multiple_justifications,
+			body_offset,
 		};
 
 		let mut reputation_change = None;
@@ -287,12 +511,22 @@ where
 		);
 
 		let maybe_block_response = if reputation_change.is_none() || small_request {
+			let mut max_body_bytes = min(MAX_BODY_BYTES as u64, allowance) as usize;
+			if request.max_body_bytes > 0 {
+				max_body_bytes = min(max_body_bytes, request.max_body_bytes as usize);
+			}
+			let body_limits = BodyLimits {
+				offset: body_offset,
+				max_bytes: max_body_bytes,
+				split: request.max_body_bytes > 0,
+			};
 			let block_response = self.get_block_response(
 				attributes,
 				from_block_id,
 				direction,
 				max_blocks,
 				support_multiple_justifications,
+				body_limits,
 			)?;
 
 			// If any of the blocks contains any data, we can consider it as successful request.
@@ -302,8 +536,8 @@ where
 				.any(|b| !b.header.is_empty() || !b.body.is_empty() || b.is_empty_justification)
 			{
 				if let Some(value) = self.seen_requests.get(&key) {
-					// If this is the first time we have processed this request, we need to change
-					// it to `Fulfilled`.
+					// If this is the first time we have processed this request, we need to
+					// change it to `Fulfilled`.
 					if let SeenRequestsValue::First = value {
 						*value = SeenRequestsValue::Fulfilled(1);
 					}
@@ -326,6 +560,7 @@ where
 		let result = if let Some(block_response) = maybe_block_response {
 			let mut data = Vec::with_capacity(block_response.encoded_len());
 			block_response.encode(&mut data)?;
+			self.on_bytes_served(peer, data.len() as u64);
 			Ok(data)
 		} else {
 			Err(())
@@ -340,6 +575,51 @@ where
 			.map_err(|_| HandleRequestError::SendResponse)
 	}
 
+	/// Number of bytes that can be served to `peer` without exceeding the limits.
+	///
+	/// Returns the name of the exhausted limit if nothing can be served.
+	fn allowance(&mut self, peer: &PeerId) -> Result<u64, &'static str> {
+		let mut allowance = u64::MAX;
+
+		if let Some(limit) = self.limits.peer_bytes_per_sec {
+			let remaining = self
+				.served_to_peers
+				.get_or_insert(*peer, ServedBytes::new)
+				.map_or(limit, |served| served.remaining(limit));
+			if remaining == 0 {
+				return Err("peer")
+			}
+			allowance = min(allowance, remaining);
+		}
+
+		if let Some(limit) = self.limits.total_bytes_per_sec {
+			let remaining = self.served_total.remaining(limit);
+			if remaining == 0 {
+				return Err("total")
+			}
+			allowance = min(allowance, remaining);
+		}
+
+		Ok(allowance)
+	}
+
+	/// Account the `bytes` served to `peer`.
+	fn on_bytes_served(&mut self, peer: &PeerId, bytes: u64) {
+		if self.limits.peer_bytes_per_sec.is_some() {
+			if let Some(served) = self.served_to_peers.get_or_insert(*peer, ServedBytes::new) {
+				served.add(bytes);
+			}
+		}
+		self.served_total.add(bytes);
+
+		if let Some(metrics) = &self.metrics {
+			metrics.served_bytes.inc_by(bytes);
+			if let Some(limit) = self.limits.total_bytes_per_sec {
+				metrics.remaining_budget.set(self.served_total.remaining(limit));
+			}
+		}
+	}
+
 	fn get_block_response(
 		&self,
 		attributes: BlockAttributes,
@@ -347,6 +627,7 @@ where
 		direction: Direction,
 		max_blocks: usize,
 		support_multiple_justifications: bool,
+		body_limits: BodyLimits,
 	) -> Result<BlockResponse, HandleRequestError> {
 		let get_header = attributes.contains(BlockAttributes::HEADER);
 		let get_body = attributes.contains(BlockAttributes::BODY);
@@ -398,10 +679,15 @@ where
 					(Vec::new(), justification, is_empty_justification)
 				};
 
+			// The extrinsics of the first block already sent to the peer are skipped.
+			let body_offset = if blocks.is_empty() { body_limits.offset } else { 0 };
 			let body = if get_body {
 				match self.client.block_body(hash)? {
-					Some(mut extrinsics) =>
-						extrinsics.iter_mut().map(|extrinsic| extrinsic.encode()).collect(),
+					Some(extrinsics) => extrinsics
+						.iter()
+						.skip(body_offset)
+						.map(|extrinsic| extrinsic.encode())
+						.collect(),
 					None => {
 						log::trace!(target: LOG_TARGET, "Missing data for block request.");
 						break
@@ -429,7 +715,7 @@ where
 				Vec::new()
 			};
 
-			let block_data = crate::schema::v1::BlockData {
+			let mut block_data = crate::schema::v1::BlockData {
 				hash: hash.encode(),
 				header: if get_header { header.encode() } else { Vec::new() },
 				body,
@@ -439,22 +725,44 @@ where
 				is_empty_justification,
 				justifications,
 				indexed_body,
+				partial_body: false,
 			};
 
 			let new_total_size = total_size +
 				block_data.body.iter().map(|ex| ex.len()).sum::<usize>() +
 				block_data.indexed_body.iter().map(|ex| ex.len()).sum::<usize>();
 
-			// Send at least one block, but make sure to not exceed the limit.
-			if !blocks.is_empty() && new_total_size > MAX_BODY_BYTES {
-				break
+			if new_total_size > body_limits.max_bytes {
+				// Send at least one block, but make sure to not exceed the limit.
+				if !blocks.is_empty() {
+					break
+				}
+				// Send the body in parts to the peers supporting it, at least one extrinsic
+				// at a time.
+				if body_limits.split {
+					let mut size = block_data.indexed_body.iter().map(|ex| ex.len()).sum::<usize>();
+					let fitting = block_data
+						.body
+						.iter()
+						.take_while(|ex| {
+							size += ex.len();
+							size <= body_limits.max_bytes
+						})
+						.count()
+						.max(1);
+					if fitting < block_data.body.len() {
+						block_data.body.truncate(fitting);
+						block_data.partial_body = true;
+					}
+				}
 			}
 
 			total_size = new_total_size;
 
+			let partial_body = block_data.partial_body;
 			blocks.push(block_data);
 
-			if blocks.len() >= max_blocks as usize {
+			if blocks.len() >= max_blocks as usize || partial_body {
 				break
 			}
 
@@ -502,6 +810,24 @@ enum HandleRequestError {
 	SendResponse,
 }
 
+/// Appends the `part` of the body of `block` received from a peer.
+///
+/// Returns `false` if the part doesn't belong to `block`, is empty or makes the body larger
+/// than [`MAX_SPLIT_BODY_BYTES`].
+fn append_body_part(
+	block: &mut crate::schema::v1::BlockData,
+	part: crate::schema::v1::BlockData,
+) -> bool {
+	let size = block.body.iter().chain(&part.body).map(|ex| ex.len()).sum::<usize>();
+	if part.hash != block.hash || part.body.is_empty() || size > MAX_SPLIT_BODY_BYTES {
+		return false
+	}
+
+	block.body.extend(part.body);
+	block.partial_body = part.partial_body;
+	true
+}
+
 /// The full block downloader implementation of [`BlockDownloader].
 #[derive(Debug)]
 pub struct FullBlockDownloader {
@@ -514,6 +840,75 @@ impl FullBlockDownloader {
 		Self { protocol_name, network }
 	}
 
+	async fn send_request(
+		&self,
+		who: PeerId,
+		request: BlockRequestSchema,
+	) -> Result<Result<(Vec<u8>, ProtocolName), RequestFailure>, oneshot::Canceled> {
+		let (tx, rx) = oneshot::channel();
+		self.network.start_request(
+			who,
+			self.protocol_name.clone(),
+			request.encode_to_vec(),
+			tx,
+			IfDisconnected::ImmediateError,
+		);
+		rx.await
+	}
+
+	/// Requests the rest of the body of the last block of `response` if it was split by the
+	/// peer.
+	///
+	/// The block is dropped from the response if its body can't be completed.
+	async fn complete_partial_body(&self, who: PeerId, response: Vec<u8>) -> Vec<u8> {
+		let Ok(mut response_schema) = BlockResponseSchema::decode(response.as_slice()) else {
+			return response
+		};
+		if !response_schema.blocks.last().is_some_and(|block| block.partial_body) {
+			return response
+		}
+
+		for _ in 0..MAX_SPLIT_BODY_REQUESTS {
+			let Some(block) = response_schema.blocks.last_mut() else { break };
+			if !block.partial_body {
+				break
+			}
+
+			let request = BlockRequestSchema {
+				fields: BlockAttributes::BODY.to_be_u32(),
+				from_block: Some(FromBlockSchema::Hash(block.hash.clone())),
+				direction: Direction::Ascending as i32,
+				max_blocks: 1,
+				support_multiple_justifications: true,
+				max_body_bytes: MAX_BODY_BYTES as u32,
+				body_offset: block.body.len() as u32,
+			};
+			let continuation = match self.send_request(who, request).await {
+				Ok(Ok((continuation, _))) => BlockResponseSchema::decode(continuation.as_slice())
+					.ok()
+					.and_then(|mut continuation| continuation.blocks.pop()),
+				_ => None,
+			};
+
+			if !continuation.is_some_and(|continuation| append_body_part(block, continuation)) {
+				debug!(
+					target: LOG_TARGET,
+					"Failed to download the rest of a split block body from {who}.",
+				);
+				response_schema.blocks.pop();
+				break
+			}
+		}
+
+		// Too many parts were requested.
+		if response_schema.blocks.last().is_some_and(|block| block.partial_body) {
+			debug!(target: LOG_TARGET, "Block body split in too many parts by {who}.");
+			response_schema.blocks.pop();
+		}
+
+		response_schema.encode_to_vec()
+	}
+
 	/// Extracts the blocks from the response schema.
 	fn blocks_from_schema<B: BlockT>(
 		&self,
@@ -588,7 +983,7 @@ impl<B: BlockT> BlockDownloader<B> for FullBlockDownloader {
 		request: BlockRequest<B>,
 	) -> Result<Result<(Vec<u8>, ProtocolName), RequestFailure>, oneshot::Canceled> {
 		// Build the request protobuf.
-		let bytes = BlockRequestSchema {
+		let request = BlockRequestSchema {
 			fields: request.fields.to_be_u32(),
 			from_block: match request.from {
 				FromBlock::Hash(h) => Some(FromBlockSchema::Hash(h.encode())),
@@ -597,18 +992,15 @@ impl<B: BlockT> BlockDownloader<B> for FullBlockDownloader {
 			direction: request.direction as i32,
 			max_blocks: request.max.unwrap_or(0),
 			support_multiple_justifications: true,
-		}
-		.encode_to_vec();
+			max_body_bytes: MAX_BODY_BYTES as u32,
+			body_offset: 0,
+		};
 
-		let (tx, rx) = oneshot::channel();
-		self.network.start_request(
-			who,
-			self.protocol_name.clone(),
-			bytes,
-			tx,
-			IfDisconnected::ImmediateError,
-		);
-		rx.await
+		match self.send_request(who, request).await {
+			Ok(Ok((response, protocol_name))) =>
+				Ok(Ok((self.complete_partial_body(who, response).await, protocol_name))),
+			result => result,
+		}
 	}
 
 	fn block_response_into_blocks(
@@ -625,3 +1017,208 @@ impl<B: BlockT> BlockDownloader<B> for FullBlockDownloader {
 			.map_err(|error| BlockResponseError::ExtractionFailed(error.to_string()))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schema::v1::BlockData as BlockDataSchema;
+	use futures::executor::block_on;
+	use sc_block_builder::BlockBuilderBuilder;
+	use sp_consensus::BlockOrigin;
+	use substrate_test_runtime_client::{
+		runtime::{Block, Hash},
+		BlockBuilderExt, ClientBlockImportExt, DefaultTestClientBuilderExt, TestClient,
+		TestClientBuilder, TestClientBuilderExt,
+	};
+
+	const EXTRINSICS: usize = 8;
+
+	/// Build a handler serving a block of [`EXTRINSICS`] extrinsics of about 1 KiB each.
+	fn handler(limits: BlockServerLimits) -> (BlockRequestHandler<Block, TestClient>, Hash) {
+		let client = TestClientBuilder::new().build();
+		let mut block_builder = BlockBuilderBuilder::new(&client)
+			.on_parent_block(client.info().genesis_hash)
+			.with_parent_block_number(0)
+			.build()
+			.unwrap();
+		for i in 0..EXTRINSICS {
+			block_builder.push_storage_change(vec![i as u8], Some(vec![0; 1024])).unwrap();
+		}
+		let block = block_builder.build().unwrap().block;
+		block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+
+		let (_, request_receiver) = async_channel::bounded(1);
+		let handler = BlockRequestHandler {
+			client: Arc::new(client),
+			request_receiver,
+			seen_requests: LruMap::new(ByLength::new(16)),
+			limits,
+			served_to_peers: LruMap::new(ByLength::new(16)),
+			served_total: ServedBytes::new(),
+			delayed_requests: VecDeque::new(),
+			max_delayed_requests: 16,
+			next_retry: Instant::now(),
+			metrics: None,
+		};
+		(handler, block.hash())
+	}
+
+	fn request(hash: Hash, max_body_bytes: u32, body_offset: u32) -> Vec<u8> {
+		BlockRequestSchema {
+			fields: (BlockAttributes::HEADER | BlockAttributes::BODY).to_be_u32(),
+			from_block: Some(FromBlockSchema::Hash(hash.encode())),
+			direction: Direction::Ascending as i32,
+			max_blocks: 1,
+			support_multiple_justifications: true,
+			max_body_bytes,
+			body_offset,
+		}
+		.encode_to_vec()
+	}
+
+	fn send(
+		handler: &mut BlockRequestHandler<Block, TestClient>,
+		peer: PeerId,
+		payload: Vec<u8>,
+	) -> oneshot::Receiver<OutgoingResponse> {
+		let (pending_response, rx) = oneshot::channel();
+		handler.on_request(IncomingRequest { peer, payload, pending_response });
+		rx
+	}
+
+	/// The block of the response sent to `rx`, `None` if no response was sent yet.
+	fn response(rx: &mut oneshot::Receiver<OutgoingResponse>) -> Option<BlockDataSchema> {
+		rx.try_recv().unwrap().map(|response| {
+			let mut response =
+				BlockResponseSchema::decode(response.result.unwrap().as_slice()).unwrap();
+			assert_eq!(response.blocks.len(), 1);
+			response.blocks.pop().unwrap()
+		})
+	}
+
+	fn full_body(handler: &BlockRequestHandler<Block, TestClient>, hash: Hash) -> Vec<Vec<u8>> {
+		let body = handler.client.block_body(hash).unwrap().unwrap();
+		body.iter().map(|extrinsic| extrinsic.encode()).collect()
+	}
+
+	#[test]
+	fn old_peers_receive_whole_bodies() {
+		let (mut handler, hash) = handler(BlockServerLimits::default());
+		let body = response(&mut send(&mut handler, PeerId::random(), request(hash, 0, 0)))
+			.unwrap()
+			.body;
+
+		assert_eq!(body.len(), EXTRINSICS);
+		assert_eq!(body, full_body(&handler, hash));
+
+		// Even beyond the rate limits, as they can't request the rest of the body.
+		let (mut handler, hash) = self::handler(BlockServerLimits {
+			peer_bytes_per_sec: Some(1),
+			total_bytes_per_sec: None,
+		});
+		let block =
+			response(&mut send(&mut handler, PeerId::random(), request(hash, 0, 0))).unwrap();
+
+		assert!(!block.partial_body);
+		assert_eq!(block.body.len(), EXTRINSICS);
+	}
+
+	#[test]
+	fn split_bodies_are_continued_from_body_offset() {
+		let (mut handler, hash) = handler(BlockServerLimits::default());
+		let peer = PeerId::random();
+
+		let mut body = Vec::new();
+		let mut responses = 0;
+		loop {
+			let block =
+				response(&mut send(&mut handler, peer, request(hash, 2500, body.len() as u32)))
+					.unwrap();
+			assert_eq!(block.hash, hash.encode());
+			assert!(!block.body.is_empty() && block.body.len() <= 2);
+			responses += 1;
+
+			body.extend(block.body);
+			if !block.partial_body {
+				break
+			}
+		}
+
+		assert_eq!(responses, EXTRINSICS / 2);
+		assert_eq!(body, full_body(&handler, hash));
+	}
+
+	#[test]
+	fn body_parts_are_bounded() {
+		let block = |hash: u8, body: Vec<Vec<u8>>| BlockDataSchema {
+			hash: vec![hash],
+			body,
+			partial_body: true,
+			..Default::default()
+		};
+		let mut partial = block(1, vec![vec![0; 16]]);
+
+		assert!(!append_body_part(&mut partial, block(2, vec![vec![0; 16]])));
+		assert!(!append_body_part(&mut partial, block(1, Vec::new())));
+		assert!(!append_body_part(&mut partial, block(1, vec![vec![0; MAX_SPLIT_BODY_BYTES]])));
+		assert_eq!(partial.body.len(), 1);
+
+		assert!(append_body_part(&mut partial, block(1, vec![vec![0; 16]])));
+		assert_eq!(partial.body.len(), 2);
+		assert!(partial.partial_body);
+	}
+
+	#[test]
+	fn peer_rate_limit_delays_requests() {
+		let (mut handler, hash) = handler(BlockServerLimits {
+			peer_bytes_per_sec: Some(2048),
+			total_bytes_per_sec: None,
+		});
+		let peer = PeerId::random();
+
+		// The bodies are truncated to the allowance of the peer, at least one extrinsic at a time.
+		for offset in 0..2 {
+			let block =
+				response(&mut send(&mut handler, peer, request(hash, 8192, offset))).unwrap();
+			assert!(block.partial_body);
+			assert_eq!(block.body.len(), 1);
+		}
+
+		// The allowance of the peer is exhausted, but not the one of the other peers.
+		let mut delayed = send(&mut handler, peer, request(hash, 8192, 2));
+		assert!(response(&mut delayed).is_none());
+		assert!(
+			response(&mut send(&mut handler, PeerId::random(), request(hash, 8192, 0))).is_some()
+		);
+
+		// Served once the window elapsed.
+		handler.retry_delayed_requests();
+		assert!(response(&mut delayed).is_none());
+		handler.served_to_peers.get(&peer).unwrap().window_start -= RATE_LIMIT_WINDOW;
+		handler.retry_delayed_requests();
+		assert_eq!(response(&mut delayed).unwrap().body.len(), 1);
+		assert!(handler.delayed_requests.is_empty());
+	}
+
+	#[test]
+	fn total_rate_limit_delays_requests() {
+		let (mut handler, hash) =
+			handler(BlockServerLimits { peer_bytes_per_sec: None, total_bytes_per_sec: Some(1) });
+
+		assert!(
+			response(&mut send(&mut handler, PeerId::random(), request(hash, 8192, 0))).is_some()
+		);
+
+		let mut delayed = send(&mut handler, PeerId::random(), request(hash, 8192, 0));
+		let canceled = send(&mut handler, PeerId::random(), request(hash, 8192, 0));
+		assert!(response(&mut delayed).is_none());
+		assert_eq!(handler.delayed_requests.len(), 2);
+
+		// Requests canceled in the meantime are dropped.
+		drop(canceled);
+		handler.served_total.window_start -= RATE_LIMIT_WINDOW;
+		handler.retry_delayed_requests();
+		assert!(response(&mut delayed).is_some());
+		assert!(handler.delayed_requests.is_empty());
+	}
+}
@@ -25,6 +25,7 @@
 pub mod chain_sync;
 mod disconnected_peers;
 pub mod polkadot;
+pub mod progress;
 pub mod state;
 pub mod state_sync;
 pub mod warp;
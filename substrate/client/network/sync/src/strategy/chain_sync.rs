@@ -218,6 +218,8 @@ pub enum ChainSyncMode {
 		/// Download indexed transactions for recent blocks.
 		storage_chain_mode: bool,
 	},
+	/// Download headers and justifications only, without any state.
+	Light,
 }
 
 /// All the data we have about a Peer that we are trying to sync with
@@ -1202,6 +1204,7 @@ where
 										justifications,
 										origin: block_data.origin,
 										allow_missing_state: true,
+										allow_missing_parent: false,
 										import_existing: self.import_existing,
 										skip_execution: true,
 										state: None,
@@ -1241,6 +1244,7 @@ where
 									justifications,
 									origin: Some(*peer_id),
 									allow_missing_state: true,
+									allow_missing_parent: false,
 									import_existing: self.import_existing,
 									skip_execution: self.skip_execution(),
 									state: None,
@@ -1383,6 +1387,7 @@ where
 							justifications,
 							origin: Some(*peer_id),
 							allow_missing_state: true,
+							allow_missing_parent: false,
 							import_existing: false,
 							skip_execution: true,
 							state: None,
@@ -1531,13 +1536,14 @@ where
 				BlockAttributes::HEADER |
 					BlockAttributes::JUSTIFICATION |
 					BlockAttributes::INDEXED_BODY,
+			ChainSyncMode::Light => BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION,
 		}
 	}
 
 	fn skip_execution(&self) -> bool {
 		match self.mode {
 			ChainSyncMode::Full => false,
-			ChainSyncMode::LightState { .. } => true,
+			ChainSyncMode::LightState { .. } | ChainSyncMode::Light => true,
 		}
 	}
 
@@ -1769,6 +1775,7 @@ where
 					justifications,
 					origin: block_data.origin,
 					allow_missing_state: true,
+					allow_missing_parent: false,
 					import_existing: self.import_existing,
 					skip_execution: self.skip_execution(),
 					state: None,
@@ -1962,7 +1969,7 @@ where
 					self.disconnected_peers.is_peer_available(&id)
 				{
 					peer.state = PeerSyncState::DownloadingState;
-					let request = sync.next_request();
+					let request = sync.next_request(0);
 					trace!(target: LOG_TARGET, "New StateRequest for {}: {:?}", id, request);
 					self.allowed_requests.clear();
 					return Some((*id, request));
@@ -2000,7 +2007,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import(response)
+			sync.import(0, response)
 		} else {
 			debug!(target: LOG_TARGET, "Ignored obsolete state response from {peer_id}");
 			return Err(BadPeer(*peer_id, rep::NOT_REQUESTED));
@@ -2017,6 +2024,7 @@ where
 					justifications,
 					origin: None,
 					allow_missing_state: true,
+					allow_missing_parent: false,
 					import_existing: true,
 					skip_execution: self.skip_execution(),
 					state: Some(state),
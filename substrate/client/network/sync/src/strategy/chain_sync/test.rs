@@ -1341,3 +1341,38 @@ fn sync_gap_filled_regardless_of_blocks_origin() {
 		assert!(sync.gap_sync.is_none());
 	}
 }
+
+#[test]
+fn light_mode_requests_headers_and_justifications_only() {
+	let client = Arc::new(TestClientBuilder::new().build());
+	let peer_id = PeerId::random();
+
+	let mut sync = ChainSync::new(
+		ChainSyncMode::Light,
+		client.clone(),
+		1,
+		64,
+		ProtocolName::Static(""),
+		Arc::new(MockBlockDownloader::new()),
+		None,
+		std::iter::empty(),
+	)
+	.unwrap();
+
+	let a1 = BlockBuilderBuilder::new(&*client)
+		.on_parent_block(client.chain_info().best_hash)
+		.with_parent_block_number(client.chain_info().best_number)
+		.build()
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+
+	sync.add_peer(peer_id, a1.hash(), *a1.header.number());
+
+	let requests = sync.block_requests();
+	assert_eq!(1, requests.len());
+	assert_eq!(peer_id, requests[0].0);
+	assert_eq!(BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION, requests[0].1.fields);
+	assert!(sync.skip_execution());
+}
@@ -25,7 +25,9 @@ use crate::{
 	service::network::NetworkServiceHandle,
 	strategy::{
 		chain_sync::{ChainSync, ChainSyncMode},
-		state::StateStrategy,
+		progress::SyncProgressStore,
+		state::{StateStrategy, PARALLEL_STATE_RANGES},
+		state_sync::{clear_persisted_progress, persisted_target, StateSync},
 		warp::{WarpSync, WarpSyncConfig},
 		StrategyKey, SyncingAction, SyncingStrategy,
 	},
@@ -34,7 +36,7 @@ use crate::{
 };
 use log::{debug, error, info, warn};
 use prometheus_endpoint::Registry;
-use sc_client_api::{BlockBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, ProofProvider};
 use sc_consensus::{BlockImportError, BlockImportStatus};
 use sc_network::ProtocolName;
 use sc_network_common::sync::{message::BlockAnnounce, SyncMode};
@@ -43,6 +45,16 @@ use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
 use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
 use std::{any::Any, collections::HashMap, sync::Arc};
 
+/// Number of restarts after which an interrupted state sync is started over with a fresh warp
+/// sync, rather than resumed.
+const MAX_STATE_SYNC_RESUMES: u32 = 3;
+
+/// Number of recent block states kept by the peers pruning their state, by default.
+///
+/// A resumed state sync targeting an older block than the best blocks of the peers minus this
+/// window is started over with a fresh warp sync.
+const STATE_PRUNING_WINDOW: u32 = 256;
+
 /// Corresponding `ChainSync` mode.
 fn chain_sync_mode(sync_mode: SyncMode) -> ChainSyncMode {
 	match sync_mode {
@@ -50,6 +62,7 @@ fn chain_sync_mode(sync_mode: SyncMode) -> ChainSyncMode {
 		SyncMode::LightState { skip_proofs, storage_chain_mode } =>
 			ChainSyncMode::LightState { skip_proofs, storage_chain_mode },
 		SyncMode::Warp => ChainSyncMode::Full,
+		SyncMode::Light => ChainSyncMode::Light,
 	}
 }
 
@@ -90,6 +103,9 @@ pub struct PolkadotSyncingStrategy<B: BlockT, Client> {
 	/// Connected peers and their best blocks used to seed a new strategy when switching to it in
 	/// `PolkadotSyncingStrategy::proceed_to_next`.
 	peer_best_blocks: HashMap<PeerId, (B::Hash, NumberFor<B>)>,
+	/// Warp sync to start over with if the state sync resumed after a restart fails or targets
+	/// a block whose state was pruned by the peers.
+	warp_fallback: Option<(WarpSyncConfig<B>, Option<ProtocolName>)>,
 }
 
 impl<B: BlockT, Client> SyncingStrategy<B> for PolkadotSyncingStrategy<B, Client>
@@ -99,6 +115,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -109,6 +126,8 @@ where
 		self.warp.as_mut().map(|s| s.add_peer(peer_id, best_hash, best_number));
 		self.state.as_mut().map(|s| s.add_peer(peer_id, best_hash, best_number));
 		self.chain_sync.as_mut().map(|s| s.add_peer(peer_id, best_hash, best_number));
+
+		self.check_resumed_state_target();
 	}
 
 	fn remove_peer(&mut self, peer_id: &PeerId) {
@@ -147,6 +166,7 @@ where
 					 (already disconnected?)",
 				);
 			}
+			self.check_resumed_state_target();
 		}
 
 		new_best
@@ -245,8 +265,10 @@ where
 		count: usize,
 		results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
 	) {
-		// Only `StateStrategy` and `ChainSync` are interested in block processing notifications.
-		if let Some(ref mut state) = self.state {
+		// `WarpSync` is only interested in the import of its target block on light nodes.
+		if let Some(ref mut warp) = self.warp {
+			warp.on_blocks_processed(results);
+		} else if let Some(ref mut state) = self.state {
 			state.on_blocks_processed(imported, count, results);
 		} else if let Some(ref mut chain_sync) = self.chain_sync {
 			chain_sync.on_blocks_processed(imported, count, results);
@@ -335,6 +357,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -354,16 +377,59 @@ where
 			config.max_blocks_per_request = MAX_BLOCKS_IN_RESPONSE as u32;
 		}
 
-		if let SyncMode::Warp = config.mode {
+		if let SyncMode::Warp | SyncMode::Light = config.mode {
+			let progress_store: Arc<dyn SyncProgressStore> = client.clone();
 			let warp_sync_config = warp_sync_config
-				.expect("Warp sync configuration must be supplied in warp sync mode.");
-			let warp_sync = WarpSync::new(
+				.expect("Warp sync configuration must be supplied in warp and light sync modes.");
+
+			// Warp sync completed before the restart, resume the state download.
+			let persisted_target = persisted_target::<B>(&*progress_store)
+				.filter(|_| config.mode.is_warp() && client.info().finalized_state.is_none());
+			if let Some((_, resumed)) = persisted_target.as_ref() {
+				if *resumed >= MAX_STATE_SYNC_RESUMES {
+					warn!(
+						target: LOG_TARGET,
+						"State sync was interrupted {resumed} times, starting over with warp sync.",
+					);
+					clear_persisted_progress::<B>(&*progress_store, 0);
+				}
+			}
+			if let Some((target_header, _)) =
+				persisted_target.filter(|(_, resumed)| *resumed < MAX_STATE_SYNC_RESUMES)
+			{
+				info!(target: LOG_TARGET, "Resuming state sync interrupted by the restart.");
+				let state_sync = StateStrategy::new_with_provider(
+					Box::new(
+						StateSync::new(client.clone(), target_header, None, None, false)
+							.with_parallel_ranges(PARALLEL_STATE_RANGES)
+							.with_progress_store(progress_store),
+					),
+					std::iter::empty(),
+					config.state_request_protocol_name.clone(),
+				);
+				return Ok(Self {
+					config,
+					client,
+					warp: None,
+					state: Some(state_sync),
+					chain_sync: None,
+					peer_best_blocks: Default::default(),
+					warp_fallback: Some((warp_sync_config, warp_sync_protocol_name)),
+				})
+			}
+
+			let mut warp_sync = WarpSync::new(
 				client.clone(),
 				warp_sync_config,
 				warp_sync_protocol_name,
 				config.block_downloader.clone(),
 				config.min_peers_to_start_warp_sync,
-			);
+			)
+			.with_progress_store(progress_store);
+			// Light nodes don't download the state, they follow the headers from the target block.
+			if config.mode.is_light() {
+				warp_sync = warp_sync.with_target_block_import();
+			}
 			Ok(Self {
 				config,
 				client,
@@ -371,6 +437,7 @@ where
 				state: None,
 				chain_sync: None,
 				peer_best_blocks: Default::default(),
+				warp_fallback: None,
 			})
 		} else {
 			let chain_sync = ChainSync::new(
@@ -390,26 +457,45 @@ where
 				state: None,
 				chain_sync: Some(chain_sync),
 				peer_best_blocks: Default::default(),
+				warp_fallback: None,
 			})
 		}
 	}
 
 	/// Proceed with the next strategy if the active one finished.
 	pub fn proceed_to_next(&mut self) -> Result<(), ClientError> {
-		// The strategies are switched as `WarpSync` -> `StateStrategy` -> `ChainSync`.
+		// The strategies are switched as `WarpSync` -> `StateStrategy` -> `ChainSync`, light nodes
+		// skip the `StateStrategy`.
 		if let Some(ref mut warp) = self.warp {
 			match warp.take_result() {
+				Some(_) if self.config.mode.is_light() => {
+					info!(
+						target: LOG_TARGET,
+						"Warp sync is complete, continuing with header sync."
+					);
+					let chain_sync = self.new_chain_sync()?;
+
+					self.warp = None;
+					self.chain_sync = Some(chain_sync);
+					Ok(())
+				},
 				Some(res) => {
 					info!(
 						target: LOG_TARGET,
 						"Warp sync is complete, continuing with state sync."
 					);
-					let state_sync = StateStrategy::new(
-						self.client.clone(),
-						res.target_header,
-						res.target_body,
-						res.target_justifications,
-						false,
+					let state_sync = StateStrategy::new_with_provider(
+						Box::new(
+							StateSync::new(
+								self.client.clone(),
+								res.target_header,
+								res.target_body,
+								res.target_justifications,
+								false,
+							)
+							.with_parallel_ranges(PARALLEL_STATE_RANGES)
+							.with_progress_store(self.client.clone()),
+						),
 						self.peer_best_blocks
 							.iter()
 							.map(|(peer_id, (_, best_number))| (*peer_id, *best_number)),
@@ -425,24 +511,7 @@ where
 						target: LOG_TARGET,
 						"Warp sync failed. Continuing with full sync."
 					);
-					let chain_sync = match ChainSync::new(
-						chain_sync_mode(self.config.mode),
-						self.client.clone(),
-						self.config.max_parallel_downloads,
-						self.config.max_blocks_per_request,
-						self.config.state_request_protocol_name.clone(),
-						self.config.block_downloader.clone(),
-						self.config.metrics_registry.as_ref(),
-						self.peer_best_blocks.iter().map(|(peer_id, (best_hash, best_number))| {
-							(*peer_id, *best_hash, *best_number)
-						}),
-					) {
-						Ok(chain_sync) => chain_sync,
-						Err(e) => {
-							error!(target: LOG_TARGET, "Failed to start `ChainSync`.");
-							return Err(e)
-						},
-					};
+					let chain_sync = self.new_chain_sync()?;
 
 					self.warp = None;
 					self.chain_sync = Some(chain_sync);
@@ -452,33 +521,78 @@ where
 		} else if let Some(state) = &self.state {
 			if state.is_succeeded() {
 				info!(target: LOG_TARGET, "State sync is complete, continuing with block sync.");
+			} else if let Some((warp_sync_config, protocol_name)) = self.warp_fallback.take() {
+				warn!(
+					target: LOG_TARGET,
+					"Resumed state sync failed. Starting over with warp sync.",
+				);
+				let mut warp_sync = WarpSync::new(
+					self.client.clone(),
+					warp_sync_config,
+					protocol_name,
+					self.config.block_downloader.clone(),
+					self.config.min_peers_to_start_warp_sync,
+				)
+				.with_progress_store(self.client.clone());
+				for (peer_id, (best_hash, best_number)) in &self.peer_best_blocks {
+					warp_sync.add_peer(*peer_id, *best_hash, *best_number);
+				}
+
+				self.state = None;
+				self.warp = Some(warp_sync);
+				return Ok(())
 			} else {
 				error!(target: LOG_TARGET, "State sync failed. Falling back to full sync.");
 			}
-			let chain_sync = match ChainSync::new(
-				chain_sync_mode(self.config.mode),
-				self.client.clone(),
-				self.config.max_parallel_downloads,
-				self.config.max_blocks_per_request,
-				self.config.state_request_protocol_name.clone(),
-				self.config.block_downloader.clone(),
-				self.config.metrics_registry.as_ref(),
-				self.peer_best_blocks.iter().map(|(peer_id, (best_hash, best_number))| {
-					(*peer_id, *best_hash, *best_number)
-				}),
-			) {
-				Ok(chain_sync) => chain_sync,
-				Err(e) => {
-					error!(target: LOG_TARGET, "Failed to start `ChainSync`.");
-					return Err(e);
-				},
-			};
+			let chain_sync = self.new_chain_sync()?;
 
 			self.state = None;
+			self.warp_fallback = None;
 			self.chain_sync = Some(chain_sync);
 			Ok(())
 		} else {
 			unreachable!("Only warp & state strategies can finish; qed")
 		}
 	}
+
+	/// Create the `ChainSync` strategy following the finished one, with the peers known so far.
+	fn new_chain_sync(&self) -> Result<ChainSync<B, Client>, ClientError> {
+		ChainSync::new(
+			chain_sync_mode(self.config.mode),
+			self.client.clone(),
+			self.config.max_parallel_downloads,
+			self.config.max_blocks_per_request,
+			self.config.state_request_protocol_name.clone(),
+			self.config.block_downloader.clone(),
+			self.config.metrics_registry.as_ref(),
+			self.peer_best_blocks
+				.iter()
+				.map(|(peer_id, (best_hash, best_number))| (*peer_id, *best_hash, *best_number)),
+		)
+		.inspect_err(|_| error!(target: LOG_TARGET, "Failed to start `ChainSync`."))
+	}
+
+	/// Abandon the state sync resumed after a restart if the peers likely pruned the state of
+	/// its target, so that it starts over with a fresh warp sync.
+	fn check_resumed_state_target(&mut self) {
+		let (Some(state), Some(_)) = (&mut self.state, &self.warp_fallback) else { return };
+
+		let mut best_numbers =
+			self.peer_best_blocks.values().map(|(_, number)| *number).collect::<Vec<_>>();
+		if best_numbers.is_empty() {
+			return
+		}
+		best_numbers.sort();
+		// Same majority as the one the state is requested from.
+		let median = best_numbers[best_numbers.len() / 2];
+		let target = state.target_number();
+		if median > target.saturating_add(STATE_PRUNING_WINDOW.into()) {
+			warn!(
+				target: LOG_TARGET,
+				"Peers are at #{median}, past the state pruning window of the resumed state \
+				 sync target #{target}.",
+			);
+			state.abandon();
+		}
+	}
 }
@@ -0,0 +1,52 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Persistence of the warp and state sync progress.
+//!
+//! The verified warp proofs and the state of the completed state key ranges are written to the
+//! auxiliary storage of the database, so that the sync can resume from them after a restart.
+
+use crate::LOG_TARGET;
+use log::warn;
+use sc_client_api::AuxStore;
+
+/// Storage of the warp and state sync progress.
+pub trait SyncProgressStore: Send + Sync {
+	/// Load the value stored at `key`, if any.
+	fn load(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+	/// Atomically write `insert` and remove `delete`.
+	///
+	/// Failures are logged: the sync goes on, but can't be resumed from this point.
+	fn store(&self, insert: &[(&[u8], &[u8])], delete: &[&[u8]]);
+}
+
+impl<T: AuxStore + Send + Sync> SyncProgressStore for T {
+	fn load(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.get_aux(key).unwrap_or_else(|e| {
+			warn!(target: LOG_TARGET, "Failed to load the sync progress: {e}");
+			None
+		})
+	}
+
+	fn store(&self, insert: &[(&[u8], &[u8])], delete: &[&[u8]]) {
+		if let Err(e) = self.insert_aux(insert, delete) {
+			warn!(target: LOG_TARGET, "Failed to persist the sync progress: {e}");
+		}
+	}
+}
@@ -42,7 +42,18 @@ use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor},
 	Justifications, SaturatedConversion,
 };
-use std::{any::Any, collections::HashMap, sync::Arc};
+use std::{
+	any::Any,
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+/// Maximum number of key ranges of the state downloaded from several peers in parallel.
+pub(crate) const PARALLEL_STATE_RANGES: usize = 16;
+
+/// Time after which the range of a pending state request is requested from another peer too.
+const STALLED_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 mod rep {
 	use sc_network::ReputationChange as Rep;
@@ -56,10 +67,21 @@ mod rep {
 
 enum PeerState {
 	Available,
-	DownloadingState,
+	DownloadingState {
+		/// Key range requested.
+		range: usize,
+		/// Number of responses imported for the range when the request was sent.
+		generation: u64,
+		/// When the request was sent.
+		started: Instant,
+	},
 }
 
 impl PeerState {
+	fn downloading(range: usize, generation: u64) -> Self {
+		PeerState::DownloadingState { range, generation, started: Instant::now() }
+	}
+
 	fn is_available(&self) -> bool {
 		matches!(self, PeerState::Available)
 	}
@@ -78,6 +100,11 @@ pub struct StateStrategy<B: BlockT> {
 	actions: Vec<SyncingAction<B>>,
 	protocol_name: ProtocolName,
 	succeeded: bool,
+	/// Whether the download was abandoned before completion.
+	abandoned: bool,
+	/// Number of responses imported for each key range, to ignore the responses to the
+	/// requests that were sent again to another peer.
+	range_generations: HashMap<usize, u64>,
 }
 
 impl<B: BlockT> StateStrategy<B> {
@@ -85,6 +112,8 @@ impl<B: BlockT> StateStrategy<B> {
 	pub const STRATEGY_KEY: StrategyKey = StrategyKey::new("State");
 
 	/// Create a new instance.
+	///
+	/// Up to [`PARALLEL_STATE_RANGES`] key ranges of the state are downloaded in parallel.
 	pub fn new<Client>(
 		client: Arc<Client>,
 		target_header: B::Header,
@@ -103,18 +132,23 @@ impl<B: BlockT> StateStrategy<B> {
 			})
 			.collect();
 		Self {
-			state_sync: Box::new(StateSync::new(
-				client,
-				target_header,
-				target_body,
-				target_justifications,
-				skip_proof,
-			)),
+			state_sync: Box::new(
+				StateSync::new(
+					client,
+					target_header,
+					target_body,
+					target_justifications,
+					skip_proof,
+				)
+				.with_parallel_ranges(PARALLEL_STATE_RANGES),
+			),
 			peers,
 			disconnected_peers: DisconnectedPeers::new(),
 			actions: Vec::new(),
 			protocol_name,
 			succeeded: false,
+			abandoned: false,
+			range_generations: HashMap::new(),
 		}
 	}
 
@@ -138,6 +172,8 @@ impl<B: BlockT> StateStrategy<B> {
 			actions: Vec::new(),
 			protocol_name,
 			succeeded: false,
+			abandoned: false,
+			range_generations: HashMap::new(),
 		}
 	}
 
@@ -192,9 +228,12 @@ impl<B: BlockT> StateStrategy<B> {
 		peer_id: &PeerId,
 		response: &[u8],
 	) -> Result<(), BadPeer> {
-		if let Some(peer) = self.peers.get_mut(&peer_id) {
-			peer.state = PeerState::Available;
-		}
+		let request = self.peers.get_mut(peer_id).and_then(|peer| {
+			match std::mem::replace(&mut peer.state, PeerState::Available) {
+				PeerState::DownloadingState { range, generation, .. } => Some((range, generation)),
+				PeerState::Available => None,
+			}
+		});
 
 		let response = match StateResponse::decode(response) {
 			Ok(response) => response,
@@ -208,15 +247,31 @@ impl<B: BlockT> StateStrategy<B> {
 			},
 		};
 
+		let Some((range, generation)) = request else {
+			debug!(target: LOG_TARGET, "Unexpected state response from {peer_id}.");
+			return Ok(())
+		};
+		if self.range_generations.get(&range).copied().unwrap_or_default() != generation {
+			// The range was requested from another peer too, which answered first.
+			debug!(target: LOG_TARGET, "Outdated state response for range {range} from {peer_id}.");
+			return Ok(())
+		}
+
 		debug!(
 			target: LOG_TARGET,
-			"Importing state data from {} with {} keys, {} proof nodes.",
+			"Importing state data for range {} from {} with {} keys, {} proof nodes.",
+			range,
 			peer_id,
 			response.entries.len(),
 			response.proof.len(),
 		);
 
-		match self.state_sync.import(response) {
+		let result = self.state_sync.import(range, response);
+		if !matches!(result, ImportResult::BadResponse) {
+			*self.range_generations.entry(range).or_default() += 1;
+		}
+
+		match result {
 			ImportResult::Import(hash, header, state, body, justifications) => {
 				let origin = BlockOrigin::NetworkInitialSync;
 				let block = IncomingBlock {
@@ -227,6 +282,7 @@ impl<B: BlockT> StateStrategy<B> {
 					justifications,
 					origin: None,
 					allow_missing_state: true,
+					allow_missing_parent: false,
 					import_existing: true,
 					skip_execution: true,
 					state: Some(state),
@@ -278,33 +334,82 @@ impl<B: BlockT> StateStrategy<B> {
 				);
 			});
 			self.succeeded |= results.into_iter().any(|result| result.is_ok());
+			self.state_sync.on_finished();
 			self.actions.push(SyncingAction::Finished);
 		}
 	}
 
-	/// Produce state request.
-	fn state_request(&mut self) -> Option<(PeerId, StateRequest)> {
-		if self.state_sync.is_complete() {
-			return None
+	/// Abandon the download, finishing the strategy without success.
+	pub fn abandon(&mut self) {
+		if self.abandoned || self.state_sync.is_complete() {
+			return
 		}
+		self.abandoned = true;
 
-		if self
-			.peers
-			.values()
-			.any(|peer| matches!(peer.state, PeerState::DownloadingState))
-		{
-			// Only one state request at a time is possible.
-			return None
+		for (peer_id, peer) in self.peers.iter_mut() {
+			if !peer.state.is_available() {
+				peer.state = PeerState::Available;
+				self.actions.push(SyncingAction::CancelRequest {
+					peer_id: *peer_id,
+					key: Self::STRATEGY_KEY,
+				});
+			}
 		}
+		self.state_sync.on_finished();
+		self.actions.push(SyncingAction::Finished);
+	}
 
-		let peer_id =
-			self.schedule_next_peer(PeerState::DownloadingState, self.state_sync.target_number())?;
-		let request = self.state_sync.next_request();
-		trace!(
-			target: LOG_TARGET,
-			"New state request to {peer_id}: {request:?}.",
-		);
-		Some((peer_id, request))
+	/// Returns the number of the block whose state is downloaded.
+	pub fn target_number(&self) -> NumberFor<B> {
+		self.state_sync.target_number()
+	}
+
+	/// Produce state requests for the key ranges that are not being downloaded, and for the
+	/// ones whose request stalled.
+	fn state_requests(&mut self) -> Vec<(PeerId, StateRequest)> {
+		if self.abandoned || self.state_sync.is_complete() {
+			return Vec::new()
+		}
+
+		// Number of pending requests for each range, and whether one of them stalled.
+		let mut pending = HashMap::<usize, (usize, bool)>::new();
+		for peer in self.peers.values() {
+			if let PeerState::DownloadingState { range, started, .. } = peer.state {
+				let entry = pending.entry(range).or_default();
+				entry.0 += 1;
+				entry.1 |= started.elapsed() >= STALLED_REQUEST_TIMEOUT;
+			}
+		}
+
+		let target_number = self.state_sync.target_number();
+		let mut requests = Vec::new();
+		for range in self.state_sync.pending_ranges() {
+			// A stalled range is requested from one more peer only.
+			let stalled = match pending.get(&range) {
+				None => false,
+				Some((1, true)) => true,
+				Some(_) => continue,
+			};
+			let generation = self.range_generations.get(&range).copied().unwrap_or_default();
+			let Some(peer_id) =
+				self.schedule_next_peer(PeerState::downloading(range, generation), target_number)
+			else {
+				break
+			};
+			if stalled {
+				debug!(
+					target: LOG_TARGET,
+					"State request for range {range} stalled, requesting it from {peer_id} too.",
+				);
+			}
+			let request = self.state_sync.next_request(range);
+			trace!(
+				target: LOG_TARGET,
+				"New state request to {peer_id}: {request:?}.",
+			);
+			requests.push((peer_id, request));
+		}
+		requests
 	}
 
 	fn schedule_next_peer(
@@ -355,7 +460,7 @@ impl<B: BlockT> StateStrategy<B> {
 		&mut self,
 		network_service: &NetworkServiceHandle,
 	) -> impl Iterator<Item = SyncingAction<B>> {
-		let state_request = self.state_request().into_iter().map(|(peer_id, request)| {
+		let state_requests = self.state_requests().into_iter().map(|(peer_id, request)| {
 			let (tx, rx) = oneshot::channel();
 
 			network_service.start_request(
@@ -378,7 +483,7 @@ impl<B: BlockT> StateStrategy<B> {
 				remove_obsolete: false,
 			}
 		});
-		self.actions.extend(state_request);
+		self.actions.extend(state_requests);
 
 		std::mem::take(&mut self.actions).into_iter()
 	}
@@ -413,8 +518,9 @@ mod test {
 		pub StateSync<B: BlockT> {}
 
 		impl<B: BlockT> StateSyncProvider<B> for StateSync<B> {
-			fn import(&mut self, response: StateResponse) -> ImportResult<B>;
-			fn next_request(&self) -> StateRequest;
+			fn import(&mut self, range: usize, response: StateResponse) -> ImportResult<B>;
+			fn next_request(&self, range: usize) -> StateRequest;
+			fn pending_ranges(&self) -> Vec<usize>;
 			fn is_complete(&self) -> bool;
 			fn target_number(&self) -> NumberFor<B>;
 			fn target_hash(&self) -> B::Hash;
@@ -446,7 +552,7 @@ mod test {
 		);
 
 		assert!(state_strategy
-			.schedule_next_peer(PeerState::DownloadingState, Zero::zero())
+			.schedule_next_peer(PeerState::downloading(0, 0), Zero::zero())
 			.is_none());
 	}
 
@@ -479,7 +585,7 @@ mod test {
 			);
 
 			let peer_id =
-				state_strategy.schedule_next_peer(PeerState::DownloadingState, Zero::zero());
+				state_strategy.schedule_next_peer(PeerState::downloading(0, 0), Zero::zero());
 			assert!(*peers.get(&peer_id.unwrap()).unwrap() >= 6);
 		}
 	}
@@ -512,7 +618,7 @@ mod test {
 				ProtocolName::Static(""),
 			);
 
-			let peer_id = state_strategy.schedule_next_peer(PeerState::DownloadingState, 10);
+			let peer_id = state_strategy.schedule_next_peer(PeerState::downloading(0, 0), 10);
 			assert!(*peers.get(&peer_id.unwrap()).unwrap() == 10);
 		}
 	}
@@ -553,7 +659,7 @@ mod test {
 		// Disconnect the peer with an inflight request.
 		state_strategy.add_peer(tenth_peer, H256::random(), 10);
 		let peer_id: Option<PeerId> =
-			state_strategy.schedule_next_peer(PeerState::DownloadingState, 10);
+			state_strategy.schedule_next_peer(PeerState::downloading(0, 0), 10);
 		assert_eq!(tenth_peer, peer_id.unwrap());
 		state_strategy.remove_peer(&tenth_peer);
 
@@ -563,12 +669,12 @@ mod test {
 		// No peer available for 10'th best block because of the backoff.
 		state_strategy.add_peer(tenth_peer, H256::random(), 10);
 		let peer_id: Option<PeerId> =
-			state_strategy.schedule_next_peer(PeerState::DownloadingState, 10);
+			state_strategy.schedule_next_peer(PeerState::downloading(0, 0), 10);
 		assert!(peer_id.is_none());
 
 		// Other requests can still happen.
 		let peer_id: Option<PeerId> =
-			state_strategy.schedule_next_peer(PeerState::DownloadingState, 9);
+			state_strategy.schedule_next_peer(PeerState::downloading(0, 0), 9);
 		assert_eq!(ninth_peer, peer_id.unwrap());
 	}
 
@@ -596,14 +702,14 @@ mod test {
 			ProtocolName::Static(""),
 		);
 
-		let (_peer_id, request) = state_strategy.state_request().unwrap();
+		let (_peer_id, request) = state_strategy.state_requests().pop().unwrap();
 		let hash = Hash::decode(&mut &*request.block).unwrap();
 
 		assert_eq!(hash, target_block.header().hash());
 	}
 
 	#[test]
-	fn no_parallel_state_requests() {
+	fn parallel_state_requests_are_sent_for_distinct_ranges() {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
@@ -626,17 +732,89 @@ mod test {
 			ProtocolName::Static(""),
 		);
 
-		// First request is sent.
-		assert!(state_strategy.state_request().is_some());
+		// One request per peer synced as much as the median is sent, each for another range.
+		let requests = state_strategy.state_requests();
+		assert_eq!(requests.len(), 5);
+		let starts = requests.iter().map(|(_, request)| request.start.clone()).collect::<Vec<_>>();
+		assert!(starts.iter().enumerate().all(|(i, start)| !starts[..i].contains(start)));
+
+		// No more requests are sent while these are pending.
+		assert!(state_strategy.state_requests().is_empty());
+	}
+
+	#[test]
+	fn stalled_state_request_is_sent_to_another_peer() {
+		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
+		let target_block = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(client.chain_info().best_hash)
+			.with_parent_block_number(client.chain_info().best_number)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+
+		let initial_peers = (0..3).map(|_| (PeerId::random(), 10));
+		let mut state_strategy = StateStrategy::new_with_provider(
+			Box::new(StateSync::new(client, target_block.header().clone(), None, None, false)),
+			initial_peers,
+			ProtocolName::Static(""),
+		);
+
+		let mut requests = state_strategy.state_requests();
+		assert_eq!(requests.len(), 1);
+		let (first_peer, first_request) = requests.pop().unwrap();
+		assert!(state_strategy.state_requests().is_empty());
+
+		// The request stalls.
+		state_strategy.peers.get_mut(&first_peer).unwrap().state = PeerState::DownloadingState {
+			range: 0,
+			generation: 0,
+			started: Instant::now() - STALLED_REQUEST_TIMEOUT,
+		};
+
+		// The same range is requested from another peer, but only once.
+		let mut requests = state_strategy.state_requests();
+		assert_eq!(requests.len(), 1);
+		let (second_peer, second_request) = requests.pop().unwrap();
+		assert_ne!(first_peer, second_peer);
+		assert_eq!(first_request, second_request);
+		assert!(state_strategy.state_requests().is_empty());
+	}
+
+	#[test]
+	fn outdated_state_response_is_ignored() {
+		let mut state_sync_provider = MockStateSync::<Block>::new();
+		// Only the first response is imported.
+		state_sync_provider
+			.expect_import()
+			.times(1)
+			.returning(|_, _| ImportResult::Continue);
+		let first_peer = PeerId::random();
+		let second_peer = PeerId::random();
+		let initial_peers = [(first_peer, 10), (second_peer, 10)].into_iter();
+		let mut state_strategy = StateStrategy::new_with_provider(
+			Box::new(state_sync_provider),
+			initial_peers,
+			ProtocolName::Static(""),
+		);
+		// Both peers were asked for the same range.
+		state_strategy.peers.get_mut(&first_peer).unwrap().state = PeerState::downloading(0, 0);
+		state_strategy.peers.get_mut(&second_peer).unwrap().state = PeerState::downloading(0, 0);
+
+		let dummy_response = StateResponse::default().encode_to_vec();
+		state_strategy.on_state_response(&first_peer, dummy_response.clone());
+		state_strategy.on_state_response(&second_peer, dummy_response);
 
-		// No parallel request is sent.
-		assert!(state_strategy.state_request().is_none());
+		// The peer is not punished for the late response.
+		assert_eq!(state_strategy.actions.len(), 0);
+		assert!(state_strategy.peers.get(&second_peer).unwrap().state.is_available());
 	}
 
 	#[test]
 	fn received_state_response_makes_peer_available_again() {
 		let mut state_sync_provider = MockStateSync::<Block>::new();
-		state_sync_provider.expect_import().return_once(|_| ImportResult::Continue);
+		state_sync_provider.expect_import().return_once(|_, _| ImportResult::Continue);
 		let peer_id = PeerId::random();
 		let initial_peers = std::iter::once((peer_id, 10));
 		let mut state_strategy = StateStrategy::new_with_provider(
@@ -645,7 +823,7 @@ mod test {
 			ProtocolName::Static(""),
 		);
 		// Manually set the peer's state.
-		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::DownloadingState;
+		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::downloading(0, 0);
 
 		let dummy_response = StateResponse::default().encode_to_vec();
 		state_strategy.on_state_response(&peer_id, dummy_response);
@@ -657,7 +835,9 @@ mod test {
 	fn bad_state_response_drops_peer() {
 		let mut state_sync_provider = MockStateSync::<Block>::new();
 		// Provider says that state response is bad.
-		state_sync_provider.expect_import().return_once(|_| ImportResult::BadResponse);
+		state_sync_provider
+			.expect_import()
+			.return_once(|_, _| ImportResult::BadResponse);
 		let peer_id = PeerId::random();
 		let initial_peers = std::iter::once((peer_id, 10));
 		let mut state_strategy = StateStrategy::new_with_provider(
@@ -666,7 +846,7 @@ mod test {
 			ProtocolName::Static(""),
 		);
 		// Manually set the peer's state.
-		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::DownloadingState;
+		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::downloading(0, 0);
 		let dummy_response = StateResponse::default().encode_to_vec();
 		// Receiving response drops the peer.
 		assert!(matches!(
@@ -675,11 +855,42 @@ mod test {
 		));
 	}
 
+	#[test]
+	fn abandoned_strategy_cancels_requests_and_finishes() {
+		let mut state_sync_provider = MockStateSync::<Block>::new();
+		state_sync_provider.expect_is_complete().return_const(false);
+		let peer_id = PeerId::random();
+		let initial_peers = [(peer_id, 10), (PeerId::random(), 10)].into_iter();
+		let mut state_strategy = StateStrategy::new_with_provider(
+			Box::new(state_sync_provider),
+			initial_peers,
+			ProtocolName::Static(""),
+		);
+		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::downloading(0, 0);
+
+		state_strategy.abandon();
+		state_strategy.abandon();
+
+		assert!(!state_strategy.is_succeeded());
+		assert_eq!(state_strategy.actions.len(), 2);
+		assert!(matches!(
+			&state_strategy.actions[0],
+			SyncingAction::CancelRequest { peer_id: id, .. } if *id == peer_id,
+		));
+		assert!(matches!(&state_strategy.actions[1], SyncingAction::Finished));
+
+		// No more requests are sent.
+		let network_provider = NetworkServiceProvider::new();
+		let network_handle = network_provider.handle();
+		assert_eq!(state_strategy.actions(&network_handle).count(), 2);
+		assert_eq!(state_strategy.actions(&network_handle).count(), 0);
+	}
+
 	#[test]
 	fn partial_state_response_doesnt_generate_actions() {
 		let mut state_sync_provider = MockStateSync::<Block>::new();
 		// Sync provider says that the response is partial.
-		state_sync_provider.expect_import().return_once(|_| ImportResult::Continue);
+		state_sync_provider.expect_import().return_once(|_, _| ImportResult::Continue);
 		let peer_id = PeerId::random();
 		let initial_peers = std::iter::once((peer_id, 10));
 		let mut state_strategy = StateStrategy::new_with_provider(
@@ -688,7 +899,7 @@ mod test {
 			ProtocolName::Static(""),
 		);
 		// Manually set the peer's state .
-		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::DownloadingState;
+		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::downloading(0, 0);
 
 		let dummy_response = StateResponse::default().encode_to_vec();
 		state_strategy.on_state_response(&peer_id, dummy_response);
@@ -723,7 +934,7 @@ mod test {
 			body.clone(),
 			justifications.clone(),
 		);
-		state_sync_provider.expect_import().return_once(move |_| import);
+		state_sync_provider.expect_import().return_once(move |_, _| import);
 
 		// Reference values to check against.
 		let expected_origin = BlockOrigin::NetworkInitialSync;
@@ -735,6 +946,7 @@ mod test {
 			justifications,
 			origin: None,
 			allow_missing_state: true,
+			allow_missing_parent: false,
 			import_existing: true,
 			skip_execution: true,
 			state: Some(state),
@@ -750,7 +962,7 @@ mod test {
 			ProtocolName::Static(""),
 		);
 		// Manually set the peer's state .
-		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::DownloadingState;
+		state_strategy.peers.get_mut(&peer_id).unwrap().state = PeerState::downloading(0, 0);
 
 		// Receive response.
 		let dummy_response = StateResponse::default().encode_to_vec();
@@ -20,10 +20,11 @@
 
 use crate::{
 	schema::v1::{KeyValueStateEntry, StateEntry, StateRequest, StateResponse},
+	strategy::progress::SyncProgressStore,
 	LOG_TARGET,
 };
 use codec::{Decode, Encode};
-use log::debug;
+use log::{debug, info};
 use sc_client_api::{CompactProof, KeyValueStates, ProofProvider};
 use sc_consensus::ImportedState;
 use smallvec::SmallVec;
@@ -32,14 +33,21 @@ use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor},
 	Justifications,
 };
-use std::{collections::HashMap, fmt, sync::Arc};
+use sp_state_machine::KeyValueStorageLevel;
+use std::{
+	collections::{HashMap, HashSet},
+	fmt,
+	sync::Arc,
+};
 
 /// Generic state sync provider. Used for mocking in tests.
 pub trait StateSyncProvider<B: BlockT>: Send + Sync {
-	/// Validate and import a state response.
-	fn import(&mut self, response: StateResponse) -> ImportResult<B>;
-	/// Produce next state request.
-	fn next_request(&self) -> StateRequest;
+	/// Validate and import a state response for the key range `range`.
+	fn import(&mut self, range: usize, response: StateResponse) -> ImportResult<B>;
+	/// Produce next state request for the key range `range`.
+	fn next_request(&self, range: usize) -> StateRequest;
+	/// Returns the key ranges that are not downloaded yet.
+	fn pending_ranges(&self) -> Vec<usize>;
 	/// Check if the state is complete.
 	fn is_complete(&self) -> bool;
 	/// Returns target block number.
@@ -48,6 +56,11 @@ pub trait StateSyncProvider<B: BlockT>: Send + Sync {
 	fn target_hash(&self) -> B::Hash;
 	/// Returns state sync estimated progress.
 	fn progress(&self) -> StateSyncProgress;
+	/// Notify that the import of the target block has finished, successfully or not, or that
+	/// the download was abandoned.
+	///
+	/// The downloaded state is not needed anymore.
+	fn on_finished(&mut self) {}
 }
 
 // Reported state sync phase.
@@ -79,6 +92,69 @@ pub struct StateSyncProgress {
 	pub phase: StateSyncPhase,
 }
 
+/// Key of the [`PersistedStateSync`] record in the progress store.
+const PROGRESS_KEY: &[u8] = b"sync_state_progress";
+
+/// Prefix of the keys of the state of the completed ranges in the progress store.
+const RANGE_KEY_PREFIX: &[u8] = b"sync_state_range";
+
+fn range_key(index: u32) -> Vec<u8> {
+	(RANGE_KEY_PREFIX, index).encode()
+}
+
+/// Key values of the tries downloaded for a range, keyed by trie root.
+type RangeState = Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>;
+
+/// A [`StateRange`], as persisted.
+///
+/// Only the state of the completed ranges is persisted, the download of the pending ones
+/// restarts from their `start`.
+#[derive(Encode, Decode)]
+struct PersistedStateRange {
+	start: Vec<Vec<u8>>,
+	end: Option<Vec<u8>>,
+	complete: bool,
+}
+
+/// Progress of a state sync, persisted when it starts and each time a range is complete.
+#[derive(Encode, Decode)]
+struct PersistedStateSync<B: BlockT> {
+	target_header: B::Header,
+	target_body: Option<Vec<B::Extrinsic>>,
+	target_justifications: Option<Justifications>,
+	skip_proof: bool,
+	ranges: Vec<PersistedStateRange>,
+	/// Number of completed ranges whose state is stored under [`RANGE_KEY_PREFIX`].
+	completed_ranges: u32,
+	/// Number of times the download was resumed after a restart.
+	resumed: u32,
+}
+
+fn load_progress<B: BlockT>(store: &dyn SyncProgressStore) -> Option<PersistedStateSync<B>> {
+	let progress = store.load(PROGRESS_KEY)?;
+	PersistedStateSync::<B>::decode(&mut &progress[..]).ok()
+}
+
+/// Returns the target header of the state sync persisted in `store`, if any, along with the
+/// number of times its download was already resumed.
+pub(crate) fn persisted_target<B: BlockT>(
+	store: &dyn SyncProgressStore,
+) -> Option<(B::Header, u32)> {
+	load_progress::<B>(store).map(|progress| (progress.target_header, progress.resumed))
+}
+
+/// Remove the state sync progress persisted in `store`, including the state of at least `ranges`
+/// completed ranges.
+pub(crate) fn clear_persisted_progress<B: BlockT>(store: &dyn SyncProgressStore, ranges: u32) {
+	let ranges =
+		load_progress::<B>(store).map_or(ranges, |progress| progress.completed_ranges.max(ranges));
+	let keys = (0..ranges).map(range_key).collect::<Vec<_>>();
+	let mut delete = keys.iter().map(Vec::as_slice).collect::<Vec<_>>();
+	delete.push(PROGRESS_KEY);
+
+	store.store(&[], &delete);
+}
+
 /// Import state chunk result.
 pub enum ImportResult<B: BlockT> {
 	/// State is complete and ready for import.
@@ -89,14 +165,141 @@ pub enum ImportResult<B: BlockT> {
 	BadResponse,
 }
 
-struct StateSyncMetadata<B: BlockT> {
+/// End of the top trie key space, see [`key_position`].
+const KEY_SPACE_END: u128 = 1 << 64;
+
+/// Minimum number of responses a range is estimated to still need for its remaining keys to be
+/// split into several ranges.
+const MIN_RESPONSES_TO_SPLIT: u128 = 4;
+
+/// Position of `key` in the top trie key space, from its first 8 bytes.
+fn key_position(key: &[u8]) -> u128 {
+	let mut bytes = [0u8; 8];
+	let len = key.len().min(bytes.len());
+	bytes[..len].copy_from_slice(&key[..len]);
+	u64::from_be_bytes(bytes) as u128
+}
+
+/// Shortest key at `position` of the top trie key space.
+fn position_key(position: u128) -> Vec<u8> {
+	let mut key = (position as u64).to_be_bytes().to_vec();
+	while key.len() > 1 && key.last() == Some(&0) {
+		key.pop();
+	}
+	key
+}
+
+/// Part of the top trie key space, downloaded independently of the other ranges.
+///
+/// The state request protocol has no end key, so the responses may go past the end of the
+/// range. The key values past the end are dropped, as they are downloaded by the next range.
+struct StateRange {
+	/// Last downloaded key: the top trie key, followed by the child trie key when the download
+	/// stopped inside a child trie. The range starts after the initial value, exclusively.
 	last_key: SmallVec<[Vec<u8>; 2]>,
+	/// Last top trie key of the range, inclusively. `None` for the last range.
+	end: Option<Vec<u8>>,
+	/// Value of `last_key` when the download of the range started, or was resumed.
+	start: SmallVec<[Vec<u8>; 2]>,
+	complete: bool,
+}
+
+impl StateRange {
+	/// Position of the download cursor in the top trie key space.
+	fn cursor(&self) -> u128 {
+		self.last_key.first().map_or(0, |key| key_position(key))
+	}
+
+	/// Position of the end of the range in the top trie key space.
+	fn end_position(&self) -> u128 {
+		self.end.as_ref().map_or(KEY_SPACE_END, |end| key_position(end))
+	}
+
+	/// Approximate size of the part of the key space left to download.
+	fn remaining(&self) -> u128 {
+		if self.complete {
+			return 0
+		}
+		self.end_position().saturating_sub(self.cursor())
+	}
+}
+
+/// Split the top trie key space into `count` ranges of keys starting with the same number of
+/// distinct bytes.
+///
+/// This is only the initial split, the ranges that turn out to be large are split again while
+/// they are downloaded, see [`StateSyncMetadata::split_range`].
+fn split_key_space(count: usize) -> Vec<StateRange> {
+	let count = count.clamp(1, 256);
+	let boundary = |index: usize| vec![(index * 256 / count) as u8];
+	(0..count)
+		.map(|index| {
+			let start =
+				if index == 0 { SmallVec::new() } else { SmallVec::from_elem(boundary(index), 1) };
+			StateRange {
+				last_key: start.clone(),
+				end: (index + 1 < count).then(|| boundary(index + 1)),
+				start,
+				complete: false,
+			}
+		})
+		.collect()
+}
+
+/// Drop the key values of `levels` (the top trie first, followed by the child tries) that are
+/// past the top trie key `end`.
+///
+/// `resumed_child` tells if the first child trie is the one the request started in, which
+/// belongs to the range whatever keys follow it. Returns `true` if anything was dropped, meaning
+/// the response went through the end of the range.
+fn truncate_to_range(
+	levels: &mut Vec<KeyValueStorageLevel>,
+	end: &[u8],
+	resumed_child: bool,
+) -> bool {
+	let Some(top) = levels.first_mut() else { return false };
+	let in_range = top.key_values.partition_point(|(key, _)| key.as_slice() <= end);
+	if in_range == top.key_values.len() {
+		return false
+	}
+
+	let child_roots = |key_values: &[(Vec<u8>, Vec<u8>)]| {
+		key_values
+			.iter()
+			.filter(|(key, _)| well_known_keys::is_child_storage_key(key))
+			.map(|(_, root)| root.clone())
+			.collect::<HashSet<_>>()
+	};
+	let past_end = top.key_values.split_off(in_range);
+	let dropped_roots = child_roots(&past_end);
+	let kept_roots = child_roots(&top.key_values);
+
+	let mut index = 0;
+	levels.retain(|level| {
+		let keep = index == 0 ||
+			(index == 1 && resumed_child) ||
+			!dropped_roots.contains(&level.state_root) ||
+			kept_roots.contains(&level.state_root);
+		index += 1;
+		keep
+	});
+	true
+}
+
+struct StateSyncMetadata<B: BlockT> {
+	ranges: Vec<StateRange>,
+	/// Maximum number of ranges downloaded in parallel.
+	max_ranges: usize,
 	target_header: B::Header,
 	target_body: Option<Vec<B::Extrinsic>>,
 	target_justifications: Option<Justifications>,
 	complete: bool,
 	imported_bytes: u64,
 	skip_proof: bool,
+	/// Number of completed ranges whose state is written to the progress store.
+	persisted_ranges: u32,
+	/// Number of times the download was resumed after a restart.
+	resumed: u32,
 }
 
 impl<B: BlockT> StateSyncMetadata<B> {
@@ -113,17 +316,61 @@ impl<B: BlockT> StateSyncMetadata<B> {
 		*self.target_header.state_root()
 	}
 
-	fn next_request(&self) -> StateRequest {
+	fn next_request(&self, range: usize) -> StateRequest {
 		StateRequest {
 			block: self.target_hash().encode(),
-			start: self.last_key.clone().into_vec(),
+			start: self.ranges.get(range).map_or(Vec::new(), |range| range.last_key.to_vec()),
 			no_proof: self.skip_proof,
 		}
 	}
 
+	/// Split the keys left to download in `range` into several ranges, if the last response,
+	/// which moved the cursor of the range from `previous_cursor`, only covered a small part of
+	/// them.
+	///
+	/// Ranges are only added while less than [`Self::max_ranges`] are pending, so that the large
+	/// parts of the state keep being downloaded from several peers once the small ones are
+	/// complete.
+	fn split_range(&mut self, range: usize, previous_cursor: u128) {
+		let pending = self.ranges.iter().filter(|range| !range.complete).count();
+		let state_range = &mut self.ranges[range];
+		let (cursor, end) = (state_range.cursor(), state_range.end_position());
+		let step = cursor.saturating_sub(previous_cursor);
+		if state_range.complete || step == 0 || pending >= self.max_ranges {
+			return
+		}
+
+		let responses_left = end.saturating_sub(cursor) / step;
+		let count =
+			(responses_left / MIN_RESPONSES_TO_SPLIT).min((self.max_ranges - pending + 1) as u128);
+		if count < 2 {
+			return
+		}
+		// The boundaries are at least `MIN_RESPONSES_TO_SPLIT` apart, between the cursor and
+		// the end of the range.
+		let boundaries = (1..count)
+			.map(|index| position_key(cursor + (end - cursor) * index / count))
+			.collect::<Vec<_>>();
+		debug!(
+			target: LOG_TARGET,
+			"Splitting the state range {range} into {count} ranges, ~{responses_left} responses left",
+		);
+
+		let end = std::mem::replace(&mut state_range.end, boundaries.first().cloned());
+		for (index, start) in boundaries.iter().enumerate() {
+			self.ranges.push(StateRange {
+				last_key: SmallVec::from_elem(start.clone(), 1),
+				end: boundaries.get(index + 1).cloned().or_else(|| end.clone()),
+				start: SmallVec::from_elem(start.clone(), 1),
+				complete: false,
+			});
+		}
+	}
+
 	fn progress(&self) -> StateSyncProgress {
-		let cursor = *self.last_key.get(0).and_then(|last| last.get(0)).unwrap_or(&0u8);
-		let percent_done = cursor as u32 * 100 / 256;
+		let remaining = self.ranges.iter().map(StateRange::remaining).sum::<u128>();
+		let percent_done =
+			((KEY_SPACE_END - remaining.min(KEY_SPACE_END)) * 100 / KEY_SPACE_END) as u32;
 		StateSyncProgress {
 			percentage: percent_done,
 			size: self.imported_bytes,
@@ -142,7 +389,12 @@ impl<B: BlockT> StateSyncMetadata<B> {
 pub struct StateSync<B: BlockT, Client> {
 	metadata: StateSyncMetadata<B>,
 	state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>,
+	/// State downloaded for the pending ranges, kept until they are complete to be persisted.
+	///
+	/// Only filled when the progress is persisted.
+	range_state: HashMap<usize, RangeState>,
 	client: Arc<Client>,
+	progress_store: Option<Arc<dyn SyncProgressStore>>,
 }
 
 impl<B, Client> StateSync<B, Client>
@@ -161,16 +413,146 @@ where
 		Self {
 			client,
 			metadata: StateSyncMetadata {
-				last_key: SmallVec::default(),
+				ranges: split_key_space(1),
+				max_ranges: 1,
 				target_header,
 				target_body,
 				target_justifications,
 				complete: false,
 				imported_bytes: 0,
 				skip_proof,
+				persisted_ranges: 0,
+				resumed: 0,
 			},
 			state: HashMap::default(),
+			range_state: HashMap::default(),
+			progress_store: None,
+		}
+	}
+
+	/// Download up to `ranges` key ranges in parallel.
+	///
+	/// The key space is initially split into `ranges` ranges, unless the download has already
+	/// started.
+	pub fn with_parallel_ranges(mut self, ranges: usize) -> Self {
+		self.metadata.max_ranges = ranges.max(1);
+		if self.metadata.imported_bytes == 0 {
+			self.metadata.ranges = split_key_space(ranges);
 		}
+		self
+	}
+
+	/// Persist the state of the ranges to `store` once they are complete, and resume the
+	/// download persisted there if it targets the same block.
+	pub fn with_progress_store(mut self, store: Arc<dyn SyncProgressStore>) -> Self {
+		self.progress_store = Some(store.clone());
+		if !self.resume() {
+			self.clear_progress();
+			store.store(&[(PROGRESS_KEY, &self.progress_record()[..])], &[]);
+		}
+		self
+	}
+
+	/// Restore the state downloaded by a previous run. Returns `false` if there is nothing to
+	/// resume from.
+	fn resume(&mut self) -> bool {
+		let Some(store) = self.progress_store.clone() else { return false };
+		let Some(progress) = load_progress::<B>(&*store) else { return false };
+		if progress.target_header.hash() != self.metadata.target_hash() ||
+			progress.skip_proof != self.metadata.skip_proof
+		{
+			debug!(target: LOG_TARGET, "Discarding the state sync progress of another target");
+			return false
+		}
+		// The import of a complete state can't be restarted from here, download it again.
+		if progress.ranges.iter().all(|range| range.complete) {
+			debug!(target: LOG_TARGET, "Discarding the progress of a complete state sync");
+			return false
+		}
+
+		for index in 0..progress.completed_ranges {
+			let Some(range_state) = store
+				.load(&range_key(index))
+				.and_then(|range_state| RangeState::decode(&mut &range_state[..]).ok())
+			else {
+				debug!(target: LOG_TARGET, "Missing state of the range {index}, starting over");
+				self.state.clear();
+				self.metadata.imported_bytes = 0;
+				return false
+			};
+			for (state_root, key_values) in range_state {
+				self.process_state_key_values(state_root, key_values);
+			}
+		}
+
+		self.metadata.target_body = progress.target_body;
+		self.metadata.target_justifications = progress.target_justifications;
+		self.metadata.ranges = progress
+			.ranges
+			.into_iter()
+			.map(|range| StateRange {
+				last_key: range.start.clone().into(),
+				end: range.end,
+				start: range.start.into(),
+				complete: range.complete,
+			})
+			.collect();
+		self.metadata.persisted_ranges = progress.completed_ranges;
+		self.metadata.resumed = progress.resumed + 1;
+		// Count the restart even if the node doesn't progress until the next one.
+		store.store(&[(PROGRESS_KEY, &self.progress_record()[..])], &[]);
+		info!(
+			target: LOG_TARGET,
+			"Resuming state sync of #{} ({} MiB downloaded)",
+			self.metadata.target_number(),
+			self.metadata.imported_bytes / (1024 * 1024),
+		);
+		true
+	}
+
+	/// Encoded progress record, referencing the state of the
+	/// [`StateSyncMetadata::persisted_ranges`] completed ranges.
+	fn progress_record(&self) -> Vec<u8> {
+		PersistedStateSync::<B> {
+			target_header: self.metadata.target_header.clone(),
+			target_body: self.metadata.target_body.clone(),
+			target_justifications: self.metadata.target_justifications.clone(),
+			skip_proof: self.metadata.skip_proof,
+			ranges: self
+				.metadata
+				.ranges
+				.iter()
+				.map(|range| PersistedStateRange {
+					start: range.start.to_vec(),
+					end: range.end.clone(),
+					complete: range.complete,
+				})
+				.collect(),
+			completed_ranges: self.metadata.persisted_ranges,
+			resumed: self.metadata.resumed,
+		}
+		.encode()
+	}
+
+	/// Persist the state downloaded for the completed `range`, along with the ranges.
+	fn persist_range(&mut self, range: usize) {
+		let Some(store) = self.progress_store.clone() else { return };
+
+		let range_state = self.range_state.remove(&range).unwrap_or_default();
+		let key = range_key(self.metadata.persisted_ranges);
+		self.metadata.persisted_ranges += 1;
+		let progress = self.progress_record();
+
+		store.store(&[(&key[..], &range_state.encode()[..]), (PROGRESS_KEY, &progress[..])], &[]);
+	}
+
+	/// Remove the persisted progress, including the state of the ranges of a discarded record.
+	fn clear_progress(&mut self) {
+		let Some(store) = &self.progress_store else { return };
+
+		clear_persisted_progress::<B>(&**store, self.metadata.persisted_ranges);
+		self.metadata.persisted_ranges = 0;
+		self.range_state.clear();
 	}
 
 	fn process_state_key_values(
@@ -180,14 +562,10 @@ where
 	) {
 		let is_top = state_root.is_empty();
 
+		// A child trie referenced from several ranges is downloaded more than once, the
+		// duplicated key values are merged on import.
 		let entry = self.state.entry(state_root).or_default();
 
-		if entry.0.len() > 0 && entry.1.len() > 1 {
-			// Already imported child_trie with same root.
-			// Warning this will not work with parallel download.
-			return;
-		}
-
 		let mut child_storage_roots = Vec::new();
 
 		for (key, value) in key_values {
@@ -205,46 +583,113 @@ where
 		}
 	}
 
-	fn process_state_verified(&mut self, values: KeyValueStates) {
+	fn process_key_value_states(&mut self, range: usize, values: KeyValueStates) {
 		for values in values.0 {
+			if self.progress_store.is_some() {
+				let range_state = self.range_state.entry(range).or_default();
+				range_state.push((values.state_root.clone(), values.key_values.clone()));
+			}
 			self.process_state_key_values(values.state_root, values.key_values);
 		}
 	}
 
-	fn process_state_unverified(&mut self, response: StateResponse) -> bool {
-		let mut complete = true;
-		// if the trie is a child trie and one of its parent trie is empty,
-		// the parent cursor stays valid.
-		// Empty parent trie content only happens when all the response content
-		// is part of a single child trie.
-		if self.metadata.last_key.len() == 2 && response.entries[0].entries.is_empty() {
-			// Do not remove the parent trie position.
-			self.metadata.last_key.pop();
-		} else {
-			self.metadata.last_key.clear();
+	fn import_verified(&mut self, range: usize, response: StateResponse) -> bool {
+		debug!(target: LOG_TARGET, "Importing state from {} trie nodes", response.proof.len());
+		let proof_size = response.proof.len() as u64;
+		let proof = match CompactProof::decode(&mut response.proof.as_ref()) {
+			Ok(proof) => proof,
+			Err(e) => {
+				debug!(target: LOG_TARGET, "Error decoding proof: {:?}", e);
+				return false
+			},
+		};
+		let target_root = self.metadata.target_root();
+		let state_range = &mut self.metadata.ranges[range];
+		let (mut values, completed) = match self.client.verify_range_proof(
+			target_root,
+			proof,
+			state_range.last_key.as_slice(),
+		) {
+			Err(e) => {
+				debug!(target: LOG_TARGET, "StateResponse failed proof verification: {}", e);
+				return false
+			},
+			Ok(values) => values,
+		};
+		debug!(target: LOG_TARGET, "Imported with {} keys", values.len());
+
+		let resumed_child = state_range.last_key.len() == 2;
+		let reached_end = state_range
+			.end
+			.as_ref()
+			.map_or(false, |end| truncate_to_range(&mut values.0, end, resumed_child));
+		if reached_end || completed == 0 {
+			state_range.complete = true;
+		} else if !values.update_last_key(completed, &mut state_range.last_key) {
+			debug!(target: LOG_TARGET, "Error updating key cursor, depth: {}", completed);
 		}
-		for state in response.entries {
-			debug!(
-				target: LOG_TARGET,
-				"Importing state from {:?} to {:?}",
-				state.entries.last().map(|e| sp_core::hexdisplay::HexDisplay::from(&e.key)),
-				state.entries.first().map(|e| sp_core::hexdisplay::HexDisplay::from(&e.key)),
-			);
-
-			if !state.complete {
-				if let Some(e) = state.entries.last() {
-					self.metadata.last_key.push(e.key.clone());
+
+		self.process_key_value_states(range, values);
+		self.metadata.imported_bytes += proof_size;
+		true
+	}
+
+	fn import_unverified(&mut self, range: usize, response: StateResponse) {
+		let mut completes = Vec::with_capacity(response.entries.len());
+		let mut levels = response
+			.entries
+			.into_iter()
+			.map(|KeyValueStateEntry { state_root, entries, complete }| {
+				debug!(
+					target: LOG_TARGET,
+					"Importing state from {:?} to {:?}",
+					entries.first().map(|e| sp_core::hexdisplay::HexDisplay::from(&e.key)),
+					entries.last().map(|e| sp_core::hexdisplay::HexDisplay::from(&e.key)),
+				);
+				completes.push(complete);
+				KeyValueStorageLevel {
+					state_root,
+					parent_storage_keys: Vec::new(),
+					key_values: entries
+						.into_iter()
+						.map(|StateEntry { key, value }| (key, value))
+						.collect(),
 				}
-				complete = false;
+			})
+			.collect::<Vec<_>>();
+
+		let state_range = &mut self.metadata.ranges[range];
+		let resumed_child = state_range.last_key.len() == 2;
+		let reached_end = state_range
+			.end
+			.as_ref()
+			.map_or(false, |end| truncate_to_range(&mut levels, end, resumed_child));
+		if reached_end {
+			state_range.complete = true;
+		} else {
+			// if the trie is a child trie and one of its parent trie is empty,
+			// the parent cursor stays valid.
+			// Empty parent trie content only happens when all the response content
+			// is part of a single child trie.
+			if resumed_child && levels.first().map_or(false, |top| top.key_values.is_empty()) {
+				// Do not remove the parent trie position.
+				state_range.last_key.pop();
+			} else {
+				state_range.last_key.clear();
 			}
-
-			let KeyValueStateEntry { state_root, entries, complete: _ } = state;
-			self.process_state_key_values(
-				state_root,
-				entries.into_iter().map(|StateEntry { key, value }| (key, value)),
-			);
+			let mut complete = true;
+			for (level, level_complete) in levels.iter().zip(completes) {
+				if !level_complete {
+					if let Some((key, _)) = level.key_values.last() {
+						state_range.last_key.push(key.clone());
+					}
+					complete = false;
+				}
+			}
+			state_range.complete = complete;
 		}
-		complete
+
+		self.process_key_value_states(range, KeyValueStates(levels))
 	}
 }
 
@@ -254,7 +699,7 @@ where
 	Client: ProofProvider<B> + Send + Sync + 'static,
 {
 	///  Validate and import a state response.
-	fn import(&mut self, response: StateResponse) -> ImportResult<B> {
+	fn import(&mut self, range: usize, response: StateResponse) -> ImportResult<B> {
 		if response.entries.is_empty() && response.proof.is_empty() {
 			debug!(target: LOG_TARGET, "Bad state response");
 			return ImportResult::BadResponse
@@ -263,45 +708,22 @@ where
 			debug!(target: LOG_TARGET, "Missing proof");
 			return ImportResult::BadResponse
 		}
-		let complete = if !self.metadata.skip_proof {
-			debug!(target: LOG_TARGET, "Importing state from {} trie nodes", response.proof.len());
-			let proof_size = response.proof.len() as u64;
-			let proof = match CompactProof::decode(&mut response.proof.as_ref()) {
-				Ok(proof) => proof,
-				Err(e) => {
-					debug!(target: LOG_TARGET, "Error decoding proof: {:?}", e);
-					return ImportResult::BadResponse
-				},
-			};
-			let (values, completed) = match self.client.verify_range_proof(
-				self.metadata.target_root(),
-				proof,
-				self.metadata.last_key.as_slice(),
-			) {
-				Err(e) => {
-					debug!(
-						target: LOG_TARGET,
-						"StateResponse failed proof verification: {}",
-						e,
-					);
-					return ImportResult::BadResponse
-				},
-				Ok(values) => values,
-			};
-			debug!(target: LOG_TARGET, "Imported with {} keys", values.len());
-
-			let complete = completed == 0;
-			if !complete && !values.update_last_key(completed, &mut self.metadata.last_key) {
-				debug!(target: LOG_TARGET, "Error updating key cursor, depth: {}", completed);
-			};
-
-			self.process_state_verified(values);
-			self.metadata.imported_bytes += proof_size;
-			complete
+		if self.metadata.ranges.get(range).map_or(true, |range| range.complete) {
+			debug!(target: LOG_TARGET, "Ignoring state response for complete range {range}");
+			return ImportResult::Continue
+		}
+		let previous_cursor = self.metadata.ranges[range].cursor();
+		if !self.metadata.skip_proof {
+			if !self.import_verified(range, response) {
+				return ImportResult::BadResponse
+			}
 		} else {
-			self.process_state_unverified(response)
-		};
-		if complete {
+			self.import_unverified(range, response);
+		}
+		self.metadata.split_range(range, previous_cursor);
+		if self.metadata.ranges.iter().all(|range| range.complete) {
+			// The state is handed over to the import, a restart downloads it again anyway.
+			self.clear_progress();
 			self.metadata.complete = true;
 			let target_hash = self.metadata.target_hash();
 			ImportResult::Import(
@@ -311,14 +733,26 @@ where
 				self.metadata.target_body.clone(),
 				self.metadata.target_justifications.clone(),
 			)
+		} else if self.metadata.ranges[range].complete {
+			self.persist_range(range);
+			ImportResult::Continue
 		} else {
 			ImportResult::Continue
 		}
 	}
 
 	/// Produce next state request.
-	fn next_request(&self) -> StateRequest {
-		self.metadata.next_request()
+	fn next_request(&self, range: usize) -> StateRequest {
+		self.metadata.next_request(range)
+	}
+
+	fn pending_ranges(&self) -> Vec<usize> {
+		self.metadata
+			.ranges
+			.iter()
+			.enumerate()
+			.filter_map(|(index, range)| (!range.complete).then_some(index))
+			.collect()
 	}
 
 	/// Check if the state is complete.
@@ -340,4 +774,158 @@ where
 	fn progress(&self) -> StateSyncProgress {
 		self.metadata.progress()
 	}
+
+	fn on_finished(&mut self) {
+		self.clear_progress();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use sp_blockchain::HeaderBackend;
+	use sp_core::storage::well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX;
+	use substrate_test_runtime_client::{
+		runtime::Block, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+	};
+
+	fn level(state_root: &[u8], keys: &[&[u8]]) -> KeyValueStorageLevel {
+		KeyValueStorageLevel {
+			state_root: state_root.to_vec(),
+			parent_storage_keys: Vec::new(),
+			key_values: keys.iter().map(|key| (key.to_vec(), state_root.to_vec())).collect(),
+		}
+	}
+
+	fn child_key(name: &[u8]) -> Vec<u8> {
+		[DEFAULT_CHILD_STORAGE_KEY_PREFIX, name].concat()
+	}
+
+	#[test]
+	fn key_space_is_split_into_adjacent_ranges() {
+		let ranges = split_key_space(4);
+		assert_eq!(ranges.len(), 4);
+
+		assert!(ranges[0].last_key.is_empty());
+		for (range, next) in ranges.iter().zip(ranges.iter().skip(1)) {
+			assert_eq!(range.end.as_ref(), next.last_key.first());
+		}
+		assert_eq!(ranges[3].end, None);
+		assert_eq!(ranges[1].last_key.to_vec(), vec![vec![64u8]]);
+	}
+
+	#[test]
+	fn large_range_is_split_once_another_one_is_complete() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let header = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let response = |key: &[u8], complete| StateResponse {
+			entries: vec![KeyValueStateEntry {
+				state_root: Vec::new(),
+				entries: vec![StateEntry { key: key.to_vec(), value: vec![1] }],
+				complete,
+			}],
+			proof: Vec::new(),
+		};
+		let mut state_sync =
+			StateSync::new(client, header, None, None, true).with_parallel_ranges(4);
+
+		// The ranges are not split while they are all pending.
+		assert!(matches!(state_sync.import(0, response(&[0, 1], false)), ImportResult::Continue));
+		assert_eq!(state_sync.metadata.ranges.len(), 4);
+
+		// Once a range is complete, the keys left in the first one are split in two.
+		assert!(matches!(state_sync.import(1, response(&[65], true)), ImportResult::Continue));
+		assert!(matches!(state_sync.import(0, response(&[0, 2], false)), ImportResult::Continue));
+		assert_eq!(state_sync.pending_ranges(), vec![0, 2, 3, 4]);
+		let boundary = vec![0x20, 0x01];
+		assert_eq!(state_sync.metadata.ranges[0].end, Some(boundary.clone()));
+		assert_eq!(state_sync.next_request(4).start, vec![boundary]);
+		assert_eq!(state_sync.metadata.ranges[4].end, Some(vec![64]));
+		assert_eq!(state_sync.progress().percentage, 25);
+	}
+
+	#[test]
+	fn response_is_truncated_to_range() {
+		let in_range = child_key(b"a");
+		let past_end = child_key(b"b");
+		let top = KeyValueStorageLevel {
+			state_root: Vec::new(),
+			parent_storage_keys: Vec::new(),
+			key_values: vec![
+				(in_range.clone(), b"root_a".to_vec()),
+				(past_end.clone(), b"root_b".to_vec()),
+			],
+		};
+		let mut levels = vec![top, level(b"root_a", &[b"1"]), level(b"root_b", &[b"2"])];
+
+		// Nothing is dropped when the response ends in the range.
+		assert!(!truncate_to_range(&mut levels, &past_end, false));
+		assert_eq!(levels.len(), 3);
+
+		// The top trie keys past the end and their child tries are dropped.
+		assert!(truncate_to_range(&mut levels, &in_range, false));
+		assert_eq!(levels.len(), 2);
+		assert_eq!(levels[0].key_values, vec![(in_range, b"root_a".to_vec())]);
+		assert_eq!(levels[1].state_root, b"root_a".to_vec());
+	}
+
+	#[test]
+	fn resumed_child_trie_is_kept() {
+		// The same child trie is referenced again past the end of the range.
+		let top = KeyValueStorageLevel {
+			state_root: Vec::new(),
+			parent_storage_keys: Vec::new(),
+			key_values: vec![(child_key(b"b"), b"root_a".to_vec())],
+		};
+		let mut levels = vec![top, level(b"root_a", &[b"1"])];
+
+		// The child trie the request started in belongs to the range.
+		assert!(truncate_to_range(&mut levels, b"\x01", true));
+		assert_eq!(levels.len(), 2);
+		assert!(levels[0].key_values.is_empty());
+	}
+
+	#[test]
+	fn progress_is_resumed_and_dropped_once_complete() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let header = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let store: Arc<dyn SyncProgressStore> = client.clone();
+		let response = |key: &[u8], complete| StateResponse {
+			entries: vec![KeyValueStateEntry {
+				state_root: Vec::new(),
+				entries: vec![StateEntry { key: key.to_vec(), value: vec![1] }],
+				complete,
+			}],
+			proof: Vec::new(),
+		};
+
+		let new_state_sync = || {
+			StateSync::new(client.clone(), header.clone(), None, None, true)
+				.with_parallel_ranges(2)
+				.with_progress_store(store.clone())
+		};
+
+		let mut state_sync = new_state_sync();
+		assert_eq!(persisted_target::<Block>(&*store), Some((header.clone(), 0)));
+		assert!(matches!(state_sync.import(0, response(&[1], false)), ImportResult::Continue));
+		assert!(matches!(state_sync.import(1, response(&[129], true)), ImportResult::Continue));
+		assert!(store.load(&range_key(0)).is_some());
+
+		// The restarts are counted, the complete range is restored and the pending one is
+		// downloaded again from its start.
+		let mut state_sync = new_state_sync();
+		assert_eq!(persisted_target::<Block>(&*store), Some((header.clone(), 1)));
+		assert_eq!(state_sync.pending_ranges(), vec![0]);
+		assert!(state_sync.next_request(0).start.is_empty());
+
+		// Nothing is left in the store once the state is complete.
+		let ImportResult::Import(_, _, imported, _, _) = state_sync.import(0, response(&[1], true))
+		else {
+			panic!("the state should be complete")
+		};
+		let keys = imported.state.0.iter().flat_map(|level| &level.key_values).count();
+		assert_eq!(keys, 2);
+		assert_eq!(persisted_target::<Block>(&*store), None);
+		assert!(store.load(&range_key(0)).is_none());
+	}
 }
@@ -24,21 +24,23 @@ use crate::{
 	block_relay_protocol::{BlockDownloader, BlockResponseError},
 	service::network::NetworkServiceHandle,
 	strategy::{
-		chain_sync::validate_blocks, disconnected_peers::DisconnectedPeers, StrategyKey,
-		SyncingAction,
+		chain_sync::validate_blocks, disconnected_peers::DisconnectedPeers,
+		progress::SyncProgressStore, StrategyKey, SyncingAction,
 	},
 	types::{BadPeer, SyncState, SyncStatus},
 	LOG_TARGET,
 };
 use codec::{Decode, Encode};
 use futures::{channel::oneshot, FutureExt};
-use log::{debug, error, trace, warn};
+use log::{debug, error, info, trace, warn};
+use sc_consensus::{BlockImportError, BlockImportStatus, IncomingBlock};
 use sc_network::{IfDisconnected, ProtocolName};
 use sc_network_common::sync::message::{
 	BlockAnnounce, BlockAttributes, BlockData, BlockRequest, Direction, FromBlock,
 };
 use sc_network_types::PeerId;
 use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
 use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor, Zero},
 	Justifications, SaturatedConversion,
@@ -48,6 +50,17 @@ use std::{any::Any, collections::HashMap, fmt, sync::Arc};
 /// Number of peers that need to be connected before warp sync is started.
 const MIN_PEERS_TO_START_WARP_SYNC: usize = 3;
 
+/// Key of the [`PersistedWarpSync`] record in the progress store.
+const PROGRESS_KEY: &[u8] = b"sync_warp_progress";
+
+/// Last verified authority set, persisted after each warp proof.
+#[derive(Encode, Decode)]
+struct PersistedWarpSync<H> {
+	set_id: SetId,
+	authorities: AuthorityList,
+	last_hash: H,
+}
+
 /// Scale-encoded warp sync proof response.
 pub struct EncodedProof(pub Vec<u8>);
 
@@ -174,6 +187,8 @@ enum Phase<B: BlockT> {
 	},
 	/// Downloading target block.
 	TargetBlock(B::Header),
+	/// Importing the target block without its state.
+	ImportingTargetBlock(B::Header),
 	/// Warp sync is complete.
 	Complete,
 }
@@ -215,6 +230,10 @@ pub struct WarpSync<B: BlockT, Client> {
 	result: Option<WarpSyncResult<B>>,
 	/// Number of peers that need to be connected before warp sync is started.
 	min_peers_to_start_warp_sync: usize,
+	/// Storage of the verified authority set, to resume after a restart.
+	progress_store: Option<Arc<dyn SyncProgressStore>>,
+	/// Whether the target block is imported without its state before finishing.
+	import_target_block: bool,
 }
 
 impl<B, Client> WarpSync<B, Client>
@@ -254,6 +273,8 @@ where
 				actions: vec![SyncingAction::Finished],
 				result: None,
 				min_peers_to_start_warp_sync,
+				progress_store: None,
+				import_target_block: false,
 			}
 		}
 
@@ -275,7 +296,28 @@ where
 			actions: Vec::new(),
 			result: None,
 			min_peers_to_start_warp_sync,
+			progress_store: None,
+			import_target_block: false,
+		}
+	}
+
+	/// Import the target block without its state, and finish once it is imported.
+	///
+	/// Used by the light nodes, which continue with the headers following the target block
+	/// rather than downloading its state.
+	pub fn with_target_block_import(mut self) -> Self {
+		self.import_target_block = true;
+		self
+	}
+
+	/// Persist the last verified authority set to `store` after each warp proof, and resume
+	/// from the one persisted there.
+	pub fn with_progress_store(mut self, store: Arc<dyn SyncProgressStore>) -> Self {
+		if matches!(self.phase, Phase::Complete) {
+			store.store(&[], &[PROGRESS_KEY]);
 		}
+		self.progress_store = Some(store);
+		self
 	}
 
 	/// Notify that a new peer has connected.
@@ -327,11 +369,27 @@ where
 			return
 		}
 
-		self.phase = Phase::WarpProof {
-			set_id: 0,
-			authorities: warp_sync_provider.current_authorities(),
-			last_hash: self.client.info().genesis_hash,
-			warp_sync_provider: Arc::clone(warp_sync_provider),
+		let persisted = self
+			.progress_store
+			.as_ref()
+			.and_then(|store| store.load(PROGRESS_KEY))
+			.and_then(|progress| PersistedWarpSync::<B::Hash>::decode(&mut &progress[..]).ok());
+		self.phase = match persisted {
+			Some(PersistedWarpSync { set_id, authorities, last_hash }) => {
+				info!(target: LOG_TARGET, "Resuming warp sync from authority set {set_id}");
+				Phase::WarpProof {
+					set_id,
+					authorities,
+					last_hash,
+					warp_sync_provider: Arc::clone(warp_sync_provider),
+				}
+			},
+			None => Phase::WarpProof {
+				set_id: 0,
+				authorities: warp_sync_provider.current_authorities(),
+				last_hash: self.client.info().genesis_hash,
+				warp_sync_provider: Arc::clone(warp_sync_provider),
+			},
 		};
 		trace!(target: LOG_TARGET, "Started warp sync with {} peers.", self.peers.len());
 	}
@@ -415,6 +473,14 @@ where
 				*authorities = new_authorities;
 				*last_hash = new_last_hash;
 				self.total_proof_bytes += response.0.len() as u64;
+				if let Some(store) = &self.progress_store {
+					let progress = PersistedWarpSync {
+						set_id: *set_id,
+						authorities: authorities.clone(),
+						last_hash: *last_hash,
+					};
+					store.store(&[(PROGRESS_KEY, &progress.encode()[..])], &[]);
+				}
 			},
 			Ok(VerificationResult::Complete(new_set_id, _, header)) => {
 				log::debug!(
@@ -502,14 +568,64 @@ where
 			return Err(BadPeer(peer_id, rep::VERIFICATION_FAIL))
 		}
 
+		let header = header.clone();
+		// The state sync persists its own progress from now on.
+		if let Some(store) = &self.progress_store {
+			store.store(&[], &[PROGRESS_KEY]);
+		}
+
+		if self.import_target_block {
+			let incoming_block = IncomingBlock {
+				hash: header.hash(),
+				header: Some(header.clone()),
+				body: None,
+				indexed_body: None,
+				justifications: block.justifications.clone(),
+				origin: None,
+				allow_missing_state: true,
+				allow_missing_parent: true,
+				import_existing: true,
+				skip_execution: true,
+				state: None,
+			};
+			debug!(target: LOG_TARGET, "Importing warp sync target block {}", header.hash());
+			self.actions.push(SyncingAction::ImportBlocks {
+				origin: BlockOrigin::NetworkInitialSync,
+				blocks: vec![incoming_block],
+			});
+			self.phase = Phase::ImportingTargetBlock(header.clone());
+		} else {
+			self.phase = Phase::Complete;
+			self.actions.push(SyncingAction::Finished);
+		}
 		self.result = Some(WarpSyncResult {
-			target_header: header.clone(),
+			target_header: header,
 			target_body: block.body,
 			target_justifications: block.justifications,
 		});
+		Ok(())
+	}
+
+	/// A batch of blocks have been processed, with or without errors.
+	///
+	/// Finishes the warp sync once the target block imported with
+	/// [`Self::with_target_block_import`] is processed.
+	pub fn on_blocks_processed(
+		&mut self,
+		results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
+	) {
+		let Phase::ImportingTargetBlock(header) = &self.phase else { return };
+		let target_hash = header.hash();
+
+		let Some((result, _)) = results.into_iter().find(|(_, hash)| *hash == target_hash) else {
+			return
+		};
+		if let Err(e) = result {
+			error!(target: LOG_TARGET, "Failed to import warp sync target block: {e:?}.");
+			self.result = None;
+		}
 		self.phase = Phase::Complete;
 		self.actions.push(SyncingAction::Finished);
-		Ok(())
 	}
 
 	/// Reserve a peer for a request assigning `new_state`.
@@ -625,7 +741,7 @@ where
 				phase: WarpSyncPhase::DownloadingWarpProofs,
 				total_bytes: self.total_proof_bytes,
 			},
-			Phase::TargetBlock(_) => WarpSyncProgress {
+			Phase::TargetBlock(_) | Phase::ImportingTargetBlock(_) => WarpSyncProgress {
 				phase: WarpSyncPhase::DownloadingTargetBlock,
 				total_bytes: self.total_proof_bytes,
 			},
@@ -647,13 +763,15 @@ where
 			state: match &self.phase {
 				Phase::WaitingForPeers { .. } => SyncState::Downloading { target: Zero::zero() },
 				Phase::WarpProof { .. } => SyncState::Downloading { target: Zero::zero() },
-				Phase::TargetBlock(header) => SyncState::Downloading { target: *header.number() },
+				Phase::TargetBlock(header) | Phase::ImportingTargetBlock(header) =>
+					SyncState::Downloading { target: *header.number() },
 				Phase::Complete => SyncState::Idle,
 			},
 			best_seen_block: match &self.phase {
 				Phase::WaitingForPeers { .. } => None,
 				Phase::WarpProof { .. } => None,
-				Phase::TargetBlock(header) => Some(*header.number()),
+				Phase::TargetBlock(header) | Phase::ImportingTargetBlock(header) =>
+					Some(*header.number()),
 				Phase::Complete => None,
 			},
 			num_peers: self.peers.len().saturated_into(),
@@ -949,6 +1067,61 @@ mod test {
 		assert!(matches!(warp_sync.phase, Phase::WarpProof { .. }))
 	}
 
+	#[derive(Default)]
+	struct InMemoryProgressStore(std::sync::Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+	impl SyncProgressStore for InMemoryProgressStore {
+		fn load(&self, key: &[u8]) -> Option<Vec<u8>> {
+			self.0.lock().unwrap().get(key).cloned()
+		}
+
+		fn store(&self, insert: &[(&[u8], &[u8])], delete: &[&[u8]]) {
+			let mut entries = self.0.lock().unwrap();
+			for (key, value) in insert {
+				entries.insert(key.to_vec(), value.to_vec());
+			}
+			for key in delete {
+				entries.remove(*key);
+			}
+		}
+	}
+
+	#[test]
+	fn warp_sync_resumes_from_persisted_authority_set() {
+		let client = mock_client_without_state();
+		let mut provider = MockWarpSyncProvider::<Block>::new();
+		provider.expect_current_authorities().never();
+		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
+
+		let last_hash = Hash::random();
+		let store = Arc::new(InMemoryProgressStore::default());
+		let progress =
+			PersistedWarpSync { set_id: 5, authorities: AuthorityList::default(), last_hash };
+		store.store(&[(PROGRESS_KEY, &progress.encode()[..])], &[]);
+
+		let mut warp_sync = WarpSync::new(
+			Arc::new(client),
+			config,
+			None,
+			Arc::new(MockBlockDownloader::new()),
+			None,
+		)
+		.with_progress_store(store);
+
+		for _ in 0..MIN_PEERS_TO_START_WARP_SYNC {
+			warp_sync.add_peer(PeerId::random(), Hash::random(), 10);
+		}
+
+		// Warp proofs are requested from the persisted authority set, not from genesis.
+		match &warp_sync.phase {
+			Phase::WarpProof { set_id, last_hash: hash, .. } => {
+				assert_eq!(*set_id, 5);
+				assert_eq!(*hash, last_hash);
+			},
+			_ => panic!("Warp sync is expected to be started."),
+		}
+	}
+
 	#[test]
 	fn no_peer_is_scheduled_if_no_peers_connected() {
 		let client = mock_client_without_state();
@@ -1718,4 +1891,81 @@ mod test {
 		assert_eq!(result.target_body, body);
 		assert_eq!(result.target_justifications, justifications);
 	}
+
+	#[test]
+	fn target_block_is_imported_before_finishing_on_light_nodes() {
+		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
+		let mut provider = MockWarpSyncProvider::<Block>::new();
+		provider
+			.expect_current_authorities()
+			.once()
+			.return_const(AuthorityList::default());
+		let target_block = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(client.chain_info().best_hash)
+			.with_parent_block_number(client.chain_info().best_number)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+		let target_header = target_block.header().clone();
+		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
+		let mut warp_sync =
+			WarpSync::new(client, config, None, Arc::new(MockBlockDownloader::new()), None)
+				.with_target_block_import();
+
+		// Make sure we have enough peers to make a request.
+		for best_number in 1..11 {
+			warp_sync.add_peer(PeerId::random(), Hash::random(), best_number);
+		}
+
+		// Manually set `TargetBlock` phase.
+		warp_sync.phase = Phase::TargetBlock(target_header.clone());
+
+		let (peer_id, request) = warp_sync.target_block_request().unwrap();
+		let response = vec![BlockData::<Block> {
+			hash: target_header.hash(),
+			header: Some(target_header.clone()),
+			body: Some(target_block.extrinsics().iter().cloned().collect::<Vec<_>>()),
+			indexed_body: None,
+			receipt: None,
+			message_queue: None,
+			justification: None,
+			justifications: None,
+		}];
+		assert!(warp_sync.on_block_response_inner(peer_id, request, response).is_ok());
+
+		let network_provider = NetworkServiceProvider::new();
+		let network_handle = network_provider.handle();
+
+		// The target block is imported without its state.
+		let actions = warp_sync.actions(&network_handle).collect::<Vec<_>>();
+		assert_eq!(actions.len(), 1);
+		assert!(matches!(
+			&actions[0],
+			SyncingAction::ImportBlocks { blocks, .. }
+				if blocks.len() == 1 &&
+					blocks[0].hash == target_header.hash() &&
+					blocks[0].allow_missing_state &&
+					blocks[0].allow_missing_parent
+		));
+		assert!(matches!(warp_sync.phase, Phase::ImportingTargetBlock(_)));
+
+		// Unrelated blocks don't finish the strategy.
+		warp_sync.on_blocks_processed(vec![(
+			Ok(BlockImportStatus::ImportedKnown(1, None)),
+			Hash::random(),
+		)]);
+		assert_eq!(warp_sync.actions(&network_handle).count(), 0);
+
+		// Strategy finishes once the target block is imported.
+		warp_sync.on_blocks_processed(vec![(
+			Ok(BlockImportStatus::ImportedKnown(1, None)),
+			target_header.hash(),
+		)]);
+		let actions = warp_sync.actions(&network_handle).collect::<Vec<_>>();
+		assert_eq!(actions.len(), 1);
+		assert!(matches!(actions[0], SyncingAction::Finished));
+		assert_eq!(warp_sync.take_result().unwrap().target_header, target_header);
+	}
 }
@@ -60,6 +60,7 @@ fn prepare_good_block() -> (TestClient, Hash, u64, PeerId, IncomingBlock<Block>)
 			justifications,
 			origin: Some(peer_id.into()),
 			allow_missing_state: false,
+			allow_missing_parent: false,
 			import_existing: false,
 			state: None,
 			skip_execution: false,
@@ -24,7 +24,8 @@ use crate::{
 		types::MethodResult,
 	},
 	common::events::{
-		ArchiveStorageDiffEvent, ArchiveStorageDiffItem, ArchiveStorageEvent, StorageQuery,
+		ArchiveStorageDiffEvent, ArchiveStorageDiffItem, ArchiveStorageDiffRangeEvent,
+		ArchiveStorageEvent, StorageQuery,
 	},
 };
 use jsonrpsee::proc_macros::rpc;
@@ -130,4 +131,25 @@ pub trait ArchiveApi<Hash> {
 		items: Vec<ArchiveStorageDiffItem<String>>,
 		previous_hash: Option<Hash>,
 	);
+
+	/// Returns the storage difference of every block between `start` and `end` (both
+	/// inclusive) against its parent.
+	///
+	/// The `end` block must be a descendant of the `start` block. Each block of the range is
+	/// announced by a `storageDiffBlock` event, followed by its storage differences.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and can change in minor or patch releases.
+	#[subscription(
+		name = "archive_v1_storageDiffRange" => "archive_v1_storageDiffRangeEvent",
+		unsubscribe = "archive_v1_storageDiffRange_stopStorageDiffRange",
+		item = ArchiveStorageDiffRangeEvent,
+	)]
+	fn archive_v1_storage_diff_range(
+		&self,
+		start: Hash,
+		end: Hash,
+		items: Vec<ArchiveStorageDiffItem<String>>,
+	);
 }
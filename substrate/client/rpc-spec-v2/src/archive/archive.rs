@@ -27,7 +27,8 @@ use crate::{
 	},
 	common::{
 		events::{
-			ArchiveStorageDiffEvent, ArchiveStorageDiffItem, ArchiveStorageEvent, StorageQuery,
+			ArchiveStorageDiffBlock, ArchiveStorageDiffEvent, ArchiveStorageDiffItem,
+			ArchiveStorageDiffRangeEvent, ArchiveStorageEvent, StorageQuery,
 		},
 		storage::{QueryResult, StorageSubscriptionClient},
 	},
@@ -48,7 +49,7 @@ use sp_blockchain::{
 };
 use sp_core::{Bytes, U256};
 use sp_runtime::{
-	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One},
 	SaturatedConversion,
 };
 use std::{collections::HashSet, marker::PhantomData, sync::Arc};
@@ -306,6 +307,222 @@ where
 
 		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
 	}
+
+	fn archive_v1_storage_diff_range(
+		&self,
+		pending: PendingSubscriptionSink,
+		start: Block::Hash,
+		end: Block::Hash,
+		items: Vec<ArchiveStorageDiffItem<String>>,
+	) {
+		let storage_client = ArchiveStorageDiff::new(self.client.clone());
+		let client = self.client.clone();
+
+		log::trace!(target: LOG_TARGET, "Storage diff range subscription started");
+
+		let fut = async move {
+			let Ok(mut sink) = pending.accept().await.map(Subscription::from) else { return };
+
+			let (tx, rx) = tokio::sync::mpsc::channel(STORAGE_QUERY_BUF);
+			let range_fut = storage_diff_range(client, storage_client, start, end, items, tx);
+
+			// The range future stops on its own once the sink is closed, because the
+			// receiving end of the channel is dropped.
+			let _ =
+				futures::future::join(range_fut, process_storage_diff_range_events(rx, &mut sink))
+					.await;
+		};
+
+		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+	}
+}
+
+/// The blocks covered by a storage diff range subscription.
+///
+/// The finalized part of the range is resolved by number while iterating, such that ranges
+/// spanning millions of blocks are never collected upfront. Only the unfinalized blocks are.
+struct DiffRange<Block: BlockT> {
+	/// The next finalized block number to report and the last finalized number of the range.
+	finalized: Option<(NumberFor<Block>, NumberFor<Block>)>,
+	/// The unfinalized blocks of the range, in reverse order.
+	unfinalized: Vec<Block::Hash>,
+}
+
+impl<Block: BlockT> DiffRange<Block> {
+	/// Resolve the blocks between `start` and `end`, both inclusive.
+	fn new<Client: HeaderBackend<Block>>(
+		client: &Client,
+		start: Block::Hash,
+		end: Block::Hash,
+	) -> Result<Self, String> {
+		let header = |hash| match client.header(hash) {
+			Ok(Some(header)) => Ok(header),
+			_ => Err(format!("Block header is not present: {hash}")),
+		};
+		let not_descendant = || format!("Block {end} is not a descendant of block {start}");
+
+		let start_number = *header(start)?.number();
+		let mut current = header(end)?;
+		if *current.number() < start_number {
+			return Err(not_descendant())
+		}
+
+		let last_finalized = client.info().finalized_number.max(start_number);
+		let mut unfinalized = Vec::new();
+		while *current.number() > last_finalized {
+			unfinalized.push(current.hash());
+			current = header(*current.parent_hash())?;
+		}
+
+		let finalized = if *current.number() == start_number {
+			if current.hash() != start {
+				return Err(not_descendant())
+			}
+			unfinalized.push(start);
+			None
+		} else {
+			// `current` is at the finalized height, so both `current` and `start` must be part
+			// of the finalized chain.
+			let is_finalized = |number, hash| client.hash(number).ok().flatten() == Some(hash);
+			if !is_finalized(*current.number(), current.hash()) ||
+				!is_finalized(start_number, start)
+			{
+				return Err(not_descendant())
+			}
+			Some((start_number, *current.number()))
+		};
+
+		Ok(Self { finalized, unfinalized })
+	}
+
+	/// Returns the header of the next block of the range.
+	fn next<Client: HeaderBackend<Block>>(
+		&mut self,
+		client: &Client,
+	) -> Result<Option<Block::Header>, String> {
+		let hash = if let Some((number, last)) = self.finalized {
+			self.finalized = (number < last).then(|| (number + One::one(), last));
+			match client.hash(number) {
+				Ok(Some(hash)) => hash,
+				_ => return Err(format!("Block hash is not present: #{number}")),
+			}
+		} else {
+			let Some(hash) = self.unfinalized.pop() else { return Ok(None) };
+			hash
+		};
+
+		match client.header(hash) {
+			Ok(Some(header)) => Ok(Some(header)),
+			_ => Err(format!("Block header is not present: {hash}")),
+		}
+	}
+}
+
+/// Generates the storage differences of every block between `start` and `end`.
+///
+/// The differences of a block are generated only after the ones of the previous block have
+/// been accepted by `tx`, which propagates the backpressure of the subscription sink.
+async fn storage_diff_range<BE, Block, Client>(
+	client: Arc<Client>,
+	storage_client: ArchiveStorageDiff<Client, Block, BE>,
+	start: Block::Hash,
+	end: Block::Hash,
+	items: Vec<ArchiveStorageDiffItem<String>>,
+	tx: mpsc::Sender<ArchiveStorageDiffRangeEvent>,
+) where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+	Client: HeaderBackend<Block> + StorageProvider<Block, BE> + Send + Sync + 'static,
+{
+	let mut range = match DiffRange::new(&*client, start, end) {
+		Ok(range) => range,
+		Err(error) => {
+			let _ = tx.send(ArchiveStorageDiffRangeEvent::err(error)).await;
+			return
+		},
+	};
+
+	loop {
+		let header = match range.next(&*client) {
+			Ok(Some(header)) => header,
+			Ok(None) => break,
+			Err(error) => {
+				let _ = tx.send(ArchiveStorageDiffRangeEvent::err(error)).await;
+				return
+			},
+		};
+
+		let block = ArchiveStorageDiffBlock {
+			hash: hex_string(&header.hash().as_ref()),
+			number: (*header.number()).saturated_into(),
+		};
+		if tx.send(ArchiveStorageDiffRangeEvent::StorageDiffBlock(block)).await.is_err() {
+			return
+		}
+
+		let (block_tx, mut block_rx) = mpsc::channel(STORAGE_QUERY_BUF);
+		let diff_fut = storage_client.handle_trie_queries(
+			header.hash(),
+			items.clone(),
+			*header.parent_hash(),
+			block_tx,
+		);
+
+		let tx = &tx;
+		// The receiver is moved into the future, such that the diff of the block is aborted
+		// as soon as forwarding stops.
+		let forward_fut = async move {
+			while let Some(event) = block_rx.recv().await {
+				let event = match event {
+					ArchiveStorageDiffEvent::StorageDiff(result) =>
+						ArchiveStorageDiffRangeEvent::StorageDiff(result),
+					ArchiveStorageDiffEvent::StorageDiffError(error) => {
+						let _ =
+							tx.send(ArchiveStorageDiffRangeEvent::StorageDiffError(error)).await;
+						return false
+					},
+					ArchiveStorageDiffEvent::StorageDiffDone => continue,
+				};
+
+				if tx.send(event).await.is_err() {
+					return false
+				}
+			}
+
+			true
+		};
+
+		let (_, proceed) = futures::future::join(diff_fut, forward_fut).await;
+		if !proceed {
+			return
+		}
+	}
+
+	let _ = tx.send(ArchiveStorageDiffRangeEvent::StorageDiffDone).await;
+}
+
+/// Sends all the events of the storage_diff_range method to the sink.
+async fn process_storage_diff_range_events(
+	mut rx: mpsc::Receiver<ArchiveStorageDiffRangeEvent>,
+	sink: &mut Subscription,
+) {
+	loop {
+		tokio::select! {
+			_ = sink.closed() => {
+				return
+			},
+
+			maybe_event = rx.recv() => {
+				let Some(event) = maybe_event else {
+					break;
+				};
+
+				if sink.send(&event).await.is_err() {
+					return
+				}
+			}
+		}
+	}
 }
 
 /// Sends all the events of the storage_diff method to the sink.
@@ -19,9 +19,10 @@
 use crate::{
 	archive::MethodResult,
 	common::events::{
-		ArchiveStorageDiffEvent, ArchiveStorageDiffItem, ArchiveStorageDiffOperationType,
-		ArchiveStorageDiffResult, ArchiveStorageDiffType, ArchiveStorageEvent, StorageQuery,
-		StorageQueryType, StorageResult, StorageResultType,
+		ArchiveStorageDiffBlock, ArchiveStorageDiffEvent, ArchiveStorageDiffItem,
+		ArchiveStorageDiffOperationType, ArchiveStorageDiffRangeEvent, ArchiveStorageDiffResult,
+		ArchiveStorageDiffType, ArchiveStorageEvent, StorageQuery, StorageQueryType, StorageResult,
+		StorageResultType,
 	},
 	hex_string,
 };
@@ -999,3 +1000,82 @@ async fn archive_storage_diff_invalid_params() {
 		ArchiveStorageDiffEvent::StorageDiffError(ref err) if err.error.contains("Header was not found")
 	);
 }
+
+#[tokio::test]
+async fn archive_storage_diff_range() {
+	let (client, api) = setup_api();
+
+	// genesis -> block 1 -> block 2 (finalized) -> block 3
+	let mut parent_hash = client.chain_info().genesis_hash;
+	let mut hashes = Vec::new();
+	for (number, value) in [b"1", b"2", b"3"].into_iter().enumerate() {
+		let mut builder = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(parent_hash)
+			.with_parent_block_number(number as u64)
+			.build()
+			.unwrap();
+		builder.push_storage_change(b":A".to_vec(), Some(value.to_vec())).unwrap();
+		let block = builder.build().unwrap().block;
+		parent_hash = block.header.hash();
+		hashes.push(parent_hash);
+		client.import(BlockOrigin::Own, block).await.unwrap();
+	}
+	client.finalize_block(hashes[1], None).unwrap();
+
+	let items = vec![ArchiveStorageDiffItem::<String> {
+		key: hex_string(b":A"),
+		return_type: ArchiveStorageDiffType::Value,
+		child_trie_key: None,
+	}];
+	let mut sub = api
+		.subscribe_unbounded(
+			"archive_v1_storageDiffRange",
+			rpc_params![format!("{:?}", hashes[0]), format!("{:?}", hashes[2]), items.clone()],
+		)
+		.await
+		.unwrap();
+
+	for (index, (hash, value)) in hashes.iter().zip([b"1", b"2", b"3"]).enumerate() {
+		let event = get_next_event::<ArchiveStorageDiffRangeEvent>(&mut sub).await;
+		assert_eq!(
+			ArchiveStorageDiffRangeEvent::StorageDiffBlock(ArchiveStorageDiffBlock {
+				hash: format!("{:?}", hash),
+				number: index as u64 + 1,
+			}),
+			event,
+		);
+
+		let operation_type = if index == 0 {
+			ArchiveStorageDiffOperationType::Added
+		} else {
+			ArchiveStorageDiffOperationType::Modified
+		};
+		let event = get_next_event::<ArchiveStorageDiffRangeEvent>(&mut sub).await;
+		assert_eq!(
+			ArchiveStorageDiffRangeEvent::StorageDiff(ArchiveStorageDiffResult {
+				key: hex_string(b":A"),
+				result: StorageResultType::Value(hex_string(value)),
+				operation_type,
+				child_trie_key: None,
+			}),
+			event,
+		);
+	}
+
+	let event = get_next_event::<ArchiveStorageDiffRangeEvent>(&mut sub).await;
+	assert_eq!(ArchiveStorageDiffRangeEvent::StorageDiffDone, event);
+
+	// The end of the range must descend from its start.
+	let mut sub = api
+		.subscribe_unbounded(
+			"archive_v1_storageDiffRange",
+			rpc_params![format!("{:?}", hashes[2]), format!("{:?}", hashes[0]), items],
+		)
+		.await
+		.unwrap();
+
+	let event = get_next_event::<ArchiveStorageDiffRangeEvent>(&mut sub).await;
+	assert_matches!(event,
+		ArchiveStorageDiffRangeEvent::StorageDiffError(ref err) if err.error.contains("is not a descendant")
+	);
+}
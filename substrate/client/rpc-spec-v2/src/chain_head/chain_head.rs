@@ -19,7 +19,7 @@
 //! API implementation for `chainHead`.
 
 use super::{
-	chain_head_storage::ChainHeadStorage,
+	chain_head_storage::{generate_remote_events, ChainHeadStorage},
 	event::{MethodResponseStarted, OperationBodyDone, OperationCallDone},
 };
 use crate::{
@@ -42,8 +42,8 @@ use jsonrpsee::{
 };
 use log::debug;
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, CallExecutor, ChildInfo, ExecutorProvider, StorageKey,
-	StorageProvider,
+	is_execution_budget_exceeded, Backend, BlockBackend, BlockchainEvents, CallExecutor, ChildInfo,
+	ExecutorProvider, RemoteFetcher, StorageKey, StorageProvider,
 };
 use sc_rpc::utils::Subscription;
 use sp_api::CallApiAt;
@@ -131,6 +131,8 @@ pub struct ChainHead<BE: Backend<Block>, Block: BlockT, Client> {
 	_phantom: PhantomData<Block>,
 	/// The maximum number of pending messages per subscription.
 	subscription_buffer_cap: usize,
+	/// Fetcher of the storage and runtime calls of light nodes, which don't have the state.
+	remote_fetcher: Option<Arc<dyn RemoteFetcher<Block>>>,
 }
 
 impl<BE: Backend<Block>, Block: BlockT, Client> ChainHead<BE, Block, Client> {
@@ -154,9 +156,18 @@ impl<BE: Backend<Block>, Block: BlockT, Client> ChainHead<BE, Block, Client> {
 			),
 			max_lagging_distance: config.max_lagging_distance,
 			subscription_buffer_cap: config.subscription_buffer_cap,
+			remote_fetcher: None,
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Answer the storage queries and runtime calls with `fetcher` instead of the local state.
+	///
+	/// The runtime version of the `chainHead_follow` events is still read from the local client.
+	pub fn with_remote_fetcher(mut self, fetcher: Arc<dyn RemoteFetcher<Block>>) -> Self {
+		self.remote_fetcher = Some(fetcher);
+		self
+	}
 }
 
 /// Helper to convert the `subscription ID` to a string.
@@ -431,6 +442,7 @@ where
 			};
 
 		let mut storage_client = ChainHeadStorage::<Client, Block, BE>::new(self.client.clone());
+		let remote_fetcher = self.remote_fetcher.clone();
 
 		// Storage items are never discarded.
 		let (rp, rp_fut) = method_started_response(block_guard.operation().operation_id(), Some(0));
@@ -449,11 +461,23 @@ where
 
 			// May fail if the channel is closed or the connection is closed.
 			// which is okay to ignore.
-			let _ = futures::future::join(
-				storage_client.generate_events(hash, items, child_trie, tx),
-				process_storage_items(rx, response_sender, operation_id, &stop_handle),
-			)
-			.await;
+			let process = process_storage_items(rx, response_sender, operation_id, &stop_handle);
+			match remote_fetcher {
+				Some(fetcher) => {
+					futures::future::join(
+						generate_remote_events(fetcher, hash, items, child_trie, tx),
+						process,
+					)
+					.await;
+				},
+				None => {
+					let _ = futures::future::join(
+						storage_client.generate_events(hash, items, child_trie, tx),
+						process,
+					)
+					.await;
+				},
+			}
 		};
 		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
 
@@ -507,6 +531,8 @@ where
 
 		let operation_id = block_guard.operation().operation_id();
 		let client = self.client.clone();
+		let remote_fetcher = self.remote_fetcher.clone();
+		let is_remote = remote_fetcher.is_some();
 
 		let (rp, rp_fut) = method_started_response(operation_id.clone(), None);
 		let fut = async move {
@@ -516,9 +542,12 @@ where
 				return
 			}
 
-			let event = client
-				.executor()
-				.call(hash, &function, &call_parameters, CallContext::Offchain)
+			let result = match remote_fetcher {
+				Some(fetcher) => fetcher.remote_call(hash, function, call_parameters.0).await,
+				None =>
+					client.executor().call(hash, &function, &call_parameters, CallContext::Offchain),
+			};
+			let event = result
 				.map(|result| {
 					FollowEvent::<Block::Hash>::OperationCallDone(OperationCallDone {
 						operation_id: operation_id.clone(),
@@ -526,16 +555,26 @@ where
 					})
 				})
 				.unwrap_or_else(|error| {
+					let error = if is_execution_budget_exceeded(&error) {
+						ChainHeadRpcError::ExecutionBudgetExceeded.to_string()
+					} else {
+						error.to_string()
+					};
 					FollowEvent::<Block::Hash>::OperationError(OperationError {
 						operation_id: operation_id.clone(),
-						error: error.to_string(),
+						error,
 					})
 				});
 
 			let _ = block_guard.response_sender().send(event).await;
 		};
-		self.executor
-			.spawn_blocking("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+		// Remote calls only wait for the peers.
+		if is_remote {
+			self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+		} else {
+			self.executor
+				.spawn_blocking("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+		}
 
 		rp
 	}
@@ -20,13 +20,16 @@
 
 use std::{marker::PhantomData, sync::Arc};
 
-use sc_client_api::{Backend, ChildInfo, StorageKey, StorageProvider};
-use sp_runtime::traits::Block as BlockT;
+use sc_client_api::{Backend, ChildInfo, RemoteFetcher, StorageKey, StorageProvider};
+use sp_runtime::traits::{Block as BlockT, Hash, HashingFor};
 use tokio::sync::mpsc;
 
-use crate::common::{
-	events::{StorageQuery, StorageQueryType},
-	storage::{IterQueryType, QueryIter, QueryResult, Storage},
+use crate::{
+	common::{
+		events::{StorageQuery, StorageQueryType, StorageResult, StorageResultType},
+		storage::{IterQueryType, QueryIter, QueryResult, Storage},
+	},
+	hex_string,
 };
 
 /// Generates the events of the `chainHead_storage` method.
@@ -121,3 +124,60 @@ where
 		Ok(())
 	}
 }
+
+/// Generate the block events for the `chainHead_storage` method of a light node, reading the
+/// values from the peers.
+///
+/// Only the value and hash queries are available, the descendants and merkle values can't be
+/// fetched without the state.
+pub async fn generate_remote_events<Block: BlockT>(
+	fetcher: Arc<dyn RemoteFetcher<Block>>,
+	hash: Block::Hash,
+	items: Vec<StorageQuery<StorageKey>>,
+	child_key: Option<ChildInfo>,
+	tx: mpsc::Sender<QueryResult>,
+) {
+	for item in items {
+		let as_hash = match item.query_type {
+			StorageQueryType::Value => false,
+			StorageQueryType::Hash => true,
+			StorageQueryType::ClosestDescendantMerkleValue |
+			StorageQueryType::DescendantsValues |
+			StorageQueryType::DescendantsHashes => {
+				let rp = QueryResult::Err(format!(
+					"Storage query {:?} is not available on light nodes",
+					item.query_type,
+				));
+				if tx.send(rp).await.is_err() {
+					break;
+				}
+				continue;
+			},
+		};
+
+		let key = item.key.0;
+		let result = match &child_key {
+			Some(child_key) =>
+				fetcher
+					.remote_read_child(hash, child_key.prefixed_storage_key(), vec![key.clone()])
+					.await,
+			None => fetcher.remote_read(hash, vec![key.clone()]).await,
+		};
+		let rp = result
+			.map(|mut values| {
+				values.remove(&key).flatten().map(|value| StorageResult {
+					key: hex_string(&key),
+					result: if as_hash {
+						StorageResultType::Hash(hex_string(&HashingFor::<Block>::hash(&value)))
+					} else {
+						StorageResultType::Value(hex_string(&value))
+					},
+					child_trie_key: child_key.as_ref().map(|c| hex_string(&c.storage_key())),
+				})
+			})
+			.map_err(|error| error.to_string());
+		if tx.send(rp).await.is_err() {
+			break;
+		}
+	}
+}
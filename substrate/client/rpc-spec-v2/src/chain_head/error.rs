@@ -44,6 +44,11 @@ pub enum Error {
 	/// Internal error.
 	#[error("Internal error: {0}")]
 	InternalError(String),
+	/// The runtime call consumed more than its execution budget.
+	///
+	/// Reported in the `operationError` event of `chainHead_v1_call`.
+	#[error("Runtime call exceeded its execution budget")]
+	ExecutionBudgetExceeded,
 }
 
 /// Errors for `chainHead` RPC module, as defined in
@@ -87,7 +92,7 @@ impl From<Error> for ErrorObject<'static> {
 				ErrorObject::owned(rpc_spec_v2::INVALID_DUPLICATE_HASHES, msg, None::<()>),
 			Error::InvalidParam(_) =>
 				ErrorObject::owned(json_rpc_spec::INVALID_PARAM_ERROR, msg, None::<()>),
-			Error::InternalError(_) =>
+			Error::InternalError(_) | Error::ExecutionBudgetExceeded =>
 				ErrorObject::owned(json_rpc_spec::INTERNAL_ERROR, msg, None::<()>),
 		}
 	}
@@ -262,6 +262,47 @@ impl ArchiveStorageDiffEvent {
 	}
 }
 
+/// The block whose storage differences are reported by the `archive_storageDiffRange` method.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageDiffBlock {
+	/// The hex-encoded hash of the block.
+	pub hash: String,
+	/// The number of the block.
+	pub number: u64,
+}
+
+/// The event generated by the `archive_storageDiffRange` method.
+///
+/// The `archive_storageDiffRange` can generate the following events:
+///  - `storageDiffBlock` event - generated before the differences of a block against its parent are
+///    reported.
+///  - `storageDiff` event - generated when a `ArchiveStorageDiffResult` is produced for the block
+///    announced by the last `storageDiffBlock` event.
+///  - `storageDiffError` event - generated when an error is produced. No further events are
+///    generated afterwards.
+///  - `storageDiffDone` event - generated when all the blocks of the range have been reported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "event")]
+pub enum ArchiveStorageDiffRangeEvent {
+	/// The `storageDiffBlock` event.
+	StorageDiffBlock(ArchiveStorageDiffBlock),
+	/// The `storageDiff` event.
+	StorageDiff(ArchiveStorageDiffResult),
+	/// The `storageDiffError` event.
+	StorageDiffError(ArchiveStorageMethodErr),
+	/// The `storageDiffDone` event.
+	StorageDiffDone,
+}
+
+impl ArchiveStorageDiffRangeEvent {
+	/// Create a new `ArchiveStorageDiffRangeEvent::StorageDiffError` event.
+	pub fn err(error: String) -> Self {
+		Self::StorageDiffError(ArchiveStorageMethodErr { error })
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -503,4 +544,42 @@ mod tests {
 		let dec: PaginatedStorageQuery<&str> = serde_json::from_str(exp).unwrap();
 		assert_eq!(dec, item);
 	}
+
+	#[test]
+	fn archive_diff_range_event() {
+		let event = ArchiveStorageDiffRangeEvent::StorageDiffBlock(ArchiveStorageDiffBlock {
+			hash: "0x1".into(),
+			number: 2,
+		});
+		// Encode
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"storageDiffBlock","hash":"0x1","number":2}"#;
+		assert_eq!(ser, exp);
+		// Decode
+		let dec: ArchiveStorageDiffRangeEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, event);
+
+		let event = ArchiveStorageDiffRangeEvent::StorageDiff(ArchiveStorageDiffResult {
+			key: "0x1".into(),
+			result: StorageResultType::Value("res".into()),
+			operation_type: ArchiveStorageDiffOperationType::Added,
+			child_trie_key: None,
+		});
+		// Encode
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"storageDiff","key":"0x1","value":"res","type":"added"}"#;
+		assert_eq!(ser, exp);
+		// Decode
+		let dec: ArchiveStorageDiffRangeEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, event);
+
+		let event = ArchiveStorageDiffRangeEvent::StorageDiffDone;
+		// Encode
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"storageDiffDone"}"#;
+		assert_eq!(ser, exp);
+		// Decode
+		let dec: ArchiveStorageDiffRangeEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, event);
+	}
 }
@@ -308,6 +308,7 @@ async fn tx_broadcast_resubmits_invalid_tx() {
 	let options = Options {
 		ready: limits.clone(),
 		future: limits,
+		sender_limits: Default::default(),
 		reject_future_transactions: false,
 		// This ensures that a transaction is not banned.
 		ban_time: std::time::Duration::ZERO,
@@ -429,6 +430,7 @@ async fn tx_broadcast_resubmits_dropped_tx() {
 	let options = Options {
 		ready: limits.clone(),
 		future: limits,
+		sender_limits: Default::default(),
 		reject_future_transactions: false,
 		// This ensures that a transaction is not banned.
 		ban_time: std::time::Duration::ZERO,
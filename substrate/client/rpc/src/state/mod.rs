@@ -19,6 +19,7 @@
 //! Substrate state API.
 
 mod state_full;
+mod state_light;
 mod utils;
 
 #[cfg(test)]
@@ -27,7 +28,8 @@ mod tests;
 use crate::SubscriptionTaskExecutor;
 use jsonrpsee::{core::async_trait, Extensions, PendingSubscriptionSink};
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, ExecutorProvider, ProofProvider, StorageProvider,
+	Backend, BlockBackend, BlockchainEvents, ExecutorProvider, ProofProvider, RemoteFetcher,
+	StorageProvider,
 };
 use sc_rpc_api::{check_if_safe, DenyUnsafe};
 use sp_api::{CallApiAt, Metadata, ProvideRuntimeApi};
@@ -189,6 +191,28 @@ where
 	(State { backend }, ChildState { backend: child_backend })
 }
 
+/// Create new state API that works on light node.
+///
+/// The storage and the runtime calls are answered with proofs fetched by `fetcher`.
+pub fn new_light<Block: BlockT, Client>(
+	client: Arc<Client>,
+	fetcher: Arc<dyn RemoteFetcher<Block>>,
+	executor: SubscriptionTaskExecutor,
+) -> (State<Block, Client>, ChildState<Block, Client>)
+where
+	Block: BlockT + 'static,
+	Block::Hash: Unpin,
+	Client: HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+{
+	let child_backend = Box::new(self::state_light::LightState::new(
+		client.clone(),
+		fetcher.clone(),
+		executor.clone(),
+	));
+	let backend = Box::new(self::state_light::LightState::new(client, fetcher, executor));
+	(State { backend }, ChildState { backend: child_backend })
+}
+
 /// State API with subscriptions support.
 pub struct State<Block, Client> {
 	backend: Box<dyn StateBackend<Block, Client>>,
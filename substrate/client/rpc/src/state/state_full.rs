@@ -33,8 +33,8 @@ use crate::{
 use futures::{future, stream, StreamExt};
 use jsonrpsee::{core::async_trait, types::ErrorObject, PendingSubscriptionSink};
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, CallExecutor, ExecutorProvider, ProofProvider,
-	StorageProvider,
+	is_execution_budget_exceeded, Backend, BlockBackend, BlockchainEvents, CallExecutor,
+	ExecutorProvider, ProofProvider, StorageProvider,
 };
 use sc_rpc_api::state::ReadProof;
 use sp_api::{CallApiAt, Metadata, ProvideRuntimeApi};
@@ -201,7 +201,13 @@ where
 					.call(block, &method, &call_data, CallContext::Offchain)
 					.map(Into::into)
 			})
-			.map_err(client_err)
+			.map_err(|e| {
+				if is_execution_budget_exceeded(&e) {
+					Error::ExecutionBudgetExceeded
+				} else {
+					client_err(e)
+				}
+			})
 	}
 
 	// TODO: This is horribly broken; either remove it, or make it streaming.
@@ -0,0 +1,397 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! State API backend for light nodes.
+//!
+//! Storage and runtime calls are answered with the proofs fetched from remote full nodes by a
+//! [`RemoteFetcher`]. Queries that would require iterating over the storage are not available.
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use super::{
+	client_err,
+	error::{Error, Result},
+	ChildStateBackend, StateBackend,
+};
+use crate::{
+	utils::{spawn_subscription_task, BoundedVecDeque, PendingSubscription},
+	DenyUnsafe, SubscriptionTaskExecutor,
+};
+
+use codec::Decode;
+use futures::{executor::block_on, future, stream, FutureExt, StreamExt};
+use jsonrpsee::{core::async_trait, PendingSubscriptionSink};
+use sc_client_api::{BlockchainEvents, RemoteFetcher};
+use sc_rpc_api::state::ReadProof;
+use sp_blockchain::{Error as ClientError, HeaderBackend};
+use sp_core::{
+	storage::{PrefixedStorageKey, StorageChangeSet, StorageData, StorageKey},
+	Bytes,
+};
+use sp_runtime::traits::{Block as BlockT, Hash, HashingFor};
+use sp_version::RuntimeVersion;
+
+/// State API backend for light nodes.
+pub struct LightState<Block: BlockT, Client> {
+	client: Arc<Client>,
+	fetcher: Arc<dyn RemoteFetcher<Block>>,
+	executor: SubscriptionTaskExecutor,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block: BlockT, Client> LightState<Block, Client>
+where
+	Client: HeaderBackend<Block>,
+{
+	/// Create new state API backend for light nodes.
+	pub fn new(
+		client: Arc<Client>,
+		fetcher: Arc<dyn RemoteFetcher<Block>>,
+		executor: SubscriptionTaskExecutor,
+	) -> Self {
+		Self { client, fetcher, executor, _phantom: PhantomData }
+	}
+
+	/// Returns given block hash or best block hash if None is passed.
+	fn block_or_best(&self, hash: Option<Block::Hash>) -> Block::Hash {
+		hash.unwrap_or_else(|| self.client.info().best_hash)
+	}
+
+	/// Read `keys` at `block`, preserving their order.
+	fn read(&self, block: Block::Hash, keys: Vec<StorageKey>) -> Result<Vec<Option<StorageData>>> {
+		let raw_keys = keys.iter().map(|key| key.0.clone()).collect();
+		let mut values = block_on(self.fetcher.remote_read(block, raw_keys)).map_err(client_err)?;
+		Ok(keys
+			.iter()
+			.map(|key| values.remove(&key.0).flatten().map(StorageData))
+			.collect())
+	}
+
+	/// Read `keys` in the child storage `storage_key` at `block`, preserving their order.
+	fn read_child(
+		&self,
+		block: Block::Hash,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<StorageKey>,
+	) -> Result<Vec<Option<StorageData>>> {
+		let raw_keys = keys.iter().map(|key| key.0.clone()).collect();
+		let mut values = block_on(self.fetcher.remote_read_child(block, storage_key, raw_keys))
+			.map_err(client_err)?;
+		Ok(keys
+			.iter()
+			.map(|key| values.remove(&key.0).flatten().map(StorageData))
+			.collect())
+	}
+
+	/// Call `method` of the runtime at `block` and decode the result.
+	fn call_decoded<T: Decode>(&self, block: Option<Block::Hash>, method: &str) -> Result<T> {
+		let block = self.block_or_best(block);
+		let result = block_on(self.fetcher.remote_call(block, method.into(), Vec::new()))
+			.map_err(client_err)?;
+		T::decode(&mut &result[..])
+			.map_err(|e| client_err(ClientError::CallResultDecode("remote call result", e)))
+	}
+}
+
+fn not_available<T>() -> Result<T> {
+	Err(client_err(ClientError::NotAvailableOnLightClient))
+}
+
+fn hash<Block: BlockT>(data: Option<StorageData>) -> Option<Block::Hash> {
+	data.map(|data| <HashingFor<Block> as Hash>::hash(&data.0))
+}
+
+#[async_trait]
+impl<Block, Client> StateBackend<Block, Client> for LightState<Block, Client>
+where
+	Block: BlockT + 'static,
+	Client: HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+{
+	fn call(
+		&self,
+		block: Option<Block::Hash>,
+		method: String,
+		call_data: Bytes,
+	) -> std::result::Result<Bytes, Error> {
+		let block = self.block_or_best(block);
+		block_on(self.fetcher.remote_call(block, method, call_data.0))
+			.map(Into::into)
+			.map_err(client_err)
+	}
+
+	fn storage_keys(
+		&self,
+		_block: Option<Block::Hash>,
+		_prefix: StorageKey,
+	) -> std::result::Result<Vec<StorageKey>, Error> {
+		not_available()
+	}
+
+	fn storage_pairs(
+		&self,
+		_block: Option<Block::Hash>,
+		_prefix: StorageKey,
+	) -> std::result::Result<Vec<(StorageKey, StorageData)>, Error> {
+		not_available()
+	}
+
+	fn storage_keys_paged(
+		&self,
+		_block: Option<Block::Hash>,
+		_prefix: Option<StorageKey>,
+		_count: u32,
+		_start_key: Option<StorageKey>,
+	) -> std::result::Result<Vec<StorageKey>, Error> {
+		not_available()
+	}
+
+	fn storage(
+		&self,
+		block: Option<Block::Hash>,
+		key: StorageKey,
+	) -> std::result::Result<Option<StorageData>, Error> {
+		let block = self.block_or_best(block);
+		Ok(self.read(block, vec![key])?.pop().flatten())
+	}
+
+	fn storage_hash(
+		&self,
+		block: Option<Block::Hash>,
+		key: StorageKey,
+	) -> std::result::Result<Option<Block::Hash>, Error> {
+		StateBackend::storage(self, block, key).map(hash::<Block>)
+	}
+
+	async fn storage_size(
+		&self,
+		block: Option<Block::Hash>,
+		key: StorageKey,
+		_deny_unsafe: DenyUnsafe,
+	) -> std::result::Result<Option<u64>, Error> {
+		// The sizes of the entries under a prefix can't be proven, only the size of the value
+		// stored at `key` is returned.
+		let block = self.block_or_best(block);
+		let mut values =
+			self.fetcher.remote_read(block, vec![key.0.clone()]).await.map_err(client_err)?;
+		Ok(values.remove(&key.0).flatten().map(|value| value.len() as u64))
+	}
+
+	fn metadata(&self, block: Option<Block::Hash>) -> std::result::Result<Bytes, Error> {
+		self.call_decoded::<Vec<u8>>(block, "Metadata_metadata").map(Into::into)
+	}
+
+	fn runtime_version(
+		&self,
+		block: Option<Block::Hash>,
+	) -> std::result::Result<RuntimeVersion, Error> {
+		self.call_decoded(block, "Core_version")
+	}
+
+	fn query_storage(
+		&self,
+		_from: Block::Hash,
+		_to: Option<Block::Hash>,
+		_keys: Vec<StorageKey>,
+	) -> std::result::Result<Vec<StorageChangeSet<Block::Hash>>, Error> {
+		not_available()
+	}
+
+	fn query_storage_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+	) -> std::result::Result<Vec<StorageChangeSet<Block::Hash>>, Error> {
+		let block = self.block_or_best(at);
+		let values = self.read(block, keys.clone())?;
+		Ok(vec![StorageChangeSet { block, changes: keys.into_iter().zip(values).collect() }])
+	}
+
+	fn read_proof(
+		&self,
+		_block: Option<Block::Hash>,
+		_keys: Vec<StorageKey>,
+	) -> std::result::Result<ReadProof<Block::Hash>, Error> {
+		not_available()
+	}
+
+	fn trace_block(
+		&self,
+		_block: Block::Hash,
+		_targets: Option<String>,
+		_storage_keys: Option<String>,
+		_methods: Option<String>,
+	) -> std::result::Result<sp_rpc::tracing::TraceBlockResponse, Error> {
+		not_available()
+	}
+
+	fn subscribe_runtime_version(&self, pending: PendingSubscriptionSink) {
+		let initial = match self.runtime_version(None) {
+			Ok(initial) => initial,
+			Err(e) => {
+				spawn_subscription_task(&self.executor, pending.reject(e));
+				return
+			},
+		};
+
+		let mut previous_version = initial.clone();
+		let fetcher = self.fetcher.clone();
+
+		// A stream of new versions
+		let version_stream = self
+			.client
+			.import_notification_stream()
+			.filter(|n| future::ready(n.is_new_best))
+			.then(move |n| fetcher.remote_call(n.hash, "Core_version".into(), Vec::new()))
+			.filter_map(move |result| {
+				let version = result.ok().and_then(|v| RuntimeVersion::decode(&mut &v[..]).ok());
+
+				match version {
+					Some(version) if version != previous_version => {
+						previous_version = version.clone();
+						future::ready(Some(version))
+					},
+					_ => future::ready(None),
+				}
+			});
+
+		let stream = stream::once(future::ready(initial)).chain(version_stream);
+		spawn_subscription_task(
+			&self.executor,
+			PendingSubscription::from(pending).pipe_from_stream(stream, BoundedVecDeque::default()),
+		);
+	}
+
+	fn subscribe_storage(
+		&self,
+		pending: PendingSubscriptionSink,
+		keys: Option<Vec<StorageKey>>,
+		_deny_unsafe: DenyUnsafe,
+	) {
+		// Without the state, the changes can only be found by reading the keys at each block.
+		let Some(keys) = keys else {
+			spawn_subscription_task(
+				&self.executor,
+				pending.reject(client_err(ClientError::NotAvailableOnLightClient)),
+			);
+			return
+		};
+
+		let raw_keys = keys.iter().map(|key| key.0.clone()).collect::<Vec<_>>();
+		let fetcher = self.fetcher.clone();
+		let initial_block = self.client.info().best_hash;
+		let mut previous_values = HashMap::<Vec<u8>, Option<Vec<u8>>>::new();
+
+		let best_blocks = stream::once(future::ready(initial_block)).chain(
+			self.client
+				.import_notification_stream()
+				.filter(|n| future::ready(n.is_new_best))
+				.map(|n| n.hash),
+		);
+		let stream = best_blocks
+			.then(move |block| {
+				fetcher.remote_read(block, raw_keys.clone()).map(move |values| (block, values))
+			})
+			.filter_map(move |(block, values)| {
+				let changes = values
+					.map(|values| {
+						keys.iter()
+							.filter_map(|key| {
+								let value = values.get(&key.0).cloned().flatten();
+								if previous_values.get(&key.0) == Some(&value) {
+									return None
+								}
+								previous_values.insert(key.0.clone(), value.clone());
+								Some((key.clone(), value.map(StorageData)))
+							})
+							.collect::<Vec<_>>()
+					})
+					.unwrap_or_default();
+
+				future::ready((!changes.is_empty()).then(|| StorageChangeSet { block, changes }))
+			});
+
+		spawn_subscription_task(
+			&self.executor,
+			PendingSubscription::from(pending).pipe_from_stream(stream, BoundedVecDeque::default()),
+		);
+	}
+}
+
+impl<Block, Client> ChildStateBackend<Block, Client> for LightState<Block, Client>
+where
+	Block: BlockT + 'static,
+	Client: HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+{
+	fn read_child_proof(
+		&self,
+		_block: Option<Block::Hash>,
+		_storage_key: PrefixedStorageKey,
+		_keys: Vec<StorageKey>,
+	) -> std::result::Result<ReadProof<Block::Hash>, Error> {
+		not_available()
+	}
+
+	fn storage_keys(
+		&self,
+		_block: Option<Block::Hash>,
+		_storage_key: PrefixedStorageKey,
+		_prefix: StorageKey,
+	) -> std::result::Result<Vec<StorageKey>, Error> {
+		not_available()
+	}
+
+	fn storage_keys_paged(
+		&self,
+		_block: Option<Block::Hash>,
+		_storage_key: PrefixedStorageKey,
+		_prefix: Option<StorageKey>,
+		_count: u32,
+		_start_key: Option<StorageKey>,
+	) -> std::result::Result<Vec<StorageKey>, Error> {
+		not_available()
+	}
+
+	fn storage(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		key: StorageKey,
+	) -> std::result::Result<Option<StorageData>, Error> {
+		let block = self.block_or_best(block);
+		Ok(self.read_child(block, storage_key, vec![key])?.pop().flatten())
+	}
+
+	fn storage_entries(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<StorageKey>,
+	) -> std::result::Result<Vec<Option<StorageData>>, Error> {
+		let block = self.block_or_best(block);
+		self.read_child(block, storage_key, keys)
+	}
+
+	fn storage_hash(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		key: StorageKey,
+	) -> std::result::Result<Option<Block::Hash>, Error> {
+		ChildStateBackend::storage(self, block, storage_key, key).map(hash::<Block>)
+	}
+}
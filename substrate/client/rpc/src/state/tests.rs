@@ -29,6 +29,7 @@ use std::sync::Arc;
 use substrate_test_runtime_client::{
 	prelude::*,
 	runtime::{ExtrinsicBuilder, Transfer},
+	WasmExecutor,
 };
 
 const STORAGE_KEY: &[u8] = b"child";
@@ -207,6 +208,32 @@ async fn should_call_contract() {
 	)
 }
 
+#[tokio::test]
+async fn call_exceeding_execution_budget_is_reported() {
+	let executor = <WasmExecutor>::builder().with_offchain_execution_budget(1).build();
+	let (client, _) = TestClientBuilder::new()
+		.build_with_native_executor::<substrate_test_runtime_client::runtime::RuntimeApi, _>(
+			executor,
+		);
+	let genesis_hash = client.genesis_hash();
+	let (api, _child) = new_full(Arc::new(client), test_executor());
+
+	assert_matches!(
+		api.call("Core_version".into(), Bytes(Vec::new()), Some(genesis_hash).into()),
+		Err(Error::ExecutionBudgetExceeded)
+	);
+
+	let api = api.into_rpc();
+	let err = api
+		.call::<_, Bytes>("state_call", ("Core_version", "0x", Some(genesis_hash)))
+		.await
+		.unwrap_err();
+	assert_matches!(
+		err,
+		RpcError::JsonRpc(e) if e.message() == "Runtime call exceeded its execution budget"
+	);
+}
+
 #[tokio::test]
 async fn should_notify_about_storage_changes() {
 	let mut sub = {
@@ -31,9 +31,9 @@ use log::{debug, error, info};
 use prometheus_endpoint::Registry;
 use sc_chain_spec::{get_extension, ChainSpec};
 use sc_client_api::{
-	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, BadBlocks,
-	BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, KeysIter, StorageProvider,
-	TrieCacheContext, UsageProvider,
+	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, AuxStore, BadBlocks,
+	BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, KeysIter, RemoteFetcher,
+	StorageProvider, TrieCacheContext, UsageProvider,
 };
 use sc_client_db::{Backend, BlocksPruning, DatabaseSettings, PruningMode};
 use sc_consensus::import_queue::{ImportQueue, ImportQueueService};
@@ -41,7 +41,7 @@ use sc_executor::{
 	sp_wasm_interface::HostFunctions, HeapAllocStrategy, NativeExecutionDispatch, RuntimeVersionOf,
 	WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY,
 };
-use sc_keystore::LocalKeystore;
+use sc_keystore::{LocalKeystore, RemoteKeystore};
 use sc_network::{
 	config::{FullNetworkConfiguration, ProtocolId, SyncMode},
 	multiaddr::Protocol,
@@ -52,10 +52,12 @@ use sc_network::{
 	NetworkBackend, NetworkStateInfo,
 };
 use sc_network_common::role::{Role, Roles};
-use sc_network_light::light_client_requests::handler::LightClientRequestHandler;
+use sc_network_light::light_client_requests::{
+	handler::LightClientRequestHandler, sender::LightClientRequestSender,
+};
 use sc_network_sync::{
 	block_relay_protocol::{BlockDownloader, BlockRelayParams},
-	block_request_handler::BlockRequestHandler,
+	block_request_handler::{BlockRequestHandler, BlockServerLimits},
 	engine::SyncingEngine,
 	service::network::{NetworkServiceHandle, NetworkServiceProvider},
 	state_request_handler::StateRequestHandler,
@@ -91,7 +93,7 @@ use sp_consensus::block_validation::{
 use sp_core::traits::{CodeExecutor, SpawnNamed};
 use sp_keystore::KeystorePtr;
 use sp_runtime::traits::{Block as BlockT, BlockIdTo, NumberFor, Zero};
-use sp_storage::{ChildInfo, ChildType, PrefixedStorageKey};
+use sp_storage::{well_known_keys, ChildInfo, ChildType, PrefixedStorageKey, StorageKey};
 use std::{
 	str::FromStr,
 	sync::Arc,
@@ -112,28 +114,48 @@ type TFullParts<TBl, TRtApi, TExec> =
 	(TFullClient<TBl, TRtApi, TExec>, Arc<TFullBackend<TBl>>, KeystoreContainer, TaskManager);
 
 /// Construct a local keystore shareable container
-pub struct KeystoreContainer(Arc<LocalKeystore>);
+pub struct KeystoreContainer {
+	keystore: KeystorePtr,
+	local: Option<Arc<LocalKeystore>>,
+}
 
 impl KeystoreContainer {
 	/// Construct KeystoreContainer
 	pub fn new(config: &KeystoreConfig) -> Result<Self, Error> {
-		let keystore = Arc::new(match config {
-			KeystoreConfig::Path { path, password } =>
+		let local = Arc::new(match config {
+			KeystoreConfig::Path { path, password, encryption_password: None } =>
 				LocalKeystore::open(path.clone(), password.clone())?,
+			KeystoreConfig::Path {
+				path,
+				password,
+				encryption_password: Some(encryption_password),
+			} => LocalKeystore::open_encrypted(path.clone(), password.clone(), encryption_password)?,
 			KeystoreConfig::InMemory => LocalKeystore::in_memory(),
+			KeystoreConfig::Remote { endpoint, auth_token } =>
+				return Ok(Self {
+					keystore: Arc::new(RemoteKeystore::new(endpoint.clone(), auth_token.clone())),
+					local: None,
+				}),
 		});
 
-		Ok(Self(keystore))
+		Ok(Self { keystore: local.clone(), local: Some(local) })
 	}
 
 	/// Returns a shared reference to a dynamic `Keystore` trait implementation.
 	pub fn keystore(&self) -> KeystorePtr {
-		self.0.clone()
+		self.keystore.clone()
 	}
 
 	/// Returns a shared reference to the local keystore .
-	pub fn local_keystore(&self) -> Arc<LocalKeystore> {
-		self.0.clone()
+	///
+	/// Fails with a remote keystore, whose private keys can't be handed out to the components
+	/// requiring a local keystore.
+	pub fn local_keystore(&self) -> Result<Arc<LocalKeystore>, Error> {
+		self.local.clone().ok_or_else(|| {
+			Error::Other(
+				"This node requires a local keystore, a remote keystore is not supported".into(),
+			)
+		})
 	}
 }
 
@@ -364,13 +386,21 @@ pub fn new_wasm_executor<H: HostFunctions>(config: &ExecutorConfiguration) -> Wa
 	let strategy = config
 		.default_heap_pages
 		.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |p| HeapAllocStrategy::Static { extra_pages: p as _ });
-	WasmExecutor::<H>::builder()
+	let builder = WasmExecutor::<H>::builder()
 		.with_execution_method(config.wasm_method)
 		.with_onchain_heap_alloc_strategy(strategy)
 		.with_offchain_heap_alloc_strategy(strategy)
 		.with_max_runtime_instances(config.max_runtime_instances)
-		.with_runtime_cache_size(config.runtime_cache_size)
-		.build()
+		.with_runtime_cache_size(config.runtime_cache_size);
+	let builder = match config.offchain_execution_budget {
+		Some(budget) => builder.with_offchain_execution_budget(budget),
+		None => builder,
+	};
+	let builder = match &config.artifact_cache_path {
+		Some(path) => builder.with_artifact_cache_path(path),
+		None => builder,
+	};
+	builder.build()
 }
 
 /// Create an instance of default DB-backend backend.
@@ -547,6 +577,26 @@ where
 		sc_transaction_pool::notification_future(client.clone(), transaction_pool.clone()),
 	);
 
+	// Persist the pool content across restarts.
+	if let Some(journal) = config.transaction_pool.journal() {
+		spawn_handle.spawn(
+			"txpool-journal",
+			Some("transaction-pool"),
+			sc_transaction_pool::journal_future(
+				client.clone(),
+				transaction_pool.clone(),
+				config.data_path.join("txpool").join("journal"),
+				journal.clone(),
+			),
+		);
+	}
+
+	spawn_handle.spawn(
+		"runtime-upgrades",
+		None,
+		precompile_runtime_upgrades(client.clone(), spawn_handle.clone()),
+	);
+
 	spawn_handle.spawn(
 		"on-transaction-imported",
 		Some("transaction-pool"),
@@ -591,6 +641,11 @@ where
 		),
 	);
 
+	// Light nodes answer the state queries with proofs fetched from their peers.
+	let remote_fetcher = config.network.sync_mode.is_light().then(|| {
+		new_remote_fetcher(&config, client.clone(), network.clone(), sync_service.clone())
+	});
+
 	let rpc_id_provider = config.rpc.id_provider.take();
 
 	// jsonrpsee RPC
@@ -617,6 +672,7 @@ where
 			backend.clone(),
 			&*rpc_builder,
 			rpc_v2_metrics.clone(),
+			remote_fetcher.clone(),
 		)
 	};
 
@@ -707,6 +763,39 @@ pub async fn propagate_transaction_notifications<Block, ExPool>(
 	}
 }
 
+/// Compile the runtime set by the imported blocks, without waiting for their finality.
+///
+/// The runtime is compiled in the background, instead of during the import of the first block
+/// using it. It is kept in the runtime cache of the executor, and in its on-disk artifact cache if
+/// it has one.
+pub async fn precompile_runtime_upgrades<Block, Client>(
+	client: Arc<Client>,
+	spawn_handle: SpawnTaskHandle,
+) where
+	Block: BlockT,
+	Client: BlockchainEvents<Block> + CallApiAt<Block> + Send + Sync + 'static,
+{
+	let code_key = StorageKey(well_known_keys::CODE.to_vec());
+	let mut upgrades = match client.storage_changes_notification_stream(Some(&[code_key]), None) {
+		Ok(upgrades) => upgrades,
+		Err(e) => {
+			error!("Cannot listen to the runtime upgrades: {e}");
+			return
+		},
+	};
+
+	while let Some(upgrade) = upgrades.next().await {
+		let client = client.clone();
+		let hash = upgrade.block;
+		spawn_handle.spawn_blocking("runtime-precompile", None, async move {
+			match client.runtime_version_at(hash) {
+				Ok(version) => debug!("Compiled runtime {version} set by block {hash:?}"),
+				Err(e) => debug!("Failed to compile the runtime set by block {hash:?}: {e}"),
+			}
+		});
+	}
+}
+
 /// Initialize telemetry with provided configuration and return telemetry handle
 pub fn init_telemetry<Block, Client, Network>(
 	name: String,
@@ -750,6 +839,43 @@ where
 	Ok(telemetry.handle())
 }
 
+/// Create the [`RemoteFetcher`] used by light nodes to fetch storage and call proofs from their
+/// peers.
+///
+/// The proofs of the runtime calls are checked with the standard host functions only.
+fn new_remote_fetcher<TBl, TCl>(
+	config: &Configuration,
+	client: Arc<TCl>,
+	network: Arc<dyn sc_network::service::traits::NetworkService>,
+	sync_service: Arc<SyncingService<TBl>>,
+) -> Arc<dyn RemoteFetcher<TBl>>
+where
+	TBl: BlockT,
+	TCl: HeaderBackend<TBl> + 'static,
+{
+	let strategy = config
+		.executor
+		.default_heap_pages
+		.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |p| HeapAllocStrategy::Static { extra_pages: p as _ });
+	let executor: WasmExecutor = WasmExecutor::builder()
+		.with_execution_method(config.executor.wasm_method)
+		.with_onchain_heap_alloc_strategy(strategy)
+		.with_offchain_heap_alloc_strategy(strategy)
+		.with_max_runtime_instances(config.executor.max_runtime_instances)
+		.with_runtime_cache_size(config.executor.runtime_cache_size)
+		.with_allow_missing_host_functions(true)
+		.build();
+
+	Arc::new(LightClientRequestSender::new(
+		config.chain_spec.fork_id(),
+		client,
+		executor,
+		// `NetworkRequest` is implemented for `Arc<dyn NetworkService>`.
+		Arc::new(network),
+		sync_service,
+	))
+}
+
 /// Generate RPC module using provided configuration
 pub fn gen_rpc_module<TBl, TBackend, TCl, TRpc, TExPool>(
 	spawn_handle: SpawnTaskHandle,
@@ -765,6 +891,7 @@ pub fn gen_rpc_module<TBl, TBackend, TCl, TRpc, TExPool>(
 	backend: Arc<TBackend>,
 	rpc_builder: &(dyn Fn(SubscriptionTaskExecutor) -> Result<RpcModule<TRpc>, Error>),
 	metrics: Option<sc_rpc_spec_v2::transaction::TransactionMetrics>,
+	remote_fetcher: Option<Arc<dyn RemoteFetcher<TBl>>>,
 ) -> Result<RpcModule<()>, Error>
 where
 	TBl: BlockT,
@@ -799,7 +926,11 @@ where
 
 	let (chain, state, child_state) = {
 		let chain = sc_rpc::chain::new_full(client.clone(), task_executor.clone()).into_rpc();
-		let (state, child_state) = sc_rpc::state::new_full(client.clone(), task_executor.clone());
+		let (state, child_state) = match remote_fetcher.clone() {
+			Some(fetcher) =>
+				sc_rpc::state::new_light(client.clone(), fetcher, task_executor.clone()),
+			None => sc_rpc::state::new_full(client.clone(), task_executor.clone()),
+		};
 		let state = state.into_rpc();
 		let child_state = child_state.into_rpc();
 
@@ -824,14 +955,18 @@ where
 	)
 	.into_rpc();
 
-	let chain_head_v2 = sc_rpc_spec_v2::chain_head::ChainHead::new(
+	let mut chain_head_v2 = sc_rpc_spec_v2::chain_head::ChainHead::new(
 		client.clone(),
 		backend.clone(),
 		task_executor.clone(),
 		// Defaults to sensible limits for the `ChainHead`.
 		sc_rpc_spec_v2::chain_head::ChainHeadConfig::default(),
-	)
-	.into_rpc();
+	);
+	// Light nodes don't have the state, storage and calls are answered by the peers.
+	if let Some(fetcher) = remote_fetcher {
+		chain_head_v2 = chain_head_v2.with_remote_fetcher(fetcher);
+	}
+	let chain_head_v2 = chain_head_v2.into_rpc();
 
 	// Part of the RPC v2 spec.
 	// An archive node that can respond to the `archive` RPC-v2 queries is a node with:
@@ -947,6 +1082,7 @@ where
 		+ BlockBackend<Block>
 		+ BlockIdTo<Block, Error = sp_blockchain::Error>
 		+ ProofProvider<Block>
+		+ AuxStore
 		+ HeaderBackend<Block>
 		+ BlockchainEvents<Block>
 		+ 'static,
@@ -1293,6 +1429,7 @@ where
 		+ BlockBackend<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error>
 		+ ProofProvider<Block>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -1371,13 +1508,19 @@ where
 {
 	// Custom protocol was not specified, use the default block handler.
 	// Allow both outgoing and incoming requests.
+	let limits = BlockServerLimits {
+		peer_bytes_per_sec: net_config.network_config.block_server_peer_rate,
+		total_bytes_per_sec: net_config.network_config.block_server_budget,
+	};
 	let BlockRelayParams { mut server, downloader, request_response_config } =
-		BlockRequestHandler::new::<Net>(
+		BlockRequestHandler::new_with_limits::<Net>(
 			network_service_handle,
 			&protocol_id,
 			fork_id,
 			client.clone(),
 			num_peers_hint,
+			limits,
+			net_config.metrics_registry.as_ref(),
 		);
 
 	spawn_handle.spawn("block-request-handler", Some("networking"), async move {
@@ -1406,13 +1549,15 @@ where
 		+ BlockBackend<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error>
 		+ ProofProvider<Block>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
 	Net: NetworkBackend<Block, <Block as BlockT>::Hash>,
 {
-	if warp_sync_config.is_none() && net_config.network_config.sync_mode.is_warp() {
-		return Err("Warp sync enabled, but no warp sync provider configured.".into())
+	let sync_mode = net_config.network_config.sync_mode;
+	if warp_sync_config.is_none() && (sync_mode.is_warp() || sync_mode.is_light()) {
+		return Err("Warp or light sync enabled, but no warp sync provider configured.".into())
 	}
 
 	if client.requires_full_sync() {
@@ -1420,6 +1565,7 @@ where
 			SyncMode::LightState { .. } =>
 				return Err("Fast sync doesn't work for archive nodes".into()),
 			SyncMode::Warp => return Err("Warp sync doesn't work for archive nodes".into()),
+			SyncMode::Light => return Err("Light sync doesn't work for archive nodes".into()),
 			SyncMode::Full => {},
 		}
 	}
@@ -0,0 +1,304 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Snapshots of the state of a finalized block.
+//!
+//! A snapshot starts with [`MAGIC`] and the little endian `u32` version of the format, followed
+//! by frames made of the little endian `u32` length of the payload, the BLAKE2-256 hash of the
+//! payload and the payload itself. The first frame holds the [`SnapshotHeader`], the following
+//! ones the storage of the block in [`SnapshotChunk`]s, and an empty frame ends the snapshot.
+
+use crate::error::Error;
+use codec::{Decode, DecodeAll, Encode};
+use log::{info, warn};
+use sc_client_api::{BlockBackend, HeaderBackend, StorageProvider};
+use sc_consensus::{
+	BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult, ImportedState, StateAction,
+	StorageChanges,
+};
+use sp_consensus::BlockOrigin;
+use sp_core::{
+	hashing::blake2_256,
+	storage::{well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX, ChildInfo},
+};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT, Zero},
+	Justifications,
+};
+use sp_state_machine::{KeyValueStates, KeyValueStorageLevel};
+use std::{
+	collections::BTreeMap,
+	io::{Read, Write},
+	sync::Arc,
+};
+
+/// Bytes a snapshot starts with.
+const MAGIC: &[u8; 8] = b"substsnp";
+
+/// Version of the snapshot format.
+const VERSION: u32 = 1;
+
+/// Size of the key values above which a chunk is written.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Maximum size of a frame. A chunk can exceed [`CHUNK_SIZE`] by its last value.
+const MAX_FRAME_SIZE: usize = 128 * 1024 * 1024;
+
+/// The block a snapshot is made of.
+///
+/// The aux storage isn't part of the snapshot, it reflects the latest state of the node rather
+/// than the one of the block. The consensus engines initialize it from the imported state.
+#[derive(Encode, Decode)]
+struct SnapshotHeader<B: BlockT> {
+	header: B::Header,
+	justifications: Option<Justifications>,
+}
+
+/// Part of the storage of the block.
+#[derive(Encode, Decode)]
+struct SnapshotChunk {
+	/// Prefixed storage key of the child trie, `None` for the top trie.
+	child_storage_key: Option<Vec<u8>>,
+	key_values: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Writes the key values of a trie in chunks.
+struct ChunkWriter<'a, W> {
+	output: &'a mut W,
+	chunk: SnapshotChunk,
+	size: usize,
+}
+
+impl<'a, W: Write> ChunkWriter<'a, W> {
+	fn new(output: &'a mut W, child_storage_key: Option<Vec<u8>>) -> Self {
+		Self { output, chunk: SnapshotChunk { child_storage_key, key_values: Vec::new() }, size: 0 }
+	}
+
+	fn push(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), Error> {
+		self.size += key.len() + value.len();
+		self.chunk.key_values.push((key, value));
+		if self.size >= CHUNK_SIZE {
+			self.write_chunk()?;
+		}
+		Ok(())
+	}
+
+	fn finish(mut self) -> Result<(), Error> {
+		if !self.chunk.key_values.is_empty() {
+			self.write_chunk()?;
+		}
+		Ok(())
+	}
+
+	fn write_chunk(&mut self) -> Result<(), Error> {
+		write_frame(&mut *self.output, &self.chunk.encode())?;
+		self.chunk.key_values.clear();
+		self.size = 0;
+		Ok(())
+	}
+}
+
+fn write_frame(output: &mut impl Write, payload: &[u8]) -> Result<(), Error> {
+	output.write_all(&(payload.len() as u32).to_le_bytes())?;
+	output.write_all(&blake2_256(payload))?;
+	output.write_all(payload)?;
+	Ok(())
+}
+
+fn read_frame(input: &mut impl Read) -> Result<Vec<u8>, Error> {
+	let mut len = [0; 4];
+	input.read_exact(&mut len)?;
+	let len = u32::from_le_bytes(len) as usize;
+	if len > MAX_FRAME_SIZE {
+		return Err(Error::Other(format!("Snapshot frame of {len} bytes is too large")))
+	}
+
+	let mut hash = [0; 32];
+	input.read_exact(&mut hash)?;
+	let mut payload = vec![0; len];
+	input.read_exact(&mut payload)?;
+	if blake2_256(&payload) != hash {
+		return Err("Snapshot is corrupted: frame hash mismatch".into())
+	}
+
+	Ok(payload)
+}
+
+fn decode_frame<T: Decode>(frame: &[u8]) -> Result<T, Error> {
+	T::decode_all(&mut &frame[..]).map_err(|e| Error::Other(format!("Snapshot is corrupted: {e}")))
+}
+
+/// Export a snapshot of the state of the finalized block `hash` to `output`.
+pub fn export_snapshot<B, BA, C>(
+	client: Arc<C>,
+	hash: B::Hash,
+	mut output: impl Write,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	BA: sc_client_api::backend::Backend<B>,
+	C: HeaderBackend<B> + BlockBackend<B> + StorageProvider<B, BA>,
+{
+	let header = client
+		.header(hash)?
+		.ok_or_else(|| Error::Other(format!("Unknown block {hash:?}")))?;
+	let number = *header.number();
+	if number > client.info().finalized_number || client.hash(number)? != Some(hash) {
+		return Err(Error::Other(format!("Block #{number} ({hash:?}) is not finalized")))
+	}
+
+	info!("Exporting snapshot of block #{number} ({hash:?})");
+
+	let justifications = client.justifications(hash)?;
+
+	output.write_all(MAGIC)?;
+	output.write_all(&VERSION.to_le_bytes())?;
+	write_frame(&mut output, &SnapshotHeader::<B> { header, justifications }.encode())?;
+
+	let mut keys = 0;
+	let mut child_storage_keys = Vec::new();
+	let mut top = ChunkWriter::new(&mut output, None);
+	for (key, value) in client.storage_pairs(hash, None, None)? {
+		// The roots of the child tries are computed again on import.
+		if key.0.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			child_storage_keys.push(key.0);
+			continue
+		}
+		top.push(key.0, value.0)?;
+		keys += 1;
+	}
+	top.finish()?;
+
+	for child_storage_key in child_storage_keys {
+		let child_info =
+			ChildInfo::new_default(&child_storage_key[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..]);
+		let mut child = ChunkWriter::new(&mut output, Some(child_storage_key));
+		for key in client.child_storage_keys(hash, child_info.clone(), None, None)? {
+			if let Some(value) = client.child_storage(hash, &child_info, &key)? {
+				child.push(key.0, value.0)?;
+				keys += 1;
+			}
+		}
+		child.finish()?;
+	}
+
+	write_frame(&mut output, &[])?;
+	output.flush()?;
+
+	info!("Exported {keys} keys");
+	Ok(())
+}
+
+/// Import a snapshot written by [`export_snapshot`] into an empty database, through
+/// `block_import`.
+///
+/// The snapshot is rejected if its block isn't `trusted_hash`. The state is checked against the
+/// state root of the header when it is imported. The block is imported as an imported state, from
+/// which the consensus engines, such as GRANDPA, read their authority set.
+///
+/// Returns the hash of the imported block.
+pub async fn import_snapshot<B, C, BI>(
+	client: Arc<C>,
+	block_import: BI,
+	mut input: impl Read,
+	trusted_hash: Option<B::Hash>,
+) -> Result<B::Hash, Error>
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+	BI: BlockImport<B>,
+{
+	if !client.info().best_number.is_zero() {
+		return Err("Snapshots can only be imported into an empty database".into())
+	}
+
+	let mut magic = [0; 8];
+	input.read_exact(&mut magic)?;
+	if &magic != MAGIC {
+		return Err("Input is not a snapshot".into())
+	}
+	let mut version = [0; 4];
+	input.read_exact(&mut version)?;
+	let version = u32::from_le_bytes(version);
+	if version != VERSION {
+		return Err(Error::Other(format!("Unsupported snapshot version {version}")))
+	}
+
+	let SnapshotHeader::<B> { header, justifications } = decode_frame(&read_frame(&mut input)?)?;
+	let hash = header.hash();
+	let number = *header.number();
+	match trusted_hash {
+		Some(trusted_hash) if trusted_hash != hash =>
+			return Err(Error::Other(format!(
+				"Snapshot is of block #{number} ({hash:?}), expected {trusted_hash:?}"
+			))),
+		Some(_) => {},
+		None => warn!(
+			"No trusted hash given, block #{number} ({hash:?}) of the snapshot is not verified"
+		),
+	}
+
+	info!("Importing snapshot of block #{number} ({hash:?})");
+
+	let mut top = Vec::new();
+	let mut children = BTreeMap::<Vec<u8>, Vec<_>>::new();
+	loop {
+		let frame = read_frame(&mut input)?;
+		if frame.is_empty() {
+			break
+		}
+		let SnapshotChunk { child_storage_key, key_values } = decode_frame(&frame)?;
+		match child_storage_key {
+			Some(child_storage_key) =>
+				children.entry(child_storage_key).or_default().extend(key_values),
+			None => top.extend(key_values),
+		}
+	}
+
+	let keys = top.len() + children.values().map(Vec::len).sum::<usize>();
+	let top = KeyValueStorageLevel {
+		state_root: Vec::new(),
+		parent_storage_keys: Vec::new(),
+		key_values: top,
+	};
+	let children =
+		children
+			.into_iter()
+			.map(|(child_storage_key, key_values)| KeyValueStorageLevel {
+				state_root: Vec::new(),
+				parent_storage_keys: vec![child_storage_key],
+				key_values,
+			});
+	let state = KeyValueStates(std::iter::once(top).chain(children).collect());
+
+	let mut import = BlockImportParams::new(BlockOrigin::File, header);
+	import.justifications = justifications;
+	import.state_action =
+		StateAction::ApplyChanges(StorageChanges::Import(ImportedState { block: hash, state }));
+	import.finalized = true;
+	import.fork_choice = Some(ForkChoiceStrategy::Custom(true));
+
+	match block_import.import_block(import).await {
+		Ok(ImportResult::Imported(_)) => {
+			info!("Imported {keys} keys");
+			Ok(hash)
+		},
+		Ok(result) => Err(Error::Other(format!("Failed to import the snapshot: {result:?}"))),
+		Err(e) => Err(Error::Other(format!("Failed to import the snapshot: {e}"))),
+	}
+}
@@ -151,9 +151,20 @@ pub enum KeystoreConfig {
 		path: PathBuf,
 		/// Node keystore's password.
 		password: Option<SecretString>,
+		/// Password the key files are encrypted with, if the keystore is encrypted at rest.
+		encryption_password: Option<SecretString>,
 	},
 	/// In-memory keystore. Recommended for in-browser nodes.
 	InMemory,
+	/// Keystore forwarding the key operations to a remote signer.
+	///
+	/// Nodes running components that require direct access to the key pairs fail to start with it.
+	Remote {
+		/// The endpoint of the remote signer.
+		endpoint: sc_keystore::remote::Endpoint,
+		/// The token authenticating the HTTP requests to the remote signer.
+		auth_token: Option<SecretString>,
+	},
 }
 
 impl KeystoreConfig {
@@ -161,7 +172,7 @@ impl KeystoreConfig {
 	pub fn path(&self) -> Option<&Path> {
 		match self {
 			Self::Path { path, .. } => Some(path),
-			Self::InMemory => None,
+			Self::InMemory | Self::Remote { .. } => None,
 		}
 	}
 }
@@ -355,6 +366,13 @@ pub struct ExecutorConfiguration {
 	pub default_heap_pages: Option<u64>,
 	/// Maximum number of different runtime versions that can be cached.
 	pub runtime_cache_size: u8,
+	/// Maximum amount of fuel an offchain runtime call can consume, `None` to not meter them.
+	pub offchain_execution_budget: Option<u64>,
+	/// Directory where the compiled runtimes are stored, to be reused after a restart.
+	///
+	/// The directory must only be writable by trusted users, the artifacts are loaded as native
+	/// code. `None` by default.
+	pub artifact_cache_path: Option<PathBuf>,
 }
 
 impl Default for ExecutorConfiguration {
@@ -364,6 +382,8 @@ impl Default for ExecutorConfiguration {
 			max_runtime_instances: 8,
 			default_heap_pages: None,
 			runtime_cache_size: 2,
+			offchain_execution_budget: None,
+			artifact_cache_path: None,
 		}
 	}
 }
@@ -59,7 +59,7 @@ pub use self::{
 		build_default_block_downloader, build_default_syncing_engine, build_network,
 		build_network_advanced, build_polkadot_syncing_strategy, gen_rpc_module, init_telemetry,
 		new_client, new_db_backend, new_full_client, new_full_parts, new_full_parts_record_import,
-		new_full_parts_with_genesis_builder, new_wasm_executor,
+		new_full_parts_with_genesis_builder, new_wasm_executor, precompile_runtime_upgrades,
 		propagate_transaction_notifications, spawn_tasks, BuildNetworkAdvancedParams,
 		BuildNetworkParams, DefaultSyncingEngineConfig, KeystoreContainer, SpawnTasksParams,
 		TFullBackend, TFullCallExecutor, TFullClient,
@@ -86,7 +86,7 @@ pub use sc_chain_spec::{
 
 use crate::config::RpcConfiguration;
 use prometheus_endpoint::Registry;
-pub use sc_consensus::ImportQueue;
+pub use sc_consensus::{BlockImport, ImportQueue};
 pub use sc_executor::NativeExecutionDispatch;
 pub use sc_network_sync::WarpSyncConfig;
 #[doc(hidden)]
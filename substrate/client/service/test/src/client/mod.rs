@@ -29,7 +29,10 @@ use sc_consensus::{
 	BlockCheckParams, BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult,
 };
 use sc_executor::WasmExecutor;
-use sc_service::client::{new_with_backend, Client, LocalCallExecutor};
+use sc_service::{
+	chain_ops::{export_snapshot, import_snapshot},
+	client::{new_with_backend, Client, LocalCallExecutor},
+};
 use sp_api::ProvideRuntimeApi;
 use sp_consensus::{BlockOrigin, Error as ConsensusError, SelectChain};
 use sp_core::{testing::TaskExecutor, traits::CallContext, H256};
@@ -2324,3 +2327,67 @@ fn finalize_after_best_block_updates_best() {
 	assert_eq!(client.chain_info().finalized_hash, a3.hash());
 	assert_eq!(client.chain_info().best_hash, a3.hash());
 }
+
+#[test]
+fn snapshot_seeds_empty_database() {
+	// G -> A1 -> A2
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let a1 = BlockBuilderBuilder::new(&*client)
+		.on_parent_block(client.chain_info().genesis_hash)
+		.with_parent_block_number(0)
+		.build()
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+	block_on(client.import(BlockOrigin::Own, a1.clone())).unwrap();
+
+	let justification = Justifications::from((TEST_ENGINE_ID, vec![1, 2, 3]));
+	let a2 = BlockBuilderBuilder::new(&*client)
+		.on_parent_block(a1.hash())
+		.with_parent_block_number(1)
+		.build()
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+	block_on(client.import_justified(BlockOrigin::Own, a2.clone(), justification.clone())).unwrap();
+
+	let mut snapshot = Vec::new();
+	export_snapshot::<Block, _, _>(client.clone(), a2.hash(), &mut snapshot).unwrap();
+
+	let seeded = Arc::new(substrate_test_runtime_client::new());
+	let hash = block_on(import_snapshot::<Block, _, _>(
+		seeded.clone(),
+		seeded.clone(),
+		&snapshot[..],
+		Some(a2.hash()),
+	))
+	.unwrap();
+
+	assert_eq!(hash, a2.hash());
+	assert_eq!(seeded.chain_info().finalized_hash, a2.hash());
+	assert_eq!(seeded.justifications(a2.hash()).unwrap(), Some(justification));
+	assert_eq!(
+		seeded.storage_pairs(a2.hash(), None, None).unwrap().collect::<Vec<_>>(),
+		client.storage_pairs(a2.hash(), None, None).unwrap().collect::<Vec<_>>(),
+	);
+
+	// A snapshot of another block than the trusted one is rejected.
+	let other = Arc::new(substrate_test_runtime_client::new());
+	assert!(block_on(import_snapshot::<Block, _, _>(
+		other.clone(),
+		other.clone(),
+		&snapshot[..],
+		Some(a1.hash()),
+	))
+	.is_err());
+
+	// A corrupted snapshot is rejected. The last byte of the last chunk precedes the length and
+	// the hash of the empty frame ending the snapshot.
+	let mut corrupted = snapshot.clone();
+	let last_chunk_byte = corrupted.len() - 37;
+	corrupted[last_chunk_byte] ^= 1;
+	assert!(block_on(import_snapshot::<Block, _, _>(other.clone(), other, &corrupted[..], None))
+		.is_err());
+}
@@ -232,7 +232,11 @@ fn node_config<E: ChainSpecExtension + Clone + 'static + Send + Sync>(
 		tokio_handle,
 		transaction_pool: Default::default(),
 		network: network_config,
-		keystore: KeystoreConfig::Path { path: root.join("key"), password: None },
+		keystore: KeystoreConfig::Path {
+			path: root.join("key"),
+			password: None,
+			encryption_password: None,
+		},
 		database: DatabaseSource::RocksDb { path: root.join("db"), cache_size: 128 },
 		trie_cache_maximum_size: Some(16 * 1024 * 1024),
 		warm_up_trie_cache: None,
@@ -40,13 +40,19 @@
 //! # Pruning.
 //! See `RefWindow` for pruning algorithm details. `StateDb` prunes on each canonicalization until
 //! pruning constraints are satisfied.
+//!
+//! # Sparse archive.
+//! In `PruningMode::SparseArchive` the state of every N-th canonical block (a checkpoint) is kept
+//! in addition to the pruning window. Deletions journaled after a checkpoint are only applied to
+//! nodes that were inserted after that checkpoint, so the nodes referenced by the checkpoint state
+//! are never removed. This relies on the reference counting of the backing database.
 
 mod noncanonical;
 mod pruning;
 #[cfg(test)]
 mod test;
 
-use codec::Codec;
+use codec::{Codec, Decode, Encode};
 use log::trace;
 use noncanonical::NonCanonicalOverlay;
 use parking_lot::RwLock;
@@ -62,6 +68,8 @@ const PRUNING_MODE: &[u8] = b"mode";
 const PRUNING_MODE_ARCHIVE: &[u8] = b"archive";
 const PRUNING_MODE_ARCHIVE_CANON: &[u8] = b"archive_canonical";
 const PRUNING_MODE_CONSTRAINED: &[u8] = b"constrained";
+const PRUNING_MODE_SPARSE_ARCHIVE: &[u8] = b"sparse_archive";
+const SPARSE_ARCHIVE_PERIOD: &[u8] = b"sparse_archive_period";
 pub(crate) const DEFAULT_MAX_BLOCK_CONSTRAINT: u32 = 256;
 
 /// Database value type.
@@ -145,6 +153,8 @@ pub enum StateDbError {
 	BlockUnavailable,
 	/// Block record is missing from the pruning window
 	BlockMissing,
+	/// Sparse archive pruning requested for a database without reference counting.
+	SparseArchiveUnsupported,
 }
 
 impl<E> From<StateDbError> for Error<E> {
@@ -196,6 +206,10 @@ impl fmt::Debug for StateDbError {
 				write!(f, "Trying to get a block record from db while it is not commit to db yet")
 			},
 			Self::BlockMissing => write!(f, "Block record is missing from the pruning window"),
+			Self::SparseArchiveUnsupported => write!(
+				f,
+				"Sparse archive pruning requires a database with reference counting support"
+			),
 		}
 	}
 }
@@ -235,6 +249,14 @@ pub enum PruningMode {
 	ArchiveAll,
 	/// Canonicalization discards non-canonical nodes. All the canonical nodes are kept in the DB.
 	ArchiveCanonical,
+	/// Maintain a pruning window, and additionally keep the state of every canonical block whose
+	/// number is a multiple of `period`.
+	SparseArchive {
+		/// Constraints of the pruning window.
+		constraints: Constraints,
+		/// Distance between two blocks whose state is kept.
+		period: u32,
+	},
 }
 
 impl PruningMode {
@@ -243,11 +265,26 @@ impl PruningMode {
 		PruningMode::Constrained(Constraints { max_blocks: Some(n) })
 	}
 
+	/// Create a mode that keeps given number of blocks and the state of every `period`-th block.
+	pub fn sparse_archive(n: u32, period: u32) -> PruningMode {
+		PruningMode::SparseArchive { constraints: Constraints { max_blocks: Some(n) }, period }
+	}
+
 	/// Is this an archive (either ArchiveAll or ArchiveCanonical) pruning mode?
 	pub fn is_archive(&self) -> bool {
+		match *self {
+			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => true,
+			PruningMode::Constrained(_) | PruningMode::SparseArchive { .. } => false,
+		}
+	}
+
+	/// Returns `true` if the state of the given canonical block is kept by this mode after it
+	/// leaves the pruning window.
+	pub fn is_checkpoint(&self, number: u64) -> bool {
 		match *self {
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => true,
 			PruningMode::Constrained(_) => false,
+			PruningMode::SparseArchive { period, .. } => period != 0 && number % period as u64 == 0,
 		}
 	}
 
@@ -257,6 +294,7 @@ impl PruningMode {
 			PruningMode::ArchiveAll => PRUNING_MODE_ARCHIVE,
 			PruningMode::ArchiveCanonical => PRUNING_MODE_ARCHIVE_CANON,
 			PruningMode::Constrained(_) => PRUNING_MODE_CONSTRAINED,
+			PruningMode::SparseArchive { .. } => PRUNING_MODE_SPARSE_ARCHIVE,
 		}
 	}
 
@@ -265,6 +303,8 @@ impl PruningMode {
 			PRUNING_MODE_ARCHIVE => Some(Self::ArchiveAll),
 			PRUNING_MODE_ARCHIVE_CANON => Some(Self::ArchiveCanonical),
 			PRUNING_MODE_CONSTRAINED => Some(Self::Constrained(Default::default())),
+			PRUNING_MODE_SPARSE_ARCHIVE =>
+				Some(Self::SparseArchive { constraints: Default::default(), period: 0 }),
 			_ => None,
 		}
 	}
@@ -319,6 +359,17 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		let pruning: Option<RefWindow<BlockHash, Key, D>> = match mode {
 			PruningMode::Constrained(Constraints { max_blocks }) =>
 				Some(RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?),
+			PruningMode::SparseArchive { constraints: Constraints { max_blocks }, period } => {
+				// Without reference counting a node that is deleted and inserted again can't be
+				// told apart from a node that is still referenced by a checkpoint.
+				if ref_counting {
+					return Err(StateDbError::SparseArchiveUnsupported.into())
+				}
+				Some(
+					RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?
+						.with_checkpoint_period(period),
+				)
+			},
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
 		};
 
@@ -338,7 +389,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 				// write changes immediately
 				Ok(CommitSet { data: changeset, meta: Default::default() })
 			},
-			PruningMode::Constrained(_) | PruningMode::ArchiveCanonical => self
+			PruningMode::Constrained(_) |
+			PruningMode::SparseArchive { .. } |
+			PruningMode::ArchiveCanonical => self
 				.non_canonical
 				.insert(hash, number, parent_hash, changeset)
 				.map_err(Into::into),
@@ -380,7 +433,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	fn is_pruned(&self, hash: &BlockHash, number: u64) -> IsPruned {
 		match self.mode {
 			PruningMode::ArchiveAll => IsPruned::NotPruned,
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::SparseArchive { .. } => {
 				if self
 					.non_canonical
 					.last_canonicalized_block_number()
@@ -397,6 +452,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 						// We don't know for sure.
 						None => IsPruned::MaybePruned,
 						Some(pruning) => match pruning.have_block(hash, number) {
+							// The state of a canonical block at this height is kept, but the
+							// block may be a discarded fork.
+							HaveBlock::No if self.mode.is_checkpoint(number) =>
+								IsPruned::MaybePruned,
 							HaveBlock::No => IsPruned::Pruned,
 							HaveBlock::Yes => IsPruned::NotPruned,
 							HaveBlock::Maybe => IsPruned::MaybePruned,
@@ -408,9 +467,12 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	}
 
 	fn prune(&mut self, commit: &mut CommitSet<Key>) -> Result<(), Error<D::Error>> {
-		if let (&mut Some(ref mut pruning), PruningMode::Constrained(constraints)) =
-			(&mut self.pruning, &self.mode)
-		{
+		let constraints = match &self.mode {
+			PruningMode::Constrained(constraints) |
+			PruningMode::SparseArchive { constraints, .. } => constraints,
+			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => return Ok(()),
+		};
+		if let Some(ref mut pruning) = self.pruning {
 			loop {
 				if pruning.window_size() <= constraints.max_blocks.unwrap_or(0) as u64 {
 					break
@@ -442,16 +504,18 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	fn revert_one(&mut self) -> Option<CommitSet<Key>> {
 		match self.mode {
 			PruningMode::ArchiveAll => Some(CommitSet::default()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) =>
-				self.non_canonical.revert_one(),
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::SparseArchive { .. } => self.non_canonical.revert_one(),
 		}
 	}
 
 	fn remove(&mut self, hash: &BlockHash) -> Option<CommitSet<Key>> {
 		match self.mode {
 			PruningMode::ArchiveAll => Some(CommitSet::default()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) =>
-				self.non_canonical.remove(hash),
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::SparseArchive { .. } => self.non_canonical.remove(hash),
 		}
 	}
 
@@ -461,11 +525,14 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	{
 		match self.mode {
 			PruningMode::ArchiveAll => Ok(()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::SparseArchive { .. } => {
 				let have_block = self.non_canonical.have_block(hash) ||
 					self.pruning.as_ref().map_or_else(
 						|| hint(),
 						|pruning| match pruning.have_block(hash, number) {
+							HaveBlock::No if self.mode.is_checkpoint(number) => hint(),
 							HaveBlock::No => false,
 							HaveBlock::Yes => true,
 							HaveBlock::Maybe => hint(),
@@ -556,18 +623,18 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 			(false, Some(stored), Some(requested)) => choose_pruning_mode(stored, requested)?,
 		};
 
-		let db_init_commit_set = if should_init {
-			let mut cs: CommitSet<Key> = Default::default();
-
+		let mut db_init_commit_set: CommitSet<Key> = Default::default();
+		if should_init {
 			let key = to_meta_key(PRUNING_MODE, &());
 			let value = selected_mode.id().to_owned();
 
-			cs.meta.inserted.push((key, value));
-
-			cs
-		} else {
-			Default::default()
-		};
+			db_init_commit_set.meta.inserted.push((key, value));
+		}
+		// The period may change between runs, so it is always written.
+		if let PruningMode::SparseArchive { period, .. } = selected_mode {
+			let key = to_meta_key(SPARSE_ARCHIVE_PERIOD, &());
+			db_init_commit_set.meta.inserted.push((key, period.encode()));
+		}
 
 		let state_db =
 			StateDb { db: RwLock::new(StateDbSync::new(selected_mode, ref_counting, db)?) };
@@ -660,6 +727,55 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 	}
 }
 
+/// Build the metadata changes that switch an archive `StateDb` to the non-archive `mode`.
+///
+/// `last_canonical` becomes the only block of the pruning window. The blocks in `non_canonical`
+/// are added to the non-canonical overlay with their change set relative to their parent, ordered
+/// by block number. This is only needed when converting from [`PruningMode::ArchiveAll`], which
+/// does not maintain an overlay.
+///
+/// The backing database is not touched: removing the trie nodes that are not referenced by the
+/// kept states anymore, as well as the nodes inserted by the non-canonical blocks, which are kept
+/// in the overlay, is up to the caller.
+pub fn convert_archive<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	mode: PruningMode,
+	last_canonical: (BlockHash, u64),
+	non_canonical: Vec<(BlockHash, u64, BlockHash, ChangeSet<Key>)>,
+) -> Result<CommitSet<Key>, Error<D::Error>> {
+	let stored = fetch_stored_pruning_mode(db)?.ok_or_else(|| {
+		StateDbError::Metadata(
+			"An existing StateDb does not have PRUNING_MODE stored in its meta-data".into(),
+		)
+	})?;
+	if !stored.is_archive() || mode.is_archive() {
+		return Err(StateDbError::IncompatiblePruningModes { stored, requested: mode }.into())
+	}
+
+	let mut commit = CommitSet::default();
+	commit
+		.meta
+		.inserted
+		.push((to_meta_key(PRUNING_MODE, &()), mode.id().to_owned()));
+	if let PruningMode::SparseArchive { period, .. } = mode {
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(SPARSE_ARCHIVE_PERIOD, &()), period.encode()));
+	}
+	NonCanonicalOverlay::<BlockHash, Key>::journal_existing(
+		&last_canonical,
+		non_canonical,
+		&mut commit,
+	)?;
+	RefWindow::<BlockHash, Key, D>::journal_existing(
+		&last_canonical.0,
+		last_canonical.1,
+		&mut commit,
+	);
+	Ok(commit)
+}
+
 /// The result return by `StateDb::is_pruned`
 #[derive(Debug, PartialEq, Eq)]
 pub enum IsPruned {
@@ -674,7 +790,18 @@ pub enum IsPruned {
 fn fetch_stored_pruning_mode<D: MetaDb>(db: &D) -> Result<Option<PruningMode>, Error<D::Error>> {
 	let meta_key_mode = to_meta_key(PRUNING_MODE, &());
 	if let Some(stored_mode) = db.get_meta(&meta_key_mode).map_err(Error::Db)? {
-		if let Some(mode) = PruningMode::from_id(&stored_mode) {
+		if let Some(mut mode) = PruningMode::from_id(&stored_mode) {
+			if let PruningMode::SparseArchive { ref mut period, .. } = mode {
+				let meta_key_period = to_meta_key(SPARSE_ARCHIVE_PERIOD, &());
+				*period = match db.get_meta(&meta_key_period).map_err(Error::Db)? {
+					Some(stored_period) => u32::decode(&mut stored_period.as_slice())?,
+					None =>
+						return Err(StateDbError::Metadata(
+							"Sparse archive StateDb does not have its period stored".into(),
+						)
+						.into()),
+				};
+			}
 			Ok(Some(mode))
 		} else {
 			Err(StateDbError::Metadata(format!(
@@ -698,6 +825,8 @@ fn choose_pruning_mode(
 			Ok(PruningMode::ArchiveCanonical),
 		(PruningMode::Constrained(_), PruningMode::Constrained(requested)) =>
 			Ok(PruningMode::Constrained(requested)),
+		(PruningMode::SparseArchive { .. }, requested @ PruningMode::SparseArchive { .. }) =>
+			Ok(requested),
 		(stored, requested) => Err(StateDbError::IncompatiblePruningModes { requested, stored }),
 	}
 }
@@ -705,8 +834,9 @@ fn choose_pruning_mode(
 #[cfg(test)]
 mod tests {
 	use crate::{
+		convert_archive,
 		test::{make_changeset, make_db, TestDb},
-		Constraints, Error, IsPruned, PruningMode, StateDb, StateDbError,
+		Constraints, Error, IsPruned, NodeDb, PruningMode, StateDb, StateDbError,
 	};
 	use sp_core::H256;
 
@@ -847,6 +977,119 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn sparse_archive_keeps_checkpoints() {
+		let mut db = make_db(&[1]);
+		let (state_db_init, state_db) =
+			StateDb::open(db.clone(), Some(PruningMode::sparse_archive(0, 2)), false, true)
+				.unwrap();
+		db.commit(&state_db_init);
+
+		for i in 1..=5u64 {
+			let hash = H256::from_low_u64_be(i);
+			db.commit(
+				&state_db
+					.insert_block(
+						&hash,
+						i,
+						&H256::from_low_u64_be(i - 1),
+						make_changeset(&[i + 1], &[i]),
+					)
+					.unwrap(),
+			);
+			db.commit(&state_db.canonicalize_block(&hash).unwrap());
+		}
+
+		// Nodes referenced by the state of blocks #2 and #4 are kept, as well as the nodes of the
+		// first block, for which the insertions are unknown.
+		assert!(db.data_eq(&make_db(&[1, 3, 5, 6])));
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(2), 2), IsPruned::MaybePruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(3), 3), IsPruned::Pruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(4), 4), IsPruned::MaybePruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(5), 5), IsPruned::Pruned);
+	}
+
+	#[test]
+	fn sparse_archive_requires_ref_counting() {
+		let db = make_db(&[]);
+		let state_db_open_result: Result<(_, StateDb<H256, H256, TestDb>), _> =
+			StateDb::open(db, Some(PruningMode::sparse_archive(256, 1000)), true, true);
+		assert!(matches!(
+			state_db_open_result,
+			Err(Error::StateDb(StateDbError::SparseArchiveUnsupported))
+		));
+	}
+
+	#[test]
+	fn convert_archive_works() {
+		let mut db = make_db(&[]);
+		let (state_db_init, state_db) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::ArchiveAll),
+			false,
+			true,
+		)
+		.unwrap();
+		db.commit(&state_db_init);
+		for i in 1..=2u64 {
+			db.commit(
+				&state_db
+					.insert_block(
+						&H256::from_low_u64_be(i),
+						i,
+						&H256::from_low_u64_be(i - 1),
+						make_changeset(&[i], &[]),
+					)
+					.unwrap(),
+			);
+		}
+
+		// Two competing blocks on top of the last canonical block, whose inserted nodes are kept
+		// in the overlay.
+		let commit = convert_archive::<H256, H256, _>(
+			&db,
+			PruningMode::blocks_pruning(1),
+			(H256::from_low_u64_be(2), 2),
+			vec![
+				(H256::from_low_u64_be(3), 3, H256::from_low_u64_be(2), make_changeset(&[3], &[2])),
+				(H256::from_low_u64_be(4), 3, H256::from_low_u64_be(2), make_changeset(&[4], &[])),
+			],
+		)
+		.unwrap();
+		db.commit(&commit);
+
+		let (_, state_db) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::blocks_pruning(1)),
+			false,
+			false,
+		)
+		.unwrap();
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(2), 2), IsPruned::NotPruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(3), 3), IsPruned::NotPruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(4), 3), IsPruned::NotPruned);
+		assert!(NodeDb::get(&db, &H256::from_low_u64_be(3)).unwrap().is_none());
+
+		db.commit(&state_db.canonicalize_block(&H256::from_low_u64_be(3)).unwrap());
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(2), 2), IsPruned::Pruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(3), 3), IsPruned::NotPruned);
+		// The nodes of the canonicalized block are committed, the fork is discarded.
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(4), 3), IsPruned::Pruned);
+		assert!(NodeDb::get(&db, &H256::from_low_u64_be(3)).unwrap().is_some());
+		assert!(NodeDb::get(&db, &H256::from_low_u64_be(4)).unwrap().is_none());
+
+		assert!(matches!(
+			convert_archive::<H256, H256, _>(
+				&db,
+				PruningMode::blocks_pruning(1),
+				(H256::from_low_u64_be(3), 3),
+				Vec::new(),
+			),
+			Err(Error::StateDb(StateDbError::IncompatiblePruningModes { .. }))
+		));
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -942,6 +1185,27 @@ mod tests {
 				Some(PruningMode::ArchiveCanonical),
 				Ok(PruningMode::ArchiveCanonical),
 			),
+			(
+				Some(PruningMode::sparse_archive(256, 1000)),
+				None,
+				Ok(PruningMode::sparse_archive(256, 1000)),
+			),
+			(
+				Some(PruningMode::sparse_archive(256, 1000)),
+				Some(PruningMode::sparse_archive(128, 500)),
+				Ok(PruningMode::sparse_archive(128, 500)),
+			),
+			(
+				Some(PruningMode::sparse_archive(256, 1000)),
+				Some(PruningMode::blocks_pruning(256)),
+				Err(()),
+			),
+			(Some(PruningMode::sparse_archive(256, 1000)), Some(PruningMode::ArchiveAll), Err(())),
+			(
+				Some(PruningMode::blocks_pruning(256)),
+				Some(PruningMode::sparse_archive(256, 1000)),
+				Err(()),
+			),
 		] {
 			check_stored_and_requested_mode_compatibility(created, reopened, expected);
 		}
@@ -241,6 +241,50 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		})
 	}
 
+	/// Journal existing blocks on top of `last_canonical`, with their change set relative to their
+	/// parent.
+	///
+	/// Like for inserted blocks, the nodes inserted by the blocks must not be in the backing
+	/// database. `blocks` must be ordered by block number, and each block must be a child of
+	/// `last_canonical` or of one of the preceding blocks.
+	pub fn journal_existing(
+		last_canonical: &(BlockHash, u64),
+		blocks: Vec<(BlockHash, u64, BlockHash, ChangeSet<Key>)>,
+		commit: &mut CommitSet<Key>,
+	) -> Result<(), StateDbError> {
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(LAST_CANONICAL, &()), last_canonical.encode()));
+
+		let mut known = HashMap::from([(last_canonical.0.clone(), last_canonical.1)]);
+		let mut level = (last_canonical.1 + 1, 0);
+		for (hash, number, parent_hash, changeset) in blocks {
+			if known.get(&parent_hash).map_or(true, |parent| parent + 1 != number) {
+				return Err(StateDbError::InvalidParent)
+			}
+			if number != level.0 {
+				level = (number, 0);
+			}
+			if level.1 >= MAX_BLOCKS_PER_LEVEL {
+				return Err(StateDbError::TooManySiblingBlocks { number })
+			}
+			known.insert(hash.clone(), number);
+			let journal_record = JournalRecord::<BlockHash, Key> {
+				hash,
+				parent_hash,
+				inserted: changeset.inserted,
+				deleted: changeset.deleted,
+			};
+			commit
+				.meta
+				.inserted
+				.push((to_journal_key(number, level.1), journal_record.encode()));
+			level.1 += 1;
+		}
+		Ok(())
+	}
+
 	/// Insert a new block into the overlay. If inserted on the second level or lover expects parent
 	/// to be present in the window.
 	pub fn insert(
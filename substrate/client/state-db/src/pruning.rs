@@ -23,10 +23,13 @@
 //! If a node is re-inserted into the window it gets removed from
 //! the death list.
 //! The changes are journaled in the DB.
+//!
+//! When a checkpoint period is set, deletions of nodes that are referenced by the state of the
+//! last checkpoint block are dropped before being journaled. See [`Checkpoints`].
 
 use crate::{
-	noncanonical::LAST_CANONICAL, to_meta_key, CommitSet, Error, Hash, MetaDb, StateDbError,
-	DEFAULT_MAX_BLOCK_CONSTRAINT, LOG_TARGET,
+	noncanonical::LAST_CANONICAL, to_meta_key, CommitSet, DBValue, Error, Hash, MetaDb,
+	StateDbError, DEFAULT_MAX_BLOCK_CONSTRAINT, LOG_TARGET,
 };
 use codec::{Decode, Encode};
 use log::trace;
@@ -42,6 +45,49 @@ pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	queue: DeathRowQueue<BlockHash, Key, D>,
 	/// Block number that is next to be pruned.
 	base: u64,
+	/// Blocks whose state is kept after leaving the window, if any.
+	checkpoints: Option<Checkpoints<Key>>,
+}
+
+/// Keeps track of the nodes that may be deleted without affecting the last checkpoint.
+///
+/// A node deleted by a block was referenced by the state of its parent. If that node was not
+/// inserted after the last checkpoint, it is also referenced by the checkpoint state, so the
+/// deletion must be dropped. Each insertion after the checkpoint allows for exactly one deletion,
+/// which keeps the reference count of the checkpoint nodes above zero.
+///
+/// The insertions are only tracked in memory. After a restart, deletions are dropped until the next
+/// checkpoint, which keeps some nodes in the database that could have been removed.
+struct Checkpoints<Key: Hash> {
+	/// Distance between two checkpoints.
+	period: u64,
+	/// Number of insertions of each node since the last checkpoint.
+	inserted: HashMap<Key, u32>,
+}
+
+impl<Key: Hash> Checkpoints<Key> {
+	fn is_checkpoint(&self, number: u64) -> bool {
+		self.period != 0 && number % self.period == 0
+	}
+
+	/// Drop the deletions of canonical block `number` that affect the last checkpoint, and record
+	/// its insertions.
+	fn note_canonical(&mut self, number: u64, inserted: &[(Key, DBValue)], deleted: &mut Vec<Key>) {
+		deleted.retain(|k| match self.inserted.get_mut(k) {
+			Some(count) if *count > 0 => {
+				*count -= 1;
+				true
+			},
+			_ => false,
+		});
+		if self.is_checkpoint(number) {
+			self.inserted.clear();
+		} else {
+			for (k, _) in inserted {
+				*self.inserted.entry(k.clone()).or_default() += 1;
+			}
+		}
+	}
 }
 
 /// `DeathRowQueue` used to keep track of blocks in the pruning window, there are two flavors:
@@ -347,7 +393,13 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			DeathRowQueue::new_db_backed(db, base, last, window_size)?
 		};
 
-		Ok(RefWindow { queue, base })
+		Ok(RefWindow { queue, base, checkpoints: None })
+	}
+
+	/// Keep the state of every canonical block whose number is a multiple of `period`.
+	pub fn with_checkpoint_period(mut self, period: u32) -> Self {
+		self.checkpoints = Some(Checkpoints { period: period as u64, inserted: HashMap::new() });
+		self
 	}
 
 	pub fn window_size(&self) -> u64 {
@@ -398,6 +450,21 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		}
 	}
 
+	/// Journal an existing canonical block as the only block of a new pruning window.
+	///
+	/// Used when the nodes of the block are already in the backing database.
+	pub fn journal_existing(hash: &BlockHash, number: u64, commit: &mut CommitSet<Key>) {
+		if number > 0 {
+			commit
+				.meta
+				.inserted
+				.push((to_meta_key(LAST_PRUNED, &()), (number - 1).encode()));
+		}
+		let journal_record: JournalRecord<BlockHash, Key> =
+			JournalRecord { hash: hash.clone(), inserted: Vec::new(), deleted: Vec::new() };
+		commit.meta.inserted.push((to_journal_key(number), journal_record.encode()));
+	}
+
 	/// Add a change set to the window. Creates a journal record and pushes it to `commit`
 	pub fn note_canonical(
 		&mut self,
@@ -429,7 +496,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		} else {
 			Default::default()
 		};
-		let deleted = std::mem::take(&mut commit.data.deleted);
+		let mut deleted = std::mem::take(&mut commit.data.deleted);
+		if let Some(checkpoints) = &mut self.checkpoints {
+			checkpoints.note_canonical(number, &commit.data.inserted, &mut deleted);
+		}
 		let journal_record = JournalRecord { hash: hash.clone(), inserted, deleted };
 		commit.meta.inserted.push((to_journal_key(number), journal_record.encode()));
 		self.queue.import(self.base, number, journal_record);
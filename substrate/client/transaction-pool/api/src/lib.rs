@@ -236,6 +236,12 @@ pub trait InPoolTransaction {
 	fn provides(&self) -> &[TransactionTag];
 	/// Return a flag indicating if the transaction should be propagated to other peers.
 	fn is_propagable(&self) -> bool;
+	/// Get the source the transaction was submitted from.
+	///
+	/// Defaults to [`TransactionSource::External`], the least trusted source.
+	fn source(&self) -> TransactionSource {
+		TransactionSource::External
+	}
 }
 
 /// Transaction pool interface.
@@ -19,9 +19,12 @@
 //! Utility for building substrate transaction pool trait object.
 
 use crate::{
-	common::api::FullChainApi,
+	common::{api::FullChainApi, journal::JournalOptions},
 	fork_aware_txpool::ForkAwareTxPool as ForkAwareFullPool,
-	graph::{base_pool::Transaction, ChainApi, ExtrinsicFor, ExtrinsicHash, IsValidator, Options},
+	graph::{
+		base_pool::Transaction, ChainApi, ExtrinsicFor, ExtrinsicHash, IsValidator, Options,
+		SenderLimits,
+	},
 	single_state_txpool::BasicPool as SingleStateFullPool,
 	TransactionPoolWrapper, LOG_TARGET,
 };
@@ -45,11 +48,16 @@ pub enum TransactionPoolType {
 pub struct TransactionPoolOptions {
 	txpool_type: TransactionPoolType,
 	options: Options,
+	journal: Option<JournalOptions>,
 }
 
 impl Default for TransactionPoolOptions {
 	fn default() -> Self {
-		Self { txpool_type: TransactionPoolType::SingleState, options: Default::default() }
+		Self {
+			txpool_type: TransactionPoolType::SingleState,
+			options: Default::default(),
+			journal: None,
+		}
 	}
 }
 
@@ -81,7 +89,24 @@ impl TransactionPoolOptions {
 			Duration::from_secs(30 * 60)
 		};
 
-		TransactionPoolOptions { options, txpool_type }
+		TransactionPoolOptions { options, txpool_type, journal: None }
+	}
+
+	/// Enables the on-disk journal of the pool content with the given options.
+	pub fn with_journal(mut self, journal: Option<JournalOptions>) -> Self {
+		self.journal = journal;
+		self
+	}
+
+	/// Sets the per-sender limits, which are enforced by the fork-aware pool only.
+	pub fn with_sender_limits(mut self, sender_limits: SenderLimits) -> Self {
+		self.options.sender_limits = sender_limits;
+		self
+	}
+
+	/// Returns the options of the on-disk journal, if it is enabled.
+	pub fn journal(&self) -> Option<&JournalOptions> {
+		self.journal.as_ref()
 	}
 
 	/// Creates predefined options for benchmarking
@@ -96,10 +121,12 @@ impl TransactionPoolOptions {
 					count: 100_000,
 					total_bytes: 100 * 1024 * 1024,
 				},
+				sender_limits: Default::default(),
 				reject_future_transactions: false,
 				ban_time: Duration::from_secs(30 * 60),
 			},
 			txpool_type: TransactionPoolType::SingleState,
+			journal: None,
 		}
 	}
 }
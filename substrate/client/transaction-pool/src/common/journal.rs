@@ -0,0 +1,359 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk journal of the transaction pool content.
+//!
+//! The journal keeps the ready and future transactions of the pool across node restarts. It is
+//! periodically rewritten with the current content of the pool, and once more on shutdown. On
+//! startup, the journaled transactions which are not older than the configured maximum age are
+//! resubmitted to the pool, which revalidates them against the best block.
+
+use crate::LOG_TARGET;
+use codec::{Decode, Encode};
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TransactionSource, TxHash};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, info, warn};
+
+/// Version of the journal file format.
+const JOURNAL_VERSION: u8 = 1;
+
+/// Options of the transaction pool journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalOptions {
+	/// Journaled transactions older than this are not resubmitted on startup.
+	pub max_age: Duration,
+	/// How often the journal is rewritten with the current content of the pool.
+	pub interval: Duration,
+}
+
+impl Default for JournalOptions {
+	fn default() -> Self {
+		Self { max_age: Duration::from_secs(3 * 60 * 60), interval: Duration::from_secs(60) }
+	}
+}
+
+/// A single journaled transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+struct JournalEntry {
+	/// Unix timestamp (in seconds) at which the transaction was journaled for the first time.
+	journaled_at: u64,
+	/// The source the transaction was originally submitted from.
+	source: TransactionSource,
+	/// The encoded transaction.
+	transaction: Vec<u8>,
+}
+
+/// Returns the current unix timestamp in seconds.
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or_default()
+}
+
+/// Runs the blocking `f` on a thread dedicated to blocking operations.
+async fn blocking<T: Send + 'static>(
+	f: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+	tokio::task::spawn_blocking(f).await.map_err(io::Error::other)?
+}
+
+/// Reads the journal at `path`. A missing journal is considered empty.
+fn read_journal(path: &Path) -> io::Result<Vec<JournalEntry>> {
+	let data = match fs::read(path) {
+		Ok(data) => data,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(error) => return Err(error),
+	};
+
+	let (version, entries) = <(u8, Vec<JournalEntry>)>::decode(&mut &data[..])
+		.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+	if version != JOURNAL_VERSION {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("Unsupported journal version: {version}"),
+		))
+	}
+
+	Ok(entries)
+}
+
+/// Replaces the journal at `path` with the given entries.
+///
+/// The entries are written to a temporary file first, so that the journal is never left
+/// partially written.
+fn write_journal(path: &Path, entries: &[JournalEntry]) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, (JOURNAL_VERSION, entries).encode())?;
+	fs::rename(&tmp_path, path)
+}
+
+/// Keeps the ones of `entries` which are not older than `max_age` at `now`.
+fn fresh_entries(entries: Vec<JournalEntry>, now: u64, max_age: Duration) -> Vec<JournalEntry> {
+	entries
+		.into_iter()
+		.filter(|entry| now.saturating_sub(entry.journaled_at) <= max_age.as_secs())
+		.collect()
+}
+
+/// Resubmits the journaled transactions to the pool at the block `at`.
+///
+/// Returns the time at which every successfully resubmitted transaction was journaled first.
+async fn restore<Pool: TransactionPool>(
+	txpool: &Pool,
+	at: <Pool::Block as BlockT>::Hash,
+	path: &Path,
+	max_age: Duration,
+) -> HashMap<TxHash<Pool>, u64> {
+	let mut journaled_at = HashMap::new();
+
+	let read_path = path.to_owned();
+	let entries = match blocking(move || read_journal(&read_path)).await {
+		Ok(entries) => entries,
+		Err(error) => {
+			warn!(target: LOG_TARGET, %error, ?path, "Failed to read the transaction pool journal");
+			return journaled_at
+		},
+	};
+	let journaled = entries.len();
+
+	let entries = fresh_entries(entries, now(), max_age)
+		.into_iter()
+		.filter_map(|entry| {
+			let transaction =
+				<Pool::Block as BlockT>::Extrinsic::decode(&mut &entry.transaction[..]).ok()?;
+			Some((entry.journaled_at, entry.source, transaction))
+		})
+		.collect::<Vec<_>>();
+
+	let mut restored = 0;
+	// Keep the journal order, which submits transactions after the ones they depend on.
+	for batch in entries.chunk_by(|(_, a, _), (_, b, _)| a == b) {
+		let source = batch[0].1;
+		let xts = batch.iter().map(|(_, _, xt)| xt.clone()).collect();
+
+		match txpool.submit_at(at, source, xts).await {
+			Ok(results) =>
+				for ((time, _, _), result) in batch.iter().zip(results) {
+					match result {
+						Ok(hash) => {
+							restored += 1;
+							journaled_at.insert(hash, *time);
+						},
+						Err(error) => {
+							debug!(
+								target: LOG_TARGET,
+								?error,
+								"Journaled transaction rejected by the pool"
+							);
+						},
+					}
+				},
+			Err(error) => {
+				warn!(target: LOG_TARGET, ?error, "Failed to resubmit journaled transactions");
+			},
+		}
+	}
+
+	info!(target: LOG_TARGET, journaled, restored, "Restored transactions from the journal");
+	journaled_at
+}
+
+/// The journal of the content of a pool.
+///
+/// The journal is written once more when dropped, so that the content of the pool at shutdown
+/// is persisted.
+struct Journal<Pool: TransactionPool> {
+	txpool: Arc<Pool>,
+	path: PathBuf,
+	/// The time at which every journaled transaction was journaled first.
+	journaled_at: HashMap<TxHash<Pool>, u64>,
+}
+
+impl<Pool: TransactionPool> Journal<Pool> {
+	/// Returns the entries of the current ready and future transactions of the pool.
+	///
+	/// `journaled_at` is updated to only contain these transactions.
+	fn entries(&mut self) -> Vec<JournalEntry> {
+		let now = now();
+		let mut entries = Vec::new();
+		let mut journaled = HashMap::new();
+
+		let mut journal = |tx: &Pool::InPoolTransaction| {
+			let time = self.journaled_at.get(tx.hash()).copied().unwrap_or(now);
+			journaled.insert(tx.hash().clone(), time);
+			entries.push(JournalEntry {
+				journaled_at: time,
+				source: tx.source(),
+				transaction: tx.data().encode(),
+			});
+		};
+		self.txpool.ready().for_each(|tx| journal(&tx));
+		self.txpool.futures().iter().for_each(journal);
+
+		self.journaled_at = journaled;
+		entries
+	}
+
+	/// Replaces the journal with the current ready and future transactions of the pool.
+	async fn rotate(&mut self) -> io::Result<()> {
+		let entries = self.entries();
+		let count = entries.len();
+		let path = self.path.clone();
+		blocking(move || write_journal(&path, &entries)).await?;
+		debug!(target: LOG_TARGET, count, "Transaction pool journal rotated");
+		Ok(())
+	}
+}
+
+impl<Pool: TransactionPool> Drop for Journal<Pool> {
+	fn drop(&mut self) {
+		let entries = self.entries();
+		match write_journal(&self.path, &entries) {
+			Ok(()) =>
+				debug!(target: LOG_TARGET, count = entries.len(), "Transaction pool journal flushed"),
+			Err(error) => warn!(
+				target: LOG_TARGET,
+				%error,
+				path = ?self.path,
+				"Failed to flush the transaction pool journal"
+			),
+		}
+	}
+}
+
+/// Resubmits the transactions of the journal at `path` to the pool, and then keeps the journal
+/// up to date with the content of the pool.
+pub async fn journal_future<Client, Pool, Block>(
+	client: Arc<Client>,
+	txpool: Arc<Pool>,
+	path: PathBuf,
+	options: JournalOptions,
+) where
+	Block: BlockT,
+	Client: HeaderBackend<Block>,
+	Pool: TransactionPool<Block = Block>,
+{
+	let journaled_at = restore(&*txpool, client.info().best_hash, &path, options.max_age).await;
+	// Only written from now on, the journal would otherwise lose the transactions not restored yet.
+	let mut journal = Journal { txpool, path, journaled_at };
+
+	loop {
+		futures_timer::Delay::new(options.interval).await;
+
+		if let Err(error) = journal.rotate().await {
+			warn!(
+				target: LOG_TARGET,
+				%error,
+				path = ?journal.path,
+				"Failed to write the transaction pool journal"
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::BasicPool;
+	use substrate_test_runtime_client::Sr25519Keyring::Alice;
+	use substrate_test_runtime_transaction_pool::{uxt, TestApi};
+
+	fn entry(journaled_at: u64, source: TransactionSource) -> JournalEntry {
+		JournalEntry { journaled_at, source, transaction: journaled_at.encode() }
+	}
+
+	#[test]
+	fn journal_roundtrip() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("txpool").join("journal");
+
+		// A missing journal is empty.
+		assert_eq!(read_journal(&path).unwrap(), vec![]);
+
+		let entries =
+			vec![entry(1, TransactionSource::Local), entry(2, TransactionSource::External)];
+		write_journal(&path, &entries).unwrap();
+		assert_eq!(read_journal(&path).unwrap(), entries);
+
+		// The journal is replaced as a whole.
+		write_journal(&path, &entries[1..]).unwrap();
+		assert_eq!(read_journal(&path).unwrap(), entries[1..]);
+	}
+
+	#[test]
+	fn corrupted_journal_is_rejected() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("journal");
+
+		fs::write(&path, (JOURNAL_VERSION + 1, Vec::<JournalEntry>::new()).encode()).unwrap();
+		assert_eq!(read_journal(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+		fs::write(&path, [JOURNAL_VERSION, 4, 1]).unwrap();
+		assert_eq!(read_journal(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+	}
+
+	#[test]
+	fn old_entries_are_not_restored() {
+		let entries = vec![
+			entry(100, TransactionSource::Local),
+			entry(150, TransactionSource::External),
+			entry(200, TransactionSource::External),
+		];
+
+		assert_eq!(fresh_entries(entries.clone(), 200, Duration::from_secs(50)), entries[1..]);
+		assert_eq!(fresh_entries(entries.clone(), 300, Duration::from_secs(50)), vec![]);
+		assert_eq!(fresh_entries(entries.clone(), 200, Duration::from_secs(100)), entries);
+	}
+
+	#[tokio::test]
+	async fn journal_is_restored_into_a_fresh_pool() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("journal");
+		let api = Arc::new(TestApi::with_alice_nonce(209));
+		let genesis = api.genesis_hash();
+		let new_pool =
+			|| Arc::new(BasicPool::new_test(api.clone(), genesis, genesis, Default::default()).0);
+
+		let txpool = new_pool();
+		let xts = vec![uxt(Alice, 209), uxt(Alice, 210), uxt(Alice, 212)];
+		let results = txpool.submit_at(genesis, TransactionSource::Local, xts).await.unwrap();
+		assert!(results.iter().all(Result::is_ok));
+		// The journal is written when dropped.
+		drop(Journal { txpool, path: path.clone(), journaled_at: Default::default() });
+
+		let txpool = new_pool();
+		let journaled_at = restore(&*txpool, genesis, &path, Duration::from_secs(60)).await;
+		assert_eq!(journaled_at.len(), 3);
+		let status = txpool.status();
+		assert_eq!((status.ready, status.future), (2, 1));
+		assert!(txpool.ready().all(|tx| tx.source() == TransactionSource::Local));
+	}
+}
@@ -25,7 +25,7 @@ use super::{
 	multi_view_listener::MultiViewListener,
 	tx_mem_pool::{InsertionInfo, TxMemPool, TXMEMPOOL_TRANSACTION_LIMIT_MULTIPLIER},
 	view::View,
-	view_store::ViewStore,
+	view_store::{ViewStore, ViewStoreSubmitOutcome},
 };
 use crate::{
 	api::FullChainApi,
@@ -233,6 +233,7 @@ where
 			Default::default(),
 			mempool_max_transactions_count,
 			ready_limits.total_bytes + future_limits.total_bytes,
+			Default::default(),
 		));
 
 		let (dropped_stream_controller, dropped_stream) =
@@ -367,6 +368,7 @@ where
 			metrics.clone(),
 			TXMEMPOOL_TRANSACTION_LIMIT_MULTIPLIER * options.total_count(),
 			options.ready.total_bytes + options.future.total_bytes,
+			options.sender_limits.clone(),
 		));
 
 		let (dropped_stream_controller, dropped_stream) =
@@ -814,7 +816,7 @@ where
 			})
 			.map(|r| {
 				r.map(|r| {
-					mempool.update_transaction_priority(&r);
+					self.update_transaction(&r);
 					r.hash()
 				})
 			})
@@ -878,7 +880,7 @@ where
 				self.mempool.remove_transactions(&[insertion.hash]);
 			})
 			.map(|mut outcome| {
-				self.mempool.update_transaction_priority(&outcome);
+				self.update_transaction(&outcome);
 				outcome.expect_watcher()
 			})
 	}
@@ -1042,7 +1044,7 @@ where
 				self.mempool.remove_transactions(&[insertion.hash]);
 			})
 			.map(|outcome| {
-				self.mempool.update_transaction_priority(&outcome);
+				self.update_transaction(&outcome);
 				outcome.hash()
 			})
 			.or_else(|_| Ok(insertion.hash))
@@ -1261,6 +1263,7 @@ where
 			duration= ?start.elapsed(),
 			"update_view_with_mempool"
 		);
+		self.mempool.update_senders_queues(&view);
 		let view = Arc::from(view);
 		self.view_store.insert_new_view(view.clone(), tree_route).await;
 		Some(view)
@@ -1725,17 +1728,45 @@ where
 				new_tx_hash = ?tx_hash,
 				"removed: replaced by"
 			);
+		}
+		self.drop_enforced_by_limits(&insertion_info.removed);
+
+		return Ok(insertion_info)
+	}
+
+	/// Updates the mempool with the outcome of the transaction submission to the view store.
+	///
+	/// The transactions exceeding the per-sender limits of the transaction's sender are dropped.
+	fn update_transaction(&self, outcome: &ViewStoreSubmitOutcome<ChainApi>) {
+		self.mempool.update_transaction_priority(outcome);
+
+		let removed = self.mempool.enforce_sender_limits(outcome.hash());
+		for tx_hash in &removed {
+			trace!(
+				target: LOG_TARGET,
+				?tx_hash,
+				new_tx_hash = ?outcome.hash(),
+				"removed: sender limits enforced by"
+			);
+		}
+		self.drop_enforced_by_limits(&removed);
+	}
+
+	/// Notifies the listeners that the given transactions, already removed from the mempool, were
+	/// dropped because of the pool limits, and removes them from all the views.
+	///
+	/// Transactions depending on the dropped ones are removed from the views too.
+	fn drop_enforced_by_limits(&self, tx_hashes: &[ExtrinsicHash<ChainApi>]) {
+		for tx_hash in tx_hashes {
 			self.view_store
 				.listener
-				.transaction_dropped(DroppedTransaction::new_enforced_by_limts(*worst_hash));
+				.transaction_dropped(DroppedTransaction::new_enforced_by_limts(*tx_hash));
 
 			self.view_store
-				.remove_transaction_subtree(*worst_hash, |listener, removed_tx_hash| {
+				.remove_transaction_subtree(*tx_hash, |listener, removed_tx_hash| {
 					listener.limits_enforced(&removed_tx_hash);
 				});
 		}
-
-		return Ok(insertion_info)
 	}
 }
 
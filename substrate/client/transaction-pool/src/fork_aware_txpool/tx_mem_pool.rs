@@ -51,13 +51,17 @@ use sp_runtime::{
 use crate::{
 	common::tracing_log_xt::log_xt_trace,
 	graph,
-	graph::{base_pool::TimedTransactionSource, tracked_map::Size, ExtrinsicFor, ExtrinsicHash},
+	graph::{
+		base_pool::TimedTransactionSource, tracked_map::Size, ExtrinsicFor, ExtrinsicHash,
+		SenderLimits,
+	},
 	LOG_TARGET,
 };
 
 use super::{
 	metrics::MetricsLink as PrometheusMetrics,
 	multi_view_listener::MultiViewListener,
+	view::View,
 	view_store::{ViewStore, ViewStoreSubmitOutcome},
 };
 
@@ -71,6 +75,15 @@ pub(crate) const TXMEMPOOL_MAX_REVALIDATION_BATCH_SIZE: usize = 1000;
 /// the view's total limit.
 pub const TXMEMPOOL_TRANSACTION_LIMIT_MULTIPLIER: usize = 4;
 
+/// The sender a transaction is attributed to, and the queue the transaction was imported to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TxSender {
+	/// The sender, see [`graph::base_pool::sender_of`].
+	key: Vec<u8>,
+	/// Was the transaction imported to the future queue.
+	is_future: bool,
+}
+
 /// Represents the transaction in the intermediary buffer.
 #[derive(Debug)]
 pub(crate) struct TxInMemPool<ChainApi, Block>
@@ -94,6 +107,9 @@ where
 	/// Priority of transaction at some block. It is assumed it will not be changed often. None if
 	/// not known.
 	priority: RwLock<Option<TransactionPriority>>,
+	/// The sender of transaction, as seen by the most recent view the transaction was submitted
+	/// to or found in. None if not known.
+	sender: RwLock<Option<TxSender>>,
 }
 
 impl<ChainApi, Block> TxInMemPool<ChainApi, Block>
@@ -154,6 +170,7 @@ where
 			validated_at: AtomicU64::new(0),
 			bytes,
 			priority: priority.into(),
+			sender: Default::default(),
 		}
 	}
 
@@ -173,6 +190,16 @@ where
 	pub(crate) fn priority(&self) -> Option<TransactionPriority> {
 		*self.priority.read()
 	}
+
+	/// Returns the sender of the transaction.
+	pub(crate) fn sender(&self) -> Option<TxSender> {
+		self.sender.read().clone()
+	}
+
+	/// Checks if the transaction is attributed to the given sender and queue.
+	fn has_sender(&self, sender: &TxSender) -> bool {
+		self.sender.read().as_ref() == Some(sender)
+	}
 }
 
 impl<ChainApi, Block> Size for Arc<TxInMemPool<ChainApi, Block>>
@@ -224,6 +251,9 @@ where
 
 	/// Maximal size of encodings of all transactions in the memory pool.
 	max_transactions_total_bytes: usize,
+
+	/// Limits of the number of transactions of a single sender.
+	sender_limits: SenderLimits,
 }
 
 /// Helper structure to encapsulate a result of [`TxMemPool::try_insert`].
@@ -257,6 +287,7 @@ where
 		metrics: PrometheusMetrics,
 		max_transactions_count: usize,
 		max_transactions_total_bytes: usize,
+		sender_limits: SenderLimits,
 	) -> Self {
 		Self {
 			api,
@@ -265,6 +296,7 @@ where
 			metrics,
 			max_transactions_count,
 			max_transactions_total_bytes,
+			sender_limits,
 		}
 	}
 
@@ -282,9 +314,17 @@ where
 			metrics: Default::default(),
 			max_transactions_count,
 			max_transactions_total_bytes,
+			sender_limits: Default::default(),
 		}
 	}
 
+	/// Sets the per-sender limits, for testing purposes.
+	#[cfg(test)]
+	fn with_sender_limits(mut self, sender_limits: SenderLimits) -> Self {
+		self.sender_limits = sender_limits;
+		self
+	}
+
 	/// Retrieves a transaction by its hash if it exists in the memory pool.
 	pub(super) fn get_by_hash(
 		&self,
@@ -353,8 +393,10 @@ where
 	/// Attempts to insert a new transaction in the memory pool and drop some worse existing
 	/// transactions.
 	///
-	/// A "worse" transaction means transaction with lower priority, or older transaction with the
-	/// same prio.
+	/// Only transactions with lower priority than the new one can be dropped. Among them, the
+	/// transactions of the senders having the most transactions in the memory pool are dropped
+	/// first, so a single sender cannot evict everyone else. A "worse" transaction of the same
+	/// sender means transaction with lower priority, or older transaction with the same prio.
 	///
 	/// This operation will not overflow the limit of the mempool. It means that cumulative
 	/// size of removed transactions will be equal (or greated) then size of newly inserted
@@ -381,35 +423,51 @@ where
 			return Err(sc_transaction_pool_api::error::Error::AlreadyImported(Box::new(hash)));
 		}
 
-		let mut sorted = transactions
-			.iter()
-			.filter_map(|(h, v)| v.priority().map(|_| (*h, v.clone())))
-			.collect::<Vec<_>>();
+		let mut sender_counts = HashMap::<Vec<u8>, usize>::new();
+		let mut candidates = Vec::new();
+		for (hash, tx) in transactions.iter() {
+			let sender = tx.sender().map(|sender| sender.key);
+			if let Some(key) = &sender {
+				*sender_counts.entry(key.clone()).or_default() += 1;
+			}
+			if tx.priority().is_some_and(|p| p < priority) {
+				candidates.push((*hash, tx.clone(), sender));
+			}
+		}
 
 		// When pushing higher prio transaction, we need to find a number of lower prio txs, such
 		// that the sum of their bytes is ge then size of new tx. Otherwise we could overflow size
-		// limits. Naive way to do it - rev-sort by priority and eat the tail.
-
-		// reverse (oldest, lowest prio last)
-		sorted.sort_by(|(_, a), (_, b)| match b.priority().cmp(&a.priority()) {
-			Ordering::Equal => match (a.source.timestamp, b.source.timestamp) {
-				(Some(a), Some(b)) => b.cmp(&a),
-				_ => Ordering::Equal,
-			},
-			ordering => ordering,
-		});
+		// limits. The worst transaction is picked again after every removal, as the removal
+		// changes the weight of its sender.
 
 		let mut total_size_removed = 0usize;
 		let mut to_be_removed = vec![];
 		let free_bytes = self.max_transactions_total_bytes - self.transactions.bytes();
 
 		loop {
-			let Some((worst_hash, worst_tx)) = sorted.pop() else {
+			// Transactions not attributed to any sender weigh as a sender of their own.
+			let weight =
+				|sender: &Option<Vec<u8>>| sender.as_ref().map_or(1, |key| sender_counts[key]);
+			let worst = candidates
+				.iter()
+				.enumerate()
+				.min_by(|(_, (_, a, a_sender)), (_, (_, b, b_sender))| {
+					weight(b_sender)
+						.cmp(&weight(a_sender))
+						.then_with(|| a.priority().cmp(&b.priority()))
+						.then_with(|| match (a.source.timestamp, b.source.timestamp) {
+							(Some(a), Some(b)) => a.cmp(&b),
+							_ => Ordering::Equal,
+						})
+				})
+				.map(|(index, _)| index);
+			let Some(worst) = worst else {
 				return Err(sc_transaction_pool_api::error::Error::ImmediatelyDropped);
 			};
 
-			if worst_tx.priority() >= new_tx.priority() {
-				return Err(sc_transaction_pool_api::error::Error::ImmediatelyDropped);
+			let (worst_hash, worst_tx, worst_sender) = candidates.swap_remove(worst);
+			if let Some(count) = worst_sender.and_then(|key| sender_counts.get_mut(&key)) {
+				*count -= 1;
 			}
 
 			total_size_removed += worst_tx.bytes;
@@ -617,15 +675,96 @@ where
 		);
 	}
 
-	/// Updates the priority of transaction stored in mempool using provided view_store submission
-	/// outcome.
+	/// Updates the priority and the sender of transaction stored in mempool using provided
+	/// view_store submission outcome.
 	pub(super) fn update_transaction_priority(&self, outcome: &ViewStoreSubmitOutcome<ChainApi>) {
-		outcome.priority().map(|priority| {
-			self.transactions
-				.write()
-				.get_mut(&outcome.hash())
-				.map(|p| *p.priority.write() = Some(priority))
+		let transactions = self.transactions.read();
+		let Some(tx) = transactions.get(&outcome.hash()) else { return };
+
+		if let Some(priority) = outcome.priority() {
+			*tx.priority.write() = Some(priority);
+		}
+		if let Some(key) = outcome.sender() {
+			*tx.sender.write() =
+				Some(TxSender { key: key.to_vec(), is_future: outcome.is_future() });
+		}
+	}
+
+	/// Updates the queues the transactions of known senders were imported to, as seen by the
+	/// given view.
+	///
+	/// Transactions move between the ready and future queues as blocks are imported, so the queues
+	/// recorded on submission are refreshed with every new view. Transactions not imported into the
+	/// view keep the queue they were last seen in.
+	pub(super) fn update_senders_queues(&self, view: &View<ChainApi>) {
+		let validated_pool = view.pool.validated_pool();
+		let transactions = self.transactions.read();
+		for (tx_hash, tx) in transactions.iter() {
+			let mut sender = tx.sender.write();
+			let Some(sender) = sender.as_mut() else { continue };
+			if validated_pool.ready_by_hash(tx_hash).is_some() {
+				sender.is_future = false;
+			} else if view.is_imported(tx_hash) {
+				sender.is_future = true;
+			}
+		}
+	}
+
+	/// Enforces the per-sender limits for the sender of the given transaction.
+	///
+	/// If the sender exceeds the limit of the queue the transaction was imported to, its
+	/// lowest-priority transactions of that queue are removed from the memory pool. Among the
+	/// transactions with the same priority, the newest ones are removed first.
+	///
+	/// Returns the hashes of the removed transactions.
+	pub(super) fn enforce_sender_limits(
+		&self,
+		tx_hash: ExtrinsicHash<ChainApi>,
+	) -> Vec<ExtrinsicHash<ChainApi>> {
+		let mut transactions = self.transactions.write();
+
+		let Some(sender) = transactions.get_mut(&tx_hash).and_then(|tx| tx.sender()) else {
+			return Vec::new()
+		};
+		let limit =
+			if sender.is_future { self.sender_limits.future } else { self.sender_limits.ready };
+		let Some(limit) = limit else { return Vec::new() };
+
+		let mut sender_transactions = transactions
+			.iter()
+			.filter(|(_, tx)| tx.has_sender(&sender))
+			.map(|(hash, tx)| (*hash, tx.clone()))
+			.collect::<Vec<_>>();
+		if sender_transactions.len() <= limit {
+			return Vec::new()
+		}
+
+		// best first (highest prio, oldest)
+		sender_transactions.sort_by(|(_, a), (_, b)| {
+			b.priority().cmp(&a.priority()).then_with(|| {
+				match (a.source.timestamp, b.source.timestamp) {
+					(Some(a), Some(b)) => a.cmp(&b),
+					_ => Ordering::Equal,
+				}
+			})
 		});
+
+		let removed = sender_transactions
+			.split_off(limit)
+			.into_iter()
+			.map(|(hash, _)| hash)
+			.collect::<Vec<_>>();
+		for hash in &removed {
+			transactions.remove(hash);
+		}
+
+		debug!(
+			target: LOG_TARGET,
+			?tx_hash,
+			removed = removed.len(),
+			"mempool::enforce_sender_limits"
+		);
+		removed
 	}
 
 	/// Counts the number of transactions in the provided iterator of hashes
@@ -993,4 +1132,71 @@ mod tx_mem_pool_tests {
 			sc_transaction_pool_api::error::Error::ImmediatelyDropped
 		));
 	}
+
+	#[test]
+	fn sender_limits_remove_lowest_prio_txs() {
+		sp_tracing::try_init_simple();
+		let api = Arc::from(TestApi::default());
+		let mempool = TxMemPool::new_test(api.clone(), usize::MAX, usize::MAX)
+			.with_sender_limits(SenderLimits { ready: Some(2), future: Some(1) });
+
+		let xts = (0..5).map(|x| Arc::from(uxt(x))).collect::<Vec<_>>();
+		let hashes = xts.iter().map(|xt| api.hash_and_length(xt).0).collect::<Vec<_>>();
+
+		let results = mempool.extend_unwatched(TransactionSource::External, &xts);
+		assert!(results.iter().all(Result::is_ok));
+
+		let update = |hash: H256, priority: u64, sender: &[u8], is_future: bool| {
+			mempool.update_transaction_priority(
+				&ViewStoreSubmitOutcome::new(hash, Some(priority))
+					.with_sender(Some(sender.to_vec()), is_future),
+			);
+			mempool.enforce_sender_limits(hash)
+		};
+
+		assert!(update(hashes[0], 20, b"alice", false).is_empty());
+		assert!(update(hashes[1], 10, b"alice", false).is_empty());
+		// the third ready tx of alice exceeds the limit, the lowest prio one is removed
+		assert_eq!(update(hashes[2], 30, b"alice", false), vec![hashes[1]]);
+		// future txs and txs of other senders are limited separately
+		assert!(update(hashes[3], 5, b"alice", true).is_empty());
+		assert!(update(hashes[4], 5, b"bob", false).is_empty());
+
+		assert_eq!(mempool.unwatched_and_watched_count(), (4, 0));
+		assert!(mempool.get_by_hash(hashes[1]).is_none());
+	}
+
+	#[test]
+	fn replacing_txs_prefers_txs_of_heaviest_sender() {
+		sp_tracing::try_init_simple();
+		const COUNT: usize = 10;
+		let api = Arc::from(TestApi::default());
+		let mempool = TxMemPool::new_test(api.clone(), usize::MAX, COUNT * LARGE_XT_SIZE);
+
+		let xts = (0..COUNT).map(|x| Arc::from(large_uxt(x))).collect::<Vec<_>>();
+		let hashes = xts.iter().map(|xt| api.hash_and_length(xt).0).collect::<Vec<_>>();
+
+		let results = mempool.extend_unwatched(TransactionSource::External, &xts);
+		assert!(results.iter().all(Result::is_ok));
+
+		// alice owns half of the pool with higher prio txs, other txs are sent by distinct senders
+		hashes.iter().enumerate().for_each(|(i, hash)| {
+			let (priority, sender) =
+				if i < COUNT / 2 { (50, b"alice".to_vec()) } else { (10, vec![i as u8]) };
+			mempool.update_transaction_priority(
+				&ViewStoreSubmitOutcome::new(*hash, Some(priority))
+					.with_sender(Some(sender), false),
+			);
+		});
+
+		let xt = Arc::from(large_uxt(98));
+		let result = mempool
+			.try_insert_with_replacement(xt, 100, TransactionSource::External, false)
+			.unwrap();
+
+		// one of alice's txs is removed, even though lower prio txs of other senders exist
+		assert_eq!(result.removed.len(), 1);
+		assert!(hashes[..COUNT / 2].contains(&result.removed[0]));
+		assert_eq!(mempool.unwatched_and_watched_count(), (COUNT, 0));
+	}
 }
@@ -188,6 +188,7 @@ impl<ChainApi: graph::ChainApi> From<ValidatedPoolSubmitOutcome<ChainApi>>
 {
 	fn from(value: ValidatedPoolSubmitOutcome<ChainApi>) -> Self {
 		Self::new(value.hash(), value.priority())
+			.with_sender(value.sender().map(<[u8]>::to_vec), value.is_future())
 	}
 }
 
@@ -72,6 +72,29 @@ impl<Hash, Ex> Imported<Hash, Ex> {
 	}
 }
 
+/// Width of the encoded nonce at the end of the `(account, nonce)` tags, see [`sender_of`].
+const NONCE_LEN: usize = core::mem::size_of::<u32>();
+
+/// Widths of the encoded account ids transactions are attributed to, see [`sender_of`].
+///
+/// These are the widths of `AccountId32` and `AccountId20`.
+const ACCOUNT_ID_LENS: [usize; 2] = [32, 20];
+
+/// Returns the sender a transaction providing given tags is attributed to.
+///
+/// Nonce-based transaction ordering makes every signed transaction provide an `(account, nonce)`
+/// tag, the nonce being encoded as a little-endian `u32`. The sender is the first provided tag
+/// without its nonce, so it is known for the transactions which do not require any tag as well.
+///
+/// Transactions whose first provided tag does not have the width of a known account id followed
+/// by the nonce are not attributed to any sender. This is the case for unsigned transactions and
+/// for runtimes using other tag layouts, whose tags would otherwise be mistaken for senders.
+pub fn sender_of(provides: &[Tag]) -> Option<Vec<u8>> {
+	let provided = provides.first()?;
+	let account_len = provided.len().checked_sub(NONCE_LEN)?;
+	ACCOUNT_ID_LENS.contains(&account_len).then(|| provided[..account_len].to_vec())
+}
+
 /// Status of pruning the queue.
 #[derive(Debug)]
 pub struct PruneStatus<Hash, Ex> {
@@ -181,6 +204,10 @@ impl<Hash, Extrinsic> InPoolTransaction for Transaction<Hash, Extrinsic> {
 	fn is_propagable(&self) -> bool {
 		self.propagate
 	}
+
+	fn source(&self) -> TransactionSource {
+		self.source.source
+	}
 }
 
 impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
@@ -633,6 +660,7 @@ impl Limit {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use codec::Encode;
 
 	type Hash = u64;
 
@@ -1274,4 +1302,21 @@ source: TimedTransactionSource { source: TransactionSource::External, timestamp:
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn sender_of_works() {
+		let tag = |account: u8, nonce: u32| ([account; 32], nonce).encode();
+
+		assert_eq!(sender_of(&[tag(1, 5)]), Some(vec![1; 32]));
+		assert_eq!(sender_of(&[tag(1, 256)]), Some(vec![1; 32]));
+		assert_eq!(sender_of(&[([1u8; 20], 5u32).encode()]), Some(vec![1; 20]));
+		// transactions without requirements are attributed to their sender as well
+		assert_eq!(sender_of(&[tag(1, 0)]), sender_of(&[tag(1, 1)]));
+		assert_ne!(sender_of(&[tag(1, 5)]), sender_of(&[tag(2, 5)]));
+		// tags not matching the `(account, u32)` layout are not attributed to any sender
+		assert_eq!(sender_of(&[([1u8; 32], 5u64).encode()]), None);
+		assert_eq!(sender_of(&[(1u8, 5u32).encode()]), None);
+		assert_eq!(sender_of(&[vec![1, 2, 3, 4]]), None);
+		assert_eq!(sender_of(&[]), None);
+	}
 }
@@ -39,7 +39,7 @@ pub mod watcher;
 
 pub use self::pool::{
 	BlockHash, ChainApi, ExtrinsicFor, ExtrinsicHash, NumberFor, Options, Pool, RawExtrinsicFor,
-	TransactionFor, ValidatedTransactionFor,
+	SenderLimits, TransactionFor, ValidatedTransactionFor,
 };
 pub use validated_pool::{
 	BaseSubmitOutcome, EventDispatcher, IsValidator, ValidatedPoolSubmitOutcome,
@@ -135,6 +135,18 @@ pub trait ChainApi: Send + Sync {
 	}
 }
 
+/// Limits of the number of transactions a single sender can have in the pool.
+///
+/// See [`base::sender_of`] for how transactions are attributed to senders. Only enforced by the
+/// fork-aware transaction pool.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SenderLimits {
+	/// Maximal number of ready transactions of a single sender. `None` if unlimited.
+	pub ready: Option<usize>,
+	/// Maximal number of future transactions of a single sender. `None` if unlimited.
+	pub future: Option<usize>,
+}
+
 /// Pool configuration options.
 #[derive(Debug, Clone)]
 pub struct Options {
@@ -142,6 +154,8 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Per-sender limits.
+	pub sender_limits: SenderLimits,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
 	/// How long the extrinsic is banned for.
@@ -153,6 +167,7 @@ impl Default for Options {
 		Self {
 			ready: base::Limit { count: 8192, total_bytes: 20 * 1024 * 1024 },
 			future: base::Limit { count: 512, total_bytes: 1 * 1024 * 1024 },
+			sender_limits: Default::default(),
 			reject_future_transactions: false,
 			ban_time: Duration::from_secs(60 * 30),
 		}
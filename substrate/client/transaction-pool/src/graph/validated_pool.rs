@@ -121,6 +121,12 @@ pub struct BaseSubmitOutcome<B: ChainApi, W> {
 
 	/// The priority of the transaction. Defaults to None if unknown.
 	priority: Option<TransactionPriority>,
+
+	/// The sender the transaction is attributed to, see [`base::sender_of`]. None if unknown.
+	sender: Option<Vec<u8>>,
+
+	/// Was the transaction imported to the future queue.
+	is_future: bool,
 }
 
 /// Type alias to outcome of submission to `ValidatedPool`.
@@ -130,7 +136,14 @@ pub type ValidatedPoolSubmitOutcome<B> =
 impl<B: ChainApi, W> BaseSubmitOutcome<B, W> {
 	/// Creates a new instance with given hash and priority.
 	pub fn new(hash: ExtrinsicHash<B>, priority: Option<TransactionPriority>) -> Self {
-		Self { hash, priority, watcher: None }
+		Self { hash, priority, watcher: None, sender: None, is_future: false }
+	}
+
+	/// Sets the sender of the transaction and the queue it was imported to.
+	pub fn with_sender(mut self, sender: Option<Vec<u8>>, is_future: bool) -> Self {
+		self.sender = sender;
+		self.is_future = is_future;
+		self
 	}
 
 	/// Sets the transaction watcher.
@@ -149,6 +162,16 @@ impl<B: ChainApi, W> BaseSubmitOutcome<B, W> {
 		self.hash
 	}
 
+	/// Provides the sender of submitted transaction.
+	pub fn sender(&self) -> Option<&[u8]> {
+		self.sender.as_deref()
+	}
+
+	/// Was the submitted transaction imported to the future queue.
+	pub fn is_future(&self) -> bool {
+		self.is_future
+	}
+
 	/// Provides a watcher. Should only be called on outcomes of `submit_and_watch`. Otherwise will
 	/// panic (that would mean logical error in program).
 	pub fn expect_watcher(&mut self) -> W {
@@ -315,6 +338,7 @@ impl<B: ChainApi, L: EventHandler<B>> ValidatedPool<B, L> {
 		match tx {
 			ValidatedTransaction::Valid(tx) => {
 				let priority = tx.priority;
+				let sender = base::sender_of(&tx.provides);
 				trace!(
 					target: LOG_TARGET,
 					tx_hash = ?tx.hash,
@@ -346,7 +370,9 @@ impl<B: ChainApi, L: EventHandler<B>> ValidatedPool<B, L> {
 
 				let mut event_dispatcher = self.event_dispatcher.write();
 				fire_events(&mut *event_dispatcher, &imported);
-				Ok(ValidatedPoolSubmitOutcome::new(*imported.hash(), Some(priority)))
+				let is_future = matches!(imported, base::Imported::Future { .. });
+				Ok(ValidatedPoolSubmitOutcome::new(*imported.hash(), Some(priority))
+					.with_sender(sender, is_future))
 			},
 			ValidatedTransaction::Invalid(tx_hash, error) => {
 				trace!(
@@ -34,11 +34,14 @@ use std::sync::Arc;
 
 pub use api::FullChainApi;
 pub use builder::{Builder, TransactionPoolHandle, TransactionPoolOptions, TransactionPoolType};
-pub use common::notification_future;
+pub use common::{
+	journal::{journal_future, JournalOptions},
+	notification_future,
+};
 pub use fork_aware_txpool::{ForkAwareTxPool, ForkAwareTxPoolTask};
 pub use graph::{
 	base_pool::{Limit as PoolLimit, TimedTransactionSource},
-	ChainApi, Options, Pool,
+	ChainApi, Options, Pool, SenderLimits,
 };
 use single_state_txpool::prune_known_txs_for_block;
 pub use single_state_txpool::{BasicPool, RevalidationType};
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS blocks (
+	block_number INTEGER NOT NULL PRIMARY KEY,
+	block_hash BLOB NOT NULL,
+	finalized INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_block_hash ON blocks (
+	block_hash
+);
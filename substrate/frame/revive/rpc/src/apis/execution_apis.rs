@@ -43,6 +43,14 @@ pub trait EthRpc {
 	#[method(name = "eth_chainId")]
 	async fn chain_id(&self) -> RpcResult<U256>;
 
+	/// Generates an access list for a transaction.
+	#[method(name = "eth_createAccessList")]
+	async fn create_access_list(
+		&self,
+		transaction: GenericTransaction,
+		block: Option<BlockNumberOrTagOrHash>,
+	) -> RpcResult<AccessListResult>;
+
 	/// Generates and returns an estimate of how much gas is necessary to allow the transaction to
 	/// complete.
 	#[method(name = "eth_estimateGas")]
@@ -95,10 +103,31 @@ pub trait EthRpc {
 	#[method(name = "eth_getCode")]
 	async fn get_code(&self, address: Address, block: BlockNumberOrTagOrHash) -> RpcResult<Bytes>;
 
+	/// Returns a list of all logs based on filter ID since the last log retrieval.
+	#[method(name = "eth_getFilterChanges")]
+	async fn get_filter_changes(&self, filter_id: U256) -> RpcResult<FilterResults>;
+
+	/// Returns a list of all logs based on filter ID.
+	#[method(name = "eth_getFilterLogs")]
+	async fn get_filter_logs(&self, filter_id: U256) -> RpcResult<FilterResults>;
+
 	/// Returns an array of all logs matching filter with given id.
 	#[method(name = "eth_getLogs")]
 	async fn get_logs(&self, filter: Option<Filter>) -> RpcResult<FilterResults>;
 
+	/// Returns the Merkle proof of the given account and storage slots.
+	///
+	/// See [`crate::proof`] for the layout of the proof and how to verify it. At most
+	/// [`MAX_PROOF_STORAGE_KEYS`](crate::client::MAX_PROOF_STORAGE_KEYS) storage keys are proven at
+	/// once.
+	#[method(name = "eth_getProof")]
+	async fn get_proof(
+		&self,
+		address: Address,
+		storage_keys: Vec<U256>,
+		block: BlockNumberOrTagOrHash,
+	) -> RpcResult<AccountProof>;
+
 	/// Returns the value from a storage position at a given address.
 	#[method(name = "eth_getStorageAt")]
 	async fn get_storage_at(
@@ -150,6 +179,18 @@ pub trait EthRpc {
 	#[method(name = "eth_maxPriorityFeePerGas")]
 	async fn max_priority_fee_per_gas(&self) -> RpcResult<U256>;
 
+	/// Creates a filter object, based on filter options, to notify when the state changes (logs).
+	#[method(name = "eth_newFilter")]
+	async fn new_filter(&self, filter: Filter) -> RpcResult<U256>;
+
+	/// Creates a filter in the node, to notify when a new block arrives.
+	#[method(name = "eth_newBlockFilter")]
+	async fn new_block_filter(&self) -> RpcResult<U256>;
+
+	/// Creates a filter in the node, to notify when new pending transactions arrive.
+	#[method(name = "eth_newPendingTransactionFilter")]
+	async fn new_pending_transaction_filter(&self) -> RpcResult<U256>;
+
 	/// Submits a raw transaction. For EIP-4844 transactions, the raw form must be the network form.
 	/// This means it includes the blobs, KZG commitments, and KZG proofs.
 	#[method(name = "eth_sendRawTransaction")]
@@ -163,6 +204,10 @@ pub trait EthRpc {
 	#[method(name = "eth_syncing")]
 	async fn syncing(&self) -> RpcResult<SyncingStatus>;
 
+	/// Uninstalls a filter with given id.
+	#[method(name = "eth_uninstallFilter")]
+	async fn uninstall_filter(&self, filter_id: U256) -> RpcResult<bool>;
+
 	/// Returns true when the client is actively listening for network connections, otherwise false
 	#[method(name = "net_listening")]
 	async fn net_listening(&self) -> RpcResult<bool>;
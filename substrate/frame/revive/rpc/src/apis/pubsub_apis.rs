@@ -0,0 +1,155 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::*;
+use jsonrpsee::{
+	core::SubscriptionResult, proc_macros::rpc, PendingSubscriptionSink, SubscriptionMessage,
+	SubscriptionSink,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// The kind of notifications requested with `eth_subscribe`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+	/// New best block headers.
+	NewHeads,
+	/// Logs included in new best blocks, matching the given filter.
+	Logs,
+	/// Hashes of the transactions submitted through this server.
+	NewPendingTransactions,
+}
+
+/// An item sent to an `eth_subscribe` subscriber.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SubscriptionItem {
+	/// A new block header.
+	Header(Block),
+	/// A log matching the subscription filter.
+	Log(Log),
+	/// A pending transaction hash.
+	TransactionHash(H256),
+}
+
+/// Ethereum publish-subscribe JSON-RPC apis.
+#[rpc(server, client)]
+pub trait EthPubSubRpc {
+	/// Subscribe to new headers, logs or pending transactions.
+	///
+	/// ## References
+	///
+	/// - <https://geth.ethereum.org/docs/interacting-with-geth/rpc/pubsub>
+	#[subscription(
+		name = "eth_subscribe" => "eth_subscription",
+		unsubscribe = "eth_unsubscribe",
+		item = SubscriptionItem
+	)]
+	async fn subscribe(&self, kind: SubscriptionKind, filter: Option<Filter>)
+		-> SubscriptionResult;
+}
+
+pub struct EthPubSubRpcServerImpl {
+	client: client::Client,
+}
+
+impl EthPubSubRpcServerImpl {
+	pub fn new(client: client::Client) -> Self {
+		Self { client }
+	}
+}
+
+/// Forward the notifications received on `rx` to the subscription sink, until either the
+/// subscription is closed or the notification channel is dropped.
+async fn pipe_notifications<T: Clone>(
+	sink: SubscriptionSink,
+	mut rx: Receiver<T>,
+	to_items: impl Fn(T) -> Vec<SubscriptionItem>,
+) -> SubscriptionResult {
+	loop {
+		let notification = tokio::select! {
+			_ = sink.closed() => return Ok(()),
+			notification = rx.recv() => notification,
+		};
+
+		match notification {
+			Ok(notification) =>
+				for item in to_items(notification) {
+					sink.send(SubscriptionMessage::from_json(&item)?).await?;
+				},
+			Err(RecvError::Lagged(skipped)) => {
+				log::warn!(target: LOG_TARGET, "Subscriber lagged behind, skipped {skipped} notifications");
+			},
+			Err(RecvError::Closed) => return Ok(()),
+		}
+	}
+}
+
+#[async_trait]
+impl EthPubSubRpcServer for EthPubSubRpcServerImpl {
+	async fn subscribe(
+		&self,
+		pending: PendingSubscriptionSink,
+		kind: SubscriptionKind,
+		filter: Option<Filter>,
+	) -> SubscriptionResult {
+		match kind {
+			SubscriptionKind::NewHeads => {
+				let rx = self.client.subscribe_block_notifications();
+				let sink = pending.accept().await?;
+				pipe_notifications(sink, rx, |notification| {
+					vec![SubscriptionItem::Header(notification.block.clone())]
+				})
+				.await
+			},
+			SubscriptionKind::Logs => {
+				let filter = filter.unwrap_or_default();
+				if filter.block_hash.is_some() {
+					pending.reject(EthRpcError::InvalidSubscriptionFilter).await;
+					return Ok(());
+				}
+
+				let rx = self.client.subscribe_block_notifications();
+				let sink = pending.accept().await?;
+				pipe_notifications(sink, rx, |notification| {
+					let block = &notification.block;
+					let removed_logs = notification.removed_logs.iter().filter(|log| {
+						filter_matches_block(&filter, &log.block_hash, log.block_number)
+					});
+					let logs = notification
+						.logs
+						.iter()
+						.filter(|_| filter_matches_block(&filter, &block.hash, block.number));
+
+					removed_logs
+						.chain(logs)
+						.filter(|log| filter_matches_log(&filter, log))
+						.cloned()
+						.map(SubscriptionItem::Log)
+						.collect()
+				})
+				.await
+			},
+			SubscriptionKind::NewPendingTransactions => {
+				let rx = self.client.subscribe_pending_transactions();
+				let sink = pending.accept().await?;
+				pipe_notifications(sink, rx, |hash| vec![SubscriptionItem::TransactionHash(hash)])
+					.await
+			},
+		}
+	}
+}
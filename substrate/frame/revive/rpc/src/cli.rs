@@ -17,9 +17,9 @@
 //! The Ethereum JSON-RPC server.
 use crate::{
 	client::{connect, Client, SubscriptionType, SubstrateBlockNumber},
-	DebugRpcServer, DebugRpcServerImpl, EthRpcServer, EthRpcServerImpl, ReceiptExtractor,
-	ReceiptProvider, SubxtBlockInfoProvider, SystemHealthRpcServer, SystemHealthRpcServerImpl,
-	LOG_TARGET,
+	DebugRpcServer, DebugRpcServerImpl, EthPubSubRpcServer, EthPubSubRpcServerImpl, EthRpcServer,
+	EthRpcServerImpl, ReceiptExtractor, ReceiptProvider, SubxtBlockInfoProvider,
+	SystemHealthRpcServer, SystemHealthRpcServerImpl, LOG_TARGET,
 };
 use clap::Parser;
 use futures::{pin_mut, FutureExt};
@@ -64,6 +64,11 @@ pub struct CliCommand {
 	#[clap(long)]
 	pub index_last_n_blocks: Option<SubstrateBlockNumber>,
 
+	/// If provided, roll back the blocks indexed from the given block number, and index them
+	/// again on startup.
+	#[clap(long)]
+	pub reindex_from_block: Option<SubstrateBlockNumber>,
+
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub shared_params: SharedParams,
@@ -154,6 +159,7 @@ pub fn run(cmd: CliCommand) -> anyhow::Result<()> {
 		database_url,
 		earliest_receipt_block,
 		index_last_n_blocks,
+		reindex_from_block,
 		shared_params,
 		..
 	} = cmd;
@@ -219,6 +225,13 @@ pub fn run(cmd: CliCommand) -> anyhow::Result<()> {
 	task_manager
 		.spawn_essential_handle()
 		.spawn("block-subscription", None, async move {
+			// Re-index before subscribing, so that new blocks are not rolled back.
+			if let Some(from) = reindex_from_block {
+				if let Err(err) = client.reindex_blocks(from).await {
+					panic!("Block re-indexing failed: {err:?}",)
+				}
+			}
+
 			let fut1 = client.subscribe_and_cache_new_blocks(SubscriptionType::BestBlocks);
 			let fut2 = client.subscribe_and_cache_new_blocks(SubscriptionType::FinalizedBlocks);
 
@@ -246,11 +259,13 @@ fn rpc_module(is_dev: bool, client: Client) -> Result<RpcModule<()>, sc_service:
 		.with_accounts(if is_dev { vec![crate::Account::default()] } else { vec![] })
 		.into_rpc();
 
+	let pubsub_api = EthPubSubRpcServerImpl::new(client.clone()).into_rpc();
 	let health_api = SystemHealthRpcServerImpl::new(client.clone()).into_rpc();
 	let debug_api = DebugRpcServerImpl::new(client).into_rpc();
 
 	let mut module = RpcModule::new(());
 	module.merge(eth_api).map_err(|e| sc_service::Error::Application(e.into()))?;
+	module.merge(pubsub_api).map_err(|e| sc_service::Error::Application(e.into()))?;
 	module.merge(health_api).map_err(|e| sc_service::Error::Application(e.into()))?;
 	module.merge(debug_api).map_err(|e| sc_service::Error::Application(e.into()))?;
 	Ok(module)
@@ -24,10 +24,15 @@ use runtime_api::RuntimeApi;
 use storage_api::StorageApi;
 
 use crate::{
+	proof::{
+		child_trie_root_key, empty_code_hash, empty_storage_hash, fallback_account_id,
+		storage_slot_key, storage_value, StoragePrefixes,
+	},
 	subxt_client::{self, revive::calls::types::EthTransact, SrcChainConfig},
-	BlockInfoProvider, BlockTag, FeeHistoryProvider, ReceiptProvider, SubxtBlockInfoProvider,
-	TracerType, TransactionInfo, LOG_TARGET,
+	BlockInfoProvider, BlockNotification, BlockTag, FeeHistoryProvider, FilterProvider,
+	ReceiptProvider, SubxtBlockInfoProvider, TracerType, TransactionInfo, LOG_TARGET,
 };
+use futures::{stream, StreamExt, TryStreamExt};
 use jsonrpsee::{
 	core::traits::ToRpcParams,
 	rpc_params,
@@ -35,12 +40,15 @@ use jsonrpsee::{
 };
 use pallet_revive::{
 	evm::{
-		decode_revert_reason, Block, BlockNumberOrTag, BlockNumberOrTagOrHash, FeeHistoryResult,
-		Filter, GenericTransaction, Log, ReceiptInfo, SyncingProgress, SyncingStatus, Trace,
-		TransactionSigned, TransactionTrace, H256, U256,
+		decode_revert_reason, AccountProof, Block, BlockNumberOrTag, BlockNumberOrTagOrHash, Bytes,
+		FeeHistoryResult, Filter, FilterResults, GenericTransaction, Log, ReceiptInfo,
+		StorageProof, SyncingProgress, SyncingStatus, Trace, TransactionSigned, TransactionTrace,
+		H160, H256, U256,
 	},
 	EthTransactError,
 };
+use sc_rpc_api::state::ReadProof;
+use sp_crypto_hashing::twox_128;
 use sp_runtime::traits::Block as BlockT;
 use sp_weights::Weight;
 use std::{ops::Range, sync::Arc, time::Duration};
@@ -53,9 +61,11 @@ use subxt::{
 		},
 	},
 	config::Header,
+	error::MetadataError,
 	Config, OnlineClient,
 };
 use thiserror::Error;
+use tokio::sync::broadcast;
 
 /// The substrate block type.
 pub type SubstrateBlock = subxt::blocks::Block<SrcChainConfig, OnlineClient<SrcChainConfig>>;
@@ -81,6 +91,12 @@ pub enum SubscriptionType {
 	FinalizedBlocks,
 }
 
+/// The maximum number of storage keys proven by a single `eth_getProof` call.
+pub const MAX_PROOF_STORAGE_KEYS: usize = 1024;
+
+/// The number of storage keys whose value and proof are fetched concurrently.
+const PROOF_FETCH_CONCURRENCY: usize = 16;
+
 /// The error type for the client.
 #[derive(Error, Debug)]
 pub enum ClientError {
@@ -124,9 +140,16 @@ pub enum ClientError {
 	/// Failed to filter logs.
 	#[error("Failed to filter logs")]
 	LogFilterFailed(#[from] anyhow::Error),
+	/// Too many filters are installed.
+	#[error("Too many filters installed")]
+	TooManyFilters,
+	/// Too many storage keys are requested in a proof.
+	#[error("Too many storage keys, at most {} can be proven at once", MAX_PROOF_STORAGE_KEYS)]
+	TooManyStorageKeys,
 }
 
 const REVERT_CODE: i32 = 3;
+
 impl From<ClientError> for ErrorObjectOwned {
 	fn from(err: ClientError) -> Self {
 		match err {
@@ -161,6 +184,7 @@ pub struct Client {
 	receipt_provider: ReceiptProvider,
 	block_provider: SubxtBlockInfoProvider,
 	fee_history_provider: FeeHistoryProvider,
+	filter_provider: FilterProvider,
 	chain_id: u64,
 	max_block_weight: Weight,
 }
@@ -228,6 +252,7 @@ impl Client {
 			receipt_provider,
 			block_provider,
 			fee_history_provider: FeeHistoryProvider::default(),
+			filter_provider: FilterProvider::default(),
 			chain_id,
 			max_block_weight,
 		})
@@ -325,14 +350,22 @@ impl Client {
 	) -> Result<(), ClientError> {
 		log::info!(target: LOG_TARGET, "🔌 Subscribing to new blocks ({subscription_type:?})");
 		self.subscribe_new_blocks(subscription_type, |block| async {
-			let (signed_txs, receipts): (Vec<_>, Vec<_>) =
-				self.receipt_provider.insert_block_receipts(&block).await?.into_iter().unzip();
+			let (receipts, retracted) = self
+				.receipt_provider
+				.insert_canonical_block_receipts(&block, subscription_type)
+				.await?;
+			let (signed_txs, receipts): (Vec<_>, Vec<_>) = receipts.into_iter().unzip();
 
 			let evm_block =
 				self.evm_block_from_receipts(&block, &receipts, signed_txs, false).await;
 			self.block_provider.update_latest(block, subscription_type).await;
 
 			self.fee_history_provider.update_fee_history(&evm_block, &receipts).await;
+
+			if matches!(subscription_type, SubscriptionType::BestBlocks) {
+				let logs = receipts.into_iter().flat_map(|receipt| receipt.logs).collect();
+				self.filter_provider.notify_block(evm_block, logs, &retracted).await;
+			}
 			Ok(())
 		})
 		.await
@@ -356,6 +389,26 @@ impl Client {
 		Ok(())
 	}
 
+	/// Roll back the blocks indexed from the given block number, and index them again up to the
+	/// latest block.
+	///
+	/// The block subscriptions can't update the canonical chain in the meantime.
+	pub async fn reindex_blocks(&self, from: SubstrateBlockNumber) -> Result<(), ClientError> {
+		let _lock = self.receipt_provider.lock_canonical_chain().await;
+		let last = self.latest_block().await.number();
+		let range = from..last;
+		log::info!(target: LOG_TARGET, "🗄️ Re-indexing blocks in range {range:?}");
+		self.receipt_provider.retract_from(from).await?;
+		self.subscribe_past_blocks(range, |block| async move {
+			self.receipt_provider.insert_block_receipts(&block).await?;
+			Ok(())
+		})
+		.await?;
+
+		log::info!(target: LOG_TARGET, "🗄️ Finished re-indexing blocks");
+		Ok(())
+	}
+
 	/// Get the block hash for the given block number or tag.
 	pub async fn block_hash_for_tag(
 		&self,
@@ -692,6 +745,150 @@ impl Client {
 		Ok(logs)
 	}
 
+	/// Get a read proof of the given main trie keys at the given block.
+	async fn read_proof(
+		&self,
+		keys: Vec<Vec<u8>>,
+		block_hash: H256,
+	) -> Result<Vec<Bytes>, ClientError> {
+		let client = RpcClient::new(self.rpc_client.clone());
+		let keys: Vec<Bytes> = keys.into_iter().map(Bytes).collect();
+		let read_proof: ReadProof<H256> = client
+			.request("state_getReadProof", subxt::backend::rpc::rpc_params![keys, block_hash])
+			.await?;
+		Ok(read_proof.proof.into_iter().map(|node| Bytes(node.0)).collect())
+	}
+
+	/// Get a read proof of the given child trie key at the given block.
+	async fn child_read_proof(
+		&self,
+		trie_id: &[u8],
+		key: Vec<u8>,
+		block_hash: H256,
+	) -> Result<Vec<Bytes>, ClientError> {
+		let client = RpcClient::new(self.rpc_client.clone());
+		let child_storage_key = Bytes(child_trie_root_key(trie_id));
+		let keys = vec![Bytes(key)];
+		let read_proof: ReadProof<H256> = client
+			.request(
+				"state_getChildReadProof",
+				subxt::backend::rpc::rpc_params![child_storage_key, keys, block_hash],
+			)
+			.await?;
+		Ok(read_proof.proof.into_iter().map(|node| Bytes(node.0)).collect())
+	}
+
+	/// The prefix of the storage map `entry` of `pallet`, as found in the runtime metadata.
+	fn storage_prefix(&self, pallet: &str, entry: &str) -> Result<Vec<u8>, MetadataError> {
+		let metadata = self.api.metadata();
+		let storage = metadata
+			.pallet_by_name_err(pallet)?
+			.storage()
+			.ok_or_else(|| MetadataError::StorageNotFoundInPallet(pallet.to_owned()))?;
+		let entry = storage
+			.entry_by_name(entry)
+			.ok_or_else(|| MetadataError::StorageEntryNotFound(entry.to_owned()))?;
+		Ok([twox_128(storage.prefix().as_bytes()), twox_128(entry.name().as_bytes())].concat())
+	}
+
+	/// The prefixes of the storage maps covered by the proofs of [`Self::get_proof`].
+	pub fn storage_prefixes(&self) -> Result<StoragePrefixes, ClientError> {
+		let prefix = |pallet, entry| self.storage_prefix(pallet, entry).map_err(subxt::Error::from);
+		Ok(StoragePrefixes {
+			system_account: prefix("System", "Account")?,
+			original_account: prefix("Revive", "OriginalAccount")?,
+			contract_info: prefix("Revive", "ContractInfoOf")?,
+		})
+	}
+
+	/// Get the Merkle proof of the given account and storage slots at the given block.
+	///
+	/// See [`crate::proof`] for a description of the proof and how to verify it.
+	pub async fn get_proof(
+		&self,
+		address: H160,
+		storage_keys: Vec<U256>,
+		block_hash: H256,
+	) -> Result<AccountProof, ClientError> {
+		if storage_keys.len() > MAX_PROOF_STORAGE_KEYS {
+			return Err(ClientError::TooManyStorageKeys);
+		}
+
+		let prefixes = self.storage_prefixes()?;
+		let runtime_api = self.runtime_api(block_hash);
+		let balance = runtime_api.balance(address).await?;
+		let nonce = runtime_api.nonce(address).await?;
+
+		let storage_api = self.storage_api(block_hash);
+		let account_id = storage_api
+			.get_original_account(&address)
+			.await?
+			.unwrap_or_else(|| fallback_account_id(&address));
+		let contract_info = match storage_api.get_contract_info(&address).await {
+			Ok(info) => Some(info),
+			Err(ClientError::ContractNotFound) => None,
+			Err(err) => return Err(err),
+		};
+
+		let mut keys = vec![
+			prefixes.system_account_key(&account_id),
+			prefixes.original_account_key(&address),
+			prefixes.contract_info_key(&address),
+		];
+		if let Some(info) = &contract_info {
+			keys.push(child_trie_root_key(&info.trie_id.0));
+		}
+		let account_proof = self.read_proof(keys, block_hash).await?;
+
+		let Some(info) = contract_info else {
+			return Ok(AccountProof {
+				address,
+				account_proof,
+				balance,
+				code_hash: empty_code_hash(),
+				nonce,
+				storage_hash: empty_storage_hash(),
+				storage_proof: storage_keys
+					.into_iter()
+					.map(|key| StorageProof { key, ..Default::default() })
+					.collect(),
+			});
+		};
+
+		let trie_id = info.trie_id.0;
+		let storage_hash = storage_api
+			.get_child_trie_root(&trie_id)
+			.await?
+			.unwrap_or_else(empty_storage_hash);
+
+		let storage_proof: Vec<StorageProof> = stream::iter(storage_keys)
+			.map(|key| {
+				let (runtime_api, trie_id) = (&runtime_api, &trie_id);
+				async move {
+					let (value, proof) = tokio::try_join!(
+						runtime_api.get_storage(address, key.to_big_endian()),
+						self.child_read_proof(trie_id, storage_slot_key(&key).to_vec(), block_hash),
+					)?;
+					let value =
+						storage_value(value.as_deref()).ok_or(ClientError::ConversionFailed)?;
+					Ok::<_, ClientError>(StorageProof { key, proof, value })
+				}
+			})
+			.buffered(PROOF_FETCH_CONCURRENCY)
+			.try_collect()
+			.await?;
+
+		Ok(AccountProof {
+			address,
+			account_proof,
+			balance,
+			code_hash: H256(info.code_hash.0),
+			nonce,
+			storage_hash,
+			storage_proof,
+		})
+	}
+
 	pub async fn fee_history(
 		&self,
 		block_count: u32,
@@ -706,4 +903,52 @@ impl Client {
 			.fee_history(block_count, latest_block.number(), reward_percentiles)
 			.await
 	}
+
+	/// Install a new log filter, and return its id.
+	pub async fn new_filter(&self, filter: Filter) -> Result<U256, ClientError> {
+		self.filter_provider.new_filter(filter).await.ok_or(ClientError::TooManyFilters)
+	}
+
+	/// Install a new block filter, and return its id.
+	pub async fn new_block_filter(&self) -> Result<U256, ClientError> {
+		self.filter_provider.new_block_filter().await.ok_or(ClientError::TooManyFilters)
+	}
+
+	/// Install a new pending transaction filter, and return its id.
+	pub async fn new_pending_transaction_filter(&self) -> Result<U256, ClientError> {
+		self.filter_provider
+			.new_pending_transaction_filter()
+			.await
+			.ok_or(ClientError::TooManyFilters)
+	}
+
+	/// Get the changes of the given filter since it was last polled.
+	pub async fn filter_changes(&self, filter_id: &U256) -> Option<FilterResults> {
+		self.filter_provider.filter_changes(filter_id).await
+	}
+
+	/// Get the log filter installed with the given id.
+	pub async fn log_filter(&self, filter_id: &U256) -> Option<Filter> {
+		self.filter_provider.log_filter(filter_id).await
+	}
+
+	/// Uninstall the given filter.
+	pub async fn uninstall_filter(&self, filter_id: &U256) -> bool {
+		self.filter_provider.uninstall_filter(filter_id).await
+	}
+
+	/// Notify the filters and subscribers of a transaction submitted through this server.
+	pub async fn notify_pending_transaction(&self, hash: H256) {
+		self.filter_provider.notify_pending_transaction(hash).await
+	}
+
+	/// Subscribe to new best blocks, notified once their receipts have been indexed.
+	pub fn subscribe_block_notifications(&self) -> broadcast::Receiver<Arc<BlockNotification>> {
+		self.filter_provider.subscribe_blocks()
+	}
+
+	/// Subscribe to the hashes of transactions submitted through this server.
+	pub fn subscribe_pending_transactions(&self) -> broadcast::Receiver<H256> {
+		self.filter_provider.subscribe_pending_transactions()
+	}
 }
@@ -16,9 +16,11 @@
 // limitations under the License.
 
 use crate::{
+	proof::child_trie_root_key,
 	subxt_client::{self, runtime_types::pallet_revive::storage::ContractInfo, SrcChainConfig},
-	ClientError, H160,
+	ClientError, H160, H256,
 };
+use codec::Decode;
 use subxt::{storage::Storage, OnlineClient};
 
 /// A wrapper around the Substrate Storage API.
@@ -47,6 +49,26 @@ impl StorageApi {
 		Ok(info)
 	}
 
+	/// Get the account id the given address was originally mapped from, if any.
+	pub async fn get_original_account(
+		&self,
+		address: &H160,
+	) -> Result<Option<[u8; 32]>, ClientError> {
+		// TODO: remove once subxt is updated
+		let address: subxt::utils::H160 = address.0.into();
+
+		let query = subxt_client::storage().revive().original_account(address);
+		let result = self.0.fetch(&query).await?.map(|account_id| account_id.0);
+		Ok(result)
+	}
+
+	/// Get the root of the child trie with the given trie id.
+	pub async fn get_child_trie_root(&self, trie_id: &[u8]) -> Result<Option<H256>, ClientError> {
+		let key = child_trie_root_key(trie_id);
+		let result = self.0.fetch_raw(key).await?.map(|root| H256::decode(&mut &root[..]));
+		Ok(result.transpose()?)
+	}
+
 	/// Get the contract code for the given contract address.
 	pub async fn get_contract_code(
 		&self,
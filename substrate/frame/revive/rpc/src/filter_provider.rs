@@ -0,0 +1,473 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{AddressOrAddresses, BlockNumberOrTag, FilterTopic, LOG_TARGET};
+use pallet_revive::evm::{Block, Filter, FilterResults, Log};
+use sp_core::{H256, U256};
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, Mutex};
+
+/// Installed filters that are not polled for this long are removed.
+const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Maximum number of installed filters.
+const MAX_FILTERS: usize = 10_000;
+
+/// Maximum number of changes buffered for a filter. Filters that are not polled before more
+/// changes accumulate are removed, so that their clients notice the missed changes.
+const MAX_FILTER_CHANGES: usize = 10_000;
+
+/// Number of recent best blocks whose logs are kept, to notify them as removed on reorgs.
+const RECENT_BLOCKS: usize = 256;
+
+/// Capacity of the notification channels used by `eth_subscribe`.
+const NOTIFICATION_CHANNEL_CAPACITY: usize = 256;
+
+/// A notification emitted once the receipts of a new best block have been indexed.
+#[derive(Debug, Clone)]
+pub struct BlockNotification {
+	/// The EVM block, without hydrated transactions.
+	pub block: Block,
+	/// The logs emitted in the block, in index order.
+	pub logs: Vec<Log>,
+	/// The logs of the recent best blocks retracted by the block, marked as removed.
+	pub removed_logs: Vec<Log>,
+}
+
+/// The kind of an installed filter, along with the changes accumulated since the last poll.
+enum FilterKind {
+	/// A filter created with `eth_newFilter`.
+	Logs { filter: Filter, changes: Vec<Log> },
+	/// A filter created with `eth_newBlockFilter`.
+	Blocks { changes: Vec<H256> },
+	/// A filter created with `eth_newPendingTransactionFilter`.
+	PendingTransactions { changes: Vec<H256> },
+}
+
+impl FilterKind {
+	/// Number of changes accumulated since the last poll.
+	fn changes_len(&self) -> usize {
+		match self {
+			Self::Logs { changes, .. } => changes.len(),
+			Self::Blocks { changes } | Self::PendingTransactions { changes } => changes.len(),
+		}
+	}
+}
+
+/// A filter installed by a client, and polled with `eth_getFilterChanges`.
+struct InstalledFilter {
+	kind: FilterKind,
+	last_poll: Instant,
+}
+
+/// Apply `update` to the installed filters, removing the expired ones and the ones with too many
+/// changes.
+fn update_filters(
+	filters: &mut HashMap<U256, InstalledFilter>,
+	mut update: impl FnMut(&mut FilterKind),
+) {
+	filters.retain(|id, installed| {
+		if installed.last_poll.elapsed() > FILTER_TIMEOUT {
+			log::debug!(target: LOG_TARGET, "Removing expired filter {id:?}");
+			return false;
+		}
+		update(&mut installed.kind);
+		if installed.kind.changes_len() > MAX_FILTER_CHANGES {
+			log::debug!(target: LOG_TARGET, "Removing filter {id:?} with too many changes");
+			return false;
+		}
+		true
+	});
+}
+
+/// FilterProvider keeps track of the installed polling filters, and of the `eth_subscribe`
+/// notification channels.
+///
+/// Filters are evaluated incrementally: each time a new block is indexed, its logs are matched
+/// against the installed log filters and buffered until the next `eth_getFilterChanges` call.
+#[derive(Clone)]
+pub struct FilterProvider {
+	/// The installed filters, keyed by filter id.
+	filters: Arc<Mutex<HashMap<U256, InstalledFilter>>>,
+	/// The hashes and logs of the recent best blocks, oldest first.
+	recent_blocks: Arc<Mutex<VecDeque<(H256, Vec<Log>)>>>,
+	/// Broadcasts new best blocks to the subscribers.
+	blocks: broadcast::Sender<Arc<BlockNotification>>,
+	/// Broadcasts the hashes of transactions submitted through this server.
+	pending_transactions: broadcast::Sender<H256>,
+}
+
+impl Default for FilterProvider {
+	fn default() -> Self {
+		let (blocks, _) = broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY);
+		let (pending_transactions, _) = broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY);
+		Self {
+			filters: Default::default(),
+			recent_blocks: Default::default(),
+			blocks,
+			pending_transactions,
+		}
+	}
+}
+
+/// Returns `true` if the given log matches the address and topics criteria of the filter.
+///
+/// The block range of the filter is not checked here, see [`filter_matches_block`].
+pub fn filter_matches_log(filter: &Filter, log: &Log) -> bool {
+	match &filter.address {
+		Some(AddressOrAddresses::Address(address)) if *address != log.address => return false,
+		Some(AddressOrAddresses::Addresses(addresses))
+			if !addresses.is_empty() && !addresses.contains(&log.address) =>
+			return false,
+		_ => {},
+	}
+
+	let Some(topics) = &filter.topics else { return true };
+	topics.iter().enumerate().all(|(i, topic)| match (topic, log.topics.get(i)) {
+		(FilterTopic::Single(expected), Some(actual)) => expected == actual,
+		(FilterTopic::Multiple(expected), Some(actual)) =>
+			expected.is_empty() || expected.contains(actual),
+		(FilterTopic::Multiple(expected), None) => expected.is_empty(),
+		(FilterTopic::Single(_), None) => false,
+	})
+}
+
+/// Returns `true` if the given block falls within the block range of the filter.
+///
+/// Block tags are treated as open bounds, since the filter is evaluated against new blocks only.
+pub fn filter_matches_block(filter: &Filter, block_hash: &H256, block_number: U256) -> bool {
+	if let Some(hash) = &filter.block_hash {
+		return hash == block_hash;
+	}
+
+	if let Some(BlockNumberOrTag::U256(from_block)) = filter.from_block {
+		if block_number < from_block {
+			return false;
+		}
+	}
+
+	if let Some(BlockNumberOrTag::U256(to_block)) = filter.to_block {
+		if block_number > to_block {
+			return false;
+		}
+	}
+
+	true
+}
+
+impl FilterProvider {
+	/// Install a new filter and return its random id, or `None` if too many filters are
+	/// installed.
+	async fn install(&self, kind: FilterKind) -> Option<U256> {
+		let mut filters = self.filters.lock().await;
+		update_filters(&mut filters, |_| {});
+		if filters.len() >= MAX_FILTERS {
+			log::debug!(target: LOG_TARGET, "Too many installed filters");
+			return None;
+		}
+
+		let id = loop {
+			let id = U256::from(rand::random::<u128>());
+			if !id.is_zero() && !filters.contains_key(&id) {
+				break id;
+			}
+		};
+		filters.insert(id, InstalledFilter { kind, last_poll: Instant::now() });
+		log::debug!(target: LOG_TARGET, "Installed filter {id:?}");
+		Some(id)
+	}
+
+	/// Install a new log filter.
+	pub async fn new_filter(&self, filter: Filter) -> Option<U256> {
+		self.install(FilterKind::Logs { filter, changes: vec![] }).await
+	}
+
+	/// Install a new block filter.
+	pub async fn new_block_filter(&self) -> Option<U256> {
+		self.install(FilterKind::Blocks { changes: vec![] }).await
+	}
+
+	/// Install a new pending transaction filter.
+	pub async fn new_pending_transaction_filter(&self) -> Option<U256> {
+		self.install(FilterKind::PendingTransactions { changes: vec![] }).await
+	}
+
+	/// Uninstall the filter with the given id, returning `true` if it existed.
+	pub async fn uninstall_filter(&self, id: &U256) -> bool {
+		self.filters.lock().await.remove(id).is_some()
+	}
+
+	/// Return the log filter with the given id, if any.
+	pub async fn log_filter(&self, id: &U256) -> Option<Filter> {
+		let mut filters = self.filters.lock().await;
+		let installed = filters.get_mut(id)?;
+		installed.last_poll = Instant::now();
+		match &installed.kind {
+			FilterKind::Logs { filter, .. } => Some(filter.clone()),
+			_ => None,
+		}
+	}
+
+	/// Drain the changes accumulated since the last poll of the filter with the given id.
+	pub async fn filter_changes(&self, id: &U256) -> Option<FilterResults> {
+		let mut filters = self.filters.lock().await;
+		let installed = filters.get_mut(id)?;
+		installed.last_poll = Instant::now();
+		let changes = match &mut installed.kind {
+			FilterKind::Logs { changes, .. } => FilterResults::Logs(core::mem::take(changes)),
+			FilterKind::Blocks { changes } | FilterKind::PendingTransactions { changes } =>
+				FilterResults::Hashes(core::mem::take(changes)),
+		};
+		Some(changes)
+	}
+
+	/// Subscribe to new best block notifications.
+	pub fn subscribe_blocks(&self) -> broadcast::Receiver<Arc<BlockNotification>> {
+		self.blocks.subscribe()
+	}
+
+	/// Subscribe to pending transaction notifications.
+	pub fn subscribe_pending_transactions(&self) -> broadcast::Receiver<H256> {
+		self.pending_transactions.subscribe()
+	}
+
+	/// Notify the filters and subscribers of a new best block, which retracted the blocks with
+	/// the given hashes.
+	///
+	/// The logs of the retracted blocks are notified again, marked as removed, if they are among
+	/// the [`RECENT_BLOCKS`] last notified blocks.
+	pub async fn notify_block(&self, block: Block, logs: Vec<Log>, retracted: &[H256]) {
+		let block_hash = block.hash;
+		let block_number = block.number;
+
+		let removed_logs = {
+			let mut recent_blocks = self.recent_blocks.lock().await;
+			let mut removed_logs = Vec::new();
+			recent_blocks.retain(|(hash, logs)| {
+				if !retracted.contains(hash) {
+					return true;
+				}
+				removed_logs
+					.extend(logs.iter().map(|log| Log { removed: Some(true), ..log.clone() }));
+				false
+			});
+			recent_blocks.push_back((block_hash, logs.clone()));
+			if recent_blocks.len() > RECENT_BLOCKS {
+				recent_blocks.pop_front();
+			}
+			removed_logs
+		};
+
+		let mut filters = self.filters.lock().await;
+		update_filters(&mut filters, |kind| match kind {
+			FilterKind::Logs { filter, changes } => {
+				changes.extend(
+					removed_logs
+						.iter()
+						.filter(|log| {
+							filter_matches_block(filter, &log.block_hash, log.block_number)
+						})
+						.filter(|log| filter_matches_log(filter, log))
+						.cloned(),
+				);
+				if filter_matches_block(filter, &block_hash, block_number) {
+					changes
+						.extend(logs.iter().filter(|log| filter_matches_log(filter, log)).cloned());
+				}
+			},
+			FilterKind::Blocks { changes } => changes.push(block_hash),
+			FilterKind::PendingTransactions { .. } => {},
+		});
+		drop(filters);
+
+		// Sending only fails when there are no subscribers.
+		let _ = self.blocks.send(Arc::new(BlockNotification { block, logs, removed_logs }));
+	}
+
+	/// Notify the filters and subscribers of a new pending transaction.
+	pub async fn notify_pending_transaction(&self, hash: H256) {
+		update_filters(&mut *self.filters.lock().await, |kind| {
+			if let FilterKind::PendingTransactions { changes } = kind {
+				changes.push(hash);
+			}
+		});
+
+		// Sending only fails when there are no subscribers.
+		let _ = self.pending_transactions.send(hash);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+	use sp_core::H160;
+
+	fn log(address: H160, topics: Vec<H256>) -> Log {
+		Log { address, topics, ..Default::default() }
+	}
+
+	fn block(number: u64) -> Block {
+		Block { hash: H256::from_low_u64_be(number), number: number.into(), ..Default::default() }
+	}
+
+	#[test]
+	fn filter_matches_log_works() {
+		let address = H160::from([1u8; 20]);
+		let topic_0 = H256::from([1u8; 32]);
+		let topic_1 = H256::from([2u8; 32]);
+		let log = log(address, vec![topic_0, topic_1]);
+
+		let filter = |address: Option<H160>| Filter {
+			address: address.map(Into::into),
+			..Default::default()
+		};
+		assert!(filter_matches_log(&filter(None), &log));
+		assert!(filter_matches_log(&filter(Some(address)), &log));
+		assert!(!filter_matches_log(&filter(Some(H160::from([2u8; 20]))), &log));
+
+		let filter = |topics| Filter { topics: Some(topics), ..Default::default() };
+		assert!(filter_matches_log(&filter(vec![FilterTopic::Single(topic_0)]), &log));
+		assert!(filter_matches_log(
+			&filter(vec![FilterTopic::Multiple(vec![]), FilterTopic::Single(topic_1)]),
+			&log
+		));
+		assert!(filter_matches_log(
+			&filter(vec![FilterTopic::Multiple(vec![topic_1, topic_0])]),
+			&log
+		));
+		assert!(!filter_matches_log(&filter(vec![FilterTopic::Single(topic_1)]), &log));
+		assert!(!filter_matches_log(
+			&filter(vec![
+				FilterTopic::Single(topic_0),
+				FilterTopic::Single(topic_1),
+				FilterTopic::Single(topic_1)
+			]),
+			&log
+		));
+	}
+
+	#[test]
+	fn filter_matches_block_works() {
+		let hash = H256::from([1u8; 32]);
+		let filter = Filter {
+			from_block: Some(U256::from(2).into()),
+			to_block: Some(U256::from(4).into()),
+			..Default::default()
+		};
+		assert!(!filter_matches_block(&filter, &hash, 1.into()));
+		assert!(filter_matches_block(&filter, &hash, 2.into()));
+		assert!(filter_matches_block(&filter, &hash, 4.into()));
+		assert!(!filter_matches_block(&filter, &hash, 5.into()));
+
+		let filter = Filter { block_hash: Some(hash), ..Default::default() };
+		assert!(filter_matches_block(&filter, &hash, 1.into()));
+		assert!(!filter_matches_block(&filter, &H256::zero(), 1.into()));
+	}
+
+	#[tokio::test]
+	async fn filter_changes_are_accumulated_and_drained() {
+		let provider = FilterProvider::default();
+		let address = H160::from([1u8; 20]);
+		let matching = log(address, vec![]);
+		let other = log(H160::from([2u8; 20]), vec![]);
+
+		let log_filter = provider
+			.new_filter(Filter { address: Some(address.into()), ..Default::default() })
+			.await
+			.unwrap();
+		let block_filter = provider.new_block_filter().await.unwrap();
+		let tx_filter = provider.new_pending_transaction_filter().await.unwrap();
+		assert_ne!(log_filter, block_filter);
+
+		provider
+			.notify_block(block(1), vec![matching.clone(), other.clone()], &[])
+			.await;
+		provider.notify_block(block(2), vec![other], &[]).await;
+		provider.notify_pending_transaction(H256::from([3u8; 32])).await;
+
+		assert_eq!(
+			provider.filter_changes(&log_filter).await,
+			Some(FilterResults::Logs(vec![matching]))
+		);
+		assert_eq!(provider.filter_changes(&log_filter).await, Some(FilterResults::Logs(vec![])));
+		assert_eq!(
+			provider.filter_changes(&block_filter).await,
+			Some(FilterResults::Hashes(vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)]))
+		);
+		assert_eq!(
+			provider.filter_changes(&tx_filter).await,
+			Some(FilterResults::Hashes(vec![H256::from([3u8; 32])]))
+		);
+
+		assert!(provider.uninstall_filter(&log_filter).await);
+		assert!(!provider.uninstall_filter(&log_filter).await);
+		assert_eq!(provider.filter_changes(&log_filter).await, None);
+	}
+
+	#[tokio::test]
+	async fn subscribers_receive_notifications() {
+		let provider = FilterProvider::default();
+		let mut blocks = provider.subscribe_blocks();
+		let mut pending = provider.subscribe_pending_transactions();
+
+		provider.notify_block(block(1), vec![], &[]).await;
+		provider.notify_pending_transaction(H256::from([1u8; 32])).await;
+
+		assert_eq!(blocks.recv().await.unwrap().block.number, U256::from(1));
+		assert_eq!(pending.recv().await.unwrap(), H256::from([1u8; 32]));
+	}
+
+	#[tokio::test]
+	async fn retracted_logs_are_notified_as_removed() {
+		let provider = FilterProvider::default();
+		let filter = provider.new_filter(Filter::default()).await.unwrap();
+		let mut blocks = provider.subscribe_blocks();
+		let retracted = Log { block_hash: block(1).hash, ..log(H160::from([1u8; 20]), vec![]) };
+
+		provider.notify_block(block(1), vec![retracted.clone()], &[]).await;
+		let fork = Block { hash: H256::from([1u8; 32]), ..block(1) };
+		provider.notify_block(fork, vec![], &[block(1).hash]).await;
+
+		let removed = Log { removed: Some(true), ..retracted.clone() };
+		assert_eq!(
+			provider.filter_changes(&filter).await,
+			Some(FilterResults::Logs(vec![retracted, removed.clone()]))
+		);
+		blocks.recv().await.unwrap();
+		assert_eq!(blocks.recv().await.unwrap().removed_logs, vec![removed]);
+	}
+
+	#[tokio::test]
+	async fn filters_with_too_many_changes_are_removed() {
+		let provider = FilterProvider::default();
+		let filter = provider.new_pending_transaction_filter().await.unwrap();
+
+		for _ in 0..MAX_FILTER_CHANGES {
+			provider.notify_pending_transaction(H256::zero()).await;
+		}
+		assert!(provider.filter_changes(&filter).await.is_some());
+
+		for _ in 0..=MAX_FILTER_CHANGES {
+			provider.notify_pending_transaction(H256::zero()).await;
+		}
+		assert_eq!(provider.filter_changes(&filter).await, None);
+	}
+}
@@ -30,6 +30,7 @@ use thiserror::Error;
 pub mod cli;
 pub mod client;
 pub mod example;
+pub mod proof;
 pub mod subxt_client;
 
 #[cfg(test)]
@@ -44,6 +45,9 @@ pub use receipt_provider::*;
 mod fee_history_provider;
 pub use fee_history_provider::*;
 
+mod filter_provider;
+pub use filter_provider::*;
+
 mod receipt_extractor;
 pub use receipt_extractor::*;
 
@@ -98,6 +102,15 @@ pub enum EthRpcError {
 	/// Received an invalid transaction
 	#[error("Invalid transaction {0:?}")]
 	TransactionTypeNotSupported(Byte),
+	/// The filter was not found, or has expired
+	#[error("Filter not found")]
+	FilterNotFound(U256),
+	/// The subscription filter is not supported
+	#[error("Block hash filters are not supported by subscriptions")]
+	InvalidSubscriptionFilter,
+	/// The runtime returned a trace of an unexpected type
+	#[error("Unexpected trace type")]
+	UnexpectedTrace,
 }
 
 // TODO use https://eips.ethereum.org/EIPS/eip-1474#error-codes
@@ -161,6 +174,42 @@ impl EthRpcServer for EthRpcServerImpl {
 		Ok(dry_run.data.into())
 	}
 
+	async fn create_access_list(
+		&self,
+		mut transaction: GenericTransaction,
+		block: Option<BlockNumberOrTagOrHash>,
+	) -> RpcResult<AccessListResult> {
+		let hash = self.client.block_hash_for_tag(block.unwrap_or_default()).await?;
+		let runtime_api = self.client.runtime_api(hash);
+
+		// Trace the transaction without any access list, so that every access is reported.
+		transaction.access_list = None;
+		let Trace::AccessList(access_list) = runtime_api
+			.trace_call(transaction.clone(), TracerType::AccessListTracer)
+			.await?
+		else {
+			return Err(EthRpcError::UnexpectedTrace.into());
+		};
+
+		// Declaring an access list doesn't change the gas used by the transaction.
+		transaction.access_list = Some(access_list.clone());
+		let (gas_used, error) = match runtime_api.dry_run(transaction.clone()).await {
+			Ok(dry_run) => (dry_run.eth_gas, None),
+			Err(err) => {
+				log::debug!(target: LOG_TARGET, "create_access_list dry run failed: {err:?}");
+				// The dry run doesn't report the gas of a failed transaction, the call trace does.
+				let Trace::Call(trace) =
+					runtime_api.trace_call(transaction, TracerType::CallTracer(None)).await?
+				else {
+					return Err(EthRpcError::UnexpectedTrace.into());
+				};
+				(trace.gas_used, Some(err.to_string()))
+			},
+		};
+
+		Ok(AccessListResult { access_list, error, gas_used })
+	}
+
 	async fn send_raw_transaction(&self, transaction: Bytes) -> RpcResult<H256> {
 		let hash = H256(keccak_256(&transaction.0));
 		let call = subxt_client::tx().revive().eth_transact(transaction.0);
@@ -170,6 +219,7 @@ impl EthRpcServer for EthRpcServerImpl {
 		})?;
 
 		log::debug!(target: LOG_TARGET, "send_raw_transaction hash: {hash:?}");
+		self.client.notify_pending_transaction(hash).await;
 		Ok(hash)
 	}
 
@@ -297,6 +347,52 @@ impl EthRpcServer for EthRpcServerImpl {
 		Ok(FilterResults::Logs(logs))
 	}
 
+	async fn new_filter(&self, filter: Filter) -> RpcResult<U256> {
+		Ok(self.client.new_filter(filter).await?)
+	}
+
+	async fn new_block_filter(&self) -> RpcResult<U256> {
+		Ok(self.client.new_block_filter().await?)
+	}
+
+	async fn new_pending_transaction_filter(&self) -> RpcResult<U256> {
+		Ok(self.client.new_pending_transaction_filter().await?)
+	}
+
+	async fn get_filter_changes(&self, filter_id: U256) -> RpcResult<FilterResults> {
+		let changes = self
+			.client
+			.filter_changes(&filter_id)
+			.await
+			.ok_or(EthRpcError::FilterNotFound(filter_id))?;
+		Ok(changes)
+	}
+
+	async fn get_filter_logs(&self, filter_id: U256) -> RpcResult<FilterResults> {
+		let filter = self
+			.client
+			.log_filter(&filter_id)
+			.await
+			.ok_or(EthRpcError::FilterNotFound(filter_id))?;
+		let logs = self.client.logs(Some(filter)).await?;
+		Ok(FilterResults::Logs(logs))
+	}
+
+	async fn uninstall_filter(&self, filter_id: U256) -> RpcResult<bool> {
+		Ok(self.client.uninstall_filter(&filter_id).await)
+	}
+
+	async fn get_proof(
+		&self,
+		address: H160,
+		storage_keys: Vec<U256>,
+		block: BlockNumberOrTagOrHash,
+	) -> RpcResult<AccountProof> {
+		let hash = self.client.block_hash_for_tag(block).await?;
+		let proof = self.client.get_proof(address, storage_keys, hash).await?;
+		Ok(proof)
+	}
+
 	async fn get_storage_at(
 		&self,
 		address: H160,
@@ -0,0 +1,337 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Merkle proofs returned by `eth_getProof`.
+//!
+//! The state of a pallet-revive chain is not stored in an Ethereum Merkle-Patricia trie, but in a
+//! Substrate base-16 trie hashed with blake2-256. The nodes returned in an [`AccountProof`] are
+//! therefore Substrate trie nodes, and must be checked with [`verify_account_proof`] rather than
+//! with an Ethereum proof verifier:
+//!
+//! - `accountProof` is a read proof of the main state trie, rooted at the `stateRoot` of the block.
+//!   It covers the `System::Account` entry of the account, the `Revive::OriginalAccount` mapping of
+//!   the address, the `Revive::ContractInfoOf` entry of the address and, for contracts, the root of
+//!   the contract's child trie.
+//! - `storageHash` is the root of the contract's child trie, or the empty trie root for accounts
+//!   without code.
+//! - `storageProof[i].proof` is a read proof of the contract's child trie, rooted at `storageHash`,
+//!   for the key `blake2_256(storageProof[i].key)`.
+//!
+//! The `balance` and `nonce` fields are derived by the runtime from the `System::Account` entry.
+//! Their encoding depends on the runtime configuration, so they are not checked by
+//! [`verify_account_proof`]. The proof does however contain the entry, so that callers that know
+//! the runtime types can decode and check them.
+use crate::{AccountProof, Bytes, H160, H256, U256};
+use codec::Decode;
+use sp_core::{keccak_256, storage::ChildInfo};
+use sp_crypto_hashing::{blake2_128, blake2_256};
+use sp_runtime::traits::BlakeTwo256;
+use sp_trie::{LayoutV1, MemoryDB};
+use thiserror::Error;
+
+/// The error type returned when an [`AccountProof`] could not be verified.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProofError {
+	/// The proof does not contain the nodes required to read the given key.
+	#[error("Incomplete proof for key 0x{}", hex::encode(.0))]
+	IncompleteProof(Vec<u8>),
+	/// The address mapping stored in the proof could not be decoded.
+	#[error("Invalid account mapping")]
+	InvalidAccountMapping,
+	/// The contract info stored in the proof could not be decoded.
+	#[error("Invalid contract info")]
+	InvalidContractInfo,
+	/// The proven code hash does not match the returned one.
+	#[error("Code hash mismatch")]
+	CodeHashMismatch,
+	/// The proven storage root does not match the returned one.
+	#[error("Storage hash mismatch")]
+	StorageHashMismatch,
+	/// The proven value of the given storage slot does not match the returned one.
+	#[error("Storage value mismatch for slot {0}")]
+	StorageValueMismatch(U256),
+}
+
+/// The hash of the code of accounts that are not contracts.
+pub fn empty_code_hash() -> H256 {
+	H256(keccak_256(&[]))
+}
+
+/// The storage hash of accounts that are not contracts.
+pub fn empty_storage_hash() -> H256 {
+	sp_trie::empty_child_trie_root::<LayoutV1<BlakeTwo256>>()
+}
+
+/// The account id an address maps to, when it has no entry in `Revive::OriginalAccount`.
+pub fn fallback_account_id(address: &H160) -> [u8; 32] {
+	let mut account_id = [0xEE; 32];
+	account_id[..20].copy_from_slice(address.as_bytes());
+	account_id
+}
+
+/// The prefixes of the storage maps covered by an [`AccountProof`].
+///
+/// They depend on the names the pallets are given in the runtime, so they are read from its
+/// metadata rather than hardcoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoragePrefixes {
+	/// The prefix of `System::Account`.
+	pub system_account: Vec<u8>,
+	/// The prefix of `Revive::OriginalAccount`.
+	pub original_account: Vec<u8>,
+	/// The prefix of `Revive::ContractInfoOf`.
+	pub contract_info: Vec<u8>,
+}
+
+impl StoragePrefixes {
+	/// The main trie key of the `System::Account` entry of the given account id.
+	pub fn system_account_key(&self, account_id: &[u8; 32]) -> Vec<u8> {
+		[&self.system_account[..], &blake2_128(account_id), account_id].concat()
+	}
+
+	/// The main trie key of the `Revive::OriginalAccount` entry of the given address.
+	pub fn original_account_key(&self, address: &H160) -> Vec<u8> {
+		[&self.original_account[..], address.as_bytes()].concat()
+	}
+
+	/// The main trie key of the `Revive::ContractInfoOf` entry of the given address.
+	pub fn contract_info_key(&self, address: &H160) -> Vec<u8> {
+		[&self.contract_info[..], address.as_bytes()].concat()
+	}
+}
+
+/// The main trie key under which the root of the given contract child trie is stored.
+pub fn child_trie_root_key(trie_id: &[u8]) -> Vec<u8> {
+	ChildInfo::new_default(trie_id).prefixed_storage_key().into_inner()
+}
+
+/// The child trie key of the given storage slot.
+pub fn storage_slot_key(slot: &U256) -> [u8; 32] {
+	blake2_256(&slot.to_big_endian())
+}
+
+/// Decode the trie id and the code hash out of an encoded `ContractInfo`.
+///
+/// These are the first two fields of the struct, so the rest of the encoding is ignored.
+pub fn decode_contract_info(mut encoded: &[u8]) -> Option<(Vec<u8>, H256)> {
+	<(Vec<u8>, H256)>::decode(&mut encoded).ok()
+}
+
+/// Decode a contract storage value into a [`U256`], the way `eth_getStorageAt` returns it.
+pub fn storage_value(value: Option<&[u8]>) -> Option<U256> {
+	match value {
+		None => Some(U256::zero()),
+		Some(value) if value.len() <= 32 => Some(U256::from_big_endian(value)),
+		Some(_) => None,
+	}
+}
+
+/// Read the value of `key` in the trie rooted at `root`, using only the nodes of a proof.
+fn read_proof_value(
+	db: &MemoryDB<BlakeTwo256>,
+	root: &H256,
+	key: &[u8],
+) -> Result<Option<Vec<u8>>, ProofError> {
+	sp_trie::read_trie_value::<LayoutV1<BlakeTwo256>, _>(db, root, key, None, None)
+		.map_err(|_| ProofError::IncompleteProof(key.to_vec()))
+}
+
+/// Build an in-memory trie database out of the nodes of a proof.
+fn proof_db(nodes: &[Bytes]) -> MemoryDB<BlakeTwo256> {
+	sp_trie::StorageProof::new(nodes.iter().map(|node| node.0.clone()))
+		.into_memory_db::<BlakeTwo256>()
+}
+
+/// Verify an [`AccountProof`] against the state root of the block it was generated at.
+///
+/// This checks that:
+/// - the `System::Account` entry and the address mapping of the account are part of the proof,
+/// - the `codeHash` and `storageHash` match the contract info stored in the main trie, or the empty
+///   code and storage hashes when the account is not a contract,
+/// - every `storageProof` value matches the value stored in the contract's child trie.
+///
+/// `prefixes` are the storage prefixes of the runtime the proof was generated with.
+pub fn verify_account_proof(
+	state_root: H256,
+	prefixes: &StoragePrefixes,
+	proof: &AccountProof,
+) -> Result<(), ProofError> {
+	let db = proof_db(&proof.account_proof);
+
+	let account_id =
+		match read_proof_value(&db, &state_root, &prefixes.original_account_key(&proof.address))? {
+			Some(value) => <[u8; 32]>::decode(&mut &value[..])
+				.map_err(|_| ProofError::InvalidAccountMapping)?,
+			None => fallback_account_id(&proof.address),
+		};
+	read_proof_value(&db, &state_root, &prefixes.system_account_key(&account_id))?;
+
+	let contract_info =
+		read_proof_value(&db, &state_root, &prefixes.contract_info_key(&proof.address))?;
+	let Some(contract_info) = contract_info else {
+		if proof.code_hash != empty_code_hash() {
+			return Err(ProofError::CodeHashMismatch);
+		}
+		if proof.storage_hash != empty_storage_hash() {
+			return Err(ProofError::StorageHashMismatch);
+		}
+		return proof.storage_proof.iter().try_for_each(|storage| {
+			if storage.value.is_zero() {
+				Ok(())
+			} else {
+				Err(ProofError::StorageValueMismatch(storage.key))
+			}
+		});
+	};
+
+	let (trie_id, code_hash) =
+		decode_contract_info(&contract_info).ok_or(ProofError::InvalidContractInfo)?;
+	if proof.code_hash != code_hash {
+		return Err(ProofError::CodeHashMismatch);
+	}
+
+	let storage_hash = read_proof_value(&db, &state_root, &child_trie_root_key(&trie_id))?
+		.and_then(|root| H256::decode(&mut &root[..]).ok())
+		.unwrap_or_else(empty_storage_hash);
+	if proof.storage_hash != storage_hash {
+		return Err(ProofError::StorageHashMismatch);
+	}
+
+	for storage in &proof.storage_proof {
+		let db = proof_db(&storage.proof);
+		let value = read_proof_value(&db, &storage_hash, &storage_slot_key(&storage.key))?;
+		if storage_value(value.as_deref()) != Some(storage.value) {
+			return Err(ProofError::StorageValueMismatch(storage.key));
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::StorageProof;
+	use codec::Encode;
+	use sp_core::storage::{StateVersion, Storage, StorageChild};
+	use sp_crypto_hashing::twox_128;
+	use sp_state_machine::{prove_child_read, prove_read, Backend, InMemoryBackend};
+
+	fn nodes(proof: sp_trie::StorageProof) -> Vec<Bytes> {
+		proof.into_iter_nodes().map(Bytes).collect()
+	}
+
+	fn prefixes() -> StoragePrefixes {
+		StoragePrefixes {
+			system_account: [twox_128(b"System"), twox_128(b"Account")].concat(),
+			original_account: [twox_128(b"Revive"), twox_128(b"OriginalAccount")].concat(),
+			contract_info: [twox_128(b"Revive"), twox_128(b"ContractInfoOf")].concat(),
+		}
+	}
+
+	#[test]
+	fn verify_account_proof_works() {
+		let prefixes = prefixes();
+		let contract = H160::from([1u8; 20]);
+		let eoa = H160::from([2u8; 20]);
+		let trie_id = b"contract_trie".to_vec();
+		let code_hash = H256::from([3u8; 32]);
+		let slot = U256::from(7);
+		let child_info = ChildInfo::new_default(&trie_id);
+
+		let mut storage = Storage::default();
+		storage.top.insert(
+			prefixes.contract_info_key(&contract),
+			(trie_id.clone(), code_hash, 0u32).encode(),
+		);
+		storage
+			.top
+			.insert(prefixes.system_account_key(&fallback_account_id(&contract)), vec![1]);
+		storage
+			.top
+			.insert(prefixes.system_account_key(&fallback_account_id(&eoa)), vec![2]);
+		storage.children_default.insert(
+			child_info.storage_key().to_vec(),
+			StorageChild {
+				data: [(storage_slot_key(&slot).to_vec(), U256::from(42).to_big_endian().to_vec())]
+					.into_iter()
+					.collect(),
+				child_info: child_info.clone(),
+			},
+		);
+
+		let backend: InMemoryBackend<BlakeTwo256> = (storage, StateVersion::V1).into();
+		let state_root = backend.storage_root(core::iter::empty(), StateVersion::V1).0;
+		let storage_hash =
+			backend.child_storage_root(&child_info, core::iter::empty(), StateVersion::V1).0;
+
+		let main_proof = |address: &H160, with_child_root: bool| {
+			let mut keys = vec![
+				prefixes.system_account_key(&fallback_account_id(address)),
+				prefixes.original_account_key(address),
+				prefixes.contract_info_key(address),
+			];
+			if with_child_root {
+				keys.push(child_trie_root_key(&trie_id));
+			}
+			nodes(prove_read(backend.clone(), keys).unwrap())
+		};
+		let child_proof = |slot: &U256| {
+			nodes(prove_child_read(backend.clone(), &child_info, [storage_slot_key(slot)]).unwrap())
+		};
+
+		let mut proof = AccountProof {
+			address: contract,
+			account_proof: main_proof(&contract, true),
+			code_hash,
+			storage_hash,
+			storage_proof: vec![StorageProof {
+				key: slot,
+				value: U256::from(42),
+				proof: child_proof(&slot),
+			}],
+			..Default::default()
+		};
+		assert_eq!(verify_account_proof(state_root, &prefixes, &proof), Ok(()));
+
+		proof.storage_proof[0].value = U256::from(43);
+		assert_eq!(
+			verify_account_proof(state_root, &prefixes, &proof),
+			Err(ProofError::StorageValueMismatch(slot))
+		);
+
+		proof.code_hash = H256::zero();
+		assert_eq!(
+			verify_account_proof(state_root, &prefixes, &proof),
+			Err(ProofError::CodeHashMismatch)
+		);
+
+		let proof = AccountProof {
+			address: eoa,
+			account_proof: main_proof(&eoa, false),
+			code_hash: empty_code_hash(),
+			storage_hash: empty_storage_hash(),
+			..Default::default()
+		};
+		assert_eq!(verify_account_proof(state_root, &prefixes, &proof), Ok(()));
+
+		let proof = AccountProof { account_proof: vec![], ..proof };
+		assert!(matches!(
+			verify_account_proof(state_root, &prefixes, &proof),
+			Err(ProofError::IncompleteProof(_))
+		));
+	}
+}
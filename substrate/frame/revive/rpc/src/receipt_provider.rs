@@ -15,7 +15,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use crate::{
-	client::{SubstrateBlock, SubstrateBlockNumber},
+	client::{SubscriptionType, SubstrateBlock, SubstrateBlockNumber},
 	Address, AddressOrAddresses, BlockInfoProvider, BlockNumberOrTag, BlockTag, Bytes, ClientError,
 	FilterTopic, ReceiptExtractor, SubxtBlockInfoProvider, LOG_TARGET,
 };
@@ -26,9 +26,13 @@ use std::{
 	collections::{BTreeMap, HashMap},
 	sync::Arc,
 };
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard};
 
 /// ReceiptProvider stores transaction receipts and logs in a SQLite database.
+///
+/// The provider also keeps track of the block indexed at each height. When a best or finalized
+/// block retracts previously indexed blocks, their receipts and logs are rolled back, so that
+/// receipts of orphaned blocks are never served.
 #[derive(Clone)]
 pub struct ReceiptProvider<B: BlockInfoProvider = SubxtBlockInfoProvider> {
 	/// The database pool.
@@ -41,6 +45,9 @@ pub struct ReceiptProvider<B: BlockInfoProvider = SubxtBlockInfoProvider> {
 	keep_latest_n_blocks: Option<usize>,
 	/// A Map of the latest block numbers to block hashes.
 	block_number_to_hash: Arc<Mutex<BTreeMap<SubstrateBlockNumber, H256>>>,
+	/// Serializes the updates of the canonical chain made by the block subscriptions and the
+	/// re-indexing.
+	canonical_lock: Arc<Mutex<()>>,
 }
 
 /// Provides information about a block,
@@ -78,6 +85,7 @@ impl<B: BlockInfoProvider> ReceiptProvider<B> {
 			receipt_extractor,
 			keep_latest_n_blocks,
 			block_number_to_hash: Default::default(),
+			canonical_lock: Default::default(),
 		})
 	}
 
@@ -107,21 +115,75 @@ impl<B: BlockInfoProvider> ReceiptProvider<B> {
 		}
 		log::debug!(target: LOG_TARGET, "Removing block hashes: {block_hashes:?}");
 
-		let placeholders = vec!["?"; block_hashes.len()].join(", ");
-		let sql = format!("DELETE FROM transaction_hashes WHERE block_hash in ({placeholders})");
-		let mut delete_tx_query = sqlx::query(&sql);
+		let mut tx = self.pool.begin().await?;
+		for block_hash in block_hashes {
+			let block_hash = block_hash.as_ref();
+			query!(r#"DELETE FROM transaction_hashes WHERE block_hash = $1"#, block_hash)
+				.execute(&mut *tx)
+				.await?;
+			query!(r#"DELETE FROM logs WHERE block_hash = $1"#, block_hash)
+				.execute(&mut *tx)
+				.await?;
+			query!(r#"DELETE FROM blocks WHERE block_hash = $1"#, block_hash)
+				.execute(&mut *tx)
+				.await?;
+		}
+		tx.commit().await?;
+		Ok(())
+	}
 
-		let sql = format!("DELETE FROM logs WHERE block_hash in ({placeholders})");
-		let mut delete_logs_query = sqlx::query(&sql);
+	/// Roll back the blocks indexed at or above the given block number, and return their hashes.
+	pub async fn retract_from(
+		&self,
+		block_number: SubstrateBlockNumber,
+	) -> Result<Vec<H256>, ClientError> {
+		let from_number = block_number as i64;
+		let rows = query!(r#"SELECT block_hash FROM blocks WHERE block_number >= $1"#, from_number)
+			.fetch_all(&self.pool)
+			.await?;
 
-		for block_hash in block_hashes {
-			delete_tx_query = delete_tx_query.bind(block_hash.as_ref());
-			delete_logs_query = delete_logs_query.bind(block_hash.as_ref());
+		let block_hashes =
+			rows.iter().map(|row| H256::from_slice(&row.block_hash)).collect::<Vec<_>>();
+		if !block_hashes.is_empty() {
+			log::debug!(target: LOG_TARGET, "Retracting blocks from #{block_number}: {block_hashes:?}");
 		}
+		self.remove(&block_hashes).await?;
+		Ok(block_hashes)
+	}
 
-		let delete_transaction_hashes = delete_tx_query.execute(&self.pool);
-		let delete_logs = delete_logs_query.execute(&self.pool);
-		tokio::try_join!(delete_transaction_hashes, delete_logs)?;
+	/// Get the hash of the block indexed at the given block number.
+	async fn indexed_block_hash(
+		&self,
+		block_number: SubstrateBlockNumber,
+	) -> Result<Option<H256>, ClientError> {
+		let block_number = block_number as i64;
+		let row = query!(r#"SELECT block_hash FROM blocks WHERE block_number = $1"#, block_number)
+			.fetch_optional(&self.pool)
+			.await?;
+		Ok(row.map(|row| H256::from_slice(&row.block_hash)))
+	}
+
+	/// Get the number of the latest indexed block that is finalized.
+	async fn latest_finalized_block_number(
+		&self,
+	) -> Result<Option<SubstrateBlockNumber>, ClientError> {
+		let row = query!(
+			r#"SELECT MAX(block_number) AS "block_number?: i64" FROM blocks WHERE finalized = 1"#
+		)
+		.fetch_one(&self.pool)
+		.await?;
+		Ok(row.block_number.map(|number| number as _))
+	}
+
+	/// Mark the blocks indexed up to the given block number as finalized.
+	async fn mark_finalized(&self, block_number: SubstrateBlockNumber) -> Result<(), ClientError> {
+		let block_number = block_number as i64;
+		query!(
+			r#"UPDATE blocks SET finalized = 1 WHERE block_number <= $1 AND finalized = 0"#,
+			block_number
+		)
+		.execute(&self.pool)
+		.await?;
 		Ok(())
 	}
 
@@ -142,6 +204,91 @@ impl<B: BlockInfoProvider> ReceiptProvider<B> {
 		self.receipt_extractor.extract_from_block(block).await
 	}
 
+	/// Get the ancestors of the given block that are not indexed yet, because they were part of
+	/// a fork that just became canonical.
+	///
+	/// The ancestors are returned from the newest to the oldest.
+	async fn enacted_ancestors(
+		&self,
+		block: &SubstrateBlock,
+	) -> Result<Vec<Arc<SubstrateBlock>>, ClientError> {
+		let latest_finalized = self.latest_finalized_block_number().await?;
+		let mut enacted = Vec::new();
+		let mut block_number = block.number();
+		let mut parent_hash = block.header().parent_hash;
+
+		while block_number > 0 {
+			// Stop once we reach a known ancestor, or the beginning of the indexed range.
+			match self.indexed_block_hash(block_number - 1).await? {
+				Some(hash) if hash != parent_hash => {},
+				_ => break,
+			}
+
+			if latest_finalized.is_some_and(|finalized| block_number - 1 <= finalized) {
+				log::warn!(
+					target: LOG_TARGET,
+					"Block #{} ({:?}) is not a descendant of the finalized block #{}",
+					block.number(),
+					block.hash(),
+					block_number - 1,
+				);
+				break;
+			}
+
+			let parent = self
+				.block_provider
+				.block_by_hash(&parent_hash)
+				.await?
+				.ok_or(ClientError::BlockNotFound)?;
+			block_number = parent.number();
+			parent_hash = parent.header().parent_hash;
+			enacted.push(parent);
+		}
+
+		Ok(enacted)
+	}
+
+	/// Extract and insert receipts from a new best or finalized block.
+	///
+	/// The blocks retracted by `block` are rolled back, and the ancestors of `block` that were not
+	/// indexed yet are indexed as well. Returns the receipts of `block`, along with the hashes of
+	/// the retracted blocks.
+	pub async fn insert_canonical_block_receipts(
+		&self,
+		block: &SubstrateBlock,
+		subscription_type: SubscriptionType,
+	) -> Result<(Vec<(TransactionSigned, ReceiptInfo)>, Vec<H256>), ClientError> {
+		let _lock = self.canonical_lock.lock().await;
+		let receipts = self.receipts_from_block(block).await?;
+
+		// The finalized block is usually already indexed by the best block subscription.
+		if matches!(subscription_type, SubscriptionType::FinalizedBlocks) &&
+			self.indexed_block_hash(block.number()).await? == Some(block.hash())
+		{
+			self.mark_finalized(block.number()).await?;
+			return Ok((receipts, Vec::new()));
+		}
+
+		let mut retracted = Vec::new();
+		for ancestor in self.enacted_ancestors(block).await?.iter().rev() {
+			let ancestor_receipts = self.receipts_from_block(ancestor).await?;
+			retracted.extend(self.insert(ancestor.as_ref(), &ancestor_receipts).await?);
+		}
+
+		retracted.extend(self.insert(block, &receipts).await?);
+		retracted.extend(self.retract_from(block.number() + 1).await?);
+
+		if matches!(subscription_type, SubscriptionType::FinalizedBlocks) {
+			self.mark_finalized(block.number()).await?;
+		}
+		Ok((receipts, retracted))
+	}
+
+	/// Lock the updates of the canonical chain, until the returned guard is dropped.
+	pub async fn lock_canonical_chain(&self) -> MutexGuard<'_, ()> {
+		self.canonical_lock.lock().await
+	}
+
 	/// Extract and insert receipts from the given block.
 	pub async fn insert_block_receipts(
 		&self,
@@ -154,31 +301,33 @@ impl<B: BlockInfoProvider> ReceiptProvider<B> {
 
 	/// Insert receipts into the provider.
 	///
+	/// The block becomes the indexed block at its height. If another block was indexed at the
+	/// same height, it was retracted by a fork and its receipts are removed. Returns the hash of
+	/// the retracted block, if any.
+	///
+	/// The block, its transaction hashes and its logs are written in a single database
+	/// transaction, so that a block is never indexed with only part of its receipts.
+	///
 	/// Note: Can be merged into `insert_block_receipts` once <https://github.com/paritytech/subxt/issues/1883> is fixed and subxt let
 	/// us create Mock `SubstrateBlock`
 	async fn insert(
 		&self,
 		block: &impl BlockInfo,
 		receipts: &[(TransactionSigned, ReceiptInfo)],
-	) -> Result<(), ClientError> {
-		if receipts.is_empty() {
-			return Ok(());
-		}
-
+	) -> Result<Option<H256>, ClientError> {
 		let block_hash = block.hash();
 		let block_hash_ref = block_hash.as_ref();
 		let block_number = block.number() as i64;
 
-		let result = sqlx::query!(
-			r#"SELECT EXISTS(SELECT 1 FROM transaction_hashes WHERE block_hash = $1) AS "exists!: bool""#,
-			block_hash_ref
-		)
-		.fetch_one(&self.pool)
-		.await?;
-
-		if result.exists {
-			return Ok(());
-		}
+		let retracted = match self.indexed_block_hash(block.number()).await? {
+			Some(hash) if hash == block_hash => return Ok(None),
+			Some(hash) => {
+				log::debug!(target: LOG_TARGET, "Block #{block_number} {hash:?} was retracted");
+				self.remove(&[hash]).await?;
+				Some(hash)
+			},
+			None => None,
+		};
 
 		// Keep track of the latest block hashes, so we can prune older blocks.
 		if let Some(keep_latest_n_blocks) = self.keep_latest_n_blocks {
@@ -204,6 +353,15 @@ impl<B: BlockInfoProvider> ReceiptProvider<B> {
 			self.remove(&to_remove).await?;
 		}
 
+		let mut tx = self.pool.begin().await?;
+		query!(
+			r#"INSERT OR REPLACE INTO blocks (block_number, block_hash) VALUES ($1, $2)"#,
+			block_number,
+			block_hash_ref
+		)
+		.execute(&mut *tx)
+		.await?;
+
 		for (_, receipt) in receipts {
 			let transaction_hash: &[u8] = receipt.transaction_hash.as_ref();
 			let transaction_index = receipt.transaction_index.as_u32() as i32;
@@ -217,7 +375,7 @@ impl<B: BlockInfoProvider> ReceiptProvider<B> {
 				block_hash_ref,
 				transaction_index
 			)
-			.execute(&self.pool)
+			.execute(&mut *tx)
 			.await?;
 
 			for log in &receipt.logs {
@@ -255,11 +413,12 @@ impl<B: BlockInfoProvider> ReceiptProvider<B> {
 					topic_3,
 					data
 				)
-				.execute(&self.pool)
+				.execute(&mut *tx)
 				.await?;
 			}
 		}
-		Ok(())
+		tx.commit().await?;
+		Ok(retracted)
 	}
 
 	/// Get logs that match the given filter.
@@ -525,6 +684,7 @@ mod tests {
 			receipt_extractor: ReceiptExtractor::new_mock(),
 			keep_latest_n_blocks: Some(10),
 			block_number_to_hash: Default::default(),
+			canonical_lock: Default::default(),
 		}
 	}
 
@@ -611,6 +771,42 @@ mod tests {
 		return Ok(());
 	}
 
+	#[sqlx::test]
+	async fn test_retract_from(pool: SqlitePool) -> anyhow::Result<()> {
+		let provider = setup_sqlite_provider(pool).await;
+
+		for i in 1u8..=3 {
+			let block = MockBlockInfo { hash: H256::from([i; 32]), number: i as _ };
+			let transaction_hash = H256::from([i; 32]);
+			let receipts = vec![(
+				TransactionSigned::default(),
+				ReceiptInfo {
+					transaction_hash,
+					logs: vec![Log {
+						block_hash: block.hash,
+						transaction_hash,
+						..Default::default()
+					}],
+					..Default::default()
+				},
+			)];
+			provider.insert(&block, &receipts).await?;
+		}
+
+		provider.retract_from(2).await?;
+		assert_eq!(count(&provider.pool, "transaction_hashes", None).await, 1);
+		assert_eq!(count(&provider.pool, "logs", None).await, 1);
+		assert_eq!(count(&provider.pool, "blocks", None).await, 1);
+		assert_eq!(provider.indexed_block_hash(1).await?, Some(H256::from([1u8; 32])));
+		assert_eq!(provider.indexed_block_hash(2).await?, None);
+		assert_eq!(provider.fetch_row(&H256::from([3u8; 32])).await, None);
+
+		// Blocks are finalized up to the given block number.
+		provider.mark_finalized(1).await?;
+		assert_eq!(provider.latest_finalized_block_number().await?, Some(1));
+		Ok(())
+	}
+
 	#[sqlx::test]
 	async fn test_receipts_count_per_block(pool: SqlitePool) -> anyhow::Result<()> {
 		let provider = setup_sqlite_provider(pool).await;
@@ -0,0 +1,172 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module keeps track of the accounts and storage keys accessed by a transaction.
+//!
+//! The first access to an account or to a storage key within a transaction is *cold*, later
+//! accesses are *warm*, as defined by EIP-2929. Cold accesses are reported to the tracer, which is
+//! how `eth_createAccessList` builds an access list. They are not charged differently from warm
+//! ones: the benchmarked costs of the host functions already account for the storage reads.
+//!
+//! Since the tracking isn't weighed, it only happens when a tracer asks for it, which is never
+//! the case on-chain.
+
+use crate::exec::Key;
+use alloc::{collections::BTreeSet, vec::Vec};
+use sp_core::H160;
+
+/// An entry representing an account or a storage key that was warmed up.
+enum JournalEntry {
+	Account(H160),
+	StorageKey(H160, Vec<u8>),
+}
+
+/// The set of warm accounts and storage keys of a transaction.
+///
+/// Just like the transient storage, accesses made by a call that is rolled back are reverted.
+/// On entry to `start_transaction` a checkpoint is recorded, and on `rollback_transaction` all the
+/// entries added since the last checkpoint are removed again.
+#[derive(Default)]
+pub struct AccessTracker {
+	accounts: BTreeSet<H160>,
+	storage_keys: BTreeSet<(H160, Vec<u8>)>,
+	// The size of the journal is bounded by the gas charged for each host function call.
+	journal: Vec<JournalEntry>,
+	// The size of the checkpoints is limited by the stack depth.
+	checkpoints: Vec<usize>,
+}
+
+impl AccessTracker {
+	/// Mark the given account as warm without recording it in the journal.
+	///
+	/// This is used for the accounts that are warm for the whole transaction, such as the origin.
+	pub fn warm_up(&mut self, address: &H160) {
+		self.accounts.insert(*address);
+	}
+
+	/// Mark the given account as accessed.
+	///
+	/// Returns `true` if this is the first access to the account, i.e. if it was cold.
+	pub fn access_account(&mut self, address: &H160) -> bool {
+		let cold = self.accounts.insert(*address);
+		if cold {
+			self.journal.push(JournalEntry::Account(*address));
+		}
+		cold
+	}
+
+	/// Mark the given storage key of the given contract as accessed.
+	///
+	/// Returns `true` if this is the first access to the storage key, i.e. if it was cold.
+	pub fn access_storage_key(&mut self, address: &H160, key: &Key) -> bool {
+		let entry = (*address, key.hash());
+		let cold = !self.storage_keys.contains(&entry);
+		if cold {
+			self.storage_keys.insert(entry.clone());
+			self.journal.push(JournalEntry::StorageKey(entry.0, entry.1));
+		}
+		cold
+	}
+
+	/// Start a new nested transaction.
+	///
+	/// For every transaction there must be a matching call to either `rollback_transaction`
+	/// or `commit_transaction`.
+	pub fn start_transaction(&mut self) {
+		self.checkpoints.push(self.journal.len());
+	}
+
+	/// Rollback the last transaction started by `start_transaction`.
+	///
+	/// The accounts and storage keys accessed during that transaction are cold again.
+	///
+	/// # Panics
+	///
+	/// Will panic if there is no open transaction.
+	pub fn rollback_transaction(&mut self) {
+		let checkpoint = self
+			.checkpoints
+			.pop()
+			.expect(
+				"A call to rollback_transaction must be preceded by a corresponding call to start_transaction;
+				the code within this crate makes sure that this is always the case; qed"
+			);
+		for entry in self.journal.drain(checkpoint..) {
+			match entry {
+				JournalEntry::Account(address) => {
+					self.accounts.remove(&address);
+				},
+				JournalEntry::StorageKey(address, key) => {
+					self.storage_keys.remove(&(address, key));
+				},
+			}
+		}
+	}
+
+	/// Commit the last transaction started by `start_transaction`.
+	///
+	/// # Panics
+	///
+	/// Will panic if there is no open transaction.
+	pub fn commit_transaction(&mut self) {
+		self.checkpoints
+			.pop()
+			.expect(
+				"A call to commit_transaction must be preceded by a corresponding call to start_transaction;
+				the code within this crate makes sure that this is always the case; qed"
+			);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn second_access_is_warm() {
+		let address = H160::from([1u8; 20]);
+		let key = Key::Fix([2u8; 32]);
+		let mut tracker = AccessTracker::default();
+		tracker.warm_up(&H160::from([4u8; 20]));
+
+		assert!(tracker.access_account(&address));
+		assert!(!tracker.access_account(&address));
+		assert!(tracker.access_storage_key(&address, &key));
+		assert!(!tracker.access_storage_key(&address, &key));
+		assert!(tracker.access_storage_key(&address, &Key::Fix([3u8; 32])));
+		assert!(!tracker.access_account(&H160::from([4u8; 20])));
+	}
+
+	#[test]
+	fn rollback_transaction_works() {
+		let address = H160::from([1u8; 20]);
+		let key = Key::Fix([2u8; 32]);
+		let mut tracker = AccessTracker::default();
+
+		tracker.start_transaction();
+		assert!(tracker.access_account(&address));
+		tracker.start_transaction();
+		assert!(tracker.access_storage_key(&address, &key));
+		tracker.rollback_transaction();
+		assert!(!tracker.access_account(&address));
+		assert!(tracker.access_storage_key(&address, &key));
+		tracker.commit_transaction();
+
+		assert!(!tracker.access_account(&address));
+		assert!(!tracker.access_storage_key(&address, &key));
+	}
+}
@@ -15,7 +15,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::evm::Bytes;
+use crate::evm::{AccessList, Bytes};
 use alloc::{collections::BTreeMap, string::String, vec::Vec};
 use codec::{Decode, Encode};
 use derive_more::From;
@@ -27,7 +27,6 @@ use serde::{
 use sp_core::{H160, H256, U256};
 
 /// The type of tracer to use.
-/// Only "callTracer" is supported for now.
 #[derive(TypeInfo, Debug, Clone, Encode, Decode, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "tracer", content = "tracerConfig", rename_all = "camelCase")]
 pub enum TracerType {
@@ -36,6 +35,16 @@ pub enum TracerType {
 
 	/// A tracer that traces the prestate.
 	PrestateTracer(Option<PrestateTracerConfig>),
+
+	/// A tracer that emits a step for every executed instruction.
+	///
+	/// This is the default tracer used by geth when no tracer is specified.
+	StructLogger(Option<StructLoggerConfig>),
+
+	/// A tracer that collects the accounts and storage keys accessed by a transaction.
+	///
+	/// This is used to implement `eth_createAccessList`.
+	AccessListTracer,
 }
 
 impl From<CallTracerConfig> for TracerType {
@@ -51,18 +60,208 @@ impl Default for TracerType {
 }
 
 /// Tracer configuration used to trace calls.
+///
+/// The struct logger options are passed at the top-level of the config, and the struct logger is
+/// used when no `tracer` is specified, so we can't rely on the derived serde impls.
 #[derive(TypeInfo, Debug, Clone, Default, PartialEq)]
-#[cfg_attr(feature = "std", derive(Deserialize, Serialize), serde(rename_all = "camelCase"))]
 pub struct TracerConfig {
 	/// The tracer type.
-	#[cfg_attr(feature = "std", serde(flatten, default))]
 	pub config: TracerType,
 
 	/// Timeout for the tracer.
-	#[cfg_attr(feature = "std", serde(with = "humantime_serde", default))]
 	pub timeout: Option<core::time::Duration>,
 }
 
+#[cfg(feature = "std")]
+impl Serialize for TracerConfig {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		#[derive(Serialize)]
+		#[serde(untagged)]
+		enum TracerOptions<'a> {
+			CallTracer(&'a CallTracerConfig),
+			PrestateTracer(&'a PrestateTracerConfig),
+		}
+
+		#[derive(Serialize)]
+		#[serde(rename_all = "camelCase")]
+		struct Helper<'a> {
+			#[serde(skip_serializing_if = "Option::is_none")]
+			tracer: Option<&'static str>,
+			#[serde(skip_serializing_if = "Option::is_none")]
+			tracer_config: Option<TracerOptions<'a>>,
+			#[serde(flatten)]
+			struct_logger_config: Option<&'a StructLoggerConfig>,
+			#[serde(with = "humantime_serde", skip_serializing_if = "Option::is_none")]
+			timeout: Option<core::time::Duration>,
+		}
+
+		let (tracer, tracer_config, struct_logger_config) = match &self.config {
+			TracerType::CallTracer(config) =>
+				(Some("callTracer"), config.as_ref().map(TracerOptions::CallTracer), None),
+			TracerType::PrestateTracer(config) =>
+				(Some("prestateTracer"), config.as_ref().map(TracerOptions::PrestateTracer), None),
+			// The struct logger is used when no tracer is specified.
+			TracerType::StructLogger(config) => (None, None, config.as_ref()),
+			TracerType::AccessListTracer => (Some("accessListTracer"), None, None),
+		};
+
+		Helper { tracer, tracer_config, struct_logger_config, timeout: self.timeout }
+			.serialize(serializer)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for TracerConfig {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		use serde::de::Error;
+
+		#[derive(Deserialize)]
+		#[serde(rename_all = "camelCase")]
+		enum TracerName {
+			CallTracer,
+			PrestateTracer,
+			StructLogger,
+			AccessListTracer,
+		}
+
+		/// The union of the options of the tracers configured with `tracerConfig`.
+		#[derive(Deserialize)]
+		#[serde(rename_all = "camelCase")]
+		struct TracerOptions {
+			with_logs: Option<bool>,
+			only_top_call: Option<bool>,
+			diff_mode: Option<bool>,
+			disable_storage: Option<bool>,
+			disable_code: Option<bool>,
+		}
+
+		/// The options of the struct logger, passed at the top-level.
+		#[derive(Deserialize)]
+		#[serde(rename_all = "camelCase")]
+		struct StructLoggerOptions {
+			enable_memory: Option<bool>,
+			disable_stack: Option<bool>,
+			disable_storage: Option<bool>,
+			enable_return_data: Option<bool>,
+			limit: Option<u64>,
+		}
+
+		#[derive(Deserialize)]
+		#[serde(rename_all = "camelCase")]
+		struct Helper {
+			tracer: Option<TracerName>,
+			tracer_config: Option<TracerOptions>,
+			#[serde(flatten)]
+			struct_logger_options: StructLoggerOptions,
+			#[serde(with = "humantime_serde", default)]
+			timeout: Option<core::time::Duration>,
+		}
+
+		// Fails on the first of `options` which is set, as it belongs to another tracer.
+		fn reject<E: Error>(tracer: &str, options: &[(&str, bool)]) -> Result<(), E> {
+			match options.iter().find(|(_, set)| *set) {
+				Some((option, _)) =>
+					Err(E::custom(format!("`{option}` is not an option of the {tracer}"))),
+				None => Ok(()),
+			}
+		}
+
+		let Helper { tracer, tracer_config, struct_logger_options, timeout } =
+			Helper::deserialize(deserializer)?;
+
+		let StructLoggerOptions {
+			enable_memory,
+			disable_stack,
+			disable_storage,
+			enable_return_data,
+			limit,
+		} = struct_logger_options;
+		let struct_logger_options = [
+			("enableMemory", enable_memory.is_some()),
+			("disableStack", disable_stack.is_some()),
+			("disableStorage", disable_storage.is_some()),
+			("enableReturnData", enable_return_data.is_some()),
+			("limit", limit.is_some()),
+		];
+
+		let config = match (tracer, tracer_config) {
+			(Some(TracerName::StructLogger) | None, Some(_)) =>
+				return Err(D::Error::custom(
+					"the struct logger options are passed at the top-level, not in `tracerConfig`",
+				)),
+			(Some(TracerName::StructLogger) | None, None) => {
+				let config = if struct_logger_options.iter().any(|(_, set)| *set) {
+					let default = StructLoggerConfig::default();
+					Some(StructLoggerConfig {
+						enable_memory: enable_memory.unwrap_or(default.enable_memory),
+						disable_stack: disable_stack.unwrap_or(default.disable_stack),
+						disable_storage: disable_storage.unwrap_or(default.disable_storage),
+						enable_return_data: enable_return_data
+							.unwrap_or(default.enable_return_data),
+						limit: limit.unwrap_or(default.limit),
+					})
+				} else {
+					None
+				};
+				TracerType::StructLogger(config)
+			},
+			(Some(TracerName::CallTracer), options) => {
+				reject::<D::Error>("callTracer", &struct_logger_options)?;
+				let config = options
+					.map(|options| {
+						reject::<D::Error>(
+							"callTracer",
+							&[
+								("diffMode", options.diff_mode.is_some()),
+								("disableStorage", options.disable_storage.is_some()),
+								("disableCode", options.disable_code.is_some()),
+							],
+						)?;
+						let default = CallTracerConfig::default();
+						Ok::<_, D::Error>(CallTracerConfig {
+							with_logs: options.with_logs.unwrap_or(default.with_logs),
+							only_top_call: options.only_top_call.unwrap_or(default.only_top_call),
+						})
+					})
+					.transpose()?;
+				TracerType::CallTracer(config)
+			},
+			(Some(TracerName::PrestateTracer), options) => {
+				reject::<D::Error>("prestateTracer", &struct_logger_options)?;
+				let config = options
+					.map(|options| {
+						reject::<D::Error>(
+							"prestateTracer",
+							&[
+								("withLogs", options.with_logs.is_some()),
+								("onlyTopCall", options.only_top_call.is_some()),
+							],
+						)?;
+						let default = PrestateTracerConfig::default();
+						Ok::<_, D::Error>(PrestateTracerConfig {
+							diff_mode: options.diff_mode.unwrap_or(default.diff_mode),
+							disable_storage: options
+								.disable_storage
+								.unwrap_or(default.disable_storage),
+							disable_code: options.disable_code.unwrap_or(default.disable_code),
+						})
+					})
+					.transpose()?;
+				TracerType::PrestateTracer(config)
+			},
+			(Some(TracerName::AccessListTracer), options) => {
+				reject::<D::Error>("accessListTracer", &struct_logger_options)?;
+				if options.is_some() {
+					return Err(D::Error::custom("the accessListTracer has no `tracerConfig`"))
+				}
+				TracerType::AccessListTracer
+			},
+		};
+
+		Ok(TracerConfig { config, timeout })
+	}
+}
+
 /// The configuration for the call tracer.
 #[derive(Clone, Debug, Decode, Serialize, Deserialize, Encode, PartialEq, TypeInfo)]
 #[serde(default, rename_all = "camelCase")]
@@ -100,6 +299,43 @@ impl Default for PrestateTracerConfig {
 	}
 }
 
+/// The configuration for the struct logger.
+///
+/// Unlike the other tracers, these options are passed at the top-level of the tracer config.
+#[derive(Clone, Debug, Decode, Serialize, Deserialize, Encode, PartialEq, TypeInfo)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StructLoggerConfig {
+	/// Whether to include the memory in each step.
+	///
+	/// PolkaVM contracts have no EVM memory, so this is a snapshot of the stack region of the
+	/// contract, copied on every step. It makes tracing much slower, hence it is off by default.
+	pub enable_memory: bool,
+
+	/// Whether to omit the stack from each step.
+	pub disable_stack: bool,
+
+	/// Whether to omit the storage from each step.
+	pub disable_storage: bool,
+
+	/// Whether to include the return data in each step.
+	pub enable_return_data: bool,
+
+	/// The maximum number of steps to record, `0` means no limit.
+	pub limit: u64,
+}
+
+impl Default for StructLoggerConfig {
+	fn default() -> Self {
+		Self {
+			enable_memory: false,
+			disable_stack: false,
+			disable_storage: false,
+			enable_return_data: false,
+			limit: 0,
+		}
+	}
+}
+
 /// Serialization should support the following JSON format:
 ///
 /// ```json
@@ -109,6 +345,10 @@ impl Default for PrestateTracerConfig {
 /// ```json
 /// { "tracer": "callTracer" }
 /// ```
+///
+/// ```json
+/// { "enableMemory": true }
+/// ```
 #[test]
 fn test_tracer_config_serialization() {
 	let tracers = vec![
@@ -137,12 +377,61 @@ fn test_tracer_config_serialization() {
 				timeout: Some(core::time::Duration::from_millis(10)),
 			},
 		),
+		(
+			r#"{"tracer": "prestateTracer", "tracerConfig": { "diffMode": true }}"#,
+			TracerConfig {
+				config: TracerType::PrestateTracer(Some(PrestateTracerConfig {
+					diff_mode: true,
+					..Default::default()
+				})),
+				timeout: None,
+			},
+		),
+		(
+			r#"{"tracer": "accessListTracer"}"#,
+			TracerConfig { config: TracerType::AccessListTracer, timeout: None },
+		),
+		(r#"{}"#, TracerConfig { config: TracerType::StructLogger(None), timeout: None }),
+		(
+			r#"{"enableMemory": true, "disableStack": true, "limit": 10}"#,
+			TracerConfig {
+				config: TracerType::StructLogger(Some(StructLoggerConfig {
+					enable_memory: true,
+					disable_stack: true,
+					limit: 10,
+					..Default::default()
+				})),
+				timeout: None,
+			},
+		),
 	];
 
 	for (json_data, expected) in tracers {
 		let result: TracerConfig =
 			serde_json::from_str(json_data).expect("Deserialization should succeed");
 		assert_eq!(result, expected);
+
+		let json = serde_json::to_string(&expected).expect("Serialization should succeed");
+		let result: TracerConfig = serde_json::from_str(&json).expect("Round trip should succeed");
+		assert_eq!(result, expected, "{json}");
+	}
+
+	let struct_logger = TracerConfig { config: TracerType::StructLogger(None), timeout: None };
+	assert_eq!(serde_json::to_string(&struct_logger).unwrap(), "{}");
+}
+
+#[test]
+fn test_tracer_config_rejects_options_of_other_tracers() {
+	let invalid = [
+		r#"{"tracer": "callTracer", "tracerConfig": { "diffMode": true }}"#,
+		r#"{"tracer": "prestateTracer", "tracerConfig": { "withLogs": false }}"#,
+		r#"{"tracer": "callTracer", "enableMemory": true}"#,
+		r#"{"tracer": "accessListTracer", "tracerConfig": {}}"#,
+		r#"{"tracerConfig": { "withLogs": false }}"#,
+	];
+
+	for json_data in invalid {
+		assert!(serde_json::from_str::<TracerConfig>(json_data).is_err(), "{json_data}");
 	}
 }
 
@@ -173,6 +462,10 @@ pub enum Trace {
 	Call(CallTrace),
 	/// A prestate trace.
 	Prestate(PrestateTrace),
+	/// An opcode trace, produced by the struct logger.
+	Opcode(OpcodeTrace),
+	/// An access list, produced by the access list tracer.
+	AccessList(AccessList),
 }
 
 /// A prestate Trace
@@ -309,6 +602,59 @@ pub struct CallLog {
 	pub position: u32,
 }
 
+/// The result of the struct logger.
+#[derive(
+	TypeInfo, Default, Encode, Decode, Serialize, Deserialize, Clone, Debug, Eq, PartialEq,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct OpcodeTrace {
+	/// The amount of gas used by the transaction.
+	pub gas: u64,
+	/// Whether the transaction failed.
+	pub failed: bool,
+	/// The data returned by the transaction.
+	pub return_value: Bytes,
+	/// The steps executed by the transaction.
+	pub struct_logs: Vec<StructLog>,
+}
+
+/// A single instruction step recorded by the struct logger.
+#[derive(
+	TypeInfo, Default, Encode, Decode, Serialize, Deserialize, Clone, Debug, Eq, PartialEq,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+	/// The program counter.
+	pub pc: u64,
+	/// The name of the instruction.
+	pub op: String,
+	/// The gas left before executing the instruction.
+	pub gas: u64,
+	/// The gas consumed by the instruction, including the gas of nested calls.
+	pub gas_cost: u64,
+	/// The call depth, starting at `1` for the top-level call.
+	pub depth: u32,
+	/// The error raised by the instruction, if any.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// The stack before executing the instruction.
+	///
+	/// For PolkaVM contracts, this holds the value of the registers.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub stack: Option<Vec<U256>>,
+	/// The memory before executing the instruction, split in 32 bytes words.
+	///
+	/// For PolkaVM contracts, this holds the stack region of the contract.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub memory: Option<Vec<Bytes>>,
+	/// The data returned by the last call.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub return_data: Option<Bytes>,
+	/// The storage slots of the current contract that were accessed since the previous step.
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	pub storage: BTreeMap<Bytes, Bytes>,
+}
+
 /// A transaction trace
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TransactionTrace {
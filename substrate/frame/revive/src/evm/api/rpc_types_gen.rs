@@ -18,7 +18,7 @@
 #![allow(missing_docs)]
 
 use super::{byte::*, TypeEip1559, TypeEip2930, TypeEip4844, TypeLegacy};
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use codec::{Decode, Encode};
 use derive_more::{From, TryInto};
 pub use ethereum_types::*;
@@ -73,6 +73,43 @@ fn deserialize_input_or_data<'d, D: Deserializer<'d>>(d: D) -> Result<InputOrDat
     }
 }
 
+/// Access list result
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct AccessListResult {
+	/// access list
+	#[serde(rename = "accessList")]
+	pub access_list: AccessList,
+	/// error, if the transaction failed
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// gas used by the transaction, or by its execution up to the failure if it failed
+	#[serde(rename = "gasUsed")]
+	pub gas_used: U256,
+}
+
+/// Account proof
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct AccountProof {
+	/// address
+	pub address: Address,
+	/// accountProof
+	#[serde(rename = "accountProof")]
+	pub account_proof: Vec<Bytes>,
+	/// balance
+	pub balance: U256,
+	/// codeHash
+	#[serde(rename = "codeHash")]
+	pub code_hash: H256,
+	/// nonce
+	pub nonce: U256,
+	/// storageHash
+	#[serde(rename = "storageHash")]
+	pub storage_hash: H256,
+	/// Storage proofs
+	#[serde(rename = "storageProof")]
+	pub storage_proof: Vec<StorageProof>,
+}
+
 /// Block object
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Block {
@@ -713,6 +750,17 @@ pub struct AccessListEntry {
 	pub storage_keys: Vec<H256>,
 }
 
+/// Storage proof
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct StorageProof {
+	/// key
+	pub key: U256,
+	/// proof
+	pub proof: Vec<Bytes>,
+	/// value
+	pub value: U256,
+}
+
 /// Filter Topic List Entry
 #[derive(Debug, Clone, Serialize, Deserialize, From, TryInto, Eq, PartialEq)]
 #[serde(untagged)]
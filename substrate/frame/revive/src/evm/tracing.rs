@@ -15,7 +15,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use crate::{
-	evm::{CallTrace, Trace},
+	evm::{CallTrace, OpcodeTrace, Trace},
 	tracing::Tracing,
 	BalanceOf, Bounded, Config, MomentOf, Weight,
 };
@@ -27,6 +27,12 @@ pub use call_tracing::*;
 mod prestate_tracing;
 pub use prestate_tracing::*;
 
+mod struct_logger;
+pub use struct_logger::*;
+
+mod access_list_tracing;
+pub use access_list_tracing::*;
+
 /// A composite tracer.
 #[derive(derive_more::From, Debug)]
 pub enum Tracer<T> {
@@ -34,6 +40,10 @@ pub enum Tracer<T> {
 	CallTracer(CallTracer<U256, fn(Weight) -> U256>),
 	/// A tracer that traces the prestate.
 	PrestateTracer(PrestateTracer<T>),
+	/// A tracer that traces every executed instruction.
+	StructLogger(StructLogger<fn(Weight) -> U256>),
+	/// A tracer that collects the accessed accounts and storage keys.
+	AccessListTracer(AccessListTracer),
 }
 
 impl<T: Config> Tracer<T>
@@ -48,6 +58,8 @@ where
 		match self {
 			Tracer::CallTracer(_) => CallTrace::default().into(),
 			Tracer::PrestateTracer(tracer) => tracer.empty_trace().into(),
+			Tracer::StructLogger(_) => OpcodeTrace::default().into(),
+			Tracer::AccessListTracer(_) => Trace::AccessList(Default::default()),
 		}
 	}
 
@@ -56,6 +68,8 @@ where
 		match self {
 			Tracer::CallTracer(inner) => inner as &mut dyn Tracing,
 			Tracer::PrestateTracer(inner) => inner as &mut dyn Tracing,
+			Tracer::StructLogger(inner) => inner as &mut dyn Tracing,
+			Tracer::AccessListTracer(inner) => inner as &mut dyn Tracing,
 		}
 	}
 
@@ -64,6 +78,8 @@ where
 		match self {
 			Tracer::CallTracer(inner) => inner.collect_trace().map(Trace::Call),
 			Tracer::PrestateTracer(inner) => Some(inner.collect_trace().into()),
+			Tracer::StructLogger(inner) => Some(inner.collect_trace().into()),
+			Tracer::AccessListTracer(inner) => Some(Trace::AccessList(inner.collect_trace())),
 		}
 	}
 }
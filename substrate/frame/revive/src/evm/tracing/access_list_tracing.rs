@@ -0,0 +1,69 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+	evm::{AccessList, AccessListEntry},
+	tracing::Tracing,
+	Key,
+};
+use alloc::collections::{BTreeMap, BTreeSet};
+use sp_core::{H160, H256};
+
+/// A tracer that collects the accounts and storage keys accessed by a transaction.
+///
+/// Only cold accesses are reported by the runtime, so the origin and the destination of the
+/// transaction, as well as precompiles, are never part of the list unless some of their storage
+/// keys were accessed. Variable sized storage keys can't be expressed in an access list and are
+/// skipped.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct AccessListTracer {
+	/// The accessed accounts, with their accessed storage keys.
+	accesses: BTreeMap<H160, BTreeSet<H256>>,
+}
+
+impl AccessListTracer {
+	/// Create a new [`AccessListTracer`] instance.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Collect the access list and return it.
+	pub fn collect_trace(&mut self) -> AccessList {
+		core::mem::take(&mut self.accesses)
+			.into_iter()
+			.map(|(address, storage_keys)| AccessListEntry {
+				address,
+				storage_keys: storage_keys.into_iter().collect(),
+			})
+			.collect()
+	}
+}
+
+impl Tracing for AccessListTracer {
+	fn is_access_tracking_enabled(&self) -> bool {
+		true
+	}
+
+	fn account_access(&mut self, addr: &H160) {
+		self.accesses.entry(*addr).or_default();
+	}
+
+	fn storage_access(&mut self, addr: &H160, key: &Key) {
+		if let Key::Fix(key) = key {
+			self.accesses.entry(*addr).or_default().insert(H256(*key));
+		}
+	}
+}
@@ -0,0 +1,187 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+	evm::{Bytes, OpcodeTrace, StructLog, StructLoggerConfig},
+	primitives::ExecReturnValue,
+	tracing::Tracing,
+	DispatchError, Key, Weight,
+};
+use alloc::{collections::BTreeMap, format, string::ToString, vec::Vec};
+use sp_core::{H160, U256};
+
+/// A tracer that records every executed instruction, similar to geth's struct logger.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct StructLogger<GasMapper> {
+	/// Map Weight to Gas equivalent.
+	gas_mapper: GasMapper,
+	/// The tracer configuration.
+	config: StructLoggerConfig,
+	/// The trace being recorded.
+	trace: OpcodeTrace,
+	/// For each active call frame, the index of its last recorded step.
+	last_steps: Vec<Option<usize>>,
+	/// The storage slots accessed since the last recorded step.
+	storage_changes: BTreeMap<Bytes, Bytes>,
+	/// The data returned by the last nested call.
+	return_data: Bytes,
+}
+
+impl<GasMapper> StructLogger<GasMapper> {
+	/// Create a new [`StructLogger`] instance.
+	pub fn new(config: StructLoggerConfig, gas_mapper: GasMapper) -> Self {
+		Self {
+			gas_mapper,
+			config,
+			trace: Default::default(),
+			last_steps: Vec::new(),
+			storage_changes: BTreeMap::new(),
+			return_data: Default::default(),
+		}
+	}
+
+	/// Collect the trace and return it.
+	pub fn collect_trace(&mut self) -> OpcodeTrace {
+		core::mem::take(&mut self.trace)
+	}
+
+	/// The current call depth, starting at `1` for the top-level call.
+	fn depth(&self) -> u32 {
+		self.last_steps.len() as u32
+	}
+
+	/// Returns `true` once the configured maximum number of steps has been recorded.
+	fn is_full(&self) -> bool {
+		self.config.limit != 0 && self.trace.struct_logs.len() as u64 >= self.config.limit
+	}
+
+	/// Record the exit of the current call frame.
+	fn exit_frame(&mut self, output: &[u8], failed: bool, gas_used: u64) {
+		self.last_steps.pop();
+		self.storage_changes.clear();
+
+		if self.last_steps.is_empty() {
+			self.trace.gas = gas_used;
+			self.trace.failed = failed;
+			self.trace.return_value = output.to_vec().into();
+		} else {
+			self.return_data = output.to_vec().into();
+		}
+	}
+}
+
+impl<GasMapper: Fn(Weight) -> U256> Tracing for StructLogger<GasMapper> {
+	fn is_opcode_tracing_enabled(&self) -> bool {
+		true
+	}
+
+	fn is_memory_tracing_enabled(&self) -> bool {
+		self.config.enable_memory
+	}
+
+	fn enter_child_span(
+		&mut self,
+		_from: H160,
+		_to: H160,
+		_is_delegate_call: bool,
+		_is_read_only: bool,
+		_value: U256,
+		_input: &[u8],
+		_gas_left: Weight,
+	) {
+		self.last_steps.push(None);
+		self.storage_changes.clear();
+	}
+
+	fn enter_opcode(
+		&mut self,
+		pc: u64,
+		opcode: &str,
+		gas_left: Weight,
+		stack: &[u64],
+		memory: Option<&[u8]>,
+	) {
+		let gas = (self.gas_mapper)(gas_left).low_u64();
+
+		// The cost of the previous step is only known once the next one is reached.
+		if let Some(Some(index)) = self.last_steps.last() {
+			let step = &mut self.trace.struct_logs[*index];
+			step.gas_cost = step.gas.saturating_sub(gas);
+		}
+
+		if self.is_full() {
+			return;
+		}
+
+		let step = StructLog {
+			pc,
+			op: opcode.to_string(),
+			gas,
+			gas_cost: 0,
+			depth: self.depth(),
+			error: None,
+			stack: (!self.config.disable_stack)
+				.then(|| stack.iter().map(|value| U256::from(*value)).collect()),
+			memory: memory
+				.map(|memory| memory.chunks(32).map(|word| Bytes::from(word.to_vec())).collect()),
+			return_data: self.config.enable_return_data.then(|| self.return_data.clone()),
+			storage: core::mem::take(&mut self.storage_changes),
+		};
+
+		self.trace.struct_logs.push(step);
+		if let Some(last_step) = self.last_steps.last_mut() {
+			*last_step = Some(self.trace.struct_logs.len() - 1);
+		}
+	}
+
+	fn storage_read(&mut self, key: &Key, value: Option<&[u8]>) {
+		if self.config.disable_storage {
+			return;
+		}
+
+		self.storage_changes
+			.insert(key.unhashed().to_vec().into(), value.unwrap_or_default().to_vec().into());
+	}
+
+	fn storage_write(&mut self, key: &Key, _old_value: Option<Vec<u8>>, new_value: Option<&[u8]>) {
+		if self.config.disable_storage {
+			return;
+		}
+
+		self.storage_changes
+			.insert(key.unhashed().to_vec().into(), new_value.unwrap_or_default().to_vec().into());
+	}
+
+	fn exit_child_span(&mut self, output: &ExecReturnValue, gas_used: Weight) {
+		let gas_used = (self.gas_mapper)(gas_used).low_u64();
+		self.exit_frame(&output.data, output.did_revert(), gas_used);
+	}
+
+	fn exit_child_span_with_error(&mut self, error: DispatchError, gas_used: Weight) {
+		let error = match error {
+			DispatchError::Module(sp_runtime::ModuleError { message, .. }) =>
+				message.unwrap_or_default().to_string(),
+			_ => format!("{:?}", error),
+		};
+
+		if let Some(Some(index)) = self.last_steps.last() {
+			self.trace.struct_logs[*index].error = Some(error);
+		}
+
+		let gas_used = (self.gas_mapper)(gas_used).low_u64();
+		self.exit_frame(&[], true, gas_used);
+	}
+}
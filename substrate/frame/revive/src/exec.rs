@@ -16,6 +16,7 @@
 // limitations under the License.
 
 use crate::{
+	access_list::AccessTracker,
 	address::{self, AddressMapper},
 	gas::GasMeter,
 	limits,
@@ -240,6 +241,12 @@ pub trait PrecompileWithInfoExt: PrecompileExt {
 	/// was deleted.
 	fn get_storage_size(&mut self, key: &Key) -> Option<u32>;
 
+	/// Mark the storage entry of the executing account by the given `key` as accessed.
+	///
+	/// The first access to the entry within the transaction is reported to the tracer. This is a
+	/// no-op unless the tracer enabled access tracking.
+	fn access_storage_key(&mut self, key: &Key);
+
 	/// Sets the storage entry by the given key to the specified value. If `value` is `None` then
 	/// the storage entry is deleted.
 	fn set_storage(
@@ -326,6 +333,12 @@ pub trait PrecompileExt: sealing::Sealed {
 	/// Check if a contract lives at the specified `address`.
 	fn is_contract(&self, address: &H160) -> bool;
 
+	/// Mark the account at the specified `address` as accessed.
+	///
+	/// The first access to the account within the transaction is reported to the tracer, unless
+	/// it is a precompile. This is a no-op unless the tracer enabled access tracking.
+	fn access_account(&mut self, address: &H160);
+
 	/// Returns the account id for the given `address`.
 	fn to_account_id(&self, address: &H160) -> AccountIdOf<Self::T>;
 
@@ -514,6 +527,11 @@ pub struct Stack<'a, T: Config, E> {
 	first_frame: Frame<T>,
 	/// Transient storage used to store data, which is kept for the duration of a transaction.
 	transient_storage: TransientStorage<T>,
+	/// The accounts and storage keys accessed so far by the transaction.
+	///
+	/// Only tracked when the tracer asks for it, see
+	/// [`crate::tracing::Tracing::is_access_tracking_enabled`].
+	access_tracker: Option<AccessTracker>,
 	/// Whether or not actual transfer of funds should be performed.
 	/// This is set to `true` exclusively when we simulate a call through eth_transact.
 	skip_transfer: bool,
@@ -887,6 +905,20 @@ where
 			return Ok(None);
 		};
 
+		let mut access_tracker = None;
+		if_tracing(|tracer| {
+			if tracer.is_access_tracking_enabled() {
+				access_tracker = Some(AccessTracker::default());
+			}
+		});
+		// The origin and the destination of the transaction are always warm.
+		if let Some(tracker) = &mut access_tracker {
+			if let Ok(origin) = origin.account_id() {
+				tracker.warm_up(&T::AddressMapper::to_address(origin));
+			}
+			tracker.warm_up(&T::AddressMapper::to_address(&first_frame.account_id));
+		}
+
 		let stack = Self {
 			origin,
 			gas_meter,
@@ -896,6 +928,7 @@ where
 			first_frame,
 			frames: Default::default(),
 			transient_storage: TransientStorage::new(limits::TRANSIENT_STORAGE_BYTES),
+			access_tracker,
 			skip_transfer,
 			_phantom: Default::default(),
 		};
@@ -1111,6 +1144,11 @@ where
 			*caller_frame = Default::default();
 		}
 
+		// Instantiated contracts are warm, but unlike the called ones they are only known now.
+		if let Some(tracker) = &mut self.access_tracker {
+			tracker.warm_up(&T::AddressMapper::to_address(&frame.account_id));
+			tracker.start_transaction();
+		}
 		self.transient_storage.start_transaction();
 
 		let do_transaction = || -> ExecResult {
@@ -1273,8 +1311,14 @@ where
 
 		if success {
 			self.transient_storage.commit_transaction();
+			if let Some(tracker) = &mut self.access_tracker {
+				tracker.commit_transaction();
+			}
 		} else {
 			self.transient_storage.rollback_transaction();
+			if let Some(tracker) = &mut self.access_tracker {
+				tracker.rollback_transaction();
+			}
 		}
 
 		self.pop_frame(success);
@@ -1643,6 +1687,14 @@ where
 		self.top_frame_mut().contract_info().size(key.into())
 	}
 
+	fn access_storage_key(&mut self, key: &Key) {
+		let address = T::AddressMapper::to_address(&self.top_frame().account_id);
+		let Some(tracker) = &mut self.access_tracker else { return };
+		if tracker.access_storage_key(&address, key) {
+			if_tracing(|tracer| tracer.storage_access(&address, key));
+		}
+	}
+
 	fn set_storage(
 		&mut self,
 		key: &Key,
@@ -1851,6 +1903,16 @@ where
 		ContractInfoOf::<T>::contains_key(&address)
 	}
 
+	fn access_account(&mut self, address: &H160) {
+		let Some(tracker) = &mut self.access_tracker else { return };
+		if <AllPrecompiles<T>>::get::<Self>(address.as_fixed_bytes()).is_some() {
+			return;
+		}
+		if tracker.access_account(address) {
+			if_tracing(|tracer| tracer.account_access(address));
+		}
+	}
+
 	fn to_account_id(&self, address: &H160) -> T::AccountId {
 		T::AddressMapper::to_account_id(address)
 	}
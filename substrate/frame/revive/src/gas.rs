@@ -280,6 +280,18 @@ impl<T: Config> GasMeter<T> {
 		self.gas_left
 	}
 
+	/// The gas left once the executor reaches the given amount of engine fuel.
+	///
+	/// Unlike [`Self::sync_from_executor`] this does not update the meter. Only used for tracing.
+	pub fn gas_left_at_engine_fuel(&self, engine_fuel: polkavm::Gas) -> Weight {
+		let consumed = self
+			.engine_meter
+			.fuel
+			.saturating_sub(engine_fuel.try_into().unwrap_or_default())
+			.saturating_mul(EngineMeter::<T>::ref_time_per_fuel());
+		self.gas_left.saturating_sub(Weight::from_parts(consumed, 0))
+	}
+
 	/// The amount of gas in terms of engine gas.
 	pub fn engine_fuel_left(&self) -> Result<polkavm::Gas, DispatchError> {
 		self.engine_meter.fuel.try_into().map_err(|_| <Error<T>>::OutOfGas.into())
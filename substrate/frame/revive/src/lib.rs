@@ -22,6 +22,7 @@
 
 extern crate alloc;
 
+mod access_list;
 mod address;
 mod benchmarking;
 mod call_builder;
@@ -44,8 +45,8 @@ pub mod weights;
 
 use crate::{
 	evm::{
-		runtime::GAS_PRICE, CallTracer, GasEncoder, GenericTransaction, PrestateTracer, Trace,
-		Tracer, TracerType, TYPE_EIP1559,
+		runtime::GAS_PRICE, AccessListTracer, CallTracer, GasEncoder, GenericTransaction,
+		PrestateTracer, StructLogger, Trace, Tracer, TracerType, TYPE_EIP1559,
 	},
 	exec::{AccountIdOf, ExecError, Executable, Key, Stack as ExecStack},
 	gas::GasMeter,
@@ -1443,6 +1444,12 @@ where
 			.into(),
 			TracerType::PrestateTracer(config) =>
 				PrestateTracer::new(config.unwrap_or_default()).into(),
+			TracerType::StructLogger(config) => StructLogger::new(
+				config.unwrap_or_default(),
+				Self::evm_gas_from_weight as fn(Weight) -> U256,
+			)
+			.into(),
+			TracerType::AccessListTracer => AccessListTracer::new().into(),
 		}
 	}
 
@@ -4395,6 +4395,76 @@ fn prestate_tracing_works() {
 	});
 }
 
+#[test]
+fn struct_logger_works() {
+	use crate::evm::*;
+	let (code, _code_hash) = compile_module("storage").unwrap();
+
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = <Test as Config>::Currency::set_balance(&ALICE, 1_000_000);
+		let min_balance = Contracts::min_balance();
+		let Contract { addr, .. } = builder::bare_instantiate(Code::Upload(code))
+			.value(min_balance * 100)
+			.build_and_unwrap_contract();
+
+		let mut tracer = StructLogger::new(Default::default(), |w: Weight| w.ref_time().into());
+		trace(&mut tracer, || {
+			builder::bare_call(addr).build_and_unwrap_result();
+		});
+		let opcode_trace = tracer.collect_trace();
+
+		assert!(!opcode_trace.failed);
+		assert!(!opcode_trace.struct_logs.is_empty());
+		assert!(opcode_trace
+			.struct_logs
+			.iter()
+			.all(|step| step.depth == 1 && step.stack.is_some()));
+		assert!(opcode_trace.struct_logs.iter().any(|step| !step.storage.is_empty()));
+		assert!(opcode_trace.struct_logs.windows(2).all(|steps| steps[0].gas >= steps[1].gas));
+
+		let config = StructLoggerConfig { disable_stack: true, limit: 5, ..Default::default() };
+		let mut tracer = StructLogger::new(config, |w: Weight| w.ref_time().into());
+		trace(&mut tracer, || {
+			builder::bare_call(addr).build_and_unwrap_result();
+		});
+		let opcode_trace = tracer.collect_trace();
+
+		assert_eq!(opcode_trace.struct_logs.len(), 5);
+		assert!(opcode_trace.struct_logs.iter().all(|step| step.stack.is_none()));
+	});
+}
+
+#[test]
+fn access_list_works() {
+	use crate::evm::*;
+	let (code, _code_hash) = compile_module("storage").unwrap();
+
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = <Test as Config>::Currency::set_balance(&ALICE, 1_000_000);
+		let min_balance = Contracts::min_balance();
+		let Contract { addr, .. } = builder::bare_instantiate(Code::Upload(code))
+			.value(min_balance * 100)
+			.build_and_unwrap_contract();
+
+		// The destination is always warm, so only its storage key is part of the list.
+		let mut tracer = AccessListTracer::new();
+		trace(&mut tracer, || {
+			builder::bare_call(addr).build_and_unwrap_result();
+		});
+		let access_list = tracer.collect_trace();
+		assert_eq!(
+			access_list,
+			vec![AccessListEntry { address: addr, storage_keys: vec![H256([1u8; 32])] }]
+		);
+
+		// Every call starts with no warm storage keys, so the same list is collected again.
+		trace(&mut tracer, || {
+			builder::bare_call(addr).build_and_unwrap_result();
+		});
+		assert_eq!(tracer.collect_trace(), access_list);
+	});
+}
+
 #[test]
 fn unknown_precompiles_revert() {
 	let (code, _code_hash) = compile_module("read_only_call").unwrap();
@@ -76,6 +76,20 @@ pub trait Tracing {
 	) {
 	}
 
+	/// Whether the accessed accounts and storage keys should be tracked.
+	///
+	/// Only then [`Self::account_access`] and [`Self::storage_access`] are called. The tracking
+	/// isn't accounted for in the weights, which is fine since tracing is off-chain only.
+	fn is_access_tracking_enabled(&self) -> bool {
+		false
+	}
+
+	/// Called on the first access to an account within the transaction
+	fn account_access(&mut self, _addr: &H160) {}
+
+	/// Called on the first access to a storage key of a contract within the transaction
+	fn storage_access(&mut self, _addr: &H160, _key: &Key) {}
+
 	/// Record a log event
 	fn log_event(&mut self, _event: H160, _topics: &[H256], _data: &[u8]) {}
 
@@ -84,4 +98,32 @@ pub trait Tracing {
 
 	/// Called when a contract call terminates with an error
 	fn exit_child_span_with_error(&mut self, _error: DispatchError, _gas_left: Weight) {}
+
+	/// Whether [`Self::enter_opcode`] should be called before every executed instruction.
+	///
+	/// Enabling this puts the interpreter in single step mode, which makes execution much slower.
+	fn is_opcode_tracing_enabled(&self) -> bool {
+		false
+	}
+
+	/// Whether the memory should be passed to [`Self::enter_opcode`].
+	///
+	/// Only the stack region of the contract is passed, and it is copied before every instruction.
+	fn is_memory_tracing_enabled(&self) -> bool {
+		false
+	}
+
+	/// Called before an instruction is executed, when opcode tracing is enabled.
+	///
+	/// `stack` holds the registers of the interpreter, and `memory` is only provided when memory
+	/// tracing is enabled.
+	fn enter_opcode(
+		&mut self,
+		_pc: u64,
+		_opcode: &str,
+		_gas_left: Weight,
+		_stack: &[u64],
+		_memory: Option<&[u8]>,
+	) {
+	}
 }
@@ -33,11 +33,12 @@ use crate::{
 	gas::{GasMeter, Token},
 	limits,
 	storage::meter::Diff,
+	tracing::if_tracing,
 	weights::WeightInfo,
 	AccountIdOf, BadOrigin, BalanceOf, CodeInfoOf, CodeVec, Config, Error, ExecError, HoldReason,
 	PristineCode, Weight, LOG_TARGET,
 };
-use alloc::vec::Vec;
+use alloc::{format, vec::Vec};
 use codec::{Decode, Encode, MaxEncodedLen};
 use frame_support::{
 	dispatch::DispatchResult,
@@ -288,6 +289,8 @@ pub struct PreparedCall<'a, E: Ext> {
 	module: polkavm::Module,
 	instance: polkavm::RawInstance,
 	runtime: Runtime<'a, E, polkavm::RawInstance>,
+	/// The program blob, only kept around when the instructions are traced.
+	traced_blob: Option<polkavm::ProgramBlob>,
 }
 
 impl<'a, E: Ext> PreparedCall<'a, E>
@@ -298,6 +301,9 @@ where
 	pub fn call(mut self) -> ExecResult {
 		let exec_result = loop {
 			let interrupt = self.instance.run();
+			if let Ok(polkavm::InterruptKind::Step) = interrupt {
+				self.trace_step();
+			}
 			if let Some(exec_result) =
 				self.runtime.handle_interrupt(interrupt, &self.module, &mut self.instance)
 			{
@@ -308,6 +314,35 @@ where
 		exec_result
 	}
 
+	/// Report the instruction about to be executed to the tracer.
+	fn trace_step(&mut self) {
+		let (Some(blob), Some(pc)) = (&self.traced_blob, self.instance.program_counter()) else {
+			return;
+		};
+
+		let opcode = blob
+			.instructions_bounded_at(polkavm::program::ISA64_V1, pc)
+			.next()
+			.map(|instruction| format!("{:?}", instruction.kind.opcode()))
+			.unwrap_or_default();
+		let gas_left = self.runtime.ext().gas_meter().gas_left_at_engine_fuel(self.instance.gas());
+		let registers = polkavm::Reg::ALL.map(|reg| self.instance.reg(reg));
+		let memory_map = self.module.memory_map();
+		let instance = &self.instance;
+
+		if_tracing(|tracer| {
+			let memory = tracer
+				.is_memory_tracing_enabled()
+				.then(|| {
+					let start = memory_map.stack_address_low();
+					let len = memory_map.stack_address_high().saturating_sub(start);
+					instance.read_memory(start, len).ok()
+				})
+				.flatten();
+			tracer.enter_opcode(pc.0.into(), &opcode, gas_left, &registers, memory.as_deref());
+		});
+	}
+
 	/// The guest memory address at which the aux data is located.
 	#[cfg(feature = "runtime-benchmarks")]
 	pub fn aux_data_base(&self) -> u32 {
@@ -358,13 +393,21 @@ impl<T: Config> ContractBlob<T> {
 				interpreter is available on all platforms; qed",
 		);
 
+		let mut step_tracing = false;
+		if_tracing(|tracer| step_tracing = tracer.is_opcode_tracing_enabled());
+
 		let mut module_config = polkavm::ModuleConfig::new();
 		module_config.set_page_size(limits::PAGE_SIZE);
 		module_config.set_gas_metering(Some(polkavm::GasMeteringKind::Sync));
 		module_config.set_allow_sbrk(false);
 		module_config.set_aux_data_size(aux_data_size);
-		let module = polkavm::Module::new(&engine, &module_config, self.code.into_inner().into())
-			.map_err(|err| {
+		module_config.set_step_tracing(step_tracing);
+		let blob = polkavm::ProgramBlob::parse(self.code.into_inner().into()).map_err(|err| {
+			log::debug!(target: LOG_TARGET, "failed to parse polkavm blob: {err:?}");
+			Error::<T>::CodeRejected
+		})?;
+		let traced_blob = step_tracing.then(|| blob.clone());
+		let module = polkavm::Module::from_blob(&engine, &module_config, blob).map_err(|err| {
 			log::debug!(target: LOG_TARGET, "failed to create polkavm module: {err:?}");
 			Error::<T>::CodeRejected
 		})?;
@@ -385,7 +428,7 @@ impl<T: Config> ContractBlob<T> {
 		instance.set_gas(gas_limit_polkavm);
 		instance.prepare_call_untyped(entry_program_counter, &[]);
 
-		Ok(PreparedCall { module, instance, runtime })
+		Ok(PreparedCall { module, instance, runtime, traced_blob })
 	}
 }
 
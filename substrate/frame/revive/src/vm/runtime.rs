@@ -677,6 +677,15 @@ impl<'a, E: Ext, M: ?Sized + Memory<E::T>> Runtime<'a, E, M> {
 		charge_gas!(self, costs)
 	}
 
+	/// Record an access to the storage `key` of the executing contract.
+	///
+	/// Transient storage is never cold and hence not tracked.
+	fn record_storage_access(&mut self, key: &Key, transient: bool) {
+		if !transient {
+			self.ext.access_storage_key(key);
+		}
+	}
+
 	/// Adjust a previously charged amount down to its actual amount.
 	///
 	/// This is when a maximum a priori amount was charged and then should be partially
@@ -860,6 +869,7 @@ impl<'a, E: Ext, M: ?Sized + Memory<E::T>> Runtime<'a, E, M> {
 		}
 
 		let key = self.decode_key(memory, key_ptr, key_len)?;
+		self.record_storage_access(&key, transient);
 
 		let value = match value {
 			StorageValue::Memory { ptr, len } => Some(memory.read(ptr, len)?),
@@ -893,6 +903,7 @@ impl<'a, E: Ext, M: ?Sized + Memory<E::T>> Runtime<'a, E, M> {
 		};
 		let charged = self.charge_gas(costs(self.ext.max_value_size()))?;
 		let key = self.decode_key(memory, key_ptr, key_len)?;
+		self.record_storage_access(&key, transient);
 		let outcome = if transient {
 			self.ext.set_transient_storage(&key, None, false)?
 		} else {
@@ -921,6 +932,7 @@ impl<'a, E: Ext, M: ?Sized + Memory<E::T>> Runtime<'a, E, M> {
 		};
 		let charged = self.charge_gas(costs(self.ext.max_value_size()))?;
 		let key = self.decode_key(memory, key_ptr, key_len)?;
+		self.record_storage_access(&key, transient);
 		let outcome = if transient {
 			self.ext.get_transient_storage(&key)
 		} else {
@@ -993,6 +1005,7 @@ impl<'a, E: Ext, M: ?Sized + Memory<E::T>> Runtime<'a, E, M> {
 		};
 		let charged = self.charge_gas(costs(self.ext.max_value_size()))?;
 		let key = self.decode_key(memory, key_ptr, key_len)?;
+		self.record_storage_access(&key, transient);
 		let outcome = if transient {
 			self.ext.get_transient_storage_size(&key)
 		} else {
@@ -1021,6 +1034,7 @@ impl<'a, E: Ext, M: ?Sized + Memory<E::T>> Runtime<'a, E, M> {
 		};
 		let charged = self.charge_gas(costs(self.ext.max_value_size()))?;
 		let key = self.decode_key(memory, key_ptr, key_len)?;
+		self.record_storage_access(&key, transient);
 		let outcome = if transient {
 			self.ext.set_transient_storage(&key, None, true)?
 		} else {
@@ -1065,6 +1079,7 @@ impl<'a, E: Ext, M: ?Sized + Memory<E::T>> Runtime<'a, E, M> {
 			Some(_) => self.charge_gas(RuntimeCosts::PrecompileBase)?,
 			None => self.charge_gas(call_type.cost())?,
 		};
+		self.ext.access_account(&callee);
 
 		let deposit_limit = memory.read_u256(deposit_ptr)?;
 
@@ -1539,6 +1554,7 @@ pub mod env {
 	fn code_hash(&mut self, memory: &mut M, addr_ptr: u32, out_ptr: u32) -> Result<(), TrapReason> {
 		self.charge_gas(RuntimeCosts::CodeHash)?;
 		let address = memory.read_h160(addr_ptr)?;
+		self.ext.access_account(&address);
 		Ok(self.write_fixed_sandbox_output(
 			memory,
 			out_ptr,
@@ -1554,6 +1570,7 @@ pub mod env {
 	fn code_size(&mut self, memory: &mut M, addr_ptr: u32) -> Result<u64, TrapReason> {
 		self.charge_gas(RuntimeCosts::CodeSize)?;
 		let address = memory.read_h160(addr_ptr)?;
+		self.ext.access_account(&address);
 		Ok(self.ext.code_size(&address))
 	}
 
@@ -1649,6 +1666,7 @@ pub mod env {
 	) -> Result<(), TrapReason> {
 		self.charge_gas(RuntimeCosts::BalanceOf)?;
 		let address = memory.read_h160(addr_ptr)?;
+		self.ext.access_account(&address);
 		Ok(self.write_fixed_sandbox_output(
 			memory,
 			out_ptr,
@@ -1980,6 +1998,7 @@ pub mod env {
 	fn is_contract(&mut self, memory: &mut M, account_ptr: u32) -> Result<u32, TrapReason> {
 		self.charge_gas(RuntimeCosts::IsContract)?;
 		let address = memory.read_h160(account_ptr)?;
+		self.ext.access_account(&address);
 		Ok(self.ext.is_contract(&address) as u32)
 	}
 
@@ -99,9 +99,18 @@ pub type BabeAuthorityWeight = u64;
 /// of 0 (regardless of whether they are plain or vrf secondary blocks).
 pub type BabeBlockWeight = u32;
 
-/// Make VRF input suitable for BABE's randomness generation.
-pub fn make_vrf_transcript(randomness: &Randomness, slot: Slot, epoch: u64) -> VrfInput {
-	VrfInput::new(
+/// Call `f` with the label and the `(domain, message)` items of the VRF input suitable for
+/// BABE's randomness generation.
+///
+/// This is what keystores signing in another process need to build the VRF input, see
+/// `sp_keystore::Keystore::sr25519_vrf_sign_transcript`.
+pub fn with_vrf_transcript_data<R>(
+	randomness: &Randomness,
+	slot: Slot,
+	epoch: u64,
+	f: impl FnOnce(&'static [u8], &[(&'static [u8], &[u8])]) -> R,
+) -> R {
+	f(
 		&BABE_ENGINE_ID,
 		&[
 			(b"slot number", &slot.to_le_bytes()),
@@ -111,6 +120,11 @@ pub fn make_vrf_transcript(randomness: &Randomness, slot: Slot, epoch: u64) -> V
 	)
 }
 
+/// Make VRF input suitable for BABE's randomness generation.
+pub fn make_vrf_transcript(randomness: &Randomness, slot: Slot, epoch: u64) -> VrfInput {
+	with_vrf_transcript_data(randomness, slot, epoch, VrfInput::new)
+}
+
 /// Make VRF signing data suitable for BABE's protocol.
 pub fn make_vrf_sign_data(randomness: &Randomness, slot: Slot, epoch: u64) -> VrfSignData {
 	make_vrf_transcript(randomness, slot, epoch).into()
@@ -106,6 +106,26 @@ pub trait Keystore: Send + Sync {
 		data: &sr25519::vrf::VrfSignData,
 	) -> Result<Option<sr25519::vrf::VrfSignature>, Error>;
 
+	/// Generate an sr25519 VRF signature for the transcript built from `label` and `items`.
+	///
+	/// Same as [`Self::sr25519_vrf_sign`] with the sign data of
+	/// `sr25519::vrf::VrfTranscript::new(label, items)`, but the inputs of the transcript are
+	/// given. Keystores which can't sign the opaque transcript themselves, like one forwarding the
+	/// requests to another process, need them.
+	///
+	/// Returns `None` if the given `key_type` and `public` combination doesn't
+	/// exist in the keystore or an `Err` when something failed.
+	fn sr25519_vrf_sign_transcript(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		label: &'static [u8],
+		items: &[(&'static [u8], &[u8])],
+	) -> Result<Option<sr25519::vrf::VrfSignature>, Error> {
+		let data = sr25519::vrf::VrfTranscript::new(label, items).into_sign_data();
+		self.sr25519_vrf_sign(key_type, public, &data)
+	}
+
 	/// Generate an sr25519 VRF pre-output for a given input data.
 	///
 	/// Receives [`KeyTypeId`] and an [`sr25519::Public`] key to be able to map
@@ -482,6 +502,16 @@ impl<T: Keystore + ?Sized> Keystore for Arc<T> {
 		(**self).sr25519_vrf_sign(key_type, public, data)
 	}
 
+	fn sr25519_vrf_sign_transcript(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		label: &'static [u8],
+		items: &[(&'static [u8], &[u8])],
+	) -> Result<Option<sr25519::vrf::VrfSignature>, Error> {
+		(**self).sr25519_vrf_sign_transcript(key_type, public, label, items)
+	}
+
 	fn sr25519_vrf_pre_output(
 		&self,
 		key_type: KeyTypeId,
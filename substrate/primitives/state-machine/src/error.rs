@@ -16,14 +16,21 @@
 // limitations under the License.
 
 /// State Machine Errors
-use core::fmt;
+use core::{any::Any, fmt};
 
 /// State Machine Error bound.
 ///
 /// This should reflect Wasm error type bound for future compatibility.
-pub trait Error: 'static + fmt::Debug + fmt::Display + Send + Sync {}
+pub trait Error: 'static + fmt::Debug + fmt::Display + Send + Sync {
+	/// Returns the error as [`Any`], to downcast it to the error type of the executor.
+	fn as_any(&self) -> &dyn Any;
+}
 
-impl<T: 'static + fmt::Debug + fmt::Display + Send + Sync> Error for T {}
+impl<T: 'static + fmt::Debug + fmt::Display + Send + Sync> Error for T {
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
 
 /// Externalities Error.
 ///
@@ -296,6 +296,7 @@ pub fn new_full<
 			name: Some(name),
 			observer_enabled: false,
 			keystore,
+			slashing_protection: None,
 			local_role: role,
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
 			protocol_name: grandpa_protocol_name,
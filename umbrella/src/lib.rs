@@ -1088,6 +1088,10 @@ pub use sc_consensus_manual_seal;
 #[cfg(feature = "sc-consensus-pow")]
 pub use sc_consensus_pow;
 
+/// Slashing protection for the GRANDPA and BEEFY voters.
+#[cfg(feature = "sc-consensus-slashing-protection")]
+pub use sc_consensus_slashing_protection;
+
 /// Generic slots-based utilities for consensus.
 #[cfg(feature = "sc-consensus-slots")]
 pub use sc_consensus_slots;